@@ -297,6 +297,8 @@ pub struct AccountDeletion {
 /// - `slot`: The slot number in which the transaction was recorded.
 /// - `block_time`: The Unix timestamp of when the transaction was processed.
 /// - `block_hash`: Block hash that can be used to detect a fork.
+/// - `index`: Position of the transaction in its block, for datasources that
+///   receive whole blocks or are told the position.
 ///
 /// Note: The `block_time` field may not be returned in all scenarios.
 #[derive(Debug, Clone)]
@@ -308,4 +310,5 @@ pub struct TransactionUpdate {
     pub slot: u64,
     pub block_time: Option<i64>,
     pub block_hash: Option<Hash>,
+    pub index: Option<u64>,
 }
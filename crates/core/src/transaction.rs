@@ -63,6 +63,8 @@ use {
 /// - `message`: The versioned message containing the transaction instructions
 ///   and account keys
 /// - `block_time`: The Unix timestamp of when the transaction was processed.
+/// - `index`: Position of the transaction in its block, when the datasource
+///   knows it.
 ///
 /// Note: The `block_time` field may not be returned in all scenarios.
 #[derive(Debug, Clone, Default)]
@@ -74,6 +76,7 @@ pub struct TransactionMetadata {
     pub message: solana_program::message::VersionedMessage,
     pub block_time: Option<i64>,
     pub block_hash: Option<Hash>,
+    pub index: Option<u64>,
 }
 
 /// Tries convert transaction update into the metadata.
@@ -113,6 +116,7 @@ impl TryFrom<crate::datasource::TransactionUpdate> for TransactionMetadata {
             message: value.transaction.message.clone(),
             block_time: value.block_time,
             block_hash: value.block_hash,
+            index: value.index,
         })
     }
 }
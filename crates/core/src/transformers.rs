@@ -739,6 +739,7 @@ mod tests {
             slot: 123,
             block_time: Some(123),
             block_hash: Hash::from_str("9bit9vXNX9HyHwL89aGDNmk3vbyAM96nvb6F4SaoM1CU").ok(),
+            index: None,
         };
         let transaction_metadata = transaction_update
             .clone()
@@ -1167,6 +1168,7 @@ mod tests {
             slot: 123,
             block_time: Some(123),
             block_hash: None,
+            index: None,
         };
         let transaction_metadata = transaction_update
             .clone()
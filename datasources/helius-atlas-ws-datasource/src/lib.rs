@@ -595,6 +595,7 @@ impl Datasource for HeliusWebsocket {
                                                 slot: tx_event.slot,
                                                 block_time: None,
                                                 block_hash: None,
+                                                index: None,
                                             }));
 
                                             metrics
@@ -279,7 +279,7 @@ fn task_processor(
                         let block_start_time = Instant::now();
                         let block_hash = Hash::from_str(&block.blockhash).ok();
                         if let Some(transactions) = block.transactions {
-                            for encoded_transaction_with_status_meta in transactions {
+                            for (index, encoded_transaction_with_status_meta) in transactions.into_iter().enumerate() {
                                 let start_time = std::time::Instant::now();
 
                                 let meta_original = if let Some(meta) = encoded_transaction_with_status_meta.clone().meta {
@@ -310,6 +310,7 @@ fn task_processor(
                                     slot,
                                     block_time: block.block_time,
                                     block_hash,
+                                    index: Some(index as u64),
                                 }));
 
                                 metrics
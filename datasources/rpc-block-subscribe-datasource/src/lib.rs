@@ -29,6 +29,9 @@ const RECONNECTION_DELAY_MS: u64 = 3000;
 pub struct Filters {
     pub block_filter: RpcBlockSubscribeFilter,
     pub block_subscribe_config: Option<RpcBlockSubscribeConfig>,
+    /// Emit transactions that failed on-chain too, with their error in the
+    /// metadata status. Off by default.
+    pub include_failed: bool,
 }
 
 impl Filters {
@@ -39,8 +42,14 @@ impl Filters {
         Filters {
             block_filter,
             block_subscribe_config,
+            include_failed: false,
         }
     }
+
+    pub const fn with_failed_transactions(mut self, include_failed: bool) -> Self {
+        self.include_failed = include_failed;
+        self
+    }
 }
 
 pub struct RpcBlockSubscribe {
@@ -153,23 +162,58 @@ impl Datasource for RpcBlockSubscribe {
                                             let meta_original = if let Some(meta) = encoded_transaction_with_status_meta.clone().meta {
                                                 meta
                                             } else {
+                                                metrics.increment_counter("block_subscribe_transactions_missing_meta", 1)
+                                                    .await
+                                                    .unwrap_or_else(|value| log::error!("Error recording metric: {}", value));
                                                 continue;
                                             };
 
-                                            if meta_original.status.is_err() {
+                                            if meta_original.status.is_err() && !filters.include_failed {
                                                 continue;
                                             }
 
                                             let Some(decoded_transaction) = encoded_transaction_with_status_meta.transaction.decode() else {
                                                 log::error!("Failed to decode transaction: {:?}", encoded_transaction_with_status_meta);
+                                                metrics.increment_counter("block_subscribe_transaction_decode_failures", 1)
+                                                    .await
+                                                    .unwrap_or_else(|value| log::error!("Error recording metric: {}", value));
                                                 continue;
                                             };
 
                                             let Ok(meta_needed) = transaction_metadata_from_original_meta(meta_original) else {
                                                 log::error!("Error getting metadata from transaction original meta.");
+                                                metrics.increment_counter("block_subscribe_transaction_metadata_failures", 1)
+                                                    .await
+                                                    .unwrap_or_else(|value| log::error!("Error recording metric: {}", value));
                                                 continue;
                                             };
 
+                                            // Account indexes of v0 messages past the static keys resolve
+                                            // against the loaded addresses, which must all be present.
+                                            let lookups = decoded_transaction
+                                                .message
+                                                .address_table_lookups()
+                                                .map_or(0, |lookups| {
+                                                    lookups
+                                                        .iter()
+                                                        .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+                                                        .sum::<usize>()
+                                                });
+                                            let loaded = meta_needed.loaded_addresses.writable.len()
+                                                + meta_needed.loaded_addresses.readonly.len();
+                                            if lookups != loaded {
+                                                log::warn!(
+                                                    "Transaction {} loads {} lookup table addresses but the metadata has {}",
+                                                    decoded_transaction.get_signature(),
+                                                    lookups,
+                                                    loaded
+                                                );
+                                                metrics.increment_counter("block_subscribe_unresolved_lookup_tables", 1)
+                                                    .await
+                                                    .unwrap_or_else(|value| log::error!("Error recording metric: {}", value));
+                                                continue;
+                                            }
+
                                             let update = Update::Transaction(Box::new(TransactionUpdate {
                                                 signature: *decoded_transaction.get_signature(),
                                                 transaction: decoded_transaction.clone(),
@@ -178,6 +222,8 @@ impl Datasource for RpcBlockSubscribe {
                                                 slot,
                                                 block_time: block.block_time,
                                                 block_hash,
+                                                // Filtered blocks only carry the matching transactions
+                                                index: None,
                                             }));
 
                                             metrics
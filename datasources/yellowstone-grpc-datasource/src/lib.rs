@@ -338,6 +338,7 @@ async fn send_subscribe_update_transaction_info(
             slot,
             block_time,
             block_hash: None,
+            index: Some(transaction_info.index),
         }));
         if let Err(e) = sender.try_send((update, id)) {
             log::error!(
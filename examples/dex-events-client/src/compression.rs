@@ -0,0 +1,106 @@
+use std::io::{self, Read, Write};
+
+/// Separates the topic from the codec of a compressed ZeroMQ message, e.g.
+/// `dex_events@zstd`. Subscribing to `dex_events` still matches by prefix.
+pub const CODEC_SEPARATOR: char = '@';
+
+/// Codec of a compressed payload, named in the topic frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Zstandard at the given level.
+    Zstd(i32),
+    /// LZ4 frame format.
+    Lz4,
+    Gzip,
+}
+
+impl Compression {
+    /// Parses a codec name (`zstd`, `lz4` or `gzip`), using the default
+    /// level for zstd.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "zstd" => Some(Compression::Zstd(zstd::DEFAULT_COMPRESSION_LEVEL)),
+            "lz4" => Some(Compression::Lz4),
+            "gzip" | "gz" => Some(Compression::Gzip),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::Zstd(_) => "zstd",
+            Compression::Lz4 => "lz4",
+            Compression::Gzip => "gzip",
+        }
+    }
+
+    /// Topic frame announcing this codec.
+    pub fn topic(&self, topic: &str) -> String {
+        format!("{}{}{}", topic, CODEC_SEPARATOR, self.name())
+    }
+
+    pub fn compress(&self, payload: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::Zstd(level) => zstd::encode_all(payload, *level),
+            Compression::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder.write_all(payload)?;
+                encoder.finish().map_err(io::Error::other)
+            }
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(payload)?;
+                encoder.finish()
+            }
+        }
+    }
+
+    pub fn decompress(&self, payload: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decompressed = Vec::new();
+        match self {
+            Compression::Zstd(_) => return zstd::decode_all(payload),
+            Compression::Lz4 => lz4_flex::frame::FrameDecoder::new(payload).read_to_end(&mut decompressed)?,
+            Compression::Gzip => flate2::read::GzDecoder::new(payload).read_to_end(&mut decompressed)?,
+        };
+        Ok(decompressed)
+    }
+}
+
+/// Splits a topic frame into the topic and the codec it names, if any.
+/// Fails on an unknown codec rather than handing compressed bytes to the
+/// decoder.
+pub fn split_topic(topic: &str) -> Result<(&str, Option<Compression>), String> {
+    match topic.rsplit_once(CODEC_SEPARATOR) {
+        Some((topic, codec)) => Compression::from_name(codec)
+            .map(|compression| (topic, Some(compression)))
+            .ok_or_else(|| format!("Unknown payload compression {}", codec)),
+        None => Ok((topic, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_every_codec() {
+        let payload = br#"{"event_type":"swap","platform":"Raydium AMM V4","signature":"sig","timestamp":1700000000,"details":{}}"#.repeat(4);
+
+        for compression in [Compression::Zstd(3), Compression::Lz4, Compression::Gzip] {
+            let compressed = compression.compress(&payload).unwrap();
+            assert!(compressed.len() < payload.len(), "{} did not compress", compression.name());
+            assert_eq!(compression.decompress(&compressed).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn splits_codec_from_topic() {
+        assert_eq!(split_topic("dex_events").unwrap(), ("dex_events", None));
+        assert_eq!(split_topic("dex.swaps@lz4").unwrap(), ("dex.swaps", Some(Compression::Lz4)));
+        assert_eq!(
+            split_topic(&Compression::Zstd(3).topic("dex_events")).unwrap().1.map(|c| c.name()),
+            Some("zstd")
+        );
+        assert!(split_topic("dex_events@brotli").is_err());
+    }
+}
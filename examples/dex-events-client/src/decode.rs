@@ -0,0 +1,154 @@
+use serde_json::{Map, Value};
+
+use crate::event::DexEventData;
+
+fn to_snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_uppercase() {
+            if !snake.is_empty() && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn snake_case_keys(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (to_snake_case(&key), snake_case_keys(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(snake_case_keys).collect()),
+        value => value,
+    }
+}
+
+/// Normalizes the envelope and the typed `payload`, whose keys are all
+/// field names. `details` is left as published: some of its keys are data,
+/// e.g. mints or window names, that a case change would corrupt.
+fn snake_case_envelope(value: Value) -> Value {
+    let Value::Object(object) = value else {
+        return value;
+    };
+    Value::Object(
+        object
+            .into_iter()
+            .map(|(key, value)| match to_snake_case(&key) {
+                key if key == "payload" => (key, snake_case_keys(value)),
+                key => (key, value),
+            })
+            .collect::<Map<_, _>>(),
+    )
+}
+
+/// Decodes a published JSON or MessagePack payload. Envelope and `payload`
+/// keys are normalized to snake_case so that payloads published with
+/// `PAYLOAD_KEY_CASE=camel` decode the same way, while `details` keeps the
+/// published key case; stringified integers are left as strings inside
+/// `details` (see [`DexEventData::detail_u64`]).
+pub fn decode(payload: &[u8]) -> Result<DexEventData, serde_json::Error> {
+    // A JSON object starts with `{`, a MessagePack map never does
+    let value: Value = match payload.first() {
+        Some(b'{' | b' ' | b'\t' | b'\n' | b'\r') | None => serde_json::from_slice(payload)?,
+        Some(_) => rmp_serde::from_slice(payload).map_err(<serde_json::Error as serde::de::Error>::custom)?,
+    };
+    serde_json::from_value(snake_case_envelope(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{event::EventType, payload::DexEventPayload},
+    };
+
+    #[test]
+    fn decodes_snake_case_payload() {
+        let payload = br#"{"event_type":"swap","platform":"Raydium AMM V4","signature":"sig","timestamp":1700000000,"details":{"amount_in":1000}}"#;
+
+        let event = decode(payload).unwrap();
+
+        assert_eq!(event.kind(), EventType::Swap);
+        assert_eq!(event.timestamp, 1_700_000_000);
+        assert_eq!(event.detail_u64("amount_in"), Some(1000));
+    }
+
+    #[test]
+    fn decodes_camel_case_payload_with_stringified_integers() {
+        let payload = br#"{"eventType":"whale_trade","platform":"Jupiter Swap","signature":"sig","timestamp":"1700000000","details":{"inputAmount":"18446744073709551615"}}"#;
+
+        let event = decode(payload).unwrap();
+
+        assert_eq!(event.kind(), EventType::WhaleTrade);
+        assert_eq!(event.timestamp, 1_700_000_000);
+        assert_eq!(event.detail_u64("inputAmount"), Some(u64::MAX));
+    }
+
+    #[test]
+    fn keeps_data_keys_of_details() {
+        let payload = br#"{"eventType":"stats","platform":"Raydium AMM V4","signature":"","timestamp":1700000000,"details":{"windows":{"1m":{"trades":1}},"byMint":{"EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v":"5"}},"payload":{"kind":"swap","instruction":"SwapBaseIn","inputMint":"EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v","executed":true}}"#;
+
+        let event = decode(payload).unwrap();
+
+        assert_eq!(event.details["byMint"]["EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"], "5");
+        assert_eq!(event.details["windows"]["1m"]["trades"], 1);
+        let Some(DexEventPayload::Swap(swap)) = event.payload else {
+            panic!("expected a swap payload");
+        };
+        assert_eq!(swap.input_mint.as_deref(), Some("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"));
+    }
+
+    #[test]
+    fn decodes_block_time_when_present() {
+        let with_block_time = br#"{"event_type":"swap","platform":"Raydium AMM V4","signature":"sig","timestamp":1700000005,"block_time":1700000000,"details":{}}"#;
+        let watermark = br#"{"event_type":"watermark","platform":"","signature":"","timestamp":1700000005,"details":{"topic":"dex_events","max_block_time":1700000000,"min_unflushed_block_time":null}}"#;
+
+        assert_eq!(decode(with_block_time).unwrap().block_time, Some(1_700_000_000));
+
+        let event = decode(watermark).unwrap();
+        assert_eq!(event.kind(), EventType::Watermark);
+        assert_eq!(event.block_time, None);
+        assert_eq!(event.detail_u64("max_block_time"), Some(1_700_000_000));
+    }
+
+    #[test]
+    fn decodes_message_pack_payload() {
+        let json: Value = serde_json::from_slice(br#"{"eventType":"swap","platform":"Raydium AMM V4","signature":"sig","timestamp":1700000000,"slot":250000000,"details":{"amountIn":18446744073709551615}}"#).unwrap();
+        let payload = rmp_serde::to_vec_named(&json).unwrap();
+
+        let event = decode(&payload).unwrap();
+
+        assert_eq!(event.kind(), EventType::Swap);
+        assert_eq!(event.slot, Some(250_000_000));
+        assert_eq!(event.detail_u64("amountIn"), Some(u64::MAX));
+    }
+
+    #[test]
+    fn decodes_slot_as_number_or_string() {
+        let number = br#"{"event_type":"swap","platform":"Raydium AMM V4","signature":"sig","timestamp":1700000005,"slot":250000000,"details":{}}"#;
+        let string = br#"{"eventType":"block_summary","platform":"","signature":"","timestamp":"1700000005","slot":"250000000","details":{"swaps":3}}"#;
+
+        assert_eq!(decode(number).unwrap().slot, Some(250_000_000));
+
+        let event = decode(string).unwrap();
+        assert_eq!(event.kind(), EventType::BlockSummary);
+        assert_eq!(event.slot, Some(250_000_000));
+    }
+
+    #[test]
+    fn decodes_event_position() {
+        let payload = br#"{"eventType":"swap","platform":"Raydium AMM V4","signature":"sig","timestamp":"1700000005","slot":"250000000","txIndex":"12","instructionIndex":"3","isInner":true,"feePayer":"payer","trader":"wallet","details":{}}"#;
+
+        let event = decode(payload).unwrap();
+        assert_eq!((event.tx_index, event.instruction_index, event.is_inner), (Some(12), Some(3), Some(true)));
+        assert_eq!(event.fee_payer.as_deref(), Some("payer"));
+        assert_eq!(event.trader.as_deref(), Some("wallet"));
+    }
+}
@@ -0,0 +1,26 @@
+#[derive(Debug)]
+pub enum ClientError {
+    Zmq(String),
+    Kafka(String),
+    WebSocket(String),
+    Decode(serde_json::Error),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ClientError::Zmq(e) => write!(f, "ZMQ Error: {}", e),
+            ClientError::Kafka(e) => write!(f, "Kafka Error: {}", e),
+            ClientError::WebSocket(e) => write!(f, "WebSocket Error: {}", e),
+            ClientError::Decode(e) => write!(f, "Decode Error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(e: serde_json::Error) -> Self {
+        ClientError::Decode(e)
+    }
+}
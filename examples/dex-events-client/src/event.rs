@@ -0,0 +1,161 @@
+use {
+    crate::payload::DexEventPayload,
+    serde::{Deserialize, Deserializer, Serialize},
+};
+
+/// Envelope of every published event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DexEventData {
+    pub event_type: String,  // "swap", "mint_burn", "liquidity", "new_pool", ...
+    pub platform: String,
+    pub signature: String,
+    /// Block time of the transaction in seconds, or when the event was
+    /// decoded for events without one.
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    pub timestamp: u64,
+    /// When the parser decoded the event, in milliseconds; `ingested_at -
+    /// block_time` is the pipeline latency. Absent for aggregates and
+    /// watermarks.
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub ingested_at: Option<u64>,
+    /// Block time of the transaction the event was decoded from, absent for
+    /// events not tied to a block (aggregates, watermarks).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_time: Option<i64>,
+    /// Slot of the transaction the event was decoded from, absent like
+    /// `block_time`.
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub slot: Option<u64>,
+    /// Position of the transaction in its block, known for the
+    /// datasources that receive whole blocks; with `slot`, `instruction_index`
+    /// and `is_inner` it orders the events of a slot.
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub tx_index: Option<u64>,
+    /// Index of the top-level instruction the event was decoded from; inner
+    /// instructions carry the index of the instruction that invoked them.
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u32_from_number_or_string")]
+    pub instruction_index: Option<u32>,
+    /// Whether the event comes from an inner (CPI) instruction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_inner: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_payer: Option<String>,
+    /// Wallet behind a trading event: the owner of the swapped token
+    /// accounts, the liquidity provider or the pool creator. Absent when it
+    /// did not sign, e.g. for the venue swap of an aggregator route.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trader: Option<String>,
+    pub details: serde_json::Value,
+    /// `details` of the trading events normalized to the same fields on
+    /// every platform, absent for the other event types.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<DexEventPayload>,
+}
+
+impl DexEventData {
+    pub fn kind(&self) -> EventType {
+        EventType::from(self.event_type.as_str())
+    }
+
+    /// Reads an unsigned detail field, whether it was published as a number
+    /// or stringified for JavaScript consumers.
+    pub fn detail_u64(&self, key: &str) -> Option<u64> {
+        match self.details.get(key)? {
+            serde_json::Value::Number(number) => number.as_u64(),
+            serde_json::Value::String(string) => string.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Whether the event comes from a failed transaction, flagged with
+    /// `success: false`.
+    pub fn is_failed(&self) -> bool {
+        self.details.get("success") == Some(&serde_json::Value::Bool(false))
+    }
+}
+
+/// Known values of [`DexEventData::event_type`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EventType {
+    Swap,
+    Liquidity,
+    NewPool,
+    MintBurn,
+    CreatorFee,
+    FeeConfig,
+    ExecutionQuality,
+    WhaleTrade,
+    LaunchFunnel,
+    Graduation,
+    Fill,
+    TokenTransfer,
+    Transaction,
+    Watermark,
+    BlockSummary,
+    PoolState,
+    PoolDepth,
+    Mev,
+    Stats,
+    ForkedOut,
+    Other(String),
+}
+
+impl From<&str> for EventType {
+    fn from(event_type: &str) -> Self {
+        match event_type {
+            "swap" => EventType::Swap,
+            "liquidity" => EventType::Liquidity,
+            "new_pool" => EventType::NewPool,
+            "mint_burn" => EventType::MintBurn,
+            "creator_fee" => EventType::CreatorFee,
+            "fee_config" => EventType::FeeConfig,
+            "execution_quality" => EventType::ExecutionQuality,
+            "whale_trade" => EventType::WhaleTrade,
+            "launch_funnel" => EventType::LaunchFunnel,
+            "graduation" => EventType::Graduation,
+            "fill" => EventType::Fill,
+            "token_transfer" => EventType::TokenTransfer,
+            "transaction" => EventType::Transaction,
+            "watermark" => EventType::Watermark,
+            "block_summary" => EventType::BlockSummary,
+            "pool_state" => EventType::PoolState,
+            "pool_depth" => EventType::PoolDepth,
+            "mev" => EventType::Mev,
+            "stats" => EventType::Stats,
+            "forked_out" => EventType::ForkedOut,
+            other => EventType::Other(other.to_string()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(u64),
+    String(String),
+}
+
+impl NumberOrString {
+    fn parse<E: serde::de::Error>(self) -> Result<u64, E> {
+        match self {
+            NumberOrString::Number(number) => Ok(number),
+            NumberOrString::String(string) => string.parse().map_err(E::custom),
+        }
+    }
+}
+
+pub(crate) fn u64_from_number_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    NumberOrString::deserialize(deserializer)?.parse()
+}
+
+pub(crate) fn option_u64_from_number_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    Option::<NumberOrString>::deserialize(deserializer)?
+        .map(NumberOrString::parse)
+        .transpose()
+}
+
+fn option_u32_from_number_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    option_u64_from_number_or_string(deserializer)?
+        .map(|value| u32::try_from(value).map_err(serde::de::Error::custom))
+        .transpose()
+}
@@ -0,0 +1,50 @@
+use rdkafka::{
+    config::ClientConfig,
+    consumer::{Consumer, StreamConsumer},
+    Message,
+};
+
+use crate::{decode::decode, error::ClientError, event::DexEventData};
+
+/// Kafka consumer subscribed to one or more publisher topics.
+pub struct KafkaEventConsumer {
+    consumer: StreamConsumer,
+}
+
+impl KafkaEventConsumer {
+    pub fn new(brokers: &str, group_id: &str, topics: &[&str]) -> Result<Self, ClientError> {
+        let config = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("group.id", group_id)
+            .set("enable.auto.commit", "true")
+            .set("auto.offset.reset", "latest")
+            .clone();
+        Self::new_with_config(config, topics)
+    }
+
+    pub fn new_with_config(config: ClientConfig, topics: &[&str]) -> Result<Self, ClientError> {
+        let consumer: StreamConsumer = config
+            .create()
+            .map_err(|e| ClientError::Kafka(format!("Failed to create consumer: {}", e)))?;
+        consumer
+            .subscribe(topics)
+            .map_err(|e| ClientError::Kafka(format!("Failed to subscribe to {:?}: {}", topics, e)))?;
+
+        Ok(Self { consumer })
+    }
+
+    /// Waits for the next event and returns it with its topic. Messages
+    /// without a payload are skipped.
+    pub async fn recv(&self) -> Result<(String, DexEventData), ClientError> {
+        loop {
+            let message = self
+                .consumer
+                .recv()
+                .await
+                .map_err(|e| ClientError::Kafka(format!("Failed to receive message: {}", e)))?;
+            if let Some(payload) = message.payload() {
+                return Ok((message.topic().to_string(), decode(payload)?));
+            }
+        }
+    }
+}
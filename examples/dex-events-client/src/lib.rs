@@ -0,0 +1,24 @@
+//! Typed consumers for the events published by `carbon-dex-events-parser`.
+//!
+//! Every subscriber yields [`DexEventData`] values decoded with [`decode`],
+//! which accepts payloads in any of the publisher's output formats.
+
+pub mod compression;
+pub mod decode;
+pub mod error;
+pub mod event;
+pub mod kafka;
+pub mod payload;
+pub mod ws;
+pub mod zmq;
+
+pub use {
+    compression::Compression,
+    decode::decode,
+    error::ClientError,
+    event::{DexEventData, EventType},
+    kafka::KafkaEventConsumer,
+    payload::DexEventPayload,
+    ws::{WsEventClient, WsSubscription},
+    zmq::ZmqSubscriber,
+};
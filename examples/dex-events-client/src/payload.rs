@@ -0,0 +1,583 @@
+use {
+    crate::event::{option_u64_from_number_or_string, u64_from_number_or_string},
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+};
+
+/// Typed body of the trading events, with the same field names whatever
+/// the platform. Published alongside the free-form `details`, whose keys
+/// follow each program's own instruction arguments.
+///
+/// Amounts are raw token units, with the same amounts in whole tokens in
+/// the `ui_` fields when the parser knew the decimals of the mint. Swaps carry the executed amounts when the
+/// parser could read them from the balance changes (`executed`); otherwise,
+/// when a program only fixes one side of a trade, the other side is absent
+/// and the bound it sets is in `min_amount_out` or `max_amount_in`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DexEventPayload {
+    Swap(SwapPayload),
+    LiquidityChange(LiquidityChangePayload),
+    NewPool(NewPoolPayload),
+    TokenLaunch(TokenLaunchPayload),
+    OrderPlaced(OrderPlacedPayload),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SwapPayload {
+    /// Instruction or program event the swap was decoded from.
+    pub instruction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trader: Option<String>,
+    /// Token traded against native SOL on bonding curves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_mint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_mint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_mint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_mint: Option<String>,
+    /// `buy` or `sell` of the base mint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub amount_in: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub amount_out: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ui_amount_in: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ui_amount_out: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub min_amount_out: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub max_amount_in: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub slippage_bps: Option<u64>,
+    /// Whether the mints and amounts are the executed ones, read from the
+    /// balance changes of the transaction.
+    #[serde(default)]
+    pub executed: bool,
+    /// Hops of an aggregator route, in execution order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub legs: Vec<SwapLeg>,
+}
+
+/// One venue swap of an aggregator route.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SwapLeg {
+    pub amm_program: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub venue: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool: Option<String>,
+    pub input_mint: String,
+    pub output_mint: String,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    pub input_amount: u64,
+    #[serde(deserialize_with = "u64_from_number_or_string")]
+    pub output_amount: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LiquidityChangePayload {
+    pub instruction: String,
+    /// `add` or `remove`.
+    pub direction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Mint of single-sided deposits and withdrawals.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mint: Option<String>,
+    /// Amount of the first pool token, exact or the instruction's bound.
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub base_amount: Option<u64>,
+    /// Amount of the second pool token, exact or the instruction's bound.
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub quote_amount: Option<u64>,
+    /// Amount of `mint` for single-sided changes.
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub token_amount: Option<u64>,
+    /// LP tokens minted or burned, exact or the instruction's bound.
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub lp_amount: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_ui_amount: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_ui_amount: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_ui_amount: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lp_ui_amount: Option<f64>,
+    /// Concentrated liquidity units, a decimal string as they exceed `u64`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub liquidity: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NewPoolPayload {
+    pub instruction: String,
+    /// Pool, or bonding curve for graduations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_mint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_mint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lp_mint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub base_amount: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub quote_amount: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_ui_amount: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_ui_amount: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenLaunchPayload {
+    pub instruction: String,
+    pub mint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curve: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "option_u64_from_number_or_string")]
+    pub supply: Option<u64>,
+    /// Launchpad behind a launch on a shared bonding curve program, e.g.
+    /// Believe on Meteora's Dynamic Bonding Curve.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launchpad: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderPlacedPayload {
+    pub instruction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_mint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_mint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<String>,
+}
+
+impl DexEventPayload {
+    /// Normalizes the `details` of a `swap`, `liquidity`, `new_pool` or
+    /// `mint_burn` event; `None` for the other event types and for details
+    /// that are not recognized.
+    pub fn from_details(event_type: &str, details: &Value) -> Option<Self> {
+        let fields = Fields(details);
+        match event_type {
+            "swap" => Some(match fields.string("type") {
+                Some(instruction) => DexEventPayload::Swap(swap(instruction, &fields)),
+                None => order_book(&fields)?,
+            }),
+            "liquidity" => Some(DexEventPayload::LiquidityChange(LiquidityChangePayload {
+                instruction: fields.string("action")?,
+                direction: fields.string("type")?,
+                pool: fields.string("pool"),
+                provider: fields.string("user"),
+                mint: fields.string("mint"),
+                base_amount: fields.first_u64(LIQUIDITY_BASE_AMOUNTS),
+                quote_amount: fields.first_u64(LIQUIDITY_QUOTE_AMOUNTS),
+                token_amount: fields.first_u64(LIQUIDITY_TOKEN_AMOUNTS),
+                lp_amount: fields.first_u64(LIQUIDITY_LP_AMOUNTS),
+                base_ui_amount: fields.first_ui(LIQUIDITY_BASE_AMOUNTS),
+                quote_ui_amount: fields.first_ui(LIQUIDITY_QUOTE_AMOUNTS),
+                token_ui_amount: fields.first_ui(LIQUIDITY_TOKEN_AMOUNTS),
+                lp_ui_amount: fields.first_ui(LIQUIDITY_LP_AMOUNTS),
+                liquidity: fields.decimal("liquidity").or_else(|| fields.decimal("liquidity_amount")),
+            })),
+            // Moonshot announces new tokens as pools of their bonding curve.
+            "new_pool" if fields.string("symbol").is_some() => Some(launch(&fields)?),
+            "new_pool" => Some(DexEventPayload::NewPool(new_pool(fields.string("type")?, &fields))),
+            "mint_burn" if fields.string("action").as_deref() == Some("CreateEvent") => Some(launch(&fields)?),
+            _ => None,
+        }
+    }
+}
+
+const SWAP_IN_AMOUNTS: &[&str] = &["amount_in", "in_amount", "quote_amount_in", "base_amount_in"];
+const SWAP_OUT_AMOUNTS: &[&str] = &["amount_out", "out_amount", "base_amount_out", "quote_amount_out"];
+const LIQUIDITY_BASE_AMOUNTS: &[&str] = &[
+    "base_amount_in", "base_amount_out", "max_coin_amount", "amount_0_max", "amount_0_min",
+    "token_max_a", "token_min_a", "maximum_token_a_amount", "minimum_token_a_amount", "minimum_a_token_out",
+    "amount_x",
+];
+const LIQUIDITY_QUOTE_AMOUNTS: &[&str] = &[
+    "quote_amount_in", "quote_amount_out", "max_pc_amount", "amount_1_max", "amount_1_min",
+    "token_max_b", "token_min_b", "maximum_token_b_amount", "minimum_token_b_amount", "minimum_b_token_out",
+    "amount_y",
+];
+const LIQUIDITY_TOKEN_AMOUNTS: &[&str] =
+    &["source_token_amount", "destination_token_amount", "lst_amount", "min_lst_out"];
+const LIQUIDITY_LP_AMOUNTS: &[&str] = &[
+    "lp_token_amount_out", "lp_token_amount_in", "pool_token_amount",
+    "minimum_pool_token_amount", "maximum_pool_token_amount", "amount", "lp_token_amount", "min_lp_out",
+];
+
+/// Maps the amount arguments of each program onto input and output.
+fn swap(instruction: String, fields: &Fields) -> SwapPayload {
+    let mut payload = SwapPayload {
+        pool: fields.first_string(&["pool", "curve"]),
+        trader: fields.string("user"),
+        mint: fields.string("mint"),
+        input_mint: fields.string("input_mint"),
+        output_mint: fields.string("output_mint"),
+        base_mint: fields.string("base_mint"),
+        quote_mint: fields.string("quote_mint"),
+        side: fields.string("side"),
+        amount_in: fields.first_u64(SWAP_IN_AMOUNTS),
+        amount_out: fields.first_u64(SWAP_OUT_AMOUNTS),
+        ui_amount_in: fields.first_ui(SWAP_IN_AMOUNTS),
+        ui_amount_out: fields.first_ui(SWAP_OUT_AMOUNTS),
+        min_amount_out: fields.first_u64(&["minimum_amount_out", "minimum_out_amount", "min_sol_output"]),
+        max_amount_in: fields.first_u64(&["max_amount_in", "max_sol_cost"]),
+        slippage_bps: fields.u64("slippage_bps"),
+        instruction,
+        executed: false,
+        legs: legs(fields),
+    };
+    if let Some(executed) = details_object(fields.0, "executed") {
+        let executed = Fields(executed);
+        payload.input_mint = executed.string("input_mint");
+        payload.output_mint = executed.string("output_mint");
+        payload.amount_in = executed.u64("input_amount");
+        payload.amount_out = executed.u64("output_amount");
+        payload.ui_amount_in = fields.f64("ui_amount_in");
+        payload.ui_amount_out = fields.f64("ui_amount_out");
+        payload.pool = executed.string("pool").or(payload.pool);
+        payload.trader = executed.string("trader").or(payload.trader);
+        payload.executed = true;
+        return payload;
+    }
+    if let Some(is_buy) = fields.0.get("is_buy").and_then(Value::as_bool) {
+        // Bonding curve trade events: SOL in and tokens out on buys.
+        let (sol, token) = (fields.u64("sol_amount"), fields.u64("token_amount"));
+        (payload.amount_in, payload.amount_out) = if is_buy { (sol, token) } else { (token, sol) };
+        let (sol, token) = (fields.ui("sol_amount"), fields.ui("token_amount"));
+        (payload.ui_amount_in, payload.ui_amount_out) = if is_buy { (sol, token) } else { (token, sol) };
+    } else if let Some(collateral) = fields.u64("collateral_amount") {
+        let token = fields.u64("token_amount");
+        (payload.amount_in, payload.amount_out) = match payload.instruction.as_str() {
+            "Sell" => (token, Some(collateral)),
+            _ => (Some(collateral), token),
+        };
+        let (collateral, token) = (fields.ui("collateral_amount"), fields.ui("token_amount"));
+        (payload.ui_amount_in, payload.ui_amount_out) = match payload.instruction.as_str() {
+            "Sell" => (token, collateral),
+            _ => (collateral, token),
+        };
+    } else if let Some(amount) = fields.u64("amount") {
+        let threshold = fields.u64("other_amount_threshold");
+        let exact_in = match fields.0.get("is_base_input").or_else(|| fields.0.get("amount_specified_is_input")) {
+            Some(flag) => flag.as_bool().unwrap_or(true),
+            // Bonding curve instructions fix the tokens bought or sold.
+            None => payload.max_amount_in.is_none(),
+        };
+        if exact_in {
+            payload.amount_in = Some(amount);
+            payload.ui_amount_in = fields.ui("amount");
+            payload.min_amount_out = payload.min_amount_out.or(threshold);
+        } else {
+            payload.amount_out = Some(amount);
+            payload.ui_amount_out = fields.ui("amount");
+            payload.max_amount_in = payload.max_amount_in.or(threshold);
+        }
+    }
+    payload
+}
+
+fn new_pool(instruction: String, fields: &Fields) -> NewPoolPayload {
+    let payload = NewPoolPayload {
+        instruction,
+        pool: fields.first_string(&["pool", "curve", "bonding_curve"]),
+        creator: fields.string("creator"),
+        base_mint: fields.first_string(&["base_mint", "token_a_mint", "mint"]),
+        quote_mint: fields.first_string(&["quote_mint", "token_b_mint"]),
+        lp_mint: fields.string("lp_mint"),
+        base_amount: fields.first_u64(&["base_amount_in", "mint_amount"]),
+        quote_amount: fields.first_u64(&["quote_amount_in", "sol_amount"]),
+        base_ui_amount: fields.first_ui(&["base_amount_in", "mint_amount"]),
+        quote_ui_amount: fields.first_ui(&["quote_amount_in", "sol_amount"]),
+    };
+    // The normalized pair orders the mints like swaps and carries the
+    // initial liquidity of every platform.
+    let Some(new_pair) = details_object(fields.0, "new_pair") else {
+        return payload;
+    };
+    let new_pair = Fields(new_pair);
+    NewPoolPayload {
+        pool: new_pair.string("pool").or(payload.pool),
+        creator: new_pair.string("creator").or(payload.creator),
+        base_mint: new_pair.string("base_mint"),
+        quote_mint: new_pair.string("quote_mint"),
+        base_amount: new_pair.u64("base_amount"),
+        quote_amount: new_pair.u64("quote_amount"),
+        base_ui_amount: new_pair.f64("base_ui_amount"),
+        quote_ui_amount: new_pair.f64("quote_ui_amount"),
+        ..payload
+    }
+}
+
+fn legs(fields: &Fields) -> Vec<SwapLeg> {
+    let Some(legs) = fields.0.get("legs").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    legs.iter()
+        .filter_map(|leg| {
+            let leg = Fields(leg);
+            Some(SwapLeg {
+                amm_program: leg.string("amm_program")?,
+                venue: leg.string("venue"),
+                pool: leg.string("pool"),
+                input_mint: leg.string("input_mint")?,
+                output_mint: leg.string("output_mint")?,
+                input_amount: leg.u64("input_amount")?,
+                output_amount: leg.u64("output_amount")?,
+            })
+        })
+        .collect()
+}
+
+fn launch(fields: &Fields) -> Option<DexEventPayload> {
+    Some(DexEventPayload::TokenLaunch(TokenLaunchPayload {
+        instruction: fields.first_string(&["action", "type"])?,
+        mint: fields.string("mint")?,
+        name: fields.string("name"),
+        symbol: fields.string("symbol"),
+        creator: fields.string("creator"),
+        curve: fields.string("curve"),
+        supply: fields.u64("amount"),
+        launchpad: fields.string("launchpad"),
+    }))
+}
+
+/// Order book programs publish their whole instruction as `instruction`;
+/// the ones placing orders are order events, the others swaps.
+fn order_book(fields: &Fields) -> Option<DexEventPayload> {
+    let instruction = fields.string("instruction")?;
+    let name = instruction
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if name.contains("Order") {
+        return Some(DexEventPayload::OrderPlaced(OrderPlacedPayload {
+            instruction: name,
+            base_mint: fields.string("base_mint"),
+            quote_mint: fields.string("quote_mint"),
+            side: fields.string("side"),
+        }));
+    }
+    Some(DexEventPayload::Swap(SwapPayload {
+        instruction: name,
+        base_mint: fields.string("base_mint"),
+        quote_mint: fields.string("quote_mint"),
+        side: fields.string("side"),
+        ..SwapPayload::default()
+    }))
+}
+
+fn details_object<'a>(details: &'a Value, key: &str) -> Option<&'a Value> {
+    details.get(key).filter(|value| value.is_object())
+}
+
+struct Fields<'a>(&'a Value);
+
+impl Fields<'_> {
+    fn string(&self, key: &str) -> Option<String> {
+        self.0.get(key)?.as_str().map(str::to_string)
+    }
+
+    fn first_string(&self, keys: &[&str]) -> Option<String> {
+        keys.iter().find_map(|key| self.string(key))
+    }
+
+    /// Unsigned field, published as a number or stringified.
+    fn u64(&self, key: &str) -> Option<u64> {
+        match self.0.get(key)? {
+            Value::Number(number) => number.as_u64(),
+            Value::String(string) => string.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn first_u64(&self, keys: &[&str]) -> Option<u64> {
+        keys.iter().find_map(|key| self.u64(key))
+    }
+
+    fn f64(&self, key: &str) -> Option<f64> {
+        self.0.get(key)?.as_f64()
+    }
+
+    /// `<key>_ui`, added by the parser next to raw amounts whose decimals
+    /// it knew.
+    fn ui(&self, key: &str) -> Option<f64> {
+        self.f64(&format!("{}_ui", key))
+    }
+
+    /// Whole-token amount of the field `first_u64` picks.
+    fn first_ui(&self, keys: &[&str]) -> Option<f64> {
+        keys.iter().find(|key| self.u64(key).is_some()).and_then(|key| self.ui(key))
+    }
+
+    fn decimal(&self, key: &str) -> Option<String> {
+        match self.0.get(key)? {
+            Value::Number(number) => Some(number.to_string()),
+            Value::String(string) => Some(string.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn normalizes_amount_names_across_platforms() {
+        let raydium = json!({"type": "SwapBaseIn", "amount_in": 100, "minimum_amount_out": 90});
+        let jupiter = json!({"type": "Route", "in_amount": "100", "slippage_bps": 50});
+        let pump_trade = json!({"type": "TradeEvent", "mint": "M", "sol_amount": 100, "token_amount": 7, "is_buy": true});
+
+        for details in [raydium, jupiter, pump_trade] {
+            let Some(DexEventPayload::Swap(swap)) = DexEventPayload::from_details("swap", &details) else {
+                panic!("not a swap: {}", details);
+            };
+            assert_eq!(swap.amount_in, Some(100));
+        }
+    }
+
+    #[test]
+    fn prefers_executed_amounts() {
+        let details = json!({
+            "type": "SwapBaseIn",
+            "amount_in": 100,
+            "minimum_amount_out": 90,
+            "executed": {"input_mint": "A", "output_mint": "B", "input_amount": 100, "output_amount": "95", "pool": "P", "trader": "T"},
+            "ui_amount_in": 0.1,
+            "ui_amount_out": 9.5
+        });
+        let Some(DexEventPayload::Swap(swap)) = DexEventPayload::from_details("swap", &details) else {
+            panic!("not a swap");
+        };
+        assert!(swap.executed);
+        assert_eq!((swap.amount_in, swap.amount_out), (Some(100), Some(95)));
+        assert_eq!((swap.ui_amount_in, swap.ui_amount_out), (Some(0.1), Some(9.5)));
+        assert_eq!((swap.pool.as_deref(), swap.trader.as_deref()), (Some("P"), Some("T")));
+    }
+
+    #[test]
+    fn reads_route_legs_in_order() {
+        let details = json!({
+            "type": "SharedAccountsRoute",
+            "in_amount": 100,
+            "legs": [
+                {"index": 0, "amm_program": "AMM1", "venue": "Whirlpool", "pool": "P1", "input_mint": "A", "output_mint": "B", "input_amount": 100, "output_amount": "40"},
+                {"index": 1, "amm_program": "AMM2", "pool": null, "input_mint": "B", "output_mint": "C", "input_amount": 40, "output_amount": 7}
+            ]
+        });
+        let Some(DexEventPayload::Swap(swap)) = DexEventPayload::from_details("swap", &details) else {
+            panic!("not a swap");
+        };
+        assert_eq!(swap.legs.len(), 2);
+        assert_eq!((swap.legs[0].pool.as_deref(), swap.legs[0].output_amount), (Some("P1"), 40));
+        assert_eq!((swap.legs[1].pool.as_deref(), swap.legs[1].venue.as_deref()), (None, None));
+        assert_eq!(swap.legs[1].output_mint, "C");
+    }
+
+    #[test]
+    fn exact_out_amounts_are_outputs() {
+        let details = json!({"type": "Swap", "amount": 5, "other_amount_threshold": 9, "amount_specified_is_input": false});
+        let Some(DexEventPayload::Swap(swap)) = DexEventPayload::from_details("swap", &details) else {
+            panic!("not a swap");
+        };
+        assert_eq!((swap.amount_out, swap.max_amount_in, swap.amount_in), (Some(5), Some(9), None));
+    }
+
+    #[test]
+    fn reads_ui_amounts_of_the_picked_fields() {
+        let details = json!({
+            "type": "add",
+            "action": "DepositEvent",
+            "base_amount_in": 2_500_000,
+            "base_amount_in_ui": 2.5,
+            "lp_token_amount_out": 7,
+            "max_coin_amount_ui": 9.0
+        });
+        let Some(DexEventPayload::LiquidityChange(change)) = DexEventPayload::from_details("liquidity", &details) else {
+            panic!("not a liquidity change");
+        };
+        assert_eq!((change.base_amount, change.base_ui_amount), (Some(2_500_000), Some(2.5)));
+        assert_eq!((change.lp_amount, change.lp_ui_amount), (Some(7), None));
+    }
+
+    #[test]
+    fn prefers_the_normalized_new_pair() {
+        let details = json!({
+            "type": "InitializeLbPair",
+            "pool": "P",
+            "base_mint": "X",
+            "quote_mint": "Y",
+            "new_pair": {
+                "pool": "P",
+                "base_mint": "Y",
+                "quote_mint": "X",
+                "creator": "C",
+                "base_amount": "1000",
+                "quote_amount": 5,
+                "base_ui_amount": 0.001,
+                "quote_ui_amount": null
+            }
+        });
+        let Some(DexEventPayload::NewPool(pool)) = DexEventPayload::from_details("new_pool", &details) else {
+            panic!("not a new pool");
+        };
+        assert_eq!((pool.base_mint.as_deref(), pool.quote_mint.as_deref()), (Some("Y"), Some("X")));
+        assert_eq!((pool.base_amount, pool.quote_amount), (Some(1000), Some(5)));
+        assert_eq!((pool.base_ui_amount, pool.quote_ui_amount), (Some(0.001), None));
+        assert_eq!(pool.creator.as_deref(), Some("C"));
+    }
+
+    #[test]
+    fn reads_the_launchpad_of_shared_curve_launches() {
+        let details = json!({
+            "type": "InitializeVirtualPoolWithSplToken",
+            "mint": "M",
+            "symbol": "TKN",
+            "curve": "P",
+            "launchpad": "Believe"
+        });
+        let Some(DexEventPayload::TokenLaunch(launch)) = DexEventPayload::from_details("new_pool", &details) else {
+            panic!("not a token launch");
+        };
+        assert_eq!(launch.launchpad.as_deref(), Some("Believe"));
+        assert_eq!(launch.curve.as_deref(), Some("P"));
+    }
+
+    #[test]
+    fn serializes_with_a_kind_tag() {
+        let details = json!({"type": "mint", "action": "CreateEvent", "mint": "M", "symbol": "TKN"});
+        let payload = DexEventPayload::from_details("mint_burn", &details).unwrap();
+        let value = serde_json::to_value(&payload).unwrap();
+
+        assert_eq!(value["kind"], "token_launch");
+        assert_eq!(serde_json::from_value::<DexEventPayload>(value).unwrap(), payload);
+    }
+}
@@ -0,0 +1,119 @@
+use {
+    futures_util::{SinkExt, StreamExt},
+    serde::{Deserialize, Serialize},
+    tokio::net::TcpStream,
+    tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream},
+};
+
+use crate::{decode::decode, error::ClientError, event::DexEventData};
+
+/// Events a WebSocket server connection receives, sent by the client as a
+/// JSON text frame. Every non-empty list must match, an empty one matches
+/// anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WsSubscription {
+    pub topics: Vec<String>,
+    pub event_types: Vec<String>,
+    pub platforms: Vec<String>,
+    /// Mints the event mentions in any `mint` or `*_mint` detail.
+    pub mints: Vec<String>,
+}
+
+impl WsSubscription {
+    pub fn matches(&self, topic: &str, data: &DexEventData) -> bool {
+        let listed = |values: &[String], value: &str| values.is_empty() || values.iter().any(|v| v == value);
+        listed(&self.topics, topic)
+            && listed(&self.event_types, &data.event_type)
+            && listed(&self.platforms, &data.platform)
+            && (self.mints.is_empty() || mentions_mint(&data.details, &self.mints))
+    }
+}
+
+fn mentions_mint(value: &serde_json::Value, mints: &[String]) -> bool {
+    match value {
+        serde_json::Value::Object(fields) => fields.iter().any(|(key, value)| match value {
+            serde_json::Value::String(mint) if key == "mint" || key.ends_with("_mint") => mints.contains(mint),
+            _ => mentions_mint(value, mints),
+        }),
+        serde_json::Value::Array(values) => values.iter().any(|value| mentions_mint(value, mints)),
+        _ => false,
+    }
+}
+
+/// WebSocket client for a server that pushes one JSON event per text or
+/// binary frame.
+pub struct WsEventClient {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl WsEventClient {
+    pub async fn connect(url: &str) -> Result<Self, ClientError> {
+        let (stream, _) = connect_async(url)
+            .await
+            .map_err(|e| ClientError::WebSocket(format!("Failed to connect to {}: {}", url, e)))?;
+
+        Ok(Self { stream })
+    }
+
+    /// Replaces the events this connection receives, for servers that
+    /// filter per connection like the parser's `ws` publisher.
+    pub async fn subscribe(&mut self, subscription: &WsSubscription) -> Result<(), ClientError> {
+        let frame = serde_json::to_string(subscription)?;
+        self.stream
+            .send(Message::Text(frame))
+            .await
+            .map_err(|e| ClientError::WebSocket(format!("Failed to subscribe: {}", e)))
+    }
+
+    /// Waits for the next event; `None` once the server closed the
+    /// connection. Control frames are skipped.
+    pub async fn recv(&mut self) -> Option<Result<DexEventData, ClientError>> {
+        while let Some(message) = self.stream.next().await {
+            let payload = match message {
+                Ok(Message::Text(text)) => text.into_bytes(),
+                Ok(Message::Binary(bytes)) => bytes,
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => continue,
+                Err(e) => {
+                    return Some(Err(ClientError::WebSocket(format!(
+                        "Failed to receive message: {}",
+                        e
+                    ))))
+                }
+            };
+
+            return Some(decode(&payload).map_err(ClientError::from));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::decode::decode};
+
+    #[test]
+    fn subscription_matches_every_listed_field() {
+        let event = decode(br#"{"event_type":"swap","platform":"Raydium AMM V4","signature":"sig","timestamp":1700000000,"details":{"input_mint":"MintA","legs":[{"output_mint":"MintB"}]}}"#).unwrap();
+        let subscription = WsSubscription {
+            event_types: vec!["swap".to_string()],
+            mints: vec!["MintB".to_string()],
+            ..WsSubscription::default()
+        };
+
+        assert!(WsSubscription::default().matches("dex_events", &event));
+        assert!(subscription.matches("dex_events", &event));
+        assert!(!WsSubscription {
+            platforms: vec!["Orca Whirlpool".to_string()],
+            ..subscription.clone()
+        }
+        .matches("dex_events", &event));
+        assert!(!WsSubscription {
+            mints: vec!["MintC".to_string()],
+            ..subscription
+        }
+        .matches("dex_events", &event));
+    }
+}
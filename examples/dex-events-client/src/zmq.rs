@@ -0,0 +1,62 @@
+use crate::{compression::split_topic, decode::decode, error::ClientError, event::DexEventData};
+
+/// ZeroMQ SUB socket subscribed to one or more publisher topics.
+pub struct ZmqSubscriber {
+    _context: zmq::Context,
+    socket: zmq::Socket,
+}
+
+impl ZmqSubscriber {
+    /// Connects to `endpoint` (e.g. `tcp://localhost:5555`) and subscribes to
+    /// `topics`. ZeroMQ matches topics by prefix; an empty topic receives
+    /// everything.
+    pub fn connect(endpoint: &str, topics: &[&str]) -> Result<Self, ClientError> {
+        let context = zmq::Context::new();
+        let socket = context
+            .socket(zmq::SUB)
+            .map_err(|e| ClientError::Zmq(format!("Failed to create socket: {}", e)))?;
+        socket
+            .connect(endpoint)
+            .map_err(|e| ClientError::Zmq(format!("Failed to connect to {}: {}", endpoint, e)))?;
+        for topic in topics {
+            socket
+                .set_subscribe(topic.as_bytes())
+                .map_err(|e| ClientError::Zmq(format!("Failed to subscribe to {}: {}", topic, e)))?;
+        }
+
+        Ok(Self {
+            _context: context,
+            socket,
+        })
+    }
+
+    /// Blocks until the next event arrives and returns it with its topic.
+    /// Payloads compressed by the publisher (`ZMQ_COMPRESSION`) are
+    /// decompressed, and the codec suffix is removed from the topic.
+    pub fn recv(&self) -> Result<(String, DexEventData), ClientError> {
+        let frames = self
+            .socket
+            .recv_multipart(0)
+            .map_err(|e| ClientError::Zmq(format!("Failed to receive message: {}", e)))?;
+        let [topic, payload] = frames.as_slice() else {
+            return Err(ClientError::Zmq(format!(
+                "Expected 2 frames (topic, payload), got {}",
+                frames.len()
+            )));
+        };
+
+        let topic = String::from_utf8_lossy(topic);
+        let (topic, compression) = split_topic(&topic).map_err(ClientError::Zmq)?;
+        let event = match compression {
+            Some(compression) => {
+                let payload = compression
+                    .decompress(payload)
+                    .map_err(|e| ClientError::Zmq(format!("Failed to decompress {} payload: {}", compression.name(), e)))?;
+                decode(&payload)?
+            }
+            None => decode(payload)?,
+        };
+
+        Ok((topic.to_string(), event))
+    }
+}
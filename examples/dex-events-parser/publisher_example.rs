@@ -16,6 +16,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs(),
+        block_time: None,
         details: json!({
             "amount_in": 1000000,
             "amount_out": 950000,
@@ -78,6 +79,7 @@ pub async fn publish_events_example() -> Result<(), Box<dyn std::error::Error>>
         platform: "Raydium AMM V4".to_string(),
         signature: "swap_signature_123".to_string(),
         timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        block_time: None,
         details: json!({"amount_in": 1000000, "amount_out": 950000}),
     };
     publisher.publish("dex_events", &swap_data).await?;
@@ -88,6 +90,7 @@ pub async fn publish_events_example() -> Result<(), Box<dyn std::error::Error>>
         platform: "Orca Whirlpool".to_string(),
         signature: "liquidity_signature_456".to_string(),
         timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        block_time: None,
         details: json!({"liquidity_amount": 5000000, "token_a": "SOL", "token_b": "USDC"}),
     };
     publisher.publish("dex_events", &liquidity_data).await?;
@@ -98,6 +101,7 @@ pub async fn publish_events_example() -> Result<(), Box<dyn std::error::Error>>
         platform: "Meteora DLMM".to_string(),
         signature: "new_pool_signature_789".to_string(),
         timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        block_time: None,
         details: json!({"active_id": 8000, "bin_step": 25}),
     };
     publisher.publish("dex_events", &new_pool_data).await?;
@@ -0,0 +1,186 @@
+use {
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::json,
+    solana_pubkey::Pubkey,
+    std::collections::HashSet,
+};
+
+use crate::{
+    DexEvent,
+    analytics::{
+        mints::{is_usd_stable, SOL_DECIMALS, USD_STABLE_DECIMALS, USDC_MINT, USDT_MINT, WSOL_MINT},
+        price_index::{PriceIndex, SwapFill},
+    },
+    processors::EventTime,
+    publishers::{Publisher, UnifiedPublisher},
+};
+
+const DEFAULT_TOPIC: &str = "whale_alerts";
+const DEFAULT_MAJOR_THRESHOLD_USD: f64 = 100_000.0;
+const DEFAULT_MEMECOIN_THRESHOLD_USD: f64 = 10_000.0;
+
+/// Threshold bucket a trade falls into. A trade is `Major` only when both
+/// legs are major mints; anything touching another token is a memecoin trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Major,
+    Memecoin,
+}
+
+impl TokenClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenClass::Major => "major",
+            TokenClass::Memecoin => "memecoin",
+        }
+    }
+}
+
+/// Alert stage that publishes `whale_trade` events for swaps whose USD value
+/// crosses the threshold of their token class, on a dedicated topic.
+#[derive(Clone)]
+pub struct WhaleAlerts {
+    publisher: UnifiedPublisher,
+    price_index: PriceIndex,
+    topic: String,
+    major_mints: HashSet<Pubkey>,
+    major_threshold_usd: f64,
+    memecoin_threshold_usd: f64,
+    sol_usd_fallback: Option<f64>,
+}
+
+impl WhaleAlerts {
+    /// Configuration:
+    /// - `WHALE_ALERT_TOPIC` (default `whale_alerts`)
+    /// - `WHALE_MAJOR_THRESHOLD_USD` (default `100000`)
+    /// - `WHALE_MEMECOIN_THRESHOLD_USD` (default `10000`)
+    /// - `WHALE_MAJOR_MINTS`: comma-separated mints treated as majors in
+    ///   addition to SOL, USDC and USDT
+    /// - `SOL_USD_PRICE`: SOL price used until a SOL/USD stablecoin swap
+    ///   has been observed by the price index
+    pub fn from_env(publisher: UnifiedPublisher, price_index: PriceIndex) -> Self {
+        let env_f64 = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<f64>().ok())
+        };
+
+        let mut major_mints: HashSet<Pubkey> = [WSOL_MINT, USDC_MINT, USDT_MINT].into_iter().collect();
+        if let Ok(mints) = std::env::var("WHALE_MAJOR_MINTS") {
+            for mint in mints.split(',').map(str::trim).filter(|mint| !mint.is_empty()) {
+                match mint.parse() {
+                    Ok(mint) => {
+                        major_mints.insert(mint);
+                    }
+                    Err(e) => log::warn!("Ignoring invalid mint in WHALE_MAJOR_MINTS {}: {}", mint, e),
+                }
+            }
+        }
+
+        Self {
+            publisher,
+            price_index,
+            topic: std::env::var("WHALE_ALERT_TOPIC").unwrap_or_else(|_| DEFAULT_TOPIC.to_string()),
+            major_mints,
+            major_threshold_usd: env_f64("WHALE_MAJOR_THRESHOLD_USD").unwrap_or(DEFAULT_MAJOR_THRESHOLD_USD),
+            memecoin_threshold_usd: env_f64("WHALE_MEMECOIN_THRESHOLD_USD")
+                .unwrap_or(DEFAULT_MEMECOIN_THRESHOLD_USD),
+            sol_usd_fallback: env_f64("SOL_USD_PRICE"),
+        }
+    }
+
+    pub fn token_class(&self, fill: &SwapFill) -> TokenClass {
+        if self.major_mints.contains(&fill.input_mint) && self.major_mints.contains(&fill.output_mint) {
+            TokenClass::Major
+        } else {
+            TokenClass::Memecoin
+        }
+    }
+
+    fn sol_usd_price(&self) -> Option<f64> {
+        [USDC_MINT, USDT_MINT]
+            .iter()
+            .find_map(|stable| self.price_index.latest_price(&WSOL_MINT, stable))
+            .map(|price| price * 10f64.powi(SOL_DECIMALS as i32 - USD_STABLE_DECIMALS as i32))
+            .or(self.sol_usd_fallback)
+    }
+
+    fn leg_usd_value(&self, mint: &Pubkey, amount: u64) -> Option<f64> {
+        if is_usd_stable(mint) {
+            Some(amount as f64 / 10f64.powi(USD_STABLE_DECIMALS as i32))
+        } else if *mint == WSOL_MINT {
+            self.sol_usd_price()
+                .map(|price| amount as f64 / 10f64.powi(SOL_DECIMALS as i32) * price)
+        } else {
+            None
+        }
+    }
+
+    /// USD value of a fill, taken from its stablecoin leg when it has one and
+    /// from its SOL leg otherwise. Fills with neither cannot be valued.
+    pub fn usd_value(&self, fill: &SwapFill) -> Option<f64> {
+        let input = self.leg_usd_value(&fill.input_mint, fill.input_amount);
+        let output = self.leg_usd_value(&fill.output_mint, fill.output_amount);
+        if is_usd_stable(&fill.output_mint) {
+            output.or(input)
+        } else {
+            input.or(output)
+        }
+    }
+
+    /// Publishes a `whale_trade` alert when the fill is above the threshold
+    /// of its token class. The fee payer is reported when `trader` is unknown.
+    /// Failed transactions traded nothing and raise no alert. Venue
+    /// processors skip swaps routed by an aggregator, so a Jupiter route is
+    /// alerted once rather than once per venue it swaps through.
+    pub async fn check(
+        &self,
+        platform: &str,
+        transaction: &TransactionMetadata,
+        trader: Option<Pubkey>,
+        fill: &SwapFill,
+    ) {
+        if transaction.meta.status.is_err() {
+            return;
+        }
+        let Some(usd_value) = self.usd_value(fill) else {
+            return;
+        };
+        let token_class = self.token_class(fill);
+        let threshold_usd = match token_class {
+            TokenClass::Major => self.major_threshold_usd,
+            TokenClass::Memecoin => self.memecoin_threshold_usd,
+        };
+        if usd_value < threshold_usd {
+            return;
+        }
+
+        let signature = transaction.signature.to_string();
+        let trader = trader.unwrap_or(transaction.fee_payer);
+        let details = json!({
+            "type": "WhaleTrade",
+            "token_class": token_class.as_str(),
+            "usd_value": usd_value,
+            "threshold_usd": threshold_usd,
+            "trader": trader.to_string(),
+            "input_mint": fill.input_mint.to_string(),
+            "output_mint": fill.output_mint.to_string(),
+            "input_amount": fill.input_amount,
+            "output_amount": fill.output_amount,
+            "slot": transaction.slot
+        });
+
+        DexEvent::WhaleTrade {
+            platform: platform.to_string(),
+            signature: signature.clone(),
+            details: details.to_string(),
+        }
+        .log();
+
+        let data = EventTime::of_transaction(transaction).envelope("whale_trade", platform.to_string(), signature, details);
+
+        if let Err(e) = self.publisher.publish(&self.topic, &data).await {
+            log::error!("Failed to publish whale alert: {}", e);
+        }
+    }
+}
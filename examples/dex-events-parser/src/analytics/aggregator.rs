@@ -0,0 +1,118 @@
+use {
+    carbon_core::{
+        datasource::DatasourceId,
+        filter::Filter,
+        instruction::{InstructionMetadata, NestedInstruction},
+    },
+    solana_pubkey::Pubkey,
+    std::str::FromStr,
+};
+
+use crate::processors::jupiter::{JUPITER_V3_PROGRAM_ID, JUPITER_V4_PROGRAM_ID};
+
+/// Aggregators whose routes invoke the venues processed here.
+pub const AGGREGATOR_PROGRAMS: [Pubkey; 3] = [
+    carbon_jupiter_swap_decoder::PROGRAM_ID,
+    JUPITER_V4_PROGRAM_ID,
+    JUPITER_V3_PROGRAM_ID,
+];
+
+/// The aggregator an inner instruction was invoked by, directly or through
+/// other programs, found by walking the transaction's inner instructions up
+/// to the instruction at `metadata.absolute_path`.
+pub fn calling_aggregator(metadata: &InstructionMetadata) -> Option<Pubkey> {
+    if metadata.stack_height < 2 {
+        return None;
+    }
+    let transaction = &metadata.transaction_metadata;
+    let account_keys = transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .chain(transaction.meta.loaded_addresses.writable.iter())
+        .chain(transaction.meta.loaded_addresses.readonly.iter())
+        .collect::<Vec<_>>();
+    let program = |index: u8| account_keys.get(index as usize).map(|key| **key);
+
+    let outer = transaction.message.instructions().get(metadata.index as usize)?;
+    let inner = transaction
+        .meta
+        .inner_instructions
+        .as_ref()?
+        .iter()
+        .find(|inner| u32::from(inner.index) == metadata.index)?;
+
+    // Same numbering as the transformer that built `absolute_path`
+    let mut callers = vec![program(outer.program_id_index)?];
+    let mut path = vec![inner.index];
+    let mut prev_height = 0;
+    for instruction in &inner.instructions {
+        let height = instruction.stack_height.unwrap_or(1) as usize;
+        path.resize(height, 0);
+        if height <= prev_height {
+            path[height - 1] += 1;
+        }
+        prev_height = height;
+        callers.truncate(height - 1);
+        callers.push(program(instruction.instruction.program_id_index)?);
+
+        if path == metadata.absolute_path {
+            let own = callers.pop()?;
+            return callers
+                .into_iter()
+                .rev()
+                .find(|caller| *caller != own && AGGREGATOR_PROGRAMS.contains(caller));
+        }
+    }
+    None
+}
+
+/// What happens to the events of venue instructions invoked by an
+/// aggregator route, which the aggregator's own swap event already counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AggregatorPolicy {
+    /// Published with the aggregator program as `via_aggregator`.
+    #[default]
+    Tag,
+    /// Not processed, the aggregator event and its `legs` stand for them.
+    Suppress,
+}
+
+impl AggregatorPolicy {
+    /// Reads `AGGREGATOR_POLICY`: `tag` (default) or `suppress`.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("AGGREGATOR_POLICY") {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Filter of the venue pipes, skipping routed instructions when
+    /// suppressing them.
+    pub fn filter(self) -> Option<Box<dyn Filter + Send + Sync + 'static>> {
+        match self {
+            AggregatorPolicy::Tag => None,
+            AggregatorPolicy::Suppress => Some(Box::new(RoutedInstructionFilter)),
+        }
+    }
+}
+
+impl FromStr for AggregatorPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "tag" => Ok(AggregatorPolicy::Tag),
+            "suppress" => Ok(AggregatorPolicy::Suppress),
+            _ => Err(format!("Invalid aggregator policy {}, expected tag or suppress", value)),
+        }
+    }
+}
+
+struct RoutedInstructionFilter;
+
+impl Filter for RoutedInstructionFilter {
+    fn filter_instruction(&self, _datasource_id: &DatasourceId, nested_instruction: &NestedInstruction) -> bool {
+        calling_aggregator(&nested_instruction.metadata).is_none()
+    }
+}
@@ -0,0 +1,85 @@
+use {
+    carbon_core::transaction::TransactionMetadata,
+    solana_pubkey::Pubkey,
+    solana_transaction_status::TransactionTokenBalance,
+};
+
+/// Position of `account` in the transaction's account keys, including the
+/// addresses loaded from lookup tables for v0 messages.
+pub fn account_index(transaction: &TransactionMetadata, account: &Pubkey) -> Option<usize> {
+    transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .chain(transaction.meta.loaded_addresses.writable.iter())
+        .chain(transaction.meta.loaded_addresses.readonly.iter())
+        .position(|key| key == account)
+}
+
+/// Mint of a token account, as reported by the transaction's token balances.
+pub fn token_account_mint(transaction: &TransactionMetadata, token_account: &Pubkey) -> Option<Pubkey> {
+    let index = account_index(transaction, token_account)?;
+    transaction
+        .meta
+        .pre_token_balances
+        .iter()
+        .chain(transaction.meta.post_token_balances.iter())
+        .flatten()
+        .find(|balance| balance.account_index as usize == index)
+        .and_then(|balance| balance.mint.parse().ok())
+}
+
+/// Owner of a token account, as reported by the transaction's token
+/// balances.
+pub fn token_account_owner(transaction: &TransactionMetadata, token_account: &Pubkey) -> Option<Pubkey> {
+    let index = account_index(transaction, token_account)?;
+    transaction
+        .meta
+        .pre_token_balances
+        .iter()
+        .chain(transaction.meta.post_token_balances.iter())
+        .flatten()
+        .find(|balance| balance.account_index as usize == index)
+        .and_then(|balance| balance.owner.parse().ok())
+}
+
+fn token_balance(balances: Option<&Vec<TransactionTokenBalance>>, index: usize) -> Option<u64> {
+    balances?
+        .iter()
+        .find(|balance| balance.account_index as usize == index)
+        .and_then(|balance| balance.ui_token_amount.amount.parse().ok())
+}
+
+/// Balance of a token account at the end of the transaction, in raw units.
+pub fn post_token_balance(transaction: &TransactionMetadata, token_account: &Pubkey) -> Option<u64> {
+    let index = account_index(transaction, token_account)?;
+    token_balance(transaction.meta.post_token_balances.as_ref(), index)
+}
+
+/// Change of a token account's balance over the whole transaction, in raw
+/// units. Accounts created or closed within the transaction count as zero on
+/// the missing side; `None` means the account holds no tokens at either end.
+pub fn token_balance_change(transaction: &TransactionMetadata, token_account: &Pubkey) -> Option<i128> {
+    let index = account_index(transaction, token_account)?;
+    let pre = token_balance(transaction.meta.pre_token_balances.as_ref(), index);
+    let post = token_balance(transaction.meta.post_token_balances.as_ref(), index);
+    if pre.is_none() && post.is_none() {
+        return None;
+    }
+
+    Some(post.unwrap_or_default() as i128 - pre.unwrap_or_default() as i128)
+}
+
+/// Amount received by a token account over the transaction.
+pub fn amount_received(transaction: &TransactionMetadata, token_account: &Pubkey) -> Option<u64> {
+    token_balance_change(transaction, token_account)
+        .filter(|change| *change >= 0)
+        .map(|change| change as u64)
+}
+
+/// Amount sent from a token account over the transaction.
+pub fn amount_sent(transaction: &TransactionMetadata, token_account: &Pubkey) -> Option<u64> {
+    token_balance_change(transaction, token_account)
+        .filter(|change| *change <= 0)
+        .map(|change| change.unsigned_abs() as u64)
+}
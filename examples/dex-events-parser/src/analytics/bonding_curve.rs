@@ -0,0 +1,221 @@
+use {
+    super::{mints::WSOL_MINT, usd_prices::UsdPrices},
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::SystemTime,
+    },
+};
+
+/// Tokens a Pumpfun bonding curve sells before it completes, in raw units
+/// (793.1M tokens with 6 decimals).
+pub const PUMPFUN_INITIAL_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000;
+/// Supply of every Pumpfun token, in raw units (1B tokens with 6 decimals).
+pub const PUMPFUN_TOKEN_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000;
+const PUMPFUN_TOKEN_DECIMALS: i32 = 6;
+const SOL_DECIMALS: i32 = 9;
+
+const DEFAULT_THRESHOLDS: [f64; 3] = [80.0, 90.0, 95.0];
+const DEFAULT_MAX_TRACKED_MINTS: usize = 100_000;
+
+struct Curve {
+    sol_raised: u64,
+    progress: f64,
+    /// Thresholds already announced, as indexes into the configured ones.
+    announced: usize,
+    first_seen_at: i64,
+    updated_at: i64,
+    bonding_curve: Option<Pubkey>,
+}
+
+/// Per-mint state of launchpad bonding curves, read from the reserves their
+/// trade events report: the SOL raised and how much of the curve's tokens
+/// were sold. A `GraduationImminent` event is emitted the first time a curve
+/// crosses each configured completion threshold, and a `Graduated` event when
+/// its liquidity migrates to a pool, after which the mint is forgotten.
+///
+/// Unlike [`super::launch_funnel::LaunchFunnel`], mints are tracked from
+/// their first trade, so curves created before startup are covered too.
+#[derive(Clone)]
+pub struct BondingCurveProgress {
+    curves: Arc<Mutex<HashMap<Pubkey, Curve>>>,
+    /// Completion percentages, ascending.
+    thresholds: Arc<Vec<f64>>,
+    max_tracked_mints: usize,
+}
+
+impl Default for BondingCurveProgress {
+    fn default() -> Self {
+        Self::new(DEFAULT_THRESHOLDS.to_vec(), DEFAULT_MAX_TRACKED_MINTS)
+    }
+}
+
+impl BondingCurveProgress {
+    pub fn new(mut thresholds: Vec<f64>, max_tracked_mints: usize) -> Self {
+        thresholds.retain(|threshold| (0.0..100.0).contains(threshold));
+        thresholds.sort_by(f64::total_cmp);
+        thresholds.dedup();
+        Self {
+            curves: Arc::default(),
+            thresholds: Arc::new(thresholds),
+            max_tracked_mints: max_tracked_mints.max(1),
+        }
+    }
+
+    /// Reads `GRADUATION_THRESHOLDS` (comma separated completion
+    /// percentages, default `80,90,95`, empty for none) and
+    /// `BONDING_CURVE_MAX_MINTS` (default `100000`).
+    pub fn from_env() -> Result<Self, String> {
+        let thresholds = match std::env::var("GRADUATION_THRESHOLDS") {
+            Ok(value) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|threshold| !threshold.is_empty())
+                .map(|threshold| {
+                    threshold
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|threshold| (0.0..100.0).contains(threshold))
+                        .ok_or_else(|| format!("Invalid graduation threshold {}, expected a percentage below 100", threshold))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(_) => DEFAULT_THRESHOLDS.to_vec(),
+        };
+        let max_tracked_mints = std::env::var("BONDING_CURVE_MAX_MINTS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_TRACKED_MINTS);
+        Ok(Self::new(thresholds, max_tracked_mints))
+    }
+
+    /// Records the reserves of `mint`'s curve after a trade. Adds them to the
+    /// trade's details as `bonding_curve` and returns the details of the
+    /// `GraduationImminent` events of the thresholds crossed for the first
+    /// time.
+    pub fn record_trade(
+        &self,
+        mint: &Pubkey,
+        real_sol_reserves: u64,
+        real_token_reserves: u64,
+        transaction: &TransactionMetadata,
+        details: &mut Value,
+    ) -> Vec<Value> {
+        let now = unix_time(transaction);
+        let sold = PUMPFUN_INITIAL_REAL_TOKEN_RESERVES.saturating_sub(real_token_reserves);
+        let progress = sold as f64 * 100.0 / PUMPFUN_INITIAL_REAL_TOKEN_RESERVES as f64;
+        details["bonding_curve"] = json!({
+            "sol_raised": real_sol_reserves,
+            "tokens_remaining": real_token_reserves,
+            "progress_pct": progress
+        });
+
+        let mut curves = self.curves.lock().unwrap_or_else(|e| e.into_inner());
+        if !curves.contains_key(mint) && curves.len() >= self.max_tracked_mints {
+            // Drop the curve that has been idle the longest.
+            if let Some(idle) = curves
+                .iter()
+                .min_by_key(|(_, curve)| curve.updated_at)
+                .map(|(mint, _)| *mint)
+            {
+                curves.remove(&idle);
+            }
+        }
+        let curve = curves.entry(*mint).or_insert_with(|| Curve {
+            sol_raised: 0,
+            progress: 0.0,
+            // Thresholds already behind the first trade seen are not news
+            announced: self.thresholds.iter().take_while(|threshold| **threshold <= progress).count(),
+            first_seen_at: now,
+            updated_at: now,
+            bonding_curve: None,
+        });
+        curve.sol_raised = real_sol_reserves;
+        curve.progress = progress;
+        curve.updated_at = now;
+
+        let mut events = Vec::new();
+        while let Some(threshold) = self.thresholds.get(curve.announced).filter(|threshold| progress >= **threshold) {
+            events.push(json!({
+                "type": "GraduationImminent",
+                "mint": mint.to_string(),
+                "threshold_pct": threshold,
+                "progress_pct": progress,
+                "sol_raised": real_sol_reserves,
+                "tokens_remaining": real_token_reserves,
+                "slot": transaction.slot,
+                "tracked_for_secs": now - curve.first_seen_at
+            }));
+            curve.announced += 1;
+        }
+        events
+    }
+
+    /// Marks the curve of `mint` as complete, waiting for its migration,
+    /// unless the transaction failed.
+    pub fn complete(&self, mint: &Pubkey, bonding_curve: Pubkey, transaction: &TransactionMetadata) {
+        if transaction.meta.status.is_err() {
+            return;
+        }
+        if let Some(curve) = self.curves.lock().unwrap_or_else(|e| e.into_inner()).get_mut(mint) {
+            curve.progress = 100.0;
+            curve.bonding_curve = Some(bonding_curve);
+            curve.updated_at = unix_time(transaction);
+        }
+    }
+
+    /// The `Graduated` event of `mint`'s liquidity moving to `pool`. Mints
+    /// without a trade seen since startup are announced without their curve
+    /// state.
+    pub fn graduate(&self, mint: &Pubkey, pool: Pubkey, sol_amount: u64, transaction: &TransactionMetadata) -> Value {
+        let now = unix_time(transaction);
+        let curve = self.curves.lock().unwrap_or_else(|e| e.into_inner()).remove(mint);
+        json!({
+            "type": "Graduated",
+            "mint": mint.to_string(),
+            "pool": pool.to_string(),
+            "bonding_curve": curve.as_ref().and_then(|curve| curve.bonding_curve).map(|curve| curve.to_string()),
+            "sol_migrated": sol_amount,
+            "sol_raised": curve.as_ref().map(|curve| curve.sol_raised),
+            "slot": transaction.slot,
+            "tracked_for_secs": curve.as_ref().map(|curve| now - curve.first_seen_at)
+        })
+    }
+}
+
+/// Adds the price implied by a Pumpfun curve's virtual reserves after a
+/// trade, and the market cap of the token's total supply at that price, as
+/// `market_cap`. Both are in SOL, and in USD too when `usd_prices` has a
+/// fresh SOL price.
+pub fn market_cap(
+    virtual_sol_reserves: u64,
+    virtual_token_reserves: u64,
+    usd_prices: &UsdPrices,
+    transaction: &TransactionMetadata,
+    details: &mut Value,
+) {
+    if virtual_token_reserves == 0 {
+        return;
+    }
+    let price_sol = (virtual_sol_reserves as f64 / 10f64.powi(SOL_DECIMALS))
+        / (virtual_token_reserves as f64 / 10f64.powi(PUMPFUN_TOKEN_DECIMALS));
+    let market_cap_sol = price_sol * (PUMPFUN_TOKEN_TOTAL_SUPPLY as f64 / 10f64.powi(PUMPFUN_TOKEN_DECIMALS));
+    let sol_usd = usd_prices.price(&WSOL_MINT, unix_time(transaction)).map(|price| price.price);
+    details["market_cap"] = json!({
+        "price_sol": price_sol,
+        "market_cap_sol": market_cap_sol,
+        "price_usd": sol_usd.map(|sol_usd| price_sol * sol_usd),
+        "market_cap_usd": sol_usd.map(|sol_usd| market_cap_sol * sol_usd)
+    });
+}
+
+fn unix_time(transaction: &TransactionMetadata) -> i64 {
+    transaction.block_time.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    })
+}
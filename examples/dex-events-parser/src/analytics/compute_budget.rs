@@ -0,0 +1,82 @@
+use {
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+};
+
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey = Pubkey::from_str_const("ComputeBudget111111111111111111111111111111");
+
+/// Base fee of each transaction signature.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+/// Limit of each instruction when the transaction sets none.
+const DEFAULT_INSTRUCTION_COMPUTE_UNITS: u32 = 200_000;
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// ComputeBudget instruction tags.
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// Compute units and fees of a transaction, from its ComputeBudget
+/// instructions and status meta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudget {
+    /// `None` for nodes that do not report it.
+    pub units_consumed: Option<u64>,
+    pub unit_limit: u32,
+    pub unit_price_micro_lamports: u64,
+    /// Total fee paid, base and priority.
+    pub fee: u64,
+    pub priority_fee: u64,
+}
+
+impl ComputeBudget {
+    pub fn of_transaction(transaction: &TransactionMetadata) -> Self {
+        let account_keys = transaction.message.static_account_keys();
+        let mut unit_limit = None;
+        let mut unit_price = 0;
+        let mut other_instructions = 0u32;
+        for instruction in transaction.message.instructions() {
+            // The program of a top level instruction is never loaded from a
+            // lookup table
+            if account_keys.get(instruction.program_id_index as usize) != Some(&COMPUTE_BUDGET_PROGRAM_ID) {
+                other_instructions += 1;
+                continue;
+            }
+            match instruction.data.split_first() {
+                Some((&SET_COMPUTE_UNIT_LIMIT, data)) => {
+                    unit_limit = data.get(..4).and_then(|data| data.try_into().ok()).map(u32::from_le_bytes);
+                }
+                Some((&SET_COMPUTE_UNIT_PRICE, data)) => {
+                    unit_price = data.get(..8).and_then(|data| data.try_into().ok()).map(u64::from_le_bytes).unwrap_or(0);
+                }
+                _ => {}
+            }
+        }
+        let unit_limit = unit_limit
+            .unwrap_or(other_instructions.saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNITS))
+            .min(MAX_COMPUTE_UNIT_LIMIT);
+
+        // Charged fee minus the base fee rather than price times limit, as
+        // the default limit is only an estimate
+        let signatures = u64::from(transaction.message.header().num_required_signatures);
+        let fee = transaction.meta.fee;
+        Self {
+            units_consumed: transaction.meta.compute_units_consumed,
+            unit_limit,
+            unit_price_micro_lamports: unit_price,
+            fee,
+            priority_fee: fee.saturating_sub(signatures * LAMPORTS_PER_SIGNATURE),
+        }
+    }
+
+    /// `compute_budget` annotation of events, fees in lamports.
+    pub fn annotation(&self) -> Value {
+        json!({
+            "units_consumed": self.units_consumed,
+            "unit_limit": self.unit_limit,
+            "unit_price_micro_lamports": self.unit_price_micro_lamports,
+            "fee": self.fee,
+            "priority_fee": self.priority_fee
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use {
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+};
+
+use super::{
+    balances::token_account_mint,
+    mints::{is_usd_stable, SOL_DECIMALS, USD_STABLE_DECIMALS, WSOL_MINT},
+};
+
+/// Decimals of `mint`, from the transaction's token balances or, for SOL
+/// and the USD stablecoins, from their well-known values.
+pub fn mint_decimals(transaction: &TransactionMetadata, mint: &Pubkey) -> Option<u8> {
+    let mint_str = mint.to_string();
+    transaction
+        .meta
+        .pre_token_balances
+        .iter()
+        .chain(transaction.meta.post_token_balances.iter())
+        .flatten()
+        .find(|balance| balance.mint == mint_str)
+        .map(|balance| balance.ui_token_amount.decimals)
+        .or_else(|| {
+            if *mint == WSOL_MINT {
+                Some(SOL_DECIMALS as u8)
+            } else if is_usd_stable(mint) {
+                Some(USD_STABLE_DECIMALS as u8)
+            } else {
+                None
+            }
+        })
+}
+
+pub fn ui_amount(raw: u64, decimals: u8) -> f64 {
+    raw as f64 / 10f64.powi(decimals as i32)
+}
+
+/// Collects which mint each raw amount field of an event is denominated in,
+/// then adds `<field>_ui` and `<field>_decimals` next to every field whose
+/// decimals are known. Raw amounts are left untouched.
+pub struct UiAmounts<'a> {
+    transaction: &'a TransactionMetadata,
+    fields: Vec<(&'static str, Pubkey)>,
+}
+
+impl<'a> UiAmounts<'a> {
+    pub fn new(transaction: &'a TransactionMetadata) -> Self {
+        Self {
+            transaction,
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn mint(&mut self, field: &'static str, mint: Pubkey) {
+        self.fields.push((field, mint));
+    }
+
+    pub fn token_account(&mut self, field: &'static str, token_account: &Pubkey) {
+        if let Some(mint) = token_account_mint(self.transaction, token_account) {
+            self.mint(field, mint);
+        }
+    }
+
+    pub fn apply(self, details: &mut Value) {
+        for (field, mint) in self.fields {
+            let Some(raw) = details.get(field).and_then(Value::as_u64) else {
+                continue;
+            };
+            let Some(decimals) = mint_decimals(self.transaction, &mint) else {
+                continue;
+            };
+            details[format!("{}_ui", field)] = json!(ui_amount(raw, decimals));
+            details[format!("{}_decimals", field)] = json!(decimals);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use {
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+};
+
+use super::price_index::{PriceIndex, SwapFill};
+
+/// Compares aggregator fills with the best direct venue price from the
+/// [`PriceIndex`] around the same slot.
+#[derive(Clone)]
+pub struct ExecutionQuality {
+    price_index: PriceIndex,
+    max_slot_lag: u64,
+}
+
+impl ExecutionQuality {
+    pub fn new(price_index: PriceIndex, max_slot_lag: u64) -> Self {
+        Self {
+            price_index,
+            max_slot_lag,
+        }
+    }
+
+    /// Reads `EXECUTION_QUALITY_MAX_SLOT_LAG` (default `0`, same slot only).
+    pub fn from_env(price_index: PriceIndex) -> Self {
+        let max_slot_lag = std::env::var("EXECUTION_QUALITY_MAX_SLOT_LAG")
+            .ok()
+            .and_then(|lag| lag.parse().ok())
+            .unwrap_or(0);
+        Self::new(price_index, max_slot_lag)
+    }
+
+    /// Builds the `execution_quality` details for a fill routed through
+    /// `venues_used`, or `None` when no venue traded the pair in the window.
+    /// `improvement_bps` is positive when the route beat the best venue and
+    /// negative for a shortfall.
+    pub fn compare(
+        &self,
+        transaction: &TransactionMetadata,
+        fill: &SwapFill,
+        venues_used: &[&str],
+    ) -> Option<Value> {
+        let best = self.price_index.best_price(
+            &fill.input_mint,
+            &fill.output_mint,
+            transaction.slot,
+            self.max_slot_lag,
+            &transaction.signature,
+        )?;
+        let realized_price = fill.price();
+        let improvement_bps = ((realized_price / best.price - 1.0) * 10_000.0).round() as i64;
+
+        // Split routes visit a venue in several steps, not always adjacent
+        let mut unique_venues = Vec::with_capacity(venues_used.len());
+        for venue in venues_used {
+            if !unique_venues.contains(venue) {
+                unique_venues.push(*venue);
+            }
+        }
+
+        Some(json!({
+            "type": "ExecutionQuality",
+            "slot": transaction.slot,
+            "input_mint": fill.input_mint.to_string(),
+            "output_mint": fill.output_mint.to_string(),
+            "input_amount": fill.input_amount,
+            "output_amount": fill.output_amount,
+            "realized_price": realized_price,
+            "best_venue": best.venue,
+            "best_venue_price": best.price,
+            "best_venue_slot": best.slot,
+            "improvement_bps": improvement_bps,
+            "venues_used": unique_venues
+        }))
+    }
+}
@@ -0,0 +1,60 @@
+use {
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+};
+
+use super::balances::account_index;
+
+/// Mainnet Jito tip payment accounts.
+pub const JITO_TIP_ACCOUNTS: [Pubkey; 8] = [
+    Pubkey::from_str_const("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
+    Pubkey::from_str_const("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    Pubkey::from_str_const("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    Pubkey::from_str_const("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    Pubkey::from_str_const("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    Pubkey::from_str_const("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    Pubkey::from_str_const("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    Pubkey::from_str_const("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
+
+/// Smallest tip the block engine accepts for a bundle.
+pub const MIN_BUNDLE_TIP_LAMPORTS: u64 = 1_000;
+
+/// Lamports paid to Jito tip accounts by the transaction, from their SOL
+/// balance changes, along with the tip accounts that were paid.
+pub fn jito_tip(transaction: &TransactionMetadata) -> (u64, Vec<Pubkey>) {
+    let mut lamports = 0u64;
+    let mut tip_accounts = Vec::new();
+    for tip_account in JITO_TIP_ACCOUNTS.iter() {
+        let Some(index) = account_index(transaction, tip_account) else {
+            continue;
+        };
+        let pre = transaction.meta.pre_balances.get(index).copied().unwrap_or_default();
+        let post = transaction.meta.post_balances.get(index).copied().unwrap_or_default();
+        if post > pre {
+            lamports = lamports.saturating_add(post - pre);
+            tip_accounts.push(*tip_account);
+        }
+    }
+
+    (lamports, tip_accounts)
+}
+
+/// `jito_tip` annotation for swap events. A tip of at least the bundle
+/// minimum marks the swap as `likely` bundled, a smaller tip as `unlikely`
+/// (the block engine would reject it) and no tip as `none`.
+pub fn jito_tip_annotation(transaction: &TransactionMetadata) -> Value {
+    let (tip_lamports, tip_accounts) = jito_tip(transaction);
+    let bundle_likelihood = match tip_lamports {
+        0 => "none",
+        tip if tip < MIN_BUNDLE_TIP_LAMPORTS => "unlikely",
+        _ => "likely",
+    };
+
+    json!({
+        "tip_lamports": tip_lamports,
+        "tip_accounts": tip_accounts.iter().map(|account| account.to_string()).collect::<Vec<_>>(),
+        "bundle_likelihood": bundle_likelihood
+    })
+}
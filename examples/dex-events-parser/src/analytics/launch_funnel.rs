@@ -0,0 +1,202 @@
+use {
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::SystemTime,
+    },
+};
+
+const DEFAULT_FIRST_TRADES: u32 = 10;
+const DEFAULT_MAX_TRACKED_MINTS: usize = 100_000;
+
+/// Lifecycle stages of a launchpad token, in the order they are reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LaunchStage {
+    Created,
+    /// First collateral entering the curve or pool, i.e. the first buy on a
+    /// bonding curve.
+    FirstLiquidity,
+    /// The configured number of trades has been reached.
+    FirstTrades,
+    /// The bonding curve completed and the token can leave the launchpad.
+    Graduated,
+    /// Liquidity was moved into an AMM pool; tracking ends here.
+    Migrated,
+}
+
+impl LaunchStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LaunchStage::Created => "created",
+            LaunchStage::FirstLiquidity => "first_liquidity",
+            LaunchStage::FirstTrades => "first_trades",
+            LaunchStage::Graduated => "graduated",
+            LaunchStage::Migrated => "migrated",
+        }
+    }
+}
+
+/// Launchpad activity that can move a mint through the funnel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchSignal {
+    Created,
+    Trade,
+    Graduated,
+    Migrated { pool: Option<Pubkey> },
+}
+
+struct Lifecycle {
+    launchpad: String,
+    stage: LaunchStage,
+    created_at: i64,
+    stage_at: i64,
+    trades: u32,
+}
+
+/// Per-mint launch lifecycle state machine shared by the launchpad
+/// processors. Only mints whose creation was observed are tracked, so every
+/// transition carries the elapsed time since creation.
+#[derive(Clone)]
+pub struct LaunchFunnel {
+    lifecycles: Arc<Mutex<HashMap<Pubkey, Lifecycle>>>,
+    first_trades: u32,
+    max_tracked_mints: usize,
+}
+
+impl LaunchFunnel {
+    pub fn new(first_trades: u32, max_tracked_mints: usize) -> Self {
+        Self {
+            lifecycles: Arc::default(),
+            first_trades: first_trades.max(1),
+            max_tracked_mints: max_tracked_mints.max(1),
+        }
+    }
+
+    /// Reads `LAUNCH_FUNNEL_FIRST_TRADES` (default `10`) and
+    /// `LAUNCH_FUNNEL_MAX_MINTS` (default `100000`).
+    pub fn from_env() -> Self {
+        let first_trades = std::env::var("LAUNCH_FUNNEL_FIRST_TRADES")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_FIRST_TRADES);
+        let max_tracked_mints = std::env::var("LAUNCH_FUNNEL_MAX_MINTS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_TRACKED_MINTS);
+        Self::new(first_trades, max_tracked_mints)
+    }
+
+    /// Applies `signal` to the lifecycle of `mint` and returns the
+    /// `launch_funnel` details of every transition it caused. Stages are
+    /// only ever advanced, so a signal can skip stages but never go back.
+    /// Failed transactions cause none.
+    pub fn record(
+        &self,
+        launchpad: &str,
+        mint: &Pubkey,
+        signal: LaunchSignal,
+        transaction: &TransactionMetadata,
+    ) -> Vec<Value> {
+        if transaction.meta.status.is_err() {
+            return Vec::new();
+        }
+        let now = transaction.block_time.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64
+        });
+        let mut lifecycles = self.lifecycles.lock().unwrap_or_else(|e| e.into_inner());
+
+        if signal == LaunchSignal::Created {
+            if lifecycles.contains_key(mint) {
+                return Vec::new();
+            }
+            if lifecycles.len() >= self.max_tracked_mints {
+                // Drop the launch that has been stuck the longest.
+                if let Some(stale) = lifecycles
+                    .iter()
+                    .min_by_key(|(_, lifecycle)| lifecycle.stage_at)
+                    .map(|(mint, _)| *mint)
+                {
+                    lifecycles.remove(&stale);
+                }
+            }
+            lifecycles.insert(
+                *mint,
+                Lifecycle {
+                    launchpad: launchpad.to_string(),
+                    stage: LaunchStage::Created,
+                    created_at: now,
+                    stage_at: now,
+                    trades: 0,
+                },
+            );
+
+            return vec![json!({
+                "type": "LaunchTransition",
+                "mint": mint.to_string(),
+                "launchpad": launchpad,
+                "from": null,
+                "to": LaunchStage::Created.as_str(),
+                "slot": transaction.slot,
+                "trades": 0,
+                "elapsed_since_created_secs": 0,
+                "elapsed_since_previous_secs": 0
+            })];
+        }
+
+        let Some(lifecycle) = lifecycles.get_mut(mint) else {
+            return Vec::new();
+        };
+
+        let mut targets = Vec::new();
+        match signal {
+            LaunchSignal::Trade => {
+                lifecycle.trades = lifecycle.trades.saturating_add(1);
+                targets.push(LaunchStage::FirstLiquidity);
+                if lifecycle.trades >= self.first_trades {
+                    targets.push(LaunchStage::FirstTrades);
+                }
+            }
+            LaunchSignal::Graduated => targets.push(LaunchStage::Graduated),
+            LaunchSignal::Migrated { .. } => targets.push(LaunchStage::Migrated),
+            LaunchSignal::Created => unreachable!("handled above"),
+        }
+
+        let mut transitions = Vec::new();
+        for target in targets {
+            if target <= lifecycle.stage {
+                continue;
+            }
+
+            let mut details = json!({
+                "type": "LaunchTransition",
+                "mint": mint.to_string(),
+                "launchpad": lifecycle.launchpad,
+                "from": lifecycle.stage.as_str(),
+                "to": target.as_str(),
+                "slot": transaction.slot,
+                "trades": lifecycle.trades,
+                "elapsed_since_created_secs": now - lifecycle.created_at,
+                "elapsed_since_previous_secs": now - lifecycle.stage_at
+            });
+            if let LaunchSignal::Migrated { pool: Some(pool) } = signal {
+                details["pool"] = json!(pool.to_string());
+            }
+            transitions.push(details);
+
+            lifecycle.stage = target;
+            lifecycle.stage_at = now;
+        }
+
+        if lifecycle.stage == LaunchStage::Migrated {
+            lifecycles.remove(mint);
+        }
+
+        transitions
+    }
+}
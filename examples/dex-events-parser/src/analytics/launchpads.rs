@@ -0,0 +1,47 @@
+use {
+    solana_pubkey::Pubkey,
+    std::{collections::HashMap, str::FromStr, sync::Arc},
+};
+
+/// Launchpads built on Meteora's Dynamic Bonding Curve, by the curve config
+/// their pools are created with. Launchpads such as Believe (Launchcoin)
+/// share the program, so the config is the only way to tell their tokens
+/// apart. Empty by default, in which case launches carry no `launchpad`.
+#[derive(Clone, Default)]
+pub struct LaunchpadConfigs {
+    names: Arc<HashMap<Pubkey, String>>,
+}
+
+impl LaunchpadConfigs {
+    pub fn new(names: HashMap<Pubkey, String>) -> Self {
+        Self { names: Arc::new(names) }
+    }
+
+    /// Reads `DBC_LAUNCHPAD_CONFIGS`, comma separated `name=config` pairs,
+    /// e.g. `Believe=<config>`. A launchpad with several configs is listed
+    /// once per config.
+    pub fn from_env() -> Result<Self, String> {
+        let Ok(value) = std::env::var("DBC_LAUNCHPAD_CONFIGS") else {
+            return Ok(Self::default());
+        };
+        let names = value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (name, config) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid DBC_LAUNCHPAD_CONFIGS entry {}, expected name=config", entry))?;
+                let config = Pubkey::from_str(config.trim())
+                    .map_err(|e| format!("Invalid config {} in DBC_LAUNCHPAD_CONFIGS: {}", config, e))?;
+                Ok((config, name.trim().to_string()))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        Ok(Self::new(names))
+    }
+
+    /// Launchpad whose pools are created with `config`.
+    pub fn name(&self, config: &Pubkey) -> Option<&str> {
+        self.names.get(config).map(String::as_str)
+    }
+}
@@ -0,0 +1,49 @@
+use {carbon_core::transaction::TransactionMetadata, solana_pubkey::Pubkey};
+
+/// SPL Memo v2.
+pub const MEMO_PROGRAM_ID: Pubkey = Pubkey::from_str_const("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+/// SPL Memo v1, still used by older clients.
+pub const MEMO_V1_PROGRAM_ID: Pubkey = Pubkey::from_str_const("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo");
+
+/// Memos of the transaction in execution order, whether top level or
+/// invoked by another program. The Memo program rejects data that is not
+/// UTF-8, so every memo of a landed transaction is a string.
+pub fn transaction_memos(transaction: &TransactionMetadata) -> Vec<String> {
+    let account_keys = transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .chain(transaction.meta.loaded_addresses.writable.iter())
+        .chain(transaction.meta.loaded_addresses.readonly.iter())
+        .collect::<Vec<_>>();
+    let is_memo = |index: u8| {
+        account_keys
+            .get(index as usize)
+            .is_some_and(|key| **key == MEMO_PROGRAM_ID || **key == MEMO_V1_PROGRAM_ID)
+    };
+    let inner_instructions = transaction.meta.inner_instructions.as_deref().unwrap_or_default();
+
+    let mut memos = Vec::new();
+    for (index, outer) in transaction.message.instructions().iter().enumerate() {
+        let inner = inner_instructions
+            .iter()
+            .filter(|inner| inner.index as usize == index)
+            .flat_map(|inner| inner.instructions.iter().map(|inner| &inner.instruction));
+        for instruction in std::iter::once(outer).chain(inner) {
+            if !is_memo(instruction.program_id_index) {
+                continue;
+            }
+            if let Ok(memo) = std::str::from_utf8(&instruction.data) {
+                memos.push(memo.to_string());
+            }
+        }
+    }
+    memos
+}
+
+/// The `memo` of events decoded from the transaction, its memos joined by
+/// newlines. `None` when it has none.
+pub fn transaction_memo(transaction: &TransactionMetadata) -> Option<String> {
+    let memos = transaction_memos(transaction);
+    (!memos.is_empty()).then(|| memos.join("\n"))
+}
@@ -0,0 +1,348 @@
+use {
+    crate::publishers::{DexEventData, DexEventPayload},
+    serde::Serialize,
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::{Arc, Mutex},
+        time::SystemTime,
+    },
+};
+
+const DEFAULT_TOPIC: &str = "mev_events";
+const DEFAULT_DELAY_SLOTS: u64 = 2;
+
+/// A swap of the block, in the order it executed.
+#[derive(Debug, Clone)]
+struct BlockSwap {
+    tx_index: u64,
+    instruction_index: u32,
+    platform: String,
+    signature: String,
+    block_time: Option<i64>,
+    pool: String,
+    /// The trader, or the fee payer when the trader did not sign.
+    wallet: String,
+    /// `buy` or `sell` of the base mint.
+    side: String,
+    base_mint: Option<String>,
+    quote_mint: Option<String>,
+    amount_in: Option<u64>,
+    amount_out: Option<u64>,
+}
+
+impl BlockSwap {
+    /// Swaps without a pool, wallet, side or position in the block cannot
+    /// be matched and are left out, as are swaps of failed transactions.
+    fn from_event(data: &DexEventData) -> Option<Self> {
+        let Some(DexEventPayload::Swap(swap)) = data.payload.as_ref().filter(|_| !data.is_failed()) else {
+            return None;
+        };
+        Some(Self {
+            tx_index: data.tx_index?,
+            instruction_index: data.instruction_index.unwrap_or_default(),
+            platform: data.platform.clone(),
+            signature: data.signature.clone(),
+            block_time: data.block_time,
+            pool: swap.pool.clone()?,
+            wallet: data.trader.clone().or_else(|| swap.trader.clone()).or_else(|| data.fee_payer.clone())?,
+            side: swap.side.clone()?,
+            base_mint: swap.base_mint.clone(),
+            quote_mint: swap.quote_mint.clone(),
+            amount_in: swap.amount_in,
+            amount_out: swap.amount_out,
+        })
+    }
+
+    /// Raw quote amount the wallet spent (negative) or received.
+    fn quote_flow(&self) -> Option<i128> {
+        match self.side.as_str() {
+            "buy" => self.amount_in.map(|amount| -(amount as i128)),
+            "sell" => self.amount_out.map(i128::from),
+            _ => None,
+        }
+    }
+}
+
+/// A victim swap caught between the two legs of a sandwich.
+#[derive(Debug, Clone, Serialize)]
+pub struct SandwichVictim {
+    pub signature: String,
+    pub wallet: String,
+    pub tx_index: u64,
+}
+
+/// A detected MEV pattern, published as the details of a `mev` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum MevEvent {
+    /// The attacker trades ahead of victims on the same pool and side, then
+    /// trades back within the same block.
+    Sandwich {
+        pool: String,
+        attacker: String,
+        /// Side of the front-run, which the victims share.
+        side: String,
+        front_run: String,
+        back_run: String,
+        victims: Vec<SandwichVictim>,
+        base_mint: Option<String>,
+        quote_mint: Option<String>,
+        /// Quote the attacker got back minus what it spent, in raw units,
+        /// when both legs report their quote amount.
+        quote_profit: Option<String>,
+    },
+}
+
+/// Flags sandwiches among the swaps a sink published, per block: an
+/// attacker swap, one or more swaps of other wallets on the same pool and
+/// side, then an opposite swap of the attacker. Each pattern is published
+/// as a `mev` event on a dedicated topic.
+///
+/// Like [`crate::publishers::BlockSummaries`], a block is only analyzed once
+/// an event `MEV_DELAY_SLOTS` slots newer has been seen. Swaps need the
+/// position of their transaction in the block, so only block datasources
+/// are analyzed.
+#[derive(Clone)]
+pub struct MevDetector {
+    topic: String,
+    delay_slots: u64,
+    blocks: Arc<Mutex<Blocks>>,
+}
+
+#[derive(Default)]
+struct Blocks {
+    open: BTreeMap<u64, Vec<BlockSwap>>,
+    newest_slot: u64,
+    analyzed_through: u64,
+}
+
+impl MevDetector {
+    /// Reads `MEV_DETECTION` (`true`/`1` to enable, default off),
+    /// `MEV_TOPIC` (default `mev_events`) and `MEV_DELAY_SLOTS` (default
+    /// `2`).
+    pub fn from_env() -> Option<Self> {
+        if !matches!(std::env::var("MEV_DETECTION").as_deref(), Ok("true") | Ok("1")) {
+            return None;
+        }
+        let delay_slots = std::env::var("MEV_DELAY_SLOTS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_DELAY_SLOTS);
+
+        Some(Self {
+            topic: std::env::var("MEV_TOPIC").unwrap_or_else(|_| DEFAULT_TOPIC.to_string()),
+            delay_slots,
+            blocks: Arc::default(),
+        })
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Adds a published event to its block. Returns the MEV events of the
+    /// blocks that are now complete, oldest first.
+    pub fn record(&self, data: &DexEventData) -> Vec<DexEventData> {
+        let Some(slot) = data.slot else {
+            return Vec::new();
+        };
+        if data.event_type == "mev" {
+            return Vec::new();
+        }
+
+        let mut blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+        if slot <= blocks.analyzed_through {
+            return Vec::new();
+        }
+        let swap = (data.event_type == "swap").then(|| BlockSwap::from_event(data)).flatten();
+        blocks.open.entry(slot).or_default().extend(swap);
+        blocks.newest_slot = blocks.newest_slot.max(slot);
+
+        let complete_through = blocks.newest_slot.saturating_sub(self.delay_slots);
+        let still_open = blocks.open.split_off(&(complete_through + 1));
+        let complete = std::mem::replace(&mut blocks.open, still_open);
+        blocks.analyzed_through = blocks.analyzed_through.max(complete_through);
+        drop(blocks);
+
+        complete
+            .into_iter()
+            .flat_map(|(slot, swaps)| {
+                sandwiches(swaps)
+                    .into_iter()
+                    .map(move |(front_run, event)| into_event(slot, &front_run, event))
+            })
+            .collect()
+    }
+}
+
+/// Sandwiches of one block, with the front-run swap of each.
+fn sandwiches(swaps: Vec<BlockSwap>) -> Vec<(BlockSwap, MevEvent)> {
+    let mut pools = HashMap::<String, Vec<BlockSwap>>::new();
+    for swap in swaps {
+        pools.entry(swap.pool.clone()).or_default().push(swap);
+    }
+
+    let mut found = Vec::new();
+    for (pool, mut swaps) in pools {
+        swaps.sort_by_key(|swap| (swap.tx_index, swap.instruction_index));
+        let mut used = vec![false; swaps.len()];
+        for front in 0..swaps.len() {
+            if used[front] {
+                continue;
+            }
+            let attacker = &swaps[front];
+            let Some(back) = (front + 1..swaps.len()).find(|&back| {
+                !used[back]
+                    && swaps[back].wallet == attacker.wallet
+                    && swaps[back].side != attacker.side
+                    && swaps[back].tx_index != attacker.tx_index
+            }) else {
+                continue;
+            };
+            let victims = swaps[front + 1..back]
+                .iter()
+                .filter(|swap| swap.wallet != attacker.wallet && swap.side == attacker.side)
+                .map(|swap| SandwichVictim {
+                    signature: swap.signature.clone(),
+                    wallet: swap.wallet.clone(),
+                    tx_index: swap.tx_index,
+                })
+                .collect::<Vec<_>>();
+            if victims.is_empty() {
+                continue;
+            }
+            used[front] = true;
+            used[back] = true;
+
+            let back_run = &swaps[back];
+            let quote_profit = (attacker.quote_mint.is_some() && attacker.quote_mint == back_run.quote_mint)
+                .then(|| Some(attacker.quote_flow()? + back_run.quote_flow()?))
+                .flatten()
+                .map(|profit| profit.to_string());
+            found.push((
+                attacker.clone(),
+                MevEvent::Sandwich {
+                    pool: pool.clone(),
+                    attacker: attacker.wallet.clone(),
+                    side: attacker.side.clone(),
+                    front_run: attacker.signature.clone(),
+                    back_run: back_run.signature.clone(),
+                    victims,
+                    base_mint: attacker.base_mint.clone(),
+                    quote_mint: attacker.quote_mint.clone(),
+                    quote_profit,
+                },
+            ));
+        }
+    }
+    found.sort_by_key(|(front_run, _)| front_run.tx_index);
+    found
+}
+
+fn into_event(slot: u64, front_run: &BlockSwap, event: MevEvent) -> DexEventData {
+    DexEventData {
+        event_type: "mev".to_string(),
+        platform: front_run.platform.clone(),
+        signature: front_run.signature.clone(),
+        timestamp: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        block_time: front_run.block_time,
+        slot: Some(slot),
+        details: serde_json::to_value(event).unwrap_or_default(),
+        ingested_at: None,
+        tx_index: Some(front_run.tx_index),
+        instruction_index: None,
+        is_inner: None,
+        fee_payer: None,
+        trader: Some(front_run.wallet.clone()),
+        payload: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap(tx_index: u64, wallet: &str, side: &str, amount_in: u64, amount_out: u64) -> BlockSwap {
+        BlockSwap {
+            tx_index,
+            instruction_index: 0,
+            platform: "raydium_amm_v4".to_string(),
+            signature: format!("tx{}", tx_index),
+            block_time: None,
+            pool: "pool".to_string(),
+            wallet: wallet.to_string(),
+            side: side.to_string(),
+            base_mint: Some("base".to_string()),
+            quote_mint: Some("quote".to_string()),
+            amount_in: Some(amount_in),
+            amount_out: Some(amount_out),
+        }
+    }
+
+    fn sandwich(event: &MevEvent) -> (&str, &str, Vec<&str>, Option<&str>) {
+        let MevEvent::Sandwich {
+            front_run,
+            back_run,
+            victims,
+            quote_profit,
+            ..
+        } = event;
+        (
+            front_run,
+            back_run,
+            victims.iter().map(|victim| victim.signature.as_str()).collect(),
+            quote_profit.as_deref(),
+        )
+    }
+
+    #[test]
+    fn finds_a_sandwich_in_execution_order() {
+        // Swaps reach the detector in publishing order, not block order
+        let found = sandwiches(vec![
+            swap(7, "attacker", "sell", 1_000, 1_150),
+            swap(5, "victim", "buy", 500, 400),
+            swap(3, "attacker", "buy", 1_000, 900),
+            swap(6, "other", "sell", 300, 350),
+        ]);
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0.tx_index, 3);
+        assert_eq!(sandwich(&found[0].1), ("tx3", "tx7", vec!["tx5"], Some("150")));
+    }
+
+    #[test]
+    fn swaps_of_the_attacker_are_not_victims() {
+        let found = sandwiches(vec![
+            swap(1, "attacker", "buy", 1_000, 900),
+            swap(2, "attacker", "buy", 1_000, 880),
+            swap(3, "attacker", "sell", 1_780, 2_100),
+        ]);
+
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn a_losing_sandwich_has_a_negative_profit() {
+        let found = sandwiches(vec![
+            swap(1, "attacker", "buy", 1_000, 900),
+            swap(2, "victim", "buy", 500, 420),
+            swap(3, "attacker", "sell", 900, 940),
+        ]);
+
+        assert_eq!(sandwich(&found[0].1).3, Some("-60"));
+    }
+
+    #[test]
+    fn both_legs_in_one_transaction_are_not_a_sandwich() {
+        let mut victim = swap(1, "victim", "buy", 500, 420);
+        victim.instruction_index = 1;
+        let mut back_run = swap(1, "attacker", "sell", 900, 1_100);
+        back_run.instruction_index = 2;
+        let found = sandwiches(vec![swap(1, "attacker", "buy", 1_000, 900), victim, back_run]);
+
+        assert!(found.is_empty());
+    }
+}
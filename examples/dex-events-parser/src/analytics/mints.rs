@@ -0,0 +1,15 @@
+use solana_pubkey::Pubkey;
+
+/// Wrapped SOL; native SOL legs are valued as this mint.
+pub const WSOL_MINT: Pubkey = Pubkey::from_str_const("So11111111111111111111111111111111111111112");
+/// Placeholder address some indexers use for native SOL, never a real mint.
+pub const NATIVE_SOL_MINT: Pubkey = Pubkey::from_str_const("So11111111111111111111111111111111111111111");
+pub const USDC_MINT: Pubkey = Pubkey::from_str_const("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+pub const USDT_MINT: Pubkey = Pubkey::from_str_const("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB");
+
+pub const SOL_DECIMALS: u32 = 9;
+pub const USD_STABLE_DECIMALS: u32 = 6;
+
+pub fn is_usd_stable(mint: &Pubkey) -> bool {
+    *mint == USDC_MINT || *mint == USDT_MINT
+}
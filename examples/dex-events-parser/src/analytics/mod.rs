@@ -0,0 +1,31 @@
+pub mod aggregator;
+pub mod balances;
+pub mod bonding_curve;
+pub mod compute_budget;
+pub mod decimals;
+pub mod execution_quality;
+pub mod jito;
+pub mod launch_funnel;
+pub mod launchpads;
+pub mod memo;
+pub mod mev;
+pub mod mints;
+pub mod new_pair;
+pub mod participation;
+pub mod pool_registry;
+pub mod pool_reserves;
+pub mod pool_state;
+pub mod price_index;
+pub mod program_logs;
+pub mod rolling_stats;
+pub mod route_legs;
+pub mod side;
+pub mod swap_event;
+pub mod slippage;
+pub mod sol_transfers;
+pub mod symbols;
+pub mod tracked_mints;
+pub mod trader;
+pub mod transfer_fee;
+pub mod transfers;
+pub mod usd_prices;
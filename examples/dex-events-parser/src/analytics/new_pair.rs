@@ -0,0 +1,77 @@
+use {
+    super::{
+        balances::post_token_balance,
+        decimals::{mint_decimals, ui_amount},
+        side::TradeSide,
+    },
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+};
+
+/// Pool creation in the same shape for every platform: the pool, its two
+/// mints ordered like the `base_mint` and `quote_mint` of swaps, the wallet
+/// that created it and the liquidity it started with.
+///
+/// The initial liquidity is read from the balances of the pool's vaults at
+/// the end of the creating transaction, so a deposit made right after the
+/// creation instruction is counted. Amounts reported by the program are used
+/// for pools whose vaults are not among the instruction's accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NewPairEvent {
+    /// Pool, or bonding curve for graduations.
+    pub pool: Pubkey,
+    /// Mint of each side of the pool, with its vault when known, in the
+    /// venue's order.
+    pub token_a: (Pubkey, Option<Pubkey>),
+    pub token_b: (Pubkey, Option<Pubkey>),
+    pub creator: Option<Pubkey>,
+    /// Initial amounts of `token_a` and `token_b` reported by the program.
+    pub reported_amounts: Option<(u64, u64)>,
+}
+
+impl NewPairEvent {
+    pub fn new(
+        pool: Pubkey,
+        token_a: (Pubkey, Option<Pubkey>),
+        token_b: (Pubkey, Option<Pubkey>),
+        creator: Option<Pubkey>,
+    ) -> Self {
+        Self { pool, token_a, token_b, creator, reported_amounts: None }
+    }
+
+    pub fn with_reported_amounts(self, amount_a: u64, amount_b: u64) -> Self {
+        Self { reported_amounts: Some((amount_a, amount_b)), ..self }
+    }
+
+    /// Adds the pair to new pool details as `new_pair`.
+    pub fn apply(&self, transaction: &TransactionMetadata, details: &mut Value) {
+        let amount = |(_, vault): (Pubkey, Option<Pubkey>), reported: Option<u64>| {
+            vault
+                .and_then(|vault| post_token_balance(transaction, &vault))
+                .or(reported)
+        };
+        let a = (self.token_a.0, amount(self.token_a, self.reported_amounts.map(|(a, _)| a)));
+        let b = (self.token_b.0, amount(self.token_b, self.reported_amounts.map(|(_, b)| b)));
+        // The second token breaks ties as the venue's quote, as in the pool registry
+        let Some(pair) = TradeSide::from_mints(a.0, b.0, Some(b.0)) else {
+            return;
+        };
+        let ((base_mint, base_amount), (quote_mint, quote_amount)) = if pair.base_mint == a.0 { (a, b) } else { (b, a) };
+        let base_decimals = mint_decimals(transaction, &base_mint);
+        let quote_decimals = mint_decimals(transaction, &quote_mint);
+
+        details["new_pair"] = json!({
+            "pool": self.pool.to_string(),
+            "base_mint": base_mint.to_string(),
+            "quote_mint": quote_mint.to_string(),
+            "creator": self.creator.map(|creator| creator.to_string()),
+            "base_amount": base_amount,
+            "quote_amount": quote_amount,
+            "base_decimals": base_decimals,
+            "quote_decimals": quote_decimals,
+            "base_ui_amount": base_amount.zip(base_decimals).map(|(amount, decimals)| ui_amount(amount, decimals)),
+            "quote_ui_amount": quote_amount.zip(quote_decimals).map(|(amount, decimals)| ui_amount(amount, decimals))
+        });
+    }
+}
@@ -0,0 +1,324 @@
+use {
+    crate::{
+        publishers::{DexEventData, Publisher, UnifiedPublisher, EVENTS_TOPIC},
+        DexEvent,
+    },
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::json,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+        hash::{Hash, Hasher},
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime},
+    },
+};
+
+const DEFAULT_PUBLISH_INTERVAL_SECS: u64 = 60;
+const DEFAULT_MAX_TRACKED_POOLS: usize = 5_000;
+
+/// 2^9 registers per sketch, about 4.6% standard error at 512 bytes.
+const HLL_PRECISION: u32 = 9;
+const HLL_REGISTERS: usize = 1 << HLL_PRECISION;
+
+/// Each window is covered by `buckets` sketches of `bucket_secs`, so it
+/// slides by one bucket at a time.
+struct Window {
+    name: &'static str,
+    bucket_secs: i64,
+    buckets: usize,
+}
+
+const WINDOWS: [Window; 3] = [
+    Window { name: "5m", bucket_secs: 60, buckets: 5 },
+    Window { name: "1h", bucket_secs: 300, buckets: 12 },
+    Window { name: "24h", bucket_secs: 3_600, buckets: 24 },
+];
+
+/// Fixed-size HyperLogLog sketch of trader addresses.
+#[derive(Clone)]
+pub(crate) struct HyperLogLog {
+    registers: Box<[u8; HLL_REGISTERS]>,
+}
+
+impl HyperLogLog {
+    pub(crate) fn new() -> Self {
+        Self {
+            registers: Box::new([0; HLL_REGISTERS]),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, trader: &Pubkey) {
+        let mut hasher = DefaultHasher::new();
+        trader.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let index = (hash >> (64 - HLL_PRECISION)) as usize;
+        let rank = ((hash << HLL_PRECISION).leading_zeros() + 1).min(64 - HLL_PRECISION + 1) as u8;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    pub(crate) fn merge(&mut self, other: &HyperLogLog) {
+        for (register, other) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    pub(crate) fn estimate(&self) -> u64 {
+        let m = HLL_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let estimate = alpha * m * m / sum;
+
+        // Linear counting is more accurate while many registers are empty.
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            return (m * (m / zeros as f64).ln()).round() as u64;
+        }
+        estimate.round() as u64
+    }
+}
+
+struct PoolParticipation {
+    platform: String,
+    last_trade_at: i64,
+    /// Per window, the bucket start times and their sketches, oldest first.
+    buckets: [VecDeque<(i64, HyperLogLog)>; WINDOWS.len()],
+}
+
+impl PoolParticipation {
+    fn new(platform: &str) -> Self {
+        Self {
+            platform: platform.to_string(),
+            last_trade_at: 0,
+            buckets: Default::default(),
+        }
+    }
+
+    fn record(&mut self, trader: &Pubkey, now: i64) {
+        self.last_trade_at = self.last_trade_at.max(now);
+        for (window, buckets) in WINDOWS.iter().zip(self.buckets.iter_mut()) {
+            let start = now - now.rem_euclid(window.bucket_secs);
+            match buckets.iter_mut().find(|(bucket_start, _)| *bucket_start == start) {
+                Some((_, sketch)) => sketch.insert(trader),
+                None => {
+                    let mut sketch = HyperLogLog::new();
+                    sketch.insert(trader);
+                    buckets.push_back((start, sketch));
+                    buckets.make_contiguous().sort_by_key(|(bucket_start, _)| *bucket_start);
+                }
+            }
+        }
+        self.prune(now);
+    }
+
+    fn prune(&mut self, now: i64) {
+        for (window, buckets) in WINDOWS.iter().zip(self.buckets.iter_mut()) {
+            let oldest = now - now.rem_euclid(window.bucket_secs)
+                - window.bucket_secs * (window.buckets as i64 - 1);
+            while buckets.front().is_some_and(|(start, _)| *start < oldest) {
+                buckets.pop_front();
+            }
+        }
+    }
+
+    fn unique_traders(&self) -> [u64; WINDOWS.len()] {
+        let mut counts = [0; WINDOWS.len()];
+        for (count, buckets) in counts.iter_mut().zip(self.buckets.iter()) {
+            let mut merged = HyperLogLog::new();
+            for (_, sketch) in buckets {
+                merged.merge(sketch);
+            }
+            *count = if buckets.is_empty() { 0 } else { merged.estimate() };
+        }
+        counts
+    }
+}
+
+/// Unique trader estimates per pool over 5 minute, 1 hour and 24 hour
+/// sliding windows. Memory is bounded by the number of tracked pools, the
+/// least recently traded pool is dropped when the limit is reached.
+#[derive(Clone)]
+pub struct ParticipationTracker {
+    pools: Arc<Mutex<HashMap<Pubkey, PoolParticipation>>>,
+    max_tracked_pools: usize,
+    publish_interval: Duration,
+}
+
+impl ParticipationTracker {
+    pub fn new(max_tracked_pools: usize, publish_interval: Duration) -> Self {
+        Self {
+            pools: Arc::default(),
+            max_tracked_pools: max_tracked_pools.max(1),
+            publish_interval,
+        }
+    }
+
+    /// Reads `PARTICIPATION_MAX_POOLS` (default `5000`) and
+    /// `PARTICIPATION_INTERVAL_SECS` (default `60`).
+    pub fn from_env() -> Self {
+        let max_tracked_pools = std::env::var("PARTICIPATION_MAX_POOLS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_TRACKED_POOLS);
+        let interval_secs = std::env::var("PARTICIPATION_INTERVAL_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_PUBLISH_INTERVAL_SECS);
+        Self::new(max_tracked_pools, Duration::from_secs(interval_secs.max(1)))
+    }
+
+    /// Counts `trader` as a participant of `pool` at the transaction's block
+    /// time, unless the transaction failed.
+    pub fn record(&self, platform: &str, pool: Pubkey, trader: Pubkey, transaction: &TransactionMetadata) {
+        if transaction.meta.status.is_err() {
+            return;
+        }
+        let now = transaction.block_time.unwrap_or_else(unix_now);
+        let mut pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+
+        if !pools.contains_key(&pool) && pools.len() >= self.max_tracked_pools {
+            if let Some(idle) = pools
+                .iter()
+                .min_by_key(|(_, participation)| participation.last_trade_at)
+                .map(|(pool, _)| *pool)
+            {
+                pools.remove(&idle);
+            }
+        }
+
+        pools
+            .entry(pool)
+            .or_insert_with(|| PoolParticipation::new(platform))
+            .record(&trader, now);
+    }
+
+    /// Publishes a `participation` event per pool traded within the last 24
+    /// hours every interval, and forgets pools idle for longer.
+    pub fn spawn_publisher(&self, publisher: UnifiedPublisher) -> tokio::task::JoinHandle<()> {
+        let tracker = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tracker.publish_interval);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                let now = unix_now();
+                let snapshot = {
+                    let mut pools = tracker.pools.lock().unwrap_or_else(|e| e.into_inner());
+                    let horizon = WINDOWS[WINDOWS.len() - 1].bucket_secs
+                        * WINDOWS[WINDOWS.len() - 1].buckets as i64;
+                    pools.retain(|_, participation| now - participation.last_trade_at < horizon);
+                    pools
+                        .iter_mut()
+                        .map(|(pool, participation)| {
+                            participation.prune(now);
+                            (*pool, participation.platform.clone(), participation.unique_traders())
+                        })
+                        .collect::<Vec<_>>()
+                };
+
+                for (pool, platform, counts) in snapshot {
+                    let mut details = json!({
+                        "type": "UniqueTraders",
+                        "pool": pool.to_string()
+                    });
+                    for (window, count) in WINDOWS.iter().zip(counts) {
+                        details[format!("unique_traders_{}", window.name)] = json!(count);
+                    }
+
+                    DexEvent::Participation {
+                        platform: platform.clone(),
+                        details: details.to_string(),
+                    }
+                    .log();
+
+                    let data = DexEventData {
+                        event_type: "participation".to_string(),
+                        platform,
+                        signature: String::new(),
+                        timestamp: now as u64,
+                        block_time: None,
+                        slot: None,
+                        details,
+                        ingested_at: None,
+                        tx_index: None,
+                        instruction_index: None,
+                        is_inner: None,
+                        fee_payer: None,
+                        trader: None,
+                        payload: None,
+                    };
+                    if let Err(e) = publisher.publish(EVENTS_TOPIC, &data).await {
+                        log::error!("Failed to publish participation metrics: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn traders(count: usize) -> Vec<Pubkey> {
+        (0..count).map(|_| Pubkey::new_unique()).collect()
+    }
+
+    #[test]
+    fn estimates_within_the_sketch_error() {
+        // Four standard errors of 4.6%
+        let estimate = sketch(&traders(10_000)).estimate();
+        assert!((8_160..=11_840).contains(&estimate), "estimate {}", estimate);
+    }
+
+    fn sketch<'a>(traders: impl IntoIterator<Item = &'a Pubkey>) -> HyperLogLog {
+        let mut sketch = HyperLogLog::new();
+        for trader in traders {
+            sketch.insert(trader);
+        }
+        sketch
+    }
+
+    #[test]
+    fn repeated_traders_count_once() {
+        let traders = traders(50);
+        let once = sketch(&traders);
+        let repeated = sketch(traders.iter().cycle().take(1_000));
+        assert_eq!(repeated.estimate(), once.estimate());
+        assert!((40..=60).contains(&once.estimate()), "estimate {}", once.estimate());
+    }
+
+    #[test]
+    fn merged_sketches_count_the_union() {
+        let traders = traders(60);
+        let mut first = sketch(&traders[..40]);
+        first.merge(&sketch(&traders[20..]));
+        assert_eq!(first.estimate(), sketch(&traders).estimate());
+    }
+
+    #[test]
+    fn old_buckets_slide_out_of_the_short_window() {
+        let traders = traders(3);
+        let mut pool = PoolParticipation::new("raydium_amm_v4");
+        let start = 1_700_000_000 - 1_700_000_000 % 3_600;
+        pool.record(&traders[0], start);
+        pool.record(&traders[1], start + 120);
+        assert_eq!(pool.unique_traders(), [2, 2, 2]);
+
+        // Ten minutes later the 5 minute window only holds the new trader
+        pool.record(&traders[2], start + 600);
+        assert_eq!(pool.unique_traders(), [1, 3, 3]);
+        assert_eq!(pool.buckets[0].len(), 1);
+    }
+}
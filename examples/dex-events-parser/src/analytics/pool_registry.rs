@@ -0,0 +1,327 @@
+use {
+    super::{side::TradeSide, transfer_fee::TransferFeeConfig},
+    carbon_core::account::AccountDecoder,
+    carbon_fluxbeam_decoder::{accounts::FluxbeamAccount, FluxbeamDecoder},
+    carbon_meteora_dlmm_decoder::{accounts::MeteoraDlmmAccount, MeteoraDlmmDecoder},
+    carbon_orca_whirlpool_decoder::{accounts::OrcaWhirlpoolAccount, OrcaWhirlpoolDecoder},
+    carbon_pump_swap_decoder::{accounts::PumpSwapAccount, PumpSwapDecoder},
+    carbon_raydium_amm_v4_decoder::{accounts::RaydiumAmmV4Account, RaydiumAmmV4Decoder},
+    carbon_raydium_clmm_decoder::{accounts::RaydiumClmmAccount, RaydiumClmmDecoder},
+    carbon_raydium_cpmm_decoder::{accounts::RaydiumCpmmAccount, RaydiumCpmmDecoder},
+    serde::{Deserialize, Serialize},
+    serde_json::{json, Value},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_commitment_config::CommitmentConfig,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::{HashMap, HashSet},
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    },
+};
+
+const DEFAULT_REGISTRY_FILE: &str = "pool-registry.json";
+/// How often newly resolved pools are written to the registry file.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Trading pair and vaults of a pool account, with base and quote ordered
+/// like the `base_mint` and `quote_mint` of swaps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolInfo {
+    pub platform: String,
+    #[serde(with = "pubkey_string")]
+    pub base_mint: Pubkey,
+    #[serde(with = "pubkey_string")]
+    pub quote_mint: Pubkey,
+    #[serde(with = "pubkey_string")]
+    pub base_vault: Pubkey,
+    #[serde(with = "pubkey_string")]
+    pub quote_vault: Pubkey,
+}
+
+impl PoolInfo {
+    /// Orders the two sides of a pool, `b` breaking ties as the venue's
+    /// quote token.
+    fn new(platform: &str, (mint_a, vault_a): (Pubkey, Pubkey), (mint_b, vault_b): (Pubkey, Pubkey)) -> Option<Self> {
+        let pair = TradeSide::from_mints(mint_a, mint_b, Some(mint_b))?;
+        let (base_vault, quote_vault) = if pair.base_mint == mint_a { (vault_a, vault_b) } else { (vault_b, vault_a) };
+        Some(Self {
+            platform: platform.to_string(),
+            base_mint: pair.base_mint,
+            quote_mint: pair.quote_mint,
+            base_vault,
+            quote_vault,
+        })
+    }
+
+    /// Decodes a pool state account of a supported venue, told apart by its
+    /// owner. `None` for any other account.
+    fn decode(account: &solana_account::Account) -> Option<Self> {
+        let owner = account.owner;
+        if owner == carbon_raydium_amm_v4_decoder::PROGRAM_ID {
+            let RaydiumAmmV4Account::AmmInfo(amm) = RaydiumAmmV4Decoder.decode_account(account)?.data else {
+                return None;
+            };
+            Self::new("Raydium AMM V4", (amm.coin_mint, amm.token_coin), (amm.pc_mint, amm.token_pc))
+        } else if owner == carbon_raydium_clmm_decoder::PROGRAM_ID {
+            let RaydiumClmmAccount::PoolState(pool) = RaydiumClmmDecoder.decode_account(account)?.data else {
+                return None;
+            };
+            Self::new("Raydium CLMM", (pool.token_mint0, pool.token_vault0), (pool.token_mint1, pool.token_vault1))
+        } else if owner == carbon_raydium_cpmm_decoder::PROGRAM_ID {
+            let RaydiumCpmmAccount::PoolState(pool) = RaydiumCpmmDecoder.decode_account(account)?.data else {
+                return None;
+            };
+            Self::new("Raydium CPMM", (pool.token0_mint, pool.token0_vault), (pool.token1_mint, pool.token1_vault))
+        } else if owner == carbon_orca_whirlpool_decoder::PROGRAM_ID {
+            let OrcaWhirlpoolAccount::Whirlpool(whirlpool) = OrcaWhirlpoolDecoder.decode_account(account)?.data else {
+                return None;
+            };
+            Self::new(
+                "Orca Whirlpool",
+                (whirlpool.token_mint_a, whirlpool.token_vault_a),
+                (whirlpool.token_mint_b, whirlpool.token_vault_b),
+            )
+        } else if owner == carbon_meteora_dlmm_decoder::PROGRAM_ID {
+            let MeteoraDlmmAccount::LbPair(pair) = MeteoraDlmmDecoder.decode_account(account)?.data else {
+                return None;
+            };
+            Self::new("Meteora DLMM", (pair.token_x_mint, pair.reserve_x), (pair.token_y_mint, pair.reserve_y))
+        } else if owner == carbon_pump_swap_decoder::PROGRAM_ID {
+            let PumpSwapAccount::Pool(pool) = PumpSwapDecoder.decode_account(account)?.data else {
+                return None;
+            };
+            Self::new(
+                "Pump Swap",
+                (pool.base_mint, pool.pool_base_token_account),
+                (pool.quote_mint, pool.pool_quote_token_account),
+            )
+        } else if owner == carbon_fluxbeam_decoder::PROGRAM_ID {
+            let FluxbeamAccount::SwapV1(swap) = FluxbeamDecoder.decode_account(account)?.data;
+            Self::new("Fluxbeam", (swap.token_a_mint, swap.token_a), (swap.token_b_mint, swap.token_b))
+        } else {
+            None
+        }
+    }
+
+    /// Adds the pair as `pair` to event details.
+    pub fn apply(&self, details: &mut Value) {
+        details["pair"] = json!({
+            "platform": self.platform,
+            "base_mint": self.base_mint.to_string(),
+            "quote_mint": self.quote_mint.to_string(),
+            "base_vault": self.base_vault.to_string(),
+            "quote_vault": self.quote_vault.to_string()
+        });
+    }
+}
+
+/// Transfer fee config of a mint, with the epoch it was read in.
+type MintTransferFee = (u64, Option<TransferFeeConfig>);
+
+/// Registry of the pools events name, mapping each pool account to its
+/// trading pair so events can be tagged with it.
+///
+/// Pools are looked up over RPC the first time they are seen and decoded
+/// with the venue's own decoder. Resolved pools are kept in a JSON file and
+/// loaded at startup, accounts that are not pools of a supported venue are
+/// only remembered until a restart. The transfer fees of the Token-2022
+/// mints they trade are looked up the same way, but only kept in memory.
+#[derive(Clone, Default)]
+pub struct PoolRegistry {
+    client: Option<Arc<RpcClient>>,
+    path: Option<PathBuf>,
+    pools: Arc<Mutex<HashMap<Pubkey, PoolInfo>>>,
+    unknown: Arc<Mutex<HashSet<Pubkey>>>,
+    transfer_fees: Arc<Mutex<HashMap<Pubkey, MintTransferFee>>>,
+    dirty: Arc<AtomicBool>,
+}
+
+impl PoolRegistry {
+    /// Reads `POOL_REGISTRY` (`true` enables it) and `POOL_REGISTRY_FILE`
+    /// (default `pool-registry.json`). Pools are looked up on
+    /// `rpc_http_url`.
+    pub fn from_env(rpc_http_url: &str) -> Result<Self, String> {
+        if !std::env::var("POOL_REGISTRY").is_ok_and(|value| value == "true" || value == "1") {
+            return Ok(Self::default());
+        }
+        let path = PathBuf::from(
+            std::env::var("POOL_REGISTRY_FILE")
+                .ok()
+                .filter(|path| !path.is_empty())
+                .unwrap_or_else(|| DEFAULT_REGISTRY_FILE.to_string()),
+        );
+        let pools: HashMap<String, PoolInfo> = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Unreadable pool registry {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        let pools = pools
+            .into_iter()
+            .map(|(pool, info)| {
+                pool.parse::<Pubkey>()
+                    .map(|pool| (pool, info))
+                    .map_err(|e| format!("Invalid pool {} in {}: {}", pool, path.display(), e))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(Self {
+            client: Some(Arc::new(RpcClient::new_with_commitment(
+                rpc_http_url.to_string(),
+                CommitmentConfig::confirmed(),
+            ))),
+            path: Some(path),
+            pools: Arc::new(Mutex::new(pools)),
+            unknown: Arc::default(),
+            transfer_fees: Arc::default(),
+            dirty: Arc::default(),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.client.is_some()
+    }
+
+    /// Number of pools known so far.
+    pub fn len(&self) -> usize {
+        self.pools.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Pair of `pool`, looked up over RPC when it was not seen before.
+    /// Failed lookups are retried the next time the pool is seen.
+    pub async fn resolve(&self, pool: &Pubkey) -> Option<PoolInfo> {
+        let client = self.client.as_ref()?;
+        if let Some(info) = self.pools.lock().unwrap_or_else(|e| e.into_inner()).get(pool) {
+            return Some(info.clone());
+        }
+        if self.unknown.lock().unwrap_or_else(|e| e.into_inner()).contains(pool) {
+            return None;
+        }
+
+        let account = match client.get_account_with_commitment(pool, CommitmentConfig::confirmed()).await {
+            Ok(response) => response.value,
+            Err(e) => {
+                log::warn!("Failed to look up pool {}: {}", pool, e);
+                return None;
+            }
+        };
+        match account.as_ref().and_then(PoolInfo::decode) {
+            Some(info) => {
+                self.pools.lock().unwrap_or_else(|e| e.into_inner()).insert(*pool, info.clone());
+                self.dirty.store(true, Ordering::Relaxed);
+                Some(info)
+            }
+            None => {
+                self.unknown.lock().unwrap_or_else(|e| e.into_inner()).insert(*pool);
+                None
+            }
+        }
+    }
+
+    /// Transfer fee config of `mint` during `epoch`, `None` when the mint
+    /// charges none. Read again once a fee set since may be in force;
+    /// failed lookups are retried the next time.
+    pub async fn transfer_fee(&self, mint: &Pubkey, epoch: u64) -> Option<TransferFeeConfig> {
+        let client = self.client.as_ref()?;
+        if let Some((fetched, config)) = self.transfer_fees.lock().unwrap_or_else(|e| e.into_inner()).get(mint) {
+            if !TransferFeeConfig::is_stale(*fetched, epoch) {
+                return *config;
+            }
+        }
+
+        let config = match client.get_account_with_commitment(mint, CommitmentConfig::confirmed()).await {
+            Ok(response) => response.value.as_ref().and_then(TransferFeeConfig::from_mint),
+            Err(e) => {
+                log::warn!("Failed to look up mint {}: {}", mint, e);
+                return None;
+            }
+        };
+        self.transfer_fees.lock().unwrap_or_else(|e| e.into_inner()).insert(*mint, (epoch, config));
+        config
+    }
+
+    /// Adds the pair of the pool named by event details, as `pool` or as the
+    /// `pool` of the executed swap, to the details.
+    pub async fn annotate(&self, details: &mut Value) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Some(pool) = details["pool"]
+            .as_str()
+            .or_else(|| details["executed"]["pool"].as_str())
+            .and_then(|pool| pool.parse::<Pubkey>().ok())
+        else {
+            return;
+        };
+        if let Some(info) = self.resolve(&pool).await {
+            info.apply(details);
+        }
+    }
+
+    /// Writes newly resolved pools to the registry file periodically.
+    pub fn spawn_saver(&self) -> tokio::task::JoinHandle<()> {
+        let registry = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SAVE_INTERVAL);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                if !registry.dirty.swap(false, Ordering::Relaxed) {
+                    continue;
+                }
+                if let Err(e) = registry.save().await {
+                    registry.dirty.store(true, Ordering::Relaxed);
+                    log::error!("Failed to save the pool registry: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let content = {
+            let pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+            let pools = pools
+                .iter()
+                .map(|(pool, info)| (pool.to_string(), info))
+                .collect::<HashMap<_, _>>();
+            serde_json::to_string(&pools).map_err(|e| e.to_string())?
+        };
+        // A crash mid-write must not leave a truncated registry behind
+        let temporary = path.with_extension("tmp");
+        tokio::fs::write(&temporary, content)
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", temporary.display(), e))?;
+        tokio::fs::rename(&temporary, path)
+            .await
+            .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+    }
+}
+
+mod pubkey_string {
+    use {
+        serde::{Deserialize, Deserializer, Serializer},
+        solana_pubkey::Pubkey,
+    };
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,347 @@
+use {
+    super::{
+        balances::{post_token_balance, token_balance_change},
+        decimals::{mint_decimals, ui_amount},
+        pool_registry::{PoolInfo, PoolRegistry},
+        usd_prices::UsdPrices,
+    },
+    crate::{
+        publishers::{DexEventData, Publisher, UnifiedPublisher, EVENTS_TOPIC},
+        DexEvent,
+    },
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime},
+    },
+};
+
+const DEFAULT_PUBLISH_INTERVAL_SECS: u64 = 10;
+const DEFAULT_MAX_TRACKED_POOLS: usize = 5_000;
+
+/// Venues pricing on `x * y = k` over their vault balances, for which the
+/// depth can be derived from the reserves alone.
+const CONSTANT_PRODUCT_PLATFORMS: [&str; 4] = ["Raydium AMM V4", "Raydium CPMM", "Pump Swap", "Fluxbeam"];
+
+/// Price moves the depth of constant product pools is published for.
+const DEPTH_MOVES: [f64; 3] = [0.01, 0.02, 0.05];
+
+struct Reserves {
+    info: PoolInfo,
+    base_decimals: Option<u8>,
+    quote_decimals: Option<u8>,
+    base: u64,
+    quote: u64,
+    /// Reserves of the last published snapshot.
+    published: Option<(u64, u64)>,
+    /// Quote per base of the last transaction that moved both vaults, for
+    /// venues whose price is not set by the reserves.
+    trade_price: Option<f64>,
+    slot: u64,
+    updated_at: i64,
+}
+
+impl Reserves {
+    /// Quote per base, adjusted for decimals.
+    fn price(&self) -> Option<f64> {
+        if !self.is_constant_product() {
+            return self.trade_price;
+        }
+        let base = ui_amount(self.base, self.base_decimals?);
+        let quote = ui_amount(self.quote, self.quote_decimals?);
+        (base > 0.0).then(|| quote / base)
+    }
+
+    fn is_constant_product(&self) -> bool {
+        CONSTANT_PRODUCT_PLATFORMS.contains(&self.info.platform.as_str())
+    }
+
+    /// Raw amounts to trade for the price to move by each of
+    /// [`DEPTH_MOVES`], fees left out.
+    fn depth(&self) -> Option<Value> {
+        if !self.is_constant_product() || self.base == 0 || self.quote == 0 {
+            return None;
+        }
+        let (base, quote) = (self.base as f64, self.quote as f64);
+        Some(
+            DEPTH_MOVES
+                .iter()
+                .map(|price_move| {
+                    json!({
+                        "price_move": price_move,
+                        "buy_quote_in": (quote * ((1.0 + price_move).sqrt() - 1.0)).round() as u64,
+                        "buy_base_out": (base * (1.0 - 1.0 / (1.0 + price_move).sqrt())).round() as u64,
+                        "sell_base_in": (base * (1.0 / (1.0 - price_move).sqrt() - 1.0)).round() as u64,
+                        "sell_quote_out": (quote * (1.0 - (1.0 - price_move).sqrt())).round() as u64
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    fn snapshot(&self, pool: &Pubkey, usd_prices: &UsdPrices, now: i64) -> Value {
+        let (published_base, published_quote) = self.published.unwrap_or_default();
+        let mut details = json!({
+            "type": "PoolDepth",
+            "pool": pool.to_string(),
+            "base_mint": self.info.base_mint.to_string(),
+            "quote_mint": self.info.quote_mint.to_string(),
+            "base_reserve": self.base,
+            "quote_reserve": self.quote,
+            "base_change": (self.base as i128 - published_base as i128).to_string(),
+            "quote_change": (self.quote as i128 - published_quote as i128).to_string(),
+            "depth": self.depth()
+        });
+        let (Some(price), Some(base_decimals), Some(quote_decimals)) = (self.price(), self.base_decimals, self.quote_decimals)
+        else {
+            return details;
+        };
+        let tvl_quote = ui_amount(self.quote, quote_decimals) + ui_amount(self.base, base_decimals) * price;
+        details["price"] = json!(price);
+        details["tvl_quote"] = json!(tvl_quote);
+        // A quote price is used first, the base price otherwise
+        let tvl_usd = usd_prices
+            .price(&self.info.quote_mint, now)
+            .map(|quote_price| tvl_quote * quote_price.price)
+            .or_else(|| {
+                let base_price = usd_prices.price(&self.info.base_mint, now)?;
+                Some(tvl_quote / price * base_price.price)
+            });
+        if let Some(tvl_usd) = tvl_usd.filter(|tvl_usd| tvl_usd.is_finite()) {
+            details["tvl_usd"] = json!(tvl_usd);
+        }
+        details
+    }
+}
+
+/// Reserves of the pools events were published for, read from the token
+/// balances of their vaults after each transaction and from vault account
+/// updates when pool states are followed, published as `pool_depth`
+/// snapshots with the pool's TVL and, for constant product pools, the
+/// amounts that move the price by 1%, 2% and 5%.
+///
+/// Vaults are found through the [`PoolRegistry`], which has to be enabled.
+/// Memory is bounded by the number of tracked pools, the least recently
+/// updated one is dropped when the limit is reached.
+#[derive(Clone, Default)]
+pub struct PoolReserves {
+    registry: PoolRegistry,
+    usd_prices: UsdPrices,
+    pools: Arc<Mutex<HashMap<Pubkey, Reserves>>>,
+    /// Vault to its pool and whether it holds the base side.
+    vaults: Arc<Mutex<HashMap<Pubkey, (Pubkey, bool)>>>,
+    max_tracked_pools: usize,
+    /// `None` when disabled.
+    publish_interval: Option<Duration>,
+}
+
+impl PoolReserves {
+    /// Reads `POOL_RESERVES` (`true`/`1` to enable, default off),
+    /// `POOL_RESERVES_INTERVAL_SECS` (default `10`) and
+    /// `POOL_RESERVES_MAX_POOLS` (default `5000`).
+    pub fn from_env(registry: PoolRegistry, usd_prices: UsdPrices) -> Result<Self, String> {
+        if !matches!(std::env::var("POOL_RESERVES").as_deref(), Ok("true") | Ok("1")) {
+            return Ok(Self::default());
+        }
+        if !registry.is_enabled() {
+            return Err("POOL_RESERVES needs POOL_REGISTRY to find the vaults of pools".to_string());
+        }
+        let interval_secs = std::env::var("POOL_RESERVES_INTERVAL_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_PUBLISH_INTERVAL_SECS);
+        let max_tracked_pools = std::env::var("POOL_RESERVES_MAX_POOLS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_TRACKED_POOLS);
+
+        Ok(Self {
+            registry,
+            usd_prices,
+            pools: Arc::default(),
+            vaults: Arc::default(),
+            max_tracked_pools: max_tracked_pools.max(1),
+            publish_interval: Some(Duration::from_secs(interval_secs.max(1))),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.publish_interval.is_some()
+    }
+
+    /// Records the vault balances of the pool of an event after its
+    /// transaction and adds them to the details as `reserves`, with how
+    /// much the transaction changed them. Nothing is recorded when either
+    /// vault is missing from the transaction's token balances.
+    pub async fn observe(&self, transaction: &TransactionMetadata, details: &mut Value) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Some(pool) = details["pool"]
+            .as_str()
+            .or_else(|| details["executed"]["pool"].as_str())
+            .and_then(|pool| pool.parse::<Pubkey>().ok())
+        else {
+            return;
+        };
+        let Some(info) = self.registry.resolve(&pool).await else {
+            return;
+        };
+        let (Some(base), Some(quote)) = (
+            post_token_balance(transaction, &info.base_vault),
+            post_token_balance(transaction, &info.quote_vault),
+        ) else {
+            return;
+        };
+        let base_change = token_balance_change(transaction, &info.base_vault).unwrap_or_default();
+        let quote_change = token_balance_change(transaction, &info.quote_vault).unwrap_or_default();
+        details["reserves"] = json!({
+            "base": base,
+            "quote": quote,
+            "base_change": base_change.to_string(),
+            "quote_change": quote_change.to_string()
+        });
+
+        let base_decimals = mint_decimals(transaction, &info.base_mint);
+        let quote_decimals = mint_decimals(transaction, &info.quote_mint);
+        // Vaults moving in opposite directions is a trade against the pool
+        let trade_price = (base_change.signum() * quote_change.signum() < 0)
+            .then(|| {
+                let base = ui_amount(base_change.unsigned_abs() as u64, base_decimals?);
+                let quote = ui_amount(quote_change.unsigned_abs() as u64, quote_decimals?);
+                (base > 0.0).then(|| quote / base)
+            })
+            .flatten();
+
+        self.vaults
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend([(info.base_vault, (pool, true)), (info.quote_vault, (pool, false))]);
+        let mut pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+        if !pools.contains_key(&pool) && pools.len() >= self.max_tracked_pools {
+            if let Some(idle) = pools
+                .iter()
+                .min_by_key(|(_, reserves)| reserves.updated_at)
+                .map(|(pool, _)| *pool)
+            {
+                if let Some(reserves) = pools.remove(&idle) {
+                    let mut vaults = self.vaults.lock().unwrap_or_else(|e| e.into_inner());
+                    vaults.remove(&reserves.info.base_vault);
+                    vaults.remove(&reserves.info.quote_vault);
+                }
+            }
+        }
+        let reserves = pools.entry(pool).or_insert_with(|| Reserves {
+            info,
+            base_decimals: None,
+            quote_decimals: None,
+            base,
+            quote,
+            published: None,
+            trade_price: None,
+            slot: 0,
+            updated_at: 0,
+        });
+        // Transactions of a pool may be processed out of order
+        if transaction.slot < reserves.slot {
+            return;
+        }
+        reserves.base = base;
+        reserves.quote = quote;
+        reserves.base_decimals = base_decimals.or(reserves.base_decimals);
+        reserves.quote_decimals = quote_decimals.or(reserves.quote_decimals);
+        reserves.trade_price = trade_price.or(reserves.trade_price);
+        reserves.slot = transaction.slot;
+        reserves.updated_at = unix_now();
+    }
+
+    /// Records the balance of a vault from an account update. Only vaults
+    /// of pools an event was seen for are known.
+    pub fn observe_vault(&self, vault: &Pubkey, amount: u64, slot: u64) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Some((pool, is_base)) = self.vaults.lock().unwrap_or_else(|e| e.into_inner()).get(vault).copied() else {
+            return;
+        };
+        let mut pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(reserves) = pools.get_mut(&pool).filter(|reserves| slot >= reserves.slot) else {
+            return;
+        };
+        if is_base {
+            reserves.base = amount;
+        } else {
+            reserves.quote = amount;
+        }
+        reserves.slot = slot;
+        reserves.updated_at = unix_now();
+    }
+
+    /// Publishes a `pool_depth` event per pool whose reserves changed since
+    /// its last snapshot every interval.
+    pub fn spawn_publisher(&self, publisher: UnifiedPublisher) -> Option<tokio::task::JoinHandle<()>> {
+        let publish_interval = self.publish_interval?;
+        let tracker = self.clone();
+
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(publish_interval);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                let now = unix_now();
+                let snapshot = {
+                    let mut pools = tracker.pools.lock().unwrap_or_else(|e| e.into_inner());
+                    pools
+                        .iter_mut()
+                        .filter(|(_, reserves)| reserves.published != Some((reserves.base, reserves.quote)))
+                        .map(|(pool, reserves)| {
+                            let details = reserves.snapshot(pool, &tracker.usd_prices, now);
+                            reserves.published = Some((reserves.base, reserves.quote));
+                            (reserves.info.platform.clone(), reserves.slot, details)
+                        })
+                        .collect::<Vec<_>>()
+                };
+
+                for (platform, slot, details) in snapshot {
+                    DexEvent::PoolDepth {
+                        platform: platform.clone(),
+                        details: details.to_string(),
+                    }
+                    .log();
+
+                    let data = DexEventData {
+                        event_type: "pool_depth".to_string(),
+                        platform,
+                        signature: String::new(),
+                        timestamp: now as u64,
+                        block_time: None,
+                        slot: Some(slot),
+                        details,
+                        ingested_at: None,
+                        tx_index: None,
+                        instruction_index: None,
+                        is_inner: None,
+                        fee_payer: None,
+                        trader: None,
+                        payload: None,
+                    };
+                    if let Err(e) = publisher.publish(EVENTS_TOPIC, &data).await {
+                        log::error!("Failed to publish pool depth: {}", e);
+                    }
+                }
+            }
+        }))
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
@@ -0,0 +1,296 @@
+use {
+    super::{
+        decimals::ui_amount,
+        mints::{is_usd_stable, SOL_DECIMALS, USD_STABLE_DECIMALS, WSOL_MINT},
+    },
+    crate::datasources::pool_accounts::PoolAccountSubscription,
+    carbon_core::account::AccountDecoder,
+    carbon_orca_whirlpool_decoder::{accounts::OrcaWhirlpoolAccount, OrcaWhirlpoolDecoder},
+    carbon_raydium_amm_v4_decoder::{
+        accounts::{amm_info::AmmInfo, RaydiumAmmV4Account},
+        RaydiumAmmV4Decoder,
+    },
+    serde_json::{json, Value},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_commitment_config::CommitmentConfig,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        str::FromStr,
+        sync::{Arc, Mutex},
+    },
+};
+
+/// Data size of a Whirlpool account.
+const WHIRLPOOL_SIZE: u64 = 653;
+/// Data size of a Raydium CLMM `PoolState` account.
+const CLMM_POOL_STATE_SIZE: u64 = 1544;
+/// Offset of `decimals` in an SPL Token or Token-2022 mint.
+const MINT_DECIMALS_OFFSET: usize = 44;
+/// Accounts per `getMultipleAccounts` request, the RPC maximum.
+const MULTIPLE_ACCOUNTS_LIMIT: usize = 100;
+
+/// Which side of an AMM pool a vault holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VaultSide {
+    Coin,
+    Pc,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AmmVault {
+    pool: Pubkey,
+    side: VaultSide,
+}
+
+/// Last known vault balances of a Raydium AMM V4 pool, and the PnL its
+/// vaults hold that is owed to the protocol rather than the pool.
+#[derive(Debug, Clone)]
+struct AmmReserves {
+    coin_mint: Pubkey,
+    pc_mint: Pubkey,
+    coin_decimals: u8,
+    pc_decimals: u8,
+    coin: Option<u64>,
+    pc: Option<u64>,
+    need_take_pnl_coin: u64,
+    need_take_pnl_pc: u64,
+}
+
+impl AmmReserves {
+    /// `ReserveUpdate` of the pool once both vaults were seen.
+    fn update(&self, pool: &Pubkey) -> Option<Value> {
+        let coin = self.coin?.saturating_sub(self.need_take_pnl_coin);
+        let pc = self.pc?.saturating_sub(self.need_take_pnl_pc);
+        let coin_ui = ui_amount(coin, self.coin_decimals);
+        let mut details = json!({
+            "type": "ReserveUpdate",
+            "pool": pool.to_string(),
+            "mint_a": self.coin_mint.to_string(),
+            "mint_b": self.pc_mint.to_string(),
+            "reserve_a": coin,
+            "reserve_b": pc,
+        });
+        if coin_ui > 0.0 {
+            details["price"] = json!(ui_amount(pc, self.pc_decimals) / coin_ui);
+        }
+        Some(details)
+    }
+}
+
+/// Live reserves and prices of pools, from account updates instead of
+/// instruction events.
+///
+/// Whirlpools and CLMM pools are priced from the square root price of their
+/// pool account. Raydium AMM V4 pools are priced from the balances of their
+/// two token vaults, so their vaults are followed along with the pool, whose
+/// account only holds the PnL to deduct from them.
+#[derive(Clone, Default)]
+pub struct PoolStates {
+    subscriptions: Vec<PoolAccountSubscription>,
+    vaults: Arc<HashMap<Pubkey, AmmVault>>,
+    reserves: Arc<Mutex<HashMap<Pubkey, AmmReserves>>>,
+    decimals: Arc<HashMap<Pubkey, u8>>,
+}
+
+impl PoolStates {
+    /// Reads `POOL_STATE_POOLS`, comma separated Whirlpool, Raydium CLMM or
+    /// Raydium AMM V4 pools to follow, and `POOL_STATE_ALL_POOLS` (`true`
+    /// follows every Whirlpool and CLMM pool). The listed pools are looked
+    /// up on `rpc_http_url` to find the AMM vaults and the mint decimals.
+    pub async fn from_env(rpc_http_url: &str) -> Result<Self, String> {
+        let pools = std::env::var("POOL_STATE_POOLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|pool| !pool.is_empty())
+            .map(|pool| Pubkey::from_str(pool).map_err(|e| format!("Invalid POOL_STATE_POOLS entry {}: {}", pool, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let all_pools = std::env::var("POOL_STATE_ALL_POOLS").is_ok_and(|value| value == "true" || value == "1");
+        if pools.is_empty() && !all_pools {
+            return Ok(Self::default());
+        }
+
+        let client = RpcClient::new_with_commitment(rpc_http_url.to_string(), CommitmentConfig::confirmed());
+        let mut states = Self::resolve(&client, &pools).await?;
+        if all_pools {
+            states.subscriptions.extend([
+                PoolAccountSubscription::Program {
+                    program_id: carbon_orca_whirlpool_decoder::PROGRAM_ID,
+                    data_size: WHIRLPOOL_SIZE,
+                },
+                PoolAccountSubscription::Program {
+                    program_id: carbon_raydium_clmm_decoder::PROGRAM_ID,
+                    data_size: CLMM_POOL_STATE_SIZE,
+                },
+            ]);
+        }
+        Ok(states)
+    }
+
+    /// Subscribes to Whirlpool and CLMM pools directly and to AMM pools and
+    /// their vaults, and reads the decimals of the Whirlpool mints, which the
+    /// pool account lacks.
+    async fn resolve(client: &RpcClient, pools: &[Pubkey]) -> Result<Self, String> {
+        let mut subscriptions = Vec::new();
+        let mut vaults = HashMap::new();
+        let mut reserves = HashMap::new();
+        let mut mints = Vec::new();
+
+        for (pool, account) in pools.iter().zip(multiple_accounts(client, pools).await?) {
+            let Some(account) = account else {
+                return Err(format!("Pool {} does not exist", pool));
+            };
+            if account.owner == carbon_raydium_amm_v4_decoder::PROGRAM_ID {
+                let Some(RaydiumAmmV4Account::AmmInfo(amm)) =
+                    RaydiumAmmV4Decoder.decode_account(&account).map(|decoded| decoded.data)
+                else {
+                    return Err(format!("Pool {} is not a Raydium AMM V4 pool", pool));
+                };
+                vaults.insert(amm.token_coin, AmmVault { pool: *pool, side: VaultSide::Coin });
+                vaults.insert(amm.token_pc, AmmVault { pool: *pool, side: VaultSide::Pc });
+                subscriptions.push(PoolAccountSubscription::Account(*pool));
+                subscriptions.push(PoolAccountSubscription::Account(amm.token_coin));
+                subscriptions.push(PoolAccountSubscription::Account(amm.token_pc));
+                reserves.insert(
+                    *pool,
+                    AmmReserves {
+                        coin_mint: amm.coin_mint,
+                        pc_mint: amm.pc_mint,
+                        coin_decimals: amm.coin_decimals as u8,
+                        pc_decimals: amm.pc_decimals as u8,
+                        coin: None,
+                        pc: None,
+                        need_take_pnl_coin: amm.out_put.need_take_pnl_coin,
+                        need_take_pnl_pc: amm.out_put.need_take_pnl_pc,
+                    },
+                );
+            } else if account.owner == carbon_orca_whirlpool_decoder::PROGRAM_ID {
+                let Some(OrcaWhirlpoolAccount::Whirlpool(whirlpool)) =
+                    OrcaWhirlpoolDecoder.decode_account(&account).map(|decoded| decoded.data)
+                else {
+                    return Err(format!("Pool {} is not a Whirlpool", pool));
+                };
+                mints.extend([whirlpool.token_mint_a, whirlpool.token_mint_b]);
+                subscriptions.push(PoolAccountSubscription::Account(*pool));
+            } else if account.owner == carbon_raydium_clmm_decoder::PROGRAM_ID {
+                subscriptions.push(PoolAccountSubscription::Account(*pool));
+            } else {
+                return Err(format!("Pool {} is owned by unsupported program {}", pool, account.owner));
+            }
+        }
+
+        let mut decimals = HashMap::new();
+        for (mint, account) in mints.iter().zip(multiple_accounts(client, &mints).await?) {
+            if let Some(value) = account.and_then(|account| account.data.get(MINT_DECIMALS_OFFSET).copied()) {
+                decimals.insert(*mint, value);
+            }
+        }
+
+        Ok(Self {
+            subscriptions,
+            vaults: Arc::new(vaults),
+            reserves: Arc::new(Mutex::new(reserves)),
+            decimals: Arc::new(decimals),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.subscriptions.is_empty()
+    }
+
+    pub fn subscriptions(&self) -> Vec<PoolAccountSubscription> {
+        self.subscriptions.clone()
+    }
+
+    /// Decimals of `mint`, looked up at startup or, for SOL and the USD
+    /// stablecoins, their well-known values.
+    pub fn mint_decimals(&self, mint: &Pubkey) -> Option<u8> {
+        self.decimals.get(mint).copied().or_else(|| {
+            if *mint == WSOL_MINT {
+                Some(SOL_DECIMALS as u8)
+            } else if is_usd_stable(mint) {
+                Some(USD_STABLE_DECIMALS as u8)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Records the balance of an AMM vault. Returns the reserves and price
+    /// of its pool once both vaults were seen, `None` for other token
+    /// accounts.
+    pub fn record_vault(&self, vault: &Pubkey, amount: u64) -> Option<(Pubkey, Value)> {
+        let AmmVault { pool, side } = *self.vaults.get(vault)?;
+        let mut reserves = self.reserves.lock().unwrap_or_else(|e| e.into_inner());
+        let pool_reserves = reserves.get_mut(&pool)?;
+        match side {
+            VaultSide::Coin => pool_reserves.coin = Some(amount),
+            VaultSide::Pc => pool_reserves.pc = Some(amount),
+        }
+        Some((pool, pool_reserves.update(&pool)?))
+    }
+
+    /// Records the PnL an AMM pool owes the protocol. Returns the reserves
+    /// and price of the pool when it changed, since most updates of the pool
+    /// account only bump its swap totals.
+    pub fn record_amm(&self, pool: &Pubkey, amm: &AmmInfo) -> Option<Value> {
+        let mut reserves = self.reserves.lock().unwrap_or_else(|e| e.into_inner());
+        let pool_reserves = reserves.get_mut(pool)?;
+        let need_take_pnl = (amm.out_put.need_take_pnl_coin, amm.out_put.need_take_pnl_pc);
+        if need_take_pnl == (pool_reserves.need_take_pnl_coin, pool_reserves.need_take_pnl_pc) {
+            return None;
+        }
+        (pool_reserves.need_take_pnl_coin, pool_reserves.need_take_pnl_pc) = need_take_pnl;
+        pool_reserves.update(pool)
+    }
+
+    /// `PriceUpdate` of a concentrated liquidity pool, priced in token B per
+    /// token A from its Q64.64 square root price. The raw price is always
+    /// set, the decimal adjusted one when the decimals of both mints are
+    /// known.
+    pub fn concentrated_pool(
+        &self,
+        pool: &Pubkey,
+        (mint_a, decimals_a): (Pubkey, Option<u8>),
+        (mint_b, decimals_b): (Pubkey, Option<u8>),
+        sqrt_price_x64: u128,
+        liquidity: u128,
+        tick: i32,
+    ) -> Value {
+        let sqrt_price = sqrt_price_x64 as f64 / 2f64.powi(64);
+        let price_raw = sqrt_price * sqrt_price;
+        let mut details = json!({
+            "type": "PriceUpdate",
+            "pool": pool.to_string(),
+            "mint_a": mint_a.to_string(),
+            "mint_b": mint_b.to_string(),
+            "sqrt_price_x64": sqrt_price_x64.to_string(),
+            "liquidity": liquidity.to_string(),
+            "tick": tick,
+            "price_raw": price_raw,
+        });
+        let decimals_a = decimals_a.or_else(|| self.mint_decimals(&mint_a));
+        let decimals_b = decimals_b.or_else(|| self.mint_decimals(&mint_b));
+        if let (Some(decimals_a), Some(decimals_b)) = (decimals_a, decimals_b) {
+            details["price"] = json!(price_raw * 10f64.powi(decimals_a as i32 - decimals_b as i32));
+        }
+        details
+    }
+}
+
+async fn multiple_accounts(
+    client: &RpcClient,
+    addresses: &[Pubkey],
+) -> Result<Vec<Option<solana_account::Account>>, String> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MULTIPLE_ACCOUNTS_LIMIT) {
+        accounts.extend(
+            client
+                .get_multiple_accounts(chunk)
+                .await
+                .map_err(|e| format!("Failed to fetch pool accounts: {}", e))?,
+        );
+    }
+    Ok(accounts)
+}
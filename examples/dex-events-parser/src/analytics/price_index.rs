@@ -0,0 +1,238 @@
+use {
+    carbon_core::transaction::TransactionMetadata,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{
+        collections::HashMap,
+        sync::{Arc, RwLock},
+    },
+};
+
+use super::balances::{amount_received, amount_sent, token_account_mint};
+
+/// Executed side of a swap: what the trader actually sent and received,
+/// taken from the balance changes of their token accounts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapFill {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub input_amount: u64,
+    pub output_amount: u64,
+}
+
+impl SwapFill {
+    pub fn from_balance_changes(
+        transaction: &TransactionMetadata,
+        source_token_account: &Pubkey,
+        destination_token_account: &Pubkey,
+    ) -> Option<Self> {
+        let input_amount = amount_sent(transaction, source_token_account).filter(|a| *a > 0)?;
+        let output_amount =
+            amount_received(transaction, destination_token_account).filter(|a| *a > 0)?;
+
+        Some(Self {
+            input_mint: token_account_mint(transaction, source_token_account)?,
+            output_mint: token_account_mint(transaction, destination_token_account)?,
+            input_amount,
+            output_amount,
+        })
+    }
+
+    /// Output received per unit of input, in raw token units.
+    pub fn price(&self) -> f64 {
+        self.output_amount as f64 / self.input_amount as f64
+    }
+}
+
+/// Last execution price seen on a venue for a mint pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceObservation {
+    pub venue: String,
+    pub slot: u64,
+    pub signature: Signature,
+    pub price: f64,
+}
+
+/// Latest observation per venue, keyed by directed (input mint, output mint) pair.
+type VenuePrices = HashMap<(Pubkey, Pubkey), HashMap<String, PriceObservation>>;
+
+/// Observations older than this many slots (about an hour) are dropped.
+const DEFAULT_TTL_SLOTS: u64 = 9_000;
+
+#[derive(Default)]
+struct Prices {
+    pairs: VenuePrices,
+    /// Newest slot recorded.
+    slot: u64,
+    /// `slot` when stale observations were last dropped.
+    pruned_at: u64,
+}
+
+impl Prices {
+    /// Drops observations older than `ttl_slots`, and pairs left without
+    /// any, once the newest slot moved on by an eighth of the TTL.
+    fn prune(&mut self, ttl_slots: u64) {
+        if self.slot < self.pruned_at + (ttl_slots / 8).max(1) {
+            return;
+        }
+        let oldest = self.slot.saturating_sub(ttl_slots);
+        self.pairs.retain(|_, venues| {
+            venues.retain(|_, observation| observation.slot >= oldest);
+            !venues.is_empty()
+        });
+        self.pruned_at = self.slot;
+    }
+}
+
+/// Live index of the latest swap price per venue and directed mint pair,
+/// fed by the processors of the venues that are traded on directly.
+/// Observations expire after a TTL in slots, so pairs that stop trading do
+/// not accumulate.
+#[derive(Clone)]
+pub struct PriceIndex {
+    prices: Arc<RwLock<Prices>>,
+    ttl_slots: u64,
+}
+
+impl Default for PriceIndex {
+    fn default() -> Self {
+        Self::with_ttl_slots(DEFAULT_TTL_SLOTS)
+    }
+}
+
+impl PriceIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ttl_slots(ttl_slots: u64) -> Self {
+        Self {
+            prices: Arc::default(),
+            ttl_slots: ttl_slots.max(1),
+        }
+    }
+
+    /// Reads `PRICE_INDEX_TTL_SLOTS` (default `9000`).
+    pub fn from_env() -> Self {
+        let ttl_slots = std::env::var("PRICE_INDEX_TTL_SLOTS")
+            .ok()
+            .and_then(|ttl| ttl.parse().ok())
+            .unwrap_or(DEFAULT_TTL_SLOTS);
+        Self::with_ttl_slots(ttl_slots)
+    }
+
+    /// Number of directed pairs with a live observation.
+    pub fn len(&self) -> usize {
+        self.prices.read().unwrap_or_else(|e| e.into_inner()).pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn record(&self, venue: &str, slot: u64, signature: Signature, fill: &SwapFill) {
+        let mut prices = self.prices.write().unwrap_or_else(|e| e.into_inner());
+        prices.slot = prices.slot.max(slot);
+        prices.prune(self.ttl_slots);
+        let venues = prices.pairs.entry((fill.input_mint, fill.output_mint)).or_default();
+        if venues.get(venue).is_some_and(|last| last.slot > slot) {
+            return;
+        }
+
+        venues.insert(
+            venue.to_string(),
+            PriceObservation {
+                venue: venue.to_string(),
+                slot,
+                signature,
+                price: fill.price(),
+            },
+        );
+    }
+
+    /// Records the fill of a direct venue swap, if it can be derived from
+    /// the transaction's balance changes, and returns it.
+    pub fn observe(
+        &self,
+        venue: &str,
+        transaction: &TransactionMetadata,
+        source_token_account: &Pubkey,
+        destination_token_account: &Pubkey,
+    ) -> Option<SwapFill> {
+        // A failed swap moved nothing
+        if transaction.meta.status.is_err() {
+            return None;
+        }
+        let fill = SwapFill::from_balance_changes(
+            transaction,
+            source_token_account,
+            destination_token_account,
+        )?;
+        self.record(venue, transaction.slot, transaction.signature, &fill);
+        Some(fill)
+    }
+
+    /// Most recent price for swapping `input_mint` into `output_mint` on any
+    /// venue, regardless of slot. Reverse-direction prices are inverted.
+    pub fn latest_price(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> Option<f64> {
+        let prices = &self.prices.read().unwrap_or_else(|e| e.into_inner()).pairs;
+        let direct = prices
+            .get(&(*input_mint, *output_mint))
+            .into_iter()
+            .flat_map(|venues| venues.values())
+            .map(|observation| (observation.slot, observation.price));
+        let inverse = prices
+            .get(&(*output_mint, *input_mint))
+            .into_iter()
+            .flat_map(|venues| venues.values())
+            .map(|observation| (observation.slot, 1.0 / observation.price));
+
+        direct
+            .chain(inverse)
+            .filter(|(_, price)| price.is_finite() && *price > 0.0)
+            .max_by_key(|(slot, _)| *slot)
+            .map(|(_, price)| price)
+    }
+
+    /// Best single-venue price for swapping `input_mint` into `output_mint`
+    /// among observations from `slot - max_slot_lag` up to `slot`. Prices
+    /// seen in the opposite direction are inverted, and observations made by
+    /// the `exclude` transaction itself are ignored.
+    pub fn best_price(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        slot: u64,
+        max_slot_lag: u64,
+        exclude: &Signature,
+    ) -> Option<PriceObservation> {
+        let prices = &self.prices.read().unwrap_or_else(|e| e.into_inner()).pairs;
+        let in_window = |observation: &&PriceObservation| {
+            observation.slot <= slot
+                && observation.slot + max_slot_lag >= slot
+                && observation.signature != *exclude
+                && observation.price.is_finite()
+                && observation.price > 0.0
+        };
+
+        let direct = prices
+            .get(&(*input_mint, *output_mint))
+            .into_iter()
+            .flat_map(|venues| venues.values())
+            .filter(in_window)
+            .cloned();
+        let inverse = prices
+            .get(&(*output_mint, *input_mint))
+            .into_iter()
+            .flat_map(|venues| venues.values())
+            .filter(in_window)
+            .map(|observation| PriceObservation {
+                price: 1.0 / observation.price,
+                ..observation.clone()
+            });
+
+        direct
+            .chain(inverse)
+            .max_by(|a, b| a.price.total_cmp(&b.price))
+    }
+}
@@ -0,0 +1,44 @@
+use {
+    base64::{engine::general_purpose::STANDARD, Engine},
+    carbon_core::transaction::TransactionMetadata,
+    solana_pubkey::Pubkey,
+};
+
+/// Payloads of the `Program data:` logs `program` emitted while running
+/// under the top-level instruction at `top_level_index`, in order.
+///
+/// Programs that `emit!` their events rather than self-CPI them are only
+/// visible in the logs. Logs are attributed to the innermost program running
+/// when they were written, so data logged by programs `program` invokes is
+/// not returned. Nothing is returned once the logs were truncated.
+pub fn program_data(transaction: &TransactionMetadata, program: &Pubkey, top_level_index: usize) -> Vec<Vec<u8>> {
+    let Some(logs) = transaction.meta.log_messages.as_ref() else {
+        return Vec::new();
+    };
+    let program = program.to_string();
+    let mut data = Vec::new();
+    let mut top_level = None::<usize>;
+    let mut frames: Vec<&str> = Vec::new();
+    for log in logs {
+        if log == "Log truncated" {
+            return Vec::new();
+        }
+        let Some(log) = log.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(payload) = log.strip_prefix("data: ") {
+            if top_level == Some(top_level_index) && frames.last() == Some(&program.as_str()) {
+                data.extend(STANDARD.decode(payload).ok());
+            }
+        } else if let Some((id, depth)) = log.split_once(" invoke [") {
+            if depth == "1]" {
+                top_level = Some(top_level.map_or(0, |index| index + 1));
+                frames.clear();
+            }
+            frames.push(id);
+        } else if log.ends_with(" success") || log.contains(" failed: ") {
+            frames.pop();
+        }
+    }
+    data
+}
@@ -0,0 +1,318 @@
+use {
+    super::participation::HyperLogLog,
+    crate::publishers::{DexEventData, DexEventPayload, Publisher, UnifiedPublisher},
+    carbon_dex_events_client::payload::SwapPayload,
+    serde_json::{json, Map, Value},
+    solana_pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashMap, VecDeque},
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime},
+    },
+};
+
+const DEFAULT_TOPIC: &str = "dex_stats";
+const DEFAULT_PUBLISH_INTERVAL_SECS: u64 = 60;
+const DEFAULT_MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Each window is covered by `buckets` buckets of `bucket_secs`, so it
+/// slides by one bucket at a time.
+struct Window {
+    name: &'static str,
+    bucket_secs: i64,
+    buckets: usize,
+}
+
+const WINDOWS: [Window; 4] = [
+    Window { name: "1m", bucket_secs: 10, buckets: 6 },
+    Window { name: "5m", bucket_secs: 60, buckets: 5 },
+    Window { name: "1h", bucket_secs: 300, buckets: 12 },
+    Window { name: "24h", bucket_secs: 3_600, buckets: 24 },
+];
+
+/// What the statistics are grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Scope {
+    Pool,
+    /// The base mint of the swap, or the token of a bonding curve trade.
+    Mint,
+}
+
+impl Scope {
+    fn name(self) -> &'static str {
+        match self {
+            Scope::Pool => "pool",
+            Scope::Mint => "mint",
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Bucket {
+    start: i64,
+    trades: u64,
+    /// Raw quote amount traded per quote mint.
+    volume: BTreeMap<String, u128>,
+    volume_usd: f64,
+    traders: HyperLogLog,
+}
+
+impl Bucket {
+    fn new(start: i64) -> Self {
+        Self {
+            start,
+            trades: 0,
+            volume: BTreeMap::new(),
+            volume_usd: 0.0,
+            traders: HyperLogLog::new(),
+        }
+    }
+}
+
+/// A swap reduced to what the statistics count.
+struct Trade {
+    at: i64,
+    quote: Option<(String, u64)>,
+    volume_usd: Option<f64>,
+    trader: Option<Pubkey>,
+}
+
+impl Trade {
+    fn from_event(data: &DexEventData, swap: &SwapPayload) -> Self {
+        // The quote leg is what the taker spent on a buy and received on a sell
+        let quote_amount = match swap.side.as_deref() {
+            Some("buy") => swap.amount_in,
+            Some("sell") => swap.amount_out,
+            _ => None,
+        };
+        Self {
+            at: data.block_time.unwrap_or_else(unix_now),
+            quote: swap.quote_mint.clone().zip(quote_amount),
+            volume_usd: data.details["usd_value"].as_f64(),
+            trader: data
+                .trader
+                .as_deref()
+                .or(swap.trader.as_deref())
+                .and_then(|trader| trader.parse().ok()),
+        }
+    }
+}
+
+struct KeyStats {
+    platform: String,
+    last_trade_at: i64,
+    /// Per window, the buckets oldest first.
+    buckets: [VecDeque<Bucket>; WINDOWS.len()],
+}
+
+impl KeyStats {
+    fn new(platform: &str) -> Self {
+        Self {
+            platform: platform.to_string(),
+            last_trade_at: 0,
+            buckets: Default::default(),
+        }
+    }
+
+    fn record(&mut self, trade: &Trade) {
+        self.last_trade_at = self.last_trade_at.max(trade.at);
+        for (window, buckets) in WINDOWS.iter().zip(self.buckets.iter_mut()) {
+            let start = trade.at - trade.at.rem_euclid(window.bucket_secs);
+            let bucket = match buckets.iter().position(|bucket| bucket.start == start) {
+                Some(position) => &mut buckets[position],
+                None => {
+                    buckets.push_back(Bucket::new(start));
+                    buckets.make_contiguous().sort_by_key(|bucket| bucket.start);
+                    let position = buckets.iter().position(|bucket| bucket.start == start).unwrap_or_default();
+                    &mut buckets[position]
+                }
+            };
+            bucket.trades += 1;
+            if let Some((mint, amount)) = &trade.quote {
+                *bucket.volume.entry(mint.clone()).or_default() += *amount as u128;
+            }
+            bucket.volume_usd += trade.volume_usd.unwrap_or_default();
+            if let Some(trader) = &trade.trader {
+                bucket.traders.insert(trader);
+            }
+        }
+        self.prune(self.last_trade_at);
+    }
+
+    fn prune(&mut self, now: i64) {
+        for (window, buckets) in WINDOWS.iter().zip(self.buckets.iter_mut()) {
+            let oldest = now - now.rem_euclid(window.bucket_secs) - window.bucket_secs * (window.buckets as i64 - 1);
+            while buckets.front().is_some_and(|bucket| bucket.start < oldest) {
+                buckets.pop_front();
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Value {
+        let mut windows = Map::new();
+        for (window, buckets) in WINDOWS.iter().zip(self.buckets.iter()) {
+            let mut traders = HyperLogLog::new();
+            let mut volume = BTreeMap::<&str, u128>::new();
+            for bucket in buckets {
+                traders.merge(&bucket.traders);
+                for (mint, amount) in &bucket.volume {
+                    *volume.entry(mint).or_default() += amount;
+                }
+            }
+            windows.insert(
+                window.name.to_string(),
+                json!({
+                    "trades": buckets.iter().map(|bucket| bucket.trades).sum::<u64>(),
+                    "unique_traders": if buckets.is_empty() { 0 } else { traders.estimate() },
+                    "volume_by_quote_mint": volume
+                        .into_iter()
+                        .map(|(mint, amount)| json!({ "mint": mint, "amount": amount.to_string() }))
+                        .collect::<Vec<_>>(),
+                    "volume_usd": buckets.iter().map(|bucket| bucket.volume_usd).sum::<f64>()
+                }),
+            );
+        }
+        Value::Object(windows)
+    }
+}
+
+/// Trade count, volume and unique traders per pool and per mint over 1
+/// minute, 5 minute, 1 hour and 24 hour sliding windows, computed from the
+/// published swaps and published as `stats` snapshots on a dedicated topic.
+/// Memory is bounded by the number of tracked keys, the least recently
+/// traded one is dropped when the limit is reached.
+#[derive(Clone)]
+pub struct RollingStats {
+    topic: String,
+    keys: Arc<Mutex<HashMap<(Scope, String), KeyStats>>>,
+    max_tracked_keys: usize,
+    publish_interval: Duration,
+}
+
+impl RollingStats {
+    /// Reads `ROLLING_STATS` (`true`/`1` to enable, default off),
+    /// `STATS_TOPIC` (default `dex_stats`), `STATS_INTERVAL_SECS` (default
+    /// `60`) and `STATS_MAX_KEYS` (default `10000`, pools and mints
+    /// together).
+    pub fn from_env() -> Option<Self> {
+        if !matches!(std::env::var("ROLLING_STATS").as_deref(), Ok("true") | Ok("1")) {
+            return None;
+        }
+        let interval_secs = std::env::var("STATS_INTERVAL_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_PUBLISH_INTERVAL_SECS);
+        let max_tracked_keys = std::env::var("STATS_MAX_KEYS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_TRACKED_KEYS);
+
+        Some(Self {
+            topic: std::env::var("STATS_TOPIC").unwrap_or_else(|_| DEFAULT_TOPIC.to_string()),
+            keys: Arc::default(),
+            max_tracked_keys: max_tracked_keys.max(1),
+            publish_interval: Duration::from_secs(interval_secs.max(1)),
+        })
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Counts a published event when it is a swap that went through.
+    pub fn record(&self, data: &DexEventData) {
+        let Some(DexEventPayload::Swap(swap)) = data
+            .payload
+            .as_ref()
+            .filter(|_| data.event_type == "swap" && !data.is_failed())
+        else {
+            return;
+        };
+        let trade = Trade::from_event(data, swap);
+        let keys = [
+            swap.pool.clone().map(|pool| (Scope::Pool, pool)),
+            swap.base_mint.clone().or_else(|| swap.mint.clone()).map(|mint| (Scope::Mint, mint)),
+        ];
+
+        let mut stats = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        for key in keys.into_iter().flatten() {
+            if !stats.contains_key(&key) && stats.len() >= self.max_tracked_keys {
+                if let Some(idle) = stats
+                    .iter()
+                    .min_by_key(|(_, key_stats)| key_stats.last_trade_at)
+                    .map(|(key, _)| key.clone())
+                {
+                    stats.remove(&idle);
+                }
+            }
+            stats
+                .entry(key)
+                .or_insert_with(|| KeyStats::new(&data.platform))
+                .record(&trade);
+        }
+    }
+
+    /// Publishes a `stats` event per pool and mint traded within the last 24
+    /// hours every interval, and forgets those idle for longer.
+    pub fn spawn_publisher(&self, publisher: UnifiedPublisher) -> tokio::task::JoinHandle<()> {
+        let stats = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(stats.publish_interval);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                let now = unix_now();
+                let snapshot = {
+                    let mut keys = stats.keys.lock().unwrap_or_else(|e| e.into_inner());
+                    let horizon = WINDOWS[WINDOWS.len() - 1].bucket_secs * WINDOWS[WINDOWS.len() - 1].buckets as i64;
+                    keys.retain(|_, key_stats| now - key_stats.last_trade_at < horizon);
+                    keys.iter_mut()
+                        .map(|((scope, key), key_stats)| {
+                            key_stats.prune(now);
+                            (*scope, key.clone(), key_stats.platform.clone(), key_stats.snapshot())
+                        })
+                        .collect::<Vec<_>>()
+                };
+
+                for (scope, key, platform, windows) in snapshot {
+                    let mut details = json!({
+                        "type": "RollingStats",
+                        "scope": scope.name(),
+                        "windows": windows
+                    });
+                    details[scope.name()] = json!(key);
+
+                    let data = DexEventData {
+                        event_type: "stats".to_string(),
+                        platform,
+                        signature: String::new(),
+                        timestamp: now as u64,
+                        block_time: None,
+                        slot: None,
+                        details,
+                        ingested_at: None,
+                        tx_index: None,
+                        instruction_index: None,
+                        is_inner: None,
+                        fee_payer: None,
+                        trader: None,
+                        payload: None,
+                    };
+                    if let Err(e) = publisher.publish(&stats.topic, &data).await {
+                        log::error!("Failed to publish rolling stats: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
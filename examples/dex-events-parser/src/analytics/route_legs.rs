@@ -0,0 +1,128 @@
+use {
+    carbon_core::{
+        deserialize::ArrangeAccounts,
+        instruction::{InstructionDecoder, NestedInstructions},
+    },
+    carbon_jupiter_swap_decoder::{instructions::JupiterSwapInstruction, JupiterSwapDecoder},
+    carbon_orca_whirlpool_decoder::{
+        instructions::{swap::Swap as WhirlpoolSwap, swap_v2::SwapV2 as WhirlpoolSwapV2, OrcaWhirlpoolInstruction},
+        OrcaWhirlpoolDecoder,
+    },
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+};
+
+use crate::decoders::{
+    obric::{ObricV2Decoder, ObricV2Instruction, ObricV2Swap},
+    zerofi::{ZeroFiDecoder, ZeroFiInstruction, ZeroFiSwap},
+};
+
+/// Position of the pool among the accounts of the swap instruction an
+/// aggregator invokes on each venue. Whirlpool swaps have two layouts, and
+/// Obric v2 and ZeroFi have no decoder crate; their swaps are decoded
+/// instead.
+const POOL_ACCOUNTS: [(Pubkey, usize); 12] = [
+    (carbon_raydium_amm_v4_decoder::PROGRAM_ID, 1),
+    (carbon_raydium_clmm_decoder::PROGRAM_ID, 2),
+    (carbon_raydium_cpmm_decoder::PROGRAM_ID, 3),
+    (carbon_raydium_stable_swap_decoder::PROGRAM_ID, 1),
+    (carbon_meteora_dlmm_decoder::PROGRAM_ID, 0),
+    (carbon_meteora_pools_decoder::PROGRAM_ID, 0),
+    (carbon_meteora_damm_v2_decoder::PROGRAM_ID, 1),
+    (carbon_pump_swap_decoder::PROGRAM_ID, 0),
+    (carbon_fluxbeam_decoder::PROGRAM_ID, 0),
+    (carbon_lifinity_amm_v2_decoder::PROGRAM_ID, 1),
+    (carbon_phoenix_v1_decoder::PROGRAM_ID, 2),
+    (carbon_openbook_v2_decoder::PROGRAM_ID, 2),
+];
+
+/// One hop of a Jupiter route, as executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteLeg {
+    /// Program of the venue swapped through.
+    pub amm_program: Pubkey,
+    /// `None` for venues whose swap layout is not known.
+    pub pool: Option<Pubkey>,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub input_amount: u64,
+    pub output_amount: u64,
+}
+
+impl RouteLeg {
+    pub fn json(&self) -> Value {
+        json!({
+            "amm_program": self.amm_program.to_string(),
+            "pool": self.pool.map(|pool| pool.to_string()),
+            "input_mint": self.input_mint.to_string(),
+            "output_mint": self.output_mint.to_string(),
+            "input_amount": self.input_amount,
+            "output_amount": self.output_amount
+        })
+    }
+}
+
+/// Hops of a Jupiter route instruction in execution order.
+///
+/// Jupiter emits a `SwapEvent` self-CPI after every venue swap, with the
+/// amounts that swap moved; the pool is read from the venue instruction
+/// invoked right before it.
+pub fn jupiter_route_legs(nested: &NestedInstructions) -> Vec<RouteLeg> {
+    let mut legs = Vec::new();
+    let mut venue_swap = None;
+    for nested_instruction in nested.iter() {
+        let instruction = &nested_instruction.instruction;
+        if instruction.program_id != carbon_jupiter_swap_decoder::PROGRAM_ID {
+            venue_swap = Some(instruction);
+            continue;
+        }
+        let Some(JupiterSwapInstruction::SwapEvent(event)) =
+            JupiterSwapDecoder.decode_instruction(instruction).map(|decoded| decoded.data)
+        else {
+            continue;
+        };
+        legs.push(RouteLeg {
+            amm_program: event.amm,
+            pool: venue_swap
+                .take()
+                .filter(|swap| swap.program_id == event.amm)
+                .and_then(pool_account),
+            input_mint: event.input_mint,
+            output_mint: event.output_mint,
+            input_amount: event.input_amount,
+            output_amount: event.output_amount,
+        });
+    }
+    legs
+}
+
+fn pool_account(instruction: &solana_instruction::Instruction) -> Option<Pubkey> {
+    if instruction.program_id == carbon_orca_whirlpool_decoder::PROGRAM_ID {
+        let decoded = OrcaWhirlpoolDecoder.decode_instruction(instruction)?;
+        return match decoded.data {
+            OrcaWhirlpoolInstruction::Swap(_) => WhirlpoolSwap::arrange_accounts(&decoded.accounts)
+                .map(|accounts| accounts.whirlpool),
+            OrcaWhirlpoolInstruction::SwapV2(_) => WhirlpoolSwapV2::arrange_accounts(&decoded.accounts)
+                .map(|accounts| accounts.whirlpool),
+            _ => None,
+        };
+    }
+    if instruction.program_id == crate::decoders::obric::PROGRAM_ID {
+        return match ObricV2Decoder.decode_instruction(instruction)?.data {
+            ObricV2Instruction::Swap(_) => {
+                ObricV2Swap::arrange_accounts(&instruction.accounts).map(|accounts| accounts.trading_pair)
+            }
+            _ => None,
+        };
+    }
+    if instruction.program_id == crate::decoders::zerofi::PROGRAM_ID {
+        return match ZeroFiDecoder.decode_instruction(instruction)?.data {
+            ZeroFiInstruction::Swap(_) => ZeroFiSwap::arrange_accounts(&instruction.accounts).map(|accounts| accounts.pair),
+            _ => None,
+        };
+    }
+    let (_, index) = POOL_ACCOUNTS
+        .iter()
+        .find(|(program_id, _)| *program_id == instruction.program_id)?;
+    instruction.accounts.get(*index).map(|account| account.pubkey)
+}
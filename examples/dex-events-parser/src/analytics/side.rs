@@ -0,0 +1,155 @@
+use {
+    super::{
+        balances::token_account_mint,
+        mints::{is_usd_stable, WSOL_MINT},
+        price_index::SwapFill,
+    },
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+    std::cmp::Ordering,
+};
+
+/// Direction of a trade from the taker's perspective.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The taker spent quote to receive base.
+    Buy,
+    /// The taker spent base to receive quote.
+    Sell,
+}
+
+impl Side {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        }
+    }
+}
+
+/// Normalized side and base/quote pair of a swap, comparable across venues.
+///
+/// The quote token is picked by the same rule everywhere, regardless of how
+/// the venue orders its pair: a USD stablecoin first, then SOL, then the
+/// venue's own quote token. Pairs without any of those fall back to the
+/// larger mint address, so both directions of a pair agree on the base.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeSide {
+    pub side: Side,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+}
+
+fn quote_rank(mint: &Pubkey) -> u8 {
+    if is_usd_stable(mint) {
+        2
+    } else if *mint == WSOL_MINT {
+        1
+    } else {
+        0
+    }
+}
+
+impl TradeSide {
+    /// Side of a taker sending `input_mint` and receiving `output_mint`.
+    /// `venue_quote` is the quote token of the pool or market, if known.
+    pub fn from_mints(input_mint: Pubkey, output_mint: Pubkey, venue_quote: Option<Pubkey>) -> Option<Self> {
+        if input_mint == output_mint {
+            return None;
+        }
+
+        let input_is_quote = match quote_rank(&input_mint).cmp(&quote_rank(&output_mint)) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => match venue_quote {
+                Some(quote) if quote == input_mint => true,
+                Some(quote) if quote == output_mint => false,
+                _ => input_mint > output_mint,
+            },
+        };
+
+        Some(if input_is_quote {
+            Self { side: Side::Buy, base_mint: output_mint, quote_mint: input_mint }
+        } else {
+            Self { side: Side::Sell, base_mint: input_mint, quote_mint: output_mint }
+        })
+    }
+
+    pub fn from_fill(fill: &SwapFill, venue_quote: Option<Pubkey>) -> Option<Self> {
+        Self::from_mints(fill.input_mint, fill.output_mint, venue_quote)
+    }
+
+    /// Side of a taker order on an order book market. Bids spend the market's
+    /// quote token, asks its base token.
+    pub fn from_order(side: Side, market_base: Pubkey, market_quote: Pubkey) -> Option<Self> {
+        match side {
+            Side::Buy => Self::from_mints(market_quote, market_base, Some(market_quote)),
+            Side::Sell => Self::from_mints(market_base, market_quote, Some(market_quote)),
+        }
+    }
+
+    /// Like [`TradeSide::from_order`], with the market mints read from the
+    /// taker's base and quote token accounts.
+    pub fn from_order_accounts(
+        transaction: &TransactionMetadata,
+        side: Side,
+        base_token_account: &Pubkey,
+        quote_token_account: &Pubkey,
+    ) -> Option<Self> {
+        Self::from_order(
+            side,
+            token_account_mint(transaction, base_token_account)?,
+            token_account_mint(transaction, quote_token_account)?,
+        )
+    }
+
+    /// Adds `side`, `base_mint` and `quote_mint` to swap details.
+    pub fn apply(&self, details: &mut Value) {
+        details["side"] = json!(self.side.as_str());
+        details["base_mint"] = json!(self.base_mint.to_string());
+        details["quote_mint"] = json!(self.quote_mint.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::analytics::mints::{USDC_MINT, USDT_MINT},
+    };
+
+    #[test]
+    fn stablecoins_quote_before_sol() {
+        let token = Pubkey::new_unique();
+        let buy = TradeSide::from_mints(USDC_MINT, WSOL_MINT, Some(WSOL_MINT)).unwrap();
+        assert_eq!((buy.side, buy.base_mint, buy.quote_mint), (Side::Buy, WSOL_MINT, USDC_MINT));
+
+        let sell = TradeSide::from_mints(token, WSOL_MINT, None).unwrap();
+        assert_eq!((sell.side, sell.base_mint, sell.quote_mint), (Side::Sell, token, WSOL_MINT));
+    }
+
+    #[test]
+    fn ties_fall_back_to_the_venue_quote() {
+        let venue_quote = TradeSide::from_mints(USDT_MINT, USDC_MINT, Some(USDT_MINT)).unwrap();
+        assert_eq!((venue_quote.side, venue_quote.quote_mint), (Side::Buy, USDT_MINT));
+
+        let venue_quote = TradeSide::from_mints(USDT_MINT, USDC_MINT, Some(USDC_MINT)).unwrap();
+        assert_eq!((venue_quote.side, venue_quote.quote_mint), (Side::Sell, USDC_MINT));
+    }
+
+    #[test]
+    fn both_directions_of_an_unknown_pair_agree_on_the_base() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let forward = TradeSide::from_mints(first, second, None).unwrap();
+        let backward = TradeSide::from_mints(second, first, None).unwrap();
+
+        assert_eq!(forward.base_mint, backward.base_mint);
+        assert_ne!(forward.side, backward.side);
+    }
+
+    #[test]
+    fn a_swap_to_the_same_mint_has_no_side() {
+        assert_eq!(TradeSide::from_mints(WSOL_MINT, WSOL_MINT, None), None);
+    }
+}
@@ -0,0 +1,112 @@
+use {
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+};
+
+use super::balances::{amount_received, amount_sent};
+
+/// Which side of a swap was fixed by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapMode {
+    /// Input is fixed, the quote bounds the output from below.
+    ExactIn,
+    /// Output is fixed, the quote bounds the input from above.
+    ExactOut,
+}
+
+/// Where the quoted amount of a swap comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteSource {
+    /// An aggregator quote (e.g. Jupiter `quoted_out_amount`).
+    Quoted,
+    /// The user's slippage bound (e.g. `minimum_amount_out`, `max_amount_in`).
+    Limit,
+}
+
+impl QuoteSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuoteSource::Quoted => "quoted",
+            QuoteSource::Limit => "limit",
+        }
+    }
+}
+
+/// Realized slippage in basis points of the quoted amount. Positive values
+/// mean the trader did worse than the quote: received less on an exact-in
+/// swap, or paid more on an exact-out swap.
+pub fn realized_slippage_bps(mode: SwapMode, quoted: u64, executed: u64) -> Option<i64> {
+    if quoted == 0 {
+        return None;
+    }
+
+    let shortfall = match mode {
+        SwapMode::ExactIn => quoted as i128 - executed as i128,
+        SwapMode::ExactOut => executed as i128 - quoted as i128,
+    };
+    i64::try_from(shortfall * 10_000 / quoted as i128).ok()
+}
+
+/// Computes the slippage fields of a swap event from the quoted amount and
+/// the balance delta of the user's token account on the executed side (the
+/// destination for exact-in swaps, the source for exact-out swaps).
+///
+/// Balance deltas cover the whole transaction, so transactions that route
+/// several swaps through the same token account are attributed in full to
+/// each of them.
+pub fn observed_slippage(
+    transaction: &TransactionMetadata,
+    mode: SwapMode,
+    source: QuoteSource,
+    quoted: u64,
+    user_token_account: &Pubkey,
+) -> Value {
+    let executed = match mode {
+        SwapMode::ExactIn => amount_received(transaction, user_token_account),
+        SwapMode::ExactOut => amount_sent(transaction, user_token_account),
+    };
+    let Some(executed) = executed else {
+        return Value::Null;
+    };
+
+    json!({
+        "mode": match mode {
+            SwapMode::ExactIn => "exact_in",
+            SwapMode::ExactOut => "exact_out",
+        },
+        "quote_source": source.as_str(),
+        "quoted_amount": quoted,
+        "executed_amount": executed,
+        "slippage_bps": realized_slippage_bps(mode, quoted, executed)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_in_slippage_is_the_output_shortfall() {
+        assert_eq!(realized_slippage_bps(SwapMode::ExactIn, 10_000, 9_950), Some(50));
+        assert_eq!(realized_slippage_bps(SwapMode::ExactIn, 10_000, 10_100), Some(-100));
+    }
+
+    #[test]
+    fn exact_out_slippage_is_the_input_overpaid() {
+        assert_eq!(realized_slippage_bps(SwapMode::ExactOut, 10_000, 10_025), Some(25));
+        assert_eq!(realized_slippage_bps(SwapMode::ExactOut, 10_000, 9_990), Some(-10));
+    }
+
+    #[test]
+    fn fractions_of_a_basis_point_round_toward_zero() {
+        assert_eq!(realized_slippage_bps(SwapMode::ExactIn, 30_000, 29_999), Some(0));
+        assert_eq!(realized_slippage_bps(SwapMode::ExactIn, 3_000, 2_999), Some(3));
+    }
+
+    #[test]
+    fn unusable_quotes_have_no_slippage() {
+        assert_eq!(realized_slippage_bps(SwapMode::ExactIn, 0, 100), None);
+        assert_eq!(realized_slippage_bps(SwapMode::ExactOut, 1, u64::MAX), None);
+    }
+}
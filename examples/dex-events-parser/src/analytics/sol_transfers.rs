@@ -0,0 +1,106 @@
+use {
+    carbon_core::{
+        deserialize::ArrangeAccounts,
+        instruction::{InstructionDecoder, NestedInstructions},
+    },
+    carbon_system_program_decoder::{
+        instructions::{transfer_sol::TransferSol, transfer_sol_with_seed::TransferSolWithSeed, SystemProgramInstruction},
+        SystemProgramDecoder,
+    },
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+};
+
+/// Native SOL moved by a System program transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolTransfer {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub lamports: u64,
+}
+
+impl SolTransfer {
+    /// `None` for other instructions and other programs.
+    pub fn decode(instruction: &solana_instruction::Instruction) -> Option<Self> {
+        let decoded = SystemProgramDecoder.decode_instruction(instruction)?;
+        match decoded.data {
+            SystemProgramInstruction::TransferSol(transfer) => {
+                let accounts = TransferSol::arrange_accounts(&decoded.accounts)?;
+                Some(Self { from: accounts.source, to: accounts.destination, lamports: transfer.amount })
+            }
+            SystemProgramInstruction::TransferSolWithSeed(transfer) => {
+                let accounts = TransferSolWithSeed::arrange_accounts(&decoded.accounts)?;
+                Some(Self { from: accounts.source, to: accounts.destination, lamports: transfer.amount })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// SOL transfers made by `nested`, in execution order, including those of
+/// CPIs further down the stack. Lamports a program moves out of accounts it
+/// owns, such as bonding curves paying sellers, are not transfers.
+pub fn sol_transfers(nested: &NestedInstructions) -> Vec<SolTransfer> {
+    let mut transfers = Vec::new();
+    collect(nested, &mut transfers);
+    transfers
+}
+
+fn collect(nested: &NestedInstructions, transfers: &mut Vec<SolTransfer>) {
+    for instruction in nested.iter() {
+        transfers.extend(SolTransfer::decode(&instruction.instruction));
+        collect(&instruction.inner_instructions, transfers);
+    }
+}
+
+/// Lamports a wallet paid within one instruction, split by the recipients
+/// it is known to pay, e.g. the bonding curve and the fee accounts of a
+/// launchpad trade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolLegs {
+    payer: Pubkey,
+    legs: Vec<(&'static str, u64)>,
+    /// Paid to accounts other than the named recipients.
+    other: u64,
+}
+
+impl SolLegs {
+    /// `None` when `payer` transferred no SOL.
+    pub fn attribute(transfers: &[SolTransfer], payer: &Pubkey, recipients: &[(&'static str, Pubkey)]) -> Option<Self> {
+        let paid: Vec<_> = transfers.iter().filter(|transfer| transfer.from == *payer).collect();
+        if paid.is_empty() {
+            return None;
+        }
+
+        let legs = recipients
+            .iter()
+            .map(|(name, recipient)| {
+                let lamports = paid.iter().filter(|transfer| transfer.to == *recipient).map(|transfer| transfer.lamports).sum();
+                (*name, lamports)
+            })
+            .collect();
+        let other = paid
+            .iter()
+            .filter(|transfer| recipients.iter().all(|(_, recipient)| transfer.to != *recipient))
+            .map(|transfer| transfer.lamports)
+            .sum();
+        Some(Self { payer: *payer, legs, other })
+    }
+
+    pub fn total(&self) -> u64 {
+        self.legs.iter().map(|(_, lamports)| lamports).sum::<u64>() + self.other
+    }
+
+    /// Adds the legs, in lamports, to event details as `sol_transfers`.
+    pub fn apply(&self, details: &mut Value) {
+        let mut sol_transfers = json!({
+            "payer": self.payer.to_string(),
+            "total": self.total(),
+            "other": self.other
+        });
+        for (name, lamports) in &self.legs {
+            sol_transfers[*name] = json!(lamports);
+        }
+        details["sol_transfers"] = sol_transfers;
+    }
+}
@@ -0,0 +1,61 @@
+use {
+    super::{
+        decimals::{mint_decimals, ui_amount},
+        price_index::SwapFill,
+    },
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+};
+
+/// Executed swap in the same shape for every platform: what the trader sent
+/// and received, read from the balance changes of the transaction rather
+/// than from the instruction arguments, which only carry the amount fixed by
+/// the trader and a limit on the other side. The swap instruction's own
+/// inner transfers are preferred when they cover both legs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapEvent {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub input_amount: u64,
+    pub output_amount: u64,
+    /// Pool or bonding curve, `None` for aggregator routes and Pumpfun
+    /// trades.
+    pub pool: Option<Pubkey>,
+    pub trader: Option<Pubkey>,
+}
+
+impl SwapEvent {
+    pub fn new(fill: &SwapFill, pool: Option<Pubkey>, trader: Option<Pubkey>) -> Self {
+        Self {
+            input_mint: fill.input_mint,
+            output_mint: fill.output_mint,
+            input_amount: fill.input_amount,
+            output_amount: fill.output_amount,
+            pool,
+            trader,
+        }
+    }
+
+    /// Adds the executed swap to swap details as `executed`, and its amounts
+    /// in whole tokens as `ui_amount_in` and `ui_amount_out` when the
+    /// decimals of both mints are known.
+    pub fn apply(&self, transaction: &TransactionMetadata, details: &mut Value) {
+        let input_decimals = mint_decimals(transaction, &self.input_mint);
+        let output_decimals = mint_decimals(transaction, &self.output_mint);
+        details["executed"] = json!({
+            "input_mint": self.input_mint.to_string(),
+            "output_mint": self.output_mint.to_string(),
+            "input_amount": self.input_amount,
+            "output_amount": self.output_amount,
+            "input_decimals": input_decimals,
+            "output_decimals": output_decimals,
+            "pool": self.pool.map(|pool| pool.to_string()),
+            "trader": self.trader.map(|trader| trader.to_string())
+        });
+        if let (Some(input_decimals), Some(output_decimals)) = (input_decimals, output_decimals) {
+            details["ui_amount_in"] = json!(ui_amount(self.input_amount, input_decimals));
+            details["ui_amount_out"] = json!(ui_amount(self.output_amount, output_decimals));
+        }
+    }
+}
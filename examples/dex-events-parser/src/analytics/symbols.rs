@@ -0,0 +1,166 @@
+use {
+    super::mints::{USDC_MINT, USDT_MINT, WSOL_MINT},
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+    std::{
+        collections::{HashMap, HashSet},
+        path::Path,
+        sync::Arc,
+    },
+};
+
+/// Symbols whose mints are fixed, token list entries cannot add to them.
+const PROTECTED_SYMBOLS: [(&str, Pubkey); 4] = [
+    ("SOL", WSOL_MINT),
+    ("WSOL", WSOL_MINT),
+    ("USDC", USDC_MINT),
+    ("USDT", USDT_MINT),
+];
+
+/// Known symbols and the mints allowed to use them, used to flag tokens
+/// imitating another token's symbol (e.g. a freshly launched "USDC").
+///
+/// Major tokens are always known. A Jupiter or solana-labs token list can
+/// be loaded to cover more symbols; symbols not listed anywhere are not
+/// judged.
+#[derive(Clone)]
+pub struct SymbolRegistry {
+    mints: Arc<HashMap<String, HashSet<Pubkey>>>,
+}
+
+impl Default for SymbolRegistry {
+    fn default() -> Self {
+        let mut mints: HashMap<String, HashSet<Pubkey>> = HashMap::new();
+        for (symbol, mint) in PROTECTED_SYMBOLS {
+            mints.entry(symbol.to_string()).or_default().insert(mint);
+        }
+        Self { mints: Arc::new(mints) }
+    }
+}
+
+impl SymbolRegistry {
+    /// Reads `TOKEN_LIST_FILE`, an optional token list in the Jupiter
+    /// (array of tokens) or solana-labs (`{"tokens": [...]}`) format. A list
+    /// that cannot be read is logged and only major tokens are known.
+    pub fn from_env() -> Self {
+        let mut registry = Self::default();
+        if let Ok(path) = std::env::var("TOKEN_LIST_FILE") {
+            match registry.load_token_list(Path::new(&path)) {
+                Ok(count) => log::info!("Loaded {} token list symbols from {}", count, path),
+                Err(e) => log::error!("Failed to load token list {}: {}", path, e),
+            }
+        }
+        registry
+    }
+
+    fn load_token_list(&mut self, path: &Path) -> Result<usize, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let list: Value = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+        let tokens = list
+            .as_array()
+            .or_else(|| list["tokens"].as_array())
+            .ok_or_else(|| "expected an array of tokens or a `tokens` field".to_string())?;
+
+        let mints = Arc::make_mut(&mut self.mints);
+        let mut count = 0;
+        for token in tokens {
+            let (Some(symbol), Some(Ok(mint))) = (
+                token["symbol"].as_str(),
+                token["address"].as_str().map(str::parse::<Pubkey>),
+            ) else {
+                continue;
+            };
+            let symbol = normalize(symbol);
+            if symbol.is_empty() || PROTECTED_SYMBOLS.iter().any(|(protected, _)| *protected == symbol) {
+                continue;
+            }
+            if mints.entry(symbol).or_default().insert(mint) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Adds `symbol_verified` to `details` when `symbol` is known: `true` if
+    /// `mint` may use it, otherwise `false` along with the mints that may
+    /// under `symbol_canonical_mints`. Returns whether the symbol imitates
+    /// another token.
+    pub fn annotate(&self, symbol: &str, mint: &Pubkey, details: &mut Value) -> bool {
+        let Some(canonical_mints) = self.mints.get(&normalize(symbol)) else {
+            return false;
+        };
+
+        let verified = canonical_mints.contains(mint);
+        details["symbol_verified"] = json!(verified);
+        if !verified {
+            let mut canonical_mints = canonical_mints.iter().map(|mint| mint.to_string()).collect::<Vec<_>>();
+            canonical_mints.sort();
+            details["symbol_canonical_mints"] = json!(canonical_mints);
+        }
+        !verified
+    }
+}
+
+/// Folds the usual disguises of a symbol: case, padding, a `$` prefix,
+/// invisible characters and Cyrillic/Greek look-alike letters.
+fn normalize(symbol: &str) -> String {
+    symbol
+        .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+        .trim_start_matches('$')
+        .chars()
+        .filter(|c| !matches!(c, '\u{200b}'..='\u{200f}' | '\u{2060}' | '\u{feff}'))
+        .flat_map(char::to_uppercase)
+        .map(|c| match c {
+            'А' | 'Α' => 'A',
+            'В' | 'Β' => 'B',
+            'С' | 'Ϲ' => 'C',
+            'Е' | 'Ε' => 'E',
+            'Н' | 'Η' => 'H',
+            'І' | 'Ι' => 'I',
+            'К' | 'Κ' => 'K',
+            'М' | 'Μ' => 'M',
+            'Ν' => 'N',
+            'О' | 'Ο' => 'O',
+            'Р' | 'Ρ' => 'P',
+            'Ѕ' => 'S',
+            'Т' | 'Τ' => 'T',
+            'Х' | 'Χ' => 'X',
+            'У' | 'Υ' => 'Y',
+            'Ζ' => 'Z',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_look_alike_symbols() {
+        // Cyrillic С, Greek Τ, a zero width space and a `$` prefix
+        assert_eq!(normalize(" $usd\u{0421}\0"), "USDC");
+        assert_eq!(normalize("US\u{200b}D\u{03a4}"), "USDT");
+        assert_eq!(normalize("ѕоl"), "SOL");
+    }
+
+    #[test]
+    fn flags_imitations_of_a_known_symbol() {
+        let registry = SymbolRegistry::default();
+        let mut details = json!({});
+        assert!(registry.annotate("USD\u{0421}", &Pubkey::new_unique(), &mut details));
+        assert_eq!(details["symbol_verified"], json!(false));
+        assert_eq!(details["symbol_canonical_mints"], json!([USDC_MINT.to_string()]));
+
+        let mut details = json!({});
+        assert!(!registry.annotate("usdc", &USDC_MINT, &mut details));
+        assert_eq!(details["symbol_verified"], json!(true));
+    }
+
+    #[test]
+    fn unknown_symbols_are_not_judged() {
+        let mut details = json!({});
+        assert!(!SymbolRegistry::default().annotate("BONKINU", &Pubkey::new_unique(), &mut details));
+        assert_eq!(details, json!({}));
+    }
+}
@@ -0,0 +1,43 @@
+use {
+    solana_pubkey::Pubkey,
+    std::{collections::HashSet, str::FromStr, sync::Arc},
+};
+
+/// Mints whose transfers, mints and burns are published on their own as
+/// `token_transfer` events. Empty by default, which publishes none.
+#[derive(Debug, Clone, Default)]
+pub struct TrackedMints {
+    mints: Arc<HashSet<Pubkey>>,
+}
+
+impl TrackedMints {
+    pub fn new(mints: impl IntoIterator<Item = Pubkey>) -> Self {
+        Self { mints: Arc::new(mints.into_iter().collect()) }
+    }
+
+    /// Reads `TOKEN_TRANSFER_MINTS`, a comma separated list of mints.
+    pub fn from_env() -> Result<Self, String> {
+        let Ok(value) = std::env::var("TOKEN_TRANSFER_MINTS") else {
+            return Ok(Self::default());
+        };
+        let mints = value
+            .split(',')
+            .map(str::trim)
+            .filter(|mint| !mint.is_empty())
+            .map(|mint| Pubkey::from_str(mint).map_err(|e| format!("Invalid mint {} in TOKEN_TRANSFER_MINTS: {}", mint, e)))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self::new(mints))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.mints.is_empty()
+    }
+
+    pub fn tracks(&self, mint: &Pubkey) -> bool {
+        self.mints.contains(mint)
+    }
+
+    pub fn mints(&self) -> impl Iterator<Item = &Pubkey> {
+        self.mints.iter()
+    }
+}
@@ -0,0 +1,26 @@
+use {solana_instruction::AccountMeta, solana_pubkey::Pubkey};
+
+/// Where the wallet behind an instruction sits among its accounts, per
+/// platform and instruction (the owner of the swapped token accounts, the
+/// liquidity provider or the pool creator).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraderAccount {
+    At(usize),
+    /// Raydium AMM V4 swaps take 17 or 18 accounts, the owner is always the
+    /// last one.
+    Last,
+}
+
+impl TraderAccount {
+    /// The wallet, when it signed the transaction. Accounts signed for by a
+    /// program (e.g. the authority of an aggregator routing through the
+    /// venue) are not a trader, the event of the outer instruction names
+    /// the wallet instead.
+    pub fn find(self, accounts: &[AccountMeta]) -> Option<Pubkey> {
+        let account = match self {
+            TraderAccount::At(index) => accounts.get(index),
+            TraderAccount::Last => accounts.last(),
+        }?;
+        account.is_signer.then_some(account.pubkey)
+    }
+}
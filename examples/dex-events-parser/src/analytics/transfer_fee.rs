@@ -0,0 +1,99 @@
+/// Slots per epoch on mainnet-beta, which has no warmup epochs.
+const SLOTS_PER_EPOCH: u64 = 432_000;
+/// Token-2022 pads the base mint to the size of a token account, then
+/// stores the account type and the extensions.
+const ACCOUNT_TYPE_OFFSET: usize = 165;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const EXTENSION_UNINITIALIZED: u16 = 0;
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+/// Both authorities and the withheld amount come before the two fees.
+const TRANSFER_FEES_OFFSET: usize = 32 + 32 + 8;
+const TRANSFER_FEE_LEN: usize = 8 + 8 + 2;
+
+/// Epoch of `slot`, which picks the transfer fee in force.
+pub fn epoch(slot: u64) -> u64 {
+    slot / SLOTS_PER_EPOCH
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TransferFee {
+    epoch: u64,
+    maximum_fee: u64,
+    basis_points: u16,
+}
+
+impl TransferFee {
+    fn read(data: &[u8]) -> Option<Self> {
+        Some(Self {
+            epoch: u64::from_le_bytes(data.get(0..8)?.try_into().ok()?),
+            maximum_fee: u64::from_le_bytes(data.get(8..16)?.try_into().ok()?),
+            basis_points: u16::from_le_bytes(data.get(16..18)?.try_into().ok()?),
+        })
+    }
+}
+
+/// The `TransferFeeConfig` extension of a Token-2022 mint: the fee its
+/// transfers pay, with the newer fee taking over from its epoch on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFeeConfig {
+    older: TransferFee,
+    newer: TransferFee,
+}
+
+impl TransferFeeConfig {
+    /// Reads the extension from a mint account. `None` for SPL Token mints
+    /// and Token-2022 mints without a transfer fee.
+    pub fn from_mint(account: &solana_account::Account) -> Option<Self> {
+        if account.owner != carbon_token_2022_decoder::PROGRAM_ID {
+            return None;
+        }
+        let data = &account.data;
+        if data.get(ACCOUNT_TYPE_OFFSET) != Some(&ACCOUNT_TYPE_MINT) {
+            return None;
+        }
+
+        let mut offset = ACCOUNT_TYPE_OFFSET + 1;
+        while let Some(header) = data.get(offset..offset + 4) {
+            let extension = u16::from_le_bytes([header[0], header[1]]);
+            let length = u16::from_le_bytes([header[2], header[3]]) as usize;
+            let value = data.get(offset + 4..offset + 4 + length)?;
+            match extension {
+                EXTENSION_UNINITIALIZED => return None,
+                EXTENSION_TRANSFER_FEE_CONFIG => {
+                    let fees = value.get(TRANSFER_FEES_OFFSET..)?;
+                    return Some(Self {
+                        older: TransferFee::read(fees)?,
+                        newer: TransferFee::read(fees.get(TRANSFER_FEE_LEN..)?)?,
+                    });
+                }
+                _ => offset += 4 + length,
+            }
+        }
+        None
+    }
+
+    fn in_force(&self, epoch: u64) -> &TransferFee {
+        if epoch >= self.newer.epoch {
+            &self.newer
+        } else {
+            &self.older
+        }
+    }
+
+    /// Fee withheld from a transfer of `amount` during `epoch`, rounded up
+    /// and capped like the token program does.
+    pub fn fee(&self, epoch: u64, amount: u64) -> u64 {
+        let fee = self.in_force(epoch);
+        if fee.basis_points == 0 || amount == 0 {
+            return 0;
+        }
+        let raw = (amount as u128 * fee.basis_points as u128).div_ceil(10_000);
+        raw.min(fee.maximum_fee as u128) as u64
+    }
+
+    /// Whether a fetched config may have been replaced by `epoch`: a new
+    /// fee set during epoch `e` takes effect at `e + 2`.
+    pub fn is_stale(fetched: u64, epoch: u64) -> bool {
+        epoch > fetched + 1
+    }
+}
@@ -0,0 +1,380 @@
+use {
+    super::{
+        balances::{amount_received, amount_sent, token_account_mint},
+        price_index::SwapFill,
+    },
+    carbon_core::{
+        deserialize::ArrangeAccounts,
+        instruction::{InstructionDecoder, NestedInstructions},
+        transaction::TransactionMetadata,
+    },
+    carbon_token_2022_decoder::{
+        instructions::{
+            burn::Burn as Burn2022, burn_checked::BurnChecked as BurnChecked2022, mint_to::MintTo as MintTo2022,
+            mint_to_checked::MintToChecked as MintToChecked2022, transfer::Transfer as Transfer2022,
+            transfer_checked::TransferChecked as TransferChecked2022, transfer_checked_with_fee::TransferCheckedWithFee,
+            Token2022Instruction,
+        },
+        Token2022Decoder,
+    },
+    carbon_token_program_decoder::{
+        instructions::{
+            burn::Burn, burn_checked::BurnChecked, mint_to::MintTo, mint_to_checked::MintToChecked,
+            transfer::Transfer, transfer_checked::TransferChecked, TokenProgramInstruction,
+        },
+        TokenProgramDecoder,
+    },
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+};
+
+/// SPL Token or Token-2022 transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenTransfer {
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub authority: Pubkey,
+    /// Only named by the checked variants.
+    pub mint: Option<Pubkey>,
+    pub amount: u64,
+    /// Token-2022 transfer fee withheld from `amount` at the destination,
+    /// only named by `TransferCheckedWithFee`.
+    pub fee: u64,
+    /// Made by Token-2022, whose mints may withhold a transfer fee.
+    pub token_2022: bool,
+}
+
+/// Tokens minted to or burned from `account`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenSupplyChange {
+    pub mint: Pubkey,
+    pub account: Pubkey,
+    /// Mint authority of mints, account owner or delegate of burns.
+    pub authority: Pubkey,
+    pub amount: u64,
+}
+
+/// SPL Token or Token-2022 instruction moving tokens or changing the supply
+/// of a mint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenActivity {
+    Transfer(TokenTransfer),
+    MintTo(TokenSupplyChange),
+    Burn(TokenSupplyChange),
+}
+
+impl TokenActivity {
+    /// `None` for other instructions and other programs.
+    pub fn decode(instruction: &solana_instruction::Instruction) -> Option<Self> {
+        if let Some(decoded) = TokenProgramDecoder.decode_instruction(instruction) {
+            return match decoded.data {
+                TokenProgramInstruction::Transfer(transfer) => {
+                    let accounts = Transfer::arrange_accounts(&decoded.accounts)?;
+                    Some(Self::transfer(accounts.source, accounts.destination, accounts.authority, None, transfer.amount, 0, false))
+                }
+                TokenProgramInstruction::TransferChecked(transfer) => {
+                    let accounts = TransferChecked::arrange_accounts(&decoded.accounts)?;
+                    Some(Self::transfer(
+                        accounts.source,
+                        accounts.destination,
+                        accounts.authority,
+                        Some(accounts.mint),
+                        transfer.amount,
+                        0,
+                        false,
+                    ))
+                }
+                TokenProgramInstruction::MintTo(mint_to) => {
+                    let accounts = MintTo::arrange_accounts(&decoded.accounts)?;
+                    Some(Self::MintTo(TokenSupplyChange::new(accounts.mint, accounts.account, accounts.authority, mint_to.amount)))
+                }
+                TokenProgramInstruction::MintToChecked(mint_to) => {
+                    let accounts = MintToChecked::arrange_accounts(&decoded.accounts)?;
+                    Some(Self::MintTo(TokenSupplyChange::new(accounts.mint, accounts.account, accounts.authority, mint_to.amount)))
+                }
+                TokenProgramInstruction::Burn(burn) => {
+                    let accounts = Burn::arrange_accounts(&decoded.accounts)?;
+                    Some(Self::Burn(TokenSupplyChange::new(accounts.mint, accounts.account, accounts.owner, burn.amount)))
+                }
+                TokenProgramInstruction::BurnChecked(burn) => {
+                    let accounts = BurnChecked::arrange_accounts(&decoded.accounts)?;
+                    Some(Self::Burn(TokenSupplyChange::new(accounts.mint, accounts.account, accounts.owner, burn.amount)))
+                }
+                _ => None,
+            };
+        }
+
+        let decoded = Token2022Decoder.decode_instruction(instruction)?;
+        match decoded.data {
+            Token2022Instruction::Transfer(transfer) => {
+                let accounts = Transfer2022::arrange_accounts(&decoded.accounts)?;
+                Some(Self::transfer(accounts.source, accounts.destination, accounts.authority, None, transfer.amount, 0, true))
+            }
+            Token2022Instruction::TransferChecked(transfer) => {
+                let accounts = TransferChecked2022::arrange_accounts(&decoded.accounts)?;
+                Some(Self::transfer(
+                    accounts.source,
+                    accounts.destination,
+                    accounts.authority,
+                    Some(accounts.mint),
+                    transfer.amount,
+                    0,
+                    true,
+                ))
+            }
+            Token2022Instruction::TransferCheckedWithFee(transfer) => {
+                let accounts = TransferCheckedWithFee::arrange_accounts(&decoded.accounts)?;
+                Some(Self::transfer(
+                    accounts.source,
+                    accounts.destination,
+                    accounts.authority,
+                    Some(accounts.mint),
+                    transfer.amount,
+                    transfer.fee,
+                    true,
+                ))
+            }
+            Token2022Instruction::MintTo(mint_to) => {
+                let accounts = MintTo2022::arrange_accounts(&decoded.accounts)?;
+                Some(Self::MintTo(TokenSupplyChange::new(accounts.mint, accounts.token, accounts.mint_authority, mint_to.amount)))
+            }
+            Token2022Instruction::MintToChecked(mint_to) => {
+                let accounts = MintToChecked2022::arrange_accounts(&decoded.accounts)?;
+                Some(Self::MintTo(TokenSupplyChange::new(accounts.mint, accounts.token, accounts.mint_authority, mint_to.amount)))
+            }
+            Token2022Instruction::Burn(burn) => {
+                let accounts = Burn2022::arrange_accounts(&decoded.accounts)?;
+                Some(Self::Burn(TokenSupplyChange::new(accounts.mint, accounts.account, accounts.authority, burn.amount)))
+            }
+            Token2022Instruction::BurnChecked(burn) => {
+                let accounts = BurnChecked2022::arrange_accounts(&decoded.accounts)?;
+                Some(Self::Burn(TokenSupplyChange::new(accounts.mint, accounts.account, accounts.authority, burn.amount)))
+            }
+            _ => None,
+        }
+    }
+
+    fn transfer(
+        source: Pubkey,
+        destination: Pubkey,
+        authority: Pubkey,
+        mint: Option<Pubkey>,
+        amount: u64,
+        fee: u64,
+        token_2022: bool,
+    ) -> Self {
+        Self::Transfer(TokenTransfer { source, destination, authority, mint, amount, fee, token_2022 })
+    }
+}
+
+impl TokenTransfer {
+    /// Transfer fee withheld at the destination. Token-2022 transfers other
+    /// than `TransferCheckedWithFee` do not name it, so it is read from the
+    /// balance changes when the source sent nothing else in the transaction.
+    pub fn withheld_fee(&self, transaction: &TransactionMetadata) -> u64 {
+        if self.fee > 0 || !self.token_2022 {
+            return self.fee;
+        }
+        if amount_sent(transaction, &self.source) != Some(self.amount) {
+            return 0;
+        }
+        amount_received(transaction, &self.destination)
+            .and_then(|received| self.amount.checked_sub(received))
+            .unwrap_or_default()
+    }
+}
+
+impl TokenSupplyChange {
+    fn new(mint: Pubkey, account: Pubkey, authority: Pubkey, amount: u64) -> Self {
+        Self { mint, account, authority, amount }
+    }
+}
+
+/// Transfers, mints and burns made by `nested`, in execution order,
+/// including those of CPIs further down the stack.
+pub fn token_activity(nested: &NestedInstructions) -> Vec<TokenActivity> {
+    let mut activity = Vec::new();
+    collect(nested, &mut activity);
+    activity
+}
+
+/// Token transfers made by `nested`, in execution order, including those of
+/// CPIs further down the stack.
+pub fn token_transfers(nested: &NestedInstructions) -> Vec<TokenTransfer> {
+    token_activity(nested)
+        .into_iter()
+        .filter_map(|activity| match activity {
+            TokenActivity::Transfer(transfer) => Some(transfer),
+            _ => None,
+        })
+        .collect()
+}
+
+fn collect(nested: &NestedInstructions, activity: &mut Vec<TokenActivity>) {
+    for instruction in nested.iter() {
+        match TokenActivity::decode(&instruction.instruction) {
+            // The only CPIs of a token instruction run its mint's transfer
+            // hook, whose own transfers are not part of the caller's
+            Some(token_activity) => activity.push(token_activity),
+            None => collect(&instruction.inner_instructions, activity),
+        }
+    }
+}
+
+/// Amounts a swap instruction moved out of the trader's source account and
+/// into their destination account, read from its own inner transfers. Unlike
+/// the transaction's balance changes these stay correct when the same
+/// accounts are used by several swaps in one transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapTransfers {
+    pub input_account: Pubkey,
+    pub output_account: Pubkey,
+    pub input_mint: Option<Pubkey>,
+    pub output_mint: Option<Pubkey>,
+    pub input_amount: u64,
+    /// Amount credited to the trader, net of Token-2022 transfer fees.
+    pub output_amount: u64,
+    /// Token-2022 transfer fees withheld from the output.
+    pub output_fee: u64,
+}
+
+impl SwapTransfers {
+    /// `None` when the instruction did not transfer out of `source` and
+    /// into `destination`, e.g. when one leg is native SOL.
+    pub fn find(
+        transaction: &TransactionMetadata,
+        nested: &NestedInstructions,
+        source: &Pubkey,
+        destination: &Pubkey,
+    ) -> Option<Self> {
+        let transfers = token_transfers(nested);
+        let sent: Vec<_> = transfers.iter().filter(|t| t.source == *source).collect();
+        let received: Vec<_> = transfers.iter().filter(|t| t.destination == *destination).collect();
+
+        let input_amount = sent.iter().map(|t| t.amount).sum::<u64>();
+        let output_fee = received.iter().map(|t| t.withheld_fee(transaction)).sum::<u64>();
+        let output_amount = received.iter().map(|t| t.amount).sum::<u64>().saturating_sub(output_fee);
+        if input_amount == 0 || output_amount == 0 {
+            return None;
+        }
+
+        Some(Self {
+            input_account: *source,
+            output_account: *destination,
+            input_mint: sent
+                .iter()
+                .find_map(|t| t.mint)
+                .or_else(|| token_account_mint(transaction, source)),
+            output_mint: received
+                .iter()
+                .find_map(|t| t.mint)
+                .or_else(|| token_account_mint(transaction, destination)),
+            input_amount,
+            output_amount,
+            output_fee,
+        })
+    }
+
+    pub fn fill(&self) -> Option<SwapFill> {
+        Some(SwapFill {
+            input_mint: self.input_mint?,
+            output_mint: self.output_mint?,
+            input_amount: self.input_amount,
+            output_amount: self.output_amount,
+        })
+    }
+
+    /// Adds the transferred amounts to swap details as `transfers`.
+    pub fn apply(&self, details: &mut Value) {
+        details["transfers"] = json!({
+            "input_account": self.input_account.to_string(),
+            "output_account": self.output_account.to_string(),
+            "input_mint": self.input_mint.map(|mint| mint.to_string()),
+            "output_mint": self.output_mint.map(|mint| mint.to_string()),
+            "input_amount": self.input_amount,
+            "output_amount": self.output_amount
+        });
+        if self.output_fee > 0 {
+            details["transfers"]["output_fee"] = json!(self.output_fee);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_account_decoder_client_types::token::UiTokenAmount,
+        solana_message::{legacy::Message, VersionedMessage},
+        solana_transaction_status::TransactionTokenBalance,
+    };
+
+    fn balance(account_index: u8, amount: u64) -> TransactionTokenBalance {
+        TransactionTokenBalance {
+            account_index,
+            mint: Pubkey::new_unique().to_string(),
+            ui_token_amount: UiTokenAmount {
+                ui_amount: None,
+                decimals: 6,
+                amount: amount.to_string(),
+                ui_amount_string: String::new(),
+            },
+            owner: Pubkey::new_unique().to_string(),
+            program_id: carbon_token_2022_decoder::PROGRAM_ID.to_string(),
+        }
+    }
+
+    /// A transaction where `source` went from 5000 to `5000 - sent` and
+    /// `destination` from 0 to `received`.
+    fn transaction(transfer: &TokenTransfer, sent: u64, received: u64) -> TransactionMetadata {
+        let mut transaction = TransactionMetadata {
+            message: VersionedMessage::Legacy(Message {
+                account_keys: vec![transfer.authority, transfer.source, transfer.destination],
+                ..Message::default()
+            }),
+            ..TransactionMetadata::default()
+        };
+        transaction.meta.pre_token_balances = Some(vec![balance(1, 5_000), balance(2, 0)]);
+        transaction.meta.post_token_balances = Some(vec![balance(1, 5_000 - sent), balance(2, received)]);
+        transaction
+    }
+
+    fn transfer(amount: u64, fee: u64, token_2022: bool) -> TokenTransfer {
+        TokenTransfer {
+            source: Pubkey::new_unique(),
+            destination: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            mint: None,
+            amount,
+            fee,
+            token_2022,
+        }
+    }
+
+    #[test]
+    fn a_named_fee_is_used_as_is() {
+        let transfer = transfer(1_000, 25, true);
+        assert_eq!(transfer.withheld_fee(&transaction(&transfer, 1_000, 990)), 25);
+    }
+
+    #[test]
+    fn spl_token_transfers_withhold_nothing() {
+        let transfer = transfer(1_000, 0, false);
+        assert_eq!(transfer.withheld_fee(&transaction(&transfer, 1_000, 990)), 0);
+    }
+
+    #[test]
+    fn an_unnamed_fee_is_read_from_the_balance_changes() {
+        let transfer = transfer(1_000, 0, true);
+        assert_eq!(transfer.withheld_fee(&transaction(&transfer, 1_000, 990)), 10);
+        assert_eq!(transfer.withheld_fee(&transaction(&transfer, 1_000, 1_000)), 0);
+    }
+
+    #[test]
+    fn no_fee_is_inferred_when_the_source_sent_more() {
+        // The source also paid for something else, so the balances do not
+        // isolate this transfer
+        let transfer = transfer(1_000, 0, true);
+        assert_eq!(transfer.withheld_fee(&transaction(&transfer, 1_500, 990)), 0);
+    }
+}
@@ -0,0 +1,153 @@
+use {
+    super::{
+        decimals::{mint_decimals, ui_amount},
+        mints::{is_usd_stable, USDC_MINT, USDT_MINT, WSOL_MINT},
+        swap_event::SwapEvent,
+    },
+    crate::{
+        datasources::pool_accounts::PoolAccountSubscription,
+        decoders::oracle::OraclePrice,
+    },
+    carbon_core::transaction::TransactionMetadata,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+const DEFAULT_MAX_STALENESS_SECS: i64 = 60;
+
+/// USD value of swaps from Pyth or Switchboard prices of their quote asset
+/// (SOL, USDC or USDT).
+///
+/// Prices are cached from account updates of the configured feeds and used
+/// only while they are at most the staleness threshold older than the block
+/// of the swap, so replays and lagging datasources are not valued with
+/// live prices.
+#[derive(Clone)]
+pub struct UsdPrices {
+    /// Feed account to the mint it prices.
+    feeds: Arc<HashMap<Pubkey, Pubkey>>,
+    prices: Arc<Mutex<HashMap<Pubkey, OraclePrice>>>,
+    max_staleness_secs: i64,
+}
+
+impl Default for UsdPrices {
+    fn default() -> Self {
+        Self {
+            feeds: Arc::default(),
+            prices: Arc::default(),
+            max_staleness_secs: DEFAULT_MAX_STALENESS_SECS,
+        }
+    }
+}
+
+impl UsdPrices {
+    /// Reads:
+    /// - `USD_PRICE_FEEDS`: comma separated `<asset>:<feed account>`, with
+    ///   `SOL`, `USDC` or `USDT` as the asset, e.g.
+    ///   `SOL:7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE`. Pyth and
+    ///   Switchboard feeds are told apart by their owner. Disabled when
+    ///   unset.
+    /// - `USD_PRICE_MAX_STALENESS_SECS` (default `60`)
+    pub fn from_env() -> Result<Self, String> {
+        let mut feeds = HashMap::new();
+        for entry in std::env::var("USD_PRICE_FEEDS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let Some((asset, account)) = entry.split_once(':') else {
+                return Err(format!("Invalid USD_PRICE_FEEDS entry {}, expected <asset>:<feed account>", entry));
+            };
+            let mint = match asset.trim().to_ascii_uppercase().as_str() {
+                "SOL" => WSOL_MINT,
+                "USDC" => USDC_MINT,
+                "USDT" => USDT_MINT,
+                other => return Err(format!("Unsupported USD_PRICE_FEEDS asset {}, expected SOL, USDC or USDT", other)),
+            };
+            let account = Pubkey::from_str(account.trim())
+                .map_err(|e| format!("Invalid USD_PRICE_FEEDS account {}: {}", account, e))?;
+            feeds.insert(account, mint);
+        }
+        let max_staleness_secs = match std::env::var("USD_PRICE_MAX_STALENESS_SECS") {
+            Ok(value) => value
+                .parse()
+                .map_err(|e| format!("Invalid USD_PRICE_MAX_STALENESS_SECS {}: {}", value, e))?,
+            Err(_) => DEFAULT_MAX_STALENESS_SECS,
+        };
+
+        Ok(Self {
+            feeds: Arc::new(feeds),
+            prices: Arc::default(),
+            max_staleness_secs,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.feeds.is_empty()
+    }
+
+    pub fn subscriptions(&self) -> Vec<PoolAccountSubscription> {
+        self.feeds.keys().copied().map(PoolAccountSubscription::Account).collect()
+    }
+
+    /// Caches the price of `feed`, unless it is older than the cached one.
+    pub fn update(&self, feed: &Pubkey, price: OraclePrice) {
+        let Some(mint) = self.feeds.get(feed) else {
+            return;
+        };
+        let mut prices = self.prices.lock().unwrap();
+        if prices.get(mint).is_none_or(|cached| cached.publish_time <= price.publish_time) {
+            prices.insert(*mint, price);
+        }
+    }
+
+    /// Price of `mint` published at most the staleness threshold away from
+    /// `at`, in unix seconds.
+    pub fn price(&self, mint: &Pubkey, at: i64) -> Option<OraclePrice> {
+        self.prices
+            .lock()
+            .unwrap()
+            .get(mint)
+            .copied()
+            .filter(|price| (at - price.publish_time).abs() <= self.max_staleness_secs)
+    }
+
+    /// Adds `usd_value` and the oracle price it was computed from as
+    /// `usd_price` to swap details, valuing the stablecoin leg first and the
+    /// SOL leg otherwise. Nothing is added without a fresh price.
+    pub fn apply(&self, transaction: &TransactionMetadata, swap: &SwapEvent, details: &mut Value) {
+        if !self.is_enabled() {
+            return;
+        }
+        let at = transaction.block_time.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|now| now.as_secs() as i64)
+                .unwrap_or_default()
+        });
+        let mut legs = [(swap.input_mint, swap.input_amount), (swap.output_mint, swap.output_amount)];
+        legs.sort_by_key(|(mint, _)| !is_usd_stable(mint));
+
+        let Some((mint, amount, price, decimals)) = legs.into_iter().find_map(|(mint, amount)| {
+            let price = self.price(&mint, at)?;
+            Some((mint, amount, price, mint_decimals(transaction, &mint)?))
+        }) else {
+            return;
+        };
+        details["usd_value"] = json!(ui_amount(amount, decimals) * price.price);
+        details["usd_price"] = json!({
+            "mint": mint.to_string(),
+            "price": price.price,
+            "source": price.provider.as_str(),
+            "publish_time": price.publish_time,
+            "age_secs": at - price.publish_time
+        });
+    }
+}
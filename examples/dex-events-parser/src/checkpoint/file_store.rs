@@ -0,0 +1,48 @@
+use {
+    super::{CheckpointError, CheckpointStore},
+    async_trait::async_trait,
+    serde::{Deserialize, Serialize},
+    std::path::PathBuf,
+};
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct Saved {
+    pub(super) slot: u64,
+}
+
+/// Checkpoint kept in a JSON file, replaced atomically on every save.
+pub struct FileCheckpoint {
+    path: PathBuf,
+}
+
+impl FileCheckpoint {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpoint {
+    async fn load(&self) -> Result<Option<u64>, CheckpointError> {
+        let content = match tokio::fs::read_to_string(&self.path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(CheckpointError(format!("Failed to read {}: {}", self.path.display(), e))),
+        };
+        serde_json::from_str::<Saved>(&content)
+            .map(|saved| Some(saved.slot))
+            .map_err(|e| CheckpointError(format!("Unreadable checkpoint {}: {}", self.path.display(), e)))
+    }
+
+    async fn save(&self, slot: u64) -> Result<(), CheckpointError> {
+        let content = serde_json::to_string(&Saved { slot }).unwrap_or_default();
+        // A crash mid-write must not leave a truncated checkpoint behind
+        let temporary = self.path.with_extension("tmp");
+        tokio::fs::write(&temporary, content)
+            .await
+            .map_err(|e| CheckpointError(format!("Failed to write {}: {}", temporary.display(), e)))?;
+        tokio::fs::rename(&temporary, &self.path)
+            .await
+            .map_err(|e| CheckpointError(format!("Failed to replace {}: {}", self.path.display(), e)))
+    }
+}
@@ -0,0 +1,227 @@
+pub mod file_store;
+pub mod redis_store;
+#[cfg(feature = "remote-storage")]
+pub mod remote_store;
+#[cfg(feature = "checkpoint-sled")]
+pub mod sled_store;
+
+use {
+    async_trait::async_trait,
+    std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tokio_util::sync::CancellationToken,
+};
+
+const DEFAULT_INTERVAL_SECS: u64 = 5;
+
+#[derive(Debug)]
+pub struct CheckpointError(pub String);
+
+impl std::fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Checkpoint Error: {}", self.0)
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+/// Where the last processed slot is kept across restarts.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// The saved slot, `None` when nothing was saved yet.
+    async fn load(&self) -> Result<Option<u64>, CheckpointError>;
+
+    async fn save(&self, slot: u64) -> Result<(), CheckpointError>;
+}
+
+/// Persists the last slot whose block was fully handed to the pipeline, so a
+/// restarted datasource fetches the blocks it missed while down.
+#[derive(Clone, Default)]
+pub struct Checkpoint {
+    store: Option<Arc<dyn CheckpointStore>>,
+    interval: Duration,
+    max_backfill_slots: Option<u64>,
+}
+
+impl Checkpoint {
+    pub fn new(store: Arc<dyn CheckpointStore>) -> Self {
+        Self {
+            store: Some(store),
+            interval: Duration::from_secs(DEFAULT_INTERVAL_SECS),
+            max_backfill_slots: None,
+        }
+    }
+
+    /// Reads `CHECKPOINT_BACKEND` (`none`, `file`, `redis` or `sled`,
+    /// default `none`), `CHECKPOINT_INTERVAL_SECS` (default `5`) and
+    /// `CHECKPOINT_MAX_BACKFILL_SLOTS` (slots fetched at most on resume,
+    /// default unlimited), plus the settings of the chosen backend:
+    ///
+    /// - `file`: `CHECKPOINT_FILE` (default `slot-checkpoint.json`)
+    /// - `redis`: `CHECKPOINT_REDIS_URL` (default `redis://127.0.0.1/`) and
+    ///   `CHECKPOINT_REDIS_KEY` (default `dex-events:checkpoint`)
+    /// - `sled`: `CHECKPOINT_SLED_PATH` (default `checkpoint-db`), requires
+    ///   the `checkpoint-sled` feature
+    ///
+    /// `CHECKPOINT_URL` takes precedence over the backend: `file:///path`
+    /// for a file, `s3://bucket/prefix` or `gs://bucket/prefix` for object
+    /// storage, which requires the `remote-storage` feature.
+    pub async fn from_env() -> Result<Self, CheckpointError> {
+        let store: Arc<dyn CheckpointStore> = match std::env::var("CHECKPOINT_URL").ok().filter(|url| !url.is_empty()) {
+            Some(url) => Self::store_at(&url)?,
+            None => match Self::backend_from_env().await? {
+                Some(store) => store,
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let interval_secs = std::env::var("CHECKPOINT_INTERVAL_SECS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_INTERVAL_SECS);
+        let max_backfill_slots = std::env::var("CHECKPOINT_MAX_BACKFILL_SLOTS")
+            .ok()
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|e| CheckpointError(format!("Invalid CHECKPOINT_MAX_BACKFILL_SLOTS {}: {}", value, e)))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            store: Some(store),
+            interval: Duration::from_secs(interval_secs.max(1)),
+            max_backfill_slots,
+        })
+    }
+
+    /// Whether a checkpoint store is configured, with `CHECKPOINT_URL` or a
+    /// `CHECKPOINT_BACKEND` other than `none`.
+    pub fn is_configured() -> bool {
+        std::env::var("CHECKPOINT_URL").is_ok_and(|url| !url.is_empty())
+            || std::env::var("CHECKPOINT_BACKEND").is_ok_and(|backend| backend != "none")
+    }
+
+    async fn backend_from_env() -> Result<Option<Arc<dyn CheckpointStore>>, CheckpointError> {
+        let store: Arc<dyn CheckpointStore> = match std::env::var("CHECKPOINT_BACKEND").as_deref() {
+            Err(_) | Ok("none") => return Ok(None),
+            Ok("file") => {
+                let path = std::env::var("CHECKPOINT_FILE").unwrap_or_else(|_| "slot-checkpoint.json".to_string());
+                Arc::new(file_store::FileCheckpoint::new(path.into()))
+            }
+            Ok("redis") => {
+                let url = std::env::var("CHECKPOINT_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string());
+                let key = std::env::var("CHECKPOINT_REDIS_KEY").unwrap_or_else(|_| "dex-events:checkpoint".to_string());
+                Arc::new(redis_store::RedisCheckpoint::connect(&url, key).await?)
+            }
+            #[cfg(feature = "checkpoint-sled")]
+            Ok("sled") => {
+                let path = std::env::var("CHECKPOINT_SLED_PATH").unwrap_or_else(|_| "checkpoint-db".to_string());
+                Arc::new(sled_store::SledCheckpoint::open(&path)?)
+            }
+            #[cfg(not(feature = "checkpoint-sled"))]
+            Ok("sled") => {
+                return Err(CheckpointError(
+                    "CHECKPOINT_BACKEND=sled requires the checkpoint-sled feature".to_string(),
+                ))
+            }
+            Ok(other) => return Err(CheckpointError(format!("Unknown CHECKPOINT_BACKEND {}", other))),
+        };
+        Ok(Some(store))
+    }
+
+    fn store_at(url: &str) -> Result<Arc<dyn CheckpointStore>, CheckpointError> {
+        match url.split_once("://") {
+            Some(("file", path)) => Ok(Arc::new(file_store::FileCheckpoint::new(path.into()))),
+            #[cfg(feature = "remote-storage")]
+            Some(("s3" | "gs", _)) => {
+                let location = crate::remote_storage::Location::parse(url).map_err(CheckpointError)?;
+                Ok(Arc::new(remote_store::RemoteCheckpoint::new(location)))
+            }
+            #[cfg(not(feature = "remote-storage"))]
+            Some(("s3" | "gs", _)) => Err(CheckpointError(
+                "CHECKPOINT_URL=s3:// or gs:// requires the remote-storage feature".to_string(),
+            )),
+            _ => Err(CheckpointError(format!(
+                "Invalid CHECKPOINT_URL {}, expected file://, s3:// or gs://",
+                url
+            ))),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Slot to resume from, given the current tip: the saved slot itself, as
+    /// its block may not have been processed entirely, capped to
+    /// `CHECKPOINT_MAX_BACKFILL_SLOTS` behind the tip.
+    pub async fn resume_from(&self, tip: u64) -> Option<u64> {
+        let store = self.store.as_ref()?;
+        let slot = match store.load().await {
+            Ok(slot) => slot?,
+            Err(e) => {
+                log::error!("[CHECKPOINT] Failed to load, not resuming: {}", e);
+                return None;
+            }
+        };
+
+        match self.max_backfill_slots {
+            Some(max) if tip.saturating_sub(slot) > max => {
+                log::warn!(
+                    "[CHECKPOINT] Slot {} is {} slots behind the tip, only the last {} are fetched",
+                    slot,
+                    tip - slot,
+                    max
+                );
+                Some(tip - max)
+            }
+            _ => Some(slot),
+        }
+    }
+
+    /// Saves `processed` every interval until cancelled. The value saved is
+    /// the one read an interval earlier, so blocks still queued in the
+    /// pipeline when the process dies are fetched again on resume.
+    pub fn spawn_saver(
+        &self,
+        processed: Arc<AtomicU64>,
+        cancellation_token: CancellationToken,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        let store = self.store.clone()?;
+        let interval = self.interval;
+
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut previous = 0;
+            let mut saved = 0;
+
+            loop {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if previous > saved {
+                    match store.save(previous).await {
+                        Ok(()) => saved = previous,
+                        Err(e) => log::error!("[CHECKPOINT] Failed to save slot {}: {}", previous, e),
+                    }
+                }
+                previous = processed.load(Ordering::Relaxed);
+            }
+        }))
+    }
+
+    /// Checks the store can be read.
+    pub async fn probe(&self) -> Result<(), CheckpointError> {
+        match &self.store {
+            Some(store) => store.load().await.map(|_| ()),
+            None => Ok(()),
+        }
+    }
+}
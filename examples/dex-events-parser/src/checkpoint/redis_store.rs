@@ -0,0 +1,46 @@
+use {
+    super::{CheckpointError, CheckpointStore},
+    async_trait::async_trait,
+    redis::aio::ConnectionManager,
+};
+
+/// Checkpoint kept under one Redis key, e.g. for instances without a
+/// persistent disk. Every instance needs its own key.
+pub struct RedisCheckpoint {
+    connection: ConnectionManager,
+    key: String,
+}
+
+impl RedisCheckpoint {
+    pub async fn connect(url: &str, key: String) -> Result<Self, CheckpointError> {
+        let client = redis::Client::open(url)
+            .map_err(|e| CheckpointError(format!("Invalid Redis URL {}: {}", url, e)))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(|e| CheckpointError(format!("Failed to connect to Redis: {}", e)))?;
+
+        Ok(Self { connection, key })
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for RedisCheckpoint {
+    async fn load(&self) -> Result<Option<u64>, CheckpointError> {
+        let mut connection = self.connection.clone();
+        redis::cmd("GET")
+            .arg(&self.key)
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| CheckpointError(format!("Redis GET failed: {}", e)))
+    }
+
+    async fn save(&self, slot: u64) -> Result<(), CheckpointError> {
+        let mut connection = self.connection.clone();
+        redis::cmd("SET")
+            .arg(&self.key)
+            .arg(slot)
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| CheckpointError(format!("Redis SET failed: {}", e)))
+    }
+}
@@ -0,0 +1,233 @@
+use {
+    super::{file_store::Saved, CheckpointError, CheckpointStore},
+    crate::remote_storage::Location,
+    async_trait::async_trait,
+    futures::TryStreamExt,
+    object_store::{path::Path, Error, PutMode, PutPayload, UpdateVersion},
+    tokio::sync::Mutex,
+};
+
+const POINTER: &str = "checkpoints/LATEST";
+const CHECKPOINTS: &str = "checkpoints";
+/// Pointer updates lost to other writers before a save gives up.
+const MAX_CONFLICTS: usize = 3;
+
+/// The pointer as last read or written: the checkpoint it names and the
+/// version a conditional update has to match.
+#[derive(Default)]
+struct Pointer {
+    checkpoint: Option<Path>,
+    version: Option<UpdateVersion>,
+}
+
+/// Checkpoint kept in S3-compatible or GCS object storage, for containers
+/// without a persistent disk.
+///
+/// Objects are never rewritten in place, since a bucket cannot rename one
+/// atomically: every save writes `checkpoints/<slot>-<uuid>.json`, then
+/// points `checkpoints/LATEST` at it, and deletes the checkpoint it pointed
+/// at before. The pointer is updated with a conditional write when the
+/// store supports it, so of two instances racing the one behind does not
+/// move the pointer back.
+pub struct RemoteCheckpoint {
+    location: Location,
+    pointer: Mutex<Pointer>,
+}
+
+impl RemoteCheckpoint {
+    pub fn new(location: Location) -> Self {
+        Self {
+            location,
+            pointer: Mutex::new(Pointer::default()),
+        }
+    }
+
+    async fn read_pointer(&self) -> Result<Pointer, CheckpointError> {
+        let result = match self.location.store().get(&self.location.path(POINTER)).await {
+            Ok(result) => result,
+            Err(Error::NotFound { .. }) => return Ok(Pointer::default()),
+            Err(e) => return Err(CheckpointError(format!("Failed to read {}: {}", POINTER, e))),
+        };
+        let version = UpdateVersion {
+            e_tag: result.meta.e_tag.clone(),
+            version: result.meta.version.clone(),
+        };
+        let key = result
+            .bytes()
+            .await
+            .map_err(|e| CheckpointError(format!("Failed to read {}: {}", POINTER, e)))?;
+        let key = std::str::from_utf8(&key).map_err(|e| CheckpointError(format!("Unreadable {}: {}", POINTER, e)))?;
+        Ok(Pointer {
+            checkpoint: Some(Path::from(key.trim())),
+            version: Some(version),
+        })
+    }
+
+    /// The slot saved in `path`, `None` when it was deleted meanwhile.
+    async fn read_checkpoint(&self, path: &Path) -> Result<Option<u64>, CheckpointError> {
+        let content = match self.location.store().get(path).await {
+            Ok(result) => result.bytes().await,
+            Err(Error::NotFound { .. }) => return Ok(None),
+            Err(e) => Err(e),
+        }
+        .map_err(|e| CheckpointError(format!("Failed to read {}: {}", path, e)))?;
+        serde_json::from_slice::<Saved>(&content)
+            .map(|saved| Some(saved.slot))
+            .map_err(|e| CheckpointError(format!("Unreadable checkpoint {}: {}", path, e)))
+    }
+
+    /// The newest checkpoint listed, for a pointer naming a deleted one.
+    async fn latest_listed(&self) -> Result<Option<u64>, CheckpointError> {
+        let objects = self
+            .location
+            .store()
+            .list(Some(&self.location.path(CHECKPOINTS)))
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(|e| CheckpointError(format!("Failed to list {}: {}", CHECKPOINTS, e)))?;
+        Ok(objects
+            .iter()
+            .filter_map(|object| object.location.filename()?.strip_suffix(".json"))
+            .filter_map(|name| name.split_once('-')?.0.parse::<u64>().ok())
+            .max())
+    }
+
+    /// Points `LATEST` at `checkpoint`, unless another instance moved it to
+    /// a later slot. Stores without conditional writes overwrite it.
+    /// Returns the checkpoint no longer pointed at: the replaced one, or
+    /// `checkpoint` itself when another instance is ahead.
+    async fn update_pointer(
+        &self,
+        pointer: &mut Pointer,
+        checkpoint: &Path,
+        slot: u64,
+    ) -> Result<Option<Path>, CheckpointError> {
+        let path = self.location.path(POINTER);
+        for _ in 0..MAX_CONFLICTS {
+            let mode = match &pointer.version {
+                Some(version) => PutMode::Update(version.clone()),
+                None => PutMode::Create,
+            };
+            let payload = PutPayload::from(checkpoint.to_string());
+            let result = match self.location.store().put_opts(&path, payload.clone(), mode.into()).await {
+                Err(Error::NotImplemented) => self.location.store().put(&path, payload).await,
+                result => result,
+            };
+            match result {
+                Ok(result) => {
+                    let replaced = pointer.checkpoint.replace(checkpoint.clone());
+                    pointer.version = Some(UpdateVersion {
+                        e_tag: result.e_tag,
+                        version: result.version,
+                    });
+                    return Ok(replaced.filter(|replaced| replaced != checkpoint));
+                }
+                Err(Error::Precondition { .. } | Error::AlreadyExists { .. }) => {
+                    *pointer = self.read_pointer().await?;
+                    let current = match &pointer.checkpoint {
+                        Some(current) => self.read_checkpoint(current).await?,
+                        None => None,
+                    };
+                    if current.is_some_and(|current| current >= slot) {
+                        log::warn!("[CHECKPOINT] Another instance saved slot {:?}, keeping it", current);
+                        return Ok(Some(checkpoint.clone()));
+                    }
+                }
+                Err(e) => return Err(CheckpointError(format!("Failed to write {}: {}", POINTER, e))),
+            }
+        }
+        Err(CheckpointError(format!("{} kept changing, slot {} not saved", POINTER, slot)))
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for RemoteCheckpoint {
+    async fn load(&self) -> Result<Option<u64>, CheckpointError> {
+        let mut pointer = self.pointer.lock().await;
+        *pointer = self.read_pointer().await?;
+        if let Some(checkpoint) = &pointer.checkpoint {
+            if let Some(slot) = self.read_checkpoint(checkpoint).await? {
+                return Ok(Some(slot));
+            }
+            log::warn!("[CHECKPOINT] {} names the deleted {}, using the latest listed", POINTER, checkpoint);
+        }
+        self.latest_listed().await
+    }
+
+    async fn save(&self, slot: u64) -> Result<(), CheckpointError> {
+        let content = serde_json::to_string(&Saved { slot }).unwrap_or_default();
+        let checkpoint = self
+            .location
+            .path(&format!("{}/{:020}-{}.json", CHECKPOINTS, slot, uuid::Uuid::now_v7()));
+        // A unique name, so a plain write never replaces another checkpoint
+        self.location
+            .store()
+            .put(&checkpoint, content.into())
+            .await
+            .map_err(|e| CheckpointError(format!("Failed to write {}: {}", checkpoint, e)))?;
+
+        let mut pointer = self.pointer.lock().await;
+        if let Some(stale) = self.update_pointer(&mut pointer, &checkpoint, slot).await? {
+            if let Err(e) = self.location.store().delete(&stale).await {
+                log::warn!("[CHECKPOINT] Failed to delete {}: {}", stale, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, object_store::memory::InMemory, std::sync::Arc};
+
+    #[tokio::test]
+    async fn resumes_from_the_latest_save_and_keeps_one_checkpoint() {
+        let location = Location::new(Arc::new(InMemory::new()), "parser");
+        let checkpoint = RemoteCheckpoint::new(location.clone());
+        assert_eq!(checkpoint.load().await.unwrap(), None);
+
+        checkpoint.save(10).await.unwrap();
+        checkpoint.save(20).await.unwrap();
+        assert_eq!(RemoteCheckpoint::new(location.clone()).load().await.unwrap(), Some(20));
+        assert_eq!(checkpoint.latest_listed().await.unwrap(), Some(20));
+        let listed = location
+            .store()
+            .list(Some(&location.path(CHECKPOINTS)))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 2, "LATEST and the checkpoint of slot 20");
+    }
+
+    #[tokio::test]
+    async fn an_instance_behind_does_not_move_the_pointer_back() {
+        let location = Location::new(Arc::new(InMemory::new()), "parser");
+        let behind = RemoteCheckpoint::new(location.clone());
+        let ahead = RemoteCheckpoint::new(location.clone());
+        behind.load().await.unwrap();
+        ahead.load().await.unwrap();
+
+        ahead.save(30).await.unwrap();
+        behind.save(25).await.unwrap();
+        assert_eq!(RemoteCheckpoint::new(location.clone()).load().await.unwrap(), Some(30));
+
+        behind.save(40).await.unwrap();
+        assert_eq!(RemoteCheckpoint::new(location).load().await.unwrap(), Some(40));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_listing_when_the_pointed_checkpoint_is_gone() {
+        let location = Location::new(Arc::new(InMemory::new()), "parser");
+        let checkpoint = RemoteCheckpoint::new(location.clone());
+        checkpoint.save(50).await.unwrap();
+        let pointed = checkpoint.pointer.lock().await.checkpoint.clone().unwrap();
+        location
+            .store()
+            .put(&location.path(&format!("{}/{:020}-other.json", CHECKPOINTS, 45)), r#"{"slot":45}"#.into())
+            .await
+            .unwrap();
+        location.store().delete(&pointed).await.unwrap();
+
+        assert_eq!(checkpoint.load().await.unwrap(), Some(45));
+    }
+}
@@ -0,0 +1,47 @@
+use {
+    super::{CheckpointError, CheckpointStore},
+    async_trait::async_trait,
+};
+
+const KEY: &[u8] = b"slot";
+
+/// Checkpoint kept in an embedded sled database on local disk.
+pub struct SledCheckpoint {
+    db: sled::Db,
+}
+
+impl SledCheckpoint {
+    pub fn open(path: &str) -> Result<Self, CheckpointError> {
+        let db = sled::open(path).map_err(|e| CheckpointError(format!("Failed to open sled at {}: {}", path, e)))?;
+        Ok(Self { db })
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for SledCheckpoint {
+    async fn load(&self) -> Result<Option<u64>, CheckpointError> {
+        let value = self
+            .db
+            .get(KEY)
+            .map_err(|e| CheckpointError(format!("sled read failed: {}", e)))?;
+        value
+            .map(|bytes| {
+                <[u8; 8]>::try_from(bytes.as_ref())
+                    .map(u64::from_be_bytes)
+                    .map_err(|_| CheckpointError("Unreadable checkpoint in sled".to_string()))
+            })
+            .transpose()
+    }
+
+    async fn save(&self, slot: u64) -> Result<(), CheckpointError> {
+        self.db
+            .insert(KEY, &slot.to_be_bytes())
+            .map_err(|e| CheckpointError(format!("sled write failed: {}", e)))?;
+        // Flushed right away, the checkpoint is only written every few seconds
+        self.db
+            .flush_async()
+            .await
+            .map(|_| ())
+            .map_err(|e| CheckpointError(format!("sled flush failed: {}", e)))
+    }
+}
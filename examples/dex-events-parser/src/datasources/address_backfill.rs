@@ -0,0 +1,185 @@
+use {
+    super::{
+        conversion::{record_failure, transaction_update},
+        error::DatasourceError,
+    },
+    async_trait::async_trait,
+    carbon_core::{
+        datasource::{Datasource, DatasourceId, Update, UpdateType},
+        error::CarbonResult,
+        metrics::MetricsCollection,
+    },
+    solana_client::{
+        nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+        rpc_config::RpcTransactionConfig,
+    },
+    solana_commitment_config::CommitmentConfig,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_transaction_status::UiTransactionEncoding,
+    std::{str::FromStr, sync::Arc},
+    tokio::sync::mpsc::Sender,
+    tokio_util::sync::CancellationToken,
+};
+
+/// Signatures listed per `getSignaturesForAddress` request, the RPC maximum.
+const SIGNATURES_PAGE: usize = 1000;
+const PROGRESS_EVERY: usize = 1000;
+
+/// Which transactions of an address to backfill. Signatures are listed from
+/// newest to oldest, so `before` is where the listing starts and `until`
+/// where it stops, both exclusive.
+#[derive(Clone)]
+pub struct AddressBackfill {
+    /// A program, pool or mint account.
+    pub address: Pubkey,
+    /// Start below this transaction instead of the latest one.
+    pub before: Option<Signature>,
+    /// Stop at this transaction instead of the first one of the address.
+    pub until: Option<Signature>,
+    /// Only the latest `limit` transactions, failed ones counting only when
+    /// they are included.
+    pub limit: Option<usize>,
+}
+
+/// Lists the transactions of an address with `getSignaturesForAddress` and
+/// emits them oldest first, each fetched over HTTP RPC, then finishes, which
+/// ends the pipeline.
+///
+/// Unlike a block backfill only the transactions mentioning the address are
+/// fetched, e.g. every trade of a Pumpfun mint when the address is the mint.
+pub struct AddressBackfillDatasource {
+    rpc_http_url: String,
+    backfill: AddressBackfill,
+    include_failed: bool,
+}
+
+impl AddressBackfillDatasource {
+    pub fn new(rpc_http_url: String, backfill: AddressBackfill) -> Self {
+        Self {
+            rpc_http_url,
+            backfill,
+            include_failed: false,
+        }
+    }
+
+    /// Backfills the transactions that failed on-chain too.
+    pub fn with_failed_transactions(mut self, include_failed: bool) -> Self {
+        self.include_failed = include_failed;
+        self
+    }
+
+    /// Transactions of the address, newest first. Failed ones are left out
+    /// unless included, they have no events otherwise.
+    async fn signatures(&self, client: &RpcClient) -> Result<Vec<(Signature, u64)>, DatasourceError> {
+        let mut signatures = Vec::new();
+        let mut before = self.backfill.before;
+        loop {
+            let config = GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: self.backfill.until,
+                limit: Some(SIGNATURES_PAGE),
+                commitment: Some(CommitmentConfig::confirmed()),
+            };
+            let page = client
+                .get_signatures_for_address_with_config(&self.backfill.address, config)
+                .await
+                .map_err(|source| DatasourceError::SignatureListing {
+                    address: self.backfill.address.to_string(),
+                    source,
+                })?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Signature::from_str(&last.signature).ok();
+            let full_page = page.len() == SIGNATURES_PAGE;
+
+            signatures.extend(page.into_iter().filter(|status| self.include_failed || status.err.is_none()).filter_map(|status| {
+                Signature::from_str(&status.signature).ok().map(|signature| (signature, status.slot))
+            }));
+            if let Some(limit) = self.backfill.limit {
+                if signatures.len() >= limit {
+                    signatures.truncate(limit);
+                    break;
+                }
+            }
+            if !full_page || before.is_none() {
+                break;
+            }
+        }
+        Ok(signatures)
+    }
+}
+
+#[async_trait]
+impl Datasource for AddressBackfillDatasource {
+    async fn consume(
+        &self,
+        id: DatasourceId,
+        sender: Sender<(Update, DatasourceId)>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let client = RpcClient::new_with_commitment(self.rpc_http_url.clone(), CommitmentConfig::confirmed());
+        let signatures = self.signatures(&client).await?;
+        log::info!(
+            "[BACKFILL] Replaying {} transactions of {}",
+            signatures.len(),
+            self.backfill.address
+        );
+
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        let total = signatures.len();
+        for (done, (signature, slot)) in signatures.into_iter().rev().enumerate() {
+            if cancellation_token.is_cancelled() {
+                log::info!("[BACKFILL] Interrupted after {} of {} transactions", done, total);
+                return Ok(());
+            }
+            if done > 0 && done % PROGRESS_EVERY == 0 {
+                log::info!("[BACKFILL] {} of {} transactions, at slot {}", done, total, slot);
+            }
+
+            let transaction = match client.get_transaction_with_config(&signature, config).await {
+                Ok(transaction) => transaction,
+                Err(source) => {
+                    log::error!(
+                        "{}",
+                        DatasourceError::TransactionFetch {
+                            signature: signature.to_string(),
+                            source,
+                        }
+                    );
+                    continue;
+                }
+            };
+            // The block hash is not part of the response
+            let update = match transaction_update(transaction.slot, transaction.block_time, None, None, transaction.transaction, self.include_failed)
+            {
+                Ok(Some(update)) => update,
+                Ok(None) => continue,
+                Err(e) => {
+                    record_failure("BACKFILL", transaction.slot, &e, &metrics).await;
+                    continue;
+                }
+            };
+            if sender.send((Update::Transaction(Box::new(update)), id.clone())).await.is_err() {
+                return Ok(());
+            }
+            metrics
+                .increment_counter("address_backfill_transactions", 1)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        }
+
+        log::info!("[BACKFILL] Replayed {} transactions of {}", total, self.backfill.address);
+        Ok(())
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        vec![UpdateType::Transaction]
+    }
+}
@@ -0,0 +1,678 @@
+use {
+    super::{
+        capture_file::{CaptureReader, CaptureSource, CaptureWriter, ChunkLimits},
+        conversion::{record_failure, transaction_update},
+        error::DatasourceError,
+    },
+    async_trait::async_trait,
+    carbon_core::{
+        datasource::{
+            AccountDeletion, AccountUpdate, BlockDetails, Datasource, DatasourceId, TransactionUpdate, Update,
+            UpdateType,
+        },
+        error::CarbonResult,
+        metrics::MetricsCollection,
+        pipeline::DEFAULT_CHANNEL_BUFFER_SIZE,
+    },
+    base64::{engine::general_purpose::STANDARD, Engine},
+    serde::{Deserialize, Serialize},
+    solana_account::Account,
+    solana_hash::Hash,
+    solana_pubkey::Pubkey,
+    solana_transaction_status::{Encodable, EncodedTransactionWithStatusMeta, UiTransactionEncoding},
+    std::{
+        fmt,
+        fs::File,
+        io::{self, Write},
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::mpsc::{self, Sender},
+    tokio_util::sync::CancellationToken,
+};
+
+/// One update of a capture, the JSON payload of a record. `at_ms` is the
+/// time since the capture started, which paces the replay.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record {
+    Transaction {
+        at_ms: u64,
+        slot: u64,
+        block_time: Option<i64>,
+        block_hash: Option<String>,
+        #[serde(default)]
+        index: Option<u64>,
+        transaction: Box<EncodedTransactionWithStatusMeta>,
+    },
+    BlockDetails {
+        at_ms: u64,
+        slot: u64,
+        block_hash: Option<String>,
+        previous_block_hash: Option<String>,
+        block_time: Option<i64>,
+        block_height: Option<u64>,
+    },
+    Account {
+        at_ms: u64,
+        slot: u64,
+        pubkey: String,
+        lamports: u64,
+        owner: String,
+        executable: bool,
+        rent_epoch: u64,
+        /// Base64 account data.
+        data: String,
+    },
+    AccountDeletion {
+        at_ms: u64,
+        slot: u64,
+        pubkey: String,
+    },
+}
+
+impl Record {
+    fn new(update: &Update, at_ms: u64) -> Self {
+        match update {
+            Update::Transaction(transaction) => Record::Transaction {
+                at_ms,
+                slot: transaction.slot,
+                block_time: transaction.block_time,
+                block_hash: transaction.block_hash.map(|hash| hash.to_string()),
+                index: transaction.index,
+                transaction: Box::new(encode(transaction)),
+            },
+            Update::BlockDetails(block) => Record::BlockDetails {
+                at_ms,
+                slot: block.slot,
+                block_hash: block.block_hash.map(|hash| hash.to_string()),
+                previous_block_hash: block.previous_block_hash.map(|hash| hash.to_string()),
+                block_time: block.block_time,
+                block_height: block.block_height,
+            },
+            Update::Account(update) => Record::Account {
+                at_ms,
+                slot: update.slot,
+                pubkey: update.pubkey.to_string(),
+                lamports: update.account.lamports,
+                owner: update.account.owner.to_string(),
+                executable: update.account.executable,
+                rent_epoch: update.account.rent_epoch,
+                data: STANDARD.encode(&update.account.data),
+            },
+            Update::AccountDeletion(deletion) => Record::AccountDeletion {
+                at_ms,
+                slot: deletion.slot,
+                pubkey: deletion.pubkey.to_string(),
+            },
+        }
+    }
+
+    fn at_ms(&self) -> u64 {
+        match self {
+            Record::Transaction { at_ms, .. }
+            | Record::BlockDetails { at_ms, .. }
+            | Record::Account { at_ms, .. }
+            | Record::AccountDeletion { at_ms, .. } => *at_ms,
+        }
+    }
+
+    fn slot(&self) -> u64 {
+        match self {
+            Record::Transaction { slot, .. }
+            | Record::BlockDetails { slot, .. }
+            | Record::Account { slot, .. }
+            | Record::AccountDeletion { slot, .. } => *slot,
+        }
+    }
+}
+
+/// The transaction as the RPC sends it, so replaying goes through the same
+/// conversion as a fetched block.
+fn encode(transaction: &TransactionUpdate) -> EncodedTransactionWithStatusMeta {
+    EncodedTransactionWithStatusMeta {
+        transaction: transaction.transaction.encode(UiTransactionEncoding::Base64),
+        meta: Some(transaction.meta.clone().into()),
+        version: Some(transaction.transaction.version()),
+    }
+}
+
+fn parse_hash(hash: Option<String>) -> Option<Hash> {
+    hash.and_then(|hash| Hash::from_str(&hash).ok())
+}
+
+const DEFAULT_ROTATE_SECS: u64 = 3600;
+
+/// Whether `location` names an object rather than a local file.
+fn is_remote(location: &str) -> bool {
+    location.starts_with("s3://") || location.starts_with("gs://")
+}
+
+/// Where [`TeeDatasource`] records: a local file, or a new object under an
+/// `s3://` or `gs://` prefix every `rotate`.
+#[derive(Debug, Clone)]
+pub enum CaptureTarget {
+    File(PathBuf),
+    Remote { url: String, rotate: Duration },
+}
+
+impl CaptureTarget {
+    /// Reads `CAPTURE_URL` (`file:///path`, `s3://bucket/prefix` or
+    /// `gs://bucket/prefix`), or else `CAPTURE_FILE`; `None` when neither is
+    /// set. Remote captures rotate every `CAPTURE_ROTATE_SECS` (default
+    /// `3600`) and require the `remote-storage` feature.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Some(url) = std::env::var("CAPTURE_URL").ok().filter(|url| !url.is_empty()) else {
+            return Ok(std::env::var("CAPTURE_FILE")
+                .ok()
+                .filter(|path| !path.is_empty())
+                .map(|path| CaptureTarget::File(path.into())));
+        };
+        if let Some(path) = url.strip_prefix("file://") {
+            return Ok(Some(CaptureTarget::File(path.into())));
+        }
+        if !is_remote(&url) {
+            return Err(format!("Invalid CAPTURE_URL {}, expected file://, s3:// or gs://", url));
+        }
+        if cfg!(not(feature = "remote-storage")) {
+            return Err("CAPTURE_URL=s3:// or gs:// requires the remote-storage feature".to_string());
+        }
+        let rotate_secs = match std::env::var("CAPTURE_ROTATE_SECS") {
+            Ok(value) => value
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid CAPTURE_ROTATE_SECS {}: {}", value, e))?,
+            Err(_) => DEFAULT_ROTATE_SECS,
+        };
+        Ok(Some(CaptureTarget::Remote {
+            url,
+            rotate: Duration::from_secs(rotate_secs.max(1)),
+        }))
+    }
+}
+
+impl fmt::Display for CaptureTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CaptureTarget::File(path) => write!(f, "{}", path.display()),
+            CaptureTarget::Remote { url, .. } => write!(f, "{}", url),
+        }
+    }
+}
+
+/// The capture being written.
+enum Output {
+    File(File),
+    #[cfg(feature = "remote-storage")]
+    Object(crate::remote_storage::ObjectWriter),
+}
+
+impl Output {
+    /// Makes the finished capture visible; a file already is.
+    fn complete(self) -> io::Result<()> {
+        match self {
+            Output::File(_) => Ok(()),
+            #[cfg(feature = "remote-storage")]
+            Output::Object(object) => object.complete(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::File(file) => file.write(buf),
+            #[cfg(feature = "remote-storage")]
+            Output::Object(object) => object.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::File(file) => file.flush(),
+            #[cfg(feature = "remote-storage")]
+            Output::Object(object) => object.flush(),
+        }
+    }
+}
+
+/// Writes the captures of a [`CaptureTarget`] from a blocking task: the
+/// file, or one object per rotation named after its first slot, started
+/// with the first record so that an idle datasource leaves no empty object.
+struct Recorder {
+    /// The prefix of remote captures, how often they rotate and their
+    /// chunk limits.
+    #[cfg(feature = "remote-storage")]
+    remote: Option<(crate::remote_storage::Location, Duration, ChunkLimits)>,
+    writer: Option<CaptureWriter<Output>>,
+    rotate_at: Option<Instant>,
+}
+
+impl Recorder {
+    fn open(target: &CaptureTarget, limits: ChunkLimits) -> io::Result<Self> {
+        let mut recorder = Self {
+            #[cfg(feature = "remote-storage")]
+            remote: None,
+            writer: None,
+            rotate_at: None,
+        };
+        match target {
+            CaptureTarget::File(path) => {
+                recorder.writer = Some(CaptureWriter::new(Output::File(File::create(path)?), limits)?);
+            }
+            #[cfg(feature = "remote-storage")]
+            CaptureTarget::Remote { url, rotate } => {
+                let location = crate::remote_storage::Location::parse(url).map_err(io::Error::other)?;
+                recorder.remote = Some((location, *rotate, limits));
+            }
+            #[cfg(not(feature = "remote-storage"))]
+            CaptureTarget::Remote { .. } => {
+                return Err(io::Error::other("Remote captures require the remote-storage feature"));
+            }
+        }
+        Ok(recorder)
+    }
+
+    /// Time left until the open chunk is due or the capture rotates.
+    fn due_in(&self) -> Option<Duration> {
+        let rotate_in = self.rotate_at.map(|at| at.saturating_duration_since(Instant::now()));
+        match (self.writer.as_ref().and_then(CaptureWriter::due_in), rotate_in) {
+            (Some(due), Some(rotate)) => Some(due.min(rotate)),
+            (due, rotate) => due.or(rotate),
+        }
+    }
+
+    /// Rotates the capture once due, otherwise closes the due chunk.
+    fn tick(&mut self) -> io::Result<()> {
+        if self.rotate_at.is_some_and(|at| at <= Instant::now()) {
+            return self.complete();
+        }
+        match &mut self.writer {
+            Some(writer) => writer.close_chunk(),
+            None => Ok(()),
+        }
+    }
+
+    fn append(&mut self, slot: u64, payload: &[u8]) -> io::Result<()> {
+        if self.writer.is_none() {
+            self.writer = Some(self.create(slot)?);
+        }
+        if let Some(writer) = &mut self.writer {
+            writer.append(slot, payload)?;
+        }
+        if self.rotate_at.is_some_and(|at| at <= Instant::now()) {
+            self.complete()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "remote-storage")]
+    fn create(&mut self, first_slot: u64) -> io::Result<CaptureWriter<Output>> {
+        let (location, rotate, limits) = self
+            .remote
+            .as_ref()
+            .ok_or_else(|| io::Error::other("The capture file is already finished"))?;
+        let path = location.path(&format!("captures/{}-{}.dexcap", first_slot, uuid::Uuid::now_v7()));
+        log::info!("[CAPTURE] Recording updates to {}", path);
+        let object = crate::remote_storage::ObjectWriter::create(location, &path, tokio::runtime::Handle::current())?;
+        self.rotate_at = Some(Instant::now() + *rotate);
+        CaptureWriter::new(Output::Object(object), *limits)
+    }
+
+    #[cfg(not(feature = "remote-storage"))]
+    fn create(&mut self, _first_slot: u64) -> io::Result<CaptureWriter<Output>> {
+        Err(io::Error::other("The capture file is already finished"))
+    }
+
+    /// Writes the index of the current capture and completes it.
+    fn complete(&mut self) -> io::Result<()> {
+        self.rotate_at = None;
+        match self.writer.take() {
+            Some(writer) => writer.finish()?.complete(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Opens a capture file, or an `s3://` or `gs://` object read with range
+/// requests, from a blocking task.
+fn open_capture(path: &Path) -> io::Result<CaptureReader<Box<dyn CaptureSource>>> {
+    match path.to_str().filter(|location| is_remote(location)) {
+        #[cfg(feature = "remote-storage")]
+        Some(url) => {
+            let (location, object) = crate::remote_storage::Location::parse_object(url).map_err(io::Error::other)?;
+            CaptureReader::open(Box::new(crate::remote_storage::ObjectReader::new(
+                &location,
+                object,
+                tokio::runtime::Handle::current(),
+            )))
+        }
+        #[cfg(not(feature = "remote-storage"))]
+        Some(_) => Err(io::Error::other("Replaying from s3:// or gs:// requires the remote-storage feature")),
+        None => CaptureReader::open(Box::new(File::open(path)?)),
+    }
+}
+
+/// Forwards every update of `datasource` unchanged and records it to a
+/// capture, which [`ReplayDatasource`] replays.
+///
+/// Captures are in the chunked format of `CAPTURE_FORMAT.md`: updates are
+/// JSON records, transactions encoded like the RPC does, compressed in zstd
+/// chunks of at most `CAPTURE_CHUNK_BYTES` or `CAPTURE_CHUNK_SECS`. The slot
+/// index is written when the datasource stops; a crash loses at most the
+/// open chunk of a file. An object is uploaded in 8 MiB parts and only
+/// appears once completed, so a crash loses what was recorded since it
+/// was started, at most one rotation.
+pub struct TeeDatasource<D> {
+    datasource: D,
+    target: CaptureTarget,
+    limits: ChunkLimits,
+}
+
+impl<D> TeeDatasource<D> {
+    pub fn new(datasource: D, target: CaptureTarget) -> Self {
+        Self {
+            datasource,
+            target,
+            limits: ChunkLimits::default(),
+        }
+    }
+
+    pub fn with_chunk_limits(mut self, limits: ChunkLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+#[async_trait]
+impl<D: Datasource> Datasource for TeeDatasource<D> {
+    async fn consume(
+        &self,
+        id: DatasourceId,
+        sender: Sender<(Update, DatasourceId)>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let mut recorder = Recorder::open(&self.target, self.limits).map_err(|source| DatasourceError::Capture {
+            path: self.target.to_string().into(),
+            source,
+        })?;
+        if let CaptureTarget::File(path) = &self.target {
+            log::info!("[CAPTURE] Recording updates to {}", path.display());
+        }
+
+        let (record_sender, mut record_receiver) = mpsc::channel::<Record>(DEFAULT_CHANNEL_BUFFER_SIZE);
+        let target = self.target.clone();
+        let runtime = tokio::runtime::Handle::current();
+        let writer = tokio::task::spawn_blocking(move || {
+            let result = (|| {
+                loop {
+                    // Closes the open chunk or rotates once due, even while
+                    // no update comes
+                    let record = match recorder.due_in() {
+                        Some(due) => match runtime.block_on(tokio::time::timeout(due, record_receiver.recv())) {
+                            Ok(record) => record,
+                            Err(_) => {
+                                recorder.tick()?;
+                                continue;
+                            }
+                        },
+                        None => record_receiver.blocking_recv(),
+                    };
+                    let Some(record) = record else {
+                        break;
+                    };
+                    recorder.append(record.slot(), &serde_json::to_vec(&record)?)?;
+                }
+                recorder.complete()
+            })();
+            if let Err(e) = result {
+                log::error!("[CAPTURE] Failed to write {}, recording stopped: {}", target, e);
+            }
+        });
+
+        let (inner_sender, mut receiver) = mpsc::channel(DEFAULT_CHANNEL_BUFFER_SIZE);
+        let child_token = cancellation_token.child_token();
+        let source = self
+            .datasource
+            .consume(id, inner_sender, child_token.clone(), metrics.clone());
+
+        let forward = async {
+            let started = Instant::now();
+            while let Some((update, update_id)) = receiver.recv().await {
+                let record = Record::new(&update, started.elapsed().as_millis() as u64);
+                // A failed writer only stops the recording
+                if record_sender.send(record).await.is_ok() {
+                    metrics
+                        .increment_counter("capture_updates_recorded", 1)
+                        .await
+                        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                }
+                if sender.send((update, update_id)).await.is_err() {
+                    child_token.cancel();
+                    break;
+                }
+            }
+            drop(record_sender);
+        };
+
+        let (result, ()) = tokio::join!(source, forward);
+        if writer.await.is_err() {
+            log::error!("[CAPTURE] Writer of {} panicked", self.target);
+        }
+        result
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        self.datasource.update_types()
+    }
+}
+
+/// Replays a capture recorded by [`TeeDatasource`], a file or an `s3://`
+/// or `gs://` object, then finishes, which ends the pipeline.
+///
+/// Updates keep their recorded pacing scaled by `speed`, e.g. `2.0` replays
+/// twice as fast. With a speed of `0` they are replayed as fast as the
+/// pipeline takes them, for regression runs of processors. A slot range
+/// only decompresses the chunks that hold slots in it.
+pub struct ReplayDatasource {
+    path: PathBuf,
+    speed: f64,
+    from_slot: Option<u64>,
+    to_slot: Option<u64>,
+}
+
+impl ReplayDatasource {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            speed: 1.0,
+            from_slot: None,
+            to_slot: None,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = speed.max(0.0);
+        self
+    }
+
+    /// Replays the updates of slots `from_slot` to `to_slot` only, both
+    /// inclusive and open when `None`.
+    pub fn with_slots(mut self, from_slot: Option<u64>, to_slot: Option<u64>) -> Self {
+        self.from_slot = from_slot;
+        self.to_slot = to_slot;
+        self
+    }
+}
+
+#[async_trait]
+impl Datasource for ReplayDatasource {
+    async fn consume(
+        &self,
+        id: DatasourceId,
+        sender: Sender<(Update, DatasourceId)>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let capture_error = |source| DatasourceError::Capture {
+            path: self.path.clone(),
+            source,
+        };
+        let path = self.path.clone();
+        // Without an index the chunks are scanned, which reads the whole file
+        let mut reader = tokio::task::spawn_blocking(move || open_capture(&path))
+            .await
+            .map_err(|e| capture_error(std::io::Error::other(e)))?
+            .map_err(capture_error)?;
+        let (from_slot, to_slot) = (self.from_slot.unwrap_or(0), self.to_slot.unwrap_or(u64::MAX));
+        let chunks = reader
+            .chunks()
+            .iter()
+            .filter(|chunk| chunk.overlaps(from_slot, to_slot))
+            .copied()
+            .collect::<Vec<_>>();
+        log::info!(
+            "[REPLAY] Replaying {} at speed {}, {} of {} chunks",
+            self.path.display(),
+            self.speed,
+            chunks.len(),
+            reader.chunks().len()
+        );
+
+        let (record_sender, mut record_receiver) = mpsc::channel::<Record>(DEFAULT_CHANNEL_BUFFER_SIZE);
+        let path = self.path.clone();
+        let reader = tokio::task::spawn_blocking(move || {
+            for chunk in chunks {
+                let records = match reader.read_chunk(&chunk) {
+                    Ok(records) => records,
+                    // The capture of a process that was killed ends mid-chunk
+                    Err(e) => {
+                        log::warn!("[REPLAY] {} ends early at offset {}: {}", path.display(), chunk.offset, e);
+                        break;
+                    }
+                };
+                for (slot, payload) in records {
+                    if slot < from_slot || slot > to_slot {
+                        continue;
+                    }
+                    match serde_json::from_slice(&payload) {
+                        Ok(record) => {
+                            if record_sender.blocking_send(record).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => log::error!("[REPLAY] Skipping a record of slot {} in {}: {}", slot, path.display(), e),
+                    }
+                }
+            }
+        });
+
+        let started = tokio::time::Instant::now();
+        // Pacing starts at the first replayed update, wherever the range starts
+        let mut first_at_ms = None;
+        let mut replayed = 0;
+        while let Some(record) = record_receiver.recv().await {
+            if self.speed > 0.0 {
+                let first_at_ms = *first_at_ms.get_or_insert(record.at_ms());
+                let at = Duration::from_millis(record.at_ms().saturating_sub(first_at_ms)).div_f64(self.speed);
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => break,
+                    _ = tokio::time::sleep_until(started + at) => {}
+                }
+            } else if cancellation_token.is_cancelled() {
+                break;
+            }
+
+            let update = match record {
+                // Failed transactions were only recorded when the live
+                // datasource handed them to the pipeline
+                Record::Transaction {
+                    slot,
+                    block_time,
+                    block_hash,
+                    index,
+                    transaction,
+                    ..
+                } => match transaction_update(slot, block_time, parse_hash(block_hash), index, *transaction, true) {
+                    Ok(Some(update)) => Update::Transaction(Box::new(update)),
+                    Ok(None) => continue,
+                    Err(e) => {
+                        record_failure("REPLAY", slot, &e, &metrics).await;
+                        continue;
+                    }
+                },
+                Record::BlockDetails {
+                    slot,
+                    block_hash,
+                    previous_block_hash,
+                    block_time,
+                    block_height,
+                    ..
+                } => Update::BlockDetails(BlockDetails {
+                    slot,
+                    block_hash: parse_hash(block_hash),
+                    previous_block_hash: parse_hash(previous_block_hash),
+                    rewards: None,
+                    num_reward_partitions: None,
+                    block_time,
+                    block_height,
+                }),
+                Record::Account {
+                    slot,
+                    pubkey,
+                    lamports,
+                    owner,
+                    executable,
+                    rent_epoch,
+                    data,
+                    ..
+                } => match (Pubkey::from_str(&pubkey), Pubkey::from_str(&owner), STANDARD.decode(data)) {
+                    (Ok(pubkey), Ok(owner), Ok(data)) => Update::Account(AccountUpdate {
+                        pubkey,
+                        account: Account {
+                            lamports,
+                            data,
+                            owner,
+                            executable,
+                            rent_epoch,
+                        },
+                        slot,
+                    }),
+                    _ => {
+                        log::error!("[REPLAY] Skipping a malformed account update of slot {}", slot);
+                        continue;
+                    }
+                },
+                Record::AccountDeletion { slot, pubkey, .. } => match Pubkey::from_str(&pubkey) {
+                    Ok(pubkey) => Update::AccountDeletion(AccountDeletion { pubkey, slot }),
+                    Err(_) => {
+                        log::error!("[REPLAY] Skipping a malformed account deletion of slot {}", slot);
+                        continue;
+                    }
+                },
+            };
+            if sender.send((update, id.clone())).await.is_err() {
+                break;
+            }
+            replayed += 1;
+            metrics
+                .increment_counter("capture_updates_replayed", 1)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        }
+
+        drop(record_receiver);
+        if reader.await.is_err() {
+            log::error!("[REPLAY] Reader of {} panicked", self.path.display());
+        }
+        log::info!("[REPLAY] Replayed {} updates of {}", replayed, self.path.display());
+        Ok(())
+    }
+
+    /// Captures record every kind of update the live datasource emitted.
+    fn update_types(&self) -> Vec<UpdateType> {
+        vec![UpdateType::Transaction, UpdateType::AccountUpdate, UpdateType::AccountDeletion]
+    }
+}
@@ -0,0 +1,465 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    time::{Duration, Instant},
+};
+
+const MAGIC: &[u8; 8] = b"DEXCAP\0\0";
+const VERSION: u16 = 1;
+/// Records hold an update as JSON, transactions encoded like the RPC does.
+const RECORD_ENCODING_JSON: u32 = 1;
+const HEADER_LEN: u64 = 16;
+const CHUNK_MAGIC: &[u8; 4] = b"CHNK";
+const CHUNK_HEADER_LEN: usize = 36;
+const INDEX_ENTRY_LEN: usize = 28;
+const TRAILER_MAGIC: &[u8; 8] = b"DEXCIDX\0";
+const TRAILER_LEN: u64 = 24;
+const RECORD_HEADER_LEN: usize = 12;
+const ZSTD_LEVEL: i32 = 3;
+const DEFAULT_CHUNK_BYTES: usize = 4 << 20;
+const DEFAULT_CHUNK_SECS: u64 = 10;
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap_or_default())
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap_or_default())
+}
+
+/// Where a chunk starts and the slots it holds, an entry of the slot index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkInfo {
+    pub offset: u64,
+    pub first_slot: u64,
+    pub last_slot: u64,
+    pub record_count: u32,
+}
+
+impl ChunkInfo {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.offset.to_le_bytes());
+        out.extend_from_slice(&self.first_slot.to_le_bytes());
+        out.extend_from_slice(&self.last_slot.to_le_bytes());
+        out.extend_from_slice(&self.record_count.to_le_bytes());
+    }
+
+    fn decode(entry: &[u8]) -> Self {
+        Self {
+            offset: u64_at(entry, 0),
+            first_slot: u64_at(entry, 8),
+            last_slot: u64_at(entry, 16),
+            record_count: u32_at(entry, 24),
+        }
+    }
+
+    /// Whether some slot of the chunk is in `[from, to]`.
+    pub fn overlaps(&self, from: u64, to: u64) -> bool {
+        self.first_slot <= to && self.last_slot >= from
+    }
+}
+
+struct ChunkHeader {
+    first_slot: u64,
+    last_slot: u64,
+    record_count: u32,
+    uncompressed_len: u32,
+    compressed_len: u32,
+    crc32: u32,
+}
+
+impl ChunkHeader {
+    fn encode(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(CHUNK_HEADER_LEN);
+        header.extend_from_slice(CHUNK_MAGIC);
+        header.extend_from_slice(&self.first_slot.to_le_bytes());
+        header.extend_from_slice(&self.last_slot.to_le_bytes());
+        header.extend_from_slice(&self.record_count.to_le_bytes());
+        header.extend_from_slice(&self.uncompressed_len.to_le_bytes());
+        header.extend_from_slice(&self.compressed_len.to_le_bytes());
+        header.extend_from_slice(&self.crc32.to_le_bytes());
+        header
+    }
+
+    fn decode(header: &[u8]) -> Option<Self> {
+        if header.len() < CHUNK_HEADER_LEN || &header[0..4] != CHUNK_MAGIC {
+            return None;
+        }
+        Some(Self {
+            first_slot: u64_at(header, 4),
+            last_slot: u64_at(header, 12),
+            record_count: u32_at(header, 20),
+            uncompressed_len: u32_at(header, 24),
+            compressed_len: u32_at(header, 28),
+            crc32: u32_at(header, 32),
+        })
+    }
+}
+
+/// When the writer closes a chunk: once it holds `bytes` uncompressed, or
+/// `interval` after its first record, which bounds what a crash loses.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkLimits {
+    pub bytes: usize,
+    pub interval: Duration,
+}
+
+impl Default for ChunkLimits {
+    fn default() -> Self {
+        Self {
+            bytes: DEFAULT_CHUNK_BYTES,
+            interval: Duration::from_secs(DEFAULT_CHUNK_SECS),
+        }
+    }
+}
+
+impl ChunkLimits {
+    /// Reads `CAPTURE_CHUNK_BYTES` (default 4 MiB) and `CAPTURE_CHUNK_SECS`
+    /// (default `10`).
+    pub fn from_env() -> Result<Self, String> {
+        let mut limits = Self::default();
+        if let Ok(value) = std::env::var("CAPTURE_CHUNK_BYTES") {
+            limits.bytes = value
+                .parse::<usize>()
+                .map_err(|e| format!("Invalid CAPTURE_CHUNK_BYTES {}: {}", value, e))?
+                .max(1);
+        }
+        if let Ok(value) = std::env::var("CAPTURE_CHUNK_SECS") {
+            let secs = value
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid CAPTURE_CHUNK_SECS {}: {}", value, e))?;
+            limits.interval = Duration::from_secs(secs.max(1));
+        }
+        Ok(limits)
+    }
+}
+
+/// Writes a capture: a header, zstd compressed chunks of records, and on
+/// [`CaptureWriter::finish`] the slot index and trailer. Every chunk is
+/// written whole, so a capture cut short is readable up to its last chunk.
+pub struct CaptureWriter<W: Write> {
+    out: W,
+    limits: ChunkLimits,
+    offset: u64,
+    chunk: Vec<u8>,
+    chunk_slots: Option<(u64, u64)>,
+    chunk_records: u32,
+    chunk_opened: Instant,
+    index: Vec<ChunkInfo>,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(mut out: W, limits: ChunkLimits) -> io::Result<Self> {
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())?;
+        out.write_all(&RECORD_ENCODING_JSON.to_le_bytes())?;
+        out.flush()?;
+        Ok(Self {
+            out,
+            limits,
+            offset: HEADER_LEN,
+            chunk: Vec::new(),
+            chunk_slots: None,
+            chunk_records: 0,
+            chunk_opened: Instant::now(),
+            index: Vec::new(),
+        })
+    }
+
+    /// Adds a record to the open chunk, closing it once full.
+    pub fn append(&mut self, slot: u64, payload: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(payload.len()).map_err(|_| invalid("Record larger than 4 GiB"))?;
+        if self.chunk_records == 0 {
+            self.chunk_opened = Instant::now();
+        }
+        self.chunk.extend_from_slice(&slot.to_le_bytes());
+        self.chunk.extend_from_slice(&len.to_le_bytes());
+        self.chunk.extend_from_slice(payload);
+        self.chunk_records += 1;
+        self.chunk_slots = Some(match self.chunk_slots {
+            Some((first, last)) => (first.min(slot), last.max(slot)),
+            None => (slot, slot),
+        });
+        if self.chunk.len() >= self.limits.bytes {
+            self.close_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Time left until the open chunk is due, `None` while it is empty.
+    pub fn due_in(&self) -> Option<Duration> {
+        (self.chunk_records > 0).then(|| self.limits.interval.saturating_sub(self.chunk_opened.elapsed()))
+    }
+
+    /// Compresses and writes the open chunk, if any.
+    pub fn close_chunk(&mut self) -> io::Result<()> {
+        let Some((first_slot, last_slot)) = self.chunk_slots.take() else {
+            return Ok(());
+        };
+        let frame = zstd::bulk::compress(&self.chunk, ZSTD_LEVEL)?;
+        let header = ChunkHeader {
+            first_slot,
+            last_slot,
+            record_count: self.chunk_records,
+            uncompressed_len: self.chunk.len() as u32,
+            compressed_len: frame.len() as u32,
+            crc32: crc32fast::hash(&frame),
+        }
+        .encode();
+        self.out.write_all(&header)?;
+        self.out.write_all(&frame)?;
+        self.out.flush()?;
+
+        self.index.push(ChunkInfo {
+            offset: self.offset,
+            first_slot,
+            last_slot,
+            record_count: self.chunk_records,
+        });
+        self.offset += (header.len() + frame.len()) as u64;
+        self.chunk.clear();
+        self.chunk_records = 0;
+        Ok(())
+    }
+
+    /// Closes the open chunk and writes the slot index and trailer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.close_chunk()?;
+        let mut index = Vec::with_capacity(self.index.len() * INDEX_ENTRY_LEN);
+        for chunk in &self.index {
+            chunk.encode(&mut index);
+        }
+        self.out.write_all(&index)?;
+        self.out.write_all(&self.offset.to_le_bytes())?;
+        self.out.write_all(&(self.index.len() as u32).to_le_bytes())?;
+        self.out.write_all(&crc32fast::hash(&index).to_le_bytes())?;
+        self.out.write_all(TRAILER_MAGIC)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Random access to the bytes of a capture.
+pub trait CaptureSource: Send {
+    fn size(&mut self) -> io::Result<u64>;
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>>;
+}
+
+impl CaptureSource for File {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        self.seek(SeekFrom::Start(offset))?;
+        let mut bytes = vec![0; len];
+        self.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<S: CaptureSource + ?Sized> CaptureSource for Box<S> {
+    fn size(&mut self) -> io::Result<u64> {
+        (**self).size()
+    }
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        (**self).read_at(offset, len)
+    }
+}
+
+/// Reads a capture chunk by chunk, through its slot index when it was
+/// finished, otherwise by walking the chunk headers up to the first
+/// incomplete or corrupt chunk.
+pub struct CaptureReader<S> {
+    source: S,
+    chunks: Vec<ChunkInfo>,
+}
+
+impl<S: CaptureSource> CaptureReader<S> {
+    pub fn open(mut source: S) -> io::Result<Self> {
+        let size = source.size()?;
+        if size < HEADER_LEN {
+            return Err(invalid("Not a capture, shorter than its header"));
+        }
+        let header = source.read_at(0, HEADER_LEN as usize)?;
+        if &header[0..8] != MAGIC {
+            return Err(invalid("Not a capture, bad magic"));
+        }
+        let version = u16::from_le_bytes([header[8], header[9]]);
+        if version != VERSION {
+            return Err(invalid(format!("Unsupported capture version {}", version)));
+        }
+        let encoding = u32_at(&header, 12);
+        if encoding != RECORD_ENCODING_JSON {
+            return Err(invalid(format!("Unsupported record encoding {}", encoding)));
+        }
+
+        let chunks = match Self::read_index(&mut source, size)? {
+            Some(chunks) => chunks,
+            None => {
+                log::warn!("[REPLAY] Capture has no valid index, it was not finished; scanning its chunks");
+                Self::scan(&mut source, size)?
+            }
+        };
+        Ok(Self { source, chunks })
+    }
+
+    fn read_index(source: &mut S, size: u64) -> io::Result<Option<Vec<ChunkInfo>>> {
+        if size < HEADER_LEN + TRAILER_LEN {
+            return Ok(None);
+        }
+        let trailer = source.read_at(size - TRAILER_LEN, TRAILER_LEN as usize)?;
+        if &trailer[16..24] != TRAILER_MAGIC {
+            return Ok(None);
+        }
+        let index_offset = u64_at(&trailer, 0);
+        let index_len = u32_at(&trailer, 8) as u64 * INDEX_ENTRY_LEN as u64;
+        if index_offset < HEADER_LEN || index_offset + index_len + TRAILER_LEN != size {
+            return Ok(None);
+        }
+        let index = source.read_at(index_offset, index_len as usize)?;
+        if crc32fast::hash(&index) != u32_at(&trailer, 12) {
+            return Ok(None);
+        }
+        Ok(Some(index.chunks_exact(INDEX_ENTRY_LEN).map(ChunkInfo::decode).collect()))
+    }
+
+    fn scan(source: &mut S, size: u64) -> io::Result<Vec<ChunkInfo>> {
+        let mut chunks = Vec::new();
+        let mut offset = HEADER_LEN;
+        while offset + CHUNK_HEADER_LEN as u64 <= size {
+            let Some(header) = ChunkHeader::decode(&source.read_at(offset, CHUNK_HEADER_LEN)?) else {
+                break;
+            };
+            let end = offset + CHUNK_HEADER_LEN as u64 + header.compressed_len as u64;
+            if end > size {
+                break;
+            }
+            let frame = source.read_at(offset + CHUNK_HEADER_LEN as u64, header.compressed_len as usize)?;
+            if crc32fast::hash(&frame) != header.crc32 {
+                break;
+            }
+            chunks.push(ChunkInfo {
+                offset,
+                first_slot: header.first_slot,
+                last_slot: header.last_slot,
+                record_count: header.record_count,
+            });
+            offset = end;
+        }
+        Ok(chunks)
+    }
+
+    pub fn chunks(&self) -> &[ChunkInfo] {
+        &self.chunks
+    }
+
+    /// The `(slot, payload)` records of `chunk`, in the order received.
+    pub fn read_chunk(&mut self, chunk: &ChunkInfo) -> io::Result<Vec<(u64, Vec<u8>)>> {
+        let header = ChunkHeader::decode(&self.source.read_at(chunk.offset, CHUNK_HEADER_LEN)?)
+            .ok_or_else(|| invalid(format!("No chunk at offset {}", chunk.offset)))?;
+        let frame = self
+            .source
+            .read_at(chunk.offset + CHUNK_HEADER_LEN as u64, header.compressed_len as usize)?;
+        if crc32fast::hash(&frame) != header.crc32 {
+            return Err(invalid(format!("Corrupt chunk at offset {}", chunk.offset)));
+        }
+        let data = zstd::bulk::decompress(&frame, header.uncompressed_len as usize)?;
+
+        let mut records = Vec::with_capacity(header.record_count as usize);
+        let mut at = 0;
+        while at + RECORD_HEADER_LEN <= data.len() {
+            let slot = u64_at(&data, at);
+            let len = u32_at(&data, at + 8) as usize;
+            let payload = data
+                .get(at + RECORD_HEADER_LEN..at + RECORD_HEADER_LEN + len)
+                .ok_or_else(|| invalid(format!("Truncated record in chunk at offset {}", chunk.offset)))?;
+            records.push((slot, payload.to_vec()));
+            at += RECORD_HEADER_LEN + len;
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl CaptureSource for io::Cursor<Vec<u8>> {
+        fn size(&mut self) -> io::Result<u64> {
+            Ok(self.get_ref().len() as u64)
+        }
+
+        fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+            self.seek(SeekFrom::Start(offset))?;
+            let mut bytes = vec![0; len];
+            self.read_exact(&mut bytes)?;
+            Ok(bytes)
+        }
+    }
+
+    fn capture(finish: bool) -> Vec<u8> {
+        let limits = ChunkLimits {
+            bytes: 64,
+            interval: Duration::from_secs(60),
+        };
+        let mut writer = CaptureWriter::new(Vec::new(), limits).unwrap();
+        for slot in 100..110 {
+            writer.append(slot, format!("update of slot {}", slot).as_bytes()).unwrap();
+        }
+        if finish {
+            writer.finish().unwrap()
+        } else {
+            writer.close_chunk().unwrap();
+            writer.out
+        }
+    }
+
+    fn slots(reader: &mut CaptureReader<io::Cursor<Vec<u8>>>, from: u64, to: u64) -> Vec<u64> {
+        let chunks = reader.chunks().iter().filter(|chunk| chunk.overlaps(from, to)).copied().collect::<Vec<_>>();
+        chunks
+            .iter()
+            .flat_map(|chunk| reader.read_chunk(chunk).unwrap())
+            .map(|(slot, _)| slot)
+            .filter(|slot| (from..=to).contains(slot))
+            .collect()
+    }
+
+    #[test]
+    fn reads_a_slot_range_through_the_index() {
+        let mut reader = CaptureReader::open(io::Cursor::new(capture(true))).unwrap();
+        assert!(reader.chunks().len() > 1);
+        assert_eq!(slots(&mut reader, 103, 105), vec![103, 104, 105]);
+
+        let chunk = reader.chunks()[0];
+        let (slot, payload) = reader.read_chunk(&chunk).unwrap().remove(0);
+        assert_eq!(slot, 100);
+        assert_eq!(payload, b"update of slot 100");
+    }
+
+    #[test]
+    fn scans_an_unfinished_capture_up_to_its_last_whole_chunk() {
+        let finished = CaptureReader::open(io::Cursor::new(capture(true))).unwrap();
+        let mut unfinished = capture(false);
+        let mut reader = CaptureReader::open(io::Cursor::new(unfinished.clone())).unwrap();
+        assert_eq!(reader.chunks(), finished.chunks());
+        assert_eq!(slots(&mut reader, 0, u64::MAX), (100..110).collect::<Vec<_>>());
+
+        // Cut in the middle of the last chunk
+        unfinished.truncate(unfinished.len() - 3);
+        let reader = CaptureReader::open(io::Cursor::new(unfinished)).unwrap();
+        assert_eq!(reader.chunks(), &finished.chunks()[..finished.chunks().len() - 1]);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(CaptureReader::open(io::Cursor::new(b"not a capture file".to_vec())).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use {
+    carbon_core::{
+        datasource::TransactionUpdate,
+        metrics::MetricsCollection,
+        transformers::transaction_metadata_from_original_meta,
+    },
+    solana_client::rpc_client::SerializableTransaction,
+    solana_hash::Hash,
+    solana_message::{compiled_instruction::CompiledInstruction, VersionedMessage},
+    solana_transaction_status::EncodedTransactionWithStatusMeta,
+};
+
+/// Why a transaction from the RPC could not be turned into an update.
+#[derive(Debug)]
+pub enum ConversionError {
+    /// The node sent the transaction without its status metadata.
+    MissingMeta,
+    /// The transaction could not be decoded, e.g. a JSON encoding or a
+    /// message version newer than v0.
+    Undecodable,
+    /// The status metadata could not be converted.
+    Metadata(carbon_core::error::Error),
+    /// A v0 message loads accounts from lookup tables, but the metadata lists
+    /// no loaded addresses.
+    MissingLoadedAddresses { lookups: usize },
+    /// The metadata lists a different number of loaded addresses than the
+    /// lookup tables of the message reference.
+    PartialLoadedAddresses { lookups: usize, loaded: usize },
+    /// An instruction references an account beyond the resolved keys, which
+    /// would shift the accounts of every decoded instruction.
+    AccountIndexOutOfRange { index: u8, keys: usize },
+    /// A webhook payload holds an invalid signature, address or instruction
+    /// data.
+    InvalidPayload(String),
+}
+
+impl ConversionError {
+    /// Label of the failure in metrics.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            ConversionError::MissingMeta => "missing_meta",
+            ConversionError::Undecodable => "undecodable",
+            ConversionError::Metadata(_) => "metadata",
+            ConversionError::MissingLoadedAddresses { .. } => "missing_loaded_addresses",
+            ConversionError::PartialLoadedAddresses { .. } => "partial_loaded_addresses",
+            ConversionError::AccountIndexOutOfRange { .. } => "account_index_out_of_range",
+            ConversionError::InvalidPayload(_) => "invalid_payload",
+        }
+    }
+}
+
+impl std::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConversionError::MissingMeta => write!(f, "Transaction has no status metadata"),
+            ConversionError::Undecodable => write!(f, "Failed to decode transaction"),
+            ConversionError::Metadata(e) => write!(f, "Failed to convert transaction metadata: {}", e),
+            ConversionError::MissingLoadedAddresses { lookups } => write!(
+                f,
+                "Message loads {} lookup table addresses but the metadata has none",
+                lookups
+            ),
+            ConversionError::PartialLoadedAddresses { lookups, loaded } => write!(
+                f,
+                "Message loads {} lookup table addresses but the metadata has {}",
+                lookups, loaded
+            ),
+            ConversionError::AccountIndexOutOfRange { index, keys } => {
+                write!(f, "Instruction references account {} of {} resolved keys", index, keys)
+            }
+            ConversionError::InvalidPayload(e) => write!(f, "Invalid webhook payload: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConversionError::Metadata(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Converts a transaction of a fetched block into an update, `None` for
+/// failed transactions unless `include_failed` is set. `index` is the
+/// position of the transaction in the block, when the whole block was
+/// fetched.
+///
+/// For v0 messages the addresses loaded from lookup tables must all be in
+/// the metadata: the pipeline resolves account indexes against the static
+/// keys followed by the loaded ones, and silently drops indexes it cannot
+/// resolve.
+pub(crate) fn transaction_update(
+    slot: u64,
+    block_time: Option<i64>,
+    block_hash: Option<Hash>,
+    index: Option<u64>,
+    encoded: EncodedTransactionWithStatusMeta,
+    include_failed: bool,
+) -> Result<Option<TransactionUpdate>, ConversionError> {
+    let meta_original = encoded.meta.ok_or(ConversionError::MissingMeta)?;
+    if meta_original.status.is_err() && !include_failed {
+        return Ok(None);
+    }
+
+    let decoded_transaction = encoded.transaction.decode().ok_or(ConversionError::Undecodable)?;
+    let meta = transaction_metadata_from_original_meta(meta_original).map_err(ConversionError::Metadata)?;
+
+    let loaded = meta.loaded_addresses.writable.len() + meta.loaded_addresses.readonly.len();
+    if let VersionedMessage::V0(message) = &decoded_transaction.message {
+        let lookups = message
+            .address_table_lookups
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+            .sum::<usize>();
+        if lookups > 0 && loaded == 0 {
+            return Err(ConversionError::MissingLoadedAddresses { lookups });
+        }
+        if lookups != loaded {
+            return Err(ConversionError::PartialLoadedAddresses { lookups, loaded });
+        }
+    }
+
+    let keys = decoded_transaction.message.static_account_keys().len() + loaded;
+    let inner_instructions = meta
+        .inner_instructions
+        .iter()
+        .flatten()
+        .flat_map(|inner| inner.instructions.iter().map(|inner| &inner.instruction));
+    check_account_indexes(decoded_transaction.message.instructions().iter().chain(inner_instructions), keys)?;
+
+    Ok(Some(TransactionUpdate {
+        signature: *decoded_transaction.get_signature(),
+        transaction: decoded_transaction,
+        meta,
+        is_vote: false,
+        slot,
+        block_time,
+        block_hash,
+        index,
+    }))
+}
+
+fn check_account_indexes<'a>(
+    instructions: impl Iterator<Item = &'a CompiledInstruction>,
+    keys: usize,
+) -> Result<(), ConversionError> {
+    for instruction in instructions {
+        let out_of_range = std::iter::once(&instruction.program_id_index)
+            .chain(instruction.accounts.iter())
+            .find(|index| **index as usize >= keys);
+        if let Some(index) = out_of_range {
+            return Err(ConversionError::AccountIndexOutOfRange { index: *index, keys });
+        }
+    }
+    Ok(())
+}
+
+/// Logs a transaction that was dropped and counts it under
+/// `transaction_conversion_failures_<reason>`.
+pub(crate) async fn record_failure(datasource: &str, slot: u64, error: &ConversionError, metrics: &MetricsCollection) {
+    log::warn!("[{}] Dropped transaction in slot {}: {}", datasource, slot, error);
+    metrics
+        .increment_counter(&format!("transaction_conversion_failures_{}", error.reason()), 1)
+        .await
+        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+}
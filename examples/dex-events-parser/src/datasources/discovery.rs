@@ -0,0 +1,197 @@
+use {
+    super::watch_list::WatchList,
+    crate::decoders::invariant::{InvariantCreatePool, InvariantDecoder, InvariantInstruction},
+    carbon_core::{
+        deserialize::{ArrangeAccounts, CarbonDeserialize},
+        filter::Filter,
+        instruction::InstructionDecoder,
+    },
+    carbon_meteora_dlmm_decoder::instructions::initialize_lb_pair::InitializeLbPair,
+    carbon_meteora_pools_decoder::{instructions::MeteoraPoolsProgramInstruction, MeteoraPoolsDecoder},
+    carbon_orca_whirlpool_decoder::instructions::initialize_pool::InitializePool,
+    carbon_pump_swap_decoder::instructions::create_pool_event::CreatePoolEvent,
+    carbon_raydium_amm_v4_decoder::instructions::initialize2::Initialize2,
+    serde_json::{json, Value},
+    solana_instruction::Instruction,
+    solana_pubkey::Pubkey,
+    std::{collections::HashSet, str::FromStr},
+};
+
+/// A platform whose new pools can be discovered, with the instruction that
+/// announces them.
+pub struct DiscoverySource {
+    pub platform: &'static str,
+    pub program_id: Pubkey,
+    /// The pool an instruction creates, `None` for any other instruction.
+    pub new_pool: fn(&Instruction) -> Option<Pubkey>,
+}
+
+pub const DISCOVERY_SOURCES: &[DiscoverySource] = &[
+    DiscoverySource {
+        platform: "Raydium AMM V4",
+        program_id: carbon_raydium_amm_v4_decoder::PROGRAM_ID,
+        new_pool: |ix| {
+            Initialize2::deserialize(&ix.data)?;
+            Initialize2::arrange_accounts(&ix.accounts).map(|accounts| accounts.amm)
+        },
+    },
+    DiscoverySource {
+        platform: "Orca Whirlpool",
+        program_id: carbon_orca_whirlpool_decoder::PROGRAM_ID,
+        new_pool: |ix| {
+            InitializePool::deserialize(&ix.data)?;
+            InitializePool::arrange_accounts(&ix.accounts).map(|accounts| accounts.whirlpool)
+        },
+    },
+    DiscoverySource {
+        platform: "Meteora DLMM",
+        program_id: carbon_meteora_dlmm_decoder::PROGRAM_ID,
+        new_pool: |ix| {
+            InitializeLbPair::deserialize(&ix.data)?;
+            InitializeLbPair::arrange_accounts(&ix.accounts).map(|accounts| accounts.lb_pair)
+        },
+    },
+    DiscoverySource {
+        platform: "Meteora Pools",
+        program_id: carbon_meteora_pools_decoder::PROGRAM_ID,
+        new_pool: |ix| match MeteoraPoolsDecoder.decode_instruction(ix)?.data {
+            // Every pool creation instruction takes the pool as first account
+            MeteoraPoolsProgramInstruction::InitializePermissionlessPool(_)
+            | MeteoraPoolsProgramInstruction::InitializePermissionlessConstantProductPoolWithConfig(_)
+            | MeteoraPoolsProgramInstruction::InitializePermissionlessConstantProductPoolWithConfig2(_)
+            | MeteoraPoolsProgramInstruction::InitializeCustomizablePermissionlessConstantProductPool(_) => {
+                ix.accounts.first().map(|account| account.pubkey)
+            }
+            _ => None,
+        },
+    },
+    DiscoverySource {
+        platform: "Pump Swap",
+        program_id: carbon_pump_swap_decoder::PROGRAM_ID,
+        new_pool: |ix| CreatePoolEvent::deserialize(&ix.data).map(|event| event.pool),
+    },
+    DiscoverySource {
+        platform: "Invariant",
+        program_id: crate::decoders::invariant::PROGRAM_ID,
+        new_pool: |ix| match InvariantDecoder.decode_instruction(ix)?.data {
+            InvariantInstruction::CreatePool(_) => {
+                InvariantCreatePool::arrange_accounts(&ix.accounts).map(|accounts| accounts.pool)
+            }
+            _ => None,
+        },
+    },
+];
+
+/// Adds newly created pools matching the configured platforms and quote
+/// mints to the watch list. Disabled outside watch mode, where every pool is
+/// processed anyway.
+#[derive(Clone, Default)]
+pub struct PoolDiscovery {
+    watch_list: Option<WatchList>,
+    platforms: HashSet<&'static str>,
+    quote_mints: HashSet<Pubkey>,
+}
+
+impl PoolDiscovery {
+    /// Reads `WATCH_DISCOVERY` (default `false`), `WATCH_DISCOVERY_PLATFORMS`
+    /// (comma separated, default every supported platform) and
+    /// `WATCH_DISCOVERY_QUOTE_MINTS` (comma separated, default any mint).
+    pub fn from_env(watch_list: &WatchList) -> Self {
+        let enabled = std::env::var("WATCH_DISCOVERY")
+            .map(|value| value == "true" || value == "1")
+            .unwrap_or(false);
+        if !enabled {
+            return Self::default();
+        }
+
+        let platforms = match std::env::var("WATCH_DISCOVERY_PLATFORMS") {
+            Ok(value) => {
+                let names = value.split(',').map(str::trim).collect::<HashSet<_>>();
+                DISCOVERY_SOURCES
+                    .iter()
+                    .map(|source| source.platform)
+                    .filter(|platform| names.contains(platform))
+                    .collect()
+            }
+            Err(_) => DISCOVERY_SOURCES.iter().map(|source| source.platform).collect(),
+        };
+        let quote_mints = std::env::var("WATCH_DISCOVERY_QUOTE_MINTS")
+            .map(|value| {
+                value
+                    .split(',')
+                    .filter_map(|mint| Pubkey::from_str(mint.trim()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        log::info!("Pool discovery enabled for {:?}", platforms);
+
+        Self {
+            watch_list: Some(watch_list.clone()),
+            platforms,
+            quote_mints,
+        }
+    }
+
+    fn source(&self, program_id: &Pubkey) -> Option<&'static DiscoverySource> {
+        self.watch_list.as_ref()?;
+        DISCOVERY_SOURCES.iter().find(|source| {
+            source.program_id == *program_id && self.platforms.contains(source.platform)
+        })
+    }
+
+    /// Programs the datasource has to follow to see new pools.
+    pub fn programs(&self) -> Vec<Pubkey> {
+        DISCOVERY_SOURCES
+            .iter()
+            .filter(|source| self.source(&source.program_id).is_some())
+            .map(|source| source.program_id)
+            .collect()
+    }
+
+    /// Watch list filters for the pipe of `program_id`, letting its pool
+    /// creations through when discovery is enabled for it.
+    pub fn filters(
+        &self,
+        watch_list: &WatchList,
+        program_id: &Pubkey,
+    ) -> Vec<Box<dyn Filter + Send + Sync + 'static>> {
+        match self.source(program_id) {
+            Some(source) => watch_list.filters_with_discovery(source.new_pool),
+            None => watch_list.filters(),
+        }
+    }
+
+    /// Starts tracking a new pool if `platform` is discovered and one of the
+    /// pool mints is a configured quote mint. Returns the `tracking_started`
+    /// details when the pool was not tracked yet.
+    pub fn discover(
+        &self,
+        platform: &str,
+        pool: Pubkey,
+        base_mint: Pubkey,
+        quote_mint: Pubkey,
+    ) -> Option<Value> {
+        let watch_list = self.watch_list.as_ref()?;
+        if !self.platforms.contains(platform) {
+            return None;
+        }
+        if !self.quote_mints.is_empty()
+            && !self.quote_mints.contains(&base_mint)
+            && !self.quote_mints.contains(&quote_mint)
+        {
+            return None;
+        }
+        if !watch_list.add(pool) {
+            return None;
+        }
+
+        log::info!("Discovered {} pool {}", platform, pool);
+        Some(json!({
+            "type": "TrackingStarted",
+            "pool": pool.to_string(),
+            "base_mint": base_mint.to_string(),
+            "quote_mint": quote_mint.to_string(),
+            "source": "discovery"
+        }))
+    }
+}
@@ -0,0 +1,120 @@
+use {
+    solana_client::{
+        client_error::{ClientError, ClientErrorKind},
+        nonblocking::pubsub_client::PubsubClientError,
+        rpc_request::RpcError,
+    },
+    std::{net::SocketAddr, path::PathBuf},
+};
+
+/// RPC error codes for slots that were skipped or whose block is not
+/// available (yet), which are expected rather than failures.
+const SKIPPED_SLOT_CODES: [i64; 3] = [-32004, -32007, -32009];
+/// RPC error code for a block that is not available yet at the requested
+/// commitment.
+const BLOCK_NOT_AVAILABLE_CODE: i64 = -32004;
+
+#[derive(Debug)]
+pub enum DatasourceError {
+    /// Opening the WebSocket failed.
+    Connection { url: String, source: PubsubClientError },
+    /// The node refused the block subscription.
+    Subscription(PubsubClientError),
+    /// Fetching a block over HTTP failed.
+    BlockFetch { slot: u64, source: ClientError },
+    /// Fetching a single transaction over HTTP failed.
+    TransactionFetch { signature: String, source: ClientError },
+    /// Listing the slots to backfill from `from` on failed.
+    Backfill { from: u64, source: ClientError },
+    /// Listing the transactions of an address failed.
+    SignatureListing { address: String, source: ClientError },
+    /// Reconnecting kept failing.
+    ReconnectionsExhausted { attempts: u32 },
+    /// The webhook server could not listen on its address.
+    Bind { addr: SocketAddr, source: std::io::Error },
+    /// The webhook server stopped with an error.
+    Server(std::io::Error),
+    /// A capture file or object could not be created or opened.
+    Capture { path: PathBuf, source: std::io::Error },
+}
+
+impl DatasourceError {
+    /// The slot was skipped by the leader or its block is not available.
+    pub fn is_skipped_slot(&self) -> bool {
+        match self {
+            DatasourceError::BlockFetch { source, .. } => matches!(
+                source.kind(),
+                ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+                    if SKIPPED_SLOT_CODES.contains(code)
+            ),
+            _ => false,
+        }
+    }
+
+    /// The block of the slot is not available yet, e.g. not confirmed.
+    pub fn is_block_not_available(&self) -> bool {
+        match self {
+            DatasourceError::BlockFetch { source, .. } => matches!(
+                source.kind(),
+                ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+                    if *code == BLOCK_NOT_AVAILABLE_CODE
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for DatasourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DatasourceError::Connection { url, source } => {
+                write!(f, "Failed to create WebSocket client for {}: {}", url, source)
+            }
+            DatasourceError::Subscription(e) => write!(f, "Failed to subscribe to blocks: {}", e),
+            DatasourceError::BlockFetch { slot, source } => write!(f, "Failed to fetch block {}: {}", slot, source),
+            DatasourceError::TransactionFetch { signature, source } => {
+                write!(f, "Failed to fetch transaction {}: {}", signature, source)
+            }
+            DatasourceError::Backfill { from, source } => {
+                write!(f, "Failed to list blocks to backfill from slot {}: {}", from, source)
+            }
+            DatasourceError::SignatureListing { address, source } => {
+                write!(f, "Failed to list transactions of {}: {}", address, source)
+            }
+            DatasourceError::ReconnectionsExhausted { attempts } => {
+                write!(f, "Gave up after {} reconnection attempts", attempts)
+            }
+            DatasourceError::Bind { addr, source } => write!(f, "Failed to listen on {}: {}", addr, source),
+            DatasourceError::Server(e) => write!(f, "Webhook server failed: {}", e),
+            DatasourceError::Capture { path, source } => {
+                write!(f, "Failed to open capture {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DatasourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DatasourceError::Connection { source, .. } => Some(source),
+            DatasourceError::Subscription(e) => Some(e),
+            DatasourceError::BlockFetch { source, .. } => Some(source),
+            DatasourceError::TransactionFetch { source, .. } => Some(source),
+            DatasourceError::Backfill { source, .. } => Some(source),
+            DatasourceError::SignatureListing { source, .. } => Some(source),
+            DatasourceError::ReconnectionsExhausted { .. } => None,
+            DatasourceError::Bind { source, .. } => Some(source),
+            DatasourceError::Server(e) => Some(e),
+            DatasourceError::Capture { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<DatasourceError> for carbon_core::error::Error {
+    fn from(error: DatasourceError) -> Self {
+        match error {
+            DatasourceError::BlockFetch { .. } => carbon_core::error::Error::FailedToReceiveUpdates(error.to_string()),
+            _ => carbon_core::error::Error::FailedToConsumeDatasource(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        datasource::{Datasource, DatasourceId, Update, UpdateType},
+        error::CarbonResult,
+        metrics::MetricsCollection,
+        pipeline::DEFAULT_CHANNEL_BUFFER_SIZE,
+    },
+    lru::LruCache,
+    solana_signature::Signature,
+    std::{
+        collections::{HashSet, VecDeque},
+        hash::Hash,
+        num::NonZeroUsize,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::mpsc::{self, Sender},
+    tokio_util::sync::CancellationToken,
+};
+
+const RECENT_CAPACITY: usize = 10_000;
+const DEFAULT_SIGNATURE_CAPACITY: usize = 100_000;
+const DEFAULT_SIGNATURE_TTL_SECS: u64 = 300;
+
+/// The last `capacity` keys seen, to forward an item arriving from several
+/// subscriptions only once.
+pub(crate) struct Recent<K> {
+    capacity: usize,
+    seen: HashSet<K>,
+    order: VecDeque<K>,
+}
+
+impl<K: Copy + Eq + Hash> Recent<K> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Returns `false` when `key` was already seen.
+    pub(crate) fn insert(&mut self, key: K) -> bool {
+        if !self.seen.insert(key) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// How many transaction signatures [`FanInDatasource`] remembers, and for
+/// how long. Redundant datasources can deliver the same transaction far
+/// apart, e.g. a hybrid datasource catching up on a gap after a webhook.
+#[derive(Debug, Clone, Copy)]
+pub struct SignatureDedup {
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl Default for SignatureDedup {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIGNATURE_CAPACITY, Duration::from_secs(DEFAULT_SIGNATURE_TTL_SECS))
+    }
+}
+
+impl SignatureDedup {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self { capacity, ttl }
+    }
+
+    /// Reads `SIGNATURE_DEDUP_CAPACITY` (default `100000`) and
+    /// `SIGNATURE_DEDUP_TTL_SECS` (default `300`).
+    pub fn from_env() -> Result<Self, String> {
+        let capacity = match std::env::var("SIGNATURE_DEDUP_CAPACITY") {
+            Ok(value) => value
+                .parse()
+                .map_err(|e| format!("Invalid SIGNATURE_DEDUP_CAPACITY {}: {}", value, e))?,
+            Err(_) => DEFAULT_SIGNATURE_CAPACITY,
+        };
+        let ttl_secs = match std::env::var("SIGNATURE_DEDUP_TTL_SECS") {
+            Ok(value) => value
+                .parse()
+                .map_err(|e| format!("Invalid SIGNATURE_DEDUP_TTL_SECS {}: {}", value, e))?,
+            Err(_) => DEFAULT_SIGNATURE_TTL_SECS,
+        };
+        Ok(Self::new(capacity, Duration::from_secs(ttl_secs)))
+    }
+}
+
+/// Signatures forwarded within the TTL, bounded to the most recent ones.
+struct SignatureWindow {
+    ttl: Duration,
+    seen: LruCache<Signature, Instant>,
+}
+
+impl SignatureWindow {
+    fn new(dedup: SignatureDedup) -> Self {
+        Self {
+            ttl: dedup.ttl,
+            seen: LruCache::new(NonZeroUsize::new(dedup.capacity).unwrap_or(NonZeroUsize::MIN)),
+        }
+    }
+
+    /// Returns `false` when `signature` was forwarded within the TTL.
+    fn insert(&mut self, signature: Signature) -> bool {
+        let now = Instant::now();
+        if self
+            .seen
+            .get(&signature)
+            .is_some_and(|seen_at| now.duration_since(*seen_at) < self.ttl)
+        {
+            return false;
+        }
+        self.seen.put(signature, now);
+        true
+    }
+}
+
+/// Runs several datasources as one, forwarding each transaction once.
+///
+/// `blockSubscribe` takes a single account or program to filter on, so
+/// following every DEX program takes one subscription each. A transaction
+/// mentioning several of them, e.g. a Jupiter route through Raydium, then
+/// arrives on each subscription and is deduplicated by signature. Block
+/// details are forwarded once per slot.
+///
+/// It also runs different datasources side by side for redundancy, e.g.
+/// hybrid and a Helius webhook, so processors keep receiving transactions
+/// while one of them is down and never see a transaction twice. Counters
+/// `signature_dedup_hits` and `signature_dedup_misses` count the transactions
+/// dropped and forwarded.
+pub struct FanInDatasource {
+    datasources: Vec<Box<dyn Datasource>>,
+    signature_dedup: SignatureDedup,
+}
+
+impl FanInDatasource {
+    pub fn new(datasources: Vec<Box<dyn Datasource>>) -> Self {
+        Self {
+            datasources,
+            signature_dedup: SignatureDedup::default(),
+        }
+    }
+
+    pub fn with_signature_dedup(mut self, signature_dedup: SignatureDedup) -> Self {
+        self.signature_dedup = signature_dedup;
+        self
+    }
+}
+
+#[async_trait]
+impl Datasource for FanInDatasource {
+    async fn consume(
+        &self,
+        id: DatasourceId,
+        sender: Sender<(Update, DatasourceId)>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        // Stops the other datasources once the pipeline stops receiving
+        let child_token = cancellation_token.child_token();
+        let (inner_sender, mut receiver) = mpsc::channel(DEFAULT_CHANNEL_BUFFER_SIZE);
+
+        let sources = futures::future::join_all(self.datasources.iter().map(|datasource| {
+            datasource.consume(id.clone(), inner_sender.clone(), child_token.clone(), metrics.clone())
+        }));
+        drop(inner_sender);
+
+        let forward = async {
+            let mut signatures = SignatureWindow::new(self.signature_dedup);
+            let mut slots = Recent::<u64>::new(RECENT_CAPACITY);
+
+            while let Some((update, update_id)) = receiver.recv().await {
+                let first = match &update {
+                    Update::Transaction(transaction) => {
+                        let first = signatures.insert(transaction.signature);
+                        let counter = if first { "signature_dedup_misses" } else { "signature_dedup_hits" };
+                        metrics
+                            .increment_counter(counter, 1)
+                            .await
+                            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                        first
+                    }
+                    Update::BlockDetails(block) => slots.insert(block.slot),
+                    _ => true,
+                };
+                if !first {
+                    continue;
+                }
+                if sender.send((update, update_id)).await.is_err() {
+                    child_token.cancel();
+                    break;
+                }
+            }
+        };
+
+        let (results, ()) = tokio::join!(sources, forward);
+        results.into_iter().collect::<CarbonResult<Vec<_>>>()?;
+        Ok(())
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        let mut update_types = Vec::new();
+        for update_type in self.datasources.iter().flat_map(|datasource| datasource.update_types()) {
+            if !update_types.contains(&update_type) {
+                update_types.push(update_type);
+            }
+        }
+        update_types
+    }
+}
@@ -0,0 +1,373 @@
+use {
+    super::{
+        conversion::{record_failure, ConversionError},
+        error::DatasourceError,
+    },
+    async_trait::async_trait,
+    axum::{
+        extract::State,
+        http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+        routing::post,
+        Json, Router,
+    },
+    carbon_core::{
+        datasource::{Datasource, DatasourceId, TransactionUpdate, Update, UpdateType},
+        error::CarbonResult,
+        metrics::MetricsCollection,
+    },
+    serde::Deserialize,
+    solana_account_decoder_client_types::token::UiTokenAmount,
+    solana_hash::Hash,
+    solana_message::{compiled_instruction::CompiledInstruction, legacy::Message, MessageHeader, VersionedMessage},
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    solana_transaction_status::{InnerInstruction, InnerInstructions, TransactionStatusMeta, TransactionTokenBalance},
+    std::{net::SocketAddr, str::FromStr, sync::Arc},
+    tokio::sync::mpsc::Sender,
+    tokio_util::sync::CancellationToken,
+};
+
+const DEFAULT_ADDR: &str = "0.0.0.0:8080";
+const DEFAULT_PATH: &str = "/helius";
+
+/// Where the webhook server listens and the `Authorization` header Helius
+/// must send.
+#[derive(Clone)]
+pub struct HeliusWebhookConfig {
+    addr: SocketAddr,
+    path: String,
+    auth: Option<String>,
+}
+
+impl HeliusWebhookConfig {
+    /// Reads `HELIUS_WEBHOOK_ADDR` (default `0.0.0.0:8080`),
+    /// `HELIUS_WEBHOOK_PATH` (default `/helius`) and `HELIUS_WEBHOOK_AUTH`,
+    /// the auth header configured on the webhook (unset accepts any request).
+    pub fn from_env() -> Result<Self, String> {
+        let addr = std::env::var("HELIUS_WEBHOOK_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+        let addr = addr
+            .parse()
+            .map_err(|e| format!("Invalid HELIUS_WEBHOOK_ADDR {}: {}", addr, e))?;
+        let path = std::env::var("HELIUS_WEBHOOK_PATH").unwrap_or_else(|_| DEFAULT_PATH.to_string());
+        if !path.starts_with('/') {
+            return Err(format!("HELIUS_WEBHOOK_PATH must start with /, got {}", path));
+        }
+
+        Ok(Self {
+            addr,
+            path,
+            auth: std::env::var("HELIUS_WEBHOOK_AUTH").ok().filter(|auth| !auth.is_empty()),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+/// Receives Helius enhanced transaction webhooks over HTTP and feeds them into
+/// the pipeline, for deployments that cannot keep a WebSocket open.
+///
+/// The enhanced payload is not the raw transaction: it has every account of
+/// the transaction with its balance changes, and the instructions with their
+/// accounts and data, but no account flags, absolute balances, lookup tables
+/// or logs. The transaction is rebuilt as a legacy message with all accounts
+/// static, and balances hold only the change, so amounts computed as post
+/// minus pre stay right.
+pub struct HeliusWebhookDatasource {
+    config: HeliusWebhookConfig,
+}
+
+impl HeliusWebhookDatasource {
+    pub fn new(config: HeliusWebhookConfig) -> Self {
+        Self { config }
+    }
+}
+
+struct WebhookState {
+    id: DatasourceId,
+    sender: Sender<(Update, DatasourceId)>,
+    metrics: Arc<MetricsCollection>,
+    auth: Option<String>,
+}
+
+#[async_trait]
+impl Datasource for HeliusWebhookDatasource {
+    async fn consume(
+        &self,
+        id: DatasourceId,
+        sender: Sender<(Update, DatasourceId)>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let listener = tokio::net::TcpListener::bind(self.config.addr)
+            .await
+            .map_err(|source| DatasourceError::Bind {
+                addr: self.config.addr,
+                source,
+            })?;
+        log::info!("[WEBHOOK] Listening for Helius webhooks on {}{}", self.config.addr, self.config.path);
+
+        let state = Arc::new(WebhookState {
+            id,
+            sender,
+            metrics,
+            auth: self.config.auth.clone(),
+        });
+        let app = Router::new()
+            .route(&self.config.path, post(receive))
+            .with_state(state);
+
+        axum::serve(listener, app)
+            .with_graceful_shutdown(cancellation_token.cancelled_owned())
+            .await
+            .map_err(DatasourceError::Server)?;
+        Ok(())
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        vec![UpdateType::Transaction]
+    }
+}
+
+/// Handles one delivery. Helius retries deliveries that are not acknowledged
+/// with a 2xx, so a full or stopped pipeline answers 503.
+async fn receive(
+    State(state): State<Arc<WebhookState>>,
+    headers: HeaderMap,
+    Json(transactions): Json<Vec<EnhancedTransaction>>,
+) -> StatusCode {
+    if let Some(auth) = &state.auth {
+        if headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok()) != Some(auth.as_str()) {
+            return StatusCode::UNAUTHORIZED;
+        }
+    }
+
+    state
+        .metrics
+        .increment_counter("helius_webhook_transactions_received", transactions.len() as u64)
+        .await
+        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+
+    for transaction in transactions {
+        let slot = transaction.slot;
+        let update = match transaction_update(transaction) {
+            Ok(Some(update)) => update,
+            Ok(None) => continue,
+            Err(e) => {
+                record_failure("WEBHOOK", slot, &e, &state.metrics).await;
+                continue;
+            }
+        };
+        if state
+            .sender
+            .send((Update::Transaction(Box::new(update)), state.id.clone()))
+            .await
+            .is_err()
+        {
+            log::warn!("[WEBHOOK] Pipeline stopped, rejecting delivery");
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+    }
+
+    StatusCode::OK
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnhancedTransaction {
+    signature: String,
+    slot: u64,
+    timestamp: Option<i64>,
+    #[serde(default)]
+    fee: u64,
+    fee_payer: String,
+    #[serde(default)]
+    transaction_error: Option<serde_json::Value>,
+    #[serde(default)]
+    instructions: Vec<EnhancedInstruction>,
+    #[serde(default)]
+    account_data: Vec<AccountData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnhancedInstruction {
+    program_id: String,
+    #[serde(default)]
+    accounts: Vec<String>,
+    #[serde(default)]
+    data: String,
+    #[serde(default)]
+    inner_instructions: Vec<EnhancedInstruction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountData {
+    account: String,
+    #[serde(default)]
+    native_balance_change: i64,
+    #[serde(default)]
+    token_balance_changes: Vec<TokenBalanceChange>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenBalanceChange {
+    mint: String,
+    raw_token_amount: RawTokenAmount,
+    token_account: String,
+    user_account: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawTokenAmount {
+    decimals: u8,
+    token_amount: String,
+}
+
+/// Account keys of the rebuilt message, in order of first appearance.
+#[derive(Default)]
+struct AccountKeys(Vec<Pubkey>);
+
+impl AccountKeys {
+    fn index(&mut self, address: &str) -> Result<u8, ConversionError> {
+        let key = Pubkey::from_str(address)
+            .map_err(|e| ConversionError::InvalidPayload(format!("address {}: {}", address, e)))?;
+        let index = match self.0.iter().position(|existing| *existing == key) {
+            Some(index) => index,
+            None => {
+                self.0.push(key);
+                self.0.len() - 1
+            }
+        };
+        u8::try_from(index)
+            .map_err(|_| ConversionError::InvalidPayload(format!("more than {} accounts", u8::MAX as usize + 1)))
+    }
+
+    fn compile(&mut self, instruction: &EnhancedInstruction) -> Result<CompiledInstruction, ConversionError> {
+        let data = bs58::decode(&instruction.data)
+            .into_vec()
+            .map_err(|e| ConversionError::InvalidPayload(format!("instruction data: {}", e)))?;
+        Ok(CompiledInstruction {
+            program_id_index: self.index(&instruction.program_id)?,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|account| self.index(account))
+                .collect::<Result<_, _>>()?,
+            data,
+        })
+    }
+}
+
+/// Rebuilds an enhanced transaction as an update, `None` for failed
+/// transactions, which are not processed.
+fn transaction_update(transaction: EnhancedTransaction) -> Result<Option<TransactionUpdate>, ConversionError> {
+    if transaction.transaction_error.as_ref().is_some_and(|error| !error.is_null()) {
+        return Ok(None);
+    }
+
+    let signature = Signature::from_str(&transaction.signature)
+        .map_err(|e| ConversionError::InvalidPayload(format!("signature {}: {}", transaction.signature, e)))?;
+
+    // The fee payer is the only signer the payload identifies
+    let mut keys = AccountKeys::default();
+    keys.index(&transaction.fee_payer)?;
+    for account in &transaction.account_data {
+        keys.index(&account.account)?;
+    }
+
+    let mut instructions = Vec::with_capacity(transaction.instructions.len());
+    let mut inner_instructions = Vec::new();
+    for (index, instruction) in transaction.instructions.iter().enumerate() {
+        instructions.push(keys.compile(instruction)?);
+        if instruction.inner_instructions.is_empty() {
+            continue;
+        }
+        // The payload flattens CPIs, so their depth is lost
+        inner_instructions.push(InnerInstructions {
+            index: index as u8,
+            instructions: instruction
+                .inner_instructions
+                .iter()
+                .map(|inner| {
+                    Ok(InnerInstruction {
+                        instruction: keys.compile(inner)?,
+                        stack_height: Some(2),
+                    })
+                })
+                .collect::<Result<_, ConversionError>>()?,
+        });
+    }
+
+    let mut pre_token_balances = Vec::new();
+    let mut post_token_balances = Vec::new();
+    for change in transaction.account_data.iter().flat_map(|account| &account.token_balance_changes) {
+        let amount = change
+            .raw_token_amount
+            .token_amount
+            .parse::<i128>()
+            .map_err(|e| ConversionError::InvalidPayload(format!("token amount: {}", e)))?;
+        let account_index = keys.index(&change.token_account)?;
+        let balance = |amount: i128| TransactionTokenBalance {
+            account_index,
+            mint: change.mint.clone(),
+            ui_token_amount: UiTokenAmount {
+                ui_amount: None,
+                decimals: change.raw_token_amount.decimals,
+                amount: amount.max(0).to_string(),
+                ui_amount_string: String::new(),
+            },
+            owner: change.user_account.clone(),
+            // Token or Token-2022 is not part of the payload
+            program_id: String::new(),
+        };
+        pre_token_balances.push(balance(-amount));
+        post_token_balances.push(balance(amount));
+    }
+
+    let mut pre_balances = vec![0; keys.0.len()];
+    let mut post_balances = vec![0; keys.0.len()];
+    for account in &transaction.account_data {
+        let index = keys.index(&account.account)? as usize;
+        pre_balances[index] = (-account.native_balance_change).max(0) as u64;
+        post_balances[index] = account.native_balance_change.max(0) as u64;
+    }
+
+    let message = Message {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 0,
+        },
+        account_keys: keys.0,
+        recent_blockhash: Hash::default(),
+        instructions,
+    };
+
+    Ok(Some(TransactionUpdate {
+        signature,
+        transaction: VersionedTransaction {
+            signatures: vec![signature],
+            message: VersionedMessage::Legacy(message),
+        },
+        meta: TransactionStatusMeta {
+            fee: transaction.fee,
+            pre_balances,
+            post_balances,
+            inner_instructions: Some(inner_instructions),
+            pre_token_balances: Some(pre_token_balances),
+            post_token_balances: Some(post_token_balances),
+            ..TransactionStatusMeta::default()
+        },
+        is_vote: false,
+        slot: transaction.slot,
+        block_time: transaction.timestamp,
+        block_hash: None,
+        index: None,
+    }))
+}
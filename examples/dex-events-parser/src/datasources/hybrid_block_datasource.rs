@@ -1,21 +1,37 @@
 use {
+    super::{
+        conversion::{record_failure, transaction_update},
+        error::DatasourceError,
+        fan_in::Recent,
+        rpc_pool::RpcPool,
+    },
+    crate::{
+        checkpoint::Checkpoint,
+        publishers::{DexEventData, Publisher, UnifiedPublisher, EVENTS_TOPIC},
+    },
     async_trait::async_trait,
     carbon_core::{
-        datasource::{Datasource, DatasourceId, TransactionUpdate, Update, UpdateType},
+        datasource::{Datasource, DatasourceId, Update, UpdateType},
         error::CarbonResult,
         metrics::MetricsCollection,
-        transformers::transaction_metadata_from_original_meta,
     },
-    futures::StreamExt,
+    futures::{Stream, StreamExt},
+    serde_json::json,
     solana_client::{
-        nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
-        rpc_client::SerializableTransaction,
+        nonblocking::pubsub_client::PubsubClient,
         rpc_config::{RpcBlockConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter},
     },
-    solana_commitment_config::CommitmentConfig,
+    solana_commitment_config::{CommitmentConfig, CommitmentLevel},
     solana_hash::Hash,
-    solana_transaction_status::{TransactionDetails, UiTransactionEncoding},
-    std::{str::FromStr, sync::Arc, time::{Duration, Instant}},
+    solana_transaction_status::{TransactionDetails, UiConfirmedBlock, UiTransactionEncoding},
+    std::{
+        str::FromStr,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant, SystemTime},
+    },
     tokio::sync::mpsc::{self, Receiver, Sender},
     tokio_util::sync::CancellationToken,
 };
@@ -23,23 +39,105 @@ use {
 const MAX_RECONNECTION_ATTEMPTS: u32 = 10;
 const RECONNECTION_DELAY_MS: u64 = 3000;
 const BLOCK_FETCH_CHANNEL_SIZE: usize = 1000;
+/// Blocks fetched over HTTP at the same time.
 const MAX_CONCURRENT_BLOCK_REQUESTS: usize = 5;
+/// Slots already fetched, as several notification subscriptions report the
+/// same block.
+const RECENT_SLOTS_CAPACITY: usize = 1000;
+/// Slots listed per `getBlocks` request when resuming from a checkpoint.
+const BACKFILL_RANGE: u64 = 10_000;
+/// Slots of a notification gap fetched at most, the most recent ones.
+const MAX_GAP_SLOTS: u64 = 1000;
+/// Time between fetching a block at processed commitment and checking it
+/// again at confirmed, and between checks while it is not confirmed yet.
+const RECHECK_DELAY: Duration = Duration::from_secs(2);
+/// Checks of a processed block before giving up on confirming it.
+const RECHECK_ATTEMPTS: u32 = 5;
+
+/// Commitment blocks are notified and fetched at, trading latency for the
+/// certainty that the block stays on the chain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CommitmentStrategy {
+    /// Lowest latency, the block may still be dropped by a fork.
+    Processed,
+    #[default]
+    Confirmed,
+    /// Rooted blocks only, some 30 seconds behind the tip.
+    Finalized,
+    /// Blocks are emitted at processed commitment and checked again once
+    /// confirmed. The confirmed block of a slot whose processed block was
+    /// forked out is emitted too.
+    ProcessedThenConfirmed,
+}
+
+impl CommitmentStrategy {
+    /// Reads `HYBRID_COMMITMENT`: `processed`, `confirmed` (default),
+    /// `finalized` or `processed_then_confirmed`.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("HYBRID_COMMITMENT") {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Commitment of the notifications and block fetches.
+    pub fn commitment(self) -> CommitmentConfig {
+        match self {
+            CommitmentStrategy::Processed | CommitmentStrategy::ProcessedThenConfirmed => {
+                CommitmentConfig::processed()
+            }
+            CommitmentStrategy::Confirmed => CommitmentConfig::confirmed(),
+            CommitmentStrategy::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+
+    /// Commitment of `getSlot` and `getBlocks`, which refuse anything below
+    /// confirmed.
+    fn listing_commitment(self) -> CommitmentConfig {
+        match self {
+            CommitmentStrategy::Finalized => CommitmentConfig::finalized(),
+            _ => CommitmentConfig::confirmed(),
+        }
+    }
+}
+
+impl FromStr for CommitmentStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "processed" => Ok(CommitmentStrategy::Processed),
+            "confirmed" => Ok(CommitmentStrategy::Confirmed),
+            "finalized" => Ok(CommitmentStrategy::Finalized),
+            "processed_then_confirmed" => Ok(CommitmentStrategy::ProcessedThenConfirmed),
+            _ => Err(format!(
+                "Invalid commitment strategy {}, expected processed, confirmed, finalized or processed_then_confirmed",
+                value
+            )),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct HybridFilters {
-    pub block_filter: RpcBlockSubscribeFilter,
+    /// One notification subscription each, a block is fetched when any of
+    /// them reports it.
+    pub block_filters: Vec<RpcBlockSubscribeFilter>,
     pub block_subscribe_config: Option<RpcBlockSubscribeConfig>,
     pub block_fetch_config: RpcBlockConfig,
+    pub commitment_strategy: CommitmentStrategy,
+    /// Whether failed transactions are handed to the pipeline too.
+    pub include_failed: bool,
 }
 
 impl HybridFilters {
     pub fn new(
-        block_filter: RpcBlockSubscribeFilter,
+        block_filters: Vec<RpcBlockSubscribeFilter>,
         commitment: Option<CommitmentConfig>,
     ) -> Self {
         // Configure WebSocket subscription for block notifications only (no transactions)
         let block_subscribe_config = Some(RpcBlockSubscribeConfig {
-            commitment: commitment.clone(),
+            commitment,
             encoding: Some(UiTransactionEncoding::Base64),
             transaction_details: Some(TransactionDetails::None), // Key: No transactions via WebSocket
             show_rewards: Some(false),
@@ -55,18 +153,48 @@ impl HybridFilters {
             max_supported_transaction_version: Some(0),
         };
 
+        let commitment_strategy = match commitment.map(|commitment| commitment.commitment) {
+            Some(CommitmentLevel::Processed) => CommitmentStrategy::Processed,
+            Some(CommitmentLevel::Finalized) => CommitmentStrategy::Finalized,
+            _ => CommitmentStrategy::Confirmed,
+        };
+
         Self {
-            block_filter,
+            block_filters,
             block_subscribe_config,
             block_fetch_config,
+            commitment_strategy,
+            include_failed: false,
         }
     }
+
+    /// Notifies and fetches blocks at the commitment of `strategy`.
+    pub fn with_commitment_strategy(mut self, strategy: CommitmentStrategy) -> Self {
+        let commitment = Some(strategy.commitment());
+        if let Some(config) = self.block_subscribe_config.as_mut() {
+            config.commitment = commitment;
+        }
+        self.block_fetch_config.commitment = commitment;
+        self.commitment_strategy = strategy;
+        self
+    }
+
+    /// Hands failed transactions to the pipeline along with the successful
+    /// ones, which are the only ones by default.
+    pub fn with_failed_transactions(mut self, include_failed: bool) -> Self {
+        self.include_failed = include_failed;
+        self
+    }
 }
 
 pub struct HybridBlockDatasource {
     pub rpc_ws_url: String,
     pub rpc_http_url: String,
     pub filters: HybridFilters,
+    checkpoint: Checkpoint,
+    fetch_urls: Vec<String>,
+    backup_ws_urls: Vec<String>,
+    retractions: Option<UnifiedPublisher>,
 }
 
 impl HybridBlockDatasource {
@@ -75,8 +203,42 @@ impl HybridBlockDatasource {
             rpc_ws_url,
             rpc_http_url,
             filters,
+            checkpoint: Checkpoint::default(),
+            fetch_urls: Vec::new(),
+            backup_ws_urls: Vec::new(),
+            retractions: None,
         }
     }
+
+    /// Publishes a `forked_out` event to `publisher` for every block emitted
+    /// at processed commitment that a fork dropped, so consumers can retract
+    /// its events.
+    pub fn with_retractions(mut self, publisher: UnifiedPublisher) -> Self {
+        self.retractions = Some(publisher);
+        self
+    }
+
+    /// WebSocket endpoints to fail over to, in order, when `rpc_ws_url`
+    /// keeps failing.
+    pub fn with_backup_ws_urls(mut self, urls: Vec<String>) -> Self {
+        self.backup_ws_urls = urls;
+        self
+    }
+
+    /// Spreads block fetches over `urls` instead of `rpc_http_url` alone,
+    /// skipping endpoints that rate limit for a while.
+    pub fn with_fetch_urls(mut self, urls: Vec<String>) -> Self {
+        self.fetch_urls = urls;
+        self
+    }
+
+    /// Saves the last slot handed to the pipeline and, on start, fetches the
+    /// blocks from the saved slot up to the tip before following
+    /// notifications.
+    pub fn with_checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
 }
 
 #[async_trait]
@@ -89,52 +251,91 @@ impl Datasource for HybridBlockDatasource {
         metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
         log::info!("Starting Hybrid Block Datasource...");
+        if self.filters.block_filters.is_empty() {
+            return Err(carbon_core::error::Error::Custom(
+                "Hybrid datasource needs at least one block filter".to_string(),
+            ));
+        }
         log::info!("WebSocket URL: {}", self.rpc_ws_url);
-        log::info!("HTTP RPC URL: {}", self.rpc_http_url);
 
-        // Create HTTP RPC client for block fetching
-        let http_client = Arc::new(RpcClient::new_with_commitment(
-            self.rpc_http_url.clone(),
-            self.filters
-                .block_fetch_config
-                .commitment
-                .unwrap_or(CommitmentConfig::confirmed()),
-        ));
+        // Create HTTP RPC clients for block fetching
+        let fetch_urls = match self.fetch_urls.is_empty() {
+            true => std::slice::from_ref(&self.rpc_http_url),
+            false => self.fetch_urls.as_slice(),
+        };
+        let rpc = Arc::new(RpcPool::new(fetch_urls, self.filters.commitment_strategy.listing_commitment()));
+        log::info!("HTTP RPC URLs: {}", rpc.labels().join(", "));
+        log::info!("Commitment strategy: {:?}", self.filters.commitment_strategy);
+
+        let resume_from = if self.checkpoint.is_enabled() {
+            match rpc.call(|client| Box::pin(client.get_slot()), &metrics).await {
+                Ok(tip) => self.checkpoint.resume_from(tip).await,
+                Err(e) => {
+                    log::error!("[CHECKPOINT] Failed to get the current slot, not resuming: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let processed = Arc::new(AtomicU64::new(0));
+        let saver_token = cancellation_token.child_token();
+        let saver = self.checkpoint.spawn_saver(processed.clone(), saver_token.clone());
 
         // Create channel for slot notifications
         let (slot_sender, slot_receiver) = mpsc::channel(BLOCK_FETCH_CHANNEL_SIZE);
 
-        // Start block notification subscriber (WebSocket)
-        let notification_task = self.start_block_notification_subscriber(
-            slot_sender,
-            cancellation_token.clone(),
-            metrics.clone(),
-        );
+        // Start one block notification subscriber (WebSocket) per filter
+        let mut notification_tasks = Vec::with_capacity(self.filters.block_filters.len());
+        for block_filter in &self.filters.block_filters {
+            notification_tasks.push(
+                self.start_block_notification_subscriber(
+                    block_filter.clone(),
+                    slot_sender.clone(),
+                    cancellation_token.clone(),
+                    metrics.clone(),
+                )
+                .await,
+            );
+        }
+        drop(slot_sender);
 
         // Start block data fetcher (HTTP RPC)
         let fetcher_task = self.start_block_data_fetcher(
-            http_client,
+            rpc,
             slot_receiver,
             sender,
             id,
+            resume_from,
+            processed,
             cancellation_token.clone(),
             metrics.clone(),
-        );
+        ).await;
 
         // Wait for tasks to complete
-        tokio::select! {
-            _ = notification_task => {
+        let result = tokio::select! {
+            (result, _, _) = futures::future::select_all(notification_tasks) => {
                 log::info!("Block notification subscriber completed");
+                match result {
+                    Ok(Err(e)) => Err(e.into()),
+                    _ => Ok(()),
+                }
             }
             _ = fetcher_task => {
                 log::info!("Block data fetcher completed");
+                Ok(())
             }
             _ = cancellation_token.cancelled() => {
                 log::info!("Hybrid Block Datasource cancelled");
+                Ok(())
             }
-        }
+        };
 
-        Ok(())
+        saver_token.cancel();
+        if let Some(saver) = saver {
+            let _ = saver.await;
+        }
+        result
     }
 
     fn update_types(&self) -> Vec<UpdateType> {
@@ -145,61 +346,65 @@ impl Datasource for HybridBlockDatasource {
 impl HybridBlockDatasource {
     async fn start_block_notification_subscriber(
         &self,
-        slot_sender: Sender<u64>,
+        block_filter: RpcBlockSubscribeFilter,
+        slot_sender: Sender<Notification>,
         cancellation_token: CancellationToken,
         metrics: Arc<MetricsCollection>,
-    ) -> tokio::task::JoinHandle<()> {
-        let rpc_ws_url = self.rpc_ws_url.clone();
+    ) -> tokio::task::JoinHandle<Result<(), DatasourceError>> {
+        let mut failover = WsFailover::new(
+            std::iter::once(self.rpc_ws_url.clone())
+                .chain(self.backup_ws_urls.iter().cloned())
+                .collect(),
+        );
         let filters = self.filters.clone();
         
         tokio::spawn(async move {
-            let mut reconnection_attempts = 0;
-
+            // Highest slot notified before the subscription dropped
+            let mut last_slot = None;
             loop {
                 if cancellation_token.is_cancelled() {
                     log::info!("Block notification subscriber cancelled");
                     break;
                 }
 
+                let rpc_ws_url = failover.url().to_string();
                 let client = match PubsubClient::new(&rpc_ws_url).await {
                     Ok(client) => client,
-                    Err(err) => {
-                        log::error!("Failed to create WebSocket client: {}", err);
-                        reconnection_attempts += 1;
-                        if reconnection_attempts >= MAX_RECONNECTION_ATTEMPTS {
-                            log::error!("Max reconnection attempts reached for WebSocket");
-                            break;
-                        }
+                    Err(source) => {
+                        let err = DatasourceError::Connection { url: rpc_ws_url, source };
+                        log::error!("{}", err);
+                        failover.failed(&metrics).await?;
                         tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
                         continue;
                     }
                 };
 
                 let (mut block_stream, _unsub) = match client
-                    .block_subscribe(filters.block_filter.clone(), filters.block_subscribe_config.clone())
+                    .block_subscribe(block_filter.clone(), filters.block_subscribe_config.clone())
                     .await
                 {
                     Ok(subscription) => subscription,
                     Err(err) => {
-                        log::error!("Failed to subscribe to blocks: {:?}", err);
-                        reconnection_attempts += 1;
-                        if reconnection_attempts > MAX_RECONNECTION_ATTEMPTS {
-                            log::error!("Max subscription attempts reached");
-                            break;
-                        }
+                        log::error!("{}", DatasourceError::Subscription(err));
+                        failover.failed(&metrics).await?;
                         tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
                         continue;
                     }
                 };
 
-                reconnection_attempts = 0;
-                log::info!("Successfully subscribed to block notifications");
+                failover.subscribed();
+                log::info!("Successfully subscribed to block notifications on {}", rpc_ws_url);
+                if let Some(last_slot) = last_slot {
+                    if slot_sender.send(Notification::Resubscribed { last_slot }).await.is_err() {
+                        break;
+                    }
+                }
 
                 loop {
                     tokio::select! {
                         _ = cancellation_token.cancelled() => {
                             log::info!("Block notification subscription cancelled");
-                            return;
+                            return Ok(());
                         }
                         block_event = block_stream.next() => {
                             match block_event {
@@ -207,8 +412,9 @@ impl HybridBlockDatasource {
                                     let slot = event.context.slot;
                                     log::debug!("Received block notification for slot: {}", slot);
                                     
+                                    last_slot = last_slot.max(Some(slot));
                                     // Send slot to fetcher
-                                    if let Err(err) = slot_sender.send(slot).await {
+                                    if let Err(err) = slot_sender.send(Notification::Block(slot)).await {
                                         log::error!("Failed to send slot to fetcher: {}", err);
                                         break;
                                     }
@@ -220,6 +426,8 @@ impl HybridBlockDatasource {
                                 }
                                 None => {
                                     log::warn!("Block notification stream closed, reconnecting...");
+                                    // The primary is tried again first
+                                    failover.restart();
                                     break;
                                 }
                             }
@@ -229,132 +437,563 @@ impl HybridBlockDatasource {
 
                 tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
             }
+
+            Ok(())
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn start_block_data_fetcher(
         &self,
-        http_client: Arc<RpcClient>,
-        mut slot_receiver: Receiver<u64>,
+        rpc: Arc<RpcPool>,
+        slot_receiver: Receiver<Notification>,
         sender: Sender<(Update, DatasourceId)>,
         id: DatasourceId,
+        resume_from: Option<u64>,
+        processed: Arc<AtomicU64>,
         cancellation_token: CancellationToken,
         metrics: Arc<MetricsCollection>,
     ) -> tokio::task::JoinHandle<()> {
-        let block_config = self.filters.block_fetch_config.clone();
-        
+        let recheck = self.filters.commitment_strategy == CommitmentStrategy::ProcessedThenConfirmed;
+        let fetcher = Arc::new(BlockFetcher {
+            rpc,
+            block_config: self.filters.block_fetch_config,
+            include_failed: self.filters.include_failed,
+            sender,
+            id,
+            processed,
+            retractions: self.retractions.clone(),
+            metrics,
+        });
+
         tokio::spawn(async move {
             log::info!("Block data fetcher started");
 
-            while let Some(slot) = slot_receiver.recv().await {
+            // Notifications queue up meanwhile, the slots fetched here are skipped
+            let mut resumed_to = 0;
+            if let Some(from) = resume_from {
+                match fetcher.backfill(from, &cancellation_token).await {
+                    Ok(Some(tip)) => resumed_to = tip,
+                    Ok(None) => return,
+                    Err(e) => log::error!("[CHECKPOINT] Resume from slot {} failed, blocks are missing: {}", from, e),
+                }
+            }
+
+            let notifications = Notifications {
+                receiver: slot_receiver,
+                recent_slots: Recent::new(RECENT_SLOTS_CAPACITY),
+                resumed_to,
+                // Notifications start some time after the resumed tip
+                gap_from: Some(resumed_to).filter(|slot| *slot > 0),
+            };
+            let slots = {
+                let fetcher = fetcher.clone();
+                futures::stream::unfold(notifications, move |mut notifications| {
+                    let fetcher = fetcher.clone();
+                    async move {
+                        let slots = match notifications.receiver.recv().await? {
+                            Notification::Block(slot) => notifications.slots_to_fetch(slot, &fetcher).await,
+                            Notification::Resubscribed { last_slot } => {
+                                notifications.gap_from = Some(notifications.gap_from.map_or(last_slot, |from| from.min(last_slot)));
+                                Vec::new()
+                            }
+                        };
+                        Some((futures::stream::iter(slots), notifications))
+                    }
+                })
+                .flatten()
+            };
+
+            let (recheck_sender, rechecker) = if recheck {
+                let (recheck_sender, recheck_receiver) = mpsc::channel(BLOCK_FETCH_CHANNEL_SIZE);
+                (Some(recheck_sender), Some(fetcher.clone().spawn_rechecker(recheck_receiver)))
+            } else {
+                (None, None)
+            };
+
+            let mut blocks = Box::pin(fetcher.clone().fetch_all(slots));
+            while let Some((slot, block)) = blocks.next().await {
                 if cancellation_token.is_cancelled() {
                     log::info!("Block data fetcher cancelled");
                     break;
                 }
-
-                log::debug!("Fetching full block data for slot: {}", slot);
-                let start_time = Instant::now();
-
-                match http_client.get_block_with_config(slot, block_config.clone()).await {
-                    Ok(block) => {
-                        let fetch_time = start_time.elapsed();
-                        log::debug!("Fetched block {} in {:?}", slot, fetch_time);
-
-                        // Record metrics
-                        metrics
-                            .record_histogram(
-                                "hybrid_block_fetch_time_milliseconds",
-                                fetch_time.as_millis() as f64,
-                            )
-                            .await
-                            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
-
-                        metrics
-                            .increment_counter("hybrid_blocks_fetched", 1)
-                            .await
-                            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
-
-                        // Process transactions from the block
-                        if let Some(transactions) = block.transactions {
-                            let block_hash = Hash::from_str(&block.blockhash).ok();
-                            
-                            for encoded_transaction_with_status_meta in transactions {
-                                let tx_start_time = Instant::now();
-
-                                let meta_original = if let Some(meta) = encoded_transaction_with_status_meta.meta.clone() {
-                                    meta
-                                } else {
-                                    continue;
-                                };
-
-                                if meta_original.status.is_err() {
-                                    continue;
-                                }
-
-                                let Some(decoded_transaction) = encoded_transaction_with_status_meta.transaction.decode() else {
-                                    log::error!("Failed to decode transaction");
-                                    continue;
-                                };
-
-                                let Ok(meta_needed) = transaction_metadata_from_original_meta(meta_original) else {
-                                    log::error!("Error processing transaction metadata");
-                                    continue;
-                                };
-
-                                let update = Update::Transaction(Box::new(TransactionUpdate {
-                                    signature: *decoded_transaction.get_signature(),
-                                    transaction: decoded_transaction,
-                                    meta: meta_needed,
-                                    is_vote: false,
-                                    slot,
-                                    block_time: block.block_time,
-                                    block_hash,
-                                }));
-
-                                // Send transaction update
-                                if let Err(err) = sender.send((update, id.clone())).await {
-                                    log::error!("Failed to send transaction update: {}", err);
-                                    break;
-                                }
-
-                                metrics
-                                    .record_histogram(
-                                        "hybrid_transaction_process_time_nanoseconds",
-                                        tx_start_time.elapsed().as_nanos() as f64,
-                                    )
-                                    .await
-                                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
-
-                                metrics
-                                    .increment_counter("hybrid_transactions_processed", 1)
-                                    .await
-                                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
-                            }
-                        }
+                if let Some(block) = block {
+                    let pending = PendingRecheck {
+                        slot,
+                        blockhash: block.blockhash.clone(),
+                        fetched_at: Instant::now(),
+                    };
+                    if !fetcher.emit(slot, block).await {
+                        break;
                     }
-                    Err(err) => {
-                        // Handle skipped slots gracefully
-                        if err.to_string().contains("-32009")
-                            || err.to_string().contains("-32004")
-                            || err.to_string().contains("-32007")
-                        {
-                            log::debug!("Slot {} was skipped or missing: {}", slot, err);
-                            metrics
-                                .increment_counter("hybrid_blocks_skipped", 1)
-                                .await
-                                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
-                        } else {
-                            log::error!("Error fetching block {}: {}", slot, err);
-                            metrics
-                                .increment_counter("hybrid_block_fetch_errors", 1)
-                                .await
-                                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                    if let Some(recheck_sender) = &recheck_sender {
+                        // Emitting must not wait on the confirmations
+                        if recheck_sender.try_send(pending).is_err() {
+                            log::warn!("Too many blocks waiting for confirmation, slot {} is not re-checked", slot);
                         }
                     }
                 }
             }
 
+            if let Some(rechecker) = rechecker {
+                rechecker.abort();
+            }
             log::info!("Block data fetcher completed");
         })
     }
-} 
\ No newline at end of file
+}
+
+/// WebSocket endpoint a notification subscriber connects to: the primary
+/// first, then the next backup each time one fails
+/// `MAX_RECONNECTION_ATTEMPTS` times in a row.
+struct WsFailover {
+    urls: Vec<String>,
+    current: usize,
+    attempts: u32,
+    /// Endpoints given up on since the last subscription.
+    exhausted: usize,
+}
+
+impl WsFailover {
+    fn new(urls: Vec<String>) -> Self {
+        Self {
+            urls,
+            current: 0,
+            attempts: 0,
+            exhausted: 0,
+        }
+    }
+
+    fn url(&self) -> &str {
+        &self.urls[self.current]
+    }
+
+    /// Counts a failed connection or subscription, errors once every
+    /// endpoint failed in a row.
+    async fn failed(&mut self, metrics: &MetricsCollection) -> Result<(), DatasourceError> {
+        self.attempts += 1;
+        if self.attempts < MAX_RECONNECTION_ATTEMPTS {
+            return Ok(());
+        }
+
+        self.exhausted += 1;
+        if self.exhausted >= self.urls.len() {
+            log::error!("Max reconnection attempts reached on every WebSocket endpoint");
+            return Err(DatasourceError::ReconnectionsExhausted {
+                attempts: MAX_RECONNECTION_ATTEMPTS * self.urls.len() as u32,
+            });
+        }
+
+        let failed = self.current;
+        self.current = (self.current + 1) % self.urls.len();
+        self.attempts = 0;
+        log::warn!(
+            "WebSocket endpoint {} keeps failing, failing over to {}",
+            self.urls[failed],
+            self.url()
+        );
+        metrics
+            .increment_counter("hybrid_ws_failovers", 1)
+            .await
+            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        Ok(())
+    }
+
+    fn subscribed(&mut self) {
+        self.attempts = 0;
+        self.exhausted = 0;
+    }
+
+    fn restart(&mut self) {
+        self.current = 0;
+    }
+}
+
+/// What a notification subscriber reports to the block fetcher.
+enum Notification {
+    Block(u64),
+    /// The subscription was established again after dropping, the blocks
+    /// after `last_slot` may have been missed meanwhile.
+    Resubscribed { last_slot: u64 },
+}
+
+/// Slots reported by the notification subscribers, turned into the slots to
+/// fetch.
+struct Notifications {
+    receiver: Receiver<Notification>,
+    recent_slots: Recent<u64>,
+    /// Slots up to here were fetched when resuming from the checkpoint.
+    resumed_to: u64,
+    /// Last slot before notifications stopped, set until the first slot
+    /// notified after that.
+    gap_from: Option<u64>,
+}
+
+impl Notifications {
+    /// `slot` unless it was already fetched, preceded by the blocks missed
+    /// while the notifications were down, if any. Slots jump between
+    /// notifications all the time, only a reconnect is a gap.
+    async fn slots_to_fetch(&mut self, slot: u64, fetcher: &BlockFetcher) -> Vec<u64> {
+        if slot <= self.resumed_to || !self.recent_slots.insert(slot) {
+            return Vec::new();
+        }
+
+        let mut slots = Vec::new();
+        if let Some(from) = self.gap_from.take().filter(|from| slot > from + 1) {
+            slots = fetcher.gap_slots(from + 1, slot - 1, &mut self.recent_slots).await;
+        }
+        slots.push(slot);
+        slots
+    }
+}
+
+/// A block emitted at processed commitment, to check again once confirmed.
+struct PendingRecheck {
+    slot: u64,
+    blockhash: String,
+    fetched_at: Instant,
+}
+
+/// Fetches blocks over HTTP RPC and hands their transactions to the pipeline.
+struct BlockFetcher {
+    rpc: Arc<RpcPool>,
+    block_config: RpcBlockConfig,
+    include_failed: bool,
+    sender: Sender<(Update, DatasourceId)>,
+    id: DatasourceId,
+    /// Highest slot whose block was entirely sent, for the checkpoint.
+    processed: Arc<AtomicU64>,
+    retractions: Option<UnifiedPublisher>,
+    metrics: Arc<MetricsCollection>,
+}
+
+impl BlockFetcher {
+    /// Fetches every block from `from` up to the current tip. Returns the tip,
+    /// or `None` when cancelled or the pipeline stopped.
+    async fn backfill(self: &Arc<Self>, from: u64, cancellation_token: &CancellationToken) -> Result<Option<u64>, DatasourceError> {
+        let tip = self
+            .rpc
+            .call(|client| Box::pin(client.get_slot()), &self.metrics)
+            .await
+            .map_err(|source| DatasourceError::Backfill { from, source })?;
+        log::info!("[CHECKPOINT] Resuming from slot {}, fetching up to slot {}", from, tip);
+
+        let mut start = from;
+        while start <= tip {
+            let end = (start + BACKFILL_RANGE - 1).min(tip);
+            let slots = self
+                .rpc
+                .call(|client| Box::pin(client.get_blocks(start, Some(end))), &self.metrics)
+                .await
+                .map_err(|source| DatasourceError::Backfill { from: start, source })?;
+
+            let mut blocks = Box::pin(self.clone().fetch_all(futures::stream::iter(slots)));
+            while let Some((slot, block)) = blocks.next().await {
+                if cancellation_token.is_cancelled() {
+                    return Ok(None);
+                }
+                if let Some(block) = block {
+                    if !self.emit(slot, block).await {
+                        return Ok(None);
+                    }
+                }
+                self.metrics
+                    .increment_counter("hybrid_blocks_resumed", 1)
+                    .await
+                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+            }
+            start = end + 1;
+        }
+
+        log::info!("[CHECKPOINT] Caught up to slot {}, switching to live notifications", tip);
+        Ok(Some(tip))
+    }
+
+    /// The blocks between two notified slots that no subscription reported,
+    /// e.g. while reconnecting. Slots skipped by their leader are not blocks
+    /// and are not gaps.
+    async fn gap_slots(&self, from: u64, to: u64, recent_slots: &mut Recent<u64>) -> Vec<u64> {
+        let start = if to - from + 1 > MAX_GAP_SLOTS {
+            log::warn!(
+                "Notification gap of {} slots from slot {}, only the last {} are fetched",
+                to - from + 1,
+                from,
+                MAX_GAP_SLOTS
+            );
+            to + 1 - MAX_GAP_SLOTS
+        } else {
+            from
+        };
+        let slots = match self
+            .rpc
+            .call(|client| Box::pin(client.get_blocks(start, Some(to))), &self.metrics)
+            .await
+        {
+            Ok(slots) => slots,
+            Err(source) => {
+                log::error!("{}", DatasourceError::Backfill { from: start, source });
+                return Vec::new();
+            }
+        };
+        let missing = slots
+            .into_iter()
+            .filter(|slot| recent_slots.insert(*slot))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return missing;
+        }
+
+        log::warn!(
+            "Missed notifications for {} blocks between slots {} and {}, fetching them",
+            missing.len(),
+            from,
+            to
+        );
+        self.metrics
+            .increment_counter("hybrid_slot_gaps_detected", 1)
+            .await
+            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        self.metrics
+            .increment_counter("hybrid_gap_blocks_fetched", missing.len() as u64)
+            .await
+            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        missing
+    }
+
+    /// Fetches up to `MAX_CONCURRENT_BLOCK_REQUESTS` blocks at a time, yielded
+    /// in the order of `slots` so transactions are emitted slot by slot.
+    fn fetch_all(
+        self: Arc<Self>,
+        slots: impl Stream<Item = u64> + Send + 'static,
+    ) -> impl Stream<Item = (u64, Option<UiConfirmedBlock>)> + Send + 'static {
+        slots
+            .map(move |slot| {
+                let fetcher = self.clone();
+                async move { (slot, fetcher.fetch(slot).await) }
+            })
+            .buffered(MAX_CONCURRENT_BLOCK_REQUESTS)
+    }
+
+    /// `None` when the slot was skipped or the fetch failed, which is logged.
+    async fn fetch(&self, slot: u64) -> Option<UiConfirmedBlock> {
+        self.fetch_with_config(slot, self.block_config).await
+    }
+
+    async fn fetch_with_config(&self, slot: u64, block_config: RpcBlockConfig) -> Option<UiConfirmedBlock> {
+        let metrics = &self.metrics;
+        log::debug!("Fetching full block data for slot: {}", slot);
+        let start_time = Instant::now();
+
+        match self
+            .rpc
+            .call(|client| Box::pin(client.get_block_with_config(slot, block_config)), metrics)
+            .await
+        {
+            Ok(block) => {
+                let fetch_time = start_time.elapsed();
+                log::debug!("Fetched block {} in {:?}", slot, fetch_time);
+
+                // Record metrics
+                metrics
+                    .record_histogram(
+                        "hybrid_block_fetch_time_milliseconds",
+                        fetch_time.as_millis() as f64,
+                    )
+                    .await
+                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+
+                metrics
+                    .increment_counter("hybrid_blocks_fetched", 1)
+                    .await
+                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+
+                Some(block)
+            }
+            Err(source) => {
+                let err = DatasourceError::BlockFetch { slot, source };
+                // Handle skipped slots gracefully
+                if err.is_skipped_slot() {
+                    log::debug!("Slot {} was skipped or missing: {}", slot, err);
+                    metrics
+                        .increment_counter("hybrid_blocks_skipped", 1)
+                        .await
+                        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                } else {
+                    log::error!("{}", err);
+                    metrics
+                        .increment_counter("hybrid_block_fetch_errors", 1)
+                        .await
+                        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                }
+                None
+            }
+        }
+    }
+
+    /// Checks the blocks emitted at processed commitment once they are
+    /// confirmed, a few at a time.
+    fn spawn_rechecker(self: Arc<Self>, receiver: Receiver<PendingRecheck>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            futures::stream::unfold(receiver, |mut receiver| async move {
+                let pending = receiver.recv().await?;
+                Some((pending, receiver))
+            })
+            .for_each_concurrent(MAX_CONCURRENT_BLOCK_REQUESTS, |pending| {
+                let fetcher = self.clone();
+                async move { fetcher.recheck(pending).await }
+            })
+            .await;
+        })
+    }
+
+    /// Fetches the confirmed block of the slot of `pending`. When the
+    /// processed block was forked out, the confirmed one, if the slot has
+    /// any, is emitted as well.
+    async fn recheck(&self, pending: PendingRecheck) {
+        let PendingRecheck { slot, blockhash, fetched_at } = pending;
+        let confirmed_config = RpcBlockConfig {
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..self.block_config
+        };
+        let header_config = RpcBlockConfig {
+            transaction_details: Some(TransactionDetails::None),
+            ..confirmed_config
+        };
+
+        tokio::time::sleep_until((fetched_at + RECHECK_DELAY).into()).await;
+        for attempt in 1..=RECHECK_ATTEMPTS {
+            let result = self
+                .rpc
+                .call(|client| Box::pin(client.get_block_with_config(slot, header_config)), &self.metrics)
+                .await
+                .map_err(|source| DatasourceError::BlockFetch { slot, source });
+            let forked_out = match result {
+                Ok(block) if block.blockhash == blockhash => {
+                    log::debug!("Block {} in slot {} confirmed", blockhash, slot);
+                    self.metrics
+                        .increment_counter("hybrid_blocks_confirmed", 1)
+                        .await
+                        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                    return;
+                }
+                Ok(block) => Some(block.blockhash),
+                Err(err) if err.is_skipped_slot() && !err.is_block_not_available() => None,
+                Err(err) => {
+                    log::debug!("Block {} not confirmed yet (attempt {}): {}", slot, attempt, err);
+                    tokio::time::sleep(RECHECK_DELAY).await;
+                    continue;
+                }
+            };
+
+            log::warn!(
+                "Block {} in slot {} was forked out, confirmed block: {}",
+                blockhash,
+                slot,
+                forked_out.as_deref().unwrap_or("none")
+            );
+            self.metrics
+                .increment_counter("hybrid_blocks_forked_out", 1)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+            self.retract(slot, &blockhash, forked_out.as_deref()).await;
+            if forked_out.is_some() {
+                if let Some(block) = self.fetch_with_config(slot, confirmed_config).await {
+                    self.emit(slot, block).await;
+                }
+            }
+            return;
+        }
+
+        log::warn!("Block {} in slot {} could not be confirmed after {} checks", blockhash, slot, RECHECK_ATTEMPTS);
+        self.metrics
+            .increment_counter("hybrid_block_rechecks_failed", 1)
+            .await
+            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+    }
+
+    /// Publishes the `forked_out` event of a block emitted at processed
+    /// commitment, whose events consumers should drop.
+    async fn retract(&self, slot: u64, blockhash: &str, confirmed_blockhash: Option<&str>) {
+        let Some(publisher) = &self.retractions else {
+            return;
+        };
+        let data = DexEventData {
+            event_type: "forked_out".to_string(),
+            platform: String::new(),
+            signature: String::new(),
+            timestamp: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs(),
+            block_time: None,
+            slot: Some(slot),
+            details: json!({
+                "type": "ForkedOut",
+                "slot": slot,
+                "blockhash": blockhash,
+                "confirmed_blockhash": confirmed_blockhash
+            }),
+            ingested_at: None,
+            tx_index: None,
+            instruction_index: None,
+            is_inner: None,
+            fee_payer: None,
+            trader: None,
+            payload: None,
+        };
+        if let Err(e) = publisher.publish(EVENTS_TOPIC, &data).await {
+            log::error!("Failed to publish the retraction of block {} in slot {}: {}", blockhash, slot, e);
+        }
+    }
+
+    /// Hands the transactions of a block to the pipeline. Returns `false`
+    /// once the pipeline stopped receiving.
+    async fn emit(&self, slot: u64, block: UiConfirmedBlock) -> bool {
+        let metrics = &self.metrics;
+
+        // Process transactions from the block
+        if let Some(transactions) = block.transactions {
+            let block_hash = Hash::from_str(&block.blockhash).ok();
+
+            for (index, encoded_transaction_with_status_meta) in transactions.into_iter().enumerate() {
+                let tx_start_time = Instant::now();
+
+                let transaction = match transaction_update(
+                    slot,
+                    block.block_time,
+                    block_hash,
+                    Some(index as u64),
+                    encoded_transaction_with_status_meta,
+                    self.include_failed,
+                ) {
+                    Ok(Some(transaction)) => transaction,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        record_failure("HYBRID", slot, &e, metrics).await;
+                        continue;
+                    }
+                };
+                let update = Update::Transaction(Box::new(transaction));
+
+                // Send transaction update
+                if let Err(err) = self.sender.send((update, self.id.clone())).await {
+                    log::error!("Failed to send transaction update: {}", err);
+                    return false;
+                }
+
+                metrics
+                    .record_histogram(
+                        "hybrid_transaction_process_time_nanoseconds",
+                        tx_start_time.elapsed().as_nanos() as f64,
+                    )
+                    .await
+                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+
+                metrics
+                    .increment_counter("hybrid_transactions_processed", 1)
+                    .await
+                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+            }
+        }
+        self.processed.fetch_max(slot, Ordering::Relaxed);
+
+        true
+    }
+}
@@ -0,0 +1,535 @@
+use {
+    super::{
+        conversion::{record_failure, transaction_update},
+        error::DatasourceError,
+    },
+    async_trait::async_trait,
+    carbon_core::{
+        datasource::{Datasource, DatasourceId, Update, UpdateType},
+        error::CarbonResult,
+        metrics::MetricsCollection,
+    },
+    serde::{Deserialize, Serialize},
+    solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcBlockConfig},
+    solana_commitment_config::CommitmentConfig,
+    solana_hash::Hash,
+    solana_signature::Signature,
+    solana_transaction_status::{TransactionDetails, UiTransactionEncoding},
+    std::{
+        collections::HashSet,
+        path::PathBuf,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+    tokio::sync::mpsc::{self, Sender},
+    tokio_util::sync::CancellationToken,
+};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+/// Slots listed per `getBlocks` request while backfilling.
+const BACKFILL_RANGE: u64 = 10_000;
+const FORWARD_CHANNEL_SIZE: usize = 1000;
+
+/// A daily or weekly UTC time range, in seconds.
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    weekday: Option<u64>,
+    start: u64,
+    length: u64,
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (weekday, range) = match spec.split_once(' ') {
+            Some((day, range)) => {
+                let weekday = WEEKDAYS
+                    .iter()
+                    .position(|weekday| weekday.eq_ignore_ascii_case(day))
+                    .ok_or_else(|| format!("Unknown weekday {} in maintenance window {}", day, spec))?;
+                (Some(weekday as u64), range.trim())
+            }
+            None => (None, spec),
+        };
+        let invalid = || format!("Maintenance window {} must look like [weekday] HH:MM-HH:MM", spec);
+        let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+        let start = time_of_day(start).ok_or_else(invalid)?;
+        let end = time_of_day(end).ok_or_else(invalid)?;
+
+        // A window may run past midnight
+        let length = (end + SECONDS_PER_DAY - start) % SECONDS_PER_DAY;
+        if length == 0 {
+            return Err(format!("Maintenance window {} is empty", spec));
+        }
+
+        Ok(Self { weekday, start, length })
+    }
+}
+
+fn time_of_day(value: &str) -> Option<u64> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let hours = hours.parse::<u64>().ok().filter(|hours| *hours < 24)?;
+    let minutes = minutes.parse::<u64>().ok().filter(|minutes| *minutes < 60)?;
+    Some(hours * 3600 + minutes * 60)
+}
+
+impl Window {
+    /// Start and end of the occurrences from yesterday to a week ahead, in
+    /// unix seconds.
+    fn occurrences(&self, now: u64) -> impl Iterator<Item = (u64, u64)> + '_ {
+        let today = now / SECONDS_PER_DAY;
+        (today.saturating_sub(1)..=today + 7)
+            // 1970-01-01 was a Thursday
+            .filter(move |day| self.weekday.is_none_or(|weekday| (day + 4) % 7 == weekday))
+            .map(move |day| {
+                let start = day * SECONDS_PER_DAY + self.start;
+                (start, start + self.length)
+            })
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// First slot left to backfill.
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    slot: u64,
+}
+
+/// Transactions sent while the live datasource and the backfill cover the
+/// same slots, so each one in the overlap is only sent once.
+struct Overlap {
+    /// Last slot the backfill covers, `u64::MAX` until it is known.
+    tip: AtomicU64,
+    sent: Mutex<HashSet<Signature>>,
+}
+
+impl Overlap {
+    fn new() -> Self {
+        Self {
+            tip: AtomicU64::new(u64::MAX),
+            sent: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Whether the transaction was not sent yet, marking it as sent.
+    fn first(&self, slot: u64, signature: Signature) -> bool {
+        slot > self.tip.load(Ordering::Relaxed)
+            || self.sent.lock().unwrap_or_else(|e| e.into_inner()).insert(signature)
+    }
+}
+
+/// Times at which the pipeline stops reading, e.g. known RPC provider
+/// maintenance. The slot reached when draining is persisted so the missed
+/// blocks are backfilled on resume, even across a restart.
+#[derive(Debug, Clone)]
+pub struct MaintenanceWindows {
+    windows: Vec<Window>,
+    checkpoint_path: PathBuf,
+}
+
+impl MaintenanceWindows {
+    /// Reads `MAINTENANCE_WINDOWS`, comma separated UTC ranges such as
+    /// `02:00-02:30` (daily) or `sun 23:30-00:30` (weekly), and
+    /// `MAINTENANCE_CHECKPOINT_FILE` (default
+    /// `maintenance-checkpoint.json`).
+    pub fn from_env() -> Result<Self, String> {
+        let windows = match std::env::var("MAINTENANCE_WINDOWS") {
+            Ok(value) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|spec| !spec.is_empty())
+                .map(Window::from_str)
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(_) => Vec::new(),
+        };
+        let checkpoint_path = std::env::var("MAINTENANCE_CHECKPOINT_FILE")
+            .unwrap_or_else(|_| "maintenance-checkpoint.json".to_string())
+            .into();
+
+        Ok(Self {
+            windows,
+            checkpoint_path,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.windows.is_empty()
+    }
+
+    pub fn window_count(&self) -> usize {
+        self.windows.len()
+    }
+
+    /// End of the window `now` falls in, if any.
+    fn active_until(&self, now: u64) -> Option<u64> {
+        self.windows
+            .iter()
+            .flat_map(|window| window.occurrences(now))
+            .filter(|(start, end)| *start <= now && now < *end)
+            .map(|(_, end)| end)
+            .max()
+    }
+
+    fn next_start(&self, now: u64) -> Option<u64> {
+        self.windows
+            .iter()
+            .flat_map(|window| window.occurrences(now))
+            .map(|(start, _)| start)
+            .filter(|start| *start > now)
+            .min()
+    }
+
+    fn load_checkpoint(&self) -> Option<u64> {
+        let content = std::fs::read_to_string(&self.checkpoint_path).ok()?;
+        match serde_json::from_str::<Checkpoint>(&content) {
+            Ok(checkpoint) => Some(checkpoint.slot),
+            Err(e) => {
+                log::warn!(
+                    "[MAINTENANCE] Ignoring unreadable checkpoint {}: {}",
+                    self.checkpoint_path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    fn save_checkpoint(&self, slot: u64) {
+        let content = serde_json::to_string(&Checkpoint { slot }).unwrap_or_default();
+        if let Err(e) = std::fs::write(&self.checkpoint_path, content) {
+            log::error!(
+                "[MAINTENANCE] Failed to write checkpoint {}: {}",
+                self.checkpoint_path.display(),
+                e
+            );
+        }
+    }
+
+    fn clear_checkpoint(&self) {
+        match std::fs::remove_file(&self.checkpoint_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => log::error!(
+                "[MAINTENANCE] Failed to remove checkpoint {}: {}",
+                self.checkpoint_path.display(),
+                e
+            ),
+        }
+    }
+
+    /// Pauses `datasource` during the windows and backfills the missed
+    /// blocks from `rpc_http_url` afterwards.
+    pub fn wrap<D>(&self, datasource: D, rpc_http_url: String) -> Maintained<D> {
+        Maintained {
+            schedule: self.clone(),
+            datasource,
+            rpc_http_url,
+            include_failed: false,
+        }
+    }
+}
+
+/// Datasource handle returned by [`MaintenanceWindows::wrap`].
+pub struct Maintained<D> {
+    schedule: MaintenanceWindows,
+    datasource: D,
+    rpc_http_url: String,
+    include_failed: bool,
+}
+
+impl<D> Maintained<D> {
+    /// Backfills failed transactions too, for datasources that hand them to
+    /// the pipeline.
+    pub fn with_failed_transactions(mut self, include_failed: bool) -> Self {
+        self.include_failed = include_failed;
+        self
+    }
+}
+
+#[async_trait]
+impl<D: Datasource> Datasource for Maintained<D> {
+    async fn consume(
+        &self,
+        id: DatasourceId,
+        sender: Sender<(Update, DatasourceId)>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        if !self.schedule.is_enabled() {
+            return self.datasource.consume(id, sender, cancellation_token, metrics).await;
+        }
+
+        let last_slot = Arc::new(AtomicU64::new(0));
+        // A checkpoint left behind means the last backfill never finished
+        let mut pending = self.schedule.load_checkpoint();
+
+        loop {
+            if let Some(end) = self.schedule.active_until(unix_now()) {
+                // Every update of the last slot was forwarded before draining
+                let slot = last_slot.load(Ordering::Relaxed);
+                if slot > 0 {
+                    pending = Some(pending.map_or(slot + 1, |pending| pending.min(slot + 1)));
+                }
+                if let Some(slot) = pending {
+                    self.schedule.save_checkpoint(slot);
+                }
+                log::info!(
+                    "[MAINTENANCE] Paused for {}s, resuming from slot {:?}",
+                    end.saturating_sub(unix_now()),
+                    pending
+                );
+                set_paused_gauge(&metrics, true).await;
+
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => return Ok(()),
+                    _ = tokio::time::sleep(Duration::from_secs(end.saturating_sub(unix_now()))) => {}
+                }
+
+                set_paused_gauge(&metrics, false).await;
+                // Another window may follow right away
+                continue;
+            }
+
+            let live = cancellation_token.child_token();
+            let overlap = pending.is_some().then(|| Arc::new(Overlap::new()));
+            let (forward_sender, mut forward_receiver) = mpsc::channel::<(Update, DatasourceId)>(FORWARD_CHANNEL_SIZE);
+            let forwarder = {
+                let sender = sender.clone();
+                let last_slot = last_slot.clone();
+                let overlap = overlap.clone();
+                tokio::spawn(async move {
+                    while let Some((update, id)) = forward_receiver.recv().await {
+                        if let Update::Transaction(transaction) = &update {
+                            last_slot.fetch_max(transaction.slot, Ordering::Relaxed);
+                            if overlap
+                                .as_ref()
+                                .is_some_and(|overlap| !overlap.first(transaction.slot, transaction.signature))
+                            {
+                                continue;
+                            }
+                        }
+                        if sender.send((update, id)).await.is_err() {
+                            break;
+                        }
+                    }
+                })
+            };
+
+            // Live updates resume first so the backfill only has to reach the
+            // current tip, the overlap is sent once by whichever gets it first
+            let backfill = pending.take().zip(overlap).map(|(from, overlap)| {
+                let schedule = self.schedule.clone();
+                let task = backfill(
+                    self.rpc_http_url.clone(),
+                    from,
+                    overlap,
+                    sender.clone(),
+                    id.clone(),
+                    live.clone(),
+                    metrics.clone(),
+                    self.include_failed,
+                );
+                let task = tokio::spawn(async move {
+                    match task.await {
+                        Ok(true) => {
+                            schedule.clear_checkpoint();
+                            true
+                        }
+                        Ok(false) => false,
+                        Err(e) => {
+                            log::error!("[MAINTENANCE] Backfill failed: {}", e);
+                            false
+                        }
+                    }
+                });
+                (from, task)
+            });
+
+            let until_next = self
+                .schedule
+                .next_start(unix_now())
+                .map_or(SECONDS_PER_DAY, |start| start.saturating_sub(unix_now()));
+
+            tokio::select! {
+                result = self.datasource.consume(id.clone(), forward_sender, live.clone(), metrics.clone()) => {
+                    return result;
+                }
+                _ = tokio::time::sleep(Duration::from_secs(until_next)) => {
+                    log::info!("[MAINTENANCE] Window starting, draining");
+                }
+                _ = cancellation_token.cancelled() => return Ok(()),
+            }
+
+            live.cancel();
+            // Everything received so far still reaches the pipeline
+            let _ = forwarder.await;
+            if let Some((from, task)) = backfill {
+                if !task.await.unwrap_or(false) {
+                    pending = Some(from);
+                }
+            }
+        }
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        self.datasource.update_types()
+    }
+}
+
+async fn set_paused_gauge(metrics: &Arc<MetricsCollection>, paused: bool) {
+    metrics
+        .update_gauge("maintenance_paused", if paused { 1.0 } else { 0.0 })
+        .await
+        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+}
+
+/// Feeds every block from `from` up to the current tip into the pipeline,
+/// skipping the transactions the live datasource already sent. Returns
+/// whether it got through all of them before being cancelled.
+#[allow(clippy::too_many_arguments)]
+async fn backfill(
+    rpc_http_url: String,
+    from: u64,
+    overlap: Arc<Overlap>,
+    sender: Sender<(Update, DatasourceId)>,
+    id: DatasourceId,
+    cancellation_token: CancellationToken,
+    metrics: Arc<MetricsCollection>,
+    include_failed: bool,
+) -> Result<bool, DatasourceError> {
+    let client = RpcClient::new_with_commitment(rpc_http_url, CommitmentConfig::confirmed());
+    let tip = client
+        .get_slot()
+        .await
+        .map_err(|source| DatasourceError::Backfill { from, source })?;
+    overlap.tip.store(tip, Ordering::Relaxed);
+    let config = RpcBlockConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        transaction_details: Some(TransactionDetails::Full),
+        rewards: Some(false),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    log::info!("[MAINTENANCE] Backfilling slots {} to {}", from, tip);
+
+    let mut transactions = 0;
+    let mut start = from;
+    while start <= tip {
+        let end = (start + BACKFILL_RANGE - 1).min(tip);
+        let slots = client
+            .get_blocks(start, Some(end))
+            .await
+            .map_err(|source| DatasourceError::Backfill { from: start, source })?;
+
+        for slot in slots {
+            if cancellation_token.is_cancelled() {
+                log::info!("[MAINTENANCE] Backfill interrupted at slot {}", slot);
+                return Ok(false);
+            }
+
+            let block = match client.get_block_with_config(slot, config).await {
+                Ok(block) => block,
+                Err(source) => {
+                    let err = DatasourceError::BlockFetch { slot, source };
+                    if !err.is_skipped_slot() {
+                        log::error!("{}", err);
+                    }
+                    continue;
+                }
+            };
+
+            let block_hash = Hash::from_str(&block.blockhash).ok();
+            for (index, encoded) in block.transactions.unwrap_or_default().into_iter().enumerate() {
+                let transaction = match transaction_update(slot, block.block_time, block_hash, Some(index as u64), encoded, include_failed) {
+                    Ok(Some(transaction)) if overlap.first(slot, transaction.signature) => transaction,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        record_failure("MAINTENANCE", slot, &e, &metrics).await;
+                        continue;
+                    }
+                };
+                if sender
+                    .send((Update::Transaction(Box::new(transaction)), id.clone()))
+                    .await
+                    .is_err()
+                {
+                    return Ok(false);
+                }
+                transactions += 1;
+            }
+
+            metrics
+                .increment_counter("maintenance_backfilled_blocks", 1)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        }
+
+        start = end + 1;
+    }
+
+    log::info!("[MAINTENANCE] Backfilled {} transactions up to slot {}", transactions, tip);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-07, a Sunday, at midnight UTC.
+    const SUNDAY: u64 = 19_729 * SECONDS_PER_DAY;
+
+    fn windows(specs: &[&str]) -> MaintenanceWindows {
+        MaintenanceWindows {
+            windows: specs.iter().map(|spec| spec.parse().unwrap()).collect(),
+            checkpoint_path: PathBuf::new(),
+        }
+    }
+
+    #[test]
+    fn parses_daily_and_weekly_windows() {
+        let daily = Window::from_str("02:00-02:30").unwrap();
+        assert_eq!((daily.weekday, daily.start, daily.length), (None, 7_200, 1_800));
+
+        let weekly = Window::from_str("Sun 23:30-00:30").unwrap();
+        assert_eq!((weekly.weekday, weekly.start, weekly.length), (Some(0), 84_600, 3_600));
+    }
+
+    #[test]
+    fn rejects_malformed_windows() {
+        for spec in ["02:00", "25:00-02:00", "02:60-03:00", "someday 02:00-03:00", "02:00-02:00"] {
+            assert!(Window::from_str(spec).is_err(), "{} parsed", spec);
+        }
+    }
+
+    #[test]
+    fn a_weekly_window_runs_past_midnight_on_its_day_only() {
+        let windows = windows(&["sun 23:30-00:30"]);
+        let start = SUNDAY + 84_600;
+
+        assert_eq!(windows.active_until(start - 1), None);
+        assert_eq!(windows.next_start(start - 1), Some(start));
+        assert_eq!(windows.active_until(start + 3_000), Some(start + 3_600));
+        assert_eq!(windows.active_until(start + 3_600), None);
+        // The Monday occurrence does not exist, the next one is a week later
+        assert_eq!(windows.active_until(start + SECONDS_PER_DAY), None);
+        assert_eq!(windows.next_start(start + 3_600), Some(start + 7 * SECONDS_PER_DAY));
+    }
+
+    #[test]
+    fn overlapping_windows_end_at_the_latest() {
+        let windows = windows(&["02:00-02:30", "02:15-03:00"]);
+        assert_eq!(windows.active_until(SUNDAY + 8_200), Some(SUNDAY + 10_800));
+    }
+}
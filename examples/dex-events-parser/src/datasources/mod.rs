@@ -1,3 +1,28 @@
+pub mod address_backfill;
+pub mod capture;
+pub mod capture_file;
+pub mod conversion;
+pub mod discovery;
+pub mod error;
+pub mod fan_in;
+pub mod helius_webhook;
 pub mod hybrid_block_datasource;
+pub mod maintenance;
+pub mod pool_accounts;
+pub mod rpc_pool;
+pub mod signature;
+pub mod watch_list;
+pub mod watched_pools_datasource;
 
-pub use hybrid_block_datasource::{HybridBlockDatasource, HybridFilters}; 
\ No newline at end of file
+pub use address_backfill::{AddressBackfill, AddressBackfillDatasource};
+pub use capture::{CaptureTarget, ReplayDatasource, TeeDatasource};
+pub use capture_file::ChunkLimits;
+pub use fan_in::{FanInDatasource, SignatureDedup};
+pub use helius_webhook::{HeliusWebhookConfig, HeliusWebhookDatasource};
+pub use hybrid_block_datasource::{CommitmentStrategy, HybridBlockDatasource, HybridFilters};
+pub use maintenance::MaintenanceWindows;
+pub use pool_accounts::{PoolAccountSubscription, PoolAccountsDatasource};
+pub use rpc_pool::RpcPool;
+pub use signature::SignatureDatasource;
+pub use watch_list::WatchList;
+pub use watched_pools_datasource::WatchedPoolsDatasource;
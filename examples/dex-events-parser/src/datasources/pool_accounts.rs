@@ -0,0 +1,193 @@
+use {
+    super::error::DatasourceError,
+    async_trait::async_trait,
+    carbon_core::{
+        datasource::{AccountUpdate, Datasource, DatasourceId, Update, UpdateType},
+        error::CarbonResult,
+        metrics::MetricsCollection,
+    },
+    futures::StreamExt,
+    solana_account_decoder_client_types::{UiAccount, UiAccountEncoding},
+    solana_client::{
+        nonblocking::pubsub_client::PubsubClient,
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        rpc_filter::RpcFilterType,
+    },
+    solana_commitment_config::CommitmentConfig,
+    solana_pubkey::Pubkey,
+    std::{str::FromStr, sync::Arc, time::Duration},
+    tokio::sync::mpsc::Sender,
+    tokio_util::sync::CancellationToken,
+};
+
+const RECONNECTION_DELAY_MS: u64 = 3000;
+
+/// What a [`PoolAccountsDatasource`] subscribes to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolAccountSubscription {
+    /// A single account over `accountSubscribe`, e.g. a pool or a vault.
+    Account(Pubkey),
+    /// Every account of a program with the given data size over
+    /// `programSubscribe`, e.g. all Whirlpools.
+    Program { program_id: Pubkey, data_size: u64 },
+}
+
+/// Emits account updates of pool state: pool accounts holding the price of
+/// concentrated liquidity pools and the token vaults holding AMM reserves.
+///
+/// Every subscription gets its own WebSocket connection and reconnects on
+/// its own when it drops.
+pub struct PoolAccountsDatasource {
+    pub rpc_ws_url: String,
+    pub subscriptions: Vec<PoolAccountSubscription>,
+    pub commitment: CommitmentConfig,
+}
+
+impl PoolAccountsDatasource {
+    pub fn new(rpc_ws_url: String, subscriptions: Vec<PoolAccountSubscription>, commitment: CommitmentConfig) -> Self {
+        Self {
+            rpc_ws_url,
+            subscriptions,
+            commitment,
+        }
+    }
+}
+
+#[async_trait]
+impl Datasource for PoolAccountsDatasource {
+    async fn consume(
+        &self,
+        id: DatasourceId,
+        sender: Sender<(Update, DatasourceId)>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        log::info!("Starting Pool Accounts Datasource with {} subscriptions...", self.subscriptions.len());
+        metrics
+            .update_gauge("pool_account_subscriptions", self.subscriptions.len() as f64)
+            .await
+            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+
+        futures::future::join_all(self.subscriptions.iter().map(|subscription| {
+            subscribe(
+                &self.rpc_ws_url,
+                subscription,
+                self.commitment,
+                &sender,
+                &id,
+                &cancellation_token,
+                &metrics,
+            )
+        }))
+        .await;
+        Ok(())
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        vec![UpdateType::AccountUpdate]
+    }
+}
+
+/// Follows one subscription until cancelled or the pipeline stops.
+async fn subscribe(
+    rpc_ws_url: &str,
+    subscription: &PoolAccountSubscription,
+    commitment: CommitmentConfig,
+    sender: &Sender<(Update, DatasourceId)>,
+    id: &DatasourceId,
+    cancellation_token: &CancellationToken,
+    metrics: &MetricsCollection,
+) {
+    let account_config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(commitment),
+        ..RpcAccountInfoConfig::default()
+    };
+
+    while !cancellation_token.is_cancelled() {
+        let client = match PubsubClient::new(rpc_ws_url).await {
+            Ok(client) => client,
+            Err(source) => {
+                log::error!("{}", DatasourceError::Connection { url: rpc_ws_url.to_string(), source });
+                tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
+                continue;
+            }
+        };
+
+        // Both streams are mapped to the account, its address and the slot
+        let subscribed = match subscription {
+            PoolAccountSubscription::Account(pubkey) => client
+                .account_subscribe(pubkey, Some(account_config.clone()))
+                .await
+                .map(|(stream, unsubscribe)| {
+                    let pubkey = *pubkey;
+                    (
+                        stream
+                            .map(move |response| (pubkey.to_string(), response.value, response.context.slot))
+                            .boxed(),
+                        unsubscribe,
+                    )
+                }),
+            PoolAccountSubscription::Program { program_id, data_size } => client
+                .program_subscribe(
+                    program_id,
+                    Some(RpcProgramAccountsConfig {
+                        filters: Some(vec![RpcFilterType::DataSize(*data_size)]),
+                        account_config: account_config.clone(),
+                        ..RpcProgramAccountsConfig::default()
+                    }),
+                )
+                .await
+                .map(|(stream, unsubscribe)| {
+                    (
+                        stream
+                            .map(|response| (response.value.pubkey, response.value.account, response.context.slot))
+                            .boxed(),
+                        unsubscribe,
+                    )
+                }),
+        };
+        let (mut account_stream, _unsub) = match subscribed {
+            Ok(subscribed) => subscribed,
+            Err(err) => {
+                log::error!("{} ({:?})", DatasourceError::Subscription(err), subscription);
+                tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
+                continue;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = cancellation_token.cancelled() => return,
+                account_event = account_stream.next() => {
+                    let Some((pubkey, account, slot)) = account_event else {
+                        log::warn!("Account stream for {:?} closed, reconnecting...", subscription);
+                        break;
+                    };
+                    let Some(update) = account_update(&pubkey, account, slot) else {
+                        log::error!("Failed to decode account {} in slot {}", pubkey, slot);
+                        continue;
+                    };
+
+                    if sender.send((update, id.clone())).await.is_err() {
+                        return;
+                    }
+                    metrics
+                        .increment_counter("pool_account_updates_processed", 1)
+                        .await
+                        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                }
+            }
+        }
+
+        tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
+    }
+}
+
+fn account_update(pubkey: &str, account: UiAccount, slot: u64) -> Option<Update> {
+    Some(Update::Account(AccountUpdate {
+        pubkey: Pubkey::from_str(pubkey).ok()?,
+        account: account.decode()?,
+        slot,
+    }))
+}
@@ -0,0 +1,159 @@
+use {
+    carbon_core::metrics::MetricsCollection,
+    futures::future::BoxFuture,
+    solana_client::{
+        client_error::{ClientErrorKind, Result as ClientResult},
+        nonblocking::rpc_client::RpcClient,
+        rpc_request::RpcError,
+    },
+    solana_commitment_config::CommitmentConfig,
+    std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// JSON-RPC error codes some providers answer with instead of HTTP 429.
+const RATE_LIMIT_CODES: [i64; 2] = [429, -32429];
+
+struct Endpoint {
+    /// Scheme and host only, URLs often carry an API key.
+    label: String,
+    client: RpcClient,
+    state: Mutex<EndpointState>,
+}
+
+#[derive(Default)]
+struct EndpointState {
+    backoff_until: Option<Instant>,
+    /// Rate limits in a row, the backoff doubles with each.
+    strikes: u32,
+    /// Time the client spent waiting on 429s so far, an increase means the
+    /// last request was rate limited even if it eventually succeeded.
+    rate_limited_time: Duration,
+}
+
+/// HTTP RPC endpoints requests are spread over round robin. An endpoint that
+/// rate limits is skipped for a while, one second doubling up to a minute
+/// while it keeps doing so.
+pub struct RpcPool {
+    endpoints: Vec<Endpoint>,
+    next: AtomicUsize,
+}
+
+impl RpcPool {
+    /// `urls` must not be empty.
+    pub fn new(urls: &[String], commitment: CommitmentConfig) -> Self {
+        assert!(!urls.is_empty(), "RpcPool needs at least one endpoint");
+        Self {
+            endpoints: urls
+                .iter()
+                .map(|url| Endpoint {
+                    label: endpoint_label(url),
+                    client: RpcClient::new_with_commitment(url.clone(), commitment),
+                    state: Mutex::new(EndpointState::default()),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn labels(&self) -> Vec<&str> {
+        self.endpoints.iter().map(|endpoint| endpoint.label.as_str()).collect()
+    }
+
+    /// Runs `request` on the next endpoint that is not backing off, and on
+    /// the following ones while it is rate limited. When every endpoint is
+    /// backing off, waits for the first to be available again.
+    pub async fn call<T>(
+        &self,
+        request: impl for<'c> Fn(&'c RpcClient) -> BoxFuture<'c, ClientResult<T>>,
+        metrics: &MetricsCollection,
+    ) -> ClientResult<T> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let endpoint = self.pick().await;
+            let result = request(&endpoint.client).await;
+
+            let retried_429s = {
+                let stats = endpoint.client.get_transport_stats();
+                let mut state = endpoint.state.lock().unwrap();
+                let increased = stats.rate_limited_time > state.rate_limited_time;
+                state.rate_limited_time = stats.rate_limited_time;
+                increased
+            };
+            let rejected = matches!(&result, Err(e) if is_rate_limited(e.kind()));
+            if !retried_429s && !rejected {
+                endpoint.state.lock().unwrap().strikes = 0;
+                return result;
+            }
+
+            let backoff = {
+                let mut state = endpoint.state.lock().unwrap();
+                let backoff = INITIAL_BACKOFF
+                    .saturating_mul(2u32.saturating_pow(state.strikes))
+                    .min(MAX_BACKOFF);
+                state.strikes = state.strikes.saturating_add(1);
+                state.backoff_until = Some(Instant::now() + backoff);
+                backoff
+            };
+            log::warn!("RPC endpoint {} is rate limiting, skipping it for {:?}", endpoint.label, backoff);
+            metrics
+                .increment_counter("rpc_endpoint_rate_limited", 1)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+
+            if !rejected || attempt >= self.endpoints.len() {
+                return result;
+            }
+        }
+    }
+
+    async fn pick(&self) -> &Endpoint {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let now = Instant::now();
+        let mut soonest: Option<(Instant, &Endpoint)> = None;
+
+        for offset in 0..self.endpoints.len() {
+            let endpoint = &self.endpoints[(start + offset) % self.endpoints.len()];
+            let backoff_until = endpoint.state.lock().unwrap().backoff_until;
+            match backoff_until {
+                Some(until) if until > now => {
+                    if soonest.is_none_or(|(soonest, _)| until < soonest) {
+                        soonest = Some((until, endpoint));
+                    }
+                }
+                _ => return endpoint,
+            }
+        }
+
+        // Every endpoint is backing off
+        let (until, endpoint) = soonest.expect("RpcPool has at least one endpoint");
+        tokio::time::sleep_until(tokio::time::Instant::from_std(until)).await;
+        endpoint
+    }
+}
+
+fn is_rate_limited(kind: &ClientErrorKind) -> bool {
+    match kind {
+        ClientErrorKind::Reqwest(e) => e.status().is_some_and(|status| status.as_u16() == 429),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => RATE_LIMIT_CODES.contains(code),
+        _ => false,
+    }
+}
+
+fn endpoint_label(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let host = rest.split(['/', '?']).next().unwrap_or(rest);
+    if scheme.is_empty() {
+        host.to_string()
+    } else {
+        format!("{}://{}", scheme, host)
+    }
+}
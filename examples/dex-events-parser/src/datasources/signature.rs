@@ -0,0 +1,94 @@
+use {
+    super::{
+        conversion::{record_failure, transaction_update},
+        error::DatasourceError,
+    },
+    async_trait::async_trait,
+    carbon_core::{
+        datasource::{Datasource, DatasourceId, Update, UpdateType},
+        error::CarbonResult,
+        metrics::MetricsCollection,
+    },
+    solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig},
+    solana_commitment_config::CommitmentConfig,
+    solana_signature::Signature,
+    solana_transaction_status::UiTransactionEncoding,
+    std::sync::Arc,
+    tokio::sync::mpsc::Sender,
+    tokio_util::sync::CancellationToken,
+};
+
+/// Fetches one historical transaction over HTTP RPC and emits it, then
+/// finishes, which ends the pipeline once the transaction is processed.
+pub struct SignatureDatasource {
+    rpc_http_url: String,
+    signature: Signature,
+    include_failed: bool,
+}
+
+impl SignatureDatasource {
+    pub fn new(rpc_http_url: String, signature: Signature) -> Self {
+        Self {
+            rpc_http_url,
+            signature,
+            include_failed: false,
+        }
+    }
+
+    /// Replays the transaction even if it failed on-chain.
+    pub fn with_failed_transactions(mut self, include_failed: bool) -> Self {
+        self.include_failed = include_failed;
+        self
+    }
+}
+
+#[async_trait]
+impl Datasource for SignatureDatasource {
+    async fn consume(
+        &self,
+        id: DatasourceId,
+        sender: Sender<(Update, DatasourceId)>,
+        _cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let client = RpcClient::new_with_commitment(self.rpc_http_url.clone(), CommitmentConfig::confirmed());
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        let transaction = client
+            .get_transaction_with_config(&self.signature, config)
+            .await
+            .map_err(|source| DatasourceError::TransactionFetch {
+                signature: self.signature.to_string(),
+                source,
+            })?;
+
+        // The block hash is not part of the response
+        let update = match transaction_update(transaction.slot, transaction.block_time, None, None, transaction.transaction, self.include_failed) {
+            Ok(Some(update)) => update,
+            Ok(None) => {
+                log::warn!(
+                    "Transaction {} failed, set INCLUDE_FAILED_TRANSACTIONS to replay it",
+                    self.signature
+                );
+                return Ok(());
+            }
+            Err(e) => {
+                record_failure("REPLAY", transaction.slot, &e, &metrics).await;
+                return Ok(());
+            }
+        };
+        log::info!("Replaying transaction {} from slot {}", self.signature, transaction.slot);
+
+        sender
+            .send((Update::Transaction(Box::new(update)), id))
+            .await
+            .map_err(|e| carbon_core::error::Error::Custom(format!("Failed to send transaction update: {}", e)))
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        vec![UpdateType::Transaction]
+    }
+}
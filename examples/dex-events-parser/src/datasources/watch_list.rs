@@ -0,0 +1,237 @@
+use {
+    crate::shard::Shard,
+    carbon_core::{datasource::DatasourceId, filter::Filter, instruction::NestedInstruction},
+    solana_instruction::Instruction,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashSet,
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{Arc, RwLock},
+        time::{Duration, SystemTime},
+    },
+    tokio::sync::watch,
+};
+
+const DEFAULT_RELOAD_INTERVAL_SECS: u64 = 5;
+/// Prefix of Anchor `emit_cpi!` event instructions.
+const ANCHOR_EVENT_IX_TAG: [u8; 8] = [0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d];
+
+/// Pool addresses the parser is restricted to in watch-only mode.
+///
+/// The list is loaded from a file with one address per line (blank lines and
+/// `#` comments are ignored) and reloaded whenever the file changes. Every
+/// change is broadcast so the datasource can adjust its subscriptions.
+/// Pools added at runtime by discovery are kept across reloads. When
+/// sharding by pool, every instance loads the same file and follows only
+/// the pools its shard owns.
+#[derive(Clone)]
+pub struct WatchList {
+    pools: Arc<RwLock<HashSet<Pubkey>>>,
+    discovered: Arc<RwLock<HashSet<Pubkey>>>,
+    changes: watch::Sender<u64>,
+    path: PathBuf,
+    reload_interval: Duration,
+    shard: Shard,
+}
+
+impl WatchList {
+    pub fn load(path: impl Into<PathBuf>, reload_interval: Duration, shard: Shard) -> std::io::Result<Self> {
+        let path = path.into();
+        let pools = read_pools(&path)?;
+        log::info!("Watching {} pools from {}", pools.len(), path.display());
+
+        Ok(Self {
+            pools: Arc::new(RwLock::new(pools)),
+            discovered: Arc::default(),
+            changes: watch::Sender::new(0),
+            path,
+            reload_interval,
+            shard,
+        })
+    }
+
+    /// Reads `WATCH_POOLS_FILE` and `WATCH_POOLS_RELOAD_SECS` (default `5`).
+    pub fn from_env(shard: Shard) -> Result<Self, String> {
+        let path = std::env::var("WATCH_POOLS_FILE")
+            .map_err(|_| "WATCH_POOLS_FILE must be set in watch mode".to_string())?;
+        let reload_secs = std::env::var("WATCH_POOLS_RELOAD_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_RELOAD_INTERVAL_SECS);
+
+        Self::load(&path, Duration::from_secs(reload_secs.max(1)), shard)
+            .map_err(|e| format!("Failed to read watch list {}: {}", path, e))
+    }
+
+    /// Pools this instance follows.
+    pub fn pools(&self) -> HashSet<Pubkey> {
+        let mut pools = self
+            .pools
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|pool| self.shard.owns_pool(pool))
+            .copied()
+            .collect::<HashSet<_>>();
+        pools.extend(self.discovered.read().unwrap_or_else(|e| e.into_inner()).iter());
+        pools
+    }
+
+    pub fn contains(&self, pool: &Pubkey) -> bool {
+        self.pools.read().unwrap_or_else(|e| e.into_inner()).contains(pool)
+            || self.discovered.read().unwrap_or_else(|e| e.into_inner()).contains(pool)
+    }
+
+    /// Starts watching `pool` until the process exits. Returns `false` if the
+    /// pool was already watched or belongs to another shard.
+    pub fn add(&self, pool: Pubkey) -> bool {
+        if !self.shard.owns_pool(&pool)
+            || self.contains(&pool)
+            || !self.discovered.write().unwrap_or_else(|e| e.into_inner()).insert(pool)
+        {
+            return false;
+        }
+
+        self.changes.send_modify(|version| *version += 1);
+        true
+    }
+
+    /// Notified whenever the set of pools changes.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    fn replace(&self, pools: HashSet<Pubkey>) {
+        let mut current = self.pools.write().unwrap_or_else(|e| e.into_inner());
+        if *current == pools {
+            return;
+        }
+
+        log::info!(
+            "Watch list reloaded: {} pools ({} added, {} removed)",
+            pools.len(),
+            pools.difference(&current).count(),
+            current.difference(&pools).count()
+        );
+        *current = pools;
+        drop(current);
+        self.changes.send_modify(|version| *version += 1);
+    }
+
+    /// Polls the watch list file and reloads it when its modification time
+    /// changes. A file that fails to parse keeps the previous list.
+    pub fn spawn_reloader(&self) -> tokio::task::JoinHandle<()> {
+        let watch_list = self.clone();
+
+        tokio::spawn(async move {
+            let mut last_modified = modified(&watch_list.path);
+            let mut interval = tokio::time::interval(watch_list.reload_interval);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                let modified = modified(&watch_list.path);
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                match read_pools(&watch_list.path) {
+                    Ok(pools) => watch_list.replace(pools),
+                    Err(e) => log::error!(
+                        "Failed to reload watch list {}: {}",
+                        watch_list.path.display(),
+                        e
+                    ),
+                }
+            }
+        })
+    }
+
+    /// Pipeline filter that drops instructions not touching a watched pool.
+    pub fn filters(&self) -> Vec<Box<dyn Filter + Send + Sync + 'static>> {
+        vec![Box::new(WatchListFilter {
+            watch_list: self.clone(),
+            new_pool: None,
+        })]
+    }
+
+    /// Like [`WatchList::filters`], but also lets through the instructions
+    /// creating a pool, as returned by `new_pool`, so new pools can be
+    /// discovered.
+    pub fn filters_with_discovery(
+        &self,
+        new_pool: fn(&Instruction) -> Option<Pubkey>,
+    ) -> Vec<Box<dyn Filter + Send + Sync + 'static>> {
+        vec![Box::new(WatchListFilter {
+            watch_list: self.clone(),
+            new_pool: Some(new_pool),
+        })]
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn read_pools(path: &Path) -> std::io::Result<HashSet<Pubkey>> {
+    let contents = std::fs::read_to_string(path)?;
+
+    Ok(contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| match Pubkey::from_str(line) {
+            Ok(pool) => Some(pool),
+            Err(_) => {
+                log::warn!("Ignoring invalid pool address in watch list: {}", line);
+                None
+            }
+        })
+        .collect())
+}
+
+struct WatchListFilter {
+    watch_list: WatchList,
+    new_pool: Option<fn(&Instruction) -> Option<Pubkey>>,
+}
+
+impl Filter for WatchListFilter {
+    fn filter_instruction(
+        &self,
+        _datasource_id: &DatasourceId,
+        nested_instruction: &NestedInstruction,
+    ) -> bool {
+        let instruction = &nested_instruction.instruction;
+        if let Some(pool) = self.new_pool.and_then(|new_pool| new_pool(instruction)) {
+            return self.watch_list.shard.owns_pool(&pool);
+        }
+
+        let pools = self.watch_list.pools.read().unwrap_or_else(|e| e.into_inner());
+        let discovered = self.watch_list.discovered.read().unwrap_or_else(|e| e.into_inner());
+        let watched = |pubkey: &Pubkey| pools.contains(pubkey) || discovered.contains(pubkey);
+
+        // Event CPIs only pass the event authority, the pool is part of the
+        // serialized event.
+        let pool = instruction
+            .accounts
+            .iter()
+            .map(|account| account.pubkey)
+            .find(watched)
+            .or_else(|| {
+                instruction
+                    .data
+                    .strip_prefix(&ANCHOR_EVENT_IX_TAG)?
+                    .windows(32)
+                    .filter_map(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .map(Pubkey::new_from_array)
+                    .find(watched)
+            });
+
+        // An instruction touching pools of several shards, such as an
+        // aggregator route, goes to the shard of the first one
+        pool.is_some_and(|pool| self.watch_list.shard.owns_pool(&pool))
+    }
+}
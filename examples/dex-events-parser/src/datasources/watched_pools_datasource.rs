@@ -0,0 +1,252 @@
+use {
+    super::{
+        conversion::{record_failure, transaction_update},
+        error::DatasourceError,
+        fan_in::Recent,
+        watch_list::WatchList,
+    },
+    async_trait::async_trait,
+    carbon_core::{
+        datasource::{Datasource, DatasourceId, Update, UpdateType},
+        error::CarbonResult,
+        metrics::MetricsCollection,
+    },
+    futures::StreamExt,
+    solana_client::{
+        nonblocking::pubsub_client::PubsubClient,
+        rpc_config::{RpcBlockSubscribeConfig, RpcBlockSubscribeFilter},
+    },
+    solana_commitment_config::CommitmentConfig,
+    solana_hash::Hash,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_transaction_status::{TransactionDetails, UiTransactionEncoding},
+    std::{
+        collections::HashMap,
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+    tokio::sync::mpsc::Sender,
+    tokio_util::sync::CancellationToken,
+};
+
+const RECONNECTION_DELAY_MS: u64 = 3000;
+const RECENT_SIGNATURES_CAPACITY: usize = 10_000;
+
+/// Block subscription datasource restricted to the pools of a [`WatchList`].
+///
+/// Instead of one subscription per program, every watched pool gets its own
+/// `blockSubscribe` mentioning the pool account, so the RPC node only sends
+/// transactions touching those pools. Subscriptions follow watch list
+/// changes. Programs added with [`WatchedPoolsDatasource::with_programs`]
+/// are followed as a whole, e.g. to discover new pools.
+pub struct WatchedPoolsDatasource {
+    pub rpc_ws_url: String,
+    pub watch_list: WatchList,
+    pub programs: Vec<Pubkey>,
+    pub commitment: CommitmentConfig,
+    pub include_failed: bool,
+}
+
+impl WatchedPoolsDatasource {
+    pub fn new(rpc_ws_url: String, watch_list: WatchList, commitment: CommitmentConfig) -> Self {
+        Self {
+            rpc_ws_url,
+            watch_list,
+            programs: Vec::new(),
+            commitment,
+            include_failed: false,
+        }
+    }
+
+    /// Emits transactions that failed on-chain too, flagged by their
+    /// metadata status.
+    pub fn with_failed_transactions(mut self, include_failed: bool) -> Self {
+        self.include_failed = include_failed;
+        self
+    }
+
+    pub fn with_programs(mut self, programs: Vec<Pubkey>) -> Self {
+        self.programs = programs;
+        self
+    }
+}
+
+#[async_trait]
+impl Datasource for WatchedPoolsDatasource {
+    async fn consume(
+        &self,
+        id: DatasourceId,
+        sender: Sender<(Update, DatasourceId)>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        log::info!("Starting Watched Pools Datasource...");
+
+        let recent_signatures = Arc::new(Mutex::new(Recent::new(RECENT_SIGNATURES_CAPACITY)));
+        let mut changes = self.watch_list.subscribe();
+        let mut subscriptions: HashMap<Pubkey, CancellationToken> = HashMap::new();
+
+        loop {
+            let mut accounts = self.watch_list.pools();
+            accounts.extend(self.programs.iter().copied());
+
+            subscriptions.retain(|account, token| {
+                if accounts.contains(account) {
+                    return true;
+                }
+                log::info!("Unsubscribing from {}", account);
+                token.cancel();
+                false
+            });
+
+            for account in accounts {
+                if subscriptions.contains_key(&account) {
+                    continue;
+                }
+                log::info!("Subscribing to {}", account);
+                let token = cancellation_token.child_token();
+                tokio::spawn(subscribe_account(
+                    self.rpc_ws_url.clone(),
+                    account,
+                    self.commitment,
+                    self.include_failed,
+                    sender.clone(),
+                    id.clone(),
+                    recent_signatures.clone(),
+                    token.clone(),
+                    metrics.clone(),
+                ));
+                subscriptions.insert(account, token);
+            }
+
+            metrics
+                .update_gauge("watched_pool_subscriptions", subscriptions.len() as f64)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+
+            tokio::select! {
+                _ = cancellation_token.cancelled() => {
+                    log::info!("Watched Pools Datasource cancelled");
+                    break;
+                }
+                changed = changes.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        vec![UpdateType::Transaction]
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn subscribe_account(
+    rpc_ws_url: String,
+    account: Pubkey,
+    commitment: CommitmentConfig,
+    include_failed: bool,
+    sender: Sender<(Update, DatasourceId)>,
+    id: DatasourceId,
+    recent_signatures: Arc<Mutex<Recent<Signature>>>,
+    cancellation_token: CancellationToken,
+    metrics: Arc<MetricsCollection>,
+) {
+    let block_filter = RpcBlockSubscribeFilter::MentionsAccountOrProgram(account.to_string());
+    let block_subscribe_config = RpcBlockSubscribeConfig {
+        commitment: Some(commitment),
+        encoding: Some(UiTransactionEncoding::Base64),
+        transaction_details: Some(TransactionDetails::Full),
+        show_rewards: Some(false),
+        max_supported_transaction_version: Some(0),
+    };
+
+    while !cancellation_token.is_cancelled() {
+        let client = match PubsubClient::new(&rpc_ws_url).await {
+            Ok(client) => client,
+            Err(source) => {
+                log::error!("{}", DatasourceError::Connection { url: rpc_ws_url.clone(), source });
+                tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
+                continue;
+            }
+        };
+
+        let (mut block_stream, _unsub) = match client
+            .block_subscribe(block_filter.clone(), Some(block_subscribe_config.clone()))
+            .await
+        {
+            Ok(subscription) => subscription,
+            Err(err) => {
+                log::error!("{} ({})", DatasourceError::Subscription(err), account);
+                tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
+                continue;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = cancellation_token.cancelled() => return,
+                block_event = block_stream.next() => {
+                    let Some(event) = block_event else {
+                        log::warn!("Block stream for {} closed, reconnecting...", account);
+                        break;
+                    };
+                    let slot = event.context.slot;
+                    let Some(block) = event.value.block else {
+                        continue;
+                    };
+                    let block_hash = Hash::from_str(&block.blockhash).ok();
+
+                    for (index, encoded_transaction_with_status_meta) in
+                        block.transactions.unwrap_or_default().into_iter().enumerate()
+                    {
+                        let transaction = match transaction_update(
+                            slot,
+                            block.block_time,
+                            block_hash,
+                            Some(index as u64),
+                            encoded_transaction_with_status_meta,
+                            include_failed,
+                        ) {
+                            Ok(Some(transaction)) => transaction,
+                            Ok(None) => continue,
+                            Err(e) => {
+                                record_failure("WATCH", slot, &e, &metrics).await;
+                                continue;
+                            }
+                        };
+
+                        if !recent_signatures
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .insert(transaction.signature)
+                        {
+                            continue;
+                        }
+
+                        let update = Update::Transaction(Box::new(transaction));
+
+                        if let Err(err) = sender.send((update, id.clone())).await {
+                            log::error!("Failed to send transaction update: {}", err);
+                            return;
+                        }
+
+                        metrics
+                            .increment_counter("watched_pool_transactions_processed", 1)
+                            .await
+                            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                    }
+                }
+            }
+        }
+
+        tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
+    }
+}
@@ -0,0 +1,221 @@
+use {super::args::Args, solana_pubkey::Pubkey};
+
+/// Invariant protocol v2, a concentrated-liquidity AMM.
+pub const PROGRAM_ID: Pubkey = Pubkey::from_str_const("HyaB3W9q6XdA5xwpU4XnSZV94htfmbmqJXZcEbRaJutt");
+
+/// Anchor discriminators of the decoded instructions.
+const SWAP_DISCRIMINATOR: [u8; 8] = [0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8];
+const CREATE_POSITION_DISCRIMINATOR: [u8; 8] = [0x30, 0xd7, 0xc5, 0x99, 0x60, 0xcb, 0xb4, 0x85];
+const REMOVE_POSITION_DISCRIMINATOR: [u8; 8] = [0xdb, 0x18, 0xec, 0x6e, 0x8a, 0x50, 0x81, 0x06];
+const CREATE_POOL_DISCRIMINATOR: [u8; 8] = [0xe9, 0x92, 0xd1, 0x8e, 0xcf, 0x68, 0x40, 0xbc];
+
+/// Decoder for the Invariant CLMM program.
+///
+/// Only swaps, position changes and pool creation are decoded. Every other
+/// instruction (fee claims, tick and reserve management, admin) is surfaced
+/// as `Unsupported` with its discriminator.
+pub struct InvariantDecoder;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct InvariantSwap {
+    pub x_to_y: bool,
+    pub amount: u64,
+    pub by_amount_in: bool,
+    pub sqrt_price_limit: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct InvariantCreatePosition {
+    pub lower_tick: i32,
+    pub upper_tick: i32,
+    pub liquidity_delta: u128,
+    pub slippage_limit_lower: u128,
+    pub slippage_limit_upper: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct InvariantRemovePosition {
+    pub index: u32,
+    pub lower_tick: i32,
+    pub upper_tick: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct InvariantCreatePool {
+    pub init_tick: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub enum InvariantInstruction {
+    Swap(InvariantSwap),
+    CreatePosition(InvariantCreatePosition),
+    RemovePosition(InvariantRemovePosition),
+    CreatePool(InvariantCreatePool),
+    Unsupported { discriminator: [u8; 8] },
+}
+
+impl carbon_core::instruction::InstructionDecoder<'_> for InvariantDecoder {
+    type InstructionType = InvariantInstruction;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<carbon_core::instruction::DecodedInstruction<Self::InstructionType>> {
+        if instruction.program_id != PROGRAM_ID {
+            return None;
+        }
+        let discriminator: [u8; 8] = instruction.data.get(..8)?.try_into().ok()?;
+        let mut args = Args(&instruction.data[8..]);
+
+        let data = match discriminator {
+            SWAP_DISCRIMINATOR => InvariantInstruction::Swap(InvariantSwap {
+                x_to_y: args.bool()?,
+                amount: args.u64()?,
+                by_amount_in: args.bool()?,
+                sqrt_price_limit: args.u128()?,
+            }),
+            CREATE_POSITION_DISCRIMINATOR => InvariantInstruction::CreatePosition(InvariantCreatePosition {
+                lower_tick: args.i32()?,
+                upper_tick: args.i32()?,
+                liquidity_delta: args.u128()?,
+                slippage_limit_lower: args.u128()?,
+                slippage_limit_upper: args.u128()?,
+            }),
+            REMOVE_POSITION_DISCRIMINATOR => InvariantInstruction::RemovePosition(InvariantRemovePosition {
+                index: args.u32()?,
+                lower_tick: args.i32()?,
+                upper_tick: args.i32()?,
+            }),
+            CREATE_POOL_DISCRIMINATOR => InvariantInstruction::CreatePool(InvariantCreatePool {
+                init_tick: args.i32()?,
+            }),
+            _ => InvariantInstruction::Unsupported { discriminator },
+        };
+
+        Some(carbon_core::instruction::DecodedInstruction {
+            program_id: instruction.program_id,
+            data,
+            accounts: instruction.accounts.clone(),
+        })
+    }
+}
+
+pub struct InvariantSwapAccounts {
+    pub pool: Pubkey,
+    pub token_x: Pubkey,
+    pub token_y: Pubkey,
+    pub account_x: Pubkey,
+    pub account_y: Pubkey,
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+    pub owner: Pubkey,
+}
+
+impl carbon_core::deserialize::ArrangeAccounts for InvariantSwap {
+    type ArrangedAccounts = InvariantSwapAccounts;
+
+    fn arrange_accounts(accounts: &[solana_instruction::AccountMeta]) -> Option<Self::ArrangedAccounts> {
+        let [_state, pool, _tickmap, token_x, token_y, account_x, account_y, reserve_x, reserve_y, owner, _remaining @ ..] =
+            accounts
+        else {
+            return None;
+        };
+
+        Some(InvariantSwapAccounts {
+            pool: pool.pubkey,
+            token_x: token_x.pubkey,
+            token_y: token_y.pubkey,
+            account_x: account_x.pubkey,
+            account_y: account_y.pubkey,
+            reserve_x: reserve_x.pubkey,
+            reserve_y: reserve_y.pubkey,
+            owner: owner.pubkey,
+        })
+    }
+}
+
+/// Accounts shared by position creation and removal, which list them at
+/// different offsets.
+pub struct InvariantPositionAccounts {
+    pub position: Pubkey,
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub token_x: Pubkey,
+    pub token_y: Pubkey,
+    pub account_x: Pubkey,
+    pub account_y: Pubkey,
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+}
+
+impl carbon_core::deserialize::ArrangeAccounts for InvariantCreatePosition {
+    type ArrangedAccounts = InvariantPositionAccounts;
+
+    fn arrange_accounts(accounts: &[solana_instruction::AccountMeta]) -> Option<Self::ArrangedAccounts> {
+        let [_state, position, pool, _position_list, _payer, owner, _lower_tick, _upper_tick, _tickmap, token_x, token_y, account_x, account_y, reserve_x, reserve_y, _remaining @ ..] =
+            accounts
+        else {
+            return None;
+        };
+
+        Some(InvariantPositionAccounts {
+            position: position.pubkey,
+            pool: pool.pubkey,
+            owner: owner.pubkey,
+            token_x: token_x.pubkey,
+            token_y: token_y.pubkey,
+            account_x: account_x.pubkey,
+            account_y: account_y.pubkey,
+            reserve_x: reserve_x.pubkey,
+            reserve_y: reserve_y.pubkey,
+        })
+    }
+}
+
+impl carbon_core::deserialize::ArrangeAccounts for InvariantRemovePosition {
+    type ArrangedAccounts = InvariantPositionAccounts;
+
+    fn arrange_accounts(accounts: &[solana_instruction::AccountMeta]) -> Option<Self::ArrangedAccounts> {
+        let [_state, removed_position, _position_list, _last_position, pool, _tickmap, _lower_tick, _upper_tick, owner, token_x, token_y, account_x, account_y, reserve_x, reserve_y, _remaining @ ..] =
+            accounts
+        else {
+            return None;
+        };
+
+        Some(InvariantPositionAccounts {
+            position: removed_position.pubkey,
+            pool: pool.pubkey,
+            owner: owner.pubkey,
+            token_x: token_x.pubkey,
+            token_y: token_y.pubkey,
+            account_x: account_x.pubkey,
+            account_y: account_y.pubkey,
+            reserve_x: reserve_x.pubkey,
+            reserve_y: reserve_y.pubkey,
+        })
+    }
+}
+
+pub struct InvariantCreatePoolAccounts {
+    pub pool: Pubkey,
+    pub fee_tier: Pubkey,
+    pub token_x: Pubkey,
+    pub token_y: Pubkey,
+}
+
+impl carbon_core::deserialize::ArrangeAccounts for InvariantCreatePool {
+    type ArrangedAccounts = InvariantCreatePoolAccounts;
+
+    fn arrange_accounts(accounts: &[solana_instruction::AccountMeta]) -> Option<Self::ArrangedAccounts> {
+        let [_state, pool, fee_tier, _tickmap, token_x, token_y, _remaining @ ..] = accounts else {
+            return None;
+        };
+
+        Some(InvariantCreatePoolAccounts {
+            pool: pool.pubkey,
+            fee_tier: fee_tier.pubkey,
+            token_x: token_x.pubkey,
+            token_y: token_y.pubkey,
+        })
+    }
+}
@@ -0,0 +1,71 @@
+use crate::processors::jupiter::JupiterProgramVersion;
+
+/// Anchor discriminator of the `route` instruction, shared by v4 and v6.
+const ROUTE_DISCRIMINATOR: [u8; 8] = [0xe5, 0x17, 0xcb, 0x97, 0x7a, 0xe3, 0xad, 0x2a];
+
+/// Size of the fixed `in_amount: u64, minimum_out_amount: u64,
+/// platform_fee_bps: u8` tail that follows the variable-length swap leg tree
+/// of a v4 `route` instruction.
+const V4_ROUTE_TAIL_LEN: usize = 8 + 8 + 1;
+
+/// Decoder for the pre-v6 Jupiter programs.
+///
+/// Only the v4 `route` instruction is decoded, and only its fixed-size tail:
+/// the swap leg tree in front of it has a layout of its own that differs from
+/// the v6 route plan. Every other legacy instruction is surfaced as
+/// `Unsupported` so the processor can count it instead of dropping it.
+pub struct JupiterLegacyDecoder;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JupiterLegacyRoute {
+    pub version: JupiterProgramVersion,
+    pub in_amount: u64,
+    pub minimum_out_amount: u64,
+    pub platform_fee_bps: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JupiterLegacyInstruction {
+    Route(JupiterLegacyRoute),
+    Unsupported {
+        version: JupiterProgramVersion,
+        discriminator: [u8; 8],
+    },
+}
+
+impl carbon_core::instruction::InstructionDecoder<'_> for JupiterLegacyDecoder {
+    type InstructionType = JupiterLegacyInstruction;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<carbon_core::instruction::DecodedInstruction<Self::InstructionType>> {
+        let version = JupiterProgramVersion::from_program_id(&instruction.program_id)
+            .filter(|version| JupiterProgramVersion::LEGACY.contains(version))?;
+        let discriminator: [u8; 8] = instruction.data.get(..8)?.try_into().ok()?;
+
+        let data = match (version, discriminator) {
+            (JupiterProgramVersion::V4, ROUTE_DISCRIMINATOR)
+                if instruction.data.len() >= 8 + V4_ROUTE_TAIL_LEN =>
+            {
+                let tail = &instruction.data[instruction.data.len() - V4_ROUTE_TAIL_LEN..];
+                JupiterLegacyInstruction::Route(JupiterLegacyRoute {
+                    version,
+                    in_amount: u64::from_le_bytes(tail[0..8].try_into().ok()?),
+                    minimum_out_amount: u64::from_le_bytes(tail[8..16].try_into().ok()?),
+                    platform_fee_bps: tail[16],
+                })
+            }
+            _ => JupiterLegacyInstruction::Unsupported {
+                version,
+                discriminator,
+            },
+        };
+
+        Some(carbon_core::instruction::DecodedInstruction {
+            program_id: instruction.program_id,
+            data,
+            accounts: instruction.accounts.clone(),
+        })
+    }
+}
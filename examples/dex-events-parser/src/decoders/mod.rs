@@ -0,0 +1,9 @@
+mod args;
+pub mod invariant;
+#[cfg(feature = "jupiter-legacy")]
+pub mod jupiter_legacy;
+pub mod obric;
+pub mod oracle;
+pub mod sanctum;
+pub mod token;
+pub mod zerofi;
@@ -0,0 +1,92 @@
+use {super::args::Args, solana_pubkey::Pubkey};
+
+/// Obric v2, a proactive market maker pricing its pairs from oracle feeds.
+pub const PROGRAM_ID: Pubkey = Pubkey::from_str_const("obriQD1zbpyLz95G5n7nJe6a4DPjpFwa5XYPoNm113y");
+
+/// Anchor discriminators of the swap instructions.
+const SWAP_DISCRIMINATOR: [u8; 8] = [0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8];
+const SWAP_X_TO_Y_DISCRIMINATOR: [u8; 8] = [0xe2, 0x4a, 0x29, 0xa6, 0x57, 0x9b, 0x29, 0x4b];
+const SWAP_Y_TO_X_DISCRIMINATOR: [u8; 8] = [0xdb, 0xa8, 0xdb, 0xae, 0xa9, 0xdd, 0xa4, 0x5f];
+
+/// Decoder for the Obric v2 swaps, which share their accounts. Pair and
+/// admin instructions are surfaced as `Unsupported`.
+pub struct ObricV2Decoder;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ObricV2Swap {
+    pub is_x_to_y: bool,
+    pub input_amount: u64,
+    pub min_output_amount: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub enum ObricV2Instruction {
+    Swap(ObricV2Swap),
+    Unsupported { discriminator: [u8; 8] },
+}
+
+impl carbon_core::instruction::InstructionDecoder<'_> for ObricV2Decoder {
+    type InstructionType = ObricV2Instruction;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<carbon_core::instruction::DecodedInstruction<Self::InstructionType>> {
+        if instruction.program_id != PROGRAM_ID {
+            return None;
+        }
+        let discriminator: [u8; 8] = instruction.data.get(..8)?.try_into().ok()?;
+        let mut args = Args(&instruction.data[8..]);
+
+        let is_x_to_y = match discriminator {
+            SWAP_DISCRIMINATOR => Some(args.bool()?),
+            SWAP_X_TO_Y_DISCRIMINATOR => Some(true),
+            SWAP_Y_TO_X_DISCRIMINATOR => Some(false),
+            _ => None,
+        };
+        let data = match is_x_to_y {
+            Some(is_x_to_y) => ObricV2Instruction::Swap(ObricV2Swap {
+                is_x_to_y,
+                input_amount: args.u64()?,
+                min_output_amount: args.u64()?,
+            }),
+            None => ObricV2Instruction::Unsupported { discriminator },
+        };
+
+        Some(carbon_core::instruction::DecodedInstruction {
+            program_id: instruction.program_id,
+            data,
+            accounts: instruction.accounts.clone(),
+        })
+    }
+}
+
+pub struct ObricV2SwapAccounts {
+    pub trading_pair: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub user_token_account_x: Pubkey,
+    pub user_token_account_y: Pubkey,
+    pub user: Pubkey,
+}
+
+impl carbon_core::deserialize::ArrangeAccounts for ObricV2Swap {
+    type ArrangedAccounts = ObricV2SwapAccounts;
+
+    fn arrange_accounts(accounts: &[solana_instruction::AccountMeta]) -> Option<Self::ArrangedAccounts> {
+        let [trading_pair, mint_x, mint_y, _reserve_x, _reserve_y, user_token_account_x, user_token_account_y, _protocol_fee, _x_price_feed, _y_price_feed, user, _remaining @ ..] =
+            accounts
+        else {
+            return None;
+        };
+
+        Some(ObricV2SwapAccounts {
+            trading_pair: trading_pair.pubkey,
+            mint_x: mint_x.pubkey,
+            mint_y: mint_y.pubkey,
+            user_token_account_x: user_token_account_x.pubkey,
+            user_token_account_y: user_token_account_y.pubkey,
+            user: user.pubkey,
+        })
+    }
+}
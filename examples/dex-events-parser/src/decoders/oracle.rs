@@ -0,0 +1,114 @@
+use {
+    carbon_core::account::{AccountDecoder, DecodedAccount},
+    solana_pubkey::Pubkey,
+};
+
+/// Pyth Solana Receiver, owner of pull `PriceUpdateV2` accounts.
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = Pubkey::from_str_const("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+/// Pyth Push Oracle, owner of the sponsored `PriceUpdateV2` feed accounts.
+pub const PYTH_PUSH_ORACLE_PROGRAM_ID: Pubkey = Pubkey::from_str_const("pythWSnswVUd12oZpeFP8e9CVaEqJg25g1Vtc2biRsT");
+/// Switchboard On-Demand, owner of `PullFeedAccountData` accounts.
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+/// Offset of the `verification_level` tag of a `PriceUpdateV2`, after the
+/// discriminator and the write authority.
+const PYTH_VERIFICATION_LEVEL_OFFSET: usize = 8 + 32;
+/// Offset of `last_update_timestamp` in a `PullFeedAccountData`.
+const SWITCHBOARD_LAST_UPDATE_OFFSET: usize = 2216;
+/// Offset of `result.value` in a `PullFeedAccountData`.
+const SWITCHBOARD_RESULT_OFFSET: usize = 2264;
+/// Switchboard values are fixed point with 18 decimals.
+const SWITCHBOARD_DECIMALS: i32 = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OracleProvider {
+    Pyth,
+    Switchboard,
+}
+
+impl OracleProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OracleProvider::Pyth => "pyth",
+            OracleProvider::Switchboard => "switchboard",
+        }
+    }
+}
+
+/// Latest price of an oracle feed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OraclePrice {
+    pub provider: OracleProvider,
+    pub price: f64,
+    /// Unix time in seconds the price was published at.
+    pub publish_time: i64,
+}
+
+/// Decoder for Pyth `PriceUpdateV2` and Switchboard On-Demand pull feed
+/// accounts, told apart by their owner. Only the price and its publish time
+/// are read, without the confidence interval.
+pub struct OracleDecoder;
+
+impl AccountDecoder<'_> for OracleDecoder {
+    type AccountType = OraclePrice;
+
+    fn decode_account(&self, account: &solana_account::Account) -> Option<DecodedAccount<Self::AccountType>> {
+        let data = if account.owner == PYTH_RECEIVER_PROGRAM_ID || account.owner == PYTH_PUSH_ORACLE_PROGRAM_ID {
+            pyth_price(&account.data)?
+        } else if account.owner == SWITCHBOARD_ON_DEMAND_PROGRAM_ID {
+            switchboard_price(&account.data)?
+        } else {
+            return None;
+        };
+
+        Some(DecodedAccount {
+            lamports: account.lamports,
+            data,
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        })
+    }
+}
+
+fn pyth_price(data: &[u8]) -> Option<OraclePrice> {
+    // `VerificationLevel::Partial { num_signatures }` takes one byte more
+    // than `VerificationLevel::Full`.
+    let message = match data.get(PYTH_VERIFICATION_LEVEL_OFFSET)? {
+        0 => PYTH_VERIFICATION_LEVEL_OFFSET + 2,
+        1 => PYTH_VERIFICATION_LEVEL_OFFSET + 1,
+        _ => return None,
+    };
+    // feed_id: [u8; 32], price: i64, conf: u64, exponent: i32, publish_time: i64
+    let price = i64::from_le_bytes(data.get(message + 32..message + 40)?.try_into().ok()?);
+    let exponent = i32::from_le_bytes(data.get(message + 48..message + 52)?.try_into().ok()?);
+    let publish_time = i64::from_le_bytes(data.get(message + 52..message + 60)?.try_into().ok()?);
+
+    Some(OraclePrice {
+        provider: OracleProvider::Pyth,
+        price: price as f64 * 10f64.powi(exponent),
+        publish_time,
+    })
+    .filter(|price| price.price > 0.0)
+}
+
+fn switchboard_price(data: &[u8]) -> Option<OraclePrice> {
+    let value = i128::from_le_bytes(
+        data.get(SWITCHBOARD_RESULT_OFFSET..SWITCHBOARD_RESULT_OFFSET + 16)?
+            .try_into()
+            .ok()?,
+    );
+    let publish_time = i64::from_le_bytes(
+        data.get(SWITCHBOARD_LAST_UPDATE_OFFSET..SWITCHBOARD_LAST_UPDATE_OFFSET + 8)?
+            .try_into()
+            .ok()?,
+    );
+
+    Some(OraclePrice {
+        provider: OracleProvider::Switchboard,
+        price: value as f64 / 10f64.powi(SWITCHBOARD_DECIMALS),
+        publish_time,
+    })
+    .filter(|price| price.price > 0.0)
+}
@@ -0,0 +1,282 @@
+use {super::args::Args, solana_pubkey::Pubkey};
+
+/// Sanctum Infinity's S Controller, the multi-LST pool.
+pub const INFINITY_PROGRAM_ID: Pubkey = Pubkey::from_str_const("5ocnV1qiCgaQR8Jb8xWnVbApfaygJ8tNoZfgPwsgx9kx");
+/// Sanctum's stake router, which swaps SOL and LSTs through stake accounts.
+pub const ROUTER_PROGRAM_ID: Pubkey = Pubkey::from_str_const("stkitrT1Uoy18Dk1fTrgPw8W6MVzoCfYoAFT4MLsmhq");
+
+/// Neither program is Anchor: instructions start with a one-byte
+/// discriminant followed by their Borsh arguments.
+const INFINITY_SWAP_EXACT_IN: u8 = 1;
+const INFINITY_SWAP_EXACT_OUT: u8 = 2;
+const INFINITY_ADD_LIQUIDITY: u8 = 3;
+const INFINITY_REMOVE_LIQUIDITY: u8 = 4;
+const ROUTER_STAKE_WRAPPED_SOL: u8 = 0;
+const ROUTER_SWAP_VIA_STAKE: u8 = 1;
+const ROUTER_PREFUND_SWAP_VIA_STAKE: u8 = 7;
+
+/// Decoder for the Infinity pool. Only swaps and liquidity changes are
+/// decoded; SOL value syncs and admin instructions are `Unsupported`.
+pub struct SanctumInfinityDecoder;
+
+/// Decoder for the stake router. Only the instructions that end in the
+/// user's token account are decoded; stake withdrawals, fee accounts and
+/// admin instructions are `Unsupported`.
+pub struct SanctumRouterDecoder;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct InfinitySwap {
+    pub exact_out: bool,
+    pub src_lst_index: u32,
+    pub dst_lst_index: u32,
+    /// Minimum output of exact-in swaps, maximum input of exact-out swaps.
+    pub limit_amount: u64,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct InfinityAddLiquidity {
+    pub lst_index: u32,
+    pub lst_amount: u64,
+    pub min_lp_out: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct InfinityRemoveLiquidity {
+    pub lst_index: u32,
+    pub lp_token_amount: u64,
+    pub min_lst_out: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub enum SanctumInfinityInstruction {
+    Swap(InfinitySwap),
+    AddLiquidity(InfinityAddLiquidity),
+    RemoveLiquidity(InfinityRemoveLiquidity),
+    Unsupported { discriminant: u8 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum RouterSwapKind {
+    /// wSOL deposited into a stake pool for its LST.
+    StakeWrappedSol,
+    /// One LST withdrawn to a stake account and deposited into another pool.
+    SwapViaStake,
+    /// `SwapViaStake` with the bridge stake account's rent prefunded.
+    PrefundSwapViaStake,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct RouterSwap {
+    pub kind: RouterSwapKind,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub enum SanctumRouterInstruction {
+    Swap(RouterSwap),
+    Unsupported { discriminant: u8 },
+}
+
+impl carbon_core::instruction::InstructionDecoder<'_> for SanctumInfinityDecoder {
+    type InstructionType = SanctumInfinityInstruction;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<carbon_core::instruction::DecodedInstruction<Self::InstructionType>> {
+        if instruction.program_id != INFINITY_PROGRAM_ID {
+            return None;
+        }
+        let (&discriminant, rest) = instruction.data.split_first()?;
+        let mut args = Args(rest);
+
+        let data = match discriminant {
+            INFINITY_SWAP_EXACT_IN | INFINITY_SWAP_EXACT_OUT => {
+                // Counts of the value calculator accounts of each side
+                args.u8()?;
+                args.u8()?;
+                SanctumInfinityInstruction::Swap(InfinitySwap {
+                    exact_out: discriminant == INFINITY_SWAP_EXACT_OUT,
+                    src_lst_index: args.u32()?,
+                    dst_lst_index: args.u32()?,
+                    limit_amount: args.u64()?,
+                    amount: args.u64()?,
+                })
+            }
+            INFINITY_ADD_LIQUIDITY => {
+                args.u8()?;
+                SanctumInfinityInstruction::AddLiquidity(InfinityAddLiquidity {
+                    lst_index: args.u32()?,
+                    lst_amount: args.u64()?,
+                    min_lp_out: args.u64()?,
+                })
+            }
+            INFINITY_REMOVE_LIQUIDITY => {
+                args.u8()?;
+                SanctumInfinityInstruction::RemoveLiquidity(InfinityRemoveLiquidity {
+                    lst_index: args.u32()?,
+                    lp_token_amount: args.u64()?,
+                    min_lst_out: args.u64()?,
+                })
+            }
+            _ => SanctumInfinityInstruction::Unsupported { discriminant },
+        };
+
+        Some(carbon_core::instruction::DecodedInstruction {
+            program_id: instruction.program_id,
+            data,
+            accounts: instruction.accounts.clone(),
+        })
+    }
+}
+
+impl carbon_core::instruction::InstructionDecoder<'_> for SanctumRouterDecoder {
+    type InstructionType = SanctumRouterInstruction;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<carbon_core::instruction::DecodedInstruction<Self::InstructionType>> {
+        if instruction.program_id != ROUTER_PROGRAM_ID {
+            return None;
+        }
+        let (&discriminant, rest) = instruction.data.split_first()?;
+        let mut args = Args(rest);
+
+        let kind = match discriminant {
+            ROUTER_STAKE_WRAPPED_SOL => Some(RouterSwapKind::StakeWrappedSol),
+            ROUTER_SWAP_VIA_STAKE => Some(RouterSwapKind::SwapViaStake),
+            ROUTER_PREFUND_SWAP_VIA_STAKE => Some(RouterSwapKind::PrefundSwapViaStake),
+            _ => None,
+        };
+        let data = match kind {
+            Some(kind) => SanctumRouterInstruction::Swap(RouterSwap { kind, amount: args.u64()? }),
+            None => SanctumRouterInstruction::Unsupported { discriminant },
+        };
+
+        Some(carbon_core::instruction::DecodedInstruction {
+            program_id: instruction.program_id,
+            data,
+            accounts: instruction.accounts.clone(),
+        })
+    }
+}
+
+pub struct InfinitySwapAccounts {
+    pub signer: Pubkey,
+    pub src_lst_mint: Pubkey,
+    pub dst_lst_mint: Pubkey,
+    pub src_lst_acc: Pubkey,
+    pub dst_lst_acc: Pubkey,
+    pub pool_state: Pubkey,
+}
+
+impl carbon_core::deserialize::ArrangeAccounts for InfinitySwap {
+    type ArrangedAccounts = InfinitySwapAccounts;
+
+    fn arrange_accounts(accounts: &[solana_instruction::AccountMeta]) -> Option<Self::ArrangedAccounts> {
+        let [signer, src_lst_mint, dst_lst_mint, src_lst_acc, dst_lst_acc, _protocol_fee_accumulator, _src_lst_token_program, _dst_lst_token_program, pool_state, _remaining @ ..] =
+            accounts
+        else {
+            return None;
+        };
+
+        Some(InfinitySwapAccounts {
+            signer: signer.pubkey,
+            src_lst_mint: src_lst_mint.pubkey,
+            dst_lst_mint: dst_lst_mint.pubkey,
+            src_lst_acc: src_lst_acc.pubkey,
+            dst_lst_acc: dst_lst_acc.pubkey,
+            pool_state: pool_state.pubkey,
+        })
+    }
+}
+
+/// Accounts shared by adding and removing liquidity: the LST side and the
+/// LP side of the user, in that order.
+pub struct InfinityLiquidityAccounts {
+    pub signer: Pubkey,
+    pub lst_mint: Pubkey,
+    pub lst_acc: Pubkey,
+    pub lp_acc: Pubkey,
+    pub lp_token_mint: Pubkey,
+    pub pool_state: Pubkey,
+}
+
+fn arrange_liquidity(accounts: &[solana_instruction::AccountMeta]) -> Option<InfinityLiquidityAccounts> {
+    let [signer, lst_mint, lst_acc, lp_acc, lp_token_mint, _protocol_fee_accumulator, _lst_token_program, _lp_token_program, pool_state, _remaining @ ..] =
+        accounts
+    else {
+        return None;
+    };
+
+    Some(InfinityLiquidityAccounts {
+        signer: signer.pubkey,
+        lst_mint: lst_mint.pubkey,
+        lst_acc: lst_acc.pubkey,
+        lp_acc: lp_acc.pubkey,
+        lp_token_mint: lp_token_mint.pubkey,
+        pool_state: pool_state.pubkey,
+    })
+}
+
+impl carbon_core::deserialize::ArrangeAccounts for InfinityAddLiquidity {
+    type ArrangedAccounts = InfinityLiquidityAccounts;
+
+    fn arrange_accounts(accounts: &[solana_instruction::AccountMeta]) -> Option<Self::ArrangedAccounts> {
+        arrange_liquidity(accounts)
+    }
+}
+
+impl carbon_core::deserialize::ArrangeAccounts for InfinityRemoveLiquidity {
+    type ArrangedAccounts = InfinityLiquidityAccounts;
+
+    fn arrange_accounts(accounts: &[solana_instruction::AccountMeta]) -> Option<Self::ArrangedAccounts> {
+        arrange_liquidity(accounts)
+    }
+}
+
+pub struct RouterSwapAccounts {
+    pub user: Pubkey,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub source_mint: Pubkey,
+    pub destination_mint: Pubkey,
+}
+
+impl RouterSwap {
+    /// Accounts of the swap, whose order depends on its kind.
+    pub fn arrange_accounts(&self, accounts: &[solana_instruction::AccountMeta]) -> Option<RouterSwapAccounts> {
+        match self.kind {
+            RouterSwapKind::StakeWrappedSol => {
+                let [user, wsol_from, dest_token_to, _wsol_bridge_in, _sol_bridge_out, _dest_token_fee_token_account, dest_token_mint, wsol_mint, _remaining @ ..] =
+                    accounts
+                else {
+                    return None;
+                };
+                Some(RouterSwapAccounts {
+                    user: user.pubkey,
+                    source: wsol_from.pubkey,
+                    destination: dest_token_to.pubkey,
+                    source_mint: wsol_mint.pubkey,
+                    destination_mint: dest_token_mint.pubkey,
+                })
+            }
+            RouterSwapKind::SwapViaStake | RouterSwapKind::PrefundSwapViaStake => {
+                let [user, src_token_from, dest_token_to, _bridge_stake, _dest_token_fee_token_account, src_token_mint, dest_token_mint, _remaining @ ..] =
+                    accounts
+                else {
+                    return None;
+                };
+                Some(RouterSwapAccounts {
+                    user: user.pubkey,
+                    source: src_token_from.pubkey,
+                    destination: dest_token_to.pubkey,
+                    source_mint: src_token_mint.pubkey,
+                    destination_mint: dest_token_mint.pubkey,
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use crate::analytics::transfers::TokenActivity;
+
+/// Decoder for the transfers, mints and burns of both SPL Token and
+/// Token-2022, so that one processor follows the two programs. Other token
+/// instructions are not decoded.
+pub struct TokenActivityDecoder;
+
+impl carbon_core::instruction::InstructionDecoder<'_> for TokenActivityDecoder {
+    type InstructionType = TokenActivity;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<carbon_core::instruction::DecodedInstruction<Self::InstructionType>> {
+        Some(carbon_core::instruction::DecodedInstruction {
+            program_id: instruction.program_id,
+            data: TokenActivity::decode(instruction)?,
+            accounts: instruction.accounts.clone(),
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use {super::args::Args, solana_pubkey::Pubkey};
+
+/// ZeroFi, a market maker quoting from its own vaults.
+pub const PROGRAM_ID: Pubkey = Pubkey::from_str_const("ZERor4xhbUycZ6gb9ntrhqscUcZmAbQDjEAtCf4hbZY");
+
+/// ZeroFi is not Anchor: instructions start with a one-byte discriminant.
+const SWAP_DISCRIMINANT: u8 = 6;
+
+/// Decoder for the ZeroFi swap, the only instruction aggregators invoke.
+/// Market making and admin instructions are surfaced as `Unsupported`.
+pub struct ZeroFiDecoder;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ZeroFiSwap {
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub enum ZeroFiInstruction {
+    Swap(ZeroFiSwap),
+    Unsupported { discriminant: u8 },
+}
+
+impl carbon_core::instruction::InstructionDecoder<'_> for ZeroFiDecoder {
+    type InstructionType = ZeroFiInstruction;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<carbon_core::instruction::DecodedInstruction<Self::InstructionType>> {
+        if instruction.program_id != PROGRAM_ID {
+            return None;
+        }
+        let (&discriminant, rest) = instruction.data.split_first()?;
+        let mut args = Args(rest);
+
+        let data = match discriminant {
+            SWAP_DISCRIMINANT => ZeroFiInstruction::Swap(ZeroFiSwap {
+                amount_in: args.u64()?,
+                minimum_amount_out: args.u64()?,
+            }),
+            _ => ZeroFiInstruction::Unsupported { discriminant },
+        };
+
+        Some(carbon_core::instruction::DecodedInstruction {
+            program_id: instruction.program_id,
+            data,
+            accounts: instruction.accounts.clone(),
+        })
+    }
+}
+
+pub struct ZeroFiSwapAccounts {
+    pub pair: Pubkey,
+    pub vault_in: Pubkey,
+    pub vault_out: Pubkey,
+    pub user_token_in: Pubkey,
+    pub user_token_out: Pubkey,
+    pub user: Pubkey,
+}
+
+impl carbon_core::deserialize::ArrangeAccounts for ZeroFiSwap {
+    type ArrangedAccounts = ZeroFiSwapAccounts;
+
+    fn arrange_accounts(accounts: &[solana_instruction::AccountMeta]) -> Option<Self::ArrangedAccounts> {
+        let [pair, _vault_info_in, vault_in, _vault_info_out, vault_out, user_token_in, user_token_out, user, _remaining @ ..] =
+            accounts
+        else {
+            return None;
+        };
+
+        Some(ZeroFiSwapAccounts {
+            pair: pair.pubkey,
+            vault_in: vault_in.pubkey,
+            vault_out: vault_out.pubkey,
+            user_token_in: user_token_in.pubkey,
+            user_token_out: user_token_out.pubkey,
+            user: user.pubkey,
+        })
+    }
+}
@@ -0,0 +1,45 @@
+use {
+    super::{DedupError, DedupStore},
+    async_trait::async_trait,
+    lru::LruCache,
+    std::{
+        num::NonZeroUsize,
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+/// Dedup window local to this instance, bounded to the most recently seen
+/// keys.
+pub struct MemoryDedup {
+    ttl: Duration,
+    seen: Mutex<LruCache<String, Instant>>,
+}
+
+impl MemoryDedup {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            ttl,
+            seen: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+#[async_trait]
+impl DedupStore for MemoryDedup {
+    async fn insert(&self, key: &str) -> Result<bool, DedupError> {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        if seen.get(key).is_some_and(|seen_at| now.duration_since(*seen_at) < self.ttl) {
+            return Ok(false);
+        }
+        seen.put(key.to_string(), now);
+        Ok(true)
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), DedupError> {
+        self.seen.lock().unwrap_or_else(|e| e.into_inner()).pop(key);
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+pub mod memory;
+pub mod redis_store;
+#[cfg(feature = "dedup-rocksdb")]
+pub mod rocksdb_store;
+
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::CarbonResult,
+        instruction::InstructionMetadata,
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    std::{
+        sync::Arc,
+        time::{Duration, SystemTime},
+    },
+};
+
+use crate::processors::InstructionInput;
+
+const DEFAULT_TTL_SECS: u64 = 600;
+const DEFAULT_CAPACITY: usize = 100_000;
+
+#[derive(Debug)]
+pub struct DedupError(pub String);
+
+impl std::fmt::Display for DedupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Dedup Error: {}", self.0)
+    }
+}
+
+impl std::error::Error for DedupError {}
+
+/// Window of instructions already processed, keyed by transaction signature
+/// and instruction path.
+#[async_trait]
+pub trait DedupStore: Send + Sync {
+    /// Marks `key` as seen for the window. Returns `false` when it already
+    /// was, in which case the instruction is skipped.
+    async fn insert(&self, key: &str) -> Result<bool, DedupError>;
+
+    /// Releases `key`, so a redelivery of an instruction that failed to
+    /// process is not skipped.
+    async fn remove(&self, key: &str) -> Result<(), DedupError>;
+}
+
+/// Skips instructions already processed within the dedup window, by this
+/// instance or, with a shared backend such as Redis, by any replica.
+#[derive(Clone, Default)]
+pub struct Dedup {
+    store: Option<Arc<dyn DedupStore>>,
+}
+
+impl Dedup {
+    pub fn new(store: Arc<dyn DedupStore>) -> Self {
+        Self { store: Some(store) }
+    }
+
+    /// Reads `DEDUP_BACKEND` (`none`, `memory`, `redis` or `rocksdb`,
+    /// default `none`) and `DEDUP_TTL_SECS` (default `600`), plus the
+    /// settings of the chosen backend:
+    ///
+    /// - `memory`: `DEDUP_CAPACITY` (default `100000`)
+    /// - `redis`: `DEDUP_REDIS_URL` (default `redis://127.0.0.1/`) and
+    ///   `DEDUP_REDIS_PREFIX` (default `dex-events:dedup:`)
+    /// - `rocksdb`: `DEDUP_ROCKSDB_PATH` (default `dedup-db`), requires the
+    ///   `dedup-rocksdb` feature
+    pub async fn from_env() -> Result<Self, DedupError> {
+        let ttl = Duration::from_secs(
+            std::env::var("DEDUP_TTL_SECS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_TTL_SECS),
+        );
+
+        let store: Arc<dyn DedupStore> = match std::env::var("DEDUP_BACKEND").as_deref() {
+            Err(_) | Ok("none") => return Ok(Self::default()),
+            Ok("memory") => {
+                let capacity = std::env::var("DEDUP_CAPACITY")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(DEFAULT_CAPACITY);
+                Arc::new(memory::MemoryDedup::new(capacity, ttl))
+            }
+            Ok("redis") => {
+                let url = std::env::var("DEDUP_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string());
+                let prefix = std::env::var("DEDUP_REDIS_PREFIX").unwrap_or_else(|_| "dex-events:dedup:".to_string());
+                Arc::new(redis_store::RedisDedup::connect(&url, prefix, ttl).await?)
+            }
+            #[cfg(feature = "dedup-rocksdb")]
+            Ok("rocksdb") => {
+                let path = std::env::var("DEDUP_ROCKSDB_PATH").unwrap_or_else(|_| "dedup-db".to_string());
+                Arc::new(rocksdb_store::RocksDbDedup::open(&path, ttl)?)
+            }
+            #[cfg(not(feature = "dedup-rocksdb"))]
+            Ok("rocksdb") => return Err(DedupError("DEDUP_BACKEND=rocksdb requires the dedup-rocksdb feature".to_string())),
+            Ok(other) => return Err(DedupError(format!("Unknown DEDUP_BACKEND {}", other))),
+        };
+
+        Ok(Self::new(store))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Writes a throwaway key, to check the backend is reachable and
+    /// writable.
+    pub async fn probe(&self) -> Result<(), DedupError> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let nanos = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_nanos();
+        store.insert(&format!("doctor:{}:{}", std::process::id(), nanos)).await.map(|_| ())
+    }
+
+    pub fn wrap<P>(&self, processor: P) -> Deduplicated<P> {
+        Deduplicated {
+            store: self.store.clone(),
+            processor,
+        }
+    }
+}
+
+/// Identifies an instruction across replicas and redeliveries.
+fn instruction_key(metadata: &InstructionMetadata) -> String {
+    let path = metadata
+        .absolute_path
+        .iter()
+        .map(|index| index.to_string())
+        .collect::<Vec<_>>()
+        .join(".");
+    format!("{}:{}", metadata.transaction_metadata.signature, path)
+}
+
+/// Processor handle returned by [`Dedup::wrap`].
+pub struct Deduplicated<P> {
+    store: Option<Arc<dyn DedupStore>>,
+    processor: P,
+}
+
+#[async_trait]
+impl<T, P> Processor for Deduplicated<P>
+where
+    T: Send + 'static,
+    P: Processor<InputType = InstructionInput<T>> + Send,
+{
+    type InputType = InstructionInput<T>;
+
+    async fn process(
+        &mut self,
+        input: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let mut claimed = None;
+        if let Some(store) = &self.store {
+            let key = instruction_key(&input.0);
+            match store.insert(&key).await {
+                Ok(true) => claimed = Some((store, key)),
+                Ok(false) => {
+                    metrics
+                        .increment_counter("dedup_duplicates_skipped", 1)
+                        .await
+                        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                    return Ok(());
+                }
+                // Processing twice beats dropping an event when the backend is down
+                Err(e) => {
+                    log::warn!("Dedup check failed for {}, processing anyway: {}", key, e);
+                    metrics
+                        .increment_counter("dedup_errors", 1)
+                        .await
+                        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                }
+            }
+        }
+
+        let result = self.processor.process(input, metrics).await;
+        if let (Err(_), Some((store, key))) = (&result, claimed) {
+            if let Err(e) = store.remove(&key).await {
+                log::warn!("Failed to release dedup key {} after a processing error: {}", key, e);
+            }
+        }
+        result
+    }
+}
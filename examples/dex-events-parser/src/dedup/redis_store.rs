@@ -0,0 +1,58 @@
+use {
+    super::{DedupError, DedupStore},
+    async_trait::async_trait,
+    redis::aio::ConnectionManager,
+    std::time::Duration,
+};
+
+/// Dedup window shared by every replica pointing at the same Redis. Keys
+/// are written with `SET NX EX`, the atomic form of check-then-`SETEX`, so
+/// exactly one replica wins each key.
+pub struct RedisDedup {
+    connection: ConnectionManager,
+    prefix: String,
+    ttl_secs: u64,
+}
+
+impl RedisDedup {
+    pub async fn connect(url: &str, prefix: String, ttl: Duration) -> Result<Self, DedupError> {
+        let client = redis::Client::open(url)
+            .map_err(|e| DedupError(format!("Invalid Redis URL {}: {}", url, e)))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(|e| DedupError(format!("Failed to connect to Redis: {}", e)))?;
+
+        Ok(Self {
+            connection,
+            prefix,
+            ttl_secs: ttl.as_secs().max(1),
+        })
+    }
+}
+
+#[async_trait]
+impl DedupStore for RedisDedup {
+    async fn insert(&self, key: &str) -> Result<bool, DedupError> {
+        let mut connection = self.connection.clone();
+        let set: Option<String> = redis::cmd("SET")
+            .arg(format!("{}{}", self.prefix, key))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.ttl_secs)
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| DedupError(format!("Redis SET failed: {}", e)))?;
+
+        Ok(set.is_some())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), DedupError> {
+        let mut connection = self.connection.clone();
+        redis::cmd("DEL")
+            .arg(format!("{}{}", self.prefix, key))
+            .query_async::<()>(&mut connection)
+            .await
+            .map_err(|e| DedupError(format!("Redis DEL failed: {}", e)))
+    }
+}
@@ -0,0 +1,77 @@
+use {
+    super::{DedupError, DedupStore},
+    async_trait::async_trait,
+    rocksdb::{Options, DB},
+    std::{
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime},
+    },
+};
+
+/// Dedup window persisted on local disk, so a restarted instance does not
+/// republish what it processed just before stopping.
+pub struct RocksDbDedup {
+    db: Arc<Mutex<DB>>,
+    ttl: Duration,
+}
+
+impl RocksDbDedup {
+    /// Opens the database with RocksDB's TTL compaction, which drops keys
+    /// older than `ttl` in the background.
+    pub fn open(path: &str, ttl: Duration) -> Result<Self, DedupError> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        let db = DB::open_with_ttl(&options, path, ttl)
+            .map_err(|e| DedupError(format!("Failed to open RocksDB at {}: {}", path, e)))?;
+
+        Ok(Self {
+            db: Arc::new(Mutex::new(db)),
+            ttl,
+        })
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[async_trait]
+impl DedupStore for RocksDbDedup {
+    async fn insert(&self, key: &str) -> Result<bool, DedupError> {
+        let db = self.db.clone();
+        let ttl = self.ttl.as_secs();
+        let key = key.as_bytes().to_vec();
+
+        tokio::task::spawn_blocking(move || {
+            let db = db.lock().unwrap_or_else(|e| e.into_inner());
+            let now = unix_now();
+            // Compaction removes expired keys lazily, so check the age too
+            let seen_at = db
+                .get(&key)
+                .map_err(|e| DedupError(format!("RocksDB get failed: {}", e)))?
+                .and_then(|value| <[u8; 8]>::try_from(value.as_slice()).ok())
+                .map(u64::from_le_bytes);
+            if seen_at.is_some_and(|seen_at| now.saturating_sub(seen_at) < ttl) {
+                return Ok(false);
+            }
+            db.put(&key, now.to_le_bytes())
+                .map_err(|e| DedupError(format!("RocksDB put failed: {}", e)))?;
+            Ok(true)
+        })
+        .await
+        .map_err(|e| DedupError(format!("RocksDB task failed: {}", e)))?
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), DedupError> {
+        let db = self.db.clone();
+        let key = key.as_bytes().to_vec();
+
+        tokio::task::spawn_blocking(move || {
+            let db = db.lock().unwrap_or_else(|e| e.into_inner());
+            db.delete(&key)
+                .map_err(|e| DedupError(format!("RocksDB delete failed: {}", e)))
+        })
+        .await
+        .map_err(|e| DedupError(format!("RocksDB task failed: {}", e)))?
+    }
+}
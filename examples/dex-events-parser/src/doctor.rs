@@ -0,0 +1,421 @@
+use {
+    crate::{
+        checkpoint::Checkpoint,
+        datasources::WatchList,
+        dedup::Dedup,
+        ha::HaConfig,
+        publishers::kafka_producer_config_from_env,
+        shard::Shard,
+    },
+    futures::StreamExt,
+    rdkafka::producer::{BaseProducer, Producer},
+    solana_client::{
+        nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+        rpc_config::{RpcBlockConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter},
+    },
+    solana_commitment_config::CommitmentConfig,
+    solana_transaction_status::{TransactionDetails, UiTransactionEncoding},
+    std::{
+        future::Future,
+        time::{Duration, Instant},
+    },
+};
+
+const DEFAULT_TIMEOUT_MS: u64 = 5_000;
+/// How far behind the tip to look for a block, since the newest slots may
+/// not be confirmed or may have been skipped.
+const BLOCK_LOOKBACK_SLOTS: u64 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    /// Works, but something looks off.
+    Warn,
+    Fail,
+    /// Not configured for this run.
+    Skipped,
+}
+
+impl CheckStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            CheckStatus::Ok => " OK ",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+            CheckStatus::Skipped => "SKIP",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub latency: Option<Duration>,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn skipped(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Skipped,
+            latency: None,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Outcome of every connectivity check.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub checks: Vec<CheckResult>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.status != CheckStatus::Fail)
+    }
+
+    fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|check| check.status == status).count()
+    }
+
+    pub fn print(&self) {
+        println!("DEX Events Parser doctor");
+        for check in &self.checks {
+            let latency = check
+                .latency
+                .map(|latency| format!("{} ms", latency.as_millis()))
+                .unwrap_or_default();
+            println!("  [{}] {:<15} {:>8}  {}", check.status.label(), check.name, latency, check.detail);
+        }
+        println!(
+            "{} ok, {} warn, {} fail, {} skipped",
+            self.count(CheckStatus::Ok),
+            self.count(CheckStatus::Warn),
+            self.count(CheckStatus::Fail),
+            self.count(CheckStatus::Skipped)
+        );
+    }
+}
+
+/// Runs `check` under `timeout`, timing it. The check returns its status
+/// and a one-line detail.
+async fn timed<F>(name: &'static str, timeout: Duration, check: F) -> CheckResult
+where
+    F: Future<Output = (CheckStatus, String)>,
+{
+    let start = Instant::now();
+    let (status, detail) = tokio::time::timeout(timeout, check)
+        .await
+        .unwrap_or_else(|_| (CheckStatus::Fail, format!("timed out after {} ms", timeout.as_millis())));
+
+    CheckResult {
+        name,
+        status,
+        latency: Some(start.elapsed()),
+        detail,
+    }
+}
+
+async fn check_rpc_http(url: String) -> (CheckStatus, String) {
+    let client = RpcClient::new_with_commitment(url, CommitmentConfig::confirmed());
+    let version = match client.get_version().await {
+        Ok(version) => version,
+        Err(e) => return (CheckStatus::Fail, format!("getVersion failed: {}", e)),
+    };
+    let slot = match client.get_slot().await {
+        Ok(slot) => slot,
+        Err(e) => return (CheckStatus::Fail, format!("getSlot failed: {}", e)),
+    };
+
+    // The hybrid datasource fetches every notified block over HTTP
+    let produced = client
+        .get_blocks_with_limit(slot.saturating_sub(BLOCK_LOOKBACK_SLOTS), 1)
+        .await
+        .ok()
+        .and_then(|slots| slots.first().copied());
+    let Some(block_slot) = produced else {
+        return (
+            CheckStatus::Warn,
+            format!("solana-core {}, slot {}, no recent block found", version.solana_core, slot),
+        );
+    };
+    let config = RpcBlockConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        transaction_details: Some(TransactionDetails::None),
+        rewards: Some(false),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    match client.get_block_with_config(block_slot, config).await {
+        Ok(_) => (
+            CheckStatus::Ok,
+            format!("solana-core {}, slot {}, block {} available", version.solana_core, slot, block_slot),
+        ),
+        Err(e) => (CheckStatus::Fail, format!("getBlock {} failed: {}", block_slot, e)),
+    }
+}
+
+/// Many providers disable `blockSubscribe`, which every datasource relies
+/// on, so subscribe and wait for the first notification.
+async fn check_rpc_ws(url: String) -> (CheckStatus, String) {
+    let client = match PubsubClient::new(&url).await {
+        Ok(client) => client,
+        Err(e) => return (CheckStatus::Fail, format!("connect failed: {}", e)),
+    };
+    let filter = RpcBlockSubscribeFilter::MentionsAccountOrProgram(
+        carbon_raydium_amm_v4_decoder::PROGRAM_ID.to_string(),
+    );
+    let config = RpcBlockSubscribeConfig {
+        commitment: Some(CommitmentConfig::confirmed()),
+        encoding: Some(UiTransactionEncoding::Base64),
+        transaction_details: Some(TransactionDetails::None),
+        show_rewards: Some(false),
+        max_supported_transaction_version: Some(0),
+    };
+    let (mut stream, unsubscribe) = match client.block_subscribe(filter, Some(config)).await {
+        Ok(subscription) => subscription,
+        Err(e) => return (CheckStatus::Fail, format!("blockSubscribe refused: {}", e)),
+    };
+
+    let result = match stream.next().await {
+        Some(notification) => (
+            CheckStatus::Ok,
+            format!("blockSubscribe delivered slot {}", notification.context.slot),
+        ),
+        None => (CheckStatus::Fail, "blockSubscribe stream closed".to_string()),
+    };
+    drop(stream);
+    unsubscribe().await;
+    result
+}
+
+/// Connects with the producer configuration, credentials included.
+async fn check_kafka() -> (CheckStatus, String) {
+    let config = match kafka_producer_config_from_env() {
+        Ok(config) => config,
+        Err(e) => return (CheckStatus::Fail, e),
+    };
+    let brokers = config.get("bootstrap.servers").unwrap_or_default().to_string();
+    let producer: BaseProducer = match config.create() {
+        Ok(producer) => producer,
+        Err(e) => return (CheckStatus::Fail, format!("invalid config: {}", e)),
+    };
+
+    let metadata = tokio::task::spawn_blocking(move || {
+        producer
+            .client()
+            .fetch_metadata(Some("dex_events"), Duration::from_millis(DEFAULT_TIMEOUT_MS))
+            .map(|metadata| {
+                let partitions = metadata
+                    .topics()
+                    .iter()
+                    .find(|topic| topic.error().is_none())
+                    .map_or(0, |topic| topic.partitions().len());
+                (metadata.brokers().len(), partitions)
+            })
+    })
+    .await;
+
+    match metadata {
+        Ok(Ok((brokers, 0))) => (CheckStatus::Warn, format!("{} brokers, topic dex_events not found", brokers)),
+        Ok(Ok((brokers, partitions))) => (
+            CheckStatus::Ok,
+            format!("{} brokers, dex_events has {} partitions", brokers, partitions),
+        ),
+        Ok(Err(e)) => (CheckStatus::Fail, format!("metadata request to {} failed: {}", brokers, e)),
+        Err(e) => (CheckStatus::Fail, format!("metadata request failed: {}", e)),
+    }
+}
+
+/// Lists the subjects, which needs the URL and credentials to be right.
+async fn check_schema_registry(url: String) -> (CheckStatus, String) {
+    let mut request = reqwest::Client::new().get(format!("{}/subjects", url.trim_end_matches('/')));
+    if let Ok(username) = std::env::var("SCHEMA_REGISTRY_USERNAME") {
+        request = request.basic_auth(username, std::env::var("SCHEMA_REGISTRY_PASSWORD").ok());
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return (CheckStatus::Fail, format!("request to {} failed: {}", url, e)),
+    };
+    if !response.status().is_success() {
+        return (CheckStatus::Fail, format!("{} answered with status {}", url, response.status()));
+    }
+    let subjects = response
+        .text()
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|body| serde_json::from_str::<Vec<String>>(&body).map_err(|e| e.to_string()));
+    match subjects {
+        Ok(subjects) if subjects.iter().any(|subject| subject == "dex_events-value") => {
+            (CheckStatus::Ok, format!("{} subjects, dex_events-value registered", subjects.len()))
+        }
+        Ok(subjects) => (
+            CheckStatus::Warn,
+            format!("{} subjects, dex_events-value not registered yet", subjects.len()),
+        ),
+        Err(e) => (CheckStatus::Fail, format!("unexpected subjects list: {}", e)),
+    }
+}
+
+/// Binds and releases the endpoint. Dropping the context waits for the
+/// socket to close, so the real publisher can bind it right after.
+fn check_zmq(endpoint: &str) -> (CheckStatus, String) {
+    let context = zmq::Context::new();
+    let bound = context
+        .socket(zmq::PUB)
+        .and_then(|socket| socket.bind(endpoint));
+    match bound {
+        Ok(()) => (CheckStatus::Ok, format!("bound {}", endpoint)),
+        Err(e) => (CheckStatus::Fail, format!("cannot bind {}: {}", endpoint, e)),
+    }
+}
+
+fn check_ws_server(addr: &str) -> (CheckStatus, String) {
+    match std::net::TcpListener::bind(addr) {
+        Ok(_) => (CheckStatus::Ok, format!("bound {}", addr)),
+        Err(e) => (CheckStatus::Fail, format!("cannot bind {}: {}", addr, e)),
+    }
+}
+
+async fn check_dedup() -> (CheckStatus, String) {
+    let backend = std::env::var("DEDUP_BACKEND").unwrap_or_else(|_| "none".to_string());
+    let dedup = match Dedup::from_env().await {
+        Ok(dedup) => dedup,
+        Err(e) => return (CheckStatus::Fail, e.to_string()),
+    };
+    match dedup.probe().await {
+        Ok(()) => (CheckStatus::Ok, format!("{} backend writable", backend)),
+        Err(e) => (CheckStatus::Fail, format!("{} backend not writable: {}", backend, e)),
+    }
+}
+
+async fn check_checkpoint() -> (CheckStatus, String) {
+    let backend = std::env::var("CHECKPOINT_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .or_else(|| std::env::var("CHECKPOINT_BACKEND").ok())
+        .unwrap_or_else(|| "none".to_string());
+    let checkpoint = match Checkpoint::from_env().await {
+        Ok(checkpoint) => checkpoint,
+        Err(e) => return (CheckStatus::Fail, e.to_string()),
+    };
+    match checkpoint.probe().await {
+        Ok(()) => (CheckStatus::Ok, format!("{} backend readable", backend)),
+        Err(e) => (CheckStatus::Fail, format!("{} backend not readable: {}", backend, e)),
+    }
+}
+
+async fn check_ha(config: HaConfig) -> (CheckStatus, String) {
+    let client = match redis::Client::open(config.redis_url.as_str()) {
+        Ok(client) => client,
+        Err(e) => return (CheckStatus::Fail, format!("invalid HA_REDIS_URL: {}", e)),
+    };
+    let mut connection = match client.get_multiplexed_async_connection().await {
+        Ok(connection) => connection,
+        Err(e) => return (CheckStatus::Fail, format!("connect failed: {}", e)),
+    };
+    let leader: redis::RedisResult<Option<String>> = redis::cmd("GET")
+        .arg(&config.lock_key)
+        .query_async(&mut connection)
+        .await;
+    match leader {
+        Ok(Some(leader)) => (CheckStatus::Ok, format!("lease held by {}", leader)),
+        Ok(None) => (CheckStatus::Ok, "lease free".to_string()),
+        Err(e) => (CheckStatus::Fail, format!("GET {} failed: {}", config.lock_key, e)),
+    }
+}
+
+/// Checks the RPC endpoints, the configured sinks and state stores, and
+/// the settings that are otherwise only validated deep into startup.
+pub async fn run() -> Report {
+    let timeout = Duration::from_millis(
+        std::env::var("DOCTOR_TIMEOUT_MS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_MS),
+    );
+    let rpc_http_url = std::env::var("RPC_HTTP_URL")
+        .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string());
+    let rpc_ws_url = std::env::var("RPC_WS_URL")
+        .unwrap_or_else(|_| "wss://api.mainnet-beta.solana.com".to_string());
+    let publisher_type = std::env::var("PUBLISHER_TYPE").unwrap_or_else(|_| "zmq".to_string());
+    let datasource_type = std::env::var("DATASOURCE_TYPE").unwrap_or_else(|_| "websocket".to_string());
+
+    let mut report = Report::default();
+    report.checks.push(timed("rpc_http", timeout, check_rpc_http(rpc_http_url)).await);
+    report.checks.push(timed("rpc_ws", timeout, check_rpc_ws(rpc_ws_url)).await);
+
+    if publisher_type == "kafka" || publisher_type == "both" {
+        report.checks.push(timed("kafka", timeout, check_kafka()).await);
+    } else {
+        report.checks.push(CheckResult::skipped("kafka", "PUBLISHER_TYPE does not use Kafka"));
+    }
+    if publisher_type == "ws" {
+        let addr = std::env::var("WS_SERVER_ADDR").unwrap_or_else(|_| "0.0.0.0:8900".to_string());
+        report.checks.push(timed("ws_server", timeout, async { check_ws_server(&addr) }).await);
+    } else {
+        report.checks.push(CheckResult::skipped("ws_server", "PUBLISHER_TYPE does not use the WebSocket server"));
+    }
+    if !matches!(publisher_type.as_str(), "kafka" | "ws" | "webhook" | "postgres" | "object-store" | "pubsub" | "mqtt" | "questdb") {
+        let endpoint = std::env::var("ZMQ_ENDPOINT").unwrap_or_else(|_| "tcp://*:5555".to_string());
+        report.checks.push(timed("zmq", timeout, async { check_zmq(&endpoint) }).await);
+    } else {
+        report.checks.push(CheckResult::skipped("zmq", "PUBLISHER_TYPE does not use ZMQ"));
+    }
+    let avro = matches!(publisher_type.as_str(), "kafka" | "both")
+        && std::env::var("KAFKA_SERIALIZATION").is_ok_and(|serialization| serialization == "avro");
+    match std::env::var("SCHEMA_REGISTRY_URL") {
+        Ok(url) if avro => report.checks.push(timed("schema_registry", timeout, check_schema_registry(url)).await),
+        Err(_) if avro => {
+            let missing = async { (CheckStatus::Fail, "SCHEMA_REGISTRY_URL is not set".to_string()) };
+            report.checks.push(timed("schema_registry", timeout, missing).await);
+        }
+        _ => report
+            .checks
+            .push(CheckResult::skipped("schema_registry", "payloads are schemaless JSON")),
+    }
+
+    if std::env::var("DEDUP_BACKEND").is_ok_and(|backend| backend != "none") {
+        report.checks.push(timed("dedup", timeout, check_dedup()).await);
+    } else {
+        report.checks.push(CheckResult::skipped("dedup", "DEDUP_BACKEND not set"));
+    }
+    if Checkpoint::is_configured() {
+        report.checks.push(timed("checkpoint", timeout, check_checkpoint()).await);
+    } else {
+        report.checks.push(CheckResult::skipped("checkpoint", "CHECKPOINT_BACKEND and CHECKPOINT_URL not set"));
+    }
+    match HaConfig::from_env() {
+        Ok(Some(config)) => report.checks.push(timed("ha", timeout, check_ha(config)).await),
+        Ok(None) => report.checks.push(CheckResult::skipped("ha", "HA_MODE not set")),
+        Err(e) => report.checks.push(timed("ha", timeout, async { (CheckStatus::Fail, e) }).await),
+    }
+
+    let shard = match Shard::from_env() {
+        Ok(shard) => {
+            let detail = if shard.is_sharded() { shard.describe() } else { "unsharded".to_string() };
+            report.checks.push(timed("shard", timeout, async { (CheckStatus::Ok, detail) }).await);
+            shard
+        }
+        Err(e) => {
+            report.checks.push(timed("shard", timeout, async { (CheckStatus::Fail, e) }).await);
+            Shard::default()
+        }
+    };
+    if datasource_type.split(',').any(|datasource_type| datasource_type.trim() == "watch") {
+        let check = async {
+            match WatchList::from_env(shard) {
+                Ok(watch_list) => (CheckStatus::Ok, format!("{} pools followed", watch_list.pools().len())),
+                Err(e) => (CheckStatus::Fail, e),
+            }
+        };
+        report.checks.push(timed("watch_list", timeout, check).await);
+    } else {
+        report.checks.push(CheckResult::skipped("watch_list", "DATASOURCE_TYPE does not include watch"));
+    }
+
+    report
+}
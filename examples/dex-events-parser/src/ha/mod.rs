@@ -0,0 +1,194 @@
+pub mod redis_lease;
+
+use {
+    crate::publishers::{DexEventData, UnifiedPublisher},
+    carbon_core::metrics::Metrics,
+    std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicBool, AtomicI64, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    },
+};
+
+const DEFAULT_LEASE_MS: u64 = 10_000;
+const DEFAULT_BUFFER_EVENTS: usize = 10_000;
+
+struct LeadershipState {
+    leader: AtomicBool,
+    /// Newest block time published while leader, `i64::MIN` before any.
+    published_block_time: AtomicI64,
+    buffer_capacity: usize,
+}
+
+/// Whether this instance may publish. Standalone instances always may; in
+/// HA mode only the holder of the shared lease does, while the standby
+/// keeps processing so its state is warm when it takes over.
+#[derive(Clone)]
+pub struct Leadership {
+    ha: bool,
+    state: Arc<LeadershipState>,
+}
+
+impl Leadership {
+    pub fn standalone() -> Self {
+        Self::new(false, true, 0)
+    }
+
+    /// Starts as standby, keeping up to `buffer_capacity` suppressed events
+    /// to replay on takeover.
+    pub fn standby(buffer_capacity: usize) -> Self {
+        Self::new(true, false, buffer_capacity)
+    }
+
+    fn new(ha: bool, leader: bool, buffer_capacity: usize) -> Self {
+        Self {
+            ha,
+            state: Arc::new(LeadershipState {
+                leader: AtomicBool::new(leader),
+                published_block_time: AtomicI64::new(i64::MIN),
+                buffer_capacity,
+            }),
+        }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.state.leader.load(Ordering::Acquire)
+    }
+
+    pub fn set_leader(&self, leader: bool) {
+        self.state.leader.store(leader, Ordering::Release);
+    }
+
+    /// Newest block time published by this instance, written to the shared
+    /// checkpoint so a successor knows where to resume.
+    pub fn published_block_time(&self) -> Option<i64> {
+        Some(self.state.published_block_time.load(Ordering::Acquire)).filter(|block_time| *block_time != i64::MIN)
+    }
+
+    /// Gate in front of the sinks, `None` outside HA mode.
+    pub fn standby_buffer(&self) -> Option<StandbyBuffer> {
+        self.ha.then(|| StandbyBuffer {
+            leadership: self.clone(),
+            events: Arc::new(Mutex::new(VecDeque::new())),
+        })
+    }
+}
+
+/// Holds back the sinks' events while this instance is standby.
+#[derive(Clone)]
+pub struct StandbyBuffer {
+    leadership: Leadership,
+    events: Arc<Mutex<VecDeque<(String, DexEventData)>>>,
+}
+
+impl StandbyBuffer {
+    /// Returns whether `data` may be published now. Otherwise it is kept,
+    /// dropping the oldest event once the buffer is full. Aggregates and
+    /// watermarks carry no block time and are regenerated by the new
+    /// leader, so they are not kept.
+    pub fn admit(&self, topic: &str, data: &DexEventData) -> bool {
+        if self.leadership.is_leader() {
+            return true;
+        }
+        if data.block_time.is_none() {
+            return false;
+        }
+
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        // Promoted while waiting for the lock
+        if self.leadership.is_leader() {
+            return true;
+        }
+        if events.len() >= self.leadership.state.buffer_capacity {
+            events.pop_front();
+        }
+        if self.leadership.state.buffer_capacity > 0 {
+            events.push_back((topic.to_string(), data.clone()));
+        }
+        false
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.leadership.is_leader()
+    }
+
+    pub fn published(&self, data: &DexEventData) {
+        if let Some(block_time) = data.block_time {
+            self.leadership.state.published_block_time.fetch_max(block_time, Ordering::AcqRel);
+        }
+    }
+
+    /// Drains the buffer, keeping events at or after `checkpoint`, the
+    /// newest block time the previous leader published. Events sharing the
+    /// checkpoint's second may be published twice, none are skipped.
+    pub fn take_since(&self, checkpoint: Option<i64>) -> Vec<(String, DexEventData)> {
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        events
+            .drain(..)
+            .filter(|(_, data)| match (data.block_time, checkpoint) {
+                (Some(block_time), Some(checkpoint)) => block_time >= checkpoint,
+                _ => true,
+            })
+            .collect()
+    }
+}
+
+/// Active/standby settings.
+#[derive(Debug, Clone)]
+pub struct HaConfig {
+    pub redis_url: String,
+    pub lock_key: String,
+    pub instance_id: String,
+    pub lease: Duration,
+    pub buffer_events: usize,
+}
+
+impl HaConfig {
+    /// Reads `HA_MODE` (`off` or `redis`, default `off`). In `redis` mode:
+    /// `HA_REDIS_URL` (default `redis://127.0.0.1/`), `HA_LOCK_KEY` (default
+    /// `dex-events:leader`), `HA_INSTANCE_ID` (default hostname and pid),
+    /// `HA_LEASE_MS` (default `10000`) and `HA_BUFFER_EVENTS` (default
+    /// `10000`).
+    pub fn from_env() -> Result<Option<Self>, String> {
+        match std::env::var("HA_MODE").as_deref() {
+            Err(_) | Ok("off") => return Ok(None),
+            Ok("redis") => {}
+            Ok(other) => return Err(format!("Unknown HA_MODE {}", other)),
+        }
+
+        let instance_id = std::env::var("HA_INSTANCE_ID").unwrap_or_else(|_| {
+            let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "parser".to_string());
+            format!("{}-{}", host, std::process::id())
+        });
+        Ok(Some(Self {
+            redis_url: std::env::var("HA_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string()),
+            lock_key: std::env::var("HA_LOCK_KEY").unwrap_or_else(|_| "dex-events:leader".to_string()),
+            instance_id,
+            lease: Duration::from_millis(
+                std::env::var("HA_LEASE_MS")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(DEFAULT_LEASE_MS)
+                    .max(300),
+            ),
+            buffer_events: std::env::var("HA_BUFFER_EVENTS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_BUFFER_EVENTS),
+        }))
+    }
+
+    /// Campaigns for the lease in the background, promoting and demoting
+    /// `leadership` as it is won and lost.
+    pub fn spawn(
+        self,
+        leadership: Leadership,
+        publisher: UnifiedPublisher,
+        metrics: Arc<dyn Metrics>,
+    ) -> tokio::task::JoinHandle<()> {
+        redis_lease::spawn(self, leadership, publisher, metrics)
+    }
+}
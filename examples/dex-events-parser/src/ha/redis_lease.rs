@@ -0,0 +1,167 @@
+use {
+    super::{HaConfig, Leadership},
+    crate::publishers::UnifiedPublisher,
+    carbon_core::metrics::Metrics,
+    redis::aio::ConnectionManager,
+    std::{sync::Arc, time::Instant},
+};
+
+/// Extends the lease and records the leader's checkpoint, but only while
+/// `KEYS[1]` still holds this instance's id.
+const RENEW_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    if ARGV[3] ~= '' then
+        redis.call('SET', KEYS[2], ARGV[3])
+    end
+    return 1
+end
+return 0
+"#;
+
+fn checkpoint_key(config: &HaConfig) -> String {
+    format!("{}:checkpoint", config.lock_key)
+}
+
+async fn acquire(connection: &mut ConnectionManager, config: &HaConfig) -> redis::RedisResult<bool> {
+    let set: Option<String> = redis::cmd("SET")
+        .arg(&config.lock_key)
+        .arg(&config.instance_id)
+        .arg("NX")
+        .arg("PX")
+        .arg(config.lease.as_millis() as u64)
+        .query_async(connection)
+        .await?;
+    Ok(set.is_some())
+}
+
+async fn renew(connection: &mut ConnectionManager, config: &HaConfig, checkpoint: Option<i64>) -> redis::RedisResult<bool> {
+    let renewed: i64 = redis::Script::new(RENEW_SCRIPT)
+        .key(&config.lock_key)
+        .key(checkpoint_key(config))
+        .arg(&config.instance_id)
+        .arg(config.lease.as_millis() as u64)
+        .arg(checkpoint.map(|block_time| block_time.to_string()).unwrap_or_default())
+        .invoke_async(connection)
+        .await?;
+    Ok(renewed == 1)
+}
+
+async fn replay(publisher: &UnifiedPublisher, checkpoint: Option<i64>) -> usize {
+    publisher.replay_standby(checkpoint).await.unwrap_or_else(|e| {
+        log::error!("[HA] Failed to replay standby events: {}", e);
+        0
+    })
+}
+
+async fn set_leader_gauge(metrics: &Arc<dyn Metrics>, leader: bool) {
+    metrics
+        .update_gauge("ha_leader", if leader { 1.0 } else { 0.0 })
+        .await
+        .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+}
+
+/// Campaigns for the Redis lease every third of its length. The leader
+/// renews it and publishes its checkpoint, stepping down one renewal
+/// before the lease may have expired; a standby takes over once it does,
+/// replaying what it held back since that checkpoint before publishing
+/// anything new.
+pub fn spawn(
+    config: HaConfig,
+    leadership: Leadership,
+    publisher: UnifiedPublisher,
+    metrics: Arc<dyn Metrics>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut connection = None;
+        let mut last_renewed = Instant::now();
+        let renew_interval = config.lease / 3;
+        // The last renewal may not have reached Redis before it counts, and
+        // the next tick comes one interval later: step down by then
+        let deadline = config.lease - renew_interval;
+        let mut interval = tokio::time::interval(renew_interval);
+        set_leader_gauge(&metrics, false).await;
+
+        loop {
+            interval.tick().await;
+
+            if connection.is_none() {
+                connection = match redis::Client::open(config.redis_url.as_str()) {
+                    Ok(client) => match ConnectionManager::new(client).await {
+                        Ok(manager) => Some(manager),
+                        Err(e) => {
+                            log::warn!("[HA] Failed to connect to Redis: {}", e);
+                            None
+                        }
+                    },
+                    Err(e) => {
+                        log::error!("[HA] Invalid HA_REDIS_URL {}: {}", config.redis_url, e);
+                        return;
+                    }
+                };
+            }
+
+            let leader = leadership.is_leader();
+            let Some(connection) = connection.as_mut() else {
+                // Without Redis a leader cannot tell whether it still holds the lease
+                if leader && last_renewed.elapsed() >= deadline {
+                    log::warn!("[HA] Lost contact with Redis, stepping down");
+                    leadership.set_leader(false);
+                    set_leader_gauge(&metrics, false).await;
+                }
+                continue;
+            };
+
+            // The lease runs from when the request was sent
+            let attempt = Instant::now();
+            if leader {
+                match renew(connection, &config, leadership.published_block_time()).await {
+                    Ok(true) => last_renewed = attempt,
+                    Ok(false) => {
+                        log::warn!("[HA] Lease lost to another instance, stepping down");
+                        leadership.set_leader(false);
+                        set_leader_gauge(&metrics, false).await;
+                    }
+                    Err(e) => {
+                        log::warn!("[HA] Failed to renew lease: {}", e);
+                        if last_renewed.elapsed() >= deadline {
+                            log::warn!("[HA] Lease expired, stepping down");
+                            leadership.set_leader(false);
+                            set_leader_gauge(&metrics, false).await;
+                        }
+                    }
+                }
+                continue;
+            }
+
+            match acquire(connection, &config).await {
+                Ok(true) => {
+                    last_renewed = attempt;
+                    let checkpoint: Option<i64> = redis::cmd("GET")
+                        .arg(checkpoint_key(&config))
+                        .query_async(connection)
+                        .await
+                        .unwrap_or_else(|e| {
+                            log::warn!("[HA] Failed to read checkpoint, replaying everything held back: {}", e);
+                            None
+                        });
+
+                    log::info!(
+                        "[HA] {} acquired leadership, resuming from block time {:?}",
+                        config.instance_id,
+                        checkpoint
+                    );
+                    // The backlog goes out before new events; the second
+                    // replay catches those held back while the first ran
+                    let mut replayed = replay(&publisher, checkpoint).await;
+                    leadership.set_leader(true);
+                    set_leader_gauge(&metrics, true).await;
+                    replayed += replay(&publisher, checkpoint).await;
+                    log::info!("[HA] Replayed {} events held back while standby", replayed);
+                }
+                Ok(false) => {}
+                Err(e) => log::warn!("[HA] Failed to acquire lease: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,201 @@
+//! DEX events parser: decodes swaps, liquidity changes and new pools of the
+//! major Solana DEX programs and publishes them to ZeroMQ or Kafka.
+//!
+//! The `carbon-dex-events-parser` binary configures everything from the
+//! environment. Other binaries can embed the same pipeline through
+//! [`DexPipelineBuilder`].
+
+pub mod alerts;
+pub mod analytics;
+pub mod checkpoint;
+pub mod dedup;
+pub mod doctor;
+pub mod ha;
+pub mod shard;
+pub mod pipeline;
+pub mod processors;
+pub mod publishers;
+#[cfg(feature = "remote-storage")]
+pub mod remote_storage;
+pub mod datasources;
+pub mod decoders;
+
+pub use pipeline::{DexDatasource, DexPipelineBuilder, Venue};
+
+#[derive(Debug, Clone)]
+pub enum DexEvent {
+    // Swap Events
+    Swap {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Add Liquidity Events
+    AddLiquidity {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Remove Liquidity Events
+    RemoveLiquidity {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Add Pair/Pool Events
+    AddPair {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    NewPair {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Creator Fee Events
+    CreatorFee {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Fee Config Events
+    FeeConfig {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Execution Quality Events
+    ExecutionQuality {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Whale Trade Alerts
+    WhaleTrade {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Launch Lifecycle Transitions
+    LaunchFunnel {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Perp And Spot Order Fills
+    Fill {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Bonding Curve Completion Thresholds And Migrations
+    Graduation {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Transfers, Mints And Burns Of Tracked Mints
+    TokenTransfer {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Decoded Instructions Of A Transaction, Grouped
+    Transaction {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Unexpected Instruction Account Layouts
+    DecodeWarning {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Pools Added By Discovery
+    TrackingStarted {
+        platform: String,
+        signature: String,
+        details: String,
+    },
+    // Unique Traders Per Pool
+    Participation {
+        platform: String,
+        details: String,
+    },
+    // Reserves And Prices From Pool Accounts
+    PoolState {
+        platform: String,
+        details: String,
+    },
+    // Reserves, TVL And Depth Per Pool
+    PoolDepth {
+        platform: String,
+        details: String,
+    },
+}
+
+impl DexEvent {
+    pub fn log(&self) {
+        match self {
+            DexEvent::Swap { platform, signature, details } => {
+                log::info!("[SWAP] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::AddLiquidity { platform, signature, details } => {
+                log::info!("[ADD_LIQUIDITY] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::RemoveLiquidity { platform, signature, details } => {
+                log::info!("[REMOVE_LIQUIDITY] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::AddPair { platform, signature, details } => {
+                log::info!("[ADD_PAIR] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::NewPair { platform, signature, details } => {
+                log::info!("[NEW_PAIR] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::CreatorFee { platform, signature, details } => {
+                log::info!("[CREATOR_FEE] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::FeeConfig { platform, signature, details } => {
+                log::info!("[FEE_CONFIG] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::ExecutionQuality { platform, signature, details } => {
+                log::info!("[EXECUTION_QUALITY] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::WhaleTrade { platform, signature, details } => {
+                log::info!("[WHALE_TRADE] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::LaunchFunnel { platform, signature, details } => {
+                log::info!("[LAUNCH_FUNNEL] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::Fill { platform, signature, details } => {
+                log::info!("[FILL] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::Graduation { platform, signature, details } => {
+                log::info!("[GRADUATION] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::TokenTransfer { platform, signature, details } => {
+                log::info!("[TOKEN_TRANSFER] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::Transaction { platform, signature, details } => {
+                log::info!("[TRANSACTION] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::DecodeWarning { platform, signature, details } => {
+                log::warn!("[DECODE_WARNING] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::TrackingStarted { platform, signature, details } => {
+                log::info!("[TRACKING_STARTED] [{}] [{}] {}", platform, signature, details);
+            }
+            DexEvent::Participation { platform, details } => {
+                log::info!("[PARTICIPATION] [{}] {}", platform, details);
+            }
+            DexEvent::PoolState { platform, details } => {
+                log::info!("[POOL_STATE] [{}] {}", platform, details);
+            }
+            DexEvent::PoolDepth { platform, details } => {
+                log::info!("[POOL_DEPTH] [{}] {}", platform, details);
+            }
+        }
+    }
+}
@@ -1,265 +1,470 @@
-use crate::publishers::{create_unified_publisher_from_env};
-
 use {
     async_trait::async_trait,
     carbon_core::{
         datasource::Update,
         error::CarbonResult,
-        metrics::MetricsCollection,
+        metrics::{Metrics, MetricsCollection},
         processor::Processor,
     },
-    carbon_log_metrics::LogMetrics,
-    carbon_rpc_block_subscribe_datasource::{Filters, RpcBlockSubscribe},
-    solana_client::rpc_config::{RpcBlockSubscribeConfig, RpcBlockSubscribeFilter},
-    solana_commitment_config::CommitmentConfig,
-    solana_transaction_status::{UiTransactionEncoding, TransactionDetails},
-    std::{env, sync::Arc},
-};
-
-
-// Import all decoder types
-use carbon_raydium_amm_v4_decoder::{
-    RaydiumAmmV4Decoder,
-    PROGRAM_ID as RAYDIUM_AMM_V4_PROGRAM_ID,
-};
-use carbon_raydium_clmm_decoder::{
-    RaydiumClmmDecoder, PROGRAM_ID as RAYDIUM_CLMM_PROGRAM_ID,
-};
-use carbon_raydium_cpmm_decoder::{
-    RaydiumCpmmDecoder, PROGRAM_ID as RAYDIUM_CPMM_PROGRAM_ID,
-};
-use carbon_jupiter_swap_decoder::{
-    JupiterSwapDecoder, PROGRAM_ID as JUPITER_SWAP_PROGRAM_ID,
-};
-use carbon_orca_whirlpool_decoder::{
-    OrcaWhirlpoolDecoder, PROGRAM_ID as ORCA_WHIRLPOOL_PROGRAM_ID,
-};
-use carbon_meteora_dlmm_decoder::{
-    MeteoraDlmmDecoder, PROGRAM_ID as METEORA_DLMM_PROGRAM_ID,
-};
-use carbon_pumpfun_decoder::{
-    PumpfunDecoder, PROGRAM_ID as PUMPFUN_PROGRAM_ID,
-};
-use carbon_lifinity_amm_v2_decoder::{
-    LifinityAmmV2Decoder, PROGRAM_ID as LIFINITY_AMM_V2_PROGRAM_ID,
-};
-use carbon_moonshot_decoder::{
-    MoonshotDecoder, PROGRAM_ID as MOONSHOT_PROGRAM_ID,
-};
-use carbon_openbook_v2_decoder::{
-    OpenbookV2Decoder, PROGRAM_ID as OPENBOOK_V2_PROGRAM_ID,
-};
-use carbon_phoenix_v1_decoder::{
-    PhoenixDecoder, PROGRAM_ID as PHOENIX_PROGRAM_ID,
-};
-use carbon_fluxbeam_decoder::{
-    FluxbeamDecoder, PROGRAM_ID as FLUXBEAM_PROGRAM_ID,
-};
-
-mod processors;
-mod publishers;
-mod datasources;
-
-use processors::{
-    raydium_amm_v4::RaydiumAmmV4Processor,
-    raydium_clmm::RaydiumClmmProcessor,
-    pumpfun::PumpfunProcessor,
-    others::{
-        RaydiumCpmmProcessor,
-        JupiterSwapProcessor,
-        OrcaWhirlpoolProcessor,
-        MeteoraDlmmProcessor,
-        OpenbookV2Processor,
-        PhoenixProcessor,
-        FluxbeamProcessor,
-        LifinityAmmV2Processor,
-        MoonshotProcessor,
+    carbon_dex_events_parser::{
+        analytics::{
+            aggregator::AggregatorPolicy, bonding_curve::BondingCurveProgress, launchpads::LaunchpadConfigs,
+            pool_registry::PoolRegistry, pool_reserves::PoolReserves, pool_state::PoolStates,
+            tracked_mints::TrackedMints, usd_prices::UsdPrices,
+        },
+        checkpoint::Checkpoint,
+        dedup::Dedup,
+        doctor,
+        ha::{HaConfig, Leadership},
+        publishers::{
+            create_unified_publisher_from_env, drain_timeout_from_env, publisher_type_from_env, ConsumerLagMonitor, DeliveryLagConfig, MintDenylist,
+            Publisher, UnifiedPublisher,
+        },
+        datasources::{
+            discovery::PoolDiscovery, AddressBackfill, CaptureTarget, ChunkLimits, CommitmentStrategy, HeliusWebhookConfig, MaintenanceWindows, SignatureDedup, WatchList,
+        },
+        shard::{Shard, ShardKey},
+        DexDatasource, DexPipelineBuilder,
     },
+    carbon_log_metrics::LogMetrics,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{env, path::PathBuf, str::FromStr, sync::Arc},
 };
-use datasources::{HybridBlockDatasource, HybridFilters};
-
-#[derive(Debug, Clone)]
-pub enum DexEvent {
-    // Swap Events
-    Swap {
-        platform: String,
-        signature: String,
-        details: String,
-    },
-    // Add Liquidity Events
-    AddLiquidity {
-        platform: String,
-        signature: String,
-        details: String,
-    },
-    // Remove Liquidity Events
-    RemoveLiquidity {
-        platform: String,
-        signature: String,
-        details: String,
-    },
-    // Add Pair/Pool Events
-    AddPair {
-        platform: String,
-        signature: String,
-        details: String,
-    },
-    NewPair {
-        platform: String,
-        signature: String,
-        details: String,
-    },
-}
-
-impl DexEvent {
-    pub fn log(&self) {
-        match self {
-            DexEvent::Swap { platform, signature, details } => {
-                log::info!("[SWAP] [{}] [{}] {}", platform, signature, details);
-            }
-            DexEvent::AddLiquidity { platform, signature, details } => {
-                log::info!("[ADD_LIQUIDITY] [{}] [{}] {}", platform, signature, details);
-            }
-            DexEvent::RemoveLiquidity { platform, signature, details } => {
-                log::info!("[REMOVE_LIQUIDITY] [{}] [{}] {}", platform, signature, details);
-            }
-            DexEvent::AddPair { platform, signature, details } => {
-                log::info!("[ADD_PAIR] [{}] [{}] {}", platform, signature, details);
-            }
-            DexEvent::NewPair { platform, signature, details } => {
-                log::info!("[NEW_PAIR] [{}] [{}] {}", platform, signature, details);
-            }
-        }
-    }
-}
 
 #[tokio::main]
 pub async fn main() -> CarbonResult<()> {
     dotenv::dotenv().ok();
     env_logger::init();
 
+    // `doctor` only checks connectivity and exits
+    if env::args().nth(1).as_deref() == Some("doctor") {
+        let report = doctor::run().await;
+        report.print();
+        return if report.passed() {
+            Ok(())
+        } else {
+            Err(carbon_core::error::Error::Custom("doctor found failing checks".to_string()))
+        };
+    }
+    if env::var("STARTUP_CHECKS").is_ok_and(|value| value == "true" || value == "1") {
+        let report = doctor::run().await;
+        report.print();
+        if !report.passed() {
+            return Err(carbon_core::error::Error::Custom(
+                "Startup checks failed, see the report above".to_string(),
+            ));
+        }
+    }
+
+    // `replay-signature <signature> [--dry-run]` reprocesses one transaction and exits,
+    // `backfill-address <address> [...]` all transactions of an address and
+    // `replay-capture <file> [...]` a capture recorded with CAPTURE_FILE or CAPTURE_URL
+    let args = env::args().collect::<Vec<_>>();
+    let replay = match args.get(1).map(String::as_str) {
+        Some("replay-signature") => {
+            let signature = args
+                .get(2)
+                .and_then(|signature| Signature::from_str(signature).ok())
+                .ok_or_else(|| {
+                    carbon_core::error::Error::Custom(
+                        "Usage: carbon-dex-events-parser replay-signature <signature> [--dry-run]".to_string(),
+                    )
+                })?;
+            Some((DexDatasource::Signature(signature), args[3..].iter().any(|arg| arg == "--dry-run")))
+        }
+        Some("backfill-address") => {
+            let backfill = address_backfill(&args[2..]).map_err(|e| {
+                carbon_core::error::Error::Custom(format!(
+                    "{}\nUsage: carbon-dex-events-parser backfill-address <address> [--before <signature>] [--until <signature>] [--limit <n>] [--dry-run]",
+                    e
+                ))
+            })?;
+            Some((DexDatasource::AddressBackfill(backfill), args[3..].iter().any(|arg| arg == "--dry-run")))
+        }
+        Some("replay-capture") => {
+            let capture = capture_replay(&args[2..]).map_err(|e| {
+                carbon_core::error::Error::Custom(format!(
+                    "{}\nUsage: carbon-dex-events-parser replay-capture <file|s3://bucket/key> [--speed <x>] [--from-slot <slot>] [--to-slot <slot>] [--dry-run]",
+                    e
+                ))
+            })?;
+            Some((capture, args[3..].iter().any(|arg| arg == "--dry-run")))
+        }
+        _ => None,
+    };
+
     log::info!("Starting DEX Events Parser...");
 
     let rpc_ws_url = env::var("RPC_WS_URL")
         .unwrap_or_else(|_| "wss://api.mainnet-beta.solana.com".to_string());
     let rpc_http_url = env::var("RPC_HTTP_URL")
         .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string());
-    let datasource_type = env::var("DATASOURCE_TYPE")
-        .unwrap_or_else(|_| "websocket".to_string());
+    // Several comma separated types run side by side for redundancy
+    let datasource_types = match replay {
+        Some(_) => vec!["replay".to_string()],
+        None => match env_list("DATASOURCE_TYPE") {
+            types if types.is_empty() => vec!["websocket".to_string()],
+            types => types,
+        },
+    };
+    let datasource_type = datasource_types.join(",");
 
     log::info!("RPC WebSocket: {}", rpc_ws_url);
     log::info!("RPC HTTP: {}", rpc_http_url);
+    // Blocks of the hybrid datasource can be fetched from several endpoints,
+    // and its notifications fail over to backup WebSocket endpoints
+    let block_fetch_urls = env_list("RPC_HTTP_URLS");
+    let backup_ws_urls = env_list("RPC_WS_BACKUP_URLS");
     log::info!("Datasource type: {}", datasource_type);
     
-    // Get publisher type from environment
-    let publisher_type = env::var("PUBLISHER_TYPE").unwrap_or_else(|_| "zmq".to_string());
+    // Get publisher type from environment; a dry-run replay sends events
+    // to stdout instead of the configured sinks
+    let publisher_type = match replay {
+        Some((_, true)) => "stdout".to_string(),
+        _ => publisher_type_from_env(),
+    };
     
     log::info!("Publisher type: {}", publisher_type);
-    let publisher = create_unified_publisher_from_env().map_err(|e| carbon_core::error::Error::Custom(format!("Failed to create publisher: {}", e)))?;
-    
-    // Configure RPC block subscribe with multiple program IDs
-    let program_ids = vec![
-        RAYDIUM_AMM_V4_PROGRAM_ID.to_string(),
-        RAYDIUM_CLMM_PROGRAM_ID.to_string(),
-        RAYDIUM_CPMM_PROGRAM_ID.to_string(),
-        JUPITER_SWAP_PROGRAM_ID.to_string(),
-        ORCA_WHIRLPOOL_PROGRAM_ID.to_string(),
-        METEORA_DLMM_PROGRAM_ID.to_string(),
-        PUMPFUN_PROGRAM_ID.to_string(),
-        OPENBOOK_V2_PROGRAM_ID.to_string(),
-        PHOENIX_PROGRAM_ID.to_string(),
-        FLUXBEAM_PROGRAM_ID.to_string(),
-        LIFINITY_AMM_V2_PROGRAM_ID.to_string(),
-        MOONSHOT_PROGRAM_ID.to_string(),
-    ];
-    
-    // Use the first program ID as the main filter
-    let block_filter = RpcBlockSubscribeFilter::MentionsAccountOrProgram(
-        program_ids[0].clone()
-    );
+    // In HA mode publishing is held back until this instance holds the lease
+    // A replay publishes right away, whatever the HA role
+    let ha = match replay {
+        Some(_) => None,
+        None => HaConfig::from_env().map_err(carbon_core::error::Error::Custom)?,
+    };
+    let leadership = ha.as_ref().map_or_else(Leadership::standalone, |config| Leadership::standby(config.buffer_events));
+    let denylist = MintDenylist::from_env().map_err(carbon_core::error::Error::Custom)?;
+    if let Some(denylist) = &denylist {
+        match denylist.quarantine_topic() {
+            Some(topic) => log::info!("Events with denied mints go to topic {}", topic),
+            None => log::info!("Events with denied mints are dropped"),
+        }
+        denylist.spawn_reloader();
+    }
+    let publisher = create_unified_publisher_from_env(&publisher_type, &leadership, denylist).await?;
+    let drain_timeout = drain_timeout_from_env().map_err(carbon_core::error::Error::Custom)?;
 
-    let block_subscribe_config = RpcBlockSubscribeConfig {
-        commitment: Some(CommitmentConfig::confirmed()),
-        encoding: Some(UiTransactionEncoding::Base64),
-        transaction_details: Some(TransactionDetails::Full),
-        show_rewards: Some(false),
-        max_supported_transaction_version: Some(0),
+    // The replayed transaction was most likely seen before
+    let dedup = match replay {
+        Some(_) => Dedup::default(),
+        None => Dedup::from_env().await.map_err(|e| carbon_core::error::Error::Custom(format!("Failed to create dedup backend: {}", e)))?,
     };
+    if dedup.is_enabled() {
+        log::info!("Deduplicating instructions across redeliveries");
+    }
+    // Only the hybrid datasource resumes from a slot
+    let checkpoint = if datasource_types.iter().any(|datasource_type| datasource_type == "hybrid") {
+        Checkpoint::from_env()
+            .await
+            .map_err(|e| carbon_core::error::Error::Custom(format!("Failed to create checkpoint store: {}", e)))?
+    } else {
+        if Checkpoint::is_configured() {
+            log::warn!("The checkpoint store is ignored with the {} datasource", datasource_type);
+        }
+        Checkpoint::default()
+    };
+    if checkpoint.is_enabled() {
+        log::info!("Resuming from the last checkpointed slot on restart");
+    }
+    let shard = match replay {
+        Some(_) => Shard::default(),
+        None => Shard::from_env().map_err(carbon_core::error::Error::Custom)?,
+    };
+    if shard.is_sharded() {
+        log::info!("Running shard {}", shard.describe());
+        // Only the watch datasource subscribes per pool
+        let unsharded = datasource_types
+            .iter()
+            .filter(|datasource_type| *datasource_type != "watch")
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if shard.key() == ShardKey::Pool && !unsharded.is_empty() {
+            return Err(carbon_core::error::Error::Custom(format!(
+                "SHARD_BY=pool requires DATASOURCE_TYPE=watch, {} cannot be sharded by pool",
+                unsharded.join(",")
+            )));
+        }
+    }
+    let maintenance = MaintenanceWindows::from_env().map_err(carbon_core::error::Error::Custom)?;
+    if maintenance.is_enabled() {
+        log::info!("Pausing for {} maintenance windows", maintenance.window_count());
+    }
+    publisher.spawn_watermarks();
+    // Shared with the pipeline, which flushes it
+    let metrics: Arc<dyn Metrics> = Arc::new(LogMetrics::new());
+    publisher.spawn_delivery_lag(DeliveryLagConfig::from_env(), metrics.clone());
+    publisher.spawn_circuit_breakers(metrics.clone());
+    publisher.spawn_queue_depth(metrics.clone());
+    publisher.spawn_publisher_metrics(metrics.clone());
+    if let Some(consumer_lag) = ConsumerLagMonitor::from_env().map_err(carbon_core::error::Error::Custom)? {
+        log::info!("Monitoring lag of {} downstream consumer groups", consumer_lag.group_count());
+        consumer_lag.spawn(metrics.clone());
+    }
+    if let Some(ha) = ha {
+        log::info!("HA mode: {} starting as standby", ha.instance_id);
+        ha.spawn(leadership, publisher.clone(), metrics.clone());
+    }
+
+    // Pool accounts are followed live only
+    let pool_states = match replay {
+        Some(_) => PoolStates::default(),
+        None => PoolStates::from_env(&rpc_http_url)
+            .await
+            .map_err(|e| carbon_core::error::Error::Custom(format!("Failed to look up pool states: {}", e)))?,
+    };
+    if pool_states.is_enabled() {
+        log::info!("Following {} pool account subscriptions", pool_states.subscriptions().len());
+    }
+    // Replayed swaps are older than any live oracle price
+    let usd_prices = match replay {
+        Some(_) => UsdPrices::default(),
+        None => UsdPrices::from_env().map_err(carbon_core::error::Error::Custom)?,
+    };
+    if usd_prices.is_enabled() {
+        log::info!("Valuing swaps in USD with {} oracle feeds", usd_prices.subscriptions().len());
+    }
+    // The pair of a pool never changes, replays are tagged too
+    let pool_registry = PoolRegistry::from_env(&rpc_http_url).map_err(carbon_core::error::Error::Custom)?;
+    if pool_registry.is_enabled() {
+        log::info!("Tagging events with their trading pair, {} pools known", pool_registry.len());
+    }
+    let pool_reserves = PoolReserves::from_env(pool_registry.clone(), usd_prices.clone())
+        .map_err(carbon_core::error::Error::Custom)?;
+    if pool_reserves.is_enabled() {
+        log::info!("Tracking pool reserves and publishing their depth");
+    }
+    let curve_progress = BondingCurveProgress::from_env().map_err(carbon_core::error::Error::Custom)?;
+    let launchpads = LaunchpadConfigs::from_env().map_err(carbon_core::error::Error::Custom)?;
+    let tracked_mints = TrackedMints::from_env().map_err(carbon_core::error::Error::Custom)?;
+    if tracked_mints.is_enabled() {
+        log::info!("Publishing the transfers of {} tracked mints", tracked_mints.mints().count());
+    }
+    let aggregator_policy = AggregatorPolicy::from_env().map_err(carbon_core::error::Error::Custom)?;
+    if aggregator_policy == AggregatorPolicy::Suppress {
+        log::info!("Skipping venue instructions routed through an aggregator");
+    }
+    let group_transactions = env::var("GROUP_TRANSACTIONS").is_ok_and(|value| value == "true" || value == "1");
+    if group_transactions {
+        log::info!("Publishing the decoded instructions of each transaction together");
+    }
+    let failed_transactions = env::var("INCLUDE_FAILED_TRANSACTIONS").is_ok_and(|value| value == "true" || value == "1");
+    if failed_transactions {
+        // The enhanced payload has no RPC error to flag them with
+        if datasource_types.iter().any(|datasource_type| datasource_type == "helius-webhook") {
+            return Err(carbon_core::error::Error::Custom(
+                "INCLUDE_FAILED_TRANSACTIONS is not supported by the helius-webhook datasource".to_string(),
+            ));
+        }
+        log::info!("Processing failed transactions, their events are flagged with success=false");
+    }
+    // Tee mode records the live datasource for `replay-capture`
+    let capture = match replay {
+        Some(_) => None,
+        None => CaptureTarget::from_env().map_err(carbon_core::error::Error::Custom)?,
+    };
+    let capture_limits = ChunkLimits::from_env().map_err(carbon_core::error::Error::Custom)?;
 
     // Create datasource based on type
-    match datasource_type.as_str() {
+    let datasource = match replay {
+        Some((datasource, _)) => datasource,
+        None => {
+            let mut datasources = datasource_types
+                .iter()
+                .map(|datasource_type| live_datasource(datasource_type, shard.clone()))
+                .collect::<CarbonResult<Vec<_>>>()?;
+            match datasources.len() {
+                1 => datasources.remove(0),
+                count => {
+                    log::info!("Running {} datasources side by side, each transaction is processed once", count);
+                    DexDatasource::Redundant(datasources)
+                }
+            }
+        }
+    };
+    let signature_dedup = SignatureDedup::from_env().map_err(carbon_core::error::Error::Custom)?;
+    let commitment_strategy = CommitmentStrategy::from_env().map_err(carbon_core::error::Error::Custom)?;
+
+    // Enrichment stages not set here are configured from the environment
+    let builder = DexPipelineBuilder::new(publisher.clone())
+        .rpc(rpc_ws_url, rpc_http_url)
+        .block_fetch_urls(block_fetch_urls)
+        .backup_ws_urls(backup_ws_urls)
+        .commitment_strategy(commitment_strategy)
+        .signature_dedup(signature_dedup)
+        .datasource(datasource)
+        .metrics(metrics)
+        .dedup(dedup)
+        .checkpoint(checkpoint)
+        .shard(shard)
+        .maintenance(maintenance)
+        .pool_states(pool_states)
+        .usd_prices(usd_prices)
+        .pool_registry(pool_registry)
+        .pool_reserves(pool_reserves)
+        .curve_progress(curve_progress)
+        .launchpads(launchpads)
+        .tracked_mints(tracked_mints)
+        .aggregator_policy(aggregator_policy)
+        .group_transactions(group_transactions)
+        .failed_transactions(failed_transactions);
+    let builder = match capture {
+        Some(target) => {
+            log::info!("Recording every update to {}", target);
+            builder.capture(target, capture_limits)
+        }
+        None => builder,
+    };
+    let mut pipeline = builder.build()?;
+    // The pipeline stops on SIGINT itself
+    let result = tokio::select! {
+        result = pipeline.run() => result,
+        _ = terminated() => {
+            log::info!("Received SIGTERM, shutting down");
+            Ok(())
+        }
+    };
+    close_publisher(&publisher, drain_timeout).await;
+    result
+}
+
+/// Resolves on SIGTERM, never on platforms without it.
+async fn terminated() {
+    #[cfg(unix)]
+    match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+        Ok(mut sigterm) => {
+            sigterm.recv().await;
+            return;
+        }
+        Err(e) => log::error!("Failed to listen for SIGTERM: {}", e),
+    }
+    std::future::pending::<()>().await
+}
+
+/// Flushes what the sinks still queue or buffer, the last block's events
+/// included, waiting at most `drain_timeout`.
+async fn close_publisher(publisher: &UnifiedPublisher, drain_timeout: std::time::Duration) {
+    log::info!("Draining the publisher for up to {:?}", drain_timeout);
+    match tokio::time::timeout(drain_timeout, publisher.close()).await {
+        Ok(Ok(())) => log::info!("Publisher drained"),
+        Ok(Err(e)) => log::error!("Failed to drain the publisher: {}", e),
+        Err(_) => log::error!("Publisher not drained within {:?}, events may be lost", drain_timeout),
+    }
+}
+
+
+/// A datasource following the chain live, `websocket` for unknown types.
+fn live_datasource(datasource_type: &str, shard: Shard) -> CarbonResult<DexDatasource> {
+    Ok(match datasource_type {
         "hybrid" => {
             log::info!("Using Hybrid Datasource (WebSocket notifications + HTTP RPC data)");
-            
-            let hybrid_filters = HybridFilters::new(
-                block_filter,
-                Some(CommitmentConfig::confirmed()),
-            );
-            
-            let hybrid_datasource = HybridBlockDatasource::new(
-                rpc_ws_url,
-                rpc_http_url,
-                hybrid_filters,
-            );
-            
-            // Create processors for all decoders
-            carbon_core::pipeline::Pipeline::builder()
-                .datasource(hybrid_datasource)
-                .metrics(Arc::new(LogMetrics::new()))
-                .metrics_flush_interval(5)
-                .instruction(RaydiumAmmV4Decoder, RaydiumAmmV4Processor::new(publisher.clone()))
-                .instruction(RaydiumClmmDecoder, RaydiumClmmProcessor::new(publisher.clone()))
-                .instruction(RaydiumCpmmDecoder, RaydiumCpmmProcessor::new(publisher.clone()))
-                .instruction(JupiterSwapDecoder, JupiterSwapProcessor::new(publisher.clone()))
-                .instruction(OrcaWhirlpoolDecoder, OrcaWhirlpoolProcessor::new(publisher.clone()))
-                .instruction(MeteoraDlmmDecoder, MeteoraDlmmProcessor::new(publisher.clone()))
-                .instruction(PumpfunDecoder, PumpfunProcessor::new(publisher.clone()))
-                .instruction(OpenbookV2Decoder, OpenbookV2Processor::new(publisher.clone()))
-                .instruction(PhoenixDecoder, PhoenixProcessor::new(publisher.clone()))
-                .instruction(FluxbeamDecoder, FluxbeamProcessor::new(publisher.clone()))
-                .instruction(LifinityAmmV2Decoder, LifinityAmmV2Processor::new(publisher.clone()))
-                .instruction(MoonshotDecoder, MoonshotProcessor::new(publisher.clone()))
-                .shutdown_strategy(carbon_core::pipeline::ShutdownStrategy::Immediate)
-                .build()?
-                .run()
-                .await?;
+            DexDatasource::Hybrid
+        }
+        "watch" => {
+            log::info!("Using Watched Pools Datasource (block subscriptions per watched pool)");
+
+            let watch_list = WatchList::from_env(shard).map_err(carbon_core::error::Error::Custom)?;
+            watch_list.spawn_reloader();
+            // New pools are only discovered in watch mode, every pool is processed otherwise
+            let discovery = PoolDiscovery::from_env(&watch_list);
+            DexDatasource::Watch { watch_list, discovery }
+        }
+        "helius-webhook" => {
+            let config = HeliusWebhookConfig::from_env().map_err(carbon_core::error::Error::Custom)?;
+            log::info!("Using Helius Webhook Datasource (enhanced transactions POSTed to {})", config.addr());
+            DexDatasource::HeliusWebhook(config)
         }
         _ => {
             log::info!("Using Traditional WebSocket Datasource (full data over WebSocket)");
-            
-            let filters = Filters::new(block_filter, Some(block_subscribe_config));
-            let datasource = RpcBlockSubscribe::new(rpc_ws_url, filters);
-            
-            // Create processors for all decoders
-            carbon_core::pipeline::Pipeline::builder()
-                .datasource(datasource)
-                .metrics(Arc::new(LogMetrics::new()))
-                .metrics_flush_interval(5)
-                .instruction(RaydiumAmmV4Decoder, RaydiumAmmV4Processor::new(publisher.clone()))
-                .instruction(RaydiumClmmDecoder, RaydiumClmmProcessor::new(publisher.clone()))
-                .instruction(RaydiumCpmmDecoder, RaydiumCpmmProcessor::new(publisher.clone()))
-                .instruction(JupiterSwapDecoder, JupiterSwapProcessor::new(publisher.clone()))
-                .instruction(OrcaWhirlpoolDecoder, OrcaWhirlpoolProcessor::new(publisher.clone()))
-                .instruction(MeteoraDlmmDecoder, MeteoraDlmmProcessor::new(publisher.clone()))
-                .instruction(PumpfunDecoder, PumpfunProcessor::new(publisher.clone()))
-                .instruction(OpenbookV2Decoder, OpenbookV2Processor::new(publisher.clone()))
-                .instruction(PhoenixDecoder, PhoenixProcessor::new(publisher.clone()))
-                .instruction(FluxbeamDecoder, FluxbeamProcessor::new(publisher.clone()))
-                .instruction(LifinityAmmV2Decoder, LifinityAmmV2Processor::new(publisher.clone()))
-                .instruction(MoonshotDecoder, MoonshotProcessor::new(publisher.clone()))
-                .shutdown_strategy(carbon_core::pipeline::ShutdownStrategy::Immediate)
-                .build()?
-                .run()
-                .await?;
+            DexDatasource::WebSocket
         }
-    }
+    })
+}
 
+/// Comma separated values of an environment variable, empty when unset.
+fn env_list(name: &str) -> Vec<String> {
+    env::var(name)
+        .map(|urls| {
+            urls.split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
 
+/// Parses `<address> [--before <signature>] [--until <signature>] [--limit <n>] [--dry-run]`.
+fn address_backfill(args: &[String]) -> Result<AddressBackfill, String> {
+    let address = args.first().ok_or("Missing address")?;
+    let mut backfill = AddressBackfill {
+        address: Pubkey::from_str(address).map_err(|e| format!("Invalid address {}: {}", address, e))?,
+        before: None,
+        until: None,
+        limit: None,
+    };
 
-    Ok(())
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        if arg == "--dry-run" {
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+        match arg.as_str() {
+            "--before" | "--until" => {
+                let signature =
+                    Signature::from_str(value).map_err(|e| format!("Invalid signature {}: {}", value, e))?;
+                if arg == "--before" {
+                    backfill.before = Some(signature);
+                } else {
+                    backfill.until = Some(signature);
+                }
+            }
+            "--limit" => {
+                backfill.limit = Some(value.parse().map_err(|e| format!("Invalid limit {}: {}", value, e))?);
+            }
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    Ok(backfill)
 }
 
+/// Parses `<file> [--speed <x>] [--from-slot <slot>] [--to-slot <slot>]
+/// [--dry-run]`, the file may be an `s3://` or `gs://` object. The speed
+/// defaults to the recorded pace and `0` replays as fast as possible. The
+/// slot range is inclusive and open on the sides not given.
+fn capture_replay(args: &[String]) -> Result<DexDatasource, String> {
+    let path = args.first().ok_or("Missing capture file")?;
+    let mut speed = 1.0;
+    let mut from_slot = None;
+    let mut to_slot = None;
 
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => {}
+            "--speed" => {
+                let value = args.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+                speed = value.parse().map_err(|e| format!("Invalid speed {}: {}", value, e))?;
+            }
+            "--from-slot" | "--to-slot" => {
+                let value = args.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+                let slot = value.parse().map_err(|e| format!("Invalid slot {}: {}", value, e))?;
+                if arg == "--from-slot" {
+                    from_slot = Some(slot);
+                } else {
+                    to_slot = Some(slot);
+                }
+            }
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    Ok(DexDatasource::Capture {
+        path: PathBuf::from(path),
+        speed,
+        from_slot,
+        to_slot,
+    })
+}
 
 // Generic Update Processor for block details
 pub struct UpdateProcessor;
@@ -273,13 +478,10 @@ impl Processor for UpdateProcessor {
         data: Self::InputType,
         _metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
-        match data {
-            Update::BlockDetails(block_details) => {
-                log::debug!("Block processed: slot={}, transactions={}", 
-                           block_details.slot, 
-                           block_details.block_time.unwrap_or(0));
-            }
-            _ => {}
+        if let Update::BlockDetails(block_details) = data {
+            log::debug!("Block processed: slot={}, transactions={}", 
+                       block_details.slot, 
+                       block_details.block_time.unwrap_or(0));
         }
         Ok(())
     }
@@ -0,0 +1,1103 @@
+use {
+    crate::{
+        alerts::whale::WhaleAlerts,
+        analytics::{
+            aggregator::AggregatorPolicy, bonding_curve::BondingCurveProgress, execution_quality::ExecutionQuality,
+            launch_funnel::LaunchFunnel, launchpads::LaunchpadConfigs,
+            participation::ParticipationTracker, pool_registry::PoolRegistry, pool_reserves::PoolReserves,
+            pool_state::PoolStates, price_index::PriceIndex, symbols::SymbolRegistry, tracked_mints::TrackedMints,
+            usd_prices::UsdPrices,
+        },
+        datasources::{
+            discovery::PoolDiscovery, AddressBackfill, AddressBackfillDatasource, CommitmentStrategy, FanInDatasource, HeliusWebhookConfig, HeliusWebhookDatasource, HybridBlockDatasource,
+            CaptureTarget, ChunkLimits, HybridFilters, MaintenanceWindows, PoolAccountsDatasource, ReplayDatasource, SignatureDatasource, SignatureDedup, TeeDatasource, WatchList, WatchedPoolsDatasource,
+        },
+        checkpoint::Checkpoint,
+        decoders::{
+            invariant::InvariantDecoder,
+            oracle::OracleDecoder,
+            sanctum::{SanctumInfinityDecoder, SanctumRouterDecoder},
+            token::TokenActivityDecoder,
+        },
+        dedup::Dedup,
+        processors::{
+            drift::DriftProcessor,
+            fluxbeam::FluxbeamProcessor,
+            invariant::InvariantProcessor,
+            jupiter::JupiterSwapProcessor,
+            moonshot::MoonshotProcessor,
+            oracle::OraclePriceProcessor,
+            pool_state::{AmmInfoStateProcessor, AmmVaultProcessor, ClmmStateProcessor, WhirlpoolStateProcessor},
+            others::{
+                LifinityAmmV2Processor, MeteoraDlmmProcessor, MeteoraPoolsProcessor, OpenbookV2Processor, OrcaWhirlpoolProcessor,
+                PhoenixProcessor, RaydiumCpmmProcessor,
+            },
+            pump_swap::PumpSwapProcessor,
+            pumpfun::PumpfunProcessor,
+            raydium_amm_v4::RaydiumAmmV4Processor,
+            raydium_clmm::RaydiumClmmProcessor,
+            raydium_launchpad::RaydiumLaunchpadProcessor,
+            sanctum::{SanctumInfinityProcessor, SanctumRouterProcessor},
+            timeout::ProcessorTimeouts,
+            token::TokenTransferProcessor,
+            transaction::{GroupedInstruction, TransactionProcessor},
+            virtual_curve::VirtualCurveProcessor,
+            InstructionInput,
+        },
+        publishers::UnifiedPublisher,
+        shard::Shard,
+    },
+    async_trait::async_trait,
+    carbon_core::{
+        datasource::{Datasource, DatasourceId, Update, UpdateType},
+        error::{CarbonResult, Error},
+        filter::Filter,
+        instruction::InstructionDecoder,
+        metrics::{Metrics, MetricsCollection},
+        pipeline::{Pipeline, PipelineBuilder, ShutdownStrategy},
+        processor::Processor,
+    },
+    carbon_drift_v2_decoder::DriftDecoder,
+    carbon_fluxbeam_decoder::FluxbeamDecoder,
+    carbon_jupiter_swap_decoder::JupiterSwapDecoder,
+    carbon_lifinity_amm_v2_decoder::LifinityAmmV2Decoder,
+    carbon_log_metrics::LogMetrics,
+    carbon_meteora_dlmm_decoder::MeteoraDlmmDecoder,
+    carbon_meteora_pools_decoder::MeteoraPoolsDecoder,
+    carbon_moonshot_decoder::MoonshotDecoder,
+    carbon_openbook_v2_decoder::OpenbookV2Decoder,
+    carbon_orca_whirlpool_decoder::OrcaWhirlpoolDecoder,
+    carbon_phoenix_v1_decoder::PhoenixDecoder,
+    carbon_pump_swap_decoder::PumpSwapDecoder,
+    carbon_pumpfun_decoder::PumpfunDecoder,
+    carbon_raydium_amm_v4_decoder::RaydiumAmmV4Decoder,
+    carbon_raydium_clmm_decoder::RaydiumClmmDecoder,
+    carbon_raydium_cpmm_decoder::RaydiumCpmmDecoder,
+    carbon_raydium_launchpad_decoder::RaydiumLaunchpadDecoder,
+    carbon_rpc_block_subscribe_datasource::{Filters, RpcBlockSubscribe},
+    carbon_token_program_decoder::TokenProgramDecoder,
+    carbon_virtual_curve_decoder::VirtualCurveDecoder,
+    solana_client::rpc_config::{RpcBlockSubscribeConfig, RpcBlockSubscribeFilter},
+    solana_commitment_config::CommitmentConfig,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_transaction_status::{TransactionDetails, UiTransactionEncoding},
+    std::{path::PathBuf, sync::Arc},
+    tokio::sync::mpsc::Sender,
+    tokio_util::sync::CancellationToken,
+};
+#[cfg(feature = "jupiter-legacy")]
+use crate::{
+    decoders::jupiter_legacy::JupiterLegacyDecoder,
+    processors::jupiter::{JupiterLegacyProcessor, JupiterProgramVersion},
+};
+
+/// A DEX program the pipeline can decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Venue {
+    RaydiumAmmV4,
+    RaydiumClmm,
+    RaydiumCpmm,
+    /// Jupiter v6, and the pre-v6 programs with the `jupiter-legacy` feature.
+    JupiterSwap,
+    OrcaWhirlpool,
+    MeteoraDlmm,
+    /// Meteora Dynamic AMM pools.
+    MeteoraPools,
+    Pumpfun,
+    PumpSwap,
+    OpenbookV2,
+    Phoenix,
+    Fluxbeam,
+    LifinityAmmV2,
+    Moonshot,
+    /// Raydium LaunchLab bonding curves.
+    RaydiumLaunchpad,
+    /// Perp and spot fills of Drift v2.
+    DriftV2,
+    /// Invariant concentrated-liquidity pools.
+    Invariant,
+    /// Sanctum's multi-LST pool.
+    SanctumInfinity,
+    /// Sanctum's stake router, for SOL to LST and LST to LST swaps.
+    SanctumRouter,
+    /// Meteora Dynamic Bonding Curves, the launches of Believe and other
+    /// launchpads.
+    VirtualCurve,
+}
+
+impl Venue {
+    pub const ALL: [Venue; 20] = [
+        Venue::RaydiumAmmV4,
+        Venue::RaydiumClmm,
+        Venue::RaydiumCpmm,
+        Venue::JupiterSwap,
+        Venue::OrcaWhirlpool,
+        Venue::MeteoraDlmm,
+        Venue::MeteoraPools,
+        Venue::Pumpfun,
+        Venue::PumpSwap,
+        Venue::OpenbookV2,
+        Venue::Phoenix,
+        Venue::Fluxbeam,
+        Venue::LifinityAmmV2,
+        Venue::Moonshot,
+        Venue::RaydiumLaunchpad,
+        Venue::DriftV2,
+        Venue::Invariant,
+        Venue::SanctumInfinity,
+        Venue::SanctumRouter,
+        Venue::VirtualCurve,
+    ];
+
+    /// Platform name, as used in events and processor metrics.
+    pub fn platform(&self) -> &'static str {
+        match self {
+            Venue::RaydiumAmmV4 => "Raydium AMM V4",
+            Venue::RaydiumClmm => "Raydium CLMM",
+            Venue::RaydiumCpmm => "Raydium CPMM",
+            Venue::JupiterSwap => "Jupiter Swap",
+            Venue::OrcaWhirlpool => "Orca Whirlpool",
+            Venue::MeteoraDlmm => "Meteora DLMM",
+            Venue::MeteoraPools => "Meteora Pools",
+            Venue::Pumpfun => "Pumpfun",
+            Venue::PumpSwap => "Pump Swap",
+            Venue::OpenbookV2 => "OpenBook V2",
+            Venue::Phoenix => "Phoenix V1",
+            Venue::Fluxbeam => "Fluxbeam",
+            Venue::LifinityAmmV2 => "Lifinity AMM V2",
+            Venue::Moonshot => "Moonshot",
+            Venue::RaydiumLaunchpad => "Raydium Launchpad",
+            Venue::DriftV2 => "Drift V2",
+            Venue::Invariant => "Invariant",
+            Venue::SanctumInfinity => "Sanctum Infinity",
+            Venue::SanctumRouter => "Sanctum Router",
+            Venue::VirtualCurve => "Virtual Curve",
+        }
+    }
+
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            Venue::RaydiumAmmV4 => carbon_raydium_amm_v4_decoder::PROGRAM_ID,
+            Venue::RaydiumClmm => carbon_raydium_clmm_decoder::PROGRAM_ID,
+            Venue::RaydiumCpmm => carbon_raydium_cpmm_decoder::PROGRAM_ID,
+            Venue::JupiterSwap => carbon_jupiter_swap_decoder::PROGRAM_ID,
+            Venue::OrcaWhirlpool => carbon_orca_whirlpool_decoder::PROGRAM_ID,
+            Venue::MeteoraDlmm => carbon_meteora_dlmm_decoder::PROGRAM_ID,
+            Venue::MeteoraPools => carbon_meteora_pools_decoder::PROGRAM_ID,
+            Venue::Pumpfun => carbon_pumpfun_decoder::PROGRAM_ID,
+            Venue::PumpSwap => carbon_pump_swap_decoder::PROGRAM_ID,
+            Venue::OpenbookV2 => carbon_openbook_v2_decoder::PROGRAM_ID,
+            Venue::Phoenix => carbon_phoenix_v1_decoder::PROGRAM_ID,
+            Venue::Fluxbeam => carbon_fluxbeam_decoder::PROGRAM_ID,
+            Venue::LifinityAmmV2 => carbon_lifinity_amm_v2_decoder::PROGRAM_ID,
+            Venue::Moonshot => carbon_moonshot_decoder::PROGRAM_ID,
+            Venue::RaydiumLaunchpad => carbon_raydium_launchpad_decoder::PROGRAM_ID,
+            Venue::DriftV2 => carbon_drift_v2_decoder::PROGRAM_ID,
+            Venue::Invariant => crate::decoders::invariant::PROGRAM_ID,
+            Venue::SanctumInfinity => crate::decoders::sanctum::INFINITY_PROGRAM_ID,
+            Venue::SanctumRouter => crate::decoders::sanctum::ROUTER_PROGRAM_ID,
+            Venue::VirtualCurve => carbon_virtual_curve_decoder::PROGRAM_ID,
+        }
+    }
+
+    /// Every program decoded for this venue.
+    fn program_ids(&self) -> Vec<Pubkey> {
+        #[allow(unused_mut)]
+        let mut program_ids = vec![self.program_id()];
+        #[cfg(feature = "jupiter-legacy")]
+        if *self == Venue::JupiterSwap {
+            program_ids.extend(JupiterProgramVersion::LEGACY.iter().map(|version| version.program_id()));
+        }
+        program_ids
+    }
+}
+
+/// Where the pipeline reads transactions from.
+pub enum DexDatasource {
+    /// Full blocks over `blockSubscribe`.
+    WebSocket,
+    /// `blockSubscribe` notifications, with the blocks fetched over HTTP RPC.
+    Hybrid,
+    /// One block subscription per watched pool. New pools are added to the
+    /// watch list when `discovery` is enabled.
+    Watch {
+        watch_list: WatchList,
+        discovery: PoolDiscovery,
+    },
+    /// A single transaction fetched over HTTP RPC, the pipeline ends after it.
+    Signature(Signature),
+    /// The transactions of an address, oldest first, fetched over HTTP RPC.
+    /// The pipeline ends after the last one.
+    AddressBackfill(AddressBackfill),
+    /// Helius enhanced transaction webhooks received over HTTP. Which
+    /// programs are followed is configured on the webhook.
+    HeliusWebhook(HeliusWebhookConfig),
+    /// A capture recorded with [`DexPipelineBuilder::capture`], a file or
+    /// an `s3://` or `gs://` object, replayed at `speed` times the recorded
+    /// pace, or as fast as possible when `0`, limited to the slots from
+    /// `from_slot` to `to_slot` when set. The pipeline ends after the last
+    /// update.
+    Capture {
+        path: PathBuf,
+        speed: f64,
+        from_slot: Option<u64>,
+        to_slot: Option<u64>,
+    },
+    /// Several live datasources side by side for redundancy, each
+    /// transaction is processed once whichever delivers it first.
+    Redundant(Vec<DexDatasource>),
+}
+
+impl DexDatasource {
+    /// Watch list and pool discovery of the watch datasource, if one is
+    /// used.
+    fn watch(&self) -> Option<(&WatchList, &PoolDiscovery)> {
+        match self {
+            DexDatasource::Watch { watch_list, discovery } => Some((watch_list, discovery)),
+            DexDatasource::Redundant(datasources) => datasources.iter().find_map(DexDatasource::watch),
+            _ => None,
+        }
+    }
+}
+
+/// Builds the DEX events pipeline: which venues are decoded, where events
+/// are published, the enrichment stages shared by the processors and the
+/// datasource.
+///
+/// Enrichment stages that are not set are configured from the environment
+/// like the binary does. Deduplication, sharding and maintenance windows are
+/// off unless set, since their configuration can fail.
+///
+/// ```ignore
+/// let pipeline = DexPipelineBuilder::new(publisher)
+///     .rpc(rpc_ws_url, rpc_http_url)
+///     .datasource(DexDatasource::Hybrid)
+///     .venues([Venue::RaydiumAmmV4, Venue::Pumpfun])
+///     .build()?;
+/// pipeline.run().await?;
+/// ```
+pub struct DexPipelineBuilder {
+    publisher: UnifiedPublisher,
+    datasource: DexDatasource,
+    rpc_ws_url: String,
+    rpc_http_url: String,
+    block_fetch_urls: Vec<String>,
+    backup_ws_urls: Vec<String>,
+    commitment_strategy: CommitmentStrategy,
+    signature_dedup: SignatureDedup,
+    venues: Vec<Venue>,
+    metrics: Option<Arc<dyn Metrics>>,
+    price_index: Option<PriceIndex>,
+    execution_quality: Option<ExecutionQuality>,
+    whale_alerts: Option<WhaleAlerts>,
+    launch_funnel: Option<LaunchFunnel>,
+    symbols: Option<SymbolRegistry>,
+    participation: Option<ParticipationTracker>,
+    timeouts: Option<ProcessorTimeouts>,
+    dedup: Dedup,
+    checkpoint: Checkpoint,
+    shard: Shard,
+    maintenance: Option<MaintenanceWindows>,
+    capture: Option<(CaptureTarget, ChunkLimits)>,
+    pool_states: PoolStates,
+    usd_prices: UsdPrices,
+    pool_registry: PoolRegistry,
+    pool_reserves: PoolReserves,
+    curve_progress: BondingCurveProgress,
+    launchpads: LaunchpadConfigs,
+    tracked_mints: TrackedMints,
+    aggregator_policy: AggregatorPolicy,
+    group_transactions: bool,
+    failed_transactions: bool,
+}
+
+impl DexPipelineBuilder {
+    /// Every venue over the public mainnet RPC WebSocket, published to
+    /// `publisher`.
+    pub fn new(publisher: UnifiedPublisher) -> Self {
+        Self {
+            publisher,
+            datasource: DexDatasource::WebSocket,
+            rpc_ws_url: "wss://api.mainnet-beta.solana.com".to_string(),
+            rpc_http_url: "https://api.mainnet-beta.solana.com".to_string(),
+            block_fetch_urls: Vec::new(),
+            backup_ws_urls: Vec::new(),
+            commitment_strategy: CommitmentStrategy::default(),
+            signature_dedup: SignatureDedup::default(),
+            venues: Venue::ALL.to_vec(),
+            metrics: None,
+            price_index: None,
+            execution_quality: None,
+            whale_alerts: None,
+            launch_funnel: None,
+            symbols: None,
+            participation: None,
+            timeouts: None,
+            dedup: Dedup::default(),
+            checkpoint: Checkpoint::default(),
+            shard: Shard::default(),
+            maintenance: None,
+            capture: None,
+            pool_states: PoolStates::default(),
+            usd_prices: UsdPrices::default(),
+            pool_registry: PoolRegistry::default(),
+            pool_reserves: PoolReserves::default(),
+            curve_progress: BondingCurveProgress::default(),
+            launchpads: LaunchpadConfigs::default(),
+            tracked_mints: TrackedMints::default(),
+            aggregator_policy: AggregatorPolicy::default(),
+            group_transactions: false,
+            failed_transactions: false,
+        }
+    }
+
+    pub fn rpc(mut self, rpc_ws_url: String, rpc_http_url: String) -> Self {
+        self.rpc_ws_url = rpc_ws_url;
+        self.rpc_http_url = rpc_http_url;
+        self
+    }
+
+    /// HTTP endpoints the [`DexDatasource::Hybrid`] datasource spreads block
+    /// fetches over, only `rpc_http_url` when empty.
+    pub fn block_fetch_urls(mut self, urls: Vec<String>) -> Self {
+        self.block_fetch_urls = urls;
+        self
+    }
+
+    /// WebSocket endpoints the [`DexDatasource::Hybrid`] datasource fails
+    /// over to when `rpc_ws_url` keeps failing.
+    pub fn backup_ws_urls(mut self, urls: Vec<String>) -> Self {
+        self.backup_ws_urls = urls;
+        self
+    }
+
+    /// Commitment the [`DexDatasource::Hybrid`] datasource notifies and
+    /// fetches blocks at, confirmed by default.
+    pub fn commitment_strategy(mut self, commitment_strategy: CommitmentStrategy) -> Self {
+        self.commitment_strategy = commitment_strategy;
+        self
+    }
+
+    /// How long [`DexDatasource::Redundant`] remembers the signatures of
+    /// the transactions it forwarded.
+    pub fn signature_dedup(mut self, signature_dedup: SignatureDedup) -> Self {
+        self.signature_dedup = signature_dedup;
+        self
+    }
+
+    pub fn datasource(mut self, datasource: DexDatasource) -> Self {
+        self.datasource = datasource;
+        self
+    }
+
+    pub fn venues(mut self, venues: impl IntoIterator<Item = Venue>) -> Self {
+        self.venues = venues.into_iter().collect();
+        self
+    }
+
+    /// Defaults to [`LogMetrics`].
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Prices of direct venue swaps, shared with execution quality and whale
+    /// alerts when those are built from the environment.
+    pub fn price_index(mut self, price_index: PriceIndex) -> Self {
+        self.price_index = Some(price_index);
+        self
+    }
+
+    pub fn execution_quality(mut self, execution_quality: ExecutionQuality) -> Self {
+        self.execution_quality = Some(execution_quality);
+        self
+    }
+
+    pub fn whale_alerts(mut self, whale_alerts: WhaleAlerts) -> Self {
+        self.whale_alerts = Some(whale_alerts);
+        self
+    }
+
+    pub fn launch_funnel(mut self, launch_funnel: LaunchFunnel) -> Self {
+        self.launch_funnel = Some(launch_funnel);
+        self
+    }
+
+    pub fn symbols(mut self, symbols: SymbolRegistry) -> Self {
+        self.symbols = Some(symbols);
+        self
+    }
+
+    pub fn participation(mut self, participation: ParticipationTracker) -> Self {
+        self.participation = Some(participation);
+        self
+    }
+
+    pub fn timeouts(mut self, timeouts: ProcessorTimeouts) -> Self {
+        self.timeouts = Some(timeouts);
+        self
+    }
+
+    pub fn dedup(mut self, dedup: Dedup) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Resumes from the last saved slot after a restart. Only used by the
+    /// [`DexDatasource::Hybrid`] datasource.
+    pub fn checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    /// Only the venues and pools owned by `shard` are processed.
+    pub fn shard(mut self, shard: Shard) -> Self {
+        self.shard = shard;
+        self
+    }
+
+    /// Pauses the datasource during the windows. Has no effect on a
+    /// [`DexDatasource::Signature`] or capture replay, or an address
+    /// backfill.
+    pub fn maintenance(mut self, maintenance: MaintenanceWindows) -> Self {
+        self.maintenance = Some(maintenance);
+        self
+    }
+
+    /// Records every update of the datasource to `target`, in chunks closed
+    /// at `limits`, to replay it later with [`DexDatasource::Capture`].
+    pub fn capture(mut self, target: CaptureTarget, limits: ChunkLimits) -> Self {
+        self.capture = Some((target, limits));
+        self
+    }
+
+    /// Follows pool accounts alongside the datasource and publishes their
+    /// reserves and prices as `pool_state` events.
+    pub fn pool_states(mut self, pool_states: PoolStates) -> Self {
+        self.pool_states = pool_states;
+        self
+    }
+
+    /// Follows oracle feeds alongside the datasource and adds the USD value
+    /// of swaps to their details.
+    pub fn usd_prices(mut self, usd_prices: UsdPrices) -> Self {
+        self.usd_prices = usd_prices;
+        self
+    }
+
+    /// Tags the events naming a pool with its trading pair.
+    pub fn pool_registry(mut self, pool_registry: PoolRegistry) -> Self {
+        self.pool_registry = pool_registry;
+        self
+    }
+
+    /// Tracks the vault reserves of the pools events are published for and
+    /// publishes their TVL and depth periodically. Needs the pool registry.
+    pub fn pool_reserves(mut self, pool_reserves: PoolReserves) -> Self {
+        self.pool_reserves = pool_reserves;
+        self
+    }
+
+    /// Completion thresholds of Pumpfun bonding curves announced as
+    /// `graduation` events before the curve migrates.
+    pub fn curve_progress(mut self, curve_progress: BondingCurveProgress) -> Self {
+        self.curve_progress = curve_progress;
+        self
+    }
+
+    /// Dynamic Bonding Curve configs of the launchpads, such as Believe,
+    /// whose token launches are tagged with the launchpad name.
+    pub fn launchpads(mut self, launchpads: LaunchpadConfigs) -> Self {
+        self.launchpads = launchpads;
+        self
+    }
+
+    /// Mints whose transfers, mints and burns are published as
+    /// `token_transfer` events. Transactions mentioning them are subscribed
+    /// to along with the venue programs.
+    pub fn tracked_mints(mut self, tracked_mints: TrackedMints) -> Self {
+        self.tracked_mints = tracked_mints;
+        self
+    }
+
+    /// Whether venue instructions routed through an aggregator are tagged
+    /// or skipped, so that their volume is not counted twice.
+    pub fn aggregator_policy(mut self, aggregator_policy: AggregatorPolicy) -> Self {
+        self.aggregator_policy = aggregator_policy;
+        self
+    }
+
+    /// Also publishes the decoded instructions of each transaction together
+    /// as one `transaction` event. Off by default.
+    pub fn group_transactions(mut self, group_transactions: bool) -> Self {
+        self.group_transactions = group_transactions;
+        self
+    }
+
+    /// Also processes failed transactions, whose events are flagged with
+    /// `success: false`. Every datasource passes them through except
+    /// [`DexDatasource::HeliusWebhook`], whose payload has no RPC error to
+    /// flag them with. Off by default.
+    pub fn failed_transactions(mut self, failed_transactions: bool) -> Self {
+        self.failed_transactions = failed_transactions;
+        self
+    }
+
+    /// Creates the datasource and one processor per venue, and starts
+    /// publishing participation events. Must be called within a Tokio
+    /// runtime, each processor runs on its own task.
+    pub fn build(mut self) -> CarbonResult<Pipeline> {
+        let program_ids: Vec<String> = self
+            .shard
+            .assign_programs(
+                self.venues
+                    .iter()
+                    .flat_map(Venue::program_ids)
+                    .chain(self.tracked_mints.mints().copied())
+                    .collect(),
+            )
+            .iter()
+            .map(|program_id| program_id.to_string())
+            .collect();
+        if program_ids.is_empty() {
+            return Err(Error::Custom(format!(
+                "Shard {} owns none of the selected DEX programs, use a lower SHARD_COUNT",
+                self.shard.describe()
+            )));
+        }
+
+        let price_index = self.price_index.clone().unwrap_or_else(PriceIndex::from_env);
+        let stages = Stages {
+            publisher: self.publisher.clone(),
+            execution_quality: self
+                .execution_quality
+                .clone()
+                .unwrap_or_else(|| ExecutionQuality::from_env(price_index.clone())),
+            whale_alerts: self
+                .whale_alerts
+                .clone()
+                .unwrap_or_else(|| WhaleAlerts::from_env(self.publisher.clone(), price_index.clone())),
+            launch_funnel: self.launch_funnel.clone().unwrap_or_else(LaunchFunnel::from_env),
+            symbols: self.symbols.clone().unwrap_or_else(SymbolRegistry::from_env),
+            participation: self.participation.clone().unwrap_or_else(ParticipationTracker::from_env),
+            // Every pool is processed anyway without a watch datasource
+            discovery: self
+                .datasource
+                .watch()
+                .map(|(_, discovery)| discovery.clone())
+                .unwrap_or_default(),
+            timeouts: self.timeouts.unwrap_or_else(ProcessorTimeouts::from_env),
+            usd_prices: self.usd_prices.clone(),
+            pool_registry: self.pool_registry.clone(),
+            pool_reserves: self.pool_reserves.clone(),
+            curve_progress: self.curve_progress.clone(),
+            launchpads: self.launchpads.clone(),
+            price_index,
+        };
+        stages.participation.spawn_publisher(self.publisher.clone());
+        if stages.pool_registry.is_enabled() {
+            stages.pool_registry.spawn_saver();
+        }
+        stages.pool_reserves.spawn_publisher(self.publisher.clone());
+
+        let builder = self.datasource_builder(&program_ids);
+        let builder = self
+            .venues
+            .iter()
+            .fold(builder, |builder, venue| self.register(builder, *venue, &stages));
+        let builder = if self.tracked_mints.is_enabled() {
+            builder.instruction(
+                TokenActivityDecoder,
+                stages.timeouts.wrap(
+                    "Token",
+                    self.dedup.wrap(TokenTransferProcessor::new(
+                        self.publisher.clone(),
+                        self.tracked_mints.clone(),
+                        self.shard.clone(),
+                    )),
+                ),
+            )
+        } else {
+            builder
+        };
+        let builder = if self.group_transactions {
+            builder.transaction::<GroupedInstruction, ()>(
+                TransactionProcessor::new(self.publisher.clone(), self.shard.clone()),
+                None,
+            )
+        } else {
+            builder
+        };
+        let builder = if self.pool_states.is_enabled() {
+            builder
+                .account(
+                    OrcaWhirlpoolDecoder,
+                    WhirlpoolStateProcessor::new(self.publisher.clone(), self.pool_states.clone()),
+                )
+                .account(
+                    RaydiumClmmDecoder,
+                    ClmmStateProcessor::new(self.publisher.clone(), self.pool_states.clone()),
+                )
+                .account(
+                    RaydiumAmmV4Decoder,
+                    AmmInfoStateProcessor::new(self.publisher.clone(), self.pool_states.clone()),
+                )
+                .account(
+                    TokenProgramDecoder,
+                    AmmVaultProcessor::new(
+                        self.publisher.clone(),
+                        self.pool_states.clone(),
+                        self.pool_reserves.clone(),
+                    ),
+                )
+        } else {
+            builder
+        };
+        let builder = if self.usd_prices.is_enabled() {
+            builder.account(OracleDecoder, OraclePriceProcessor::new(self.usd_prices.clone()))
+        } else {
+            builder
+        };
+
+        builder
+            .metrics(self.metrics.clone().unwrap_or_else(|| Arc::new(LogMetrics::new())))
+            .metrics_flush_interval(5)
+            .shutdown_strategy(ShutdownStrategy::Immediate)
+            .build()
+    }
+
+    /// Pipeline builder reading from the configured datasource, with one
+    /// block subscription per program in `program_ids`, and from the pool
+    /// and oracle accounts when pool states or USD prices are followed.
+    fn datasource_builder(&self, program_ids: &[String]) -> PipelineBuilder {
+        let block_filters = program_ids
+            .iter()
+            .map(|program_id| RpcBlockSubscribeFilter::MentionsAccountOrProgram(program_id.clone()))
+            .collect::<Vec<_>>();
+
+        let mut datasource = self.build_datasource(&self.datasource, &block_filters);
+        let subscriptions = self
+            .pool_states
+            .subscriptions()
+            .into_iter()
+            .chain(self.usd_prices.subscriptions())
+            .collect::<Vec<_>>();
+        if !subscriptions.is_empty() {
+            let pool_accounts =
+                PoolAccountsDatasource::new(self.rpc_ws_url.clone(), subscriptions, CommitmentConfig::confirmed());
+            datasource = Box::new(FanInDatasource::new(vec![datasource, Box::new(pool_accounts)]));
+        }
+        let datasource = DynDatasource(datasource);
+        match &self.capture {
+            Some((target, limits)) => Pipeline::builder()
+                .datasource(TeeDatasource::new(datasource, target.clone()).with_chunk_limits(*limits)),
+            None => Pipeline::builder().datasource(datasource),
+        }
+    }
+
+    fn build_datasource(
+        &self,
+        datasource: &DexDatasource,
+        block_filters: &[RpcBlockSubscribeFilter],
+    ) -> Box<dyn Datasource> {
+        match datasource {
+            DexDatasource::WebSocket => {
+                let block_subscribe_config = RpcBlockSubscribeConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    transaction_details: Some(TransactionDetails::Full),
+                    show_rewards: Some(false),
+                    max_supported_transaction_version: Some(0),
+                };
+                let datasources = block_filters
+                    .iter()
+                    .map(|block_filter| {
+                        let filters = Filters::new(block_filter.clone(), Some(block_subscribe_config.clone()))
+                            .with_failed_transactions(self.failed_transactions);
+                        Box::new(RpcBlockSubscribe::new(self.rpc_ws_url.clone(), filters)) as Box<dyn Datasource>
+                    })
+                    .collect();
+                self.maintained(FanInDatasource::new(datasources), self.failed_transactions)
+            }
+            DexDatasource::Hybrid => {
+                let filters = HybridFilters::new(block_filters.to_vec(), None)
+                    .with_commitment_strategy(self.commitment_strategy)
+                    .with_failed_transactions(self.failed_transactions);
+                self.maintained(
+                    HybridBlockDatasource::new(self.rpc_ws_url.clone(), self.rpc_http_url.clone(), filters)
+                        .with_retractions(self.publisher.clone())
+                        .with_checkpoint(self.checkpoint.clone())
+                        .with_fetch_urls(self.block_fetch_urls.clone())
+                        .with_backup_ws_urls(self.backup_ws_urls.clone()),
+                    self.failed_transactions,
+                )
+            }
+            DexDatasource::Watch { watch_list, discovery } => self.maintained(
+                WatchedPoolsDatasource::new(self.rpc_ws_url.clone(), watch_list.clone(), CommitmentConfig::confirmed())
+                    .with_programs(
+                        discovery
+                            .programs()
+                            .into_iter()
+                            .filter(|program_id| self.shard.owns_program(program_id))
+                            .collect(),
+                    )
+                    .with_failed_transactions(self.failed_transactions),
+                self.failed_transactions,
+            ),
+            DexDatasource::Signature(signature) => {
+                Box::new(
+                    SignatureDatasource::new(self.rpc_http_url.clone(), *signature)
+                        .with_failed_transactions(self.failed_transactions),
+                )
+            }
+            DexDatasource::AddressBackfill(backfill) => {
+                Box::new(
+                    AddressBackfillDatasource::new(self.rpc_http_url.clone(), backfill.clone())
+                        .with_failed_transactions(self.failed_transactions),
+                )
+            }
+            DexDatasource::HeliusWebhook(config) => Box::new(HeliusWebhookDatasource::new(config.clone())),
+            DexDatasource::Capture { path, speed, from_slot, to_slot } => Box::new(
+                ReplayDatasource::new(path.clone())
+                    .with_speed(*speed)
+                    .with_slots(*from_slot, *to_slot),
+            ),
+            DexDatasource::Redundant(datasources) => Box::new(
+                FanInDatasource::new(
+                    datasources
+                        .iter()
+                        .map(|datasource| self.build_datasource(datasource, block_filters))
+                        .collect(),
+                )
+                .with_signature_dedup(self.signature_dedup),
+            ),
+        }
+    }
+
+    /// Pauses `datasource` during the maintenance windows, if any. The
+    /// missed blocks are backfilled with their failed transactions when
+    /// `include_failed` is set.
+    fn maintained(&self, datasource: impl Datasource + 'static, include_failed: bool) -> Box<dyn Datasource> {
+        match &self.maintenance {
+            Some(maintenance) => Box::new(
+                maintenance
+                    .wrap(datasource, self.rpc_http_url.clone())
+                    .with_failed_transactions(include_failed),
+            ),
+            None => Box::new(datasource),
+        }
+    }
+
+    fn register(&self, builder: PipelineBuilder, venue: Venue, stages: &Stages) -> PipelineBuilder {
+        let publisher = stages.publisher.clone();
+        match venue {
+            Venue::RaydiumAmmV4 => self.pipe(
+                builder,
+                venue,
+                RaydiumAmmV4Decoder,
+                RaydiumAmmV4Processor::new(
+                    publisher,
+                    stages.price_index.clone(),
+                    stages.whale_alerts.clone(),
+                    stages.discovery.clone(),
+                    stages.participation.clone(),
+                    stages.usd_prices.clone(),
+                    stages.pool_registry.clone(),
+                    stages.pool_reserves.clone(),
+                ),
+                stages,
+            ),
+            Venue::RaydiumClmm => self.pipe(
+                builder,
+                venue,
+                RaydiumClmmDecoder,
+                RaydiumClmmProcessor::new(
+                    publisher,
+                    stages.usd_prices.clone(),
+                    stages.pool_registry.clone(),
+                    stages.pool_reserves.clone(),
+                ),
+                stages,
+            ),
+            Venue::RaydiumCpmm => self.pipe(
+                builder,
+                venue,
+                RaydiumCpmmDecoder,
+                RaydiumCpmmProcessor::new(
+                    publisher,
+                    stages.price_index.clone(),
+                    stages.whale_alerts.clone(),
+                    stages.participation.clone(),
+                    stages.usd_prices.clone(),
+                    stages.pool_registry.clone(),
+                    stages.pool_reserves.clone(),
+                ),
+                stages,
+            ),
+            Venue::JupiterSwap => {
+                let builder = self.pipe(
+                    builder,
+                    venue,
+                    JupiterSwapDecoder,
+                    JupiterSwapProcessor::new(
+                        publisher,
+                        stages.execution_quality.clone(),
+                        stages.whale_alerts.clone(),
+                        stages.usd_prices.clone(),
+                    ),
+                    stages,
+                );
+                #[cfg(feature = "jupiter-legacy")]
+                let builder = self.pipe(
+                    builder,
+                    venue,
+                    JupiterLegacyDecoder,
+                    JupiterLegacyProcessor::new(stages.publisher.clone()),
+                    stages,
+                );
+                builder
+            }
+            Venue::OrcaWhirlpool => self.pipe(
+                builder,
+                venue,
+                OrcaWhirlpoolDecoder,
+                OrcaWhirlpoolProcessor::new(
+                    publisher,
+                    stages.price_index.clone(),
+                    stages.whale_alerts.clone(),
+                    stages.discovery.clone(),
+                    stages.participation.clone(),
+                    stages.usd_prices.clone(),
+                    stages.pool_registry.clone(),
+                    stages.pool_reserves.clone(),
+                ),
+                stages,
+            ),
+            Venue::MeteoraDlmm => self.pipe(
+                builder,
+                venue,
+                MeteoraDlmmDecoder,
+                MeteoraDlmmProcessor::new(
+                    publisher,
+                    stages.discovery.clone(),
+                    stages.participation.clone(),
+                    stages.usd_prices.clone(),
+                    stages.pool_registry.clone(),
+                    stages.pool_reserves.clone(),
+                ),
+                stages,
+            ),
+            Venue::MeteoraPools => self.pipe(
+                builder,
+                venue,
+                MeteoraPoolsDecoder,
+                MeteoraPoolsProcessor::new(
+                    publisher,
+                    stages.price_index.clone(),
+                    stages.whale_alerts.clone(),
+                    stages.discovery.clone(),
+                    stages.participation.clone(),
+                    stages.usd_prices.clone(),
+                ),
+                stages,
+            ),
+            Venue::Pumpfun => self.pipe(
+                builder,
+                venue,
+                PumpfunDecoder,
+                PumpfunProcessor::new(
+                    publisher,
+                    stages.whale_alerts.clone(),
+                    stages.launch_funnel.clone(),
+                    stages.symbols.clone(),
+                    stages.usd_prices.clone(),
+                    stages.curve_progress.clone(),
+                ),
+                stages,
+            ),
+            Venue::PumpSwap => self.pipe(
+                builder,
+                venue,
+                PumpSwapDecoder,
+                PumpSwapProcessor::new(
+                    publisher,
+                    stages.whale_alerts.clone(),
+                    stages.discovery.clone(),
+                    stages.participation.clone(),
+                    stages.usd_prices.clone(),
+                    stages.pool_registry.clone(),
+                    stages.pool_reserves.clone(),
+                ),
+                stages,
+            ),
+            Venue::OpenbookV2 => {
+                self.pipe(builder, venue, OpenbookV2Decoder, OpenbookV2Processor::new(publisher), stages)
+            }
+            Venue::Phoenix => self.pipe(builder, venue, PhoenixDecoder, PhoenixProcessor::new(publisher), stages),
+            Venue::Fluxbeam => self.pipe(
+                builder,
+                venue,
+                FluxbeamDecoder,
+                FluxbeamProcessor::new(
+                    publisher,
+                    stages.price_index.clone(),
+                    stages.whale_alerts.clone(),
+                    stages.participation.clone(),
+                    stages.usd_prices.clone(),
+                    stages.pool_registry.clone(),
+                    stages.pool_reserves.clone(),
+                ),
+                stages,
+            ),
+            Venue::LifinityAmmV2 => {
+                self.pipe(builder, venue, LifinityAmmV2Decoder, LifinityAmmV2Processor::new(publisher), stages)
+            }
+            Venue::Moonshot => self.pipe(
+                builder,
+                venue,
+                MoonshotDecoder,
+                MoonshotProcessor::new(publisher, stages.launch_funnel.clone(), stages.symbols.clone()),
+                stages,
+            ),
+            Venue::RaydiumLaunchpad => self.pipe(
+                builder,
+                venue,
+                RaydiumLaunchpadDecoder,
+                RaydiumLaunchpadProcessor::new(
+                    publisher,
+                    stages.whale_alerts.clone(),
+                    stages.launch_funnel.clone(),
+                    stages.symbols.clone(),
+                    stages.usd_prices.clone(),
+                ),
+                stages,
+            ),
+            Venue::DriftV2 => self.pipe(builder, venue, DriftDecoder, DriftProcessor::new(publisher), stages),
+            Venue::Invariant => self.pipe(
+                builder,
+                venue,
+                InvariantDecoder,
+                InvariantProcessor::new(
+                    publisher,
+                    stages.price_index.clone(),
+                    stages.whale_alerts.clone(),
+                    stages.discovery.clone(),
+                    stages.participation.clone(),
+                    stages.usd_prices.clone(),
+                ),
+                stages,
+            ),
+            Venue::SanctumInfinity => self.pipe(
+                builder,
+                venue,
+                SanctumInfinityDecoder,
+                SanctumInfinityProcessor::new(
+                    publisher,
+                    stages.price_index.clone(),
+                    stages.whale_alerts.clone(),
+                    stages.participation.clone(),
+                    stages.usd_prices.clone(),
+                ),
+                stages,
+            ),
+            Venue::SanctumRouter => self.pipe(
+                builder,
+                venue,
+                SanctumRouterDecoder,
+                SanctumRouterProcessor::new(
+                    publisher,
+                    stages.price_index.clone(),
+                    stages.whale_alerts.clone(),
+                    stages.usd_prices.clone(),
+                ),
+                stages,
+            ),
+            Venue::VirtualCurve => self.pipe(
+                builder,
+                venue,
+                VirtualCurveDecoder,
+                VirtualCurveProcessor::new(
+                    publisher,
+                    stages.whale_alerts.clone(),
+                    stages.launch_funnel.clone(),
+                    stages.symbols.clone(),
+                    stages.usd_prices.clone(),
+                    stages.launchpads.clone(),
+                ),
+                stages,
+            ),
+        }
+    }
+
+    /// Adds the pipe of one decoder, its processor wrapped in
+    /// deduplication, sharding and the processor timeout.
+    fn pipe<T, P>(
+        &self,
+        builder: PipelineBuilder,
+        venue: Venue,
+        decoder: impl for<'a> InstructionDecoder<'a, InstructionType = T> + Send + Sync + 'static,
+        processor: P,
+        stages: &Stages,
+    ) -> PipelineBuilder
+    where
+        T: Send + Sync + 'static,
+        P: Processor<InputType = InstructionInput<T>> + Send + Sync + 'static,
+    {
+        let processor = stages.timeouts.wrap(venue.platform(), self.shard.wrap(self.dedup.wrap(processor)));
+        builder.instruction_with_filters(decoder, processor, self.filters(venue, stages))
+    }
+
+    /// Watch list filters in watch mode, letting the pool creations of
+    /// discovered venues through. Other datasources process every pool.
+    /// Venues also skip the instructions of aggregator routes when those
+    /// are suppressed.
+    fn filters(&self, venue: Venue, stages: &Stages) -> Vec<Box<dyn Filter + Send + Sync + 'static>> {
+        let mut filters = match self.datasource.watch() {
+            Some((watch_list, _)) => stages.discovery.filters(watch_list, &venue.program_id()),
+            None => Vec::new(),
+        };
+        if venue != Venue::JupiterSwap {
+            filters.extend(self.aggregator_policy.filter());
+        }
+        filters
+    }
+}
+
+/// Enrichment stages resolved at build time, shared by every processor.
+struct Stages {
+    publisher: UnifiedPublisher,
+    price_index: PriceIndex,
+    execution_quality: ExecutionQuality,
+    whale_alerts: WhaleAlerts,
+    launch_funnel: LaunchFunnel,
+    symbols: SymbolRegistry,
+    participation: ParticipationTracker,
+    discovery: PoolDiscovery,
+    timeouts: ProcessorTimeouts,
+    usd_prices: UsdPrices,
+    pool_registry: PoolRegistry,
+    pool_reserves: PoolReserves,
+    curve_progress: BondingCurveProgress,
+    launchpads: LaunchpadConfigs,
+}
+
+/// A datasource picked at runtime, for [`PipelineBuilder::datasource`].
+struct DynDatasource(Box<dyn Datasource>);
+
+#[async_trait]
+impl Datasource for DynDatasource {
+    async fn consume(
+        &self,
+        id: DatasourceId,
+        sender: Sender<(Update, DatasourceId)>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        self.0.consume(id, sender, cancellation_token, metrics).await
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        self.0.update_types()
+    }
+}
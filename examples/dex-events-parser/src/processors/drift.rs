@@ -0,0 +1,124 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        deserialize::CarbonDeserialize,
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    carbon_drift_v2_decoder::{
+        instructions::{order_action_record_event::OrderActionRecordEvent, DriftInstruction},
+        types::{MarketType, OrderAction, PositionDirection},
+        PROGRAM_ID,
+    },
+    serde_json::json,
+    std::sync::Arc,
+};
+
+use crate::{
+    analytics::{jito::jito_tip_annotation, program_logs::program_data},
+    publishers::UnifiedPublisher,
+};
+
+use super::{CommonProcessor, EventTime};
+
+/// Prefix of Anchor's self-CPI events, which the decoder expects in front of
+/// the event discriminator. Drift logs its events instead.
+const EVENT_IX_TAG: [u8; 8] = [0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d];
+/// Perp base amounts have 9 decimals and quote amounts 6 (USDC).
+const PERP_BASE_PRECISION: f64 = 1e9;
+const QUOTE_PRECISION: f64 = 1e6;
+
+// Drift V2 Processor
+pub struct DriftProcessor {
+    publisher: UnifiedPublisher,
+}
+
+impl DriftProcessor {
+    pub fn new(publisher: UnifiedPublisher) -> Self {
+        Self { publisher }
+    }
+}
+
+#[async_trait]
+impl Processor for DriftProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<DriftInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, _, _): Self::InputType,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        // Fills are only in the `OrderActionRecord` logs of the instruction.
+        // Drift instructions invoked by another program share the logs of
+        // their top-level instruction and are skipped.
+        if metadata.stack_height != 1 {
+            return Ok(());
+        }
+        let transaction = &metadata.transaction_metadata;
+        let signature = transaction.signature.to_string();
+        let platform = "Drift V2".to_string();
+        let timestamp = EventTime::now(&metadata);
+        let debug = format!("{:?}", instruction.data);
+        let instruction_name = debug.split('(').next().unwrap_or_default();
+
+        let fills = program_data(transaction, &PROGRAM_ID, metadata.index as usize)
+            .into_iter()
+            .filter_map(|data| OrderActionRecordEvent::deserialize(&[EVENT_IX_TAG.as_slice(), &data].concat()))
+            .filter(|record| record.action == OrderAction::Fill);
+        for record in fills {
+            let perp = record.market_type == MarketType::Perp;
+            // Spot base decimals depend on the market's mint
+            let price = record
+                .base_asset_amount_filled
+                .zip(record.quote_asset_amount_filled)
+                .filter(|(base, _)| perp && *base > 0)
+                .map(|(base, quote)| (quote as f64 / QUOTE_PRECISION) / (base as f64 / PERP_BASE_PRECISION));
+            let details = json!({
+                "type": if perp { "PerpFill" } else { "SpotFill" },
+                "instruction": instruction_name,
+                "market_index": record.market_index,
+                "market_type": if perp { "perp" } else { "spot" },
+                "fill_record_id": record.fill_record_id,
+                "action_explanation": format!("{:?}", record.action_explanation),
+                "taker": record.taker.map(|taker| taker.to_string()),
+                "taker_order_id": record.taker_order_id,
+                "taker_direction": record.taker_order_direction.as_ref().map(direction),
+                "maker": record.maker.map(|maker| maker.to_string()),
+                "maker_order_id": record.maker_order_id,
+                "maker_direction": record.maker_order_direction.as_ref().map(direction),
+                "base_asset_amount_filled": record.base_asset_amount_filled,
+                "quote_asset_amount_filled": record.quote_asset_amount_filled,
+                "price": price,
+                "oracle_price": record.oracle_price as f64 / QUOTE_PRECISION,
+                "taker_fee": record.taker_fee,
+                "maker_fee": record.maker_fee,
+                "filler": record.filler.map(|filler| filler.to_string()),
+                "filler_reward": record.filler_reward,
+                "ts": record.ts,
+                "jito_tip": jito_tip_annotation(transaction)
+            });
+            self.common_process_event("fill", platform.clone(), signature.clone(), timestamp.clone(), details)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn direction(direction: &PositionDirection) -> &'static str {
+    match direction {
+        PositionDirection::Long => "long",
+        PositionDirection::Short => "short",
+    }
+}
+
+impl CommonProcessor for DriftProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
@@ -0,0 +1,302 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        deserialize::ArrangeAccounts,
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        processor::Processor,
+        transaction::TransactionMetadata,
+    },
+    carbon_fluxbeam_decoder::instructions::{
+        deposit_all_token_types::DepositAllTokenTypes,
+        deposit_single_token_type_exact_amount_in::DepositSingleTokenTypeExactAmountIn, initialize::Initialize,
+        swap::Swap, withdraw_all_token_types::WithdrawAllTokenTypes,
+        withdraw_single_token_type_exact_amount_out::WithdrawSingleTokenTypeExactAmountOut, FluxbeamInstruction,
+    },
+    serde_json::json,
+    solana_pubkey::Pubkey,
+    std::sync::Arc,
+};
+
+use crate::{
+    alerts::whale::WhaleAlerts,
+    analytics::{
+        balances::{amount_sent, token_account_mint},
+        decimals::UiAmounts,
+        jito::jito_tip_annotation,
+        new_pair::NewPairEvent,
+        participation::ParticipationTracker,
+        pool_registry::PoolRegistry,
+        pool_reserves::PoolReserves,
+        price_index::PriceIndex,
+        side::TradeSide,
+        slippage::{observed_slippage, QuoteSource, SwapMode},
+        swap_event::SwapEvent,
+        trader::TraderAccount,
+        transfer_fee::epoch,
+        transfers::SwapTransfers,
+        usd_prices::UsdPrices,
+    },
+    publishers::UnifiedPublisher,
+};
+
+use super::{
+    layout::{check_layout, AccountRole},
+    CommonProcessor, EventTime,
+};
+
+/// Fluxbeam is an SPL token-swap fork whose pools commonly hold Token-2022
+/// mints, so the amount leaving one account can differ from the amount
+/// arriving in the other when a mint charges a transfer fee.
+pub struct FluxbeamProcessor {
+    publisher: UnifiedPublisher,
+    price_index: PriceIndex,
+    whale_alerts: WhaleAlerts,
+    participation: ParticipationTracker,
+    usd_prices: UsdPrices,
+    pool_registry: PoolRegistry,
+    pool_reserves: PoolReserves,
+}
+
+impl FluxbeamProcessor {
+    pub fn new(
+        publisher: UnifiedPublisher,
+        price_index: PriceIndex,
+        whale_alerts: WhaleAlerts,
+        participation: ParticipationTracker,
+        usd_prices: UsdPrices,
+        pool_registry: PoolRegistry,
+        pool_reserves: PoolReserves,
+    ) -> Self {
+        Self { publisher, price_index, whale_alerts, participation, usd_prices, pool_registry, pool_reserves }
+    }
+
+    /// Transfer fee `mint` withholds from a transfer of `amount`, going by
+    /// the `TransferFeeConfig` of the mint in the transaction's epoch.
+    async fn transfer_fee(&self, transaction: &TransactionMetadata, mint: &Pubkey, amount: Option<u64>) -> Option<u64> {
+        let config = self.pool_registry.transfer_fee(mint, epoch(transaction.slot)).await?;
+        Some(config.fee(epoch(transaction.slot), amount?)).filter(|fee| *fee > 0)
+    }
+}
+
+#[async_trait]
+impl Processor for FluxbeamProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<FluxbeamInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let transaction = &metadata.transaction_metadata;
+        let signature = transaction.signature.to_string();
+        let platform = "Fluxbeam".to_string();
+        let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction.data, &instruction.accounts));
+        let routed = timestamp.via_aggregator.is_some();
+
+        let mut fill = None;
+        let mut transfers = None;
+        let mut ui_amounts = UiAmounts::new(transaction);
+        let mut decode_warnings = Vec::new();
+        let mut trade = None;
+        let mut new_pair = None;
+        let (event_type, mut details) = match instruction.data {
+            FluxbeamInstruction::Swap(swap) => {
+                let mut details = json!({
+                    "type": "Swap",
+                    "amount_in": swap.amount_in,
+                    "minimum_amount_out": swap.minimum_amount_out
+                });
+                if let Some(accounts) = check_layout::<Swap>(
+                    "Swap",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    15..=16,
+                    |a| vec![
+                        ("user_transfer_authority", a.user_transfer_authority, AccountRole::Signer),
+                        ("source", a.source, AccountRole::Writable),
+                        ("destination", a.destination, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                ) {
+                    trade = Some((accounts.swap, accounts.user_transfer_authority));
+                    fill = self.price_index.observe(&platform, transaction, &accounts.source, &accounts.destination);
+                    transfers = SwapTransfers::find(transaction, &nested, &accounts.source, &accounts.destination);
+                    details["pool"] = json!(accounts.swap.to_string());
+                    details["input_mint"] = json!(accounts.source_mint.to_string());
+                    details["output_mint"] = json!(accounts.destination_mint.to_string());
+                    details["slippage"] = observed_slippage(
+                        transaction,
+                        SwapMode::ExactIn,
+                        QuoteSource::Limit,
+                        swap.minimum_amount_out,
+                        &accounts.destination,
+                    );
+                    ui_amounts.mint("amount_in", accounts.source_mint);
+                    ui_amounts.mint("minimum_amount_out", accounts.destination_mint);
+
+                    if let Some(fee) = self.transfer_fee(transaction, &accounts.source_mint, Some(swap.amount_in)).await {
+                        details["input_transfer_fee"] = json!(fee);
+                        ui_amounts.mint("input_transfer_fee", accounts.source_mint);
+                    }
+                    // Charged on what leaves the vault
+                    let amount_out = amount_sent(transaction, &accounts.swap_destination);
+                    if let Some(fee) = self.transfer_fee(transaction, &accounts.destination_mint, amount_out).await {
+                        details["output_transfer_fee"] = json!(fee);
+                        ui_amounts.mint("output_transfer_fee", accounts.destination_mint);
+                    }
+                }
+                ("swap", details)
+            }
+            FluxbeamInstruction::DepositAllTokenTypes(deposit) => {
+                let mut details = json!({
+                    "type": "add",
+                    "action": "DepositAllTokenTypes",
+                    "pool_token_amount": deposit.pool_token_amount,
+                    "maximum_token_a_amount": deposit.maximum_token_a_amount,
+                    "maximum_token_b_amount": deposit.maximum_token_b_amount
+                });
+                if let Some(accounts) = DepositAllTokenTypes::arrange_accounts(&instruction.accounts) {
+                    details["pool"] = json!(accounts.swap.to_string());
+                    ui_amounts.token_account("maximum_token_a_amount", &accounts.swap_token_a);
+                    ui_amounts.token_account("maximum_token_b_amount", &accounts.swap_token_b);
+                }
+                ("liquidity", details)
+            }
+            FluxbeamInstruction::WithdrawAllTokenTypes(withdraw) => {
+                let mut details = json!({
+                    "type": "remove",
+                    "action": "WithdrawAllTokenTypes",
+                    "pool_token_amount": withdraw.pool_token_amount,
+                    "minimum_token_a_amount": withdraw.minimum_token_a_amount,
+                    "minimum_token_b_amount": withdraw.minimum_token_b_amount
+                });
+                if let Some(accounts) = WithdrawAllTokenTypes::arrange_accounts(&instruction.accounts) {
+                    details["pool"] = json!(accounts.swap.to_string());
+                    ui_amounts.token_account("minimum_token_a_amount", &accounts.swap_token_a);
+                    ui_amounts.token_account("minimum_token_b_amount", &accounts.swap_token_b);
+                }
+                ("liquidity", details)
+            }
+            FluxbeamInstruction::DepositSingleTokenTypeExactAmountIn(deposit) => {
+                let mut details = json!({
+                    "type": "add",
+                    "action": "DepositSingleTokenTypeExactAmountIn",
+                    "source_token_amount": deposit.source_token_amount,
+                    "minimum_pool_token_amount": deposit.minimum_pool_token_amount
+                });
+                if let Some(accounts) = DepositSingleTokenTypeExactAmountIn::arrange_accounts(&instruction.accounts) {
+                    details["pool"] = json!(accounts.swap.to_string());
+                    details["mint"] = json!(accounts.source_mint.to_string());
+                    ui_amounts.mint("source_token_amount", accounts.source_mint);
+                }
+                ("liquidity", details)
+            }
+            FluxbeamInstruction::WithdrawSingleTokenTypeExactAmountOut(withdraw) => {
+                let mut details = json!({
+                    "type": "remove",
+                    "action": "WithdrawSingleTokenTypeExactAmountOut",
+                    "destination_token_amount": withdraw.destination_token_amount,
+                    "maximum_pool_token_amount": withdraw.maximum_pool_token_amount
+                });
+                if let Some(accounts) = WithdrawSingleTokenTypeExactAmountOut::arrange_accounts(&instruction.accounts) {
+                    details["pool"] = json!(accounts.swap.to_string());
+                    details["mint"] = json!(accounts.destination_mint.to_string());
+                    ui_amounts.mint("destination_token_amount", accounts.destination_mint);
+                }
+                ("liquidity", details)
+            }
+            FluxbeamInstruction::Initialize(init) => {
+                let mut details = json!({
+                    "type": "Initialize",
+                    "fees": init.fees,
+                    "curve_type": format!("{:?}", init.swap_curve.curve_type)
+                });
+                if let Some(accounts) = Initialize::arrange_accounts(&instruction.accounts) {
+                    details["pool"] = json!(accounts.swap.to_string());
+                    details["lp_mint"] = json!(accounts.pool.to_string());
+                    // Vaults are funded before Initialize, so their mints are
+                    // in the transaction's token balances.
+                    if let Some(mint) = token_account_mint(transaction, &accounts.token_a) {
+                        details["token_a_mint"] = json!(mint.to_string());
+                    }
+                    if let Some(mint) = token_account_mint(transaction, &accounts.token_b) {
+                        details["token_b_mint"] = json!(mint.to_string());
+                    }
+                    // Initialize takes no signer, the pool is created by the fee payer
+                    if let (Some(mint_a), Some(mint_b)) = (
+                        token_account_mint(transaction, &accounts.token_a),
+                        token_account_mint(transaction, &accounts.token_b),
+                    ) {
+                        new_pair = Some(NewPairEvent::new(
+                            accounts.swap,
+                            (mint_a, Some(accounts.token_a)),
+                            (mint_b, Some(accounts.token_b)),
+                            Some(transaction.fee_payer),
+                        ));
+                    }
+                }
+                ("new_pool", details)
+            }
+        };
+
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(transaction);
+        }
+        if let Some(side) = fill.as_ref().and_then(|fill| TradeSide::from_fill(fill, None)) {
+            side.apply(&mut details);
+        }
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        let executed = transfers.as_ref().and_then(SwapTransfers::fill).or_else(|| fill.clone());
+        if let (Some(executed), Some((pool, trader))) = (&executed, trade) {
+            let swap_event = SwapEvent::new(executed, Some(pool), Some(trader));
+            swap_event.apply(transaction, &mut details);
+            self.usd_prices.apply(transaction, &swap_event, &mut details);
+        }
+        ui_amounts.apply(&mut details);
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(transaction, &mut details);
+        }
+        self.pool_reserves.observe(transaction, &mut details).await;
+
+        self.common_process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details)
+            .await?;
+        if let (Some(fill), false) = (&fill, routed) {
+            self.whale_alerts.check(&platform, transaction, None, fill).await;
+        }
+        if let Some((pool, trader)) = trade {
+            self.participation.record(&platform, pool, trader, transaction);
+        }
+        self.publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Wallet behind each published instruction, its user transfer authority.
+/// Pool initialization names no wallet.
+fn trader(instruction: &FluxbeamInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<solana_pubkey::Pubkey> {
+    match instruction {
+        FluxbeamInstruction::Swap(_)
+        | FluxbeamInstruction::DepositAllTokenTypes(_)
+        | FluxbeamInstruction::WithdrawAllTokenTypes(_)
+        | FluxbeamInstruction::DepositSingleTokenTypeExactAmountIn(_)
+        | FluxbeamInstruction::WithdrawSingleTokenTypeExactAmountOut(_) => TraderAccount::At(2).find(accounts),
+        _ => None,
+    }
+}
+
+impl CommonProcessor for FluxbeamProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+
+    fn pool_registry(&self) -> Option<&PoolRegistry> { Some(&self.pool_registry) }
+}
@@ -0,0 +1,281 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        processor::Processor,
+        transaction::TransactionMetadata,
+    },
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+    std::sync::Arc,
+};
+
+use crate::{
+    alerts::whale::WhaleAlerts,
+    analytics::{
+        balances::token_balance_change,
+        decimals::UiAmounts,
+        jito::jito_tip_annotation,
+        new_pair::NewPairEvent,
+        participation::ParticipationTracker,
+        price_index::PriceIndex,
+        side::TradeSide,
+        swap_event::SwapEvent,
+        trader::TraderAccount,
+        transfers::SwapTransfers,
+        usd_prices::UsdPrices,
+    },
+    datasources::discovery::PoolDiscovery,
+    decoders::invariant::{
+        InvariantCreatePool, InvariantCreatePosition, InvariantInstruction, InvariantPositionAccounts,
+        InvariantRemovePosition, InvariantSwap,
+    },
+    publishers::UnifiedPublisher,
+};
+
+use super::{
+    layout::{check_layout, AccountRole},
+    CommonProcessor, EventTime,
+};
+
+/// Invariant CLMM. Positions are opened and closed whole, so each one is a
+/// single add and a single remove of liquidity.
+pub struct InvariantProcessor {
+    publisher: UnifiedPublisher,
+    price_index: PriceIndex,
+    whale_alerts: WhaleAlerts,
+    discovery: PoolDiscovery,
+    participation: ParticipationTracker,
+    usd_prices: UsdPrices,
+}
+
+impl InvariantProcessor {
+    pub fn new(
+        publisher: UnifiedPublisher,
+        price_index: PriceIndex,
+        whale_alerts: WhaleAlerts,
+        discovery: PoolDiscovery,
+        participation: ParticipationTracker,
+        usd_prices: UsdPrices,
+    ) -> Self {
+        Self { publisher, price_index, whale_alerts, discovery, participation, usd_prices }
+    }
+}
+
+#[async_trait]
+impl Processor for InvariantProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<InvariantInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let transaction = &metadata.transaction_metadata;
+        let signature = transaction.signature.to_string();
+        let platform = "Invariant".to_string();
+        let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction.data, &instruction.accounts));
+        let routed = timestamp.via_aggregator.is_some();
+
+        let mut fill = None;
+        let mut transfers = None;
+        let mut trade = None;
+        let mut new_pool = None;
+        let mut new_pair = None;
+        let mut ui_amounts = UiAmounts::new(transaction);
+        let mut decode_warnings = Vec::new();
+        let (event_type, mut details) = match instruction.data {
+            InvariantInstruction::Swap(swap) => {
+                let mut details = json!({
+                    "type": "Swap",
+                    "x_to_y": swap.x_to_y,
+                    "amount": swap.amount,
+                    "by_amount_in": swap.by_amount_in,
+                    "sqrt_price_limit": swap.sqrt_price_limit
+                });
+                if let Some(accounts) = check_layout::<InvariantSwap>(
+                    "Swap",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    13..=usize::MAX,
+                    |a| vec![
+                        ("owner", a.owner, AccountRole::Signer),
+                        ("account_x", a.account_x, AccountRole::Writable),
+                        ("account_y", a.account_y, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                ) {
+                    let (source, destination, input_mint, output_mint) = if swap.x_to_y {
+                        (accounts.account_x, accounts.account_y, accounts.token_x, accounts.token_y)
+                    } else {
+                        (accounts.account_y, accounts.account_x, accounts.token_y, accounts.token_x)
+                    };
+                    trade = Some((accounts.pool, accounts.owner));
+                    fill = self.price_index.observe(&platform, transaction, &source, &destination);
+                    transfers = SwapTransfers::find(transaction, &nested, &source, &destination);
+                    details["pool"] = json!(accounts.pool.to_string());
+                    details["input_mint"] = json!(input_mint.to_string());
+                    details["output_mint"] = json!(output_mint.to_string());
+                    // `amount` is the input when swapping by amount in, the output otherwise
+                    ui_amounts.mint("amount", if swap.by_amount_in { input_mint } else { output_mint });
+                }
+                ("swap", details)
+            }
+            InvariantInstruction::CreatePosition(create) => {
+                let mut details = json!({
+                    "type": "add",
+                    "action": "CreatePosition",
+                    "lower_tick": create.lower_tick,
+                    "upper_tick": create.upper_tick,
+                    "liquidity": create.liquidity_delta
+                });
+                if let Some(accounts) = check_layout::<InvariantCreatePosition>(
+                    "CreatePosition",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    15..=usize::MAX,
+                    |a| vec![("owner", a.owner, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) {
+                    position_amounts(transaction, &accounts, &mut details, &mut ui_amounts);
+                }
+                ("liquidity", details)
+            }
+            InvariantInstruction::RemovePosition(remove) => {
+                let mut details = json!({
+                    "type": "remove",
+                    "action": "RemovePosition",
+                    "index": remove.index,
+                    "lower_tick": remove.lower_tick,
+                    "upper_tick": remove.upper_tick
+                });
+                if let Some(accounts) = check_layout::<InvariantRemovePosition>(
+                    "RemovePosition",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    15..=usize::MAX,
+                    |a| vec![("owner", a.owner, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) {
+                    position_amounts(transaction, &accounts, &mut details, &mut ui_amounts);
+                }
+                ("liquidity", details)
+            }
+            InvariantInstruction::CreatePool(create) => {
+                let mut details = json!({
+                    "type": "CreatePool",
+                    "init_tick": create.init_tick
+                });
+                if let Some(accounts) = check_layout::<InvariantCreatePool>(
+                    "CreatePool",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    6..=usize::MAX,
+                    |_| Vec::new(),
+                    &mut decode_warnings,
+                ) {
+                    details["pool"] = json!(accounts.pool.to_string());
+                    details["fee_tier"] = json!(accounts.fee_tier.to_string());
+                    details["base_mint"] = json!(accounts.token_x.to_string());
+                    details["quote_mint"] = json!(accounts.token_y.to_string());
+                    new_pool = Some((accounts.pool, accounts.token_x, accounts.token_y));
+                    // Pools are created empty, liquidity comes with the first positions
+                    new_pair = Some(NewPairEvent::new(
+                        accounts.pool,
+                        (accounts.token_x, None),
+                        (accounts.token_y, None),
+                        Some(transaction.fee_payer),
+                    ));
+                }
+                ("new_pool", details)
+            }
+            InvariantInstruction::Unsupported { .. } => return Ok(()),
+        };
+
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(transaction);
+        }
+        if let Some(side) = fill.as_ref().and_then(|fill| TradeSide::from_fill(fill, None)) {
+            side.apply(&mut details);
+        }
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        let executed = transfers.as_ref().and_then(SwapTransfers::fill).or_else(|| fill.clone());
+        if let (Some(executed), Some((pool, trader))) = (&executed, trade) {
+            let swap_event = SwapEvent::new(executed, Some(pool), Some(trader));
+            swap_event.apply(transaction, &mut details);
+            self.usd_prices.apply(transaction, &swap_event, &mut details);
+        }
+        ui_amounts.apply(&mut details);
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(transaction, &mut details);
+        }
+
+        self.common_process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details)
+            .await?;
+        if let (Some(fill), false) = (&fill, routed) {
+            self.whale_alerts.check(&platform, transaction, None, fill).await;
+        }
+        if let Some((pool, trader)) = trade {
+            self.participation.record(&platform, pool, trader, transaction);
+        }
+        if let Some(tracking) = new_pool.and_then(|(pool, base_mint, quote_mint)| {
+            self.discovery.discover(&platform, pool, base_mint, quote_mint)
+        }) {
+            self.common_process_event("tracking_started", platform.clone(), signature.clone(), timestamp.clone(), tracking)
+                .await?;
+        }
+        self.publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Pool, position and token amounts of a position change. Neither
+/// instruction takes token amounts, so they are read from the balance
+/// changes of the pool reserves.
+fn position_amounts(
+    transaction: &TransactionMetadata,
+    accounts: &InvariantPositionAccounts,
+    details: &mut Value,
+    ui_amounts: &mut UiAmounts,
+) {
+    details["pool"] = json!(accounts.pool.to_string());
+    details["position"] = json!(accounts.position.to_string());
+    details["user"] = json!(accounts.owner.to_string());
+    details["token_x_mint"] = json!(accounts.token_x.to_string());
+    details["token_y_mint"] = json!(accounts.token_y.to_string());
+    if let Some(change) = token_balance_change(transaction, &accounts.reserve_x) {
+        details["amount_x"] = json!(change.unsigned_abs() as u64);
+        ui_amounts.mint("amount_x", accounts.token_x);
+    }
+    if let Some(change) = token_balance_change(transaction, &accounts.reserve_y) {
+        details["amount_y"] = json!(change.unsigned_abs() as u64);
+        ui_amounts.mint("amount_y", accounts.token_y);
+    }
+}
+
+/// Wallet behind each published instruction, the position or swap owner.
+/// Pool creation names no wallet.
+fn trader(instruction: &InvariantInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<Pubkey> {
+    let account = match instruction {
+        InvariantInstruction::Swap(_) => TraderAccount::At(9),
+        InvariantInstruction::CreatePosition(_) => TraderAccount::At(5),
+        InvariantInstruction::RemovePosition(_) => TraderAccount::At(8),
+        _ => return None,
+    };
+    account.find(accounts)
+}
+
+impl CommonProcessor for InvariantProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
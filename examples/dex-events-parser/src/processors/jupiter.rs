@@ -0,0 +1,571 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    carbon_jupiter_swap_decoder::{
+        instructions::{
+            exact_out_route::ExactOutRoute, route::Route,
+            route_with_token_ledger::RouteWithTokenLedger,
+            shared_accounts_exact_out_route::SharedAccountsExactOutRoute,
+            shared_accounts_route::SharedAccountsRoute,
+            shared_accounts_route_with_token_ledger::SharedAccountsRouteWithTokenLedger,
+            JupiterSwapInstruction,
+        },
+        types::{RoutePlanStep, Swap},
+        PROGRAM_ID as JUPITER_V6_PROGRAM_ID,
+    },
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+    std::sync::Arc,
+};
+
+use crate::{
+    alerts::whale::WhaleAlerts,
+    analytics::{
+        balances::token_account_owner,
+        decimals::UiAmounts,
+        execution_quality::ExecutionQuality,
+        jito::jito_tip_annotation,
+        price_index::SwapFill,
+        route_legs::{jupiter_route_legs, RouteLeg},
+        side::TradeSide,
+        slippage::{observed_slippage, QuoteSource, SwapMode},
+        swap_event::SwapEvent,
+        trader::TraderAccount,
+        transfers::SwapTransfers,
+        usd_prices::UsdPrices,
+    },
+    publishers::UnifiedPublisher,
+};
+
+use super::{
+    layout::{check_layout, AccountRole},
+    CommonProcessor, EventTime,
+};
+
+pub const JUPITER_V4_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("JUP4Fb2cqiRUcaTHdrPC8h2gNsA2ETXiPDD33WcGuJB");
+pub const JUPITER_V3_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("JUP3c2Uh3WA4Ng34tw6kPd2G4C5BB21Xo36Je1s32Ph");
+
+/// Jupiter aggregator program generations. Every generation lives at its own
+/// program ID and uses its own instruction layout, so events carry the
+/// version they were decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JupiterProgramVersion {
+    V6,
+    V4,
+    V3,
+}
+
+impl JupiterProgramVersion {
+    pub const LEGACY: [JupiterProgramVersion; 2] =
+        [JupiterProgramVersion::V4, JupiterProgramVersion::V3];
+
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            JupiterProgramVersion::V6 => JUPITER_V6_PROGRAM_ID,
+            JupiterProgramVersion::V4 => JUPITER_V4_PROGRAM_ID,
+            JupiterProgramVersion::V3 => JUPITER_V3_PROGRAM_ID,
+        }
+    }
+
+    pub fn from_program_id(program_id: &Pubkey) -> Option<Self> {
+        [JupiterProgramVersion::V6]
+            .into_iter()
+            .chain(Self::LEGACY)
+            .find(|version| version.program_id() == *program_id)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JupiterProgramVersion::V6 => "v6",
+            JupiterProgramVersion::V4 => "v4",
+            JupiterProgramVersion::V3 => "v3",
+        }
+    }
+}
+
+/// Name of the venue a route step swaps through, without the per-venue
+/// direction arguments (e.g. `Whirlpool { a_to_b: true }` -> `Whirlpool`).
+pub fn venue_name(swap: &Swap) -> &'static str {
+    match swap {
+        Swap::Saber => "Saber",
+        Swap::SaberAddDecimalsDeposit => "SaberAddDecimalsDeposit",
+        Swap::SaberAddDecimalsWithdraw => "SaberAddDecimalsWithdraw",
+        Swap::TokenSwap => "TokenSwap",
+        Swap::Sencha => "Sencha",
+        Swap::Step => "Step",
+        Swap::Cropper => "Cropper",
+        Swap::Raydium => "Raydium",
+        Swap::Crema { .. } => "Crema",
+        Swap::Lifinity => "Lifinity",
+        Swap::Mercurial => "Mercurial",
+        Swap::Cykura => "Cykura",
+        Swap::Serum { .. } => "Serum",
+        Swap::MarinadeDeposit => "MarinadeDeposit",
+        Swap::MarinadeUnstake => "MarinadeUnstake",
+        Swap::Aldrin { .. } => "Aldrin",
+        Swap::AldrinV2 { .. } => "AldrinV2",
+        Swap::Whirlpool { .. } => "Whirlpool",
+        Swap::Invariant { .. } => "Invariant",
+        Swap::Meteora => "Meteora",
+        Swap::GooseFX => "GooseFX",
+        Swap::DeltaFi { .. } => "DeltaFi",
+        Swap::Balansol => "Balansol",
+        Swap::MarcoPolo { .. } => "MarcoPolo",
+        Swap::Dradex { .. } => "Dradex",
+        Swap::LifinityV2 => "LifinityV2",
+        Swap::RaydiumClmm => "RaydiumClmm",
+        Swap::Openbook { .. } => "Openbook",
+        Swap::Phoenix { .. } => "Phoenix",
+        Swap::Symmetry { .. } => "Symmetry",
+        Swap::TokenSwapV2 => "TokenSwapV2",
+        Swap::HeliumTreasuryManagementRedeemV0 => "HeliumTreasuryManagementRedeemV0",
+        Swap::StakeDexStakeWrappedSol => "StakeDexStakeWrappedSol",
+        Swap::StakeDexSwapViaStake { .. } => "StakeDexSwapViaStake",
+        Swap::GooseFXV2 => "GooseFXV2",
+        Swap::Perps => "Perps",
+        Swap::PerpsAddLiquidity => "PerpsAddLiquidity",
+        Swap::PerpsRemoveLiquidity => "PerpsRemoveLiquidity",
+        Swap::MeteoraDlmm => "MeteoraDlmm",
+        Swap::OpenBookV2 { .. } => "OpenBookV2",
+        Swap::RaydiumClmmV2 => "RaydiumClmmV2",
+        Swap::StakeDexPrefundWithdrawStakeAndDepositStake { .. } => "StakeDexPrefundWithdrawStakeAndDepositStake",
+        Swap::Clone { .. } => "Clone",
+        Swap::SanctumS { .. } => "SanctumS",
+        Swap::SanctumSAddLiquidity { .. } => "SanctumSAddLiquidity",
+        Swap::SanctumSRemoveLiquidity { .. } => "SanctumSRemoveLiquidity",
+        Swap::RaydiumCP => "RaydiumCP",
+        Swap::WhirlpoolSwapV2 { .. } => "WhirlpoolSwapV2",
+        Swap::OneIntro => "OneIntro",
+        Swap::PumpdotfunWrappedBuy => "PumpdotfunWrappedBuy",
+        Swap::PumpdotfunWrappedSell => "PumpdotfunWrappedSell",
+        Swap::PerpsV2 => "PerpsV2",
+        Swap::PerpsV2AddLiquidity => "PerpsV2AddLiquidity",
+        Swap::PerpsV2RemoveLiquidity => "PerpsV2RemoveLiquidity",
+        Swap::MoonshotWrappedBuy => "MoonshotWrappedBuy",
+        Swap::MoonshotWrappedSell => "MoonshotWrappedSell",
+        Swap::StabbleStableSwap => "StabbleStableSwap",
+        Swap::StabbleWeightedSwap => "StabbleWeightedSwap",
+        Swap::Obric { .. } => "Obric",
+        Swap::FoxBuyFromEstimatedCost => "FoxBuyFromEstimatedCost",
+        Swap::FoxClaimPartial { .. } => "FoxClaimPartial",
+        Swap::SolFi { .. } => "SolFi",
+        Swap::SolayerDelegateNoInit => "SolayerDelegateNoInit",
+        Swap::SolayerUndelegateNoInit => "SolayerUndelegateNoInit",
+        Swap::TokenMill { .. } => "TokenMill",
+        Swap::DaosFunBuy => "DaosFunBuy",
+        Swap::DaosFunSell => "DaosFunSell",
+        Swap::ZeroFi => "ZeroFi",
+        Swap::StakeDexWithdrawWrappedSol => "StakeDexWithdrawWrappedSol",
+        Swap::VirtualsBuy => "VirtualsBuy",
+        Swap::VirtualsSell => "VirtualsSell",
+        Swap::Perena { .. } => "Perena",
+        Swap::PumpdotfunAmmBuy => "PumpdotfunAmmBuy",
+        Swap::PumpdotfunAmmSell => "PumpdotfunAmmSell",
+        Swap::Gamma => "Gamma",
+    }
+}
+
+/// Serializes a v6 route plan with the split percentage of every step.
+pub fn route_plan_json(route_plan: &[RoutePlanStep]) -> Value {
+    Value::Array(
+        route_plan
+            .iter()
+            .map(|step| {
+                json!({
+                    "venue": venue_name(&step.swap),
+                    "percent": step.percent,
+                    "input_index": step.input_index,
+                    "output_index": step.output_index
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Serializes the executed hops of a route in order. Every step of the plan
+/// swaps once, so when both have the same length the venue and split
+/// percentage of the matching step are added.
+pub fn route_legs_json(legs: &[RouteLeg], route_plan: &[RoutePlanStep]) -> Value {
+    let steps = (legs.len() == route_plan.len()).then_some(route_plan);
+    Value::Array(
+        legs.iter()
+            .enumerate()
+            .map(|(index, leg)| {
+                let mut json = leg.json();
+                json["index"] = json!(index);
+                if let Some(step) = steps.map(|steps| &steps[index]) {
+                    json["venue"] = json!(venue_name(&step.swap));
+                    json["percent"] = json!(step.percent);
+                }
+                json
+            })
+            .collect(),
+    )
+}
+
+// Jupiter Swap Processor (v6)
+pub struct JupiterSwapProcessor {
+    publisher: UnifiedPublisher,
+    execution_quality: ExecutionQuality,
+    whale_alerts: WhaleAlerts,
+    usd_prices: UsdPrices,
+}
+
+impl JupiterSwapProcessor {
+    pub fn new(
+        publisher: UnifiedPublisher,
+        execution_quality: ExecutionQuality,
+        whale_alerts: WhaleAlerts,
+        usd_prices: UsdPrices,
+    ) -> Self {
+        Self { publisher, execution_quality, whale_alerts, usd_prices }
+    }
+}
+
+#[async_trait]
+impl Processor for JupiterSwapProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<JupiterSwapInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let signature = metadata.transaction_metadata.signature.to_string();
+        let platform = "Jupiter Swap".to_string();
+        let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction.data, &instruction.accounts));
+        let program_version = JupiterProgramVersion::from_program_id(&instruction.program_id)
+            .unwrap_or(JupiterProgramVersion::V6)
+            .as_str();
+        let transaction = &metadata.transaction_metadata;
+        let accounts = &instruction.accounts;
+        let mut decode_warnings = Vec::new();
+
+        // Every route variant is decoded into its quote, the user's source and
+        // destination token accounts, the route plan and its own arguments.
+        let (name, mode, quoted_amount, user_accounts, route_plan, args) = match instruction.data {
+            JupiterSwapInstruction::Route(route) => (
+                "Route",
+                SwapMode::ExactIn,
+                route.quoted_out_amount,
+                check_layout::<Route>(
+                    "Route",
+                    accounts,
+                    metadata.stack_height,
+                    9..=usize::MAX,
+                    |a| vec![
+                        ("user_transfer_authority", a.user_transfer_authority, AccountRole::Signer),
+                        ("user_source_token_account", a.user_source_token_account, AccountRole::Writable),
+                        ("user_destination_token_account", a.user_destination_token_account, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                .map(|a| (a.user_source_token_account, a.user_destination_token_account)),
+                route.route_plan,
+                json!({
+                    "platform_fee_bps": route.platform_fee_bps,
+                    "slippage_bps": route.slippage_bps,
+                    "in_amount": route.in_amount,
+                    "quoted_out_amount": route.quoted_out_amount
+                }),
+            ),
+            JupiterSwapInstruction::RouteWithTokenLedger(route) => (
+                "RouteWithTokenLedger",
+                SwapMode::ExactIn,
+                route.quoted_out_amount,
+                check_layout::<RouteWithTokenLedger>(
+                    "RouteWithTokenLedger",
+                    accounts,
+                    metadata.stack_height,
+                    10..=usize::MAX,
+                    |a| vec![
+                        ("user_transfer_authority", a.user_transfer_authority, AccountRole::Signer),
+                        ("user_source_token_account", a.user_source_token_account, AccountRole::Writable),
+                        ("user_destination_token_account", a.user_destination_token_account, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                .map(|a| (a.user_source_token_account, a.user_destination_token_account)),
+                route.route_plan,
+                json!({
+                    "platform_fee_bps": route.platform_fee_bps,
+                    "slippage_bps": route.slippage_bps,
+                    "quoted_out_amount": route.quoted_out_amount
+                }),
+            ),
+            JupiterSwapInstruction::SharedAccountsRoute(route) => (
+                "SharedAccountsRoute",
+                SwapMode::ExactIn,
+                route.quoted_out_amount,
+                check_layout::<SharedAccountsRoute>(
+                    "SharedAccountsRoute",
+                    accounts,
+                    metadata.stack_height,
+                    13..=usize::MAX,
+                    |a| vec![
+                        ("user_transfer_authority", a.user_transfer_authority, AccountRole::Signer),
+                        ("source_token_account", a.source_token_account, AccountRole::Writable),
+                        ("destination_token_account", a.destination_token_account, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                .map(|a| (a.source_token_account, a.destination_token_account)),
+                route.route_plan,
+                json!({
+                    "platform_fee_bps": route.platform_fee_bps,
+                    "slippage_bps": route.slippage_bps,
+                    "in_amount": route.in_amount,
+                    "quoted_out_amount": route.quoted_out_amount
+                }),
+            ),
+            JupiterSwapInstruction::SharedAccountsRouteWithTokenLedger(route) => (
+                "SharedAccountsRouteWithTokenLedger",
+                SwapMode::ExactIn,
+                route.quoted_out_amount,
+                check_layout::<SharedAccountsRouteWithTokenLedger>(
+                    "SharedAccountsRouteWithTokenLedger",
+                    accounts,
+                    metadata.stack_height,
+                    14..=usize::MAX,
+                    |a| vec![
+                        ("user_transfer_authority", a.user_transfer_authority, AccountRole::Signer),
+                        ("source_token_account", a.source_token_account, AccountRole::Writable),
+                        ("destination_token_account", a.destination_token_account, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                .map(|a| (a.source_token_account, a.destination_token_account)),
+                route.route_plan,
+                json!({
+                    "platform_fee_bps": route.platform_fee_bps,
+                    "slippage_bps": route.slippage_bps,
+                    "quoted_out_amount": route.quoted_out_amount
+                }),
+            ),
+            JupiterSwapInstruction::ExactOutRoute(exact_out_route) => (
+                "ExactOutRoute",
+                SwapMode::ExactOut,
+                exact_out_route.quoted_in_amount,
+                check_layout::<ExactOutRoute>(
+                    "ExactOutRoute",
+                    accounts,
+                    metadata.stack_height,
+                    11..=usize::MAX,
+                    |a| vec![
+                        ("user_transfer_authority", a.user_transfer_authority, AccountRole::Signer),
+                        ("user_source_token_account", a.user_source_token_account, AccountRole::Writable),
+                        ("user_destination_token_account", a.user_destination_token_account, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                .map(|a| (a.user_source_token_account, a.user_destination_token_account)),
+                exact_out_route.route_plan,
+                json!({
+                    "platform_fee_bps": exact_out_route.platform_fee_bps,
+                    "slippage_bps": exact_out_route.slippage_bps,
+                    "out_amount": exact_out_route.out_amount,
+                    "quoted_in_amount": exact_out_route.quoted_in_amount
+                }),
+            ),
+            JupiterSwapInstruction::SharedAccountsExactOutRoute(exact_out_route) => (
+                "SharedAccountsExactOutRoute",
+                SwapMode::ExactOut,
+                exact_out_route.quoted_in_amount,
+                check_layout::<SharedAccountsExactOutRoute>(
+                    "SharedAccountsExactOutRoute",
+                    accounts,
+                    metadata.stack_height,
+                    13..=usize::MAX,
+                    |a| vec![
+                        ("user_transfer_authority", a.user_transfer_authority, AccountRole::Signer),
+                        ("source_token_account", a.source_token_account, AccountRole::Writable),
+                        ("destination_token_account", a.destination_token_account, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                .map(|a| (a.source_token_account, a.destination_token_account)),
+                exact_out_route.route_plan,
+                json!({
+                    "platform_fee_bps": exact_out_route.platform_fee_bps,
+                    "slippage_bps": exact_out_route.slippage_bps,
+                    "out_amount": exact_out_route.out_amount,
+                    "quoted_in_amount": exact_out_route.quoted_in_amount
+                }),
+            ),
+            _ => return Ok(()),
+        };
+
+        let slippage = user_accounts
+            .map(|(source, destination)| observed_slippage(
+                transaction,
+                mode,
+                QuoteSource::Quoted,
+                quoted_amount,
+                match mode {
+                    SwapMode::ExactIn => &destination,
+                    SwapMode::ExactOut => &source,
+                },
+            ))
+            .unwrap_or_default();
+
+        let mut details = json!({
+            "type": name,
+            "program_version": program_version,
+            "slippage": slippage,
+            "route_plan": route_plan_json(&route_plan),
+            "jito_tip": jito_tip_annotation(transaction)
+        });
+        if let (Some(details), Value::Object(args)) = (details.as_object_mut(), args) {
+            details.extend(args);
+        }
+        let legs = jupiter_route_legs(&nested);
+        if !legs.is_empty() {
+            details["legs"] = route_legs_json(&legs, &route_plan);
+        }
+        if let Some((source, destination)) = &user_accounts {
+            let mut ui_amounts = UiAmounts::new(transaction);
+            ui_amounts.token_account("in_amount", source);
+            ui_amounts.token_account("quoted_in_amount", source);
+            ui_amounts.token_account("out_amount", destination);
+            ui_amounts.token_account("quoted_out_amount", destination);
+            ui_amounts.apply(&mut details);
+        }
+        let fill = user_accounts
+            .and_then(|(source, destination)| SwapFill::from_balance_changes(transaction, &source, &destination));
+        if let Some(side) = fill.as_ref().and_then(|fill| TradeSide::from_fill(fill, None)) {
+            side.apply(&mut details);
+        }
+        let transfers = user_accounts
+            .and_then(|(source, destination)| SwapTransfers::find(transaction, &nested, &source, &destination));
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        let executed = transfers.as_ref().and_then(SwapTransfers::fill).or_else(|| fill.clone());
+        if let (Some(executed), Some((source, _))) = (&executed, &user_accounts) {
+            // Routes span several pools; the trader owns the source account
+            let swap_event = SwapEvent::new(executed, None, token_account_owner(transaction, source));
+            swap_event.apply(transaction, &mut details);
+            self.usd_prices.apply(transaction, &swap_event, &mut details);
+        }
+
+        self.common_process_event("swap", platform.clone(), signature.clone(), timestamp.clone(), details)
+            .await?;
+        self.publish_decode_warnings(&platform, &signature, timestamp.clone(), decode_warnings, &metrics)
+            .await?;
+
+        let Some(fill) = fill else {
+            return Ok(());
+        };
+        let venues = route_plan
+            .iter()
+            .map(|step| venue_name(&step.swap))
+            .collect::<Vec<_>>();
+        if let Some(quality) = self.execution_quality.compare(transaction, &fill, &venues) {
+            self.common_process_event("execution_quality", platform.clone(), signature, timestamp, quality)
+                .await?;
+        }
+        self.whale_alerts.check(&platform, transaction, None, &fill).await;
+
+        Ok(())
+    }
+}
+
+/// Wallet behind each published route, its user transfer authority.
+fn trader(instruction: &JupiterSwapInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<solana_pubkey::Pubkey> {
+    let account = match instruction {
+        JupiterSwapInstruction::Route(_)
+        | JupiterSwapInstruction::RouteWithTokenLedger(_)
+        | JupiterSwapInstruction::ExactOutRoute(_) => TraderAccount::At(1),
+        JupiterSwapInstruction::SharedAccountsRoute(_)
+        | JupiterSwapInstruction::SharedAccountsRouteWithTokenLedger(_)
+        | JupiterSwapInstruction::SharedAccountsExactOutRoute(_) => TraderAccount::At(2),
+        _ => return None,
+    };
+    account.find(accounts)
+}
+
+impl CommonProcessor for JupiterSwapProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
+
+// Jupiter legacy (v3/v4) Processor
+#[cfg(feature = "jupiter-legacy")]
+pub struct JupiterLegacyProcessor {
+    publisher: UnifiedPublisher,
+}
+
+#[cfg(feature = "jupiter-legacy")]
+impl JupiterLegacyProcessor {
+    pub fn new(publisher: UnifiedPublisher) -> Self {
+        Self { publisher }
+    }
+}
+
+#[cfg(feature = "jupiter-legacy")]
+#[async_trait]
+impl Processor for JupiterLegacyProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<crate::decoders::jupiter_legacy::JupiterLegacyInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, _, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        use crate::decoders::jupiter_legacy::JupiterLegacyInstruction;
+
+        let signature = metadata.transaction_metadata.signature.to_string();
+        let platform = "Jupiter Swap".to_string();
+        let timestamp = EventTime::now(&metadata);
+
+        let details = match instruction.data {
+            JupiterLegacyInstruction::Route(route) => json!({
+                "type": "Route",
+                "program_version": route.version.as_str(),
+                "platform_fee_bps": route.platform_fee_bps,
+                "in_amount": route.in_amount,
+                "minimum_out_amount": route.minimum_out_amount,
+                // Legacy routes encode their split percentages in a recursive
+                // swap-leg tree that is not decoded.
+                "route_plan": Value::Null,
+                "jito_tip": jito_tip_annotation(&metadata.transaction_metadata)
+            }),
+            JupiterLegacyInstruction::Unsupported { version, discriminator } => {
+                log::debug!(
+                    "Unsupported Jupiter {} instruction {:02x?} in {}",
+                    version.as_str(),
+                    discriminator,
+                    signature
+                );
+                metrics
+                    .increment_counter("jupiter_legacy_unsupported_instructions", 1)
+                    .await
+                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                return Ok(());
+            }
+        };
+
+        self.common_process_event("swap", platform, signature, timestamp, details).await
+    }
+}
+
+#[cfg(feature = "jupiter-legacy")]
+impl CommonProcessor for JupiterLegacyProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
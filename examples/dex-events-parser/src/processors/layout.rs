@@ -0,0 +1,118 @@
+use {
+    carbon_core::deserialize::ArrangeAccounts,
+    serde_json::{json, Value},
+    solana_instruction::AccountMeta,
+    solana_pubkey::Pubkey,
+    std::ops::RangeInclusive,
+};
+
+/// Role an attributed account must have in the instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountRole {
+    Signer,
+    Writable,
+}
+
+impl AccountRole {
+    fn holds(&self, meta: &AccountMeta) -> bool {
+        match self {
+            AccountRole::Signer => meta.is_signer,
+            AccountRole::Writable => meta.is_writable,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            AccountRole::Signer => "signer",
+            AccountRole::Writable => "writable",
+        }
+    }
+}
+
+/// An instruction whose accounts do not match the layout the decoder
+/// expects, typically after a program upgrade.
+#[derive(Debug, Clone)]
+pub struct LayoutMismatch {
+    pub instruction: &'static str,
+    pub account_count: usize,
+    pub problems: Vec<String>,
+}
+
+impl LayoutMismatch {
+    /// `decode_warning` event details.
+    pub fn details(&self) -> Value {
+        json!({
+            "type": "DecodeWarning",
+            "instruction": self.instruction,
+            "account_count": self.account_count,
+            "problems": self.problems
+        })
+    }
+}
+
+fn describe(expected: &RangeInclusive<usize>) -> String {
+    if expected.start() == expected.end() {
+        format!("{}", expected.start())
+    } else if *expected.end() == usize::MAX {
+        format!("at least {}", expected.start())
+    } else {
+        format!("{} to {}", expected.start(), expected.end())
+    }
+}
+
+/// Arranges the accounts of `instruction` and checks them against the
+/// expected account count and the roles of the accounts events are
+/// attributed to. On mismatch the arranged accounts are withheld, so no event
+/// is attributed to the wrong account, and the mismatch is pushed to
+/// `warnings`.
+///
+/// Signer roles are only checked for top-level instructions: an inner
+/// instruction at `stack_height` above 1 is a CPI, whose PDA signers sign via
+/// `invoke_signed` and are not marked as signers in the account list.
+pub fn check_layout<T: ArrangeAccounts>(
+    instruction: &'static str,
+    accounts: &[AccountMeta],
+    stack_height: u32,
+    expected_count: RangeInclusive<usize>,
+    roles: impl FnOnce(&T::ArrangedAccounts) -> Vec<(&'static str, Pubkey, AccountRole)>,
+    warnings: &mut Vec<LayoutMismatch>,
+) -> Option<T::ArrangedAccounts> {
+    let mut problems = Vec::new();
+    if !expected_count.contains(&accounts.len()) {
+        problems.push(format!(
+            "expected {} accounts, got {}",
+            describe(&expected_count),
+            accounts.len()
+        ));
+    }
+
+    let arranged = T::arrange_accounts(accounts);
+    match &arranged {
+        Some(arranged) => {
+            for (name, pubkey, role) in roles(arranged) {
+                if role == AccountRole::Signer && stack_height > 1 {
+                    continue;
+                }
+                let holds = accounts
+                    .iter()
+                    .filter(|meta| meta.pubkey == pubkey)
+                    .any(|meta| role.holds(meta));
+                if !holds {
+                    problems.push(format!("{} {} is not {}", name, pubkey, role.as_str()));
+                }
+            }
+        }
+        None => problems.push("accounts could not be arranged".to_string()),
+    }
+
+    if problems.is_empty() {
+        return arranged;
+    }
+
+    warnings.push(LayoutMismatch {
+        instruction,
+        account_count: accounts.len(),
+        problems,
+    });
+    None
+}
@@ -1,9 +1,302 @@
 pub mod raydium_amm_v4;
 pub mod raydium_clmm;
 pub mod pumpfun;
+pub mod pump_swap;
+pub mod jupiter;
+pub mod drift;
+pub mod fluxbeam;
+pub mod invariant;
+pub mod layout;
+pub mod moonshot;
+pub mod oracle;
 pub mod others;
+pub mod pool_state;
+pub mod raydium_launchpad;
+pub mod sanctum;
+pub mod timeout;
+pub mod token;
+pub mod transaction;
+pub mod virtual_curve;
+
+use {
+    carbon_core::{
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        transaction::TransactionMetadata,
+    },
+    solana_pubkey::Pubkey,
+    std::{sync::Arc, time::SystemTime},
+    crate::{DexEvent, analytics::{aggregator::calling_aggregator, compute_budget::ComputeBudget, memo::transaction_memo, pool_registry::PoolRegistry}, publishers::{DexEventData, DexEventPayload, Publisher, UnifiedPublisher, EVENTS_TOPIC}},
+    layout::LayoutMismatch,
+};
 
 // pub use raydium_amm_v4::RaydiumAmmV4Processor;
 // pub use raydium_clmm::RaydiumClmmProcessor;
 // pub use pumpfun::PumpfunProcessor;
-// pub use others::*; 
\ No newline at end of file
+// pub use others::*;
+
+/// Input of every instruction processor.
+pub(crate) type InstructionInput<T> = (
+    InstructionMetadata,
+    DecodedInstruction<T>,
+    NestedInstructions,
+    solana_instruction::Instruction,
+);
+
+/// When an event happened and was processed, the slot and position of the
+/// instruction it was decoded from, and the wallet behind it.
+#[derive(Debug, Clone)]
+pub(crate) struct EventTime {
+    /// Block time in seconds, or `ingested_at` for events without one.
+    pub timestamp: u64,
+    /// When the parser decoded the event, in milliseconds.
+    pub ingested_at: u64,
+    pub block_time: Option<i64>,
+    pub slot: u64,
+    pub tx_index: Option<u64>,
+    pub instruction_index: Option<u32>,
+    pub is_inner: Option<bool>,
+    pub fee_payer: Option<Pubkey>,
+    pub trader: Option<Pubkey>,
+    /// Aggregator whose route invoked the instruction, published as
+    /// `via_aggregator` in the details. Such venue swaps raise no whale
+    /// alert, the aggregator's route already does.
+    pub via_aggregator: Option<Pubkey>,
+    /// SPL Memos of the transaction, published as `memo` in the details.
+    pub memo: Option<Arc<str>>,
+    /// Published as `compute_budget` in the details.
+    pub compute_budget: Option<ComputeBudget>,
+    /// Why the transaction failed, its events are published with
+    /// `success: false` in the details.
+    pub error: Option<Arc<str>>,
+}
+
+impl EventTime {
+    pub fn now(metadata: &InstructionMetadata) -> Self {
+        Self {
+            instruction_index: Some(metadata.index),
+            is_inner: Some(metadata.stack_height > 1),
+            via_aggregator: calling_aggregator(metadata),
+            ..Self::of_transaction(&metadata.transaction_metadata)
+        }
+    }
+
+    /// For events measured on the whole transaction.
+    pub fn of_transaction(transaction: &TransactionMetadata) -> Self {
+        let ingested_at = unix_millis();
+        Self {
+            timestamp: transaction
+                .block_time
+                .and_then(|block_time| u64::try_from(block_time).ok())
+                .unwrap_or(ingested_at / 1000),
+            ingested_at,
+            block_time: transaction.block_time,
+            slot: transaction.slot,
+            tx_index: transaction.index,
+            instruction_index: None,
+            is_inner: None,
+            fee_payer: Some(transaction.fee_payer),
+            trader: None,
+            via_aggregator: None,
+            memo: transaction_memo(transaction).map(Arc::from),
+            compute_budget: Some(ComputeBudget::of_transaction(transaction)),
+            error: transaction.meta.status.as_ref().err().map(|error| Arc::from(error.to_string())),
+        }
+    }
+
+    /// For account updates, which carry no block time.
+    pub fn at_slot(slot: u64) -> Self {
+        let ingested_at = unix_millis();
+        Self {
+            timestamp: ingested_at / 1000,
+            ingested_at,
+            block_time: None,
+            slot,
+            tx_index: None,
+            instruction_index: None,
+            is_inner: None,
+            fee_payer: None,
+            trader: None,
+            via_aggregator: None,
+            memo: None,
+            compute_budget: None,
+            error: None,
+        }
+    }
+
+    /// With the wallet that signed the instruction, see
+    /// [`crate::analytics::trader::TraderAccount`].
+    pub fn with_trader(self, trader: Option<Pubkey>) -> Self {
+        Self { trader, ..self }
+    }
+
+    /// The published envelope of an event decoded at this time.
+    pub fn envelope(self, event_type: &str, platform: String, signature: String, mut details: serde_json::Value) -> DexEventData {
+        if let (Some(aggregator), Some(object)) = (self.via_aggregator, details.as_object_mut()) {
+            object.insert("via_aggregator".to_string(), serde_json::json!(aggregator.to_string()));
+        }
+        if let (Some(memo), Some(object)) = (&self.memo, details.as_object_mut()) {
+            object.insert("memo".to_string(), serde_json::json!(memo.as_ref()));
+        }
+        if let (Some(compute_budget), Some(object)) = (self.compute_budget, details.as_object_mut()) {
+            object.insert("compute_budget".to_string(), compute_budget.annotation());
+        }
+        if let (Some(error), Some(object)) = (&self.error, details.as_object_mut()) {
+            object.insert("success".to_string(), serde_json::json!(false));
+            object.insert("error".to_string(), serde_json::json!(error.as_ref()));
+        }
+        DexEventData {
+            event_type: event_type.to_string(),
+            platform,
+            signature,
+            timestamp: self.timestamp,
+            ingested_at: Some(self.ingested_at),
+            block_time: self.block_time,
+            slot: Some(self.slot),
+            tx_index: self.tx_index,
+            instruction_index: self.instruction_index,
+            is_inner: self.is_inner,
+            fee_payer: self.fee_payer.map(|fee_payer| fee_payer.to_string()),
+            trader: self.trader.map(|trader| trader.to_string()),
+            payload: DexEventPayload::from_details(event_type, &details),
+            details,
+        }
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+// Trait for common event processing
+pub(crate) trait CommonProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher;
+
+    /// Registry tagging the events of processors whose events name a pool
+    /// with its trading pair.
+    fn pool_registry(&self) -> Option<&PoolRegistry> {
+        None
+    }
+    
+    async fn common_process_event(&self, event_type: &str, platform: String, signature: String, timestamp: EventTime, mut details: serde_json::Value) -> CarbonResult<()> {
+        if let Some(pool_registry) = self.pool_registry() {
+            pool_registry.annotate(&mut details).await;
+        }
+
+        // Create DexEvent for logging
+        let event = match event_type {
+            "swap" => DexEvent::Swap {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "liquidity" => {
+                if details["type"] == "add" {
+                    DexEvent::AddLiquidity {
+                        platform: platform.clone(),
+                        signature: signature.clone(),
+                        details: details.to_string(),
+                    }
+                } else {
+                    DexEvent::RemoveLiquidity {
+                        platform: platform.clone(),
+                        signature: signature.clone(),
+                        details: details.to_string(),
+                    }
+                }
+            }
+            "new_pool" => DexEvent::AddPair {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "creator_fee" => DexEvent::CreatorFee {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "fee_config" => DexEvent::FeeConfig {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "execution_quality" => DexEvent::ExecutionQuality {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "launch_funnel" => DexEvent::LaunchFunnel {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "decode_warning" => DexEvent::DecodeWarning {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "tracking_started" => DexEvent::TrackingStarted {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "fill" => DexEvent::Fill {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "graduation" => DexEvent::Graduation {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "token_transfer" => DexEvent::TokenTransfer {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "transaction" => DexEvent::Transaction {
+                platform: platform.clone(),
+                signature: signature.clone(),
+                details: details.to_string(),
+            },
+            "pool_state" => DexEvent::PoolState {
+                platform: platform.clone(),
+                details: details.to_string(),
+            },
+            _ => return Ok(()),
+        };
+
+        // Log the event
+        event.log();
+
+        // Create ZeroMQ event data
+        let zmq_data = timestamp.envelope(event_type, platform, signature, details);
+
+        // Publish to ZeroMQ
+        if let Err(e) = self.get_publisher().publish(EVENTS_TOPIC, &zmq_data).await {
+            // An unreachable sink is expected to recover, anything else needs a look
+            if e.is_retryable() {
+                log::warn!("Sink unavailable, event not published: {}", e);
+            } else {
+                log::error!("Failed to publish to ZeroMQ: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn publish_decode_warnings(&self, platform: &str, signature: &str, timestamp: EventTime, warnings: Vec<LayoutMismatch>, metrics: &MetricsCollection) -> CarbonResult<()> {
+        for warning in warnings {
+            metrics
+                .increment_counter("instruction_layout_mismatches", 1)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+            self.common_process_event("decode_warning", platform.to_string(), signature.to_string(), timestamp.clone(), warning.details())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    carbon_moonshot_decoder::instructions::{
+        buy::Buy, migrate_funds::MigrateFunds, sell::Sell, token_mint::TokenMint,
+        MoonshotInstruction,
+    },
+    serde_json::json,
+    std::sync::Arc,
+};
+
+use crate::{
+    analytics::{
+        decimals::UiAmounts,
+        jito::jito_tip_annotation,
+        launch_funnel::{LaunchFunnel, LaunchSignal},
+        mints::WSOL_MINT,
+        side::TradeSide,
+        sol_transfers::{sol_transfers, SolLegs},
+        symbols::SymbolRegistry,
+        trader::TraderAccount,
+    },
+    publishers::UnifiedPublisher,
+};
+
+use super::{
+    layout::{check_layout, AccountRole},
+    CommonProcessor, EventTime,
+};
+
+// Moonshot Processor
+pub struct MoonshotProcessor {
+    publisher: UnifiedPublisher,
+    launch_funnel: LaunchFunnel,
+    symbols: SymbolRegistry,
+}
+
+impl MoonshotProcessor {
+    pub fn new(publisher: UnifiedPublisher, launch_funnel: LaunchFunnel, symbols: SymbolRegistry) -> Self {
+        Self { publisher, launch_funnel, symbols }
+    }
+}
+
+#[async_trait]
+impl Processor for MoonshotProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<MoonshotInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let transaction = &metadata.transaction_metadata;
+        let signature = transaction.signature.to_string();
+        let platform = "Moonshot".to_string();
+        let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction.data, &instruction.accounts));
+
+        // The mint is only known from the instruction accounts, so the
+        // instructions are published rather than the TradeEvent/MigrationEvent
+        // self-CPIs, which would otherwise double count every trade.
+        let mut ui_amounts = UiAmounts::new(transaction);
+        let mut decode_warnings = Vec::new();
+        let mut trade_side = None;
+        let mut token_symbol = None;
+        let mut sol_legs = None;
+        let (event_type, mut details, funnel) = match instruction.data {
+            MoonshotInstruction::TokenMint(token_mint) => {
+                let Some(accounts) = check_layout::<TokenMint>(
+                    "TokenMint",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    11..=11,
+                    |a| vec![("sender", a.sender, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                let params = token_mint.mint_params;
+                ui_amounts.mint("amount", accounts.mint);
+                token_symbol = Some((params.symbol.0.clone(), accounts.mint));
+                ("new_pool", json!({
+                    "type": "TokenMint",
+                    "mint": accounts.mint.to_string(),
+                    "curve": accounts.curve_account.to_string(),
+                    "creator": accounts.sender.to_string(),
+                    "name": params.name.0,
+                    "symbol": params.symbol.0,
+                    "decimals": params.decimals,
+                    "amount": params.amount,
+                    "migration_target": params.migration_target
+                }), vec![(accounts.mint, LaunchSignal::Created)])
+            }
+            MoonshotInstruction::Buy(buy) => {
+                let Some(accounts) = check_layout::<Buy>(
+                    "Buy",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    11..=11,
+                    |a| vec![("sender", a.sender, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                // Moonshot curves are collateralized in SOL only.
+                ui_amounts.mint("token_amount", accounts.mint);
+                ui_amounts.mint("collateral_amount", WSOL_MINT);
+                trade_side = TradeSide::from_mints(WSOL_MINT, accounts.mint, Some(WSOL_MINT));
+                // Sellers are paid out of the curve's own lamports, only
+                // buys move SOL with System program transfers
+                sol_legs = SolLegs::attribute(&sol_transfers(&nested), &accounts.sender, &[
+                    ("curve", accounts.curve_account),
+                    ("dex_fee", accounts.dex_fee),
+                    ("helio_fee", accounts.helio_fee),
+                ]);
+                ("swap", json!({
+                    "type": "Buy",
+                    "mint": accounts.mint.to_string(),
+                    "curve": accounts.curve_account.to_string(),
+                    "user": accounts.sender.to_string(),
+                    "token_amount": buy.data.token_amount,
+                    "collateral_amount": buy.data.collateral_amount,
+                    "fixed_side": buy.data.fixed_side,
+                    "slippage_bps": buy.data.slippage_bps
+                }), vec![(accounts.mint, LaunchSignal::Trade)])
+            }
+            MoonshotInstruction::Sell(sell) => {
+                let Some(accounts) = check_layout::<Sell>(
+                    "Sell",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    11..=11,
+                    |a| vec![("sender", a.sender, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                // Moonshot curves are collateralized in SOL only.
+                ui_amounts.mint("token_amount", accounts.mint);
+                ui_amounts.mint("collateral_amount", WSOL_MINT);
+                trade_side = TradeSide::from_mints(accounts.mint, WSOL_MINT, Some(WSOL_MINT));
+                ("swap", json!({
+                    "type": "Sell",
+                    "mint": accounts.mint.to_string(),
+                    "curve": accounts.curve_account.to_string(),
+                    "user": accounts.sender.to_string(),
+                    "token_amount": sell.data.token_amount,
+                    "collateral_amount": sell.data.collateral_amount,
+                    "fixed_side": sell.data.fixed_side,
+                    "slippage_bps": sell.data.slippage_bps
+                }), vec![(accounts.mint, LaunchSignal::Trade)])
+            }
+            MoonshotInstruction::MigrateFunds(_) => {
+                // Moonshot completes the curve and moves the funds to the
+                // target DEX in a single instruction.
+                let Some(accounts) = check_layout::<MigrateFunds>(
+                    "MigrateFunds",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    12..=12,
+                    |_| Vec::new(),
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                ("new_pool", json!({
+                    "type": "MigrateFunds",
+                    "mint": accounts.mint.to_string(),
+                    "curve": accounts.curve_account.to_string()
+                }), vec![
+                    (accounts.mint, LaunchSignal::Graduated),
+                    (accounts.mint, LaunchSignal::Migrated { pool: None }),
+                ])
+            }
+            _ => return Ok(()),
+        };
+
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(transaction);
+        }
+        if let Some(side) = trade_side {
+            side.apply(&mut details);
+        }
+        if let Some(sol_legs) = &sol_legs {
+            sol_legs.apply(&mut details);
+        }
+        if let Some((symbol, mint)) = &token_symbol {
+            if self.symbols.annotate(symbol, mint, &mut details) {
+                log::warn!("{} token {} imitates the symbol {}", platform, mint, symbol);
+                metrics
+                    .increment_counter("symbol_collisions", 1)
+                    .await
+                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+            }
+        }
+        ui_amounts.apply(&mut details);
+
+        self.common_process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details)
+            .await?;
+
+        for (mint, signal) in funnel {
+            for transition in self.launch_funnel.record(&platform, &mint, signal, transaction) {
+                self.common_process_event("launch_funnel", platform.clone(), signature.clone(), timestamp.clone(), transition)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Wallet behind each published instruction. Migrations are signed by the
+/// Moonshot backend, not a trader.
+fn trader(instruction: &MoonshotInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<solana_pubkey::Pubkey> {
+    match instruction {
+        MoonshotInstruction::TokenMint(_) | MoonshotInstruction::Buy(_) | MoonshotInstruction::Sell(_) => {
+            TraderAccount::At(0).find(accounts)
+        }
+        _ => None,
+    }
+}
+
+impl CommonProcessor for MoonshotProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
@@ -0,0 +1,40 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        account::AccountProcessorInputType, error::CarbonResult, metrics::MetricsCollection,
+        processor::Processor,
+    },
+    std::sync::Arc,
+};
+
+use crate::{analytics::usd_prices::UsdPrices, decoders::oracle::OraclePrice};
+
+/// Caches the prices of the oracle feeds swaps are valued with. Nothing is
+/// published.
+pub struct OraclePriceProcessor {
+    usd_prices: UsdPrices,
+}
+
+impl OraclePriceProcessor {
+    pub fn new(usd_prices: UsdPrices) -> Self {
+        Self { usd_prices }
+    }
+}
+
+#[async_trait]
+impl Processor for OraclePriceProcessor {
+    type InputType = AccountProcessorInputType<OraclePrice>;
+
+    async fn process(
+        &mut self,
+        (metadata, account, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        self.usd_prices.update(&metadata.pubkey, account.data);
+        metrics
+            .increment_counter("oracle_price_updates", 1)
+            .await
+            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        Ok(())
+    }
+}
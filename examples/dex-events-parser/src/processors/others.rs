@@ -6,30 +6,88 @@ use {
         metrics::MetricsCollection,
         processor::Processor,
     },
-    std::{sync::Arc, time::SystemTime},
+    std::sync::Arc,
     serde_json::json,
 };
 
-use carbon_raydium_cpmm_decoder::instructions::RaydiumCpmmInstruction;
-use carbon_jupiter_swap_decoder::instructions::JupiterSwapInstruction;
-use carbon_orca_whirlpool_decoder::instructions::OrcaWhirlpoolInstruction;
-use carbon_meteora_dlmm_decoder::instructions::MeteoraDlmmInstruction;
-use carbon_openbook_v2_decoder::instructions::OpenbookV2Instruction;
-use carbon_phoenix_v1_decoder::instructions::PhoenixInstruction;
-use carbon_fluxbeam_decoder::instructions::FluxbeamInstruction;
-use carbon_lifinity_amm_v2_decoder::instructions::LifinityAmmV2Instruction;
-use carbon_moonshot_decoder::instructions::MoonshotInstruction;
+use carbon_raydium_cpmm_decoder::instructions::{
+    initialize::Initialize as CpmmInitialize, swap_base_input::SwapBaseInput, swap_base_output::SwapBaseOutput,
+    RaydiumCpmmInstruction,
+};
+use carbon_core::deserialize::ArrangeAccounts;
+use carbon_orca_whirlpool_decoder::instructions::{
+    initialize_pool::InitializePool, swap::Swap as WhirlpoolSwap, OrcaWhirlpoolInstruction,
+};
+use carbon_meteora_dlmm_decoder::instructions::{
+    initialize_lb_pair::InitializeLbPair, swap::Swap as DlmmSwap, MeteoraDlmmInstruction,
+};
+use carbon_meteora_pools_decoder::instructions::{
+    add_balance_liquidity::AddBalanceLiquidity,
+    initialize_customizable_permissionless_constant_product_pool::InitializeCustomizablePermissionlessConstantProductPool,
+    initialize_permissionless_constant_product_pool_with_config::InitializePermissionlessConstantProductPoolWithConfig,
+    initialize_permissionless_constant_product_pool_with_config2::InitializePermissionlessConstantProductPoolWithConfig2,
+    initialize_permissionless_pool::InitializePermissionlessPool, remove_balance_liquidity::RemoveBalanceLiquidity,
+    swap::Swap as MeteoraPoolsSwap, MeteoraPoolsProgramInstruction,
+};
+use carbon_openbook_v2_decoder::{
+    instructions::{place_take_order::PlaceTakeOrder, OpenbookV2Instruction},
+    types::Side as OpenbookSide,
+};
+use carbon_phoenix_v1_decoder::{
+    instructions::{swap::Swap as PhoenixSwap, PhoenixInstruction},
+    types::{OrderPacket, Side as PhoenixSide},
+};
+use carbon_lifinity_amm_v2_decoder::instructions::{swap::Swap as LifinitySwap, LifinityAmmV2Instruction};
+use carbon_core::transaction::TransactionMetadata;
 
-use crate::{DexEvent, publishers::{DexEventData, UnifiedPublisher, Publisher}};
+use crate::{
+    alerts::whale::WhaleAlerts,
+    analytics::{
+        decimals::UiAmounts,
+        jito::jito_tip_annotation,
+        new_pair::NewPairEvent,
+        participation::ParticipationTracker,
+        pool_registry::PoolRegistry,
+        pool_reserves::PoolReserves,
+        price_index::{PriceIndex, SwapFill},
+        side::{Side, TradeSide},
+        slippage::{observed_slippage, QuoteSource, SwapMode},
+        swap_event::SwapEvent,
+        trader::TraderAccount,
+        transfers::SwapTransfers,
+        usd_prices::UsdPrices,
+    },
+    datasources::discovery::PoolDiscovery,
+    publishers::UnifiedPublisher,
+};
+
+use super::{
+    layout::{check_layout, AccountRole},
+    CommonProcessor, EventTime,
+};
 
 // Raydium CPMM Processor
 pub struct RaydiumCpmmProcessor {
     publisher: UnifiedPublisher,
+    price_index: PriceIndex,
+    whale_alerts: WhaleAlerts,
+    participation: ParticipationTracker,
+    usd_prices: UsdPrices,
+    pool_registry: PoolRegistry,
+    pool_reserves: PoolReserves,
 }
 
 impl RaydiumCpmmProcessor {
-    pub fn new(publisher: UnifiedPublisher) -> Self {
-        Self { publisher }
+    pub fn new(
+        publisher: UnifiedPublisher,
+        price_index: PriceIndex,
+        whale_alerts: WhaleAlerts,
+        participation: ParticipationTracker,
+        usd_prices: UsdPrices,
+        pool_registry: PoolRegistry,
+        pool_reserves: PoolReserves,
+    ) -> Self {
+        Self { publisher, price_index, whale_alerts, participation, usd_prices, pool_registry, pool_reserves }
     }
 }
 
@@ -44,96 +102,200 @@ impl Processor for RaydiumCpmmProcessor {
 
     async fn process(
         &mut self,
-        (metadata, instruction, _, _): Self::InputType,
-        _metrics: Arc<MetricsCollection>,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
         let signature = metadata.transaction_metadata.signature.to_string();
         let platform = "Raydium CPMM".to_string();
-        let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+        let timestamp = EventTime::now(&metadata).with_trader(cpmm_trader(&instruction.data, &instruction.accounts));
+        let routed = timestamp.via_aggregator.is_some();
 
-        let (event_type, details) = match instruction.data {
+        let mut fill = None;
+        let mut transfers = None;
+        let mut ui_amounts = UiAmounts::new(&metadata.transaction_metadata);
+        let mut decode_warnings = Vec::new();
+        let mut trade = None;
+        let mut new_pair = None;
+        let (event_type, mut details) = match instruction.data {
             RaydiumCpmmInstruction::SwapBaseInput(swap) => {
+                let slippage = check_layout::<SwapBaseInput>(
+                    "SwapBaseInput",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    13..=13,
+                    |a| vec![
+                        ("payer", a.payer, AccountRole::Signer),
+                        ("input_token_account", a.input_token_account, AccountRole::Writable),
+                        ("output_token_account", a.output_token_account, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                    .map(|accounts| {
+                        trade = Some((accounts.pool_state, accounts.payer));
+                        fill = self.price_index.observe(
+                            &platform,
+                            &metadata.transaction_metadata,
+                            &accounts.input_token_account,
+                            &accounts.output_token_account,
+                        );
+                        transfers = SwapTransfers::find(
+                            &metadata.transaction_metadata,
+                            &nested,
+                            &accounts.input_token_account,
+                            &accounts.output_token_account,
+                        );
+                        ui_amounts.token_account("amount_in", &accounts.input_token_account);
+                        ui_amounts.token_account("minimum_amount_out", &accounts.output_token_account);
+                        observed_slippage(
+                            &metadata.transaction_metadata,
+                            SwapMode::ExactIn,
+                            QuoteSource::Limit,
+                            swap.minimum_amount_out,
+                            &accounts.output_token_account,
+                        )
+                    })
+                    .unwrap_or_default();
                 ("swap", json!({
                     "type": "SwapBaseInput",
                     "amount_in": swap.amount_in,
-                    "minimum_amount_out": swap.minimum_amount_out
+                    "minimum_amount_out": swap.minimum_amount_out,
+                    "slippage": slippage
                 }))
             }
             RaydiumCpmmInstruction::SwapBaseOutput(swap) => {
+                let slippage = check_layout::<SwapBaseOutput>(
+                    "SwapBaseOutput",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    13..=13,
+                    |a| vec![
+                        ("payer", a.payer, AccountRole::Signer),
+                        ("input_token_account", a.input_token_account, AccountRole::Writable),
+                        ("output_token_account", a.output_token_account, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                    .map(|accounts| {
+                        trade = Some((accounts.pool_state, accounts.payer));
+                        fill = self.price_index.observe(
+                            &platform,
+                            &metadata.transaction_metadata,
+                            &accounts.input_token_account,
+                            &accounts.output_token_account,
+                        );
+                        transfers = SwapTransfers::find(
+                            &metadata.transaction_metadata,
+                            &nested,
+                            &accounts.input_token_account,
+                            &accounts.output_token_account,
+                        );
+                        ui_amounts.token_account("max_amount_in", &accounts.input_token_account);
+                        ui_amounts.token_account("amount_out", &accounts.output_token_account);
+                        observed_slippage(
+                            &metadata.transaction_metadata,
+                            SwapMode::ExactOut,
+                            QuoteSource::Limit,
+                            swap.max_amount_in,
+                            &accounts.input_token_account,
+                        )
+                    })
+                    .unwrap_or_default();
                 ("swap", json!({
                     "type": "SwapBaseOutput",
                     "max_amount_in": swap.max_amount_in,
-                    "amount_out": swap.amount_out
+                    "amount_out": swap.amount_out,
+                    "slippage": slippage
                 }))
             }
+            RaydiumCpmmInstruction::Initialize(init) => {
+                let mut details = json!({
+                    "type": "Initialize",
+                    "init_amount0": init.init_amount0,
+                    "init_amount1": init.init_amount1,
+                    "open_time": init.open_time
+                });
+                if let Some(accounts) = CpmmInitialize::arrange_accounts(&instruction.accounts) {
+                    new_pair = Some(
+                        NewPairEvent::new(
+                            accounts.pool_state,
+                            (accounts.token0_mint, Some(accounts.token0_vault)),
+                            (accounts.token1_mint, Some(accounts.token1_vault)),
+                            Some(accounts.creator),
+                        )
+                        .with_reported_amounts(init.init_amount0, init.init_amount1),
+                    );
+                    ui_amounts.mint("init_amount0", accounts.token0_mint);
+                    ui_amounts.mint("init_amount1", accounts.token1_mint);
+                    details["pool"] = json!(accounts.pool_state.to_string());
+                    details["base_mint"] = json!(accounts.token0_mint.to_string());
+                    details["quote_mint"] = json!(accounts.token1_mint.to_string());
+                    details["lp_mint"] = json!(accounts.lp_mint.to_string());
+                }
+                ("new_pool", details)
+            }
             _ => return Ok(()),
         };
 
-        self.process_event(event_type, platform, signature, timestamp, details).await
-    }
-}
-
-// Jupiter Swap Processor
-pub struct JupiterSwapProcessor {
-    publisher: UnifiedPublisher,
-}
-
-impl JupiterSwapProcessor {
-    pub fn new(publisher: UnifiedPublisher) -> Self {
-        Self { publisher }
-    }
-}
-
-#[async_trait]
-impl Processor for JupiterSwapProcessor {
-    type InputType = (
-        InstructionMetadata,
-        DecodedInstruction<JupiterSwapInstruction>,
-        NestedInstructions,
-        solana_instruction::Instruction,
-    );
-
-    async fn process(
-        &mut self,
-        (metadata, instruction, _, _): Self::InputType,
-        _metrics: Arc<MetricsCollection>,
-    ) -> CarbonResult<()> {
-        let signature = metadata.transaction_metadata.signature.to_string();
-        let platform = "Jupiter Swap".to_string();
-        let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(&metadata.transaction_metadata);
+        }
+        if let Some(side) = fill.as_ref().and_then(|fill| TradeSide::from_fill(fill, None)) {
+            side.apply(&mut details);
+        }
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        let executed = transfers.as_ref().and_then(SwapTransfers::fill).or_else(|| fill.clone());
+        if let (Some(executed), Some((pool, trader))) = (&executed, trade) {
+            let swap_event = SwapEvent::new(executed, Some(pool), Some(trader));
+            swap_event.apply(&metadata.transaction_metadata, &mut details);
+            self.usd_prices.apply(&metadata.transaction_metadata, &swap_event, &mut details);
+        }
+        ui_amounts.apply(&mut details);
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(&metadata.transaction_metadata, &mut details);
+        }
+        self.pool_reserves.observe(&metadata.transaction_metadata, &mut details).await;
 
-        let (event_type, details) = match instruction.data {
-            JupiterSwapInstruction::Route(route) => {
-                ("swap", json!({
-                    "type": "Route",
-                    "platform_fee_bps": route.platform_fee_bps,
-                    "in_amount": route.in_amount,
-                    "quoted_out_amount": route.quoted_out_amount
-                }))
-            }
-            JupiterSwapInstruction::ExactOutRoute(exact_out_route) => {
-                ("swap", json!({
-                    "type": "ExactOutRoute",
-                    "platform_fee_bps": exact_out_route.platform_fee_bps,
-                    "out_amount": exact_out_route.out_amount,
-                    "quoted_in_amount": exact_out_route.quoted_in_amount
-                }))
-            }
-            _ => return Ok(()),
-        };
+        self.process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details).await?;
+        if let (Some(fill), false) = (&fill, routed) {
+            self.whale_alerts.check(&platform, &metadata.transaction_metadata, None, fill).await;
+        }
+        if let Some((pool, trader)) = trade {
+            self.participation.record(&platform, pool, trader, &metadata.transaction_metadata);
+        }
+        self.publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+            .await?;
 
-        self.process_event(event_type, platform, signature, timestamp, details).await
+        Ok(())
     }
 }
 
 // Orca Whirlpool Processor
 pub struct OrcaWhirlpoolProcessor {
     publisher: UnifiedPublisher,
+    price_index: PriceIndex,
+    whale_alerts: WhaleAlerts,
+    discovery: PoolDiscovery,
+    participation: ParticipationTracker,
+    usd_prices: UsdPrices,
+    pool_registry: PoolRegistry,
+    pool_reserves: PoolReserves,
 }
 
 impl OrcaWhirlpoolProcessor {
-    pub fn new(publisher: UnifiedPublisher) -> Self {
-        Self { publisher }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        publisher: UnifiedPublisher,
+        price_index: PriceIndex,
+        whale_alerts: WhaleAlerts,
+        discovery: PoolDiscovery,
+        participation: ParticipationTracker,
+        usd_prices: UsdPrices,
+        pool_registry: PoolRegistry,
+        pool_reserves: PoolReserves,
+    ) -> Self {
+        Self { publisher, price_index, whale_alerts, discovery, participation, usd_prices, pool_registry, pool_reserves }
     }
 }
 
@@ -148,20 +310,88 @@ impl Processor for OrcaWhirlpoolProcessor {
 
     async fn process(
         &mut self,
-        (metadata, instruction, _, _): Self::InputType,
-        _metrics: Arc<MetricsCollection>,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
         let signature = metadata.transaction_metadata.signature.to_string();
         let platform = "Orca Whirlpool".to_string();
-        let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+        let timestamp = EventTime::now(&metadata).with_trader(whirlpool_trader(&instruction.data, &instruction.accounts));
+        let routed = timestamp.via_aggregator.is_some();
 
-        let (event_type, details) = match instruction.data {
+        let mut fill = None;
+        let mut transfers = None;
+        let mut ui_amounts = UiAmounts::new(&metadata.transaction_metadata);
+        let mut decode_warnings = Vec::new();
+        let mut new_pool = None;
+        let mut new_pair = None;
+        let mut trade = None;
+        let mut venue_quote = None;
+        let (event_type, mut details) = match instruction.data {
             OrcaWhirlpoolInstruction::Swap(swap) => {
+                // The threshold bounds the output of exact-in swaps and the
+                // input of exact-out swaps; `a_to_b` picks the user account.
+                let slippage = check_layout::<WhirlpoolSwap>(
+                    "Swap",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    11..=11,
+                    |a| vec![
+                        ("token_authority", a.token_authority, AccountRole::Signer),
+                        ("token_owner_account_a", a.token_owner_account_a, AccountRole::Writable),
+                        ("token_owner_account_b", a.token_owner_account_b, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                    .map(|accounts| {
+                        trade = Some((accounts.whirlpool, accounts.token_authority));
+                        let (source, destination) = if swap.a_to_b {
+                            (accounts.token_owner_account_a, accounts.token_owner_account_b)
+                        } else {
+                            (accounts.token_owner_account_b, accounts.token_owner_account_a)
+                        };
+                        fill = self.price_index.observe(
+                            &platform,
+                            &metadata.transaction_metadata,
+                            &source,
+                            &destination,
+                        );
+                        transfers = SwapTransfers::find(&metadata.transaction_metadata, &nested, &source, &destination);
+                        // Token B is the quote token of a whirlpool.
+                        venue_quote = fill
+                            .as_ref()
+                            .map(|fill| if swap.a_to_b { fill.output_mint } else { fill.input_mint });
+                        // `amount` is on the input side of exact-in swaps and
+                        // the output side of exact-out swaps.
+                        if swap.amount_specified_is_input {
+                            ui_amounts.token_account("amount", &source);
+                            ui_amounts.token_account("other_amount_threshold", &destination);
+                        } else {
+                            ui_amounts.token_account("amount", &destination);
+                            ui_amounts.token_account("other_amount_threshold", &source);
+                        }
+                        let (mode, user_token_account) =
+                            match (swap.amount_specified_is_input, swap.a_to_b) {
+                                (true, true) => (SwapMode::ExactIn, accounts.token_owner_account_b),
+                                (true, false) => (SwapMode::ExactIn, accounts.token_owner_account_a),
+                                (false, true) => (SwapMode::ExactOut, accounts.token_owner_account_a),
+                                (false, false) => (SwapMode::ExactOut, accounts.token_owner_account_b),
+                            };
+                        observed_slippage(
+                            &metadata.transaction_metadata,
+                            mode,
+                            QuoteSource::Limit,
+                            swap.other_amount_threshold,
+                            &user_token_account,
+                        )
+                    })
+                    .unwrap_or_default();
                 ("swap", json!({
                     "type": "Swap",
                     "amount": swap.amount,
                     "other_amount_threshold": swap.other_amount_threshold,
-                    "sqrt_price_limit": swap.sqrt_price_limit
+                    "sqrt_price_limit": swap.sqrt_price_limit,
+                    "amount_specified_is_input": swap.amount_specified_is_input,
+                    "slippage": slippage
                 }))
             }
             OrcaWhirlpoolInstruction::IncreaseLiquidity(increase) => {
@@ -183,27 +413,89 @@ impl Processor for OrcaWhirlpoolProcessor {
                 }))
             }
             OrcaWhirlpoolInstruction::InitializePool(init) => {
-                ("new_pool", json!({
+                let mut details = json!({
                     "type": "InitializePool",
                     "tick_spacing": init.tick_spacing,
                     "initial_sqrt_price": init.initial_sqrt_price
-                }))
+                });
+                if let Some(accounts) = InitializePool::arrange_accounts(&instruction.accounts) {
+                    new_pool = Some((accounts.whirlpool, accounts.token_mint_a, accounts.token_mint_b));
+                    new_pair = Some(NewPairEvent::new(
+                        accounts.whirlpool,
+                        (accounts.token_mint_a, Some(accounts.token_vault_a)),
+                        (accounts.token_mint_b, Some(accounts.token_vault_b)),
+                        Some(accounts.funder),
+                    ));
+                    details["pool"] = json!(accounts.whirlpool.to_string());
+                    details["base_mint"] = json!(accounts.token_mint_a.to_string());
+                    details["quote_mint"] = json!(accounts.token_mint_b.to_string());
+                }
+                ("new_pool", details)
             }
             _ => return Ok(()),
         };
 
-        self.process_event(event_type, platform, signature, timestamp, details).await
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(&metadata.transaction_metadata);
+        }
+        if let Some(side) = fill.as_ref().and_then(|fill| TradeSide::from_fill(fill, venue_quote)) {
+            side.apply(&mut details);
+        }
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        let executed = transfers.as_ref().and_then(SwapTransfers::fill).or_else(|| fill.clone());
+        if let (Some(executed), Some((pool, trader))) = (&executed, trade) {
+            let swap_event = SwapEvent::new(executed, Some(pool), Some(trader));
+            swap_event.apply(&metadata.transaction_metadata, &mut details);
+            self.usd_prices.apply(&metadata.transaction_metadata, &swap_event, &mut details);
+        }
+        ui_amounts.apply(&mut details);
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(&metadata.transaction_metadata, &mut details);
+        }
+        self.pool_reserves.observe(&metadata.transaction_metadata, &mut details).await;
+
+        self.process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details).await?;
+        if let (Some(fill), false) = (&fill, routed) {
+            self.whale_alerts.check(&platform, &metadata.transaction_metadata, None, fill).await;
+        }
+        if let Some((pool, trader)) = trade {
+            self.participation.record(&platform, pool, trader, &metadata.transaction_metadata);
+        }
+        self.publish_decode_warnings(&platform, &signature, timestamp.clone(), decode_warnings, &metrics)
+            .await?;
+        if let Some(tracking) = new_pool.and_then(|(pool, base_mint, quote_mint)| {
+            self.discovery.discover(&platform, pool, base_mint, quote_mint)
+        }) {
+            self.common_process_event("tracking_started", platform.clone(), signature.clone(), timestamp, tracking)
+                .await?;
+        }
+
+        Ok(())
     }
 }
 
 // Meteora DLMM Processor
 pub struct MeteoraDlmmProcessor {
     publisher: UnifiedPublisher,
+    discovery: PoolDiscovery,
+    participation: ParticipationTracker,
+    usd_prices: UsdPrices,
+    pool_registry: PoolRegistry,
+    pool_reserves: PoolReserves,
 }
 
 impl MeteoraDlmmProcessor {
-    pub fn new(publisher: UnifiedPublisher) -> Self {
-        Self { publisher }
+    pub fn new(
+        publisher: UnifiedPublisher,
+        discovery: PoolDiscovery,
+        participation: ParticipationTracker,
+        usd_prices: UsdPrices,
+        pool_registry: PoolRegistry,
+        pool_reserves: PoolReserves,
+    ) -> Self {
+        Self { publisher, discovery, participation, usd_prices, pool_registry, pool_reserves }
     }
 }
 
@@ -218,15 +510,39 @@ impl Processor for MeteoraDlmmProcessor {
 
     async fn process(
         &mut self,
-        (metadata, instruction, _, _): Self::InputType,
+        (metadata, instruction, nested, _): Self::InputType,
         _metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
         let signature = metadata.transaction_metadata.signature.to_string();
         let platform = "Meteora DLMM".to_string();
-        let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+        let timestamp = EventTime::now(&metadata).with_trader(dlmm_trader(&instruction.data, &instruction.accounts));
 
-        let (event_type, details) = match instruction.data {
+        let mut new_pool = None;
+        let mut new_pair = None;
+        let mut trade = None;
+        let mut fill = None;
+        let mut transfers = None;
+        let mut trade_side = None;
+        let (event_type, mut details) = match instruction.data {
             MeteoraDlmmInstruction::Swap(swap) => {
+                if let Some(accounts) = DlmmSwap::arrange_accounts(&instruction.accounts) {
+                    trade = Some((accounts.lb_pair, accounts.user));
+                    fill = SwapFill::from_balance_changes(
+                        &metadata.transaction_metadata,
+                        &accounts.user_token_in,
+                        &accounts.user_token_out,
+                    );
+                    transfers = SwapTransfers::find(
+                        &metadata.transaction_metadata,
+                        &nested,
+                        &accounts.user_token_in,
+                        &accounts.user_token_out,
+                    );
+                    // Token Y is the quote token of an LB pair.
+                    trade_side = fill
+                        .as_ref()
+                        .and_then(|fill| TradeSide::from_fill(fill, Some(accounts.token_y_mint)));
+                }
                 ("swap", json!({
                     "type": "Swap",
                     "amount_in": swap.amount_in
@@ -247,22 +563,314 @@ impl Processor for MeteoraDlmmProcessor {
                 }))
             }
             MeteoraDlmmInstruction::InitializeLbPair(init) => {
-                ("new_pool", json!({
+                let mut details = json!({
                     "type": "InitializeLbPair",
                     "active_id": init.active_id,
                     "bin_step": init.bin_step
+                });
+                if let Some(accounts) = InitializeLbPair::arrange_accounts(&instruction.accounts) {
+                    new_pool = Some((accounts.lb_pair, accounts.token_mint_x, accounts.token_mint_y));
+                    new_pair = Some(NewPairEvent::new(
+                        accounts.lb_pair,
+                        (accounts.token_mint_x, Some(accounts.reserve_x)),
+                        (accounts.token_mint_y, Some(accounts.reserve_y)),
+                        Some(accounts.funder),
+                    ));
+                    details["pool"] = json!(accounts.lb_pair.to_string());
+                    details["base_mint"] = json!(accounts.token_mint_x.to_string());
+                    details["quote_mint"] = json!(accounts.token_mint_y.to_string());
+                }
+                ("new_pool", details)
+            }
+            _ => return Ok(()),
+        };
+
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(&metadata.transaction_metadata);
+        }
+        if let Some(side) = trade_side {
+            side.apply(&mut details);
+        }
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        let executed = transfers.as_ref().and_then(SwapTransfers::fill).or_else(|| fill.clone());
+        if let (Some(executed), Some((pool, trader))) = (&executed, trade) {
+            let swap_event = SwapEvent::new(executed, Some(pool), Some(trader));
+            swap_event.apply(&metadata.transaction_metadata, &mut details);
+            self.usd_prices.apply(&metadata.transaction_metadata, &swap_event, &mut details);
+        }
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(&metadata.transaction_metadata, &mut details);
+        }
+        self.pool_reserves.observe(&metadata.transaction_metadata, &mut details).await;
+
+        self.process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details).await?;
+        if let Some((pool, trader)) = trade {
+            self.participation.record(&platform, pool, trader, &metadata.transaction_metadata);
+        }
+        if let Some(tracking) = new_pool.and_then(|(pool, base_mint, quote_mint)| {
+            self.discovery.discover(&platform, pool, base_mint, quote_mint)
+        }) {
+            self.common_process_event("tracking_started", platform.clone(), signature.clone(), timestamp, tracking)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+// Meteora Dynamic AMM (Pools) Processor
+pub struct MeteoraPoolsProcessor {
+    publisher: UnifiedPublisher,
+    price_index: PriceIndex,
+    whale_alerts: WhaleAlerts,
+    discovery: PoolDiscovery,
+    participation: ParticipationTracker,
+    usd_prices: UsdPrices,
+}
+
+impl MeteoraPoolsProcessor {
+    pub fn new(
+        publisher: UnifiedPublisher,
+        price_index: PriceIndex,
+        whale_alerts: WhaleAlerts,
+        discovery: PoolDiscovery,
+        participation: ParticipationTracker,
+        usd_prices: UsdPrices,
+    ) -> Self {
+        Self { publisher, price_index, whale_alerts, discovery, participation, usd_prices }
+    }
+}
+
+#[async_trait]
+impl Processor for MeteoraPoolsProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<MeteoraPoolsProgramInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, nested, _): Self::InputType,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let signature = metadata.transaction_metadata.signature.to_string();
+        let platform = "Meteora Pools".to_string();
+        let timestamp =
+            EventTime::now(&metadata).with_trader(meteora_pools_trader(&instruction.data, &instruction.accounts));
+        let routed = timestamp.via_aggregator.is_some();
+
+        let mut new_pool = None;
+        let mut new_pair = None;
+        let mut trade = None;
+        let mut fill = None;
+        let mut transfers = None;
+        let mut ui_amounts = UiAmounts::new(&metadata.transaction_metadata);
+        let (event_type, mut details) = match instruction.data {
+            MeteoraPoolsProgramInstruction::Swap(swap) => {
+                if let Some(accounts) = MeteoraPoolsSwap::arrange_accounts(&instruction.accounts) {
+                    trade = Some((accounts.pool, accounts.user));
+                    fill = self.price_index.observe(
+                        &platform,
+                        &metadata.transaction_metadata,
+                        &accounts.user_source_token,
+                        &accounts.user_destination_token,
+                    );
+                    transfers = SwapTransfers::find(
+                        &metadata.transaction_metadata,
+                        &nested,
+                        &accounts.user_source_token,
+                        &accounts.user_destination_token,
+                    );
+                    ui_amounts.token_account("in_amount", &accounts.user_source_token);
+                    ui_amounts.token_account("minimum_out_amount", &accounts.user_destination_token);
+                }
+                ("swap", json!({
+                    "type": "Swap",
+                    "in_amount": swap.in_amount,
+                    "minimum_out_amount": swap.minimum_out_amount
                 }))
             }
+            MeteoraPoolsProgramInstruction::AddBalanceLiquidity(add) => {
+                let mut details = json!({
+                    "type": "add",
+                    "action": "AddBalanceLiquidity",
+                    "pool_token_amount": add.pool_token_amount,
+                    "maximum_token_a_amount": add.maximum_token_a_amount,
+                    "maximum_token_b_amount": add.maximum_token_b_amount
+                });
+                if let Some(accounts) = AddBalanceLiquidity::arrange_accounts(&instruction.accounts) {
+                    ui_amounts.mint("pool_token_amount", accounts.lp_mint);
+                    ui_amounts.token_account("maximum_token_a_amount", &accounts.user_a_token);
+                    ui_amounts.token_account("maximum_token_b_amount", &accounts.user_b_token);
+                    details["pool"] = json!(accounts.pool.to_string());
+                    details["user"] = json!(accounts.user.to_string());
+                }
+                ("liquidity", details)
+            }
+            MeteoraPoolsProgramInstruction::RemoveBalanceLiquidity(remove) => {
+                let mut details = json!({
+                    "type": "remove",
+                    "action": "RemoveBalanceLiquidity",
+                    "pool_token_amount": remove.pool_token_amount,
+                    "minimum_a_token_out": remove.minimum_a_token_out,
+                    "minimum_b_token_out": remove.minimum_b_token_out
+                });
+                if let Some(accounts) = RemoveBalanceLiquidity::arrange_accounts(&instruction.accounts) {
+                    ui_amounts.mint("pool_token_amount", accounts.lp_mint);
+                    ui_amounts.token_account("minimum_a_token_out", &accounts.user_a_token);
+                    ui_amounts.token_account("minimum_b_token_out", &accounts.user_b_token);
+                    details["pool"] = json!(accounts.pool.to_string());
+                    details["user"] = json!(accounts.user.to_string());
+                }
+                ("liquidity", details)
+            }
+            MeteoraPoolsProgramInstruction::InitializePermissionlessPool(init) => {
+                let accounts = InitializePermissionlessPool::arrange_accounts(&instruction.accounts).map(|a| {
+                    (a.pool, a.lp_mint, a.token_a_mint, a.token_b_mint, a.payer)
+                });
+                ("new_pool", meteora_pools_initialize(
+                    "InitializePermissionlessPool",
+                    init.token_a_amount,
+                    init.token_b_amount,
+                    accounts,
+                    &mut new_pool,
+                    &mut new_pair,
+                    &mut ui_amounts,
+                ))
+            }
+            MeteoraPoolsProgramInstruction::InitializePermissionlessConstantProductPoolWithConfig(init) => {
+                let accounts =
+                    InitializePermissionlessConstantProductPoolWithConfig::arrange_accounts(&instruction.accounts)
+                        .map(|a| (a.pool, a.lp_mint, a.token_a_mint, a.token_b_mint, a.payer));
+                ("new_pool", meteora_pools_initialize(
+                    "InitializePermissionlessConstantProductPoolWithConfig",
+                    init.token_a_amount,
+                    init.token_b_amount,
+                    accounts,
+                    &mut new_pool,
+                    &mut new_pair,
+                    &mut ui_amounts,
+                ))
+            }
+            MeteoraPoolsProgramInstruction::InitializePermissionlessConstantProductPoolWithConfig2(init) => {
+                let accounts =
+                    InitializePermissionlessConstantProductPoolWithConfig2::arrange_accounts(&instruction.accounts)
+                        .map(|a| (a.pool, a.lp_mint, a.token_a_mint, a.token_b_mint, a.payer));
+                ("new_pool", meteora_pools_initialize(
+                    "InitializePermissionlessConstantProductPoolWithConfig2",
+                    init.token_a_amount,
+                    init.token_b_amount,
+                    accounts,
+                    &mut new_pool,
+                    &mut new_pair,
+                    &mut ui_amounts,
+                ))
+            }
+            MeteoraPoolsProgramInstruction::InitializeCustomizablePermissionlessConstantProductPool(init) => {
+                let accounts =
+                    InitializeCustomizablePermissionlessConstantProductPool::arrange_accounts(&instruction.accounts)
+                        .map(|a| (a.pool, a.lp_mint, a.token_a_mint, a.token_b_mint, a.payer));
+                ("new_pool", meteora_pools_initialize(
+                    "InitializeCustomizablePermissionlessConstantProductPool",
+                    init.token_a_amount,
+                    init.token_b_amount,
+                    accounts,
+                    &mut new_pool,
+                    &mut new_pair,
+                    &mut ui_amounts,
+                ))
+            }
             _ => return Ok(()),
         };
 
-        self.process_event(event_type, platform, signature, timestamp, details).await
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(&metadata.transaction_metadata);
+        }
+        if let Some(side) = fill.as_ref().and_then(|fill| TradeSide::from_fill(fill, None)) {
+            side.apply(&mut details);
+        }
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        let executed = transfers.as_ref().and_then(SwapTransfers::fill).or_else(|| fill.clone());
+        if let (Some(executed), Some((pool, trader))) = (&executed, trade) {
+            let swap_event = SwapEvent::new(executed, Some(pool), Some(trader));
+            swap_event.apply(&metadata.transaction_metadata, &mut details);
+            self.usd_prices.apply(&metadata.transaction_metadata, &swap_event, &mut details);
+        }
+        ui_amounts.apply(&mut details);
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(&metadata.transaction_metadata, &mut details);
+        }
+
+        self.process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details).await?;
+        if let (Some(fill), false) = (&fill, routed) {
+            self.whale_alerts.check(&platform, &metadata.transaction_metadata, None, fill).await;
+        }
+        if let Some((pool, trader)) = trade {
+            self.participation.record(&platform, pool, trader, &metadata.transaction_metadata);
+        }
+        if let Some(tracking) = new_pool.and_then(|(pool, base_mint, quote_mint)| {
+            self.discovery.discover(&platform, pool, base_mint, quote_mint)
+        }) {
+            self.common_process_event("tracking_started", platform.clone(), signature.clone(), timestamp, tracking)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Details of the pool creation instructions of Meteora Pools, which share
+/// their accounts: pool, LP mint, token A and B mints and payer.
+///
+/// The pool's tokens are held by Meteora vaults shared with every other pool
+/// of the same mint, so the initial liquidity is the amounts the creator
+/// deposited rather than a vault balance.
+fn meteora_pools_initialize(
+    instruction: &str,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    accounts: Option<(
+        solana_pubkey::Pubkey,
+        solana_pubkey::Pubkey,
+        solana_pubkey::Pubkey,
+        solana_pubkey::Pubkey,
+        solana_pubkey::Pubkey,
+    )>,
+    new_pool: &mut Option<(solana_pubkey::Pubkey, solana_pubkey::Pubkey, solana_pubkey::Pubkey)>,
+    new_pair: &mut Option<NewPairEvent>,
+    ui_amounts: &mut UiAmounts,
+) -> serde_json::Value {
+    let mut details = json!({
+        "type": instruction,
+        "token_a_amount": token_a_amount,
+        "token_b_amount": token_b_amount
+    });
+    if let Some((pool, lp_mint, token_a_mint, token_b_mint, payer)) = accounts {
+        *new_pool = Some((pool, token_a_mint, token_b_mint));
+        *new_pair = Some(
+            NewPairEvent::new(pool, (token_a_mint, None), (token_b_mint, None), Some(payer))
+                .with_reported_amounts(token_a_amount, token_b_amount),
+        );
+        ui_amounts.mint("token_a_amount", token_a_mint);
+        ui_amounts.mint("token_b_amount", token_b_mint);
+        details["pool"] = json!(pool.to_string());
+        details["base_mint"] = json!(token_a_mint.to_string());
+        details["quote_mint"] = json!(token_b_mint.to_string());
+        details["lp_mint"] = json!(lp_mint.to_string());
+        details["creator"] = json!(payer.to_string());
     }
+    details
 }
 
 // Các processors khác tương tự...
 macro_rules! simple_processor {
-    ($name:ident, $instruction_type:ty, $platform_name:expr) => {
+    ($name:ident, $instruction_type:ty, $platform_name:expr, $trade_side:expr, $trader:expr) => {
         pub struct $name {
             publisher: UnifiedPublisher,
         }
@@ -289,11 +897,18 @@ macro_rules! simple_processor {
             ) -> CarbonResult<()> {
                 let signature = metadata.transaction_metadata.signature.to_string();
                 let platform = $platform_name.to_string();
-                let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+                let trader: fn(&DecodedInstruction<$instruction_type>) -> Option<solana_pubkey::Pubkey> = $trader;
+                let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction));
                 
-                let details = json!({
-                    "instruction": format!("{:?}", instruction.data)
+                let mut details = json!({
+                    "instruction": format!("{:?}", instruction.data),
+                    "jito_tip": jito_tip_annotation(&metadata.transaction_metadata)
                 });
+                let trade_side: fn(&TransactionMetadata, &DecodedInstruction<$instruction_type>) -> Option<TradeSide> =
+                    $trade_side;
+                if let Some(side) = trade_side(&metadata.transaction_metadata, &instruction) {
+                    side.apply(&mut details);
+                }
 
                 self.process_event("swap", platform, signature, timestamp, details).await
             }
@@ -301,156 +916,180 @@ macro_rules! simple_processor {
     };
 }
 
-simple_processor!(OpenbookV2Processor, OpenbookV2Instruction, "OpenBook V2");
-simple_processor!(PhoenixProcessor, PhoenixInstruction, "Phoenix V1");
-simple_processor!(FluxbeamProcessor, FluxbeamInstruction, "Fluxbeam");
-simple_processor!(LifinityAmmV2Processor, LifinityAmmV2Instruction, "Lifinity AMM V2");
-simple_processor!(MoonshotProcessor, MoonshotInstruction, "Moonshot");
+simple_processor!(OpenbookV2Processor, OpenbookV2Instruction, "OpenBook V2", |transaction, instruction| {
+    let OpenbookV2Instruction::PlaceTakeOrder(take) = &instruction.data else {
+        return None;
+    };
+    let accounts = PlaceTakeOrder::arrange_accounts(&instruction.accounts)?;
+    let side = match take.args.side {
+        OpenbookSide::Bid => Side::Buy,
+        OpenbookSide::Ask => Side::Sell,
+    };
+    TradeSide::from_order_accounts(transaction, side, &accounts.user_base_account, &accounts.user_quote_account)
+}, |instruction| match instruction.data {
+    OpenbookV2Instruction::PlaceTakeOrder(_) => TraderAccount::At(0).find(&instruction.accounts),
+    _ => None,
+});
+simple_processor!(PhoenixProcessor, PhoenixInstruction, "Phoenix V1", |transaction, instruction| {
+    let PhoenixInstruction::Swap(swap) = &instruction.data else {
+        return None;
+    };
+    let accounts = PhoenixSwap::arrange_accounts(&instruction.accounts)?;
+    let side = match &swap.order_packet {
+        OrderPacket::PostOnly { side, .. }
+        | OrderPacket::Limit { side, .. }
+        | OrderPacket::ImmediateOrCancel { side, .. } => side,
+    };
+    let side = match side {
+        PhoenixSide::Bid => Side::Buy,
+        PhoenixSide::Ask => Side::Sell,
+    };
+    TradeSide::from_order_accounts(transaction, side, &accounts.base_account, &accounts.quote_account)
+}, |instruction| match instruction.data {
+    PhoenixInstruction::Swap(_) => TraderAccount::At(3).find(&instruction.accounts),
+    _ => None,
+});
+simple_processor!(LifinityAmmV2Processor, LifinityAmmV2Instruction, "Lifinity AMM V2", |transaction, instruction| {
+    let LifinityAmmV2Instruction::Swap(_) = &instruction.data else {
+        return None;
+    };
+    let accounts = LifinitySwap::arrange_accounts(&instruction.accounts)?;
+    let fill = SwapFill::from_balance_changes(transaction, &accounts.source_info, &accounts.destination_info)?;
+    TradeSide::from_fill(&fill, None)
+}, |instruction| match instruction.data {
+    LifinityAmmV2Instruction::Swap(_) => TraderAccount::At(2).find(&instruction.accounts),
+    _ => None,
+});
 
-// Shared helper implementation for all processors
-impl RaydiumCpmmProcessor {
-    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: u64, details: serde_json::Value) -> CarbonResult<()> {
-        self.common_process_event(event_type, platform, signature, timestamp, details).await
+/// Wallet behind each published instruction, the payer of swaps and the
+/// creator of new pools.
+fn cpmm_trader(instruction: &RaydiumCpmmInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<solana_pubkey::Pubkey> {
+    match instruction {
+        RaydiumCpmmInstruction::SwapBaseInput(_)
+        | RaydiumCpmmInstruction::SwapBaseOutput(_)
+        | RaydiumCpmmInstruction::Initialize(_) => TraderAccount::At(0).find(accounts),
+        _ => None,
     }
 }
 
-impl JupiterSwapProcessor {
-    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: u64, details: serde_json::Value) -> CarbonResult<()> {
-        self.common_process_event(event_type, platform, signature, timestamp, details).await
-    }
+/// Wallet behind each published instruction: the token authority of swaps,
+/// the position authority of liquidity changes and the funder of new pools.
+fn whirlpool_trader(instruction: &OrcaWhirlpoolInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<solana_pubkey::Pubkey> {
+    let account = match instruction {
+        OrcaWhirlpoolInstruction::Swap(_) => TraderAccount::At(1),
+        OrcaWhirlpoolInstruction::IncreaseLiquidity(_) | OrcaWhirlpoolInstruction::DecreaseLiquidity(_) => {
+            TraderAccount::At(2)
+        }
+        OrcaWhirlpoolInstruction::InitializePool(_) => TraderAccount::At(3),
+        _ => return None,
+    };
+    account.find(accounts)
 }
 
-impl OrcaWhirlpoolProcessor {
-    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: u64, details: serde_json::Value) -> CarbonResult<()> {
-        self.common_process_event(event_type, platform, signature, timestamp, details).await
-    }
+/// Wallet behind each published instruction: the user of swaps, the sender
+/// of liquidity changes and the funder of new pairs.
+fn dlmm_trader(instruction: &MeteoraDlmmInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<solana_pubkey::Pubkey> {
+    let account = match instruction {
+        MeteoraDlmmInstruction::Swap(_) => TraderAccount::At(10),
+        MeteoraDlmmInstruction::AddLiquidity(_) | MeteoraDlmmInstruction::RemoveLiquidity(_) => TraderAccount::At(11),
+        MeteoraDlmmInstruction::InitializeLbPair(_) => TraderAccount::At(8),
+        _ => return None,
+    };
+    account.find(accounts)
 }
 
-impl MeteoraDlmmProcessor {
-    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: u64, details: serde_json::Value) -> CarbonResult<()> {
+/// Wallet behind each published instruction: the user of swaps and
+/// liquidity changes and the payer of new pools.
+fn meteora_pools_trader(instruction: &MeteoraPoolsProgramInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<solana_pubkey::Pubkey> {
+    let account = match instruction {
+        MeteoraPoolsProgramInstruction::Swap(_) => TraderAccount::At(12),
+        MeteoraPoolsProgramInstruction::AddBalanceLiquidity(_) | MeteoraPoolsProgramInstruction::RemoveBalanceLiquidity(_) => {
+            TraderAccount::At(13)
+        }
+        MeteoraPoolsProgramInstruction::InitializePermissionlessPool(_)
+        | MeteoraPoolsProgramInstruction::InitializeCustomizablePermissionlessConstantProductPool(_) => TraderAccount::At(17),
+        MeteoraPoolsProgramInstruction::InitializePermissionlessConstantProductPoolWithConfig(_)
+        | MeteoraPoolsProgramInstruction::InitializePermissionlessConstantProductPoolWithConfig2(_) => TraderAccount::At(18),
+        _ => return None,
+    };
+    account.find(accounts)
+}
+
+// Shared helper implementation for all processors
+impl RaydiumCpmmProcessor {
+    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: EventTime, details: serde_json::Value) -> CarbonResult<()> {
         self.common_process_event(event_type, platform, signature, timestamp, details).await
     }
 }
 
-impl OpenbookV2Processor {
-    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: u64, details: serde_json::Value) -> CarbonResult<()> {
+impl OrcaWhirlpoolProcessor {
+    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: EventTime, details: serde_json::Value) -> CarbonResult<()> {
         self.common_process_event(event_type, platform, signature, timestamp, details).await
     }
 }
 
-impl PhoenixProcessor {
-    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: u64, details: serde_json::Value) -> CarbonResult<()> {
+impl MeteoraDlmmProcessor {
+    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: EventTime, details: serde_json::Value) -> CarbonResult<()> {
         self.common_process_event(event_type, platform, signature, timestamp, details).await
     }
 }
 
-impl FluxbeamProcessor {
-    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: u64, details: serde_json::Value) -> CarbonResult<()> {
+impl MeteoraPoolsProcessor {
+    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: EventTime, details: serde_json::Value) -> CarbonResult<()> {
         self.common_process_event(event_type, platform, signature, timestamp, details).await
     }
 }
 
-impl LifinityAmmV2Processor {
-    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: u64, details: serde_json::Value) -> CarbonResult<()> {
+impl OpenbookV2Processor {
+    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: EventTime, details: serde_json::Value) -> CarbonResult<()> {
         self.common_process_event(event_type, platform, signature, timestamp, details).await
     }
 }
 
-impl MoonshotProcessor {
-    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: u64, details: serde_json::Value) -> CarbonResult<()> {
+impl PhoenixProcessor {
+    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: EventTime, details: serde_json::Value) -> CarbonResult<()> {
         self.common_process_event(event_type, platform, signature, timestamp, details).await
     }
 }
 
-// Trait for common event processing
-trait CommonProcessor {
-    fn get_publisher(&self) -> &UnifiedPublisher;
-    
-    async fn common_process_event(&self, event_type: &str, platform: String, signature: String, timestamp: u64, details: serde_json::Value) -> CarbonResult<()> {
-        // Create DexEvent for logging
-        let event = match event_type {
-            "swap" => DexEvent::Swap {
-                platform: platform.clone(),
-                signature: signature.clone(),
-                details: details.to_string(),
-            },
-            "liquidity" => {
-                if details["type"] == "add" {
-                    DexEvent::AddLiquidity {
-                        platform: platform.clone(),
-                        signature: signature.clone(),
-                        details: details.to_string(),
-                    }
-                } else {
-                    DexEvent::RemoveLiquidity {
-                        platform: platform.clone(),
-                        signature: signature.clone(),
-                        details: details.to_string(),
-                    }
-                }
-            }
-            "new_pool" => DexEvent::AddPair {
-                platform: platform.clone(),
-                signature: signature.clone(),
-                details: details.to_string(),
-            },
-            _ => return Ok(()),
-        };
-
-        // Log the event
-        event.log();
-
-        // Create ZeroMQ event data
-        let zmq_data = DexEventData {
-            event_type: event_type.to_string(),
-            platform,
-            signature,
-            timestamp,
-            details,
-        };
-
-        // Publish to ZeroMQ
-        if let Err(e) = self.get_publisher().publish("dex_events", &zmq_data).await {
-            log::error!("Failed to publish to ZeroMQ: {}", e);
-        }
-
-        Ok(())
+impl LifinityAmmV2Processor {
+    async fn process_event(&self, event_type: &str, platform: String, signature: String, timestamp: EventTime, details: serde_json::Value) -> CarbonResult<()> {
+        self.common_process_event(event_type, platform, signature, timestamp, details).await
     }
 }
 
 // Implement the trait for all processors
 impl CommonProcessor for RaydiumCpmmProcessor {
     fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
-}
 
-impl CommonProcessor for JupiterSwapProcessor {
-    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+    fn pool_registry(&self) -> Option<&PoolRegistry> { Some(&self.pool_registry) }
 }
 
 impl CommonProcessor for OrcaWhirlpoolProcessor {
     fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+
+    fn pool_registry(&self) -> Option<&PoolRegistry> { Some(&self.pool_registry) }
 }
 
 impl CommonProcessor for MeteoraDlmmProcessor {
     fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+
+    fn pool_registry(&self) -> Option<&PoolRegistry> { Some(&self.pool_registry) }
 }
 
-impl CommonProcessor for OpenbookV2Processor {
+impl CommonProcessor for MeteoraPoolsProcessor {
     fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
 }
 
-impl CommonProcessor for PhoenixProcessor {
+impl CommonProcessor for OpenbookV2Processor {
     fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
 }
 
-impl CommonProcessor for FluxbeamProcessor {
+impl CommonProcessor for PhoenixProcessor {
     fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
 }
 
 impl CommonProcessor for LifinityAmmV2Processor {
     fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
 }
-
-impl CommonProcessor for MoonshotProcessor {
-    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
-} 
\ No newline at end of file
+ 
\ No newline at end of file
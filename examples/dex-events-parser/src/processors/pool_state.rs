@@ -0,0 +1,202 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        account::AccountProcessorInputType, error::CarbonResult, metrics::MetricsCollection,
+        processor::Processor,
+    },
+    carbon_orca_whirlpool_decoder::accounts::OrcaWhirlpoolAccount,
+    carbon_raydium_amm_v4_decoder::accounts::RaydiumAmmV4Account,
+    carbon_raydium_clmm_decoder::accounts::RaydiumClmmAccount,
+    carbon_token_program_decoder::accounts::TokenProgramAccount,
+    std::sync::Arc,
+};
+
+use crate::{
+    analytics::{pool_reserves::PoolReserves, pool_state::PoolStates},
+    publishers::UnifiedPublisher,
+};
+
+use super::{CommonProcessor, EventTime};
+
+/// Publishes the price of a Whirlpool on every update of its account.
+pub struct WhirlpoolStateProcessor {
+    publisher: UnifiedPublisher,
+    pool_states: PoolStates,
+}
+
+impl WhirlpoolStateProcessor {
+    pub fn new(publisher: UnifiedPublisher, pool_states: PoolStates) -> Self {
+        Self { publisher, pool_states }
+    }
+}
+
+#[async_trait]
+impl Processor for WhirlpoolStateProcessor {
+    type InputType = AccountProcessorInputType<OrcaWhirlpoolAccount>;
+
+    async fn process(
+        &mut self,
+        (metadata, account, _): Self::InputType,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let OrcaWhirlpoolAccount::Whirlpool(whirlpool) = account.data else {
+            return Ok(());
+        };
+        let details = self.pool_states.concentrated_pool(
+            &metadata.pubkey,
+            (whirlpool.token_mint_a, None),
+            (whirlpool.token_mint_b, None),
+            whirlpool.sqrt_price,
+            whirlpool.liquidity,
+            whirlpool.tick_current_index,
+        );
+        self.common_process_event(
+            "pool_state",
+            "Orca Whirlpool".to_string(),
+            String::new(),
+            EventTime::at_slot(metadata.slot),
+            details,
+        )
+        .await
+    }
+}
+
+/// Publishes the price of a Raydium CLMM pool on every update of its account.
+pub struct ClmmStateProcessor {
+    publisher: UnifiedPublisher,
+    pool_states: PoolStates,
+}
+
+impl ClmmStateProcessor {
+    pub fn new(publisher: UnifiedPublisher, pool_states: PoolStates) -> Self {
+        Self { publisher, pool_states }
+    }
+}
+
+#[async_trait]
+impl Processor for ClmmStateProcessor {
+    type InputType = AccountProcessorInputType<RaydiumClmmAccount>;
+
+    async fn process(
+        &mut self,
+        (metadata, account, _): Self::InputType,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let RaydiumClmmAccount::PoolState(pool) = account.data else {
+            return Ok(());
+        };
+        let details = self.pool_states.concentrated_pool(
+            &metadata.pubkey,
+            (pool.token_mint0, Some(pool.mint_decimals0)),
+            (pool.token_mint1, Some(pool.mint_decimals1)),
+            pool.sqrt_price_x64,
+            pool.liquidity,
+            pool.tick_current,
+        );
+        self.common_process_event(
+            "pool_state",
+            "Raydium CLMM".to_string(),
+            String::new(),
+            EventTime::at_slot(metadata.slot),
+            details,
+        )
+        .await
+    }
+}
+
+/// Publishes the reserves and price of a Raydium AMM V4 pool whenever one of
+/// its vaults changes.
+pub struct AmmVaultProcessor {
+    publisher: UnifiedPublisher,
+    pool_states: PoolStates,
+    pool_reserves: PoolReserves,
+}
+
+impl AmmVaultProcessor {
+    pub fn new(publisher: UnifiedPublisher, pool_states: PoolStates, pool_reserves: PoolReserves) -> Self {
+        Self { publisher, pool_states, pool_reserves }
+    }
+}
+
+#[async_trait]
+impl Processor for AmmVaultProcessor {
+    type InputType = AccountProcessorInputType<TokenProgramAccount>;
+
+    async fn process(
+        &mut self,
+        (metadata, account, _): Self::InputType,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let TokenProgramAccount::Account(token_account) = account.data else {
+            return Ok(());
+        };
+        self.pool_reserves.observe_vault(&metadata.pubkey, token_account.amount, metadata.slot);
+        // Waits for the other vault of the pool
+        let Some((_, details)) = self.pool_states.record_vault(&metadata.pubkey, token_account.amount) else {
+            return Ok(());
+        };
+        self.common_process_event(
+            "pool_state",
+            "Raydium AMM V4".to_string(),
+            String::new(),
+            EventTime::at_slot(metadata.slot),
+            details,
+        )
+        .await
+    }
+}
+
+/// Publishes the reserves and price of a Raydium AMM V4 pool when the PnL
+/// its vaults hold for the protocol changes.
+pub struct AmmInfoStateProcessor {
+    publisher: UnifiedPublisher,
+    pool_states: PoolStates,
+}
+
+impl AmmInfoStateProcessor {
+    pub fn new(publisher: UnifiedPublisher, pool_states: PoolStates) -> Self {
+        Self { publisher, pool_states }
+    }
+}
+
+#[async_trait]
+impl Processor for AmmInfoStateProcessor {
+    type InputType = AccountProcessorInputType<RaydiumAmmV4Account>;
+
+    async fn process(
+        &mut self,
+        (metadata, account, _): Self::InputType,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let RaydiumAmmV4Account::AmmInfo(amm) = account.data else {
+            return Ok(());
+        };
+        let Some(details) = self.pool_states.record_amm(&metadata.pubkey, &amm) else {
+            return Ok(());
+        };
+        self.common_process_event(
+            "pool_state",
+            "Raydium AMM V4".to_string(),
+            String::new(),
+            EventTime::at_slot(metadata.slot),
+            details,
+        )
+        .await
+    }
+}
+
+impl CommonProcessor for WhirlpoolStateProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
+
+impl CommonProcessor for ClmmStateProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
+
+impl CommonProcessor for AmmVaultProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
+
+impl CommonProcessor for AmmInfoStateProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
@@ -0,0 +1,324 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    carbon_pump_swap_decoder::instructions::PumpSwapInstruction,
+    serde_json::json,
+    std::sync::Arc,
+};
+
+use crate::{
+    alerts::whale::WhaleAlerts,
+    analytics::{
+        balances::token_account_mint,
+        decimals::UiAmounts,
+        jito::jito_tip_annotation,
+        new_pair::NewPairEvent,
+        participation::ParticipationTracker,
+        pool_registry::PoolRegistry,
+        pool_reserves::PoolReserves,
+        price_index::SwapFill,
+        side::TradeSide,
+        swap_event::SwapEvent,
+        usd_prices::UsdPrices,
+    },
+    datasources::discovery::PoolDiscovery,
+    publishers::UnifiedPublisher,
+};
+
+use super::{CommonProcessor, EventTime};
+
+// Pump Swap (Pumpfun AMM) Processor
+pub struct PumpSwapProcessor {
+    publisher: UnifiedPublisher,
+    whale_alerts: WhaleAlerts,
+    discovery: PoolDiscovery,
+    participation: ParticipationTracker,
+    usd_prices: UsdPrices,
+    pool_registry: PoolRegistry,
+    pool_reserves: PoolReserves,
+}
+
+impl PumpSwapProcessor {
+    pub fn new(
+        publisher: UnifiedPublisher,
+        whale_alerts: WhaleAlerts,
+        discovery: PoolDiscovery,
+        participation: ParticipationTracker,
+        usd_prices: UsdPrices,
+        pool_registry: PoolRegistry,
+        pool_reserves: PoolReserves,
+    ) -> Self {
+        Self { publisher, whale_alerts, discovery, participation, usd_prices, pool_registry, pool_reserves }
+    }
+}
+
+#[async_trait]
+impl Processor for PumpSwapProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<PumpSwapInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, _, _): Self::InputType,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let transaction = &metadata.transaction_metadata;
+        let signature = transaction.signature.to_string();
+        let platform = "Pump Swap".to_string();
+        let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction.data));
+        let routed = timestamp.via_aggregator.is_some();
+
+        // The program emits a self-CPI event for every instruction, which
+        // carries the executed amounts and fees, so only events are published.
+        let mut whale_trade = None;
+        let mut new_pool = None;
+        let mut new_pair = None;
+        let mut trade = None;
+        let mut venue_quote = None;
+        let mut ui_amounts = UiAmounts::new(transaction);
+        let (event_type, mut details) = match instruction.data {
+            PumpSwapInstruction::BuyEvent(buy) => {
+                trade = Some((buy.pool, buy.user));
+                let base_mint = token_account_mint(transaction, &buy.user_base_token_account);
+                let quote_mint = token_account_mint(transaction, &buy.user_quote_token_account);
+                if let Some(base_mint) = base_mint {
+                    ui_amounts.mint("base_amount_out", base_mint);
+                }
+                venue_quote = quote_mint;
+                if let Some(quote_mint) = quote_mint {
+                    for field in ["quote_amount_in", "user_quote_amount_in", "lp_fee", "protocol_fee", "coin_creator_fee"] {
+                        ui_amounts.mint(field, quote_mint);
+                    }
+                }
+                if let (Some(base_mint), Some(quote_mint)) = (base_mint, quote_mint) {
+                    whale_trade = Some((buy.user, SwapFill {
+                        input_mint: quote_mint,
+                        output_mint: base_mint,
+                        input_amount: buy.user_quote_amount_in,
+                        output_amount: buy.base_amount_out,
+                    }));
+                }
+                ("swap", json!({
+                    "type": "BuyEvent",
+                    "pool": buy.pool.to_string(),
+                    "user": buy.user.to_string(),
+                    "base_amount_out": buy.base_amount_out,
+                    "quote_amount_in": buy.quote_amount_in,
+                    "user_quote_amount_in": buy.user_quote_amount_in,
+                    "lp_fee": buy.lp_fee,
+                    "protocol_fee": buy.protocol_fee,
+                    "coin_creator": buy.coin_creator.to_string(),
+                    "coin_creator_fee_basis_points": buy.coin_creator_fee_basis_points,
+                    "coin_creator_fee": buy.coin_creator_fee
+                }))
+            }
+            PumpSwapInstruction::SellEvent(sell) => {
+                trade = Some((sell.pool, sell.user));
+                let base_mint = token_account_mint(transaction, &sell.user_base_token_account);
+                let quote_mint = token_account_mint(transaction, &sell.user_quote_token_account);
+                if let Some(base_mint) = base_mint {
+                    ui_amounts.mint("base_amount_in", base_mint);
+                }
+                venue_quote = quote_mint;
+                if let Some(quote_mint) = quote_mint {
+                    for field in ["quote_amount_out", "user_quote_amount_out", "lp_fee", "protocol_fee", "coin_creator_fee"] {
+                        ui_amounts.mint(field, quote_mint);
+                    }
+                }
+                if let (Some(base_mint), Some(quote_mint)) = (base_mint, quote_mint) {
+                    whale_trade = Some((sell.user, SwapFill {
+                        input_mint: base_mint,
+                        output_mint: quote_mint,
+                        input_amount: sell.base_amount_in,
+                        output_amount: sell.user_quote_amount_out,
+                    }));
+                }
+                ("swap", json!({
+                    "type": "SellEvent",
+                    "pool": sell.pool.to_string(),
+                    "user": sell.user.to_string(),
+                    "base_amount_in": sell.base_amount_in,
+                    "quote_amount_out": sell.quote_amount_out,
+                    "user_quote_amount_out": sell.user_quote_amount_out,
+                    "lp_fee": sell.lp_fee,
+                    "protocol_fee": sell.protocol_fee,
+                    "coin_creator": sell.coin_creator.to_string(),
+                    "coin_creator_fee_basis_points": sell.coin_creator_fee_basis_points,
+                    "coin_creator_fee": sell.coin_creator_fee
+                }))
+            }
+            PumpSwapInstruction::DepositEvent(deposit) => {
+                ui_amounts.token_account("base_amount_in", &deposit.user_base_token_account);
+                ui_amounts.token_account("quote_amount_in", &deposit.user_quote_token_account);
+                ui_amounts.token_account("lp_token_amount_out", &deposit.user_pool_token_account);
+                ("liquidity", json!({
+                    "type": "add",
+                    "action": "DepositEvent",
+                    "pool": deposit.pool.to_string(),
+                    "user": deposit.user.to_string(),
+                    "base_amount_in": deposit.base_amount_in,
+                    "quote_amount_in": deposit.quote_amount_in,
+                    "lp_token_amount_out": deposit.lp_token_amount_out
+                }))
+            }
+            PumpSwapInstruction::WithdrawEvent(withdraw) => {
+                ui_amounts.token_account("base_amount_out", &withdraw.user_base_token_account);
+                ui_amounts.token_account("quote_amount_out", &withdraw.user_quote_token_account);
+                ui_amounts.token_account("lp_token_amount_in", &withdraw.user_pool_token_account);
+                ("liquidity", json!({
+                    "type": "remove",
+                    "action": "WithdrawEvent",
+                    "pool": withdraw.pool.to_string(),
+                    "user": withdraw.user.to_string(),
+                    "base_amount_out": withdraw.base_amount_out,
+                    "quote_amount_out": withdraw.quote_amount_out,
+                    "lp_token_amount_in": withdraw.lp_token_amount_in
+                }))
+            }
+            PumpSwapInstruction::CreatePoolEvent(create) => {
+                new_pool = Some((create.pool, create.base_mint, create.quote_mint));
+                // The pool's vaults are not in the event, its reserves are
+                new_pair = Some(
+                    NewPairEvent::new(create.pool, (create.base_mint, None), (create.quote_mint, None), Some(create.creator))
+                        .with_reported_amounts(create.pool_base_amount, create.pool_quote_amount),
+                );
+                ui_amounts.mint("base_amount_in", create.base_mint);
+                ui_amounts.mint("quote_amount_in", create.quote_mint);
+                ("new_pool", json!({
+                    "type": "CreatePoolEvent",
+                    "pool": create.pool.to_string(),
+                    "creator": create.creator.to_string(),
+                    "base_mint": create.base_mint.to_string(),
+                    "quote_mint": create.quote_mint.to_string(),
+                    "base_amount_in": create.base_amount_in,
+                    "quote_amount_in": create.quote_amount_in,
+                    "coin_creator": create.coin_creator.to_string()
+                }))
+            }
+            PumpSwapInstruction::CollectCoinCreatorFeeEvent(collect) => {
+                ("creator_fee", json!({
+                    "type": "CollectCoinCreatorFeeEvent",
+                    "action": "claim",
+                    "coin_creator": collect.coin_creator.to_string(),
+                    "coin_creator_fee": collect.coin_creator_fee,
+                    "coin_creator_vault_ata": collect.coin_creator_vault_ata.to_string(),
+                    "coin_creator_token_account": collect.coin_creator_token_account.to_string()
+                }))
+            }
+            PumpSwapInstruction::SetBondingCurveCoinCreatorEvent(set_creator) => {
+                ("creator_fee", json!({
+                    "type": "SetBondingCurveCoinCreatorEvent",
+                    "action": "set_creator",
+                    "base_mint": set_creator.base_mint.to_string(),
+                    "pool": set_creator.pool.to_string(),
+                    "coin_creator": set_creator.coin_creator.to_string()
+                }))
+            }
+            PumpSwapInstruction::SetMetaplexCoinCreatorEvent(set_creator) => {
+                ("creator_fee", json!({
+                    "type": "SetMetaplexCoinCreatorEvent",
+                    "action": "set_creator",
+                    "base_mint": set_creator.base_mint.to_string(),
+                    "pool": set_creator.pool.to_string(),
+                    "coin_creator": set_creator.coin_creator.to_string()
+                }))
+            }
+            PumpSwapInstruction::CreateConfigEvent(config) => {
+                ("fee_config", json!({
+                    "type": "CreateConfigEvent",
+                    "admin": config.admin.to_string(),
+                    "lp_fee_basis_points": config.lp_fee_basis_points,
+                    "protocol_fee_basis_points": config.protocol_fee_basis_points,
+                    "coin_creator_fee_basis_points": config.coin_creator_fee_basis_points,
+                    "protocol_fee_recipients": config
+                        .protocol_fee_recipients
+                        .iter()
+                        .map(|recipient| recipient.to_string())
+                        .collect::<Vec<_>>()
+                }))
+            }
+            PumpSwapInstruction::UpdateFeeConfigEvent(config) => {
+                ("fee_config", json!({
+                    "type": "UpdateFeeConfigEvent",
+                    "admin": config.admin.to_string(),
+                    "lp_fee_basis_points": config.lp_fee_basis_points,
+                    "protocol_fee_basis_points": config.protocol_fee_basis_points,
+                    "coin_creator_fee_basis_points": config.coin_creator_fee_basis_points,
+                    "protocol_fee_recipients": config
+                        .protocol_fee_recipients
+                        .iter()
+                        .map(|recipient| recipient.to_string())
+                        .collect::<Vec<_>>()
+                }))
+            }
+            _ => return Ok(()),
+        };
+
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(transaction);
+        }
+        if let Some(side) = whale_trade
+            .as_ref()
+            .and_then(|(_, fill)| TradeSide::from_fill(fill, venue_quote))
+        {
+            side.apply(&mut details);
+        }
+        if let (Some((_, fill)), Some((pool, trader))) = (&whale_trade, trade) {
+            let swap_event = SwapEvent::new(fill, Some(pool), Some(trader));
+            swap_event.apply(transaction, &mut details);
+            self.usd_prices.apply(transaction, &swap_event, &mut details);
+        }
+        ui_amounts.apply(&mut details);
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(transaction, &mut details);
+        }
+        self.pool_reserves.observe(transaction, &mut details).await;
+
+        self.common_process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details)
+            .await?;
+        if let Some(tracking) = new_pool.and_then(|(pool, base_mint, quote_mint)| {
+            self.discovery.discover(&platform, pool, base_mint, quote_mint)
+        }) {
+            self.common_process_event("tracking_started", platform.clone(), signature, timestamp, tracking)
+                .await?;
+        }
+        if let (Some((trader, fill)), false) = (&whale_trade, routed) {
+            self.whale_alerts.check(&platform, transaction, Some(*trader), fill).await;
+        }
+        if let Some((pool, trader)) = trade {
+            self.participation.record(&platform, pool, trader, transaction);
+        }
+
+        Ok(())
+    }
+}
+
+/// Wallet behind each published event. Config events name the admin.
+fn trader(instruction: &PumpSwapInstruction) -> Option<solana_pubkey::Pubkey> {
+    match instruction {
+        PumpSwapInstruction::BuyEvent(buy) => Some(buy.user),
+        PumpSwapInstruction::SellEvent(sell) => Some(sell.user),
+        PumpSwapInstruction::DepositEvent(deposit) => Some(deposit.user),
+        PumpSwapInstruction::WithdrawEvent(withdraw) => Some(withdraw.user),
+        PumpSwapInstruction::CreatePoolEvent(create) => Some(create.creator),
+        PumpSwapInstruction::CollectCoinCreatorFeeEvent(collect) => Some(collect.coin_creator),
+        PumpSwapInstruction::CreateConfigEvent(config) => Some(config.admin),
+        PumpSwapInstruction::UpdateFeeConfigEvent(config) => Some(config.admin),
+        _ => None,
+    }
+}
+
+impl CommonProcessor for PumpSwapProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+
+    fn pool_registry(&self) -> Option<&PoolRegistry> { Some(&self.pool_registry) }
+}
@@ -6,20 +6,54 @@ use {
         metrics::MetricsCollection,
         processor::Processor,
     },
-    carbon_pumpfun_decoder::instructions::PumpfunInstruction,
-    std::{sync::Arc, time::SystemTime},
+    carbon_core::deserialize::ArrangeAccounts,
+    carbon_pumpfun_decoder::instructions::{buy::Buy, sell::Sell, PumpfunInstruction},
+    std::sync::Arc,
     serde_json::json,
 };
 
-use crate::{DexEvent, publishers::{DexEventData, UnifiedPublisher, Publisher}};
+use crate::{
+    DexEvent,
+    alerts::whale::WhaleAlerts,
+    analytics::{
+        bonding_curve::{market_cap, BondingCurveProgress},
+        decimals::UiAmounts,
+        jito::jito_tip_annotation,
+        launch_funnel::{LaunchFunnel, LaunchSignal},
+        mints::WSOL_MINT,
+        new_pair::NewPairEvent,
+        price_index::SwapFill,
+        side::TradeSide,
+        sol_transfers::{sol_transfers, SolLegs},
+        swap_event::SwapEvent,
+        trader::TraderAccount,
+        usd_prices::UsdPrices,
+        symbols::SymbolRegistry,
+    },
+    publishers::{UnifiedPublisher, Publisher, EVENTS_TOPIC},
+};
+
+use super::{CommonProcessor, EventTime};
 
 pub struct PumpfunProcessor {
     publisher: UnifiedPublisher,
+    whale_alerts: WhaleAlerts,
+    launch_funnel: LaunchFunnel,
+    symbols: SymbolRegistry,
+    usd_prices: UsdPrices,
+    curve_progress: BondingCurveProgress,
 }
 
 impl PumpfunProcessor {
-    pub fn new(publisher: UnifiedPublisher) -> Self {
-        Self { publisher }
+    pub fn new(
+        publisher: UnifiedPublisher,
+        whale_alerts: WhaleAlerts,
+        launch_funnel: LaunchFunnel,
+        symbols: SymbolRegistry,
+        usd_prices: UsdPrices,
+        curve_progress: BondingCurveProgress,
+    ) -> Self {
+        Self { publisher, whale_alerts, launch_funnel, symbols, usd_prices, curve_progress }
     }
 }
 
@@ -34,18 +68,33 @@ impl Processor for PumpfunProcessor {
 
     async fn process(
         &mut self,
-        (metadata, instruction, _, _): Self::InputType,
-        _metrics: Arc<MetricsCollection>,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
         let signature = metadata.transaction_metadata.signature.to_string();
         let platform = "Pumpfun".to_string();
-        let timestamp = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction.data, &instruction.accounts));
 
-        let (event_type, details) = match instruction.data {
+        let mut whale_trade = None;
+        let mut funnel = None;
+        let mut trade_side = None;
+        let mut token_symbol = None;
+        let mut new_pair = None;
+        let mut curve_trade = None;
+        let mut graduation = None;
+        let mut curve_price = None;
+        let mut sol_legs = None;
+        let mut ui_amounts = UiAmounts::new(&metadata.transaction_metadata);
+        let (event_type, mut details) = match instruction.data {
             PumpfunInstruction::Buy(buy) => {
+                if let Some(accounts) = Buy::arrange_accounts(&instruction.accounts) {
+                    trade_side = TradeSide::from_mints(WSOL_MINT, accounts.mint, Some(WSOL_MINT));
+                    sol_legs = SolLegs::attribute(&sol_transfers(&nested), &accounts.user, &[
+                        ("curve", accounts.bonding_curve),
+                        ("protocol_fee", accounts.fee_recipient),
+                        ("creator_fee", accounts.creator_vault),
+                    ]);
+                }
                 ("swap", json!({
                     "type": "Buy",
                     "amount": buy.amount,
@@ -53,6 +102,8 @@ impl Processor for PumpfunProcessor {
                 }))
             }
             PumpfunInstruction::Sell(sell) => {
+                trade_side = Sell::arrange_accounts(&instruction.accounts)
+                    .and_then(|accounts| TradeSide::from_mints(accounts.mint, WSOL_MINT, Some(WSOL_MINT)));
                 ("swap", json!({
                     "type": "Sell",
                     "amount": sell.amount,
@@ -60,6 +111,22 @@ impl Processor for PumpfunProcessor {
                 }))
             }
             PumpfunInstruction::TradeEvent(trade) => {
+                // Bonding curve trades are always against native SOL.
+                let (input_mint, output_mint, input_amount, output_amount) = if trade.is_buy {
+                    (WSOL_MINT, trade.mint, trade.sol_amount, trade.token_amount)
+                } else {
+                    (trade.mint, WSOL_MINT, trade.token_amount, trade.sol_amount)
+                };
+                funnel = Some((trade.mint, LaunchSignal::Trade));
+                curve_trade = Some((trade.mint, trade.real_sol_reserves, trade.real_token_reserves));
+                curve_price = Some((trade.virtual_sol_reserves, trade.virtual_token_reserves));
+                ui_amounts.mint("sol_amount", WSOL_MINT);
+                ui_amounts.mint("token_amount", trade.mint);
+                trade_side = TradeSide::from_mints(input_mint, output_mint, Some(WSOL_MINT));
+                whale_trade = Some((
+                    trade.user,
+                    SwapFill { input_mint, output_mint, input_amount, output_amount },
+                ));
                 ("swap", json!({
                     "type": "TradeEvent",
                     "mint": trade.mint.to_string(),
@@ -69,6 +136,8 @@ impl Processor for PumpfunProcessor {
                 }))
             }
             PumpfunInstruction::CreateEvent(create) => {
+                funnel = Some((create.mint, LaunchSignal::Created));
+                token_symbol = Some((create.symbol.clone(), create.mint));
                 ("mint_burn", json!({
                     "type": "mint",
                     "action": "CreateEvent",
@@ -78,15 +147,97 @@ impl Processor for PumpfunProcessor {
                 }))
             }
             PumpfunInstruction::CompleteEvent(complete) => {
+                funnel = Some((complete.mint, LaunchSignal::Graduated));
+                self.curve_progress.complete(&complete.mint, complete.bonding_curve, &metadata.transaction_metadata);
+                // The curve is complete but not migrated yet, it stands for the pool
+                new_pair = Some(NewPairEvent::new(
+                    complete.bonding_curve,
+                    (complete.mint, None),
+                    (WSOL_MINT, None),
+                    Some(complete.user),
+                ));
                 ("new_pool", json!({
                     "type": "CompleteEvent",
                     "mint": complete.mint.to_string(),
                     "bonding_curve": complete.bonding_curve.to_string()
                 }))
             }
+            PumpfunInstruction::CompletePumpAmmMigrationEvent(migration) => {
+                funnel = Some((migration.mint, LaunchSignal::Migrated { pool: Some(migration.pool) }));
+                graduation = Some(self.curve_progress.graduate(
+                    &migration.mint,
+                    migration.pool,
+                    migration.sol_amount,
+                    &metadata.transaction_metadata,
+                ));
+                new_pair = Some(
+                    NewPairEvent::new(migration.pool, (migration.mint, None), (WSOL_MINT, None), Some(migration.user))
+                        .with_reported_amounts(migration.mint_amount, migration.sol_amount),
+                );
+                ui_amounts.mint("sol_amount", WSOL_MINT);
+                ui_amounts.mint("mint_amount", migration.mint);
+                ("new_pool", json!({
+                    "type": "CompletePumpAmmMigrationEvent",
+                    "mint": migration.mint.to_string(),
+                    "bonding_curve": migration.bonding_curve.to_string(),
+                    "pool": migration.pool.to_string(),
+                    "mint_amount": migration.mint_amount,
+                    "sol_amount": migration.sol_amount,
+                    "pool_migration_fee": migration.pool_migration_fee
+                }))
+            }
             _ => return Ok(()),
         };
 
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(&metadata.transaction_metadata);
+        }
+        if let Some(side) = trade_side {
+            side.apply(&mut details);
+        }
+        if let Some(sol_legs) = &sol_legs {
+            sol_legs.apply(&mut details);
+        }
+        if let Some((trader, fill)) = &whale_trade {
+            // The trade event does not name the bonding curve
+            let swap_event = SwapEvent::new(fill, None, Some(*trader));
+            swap_event.apply(&metadata.transaction_metadata, &mut details);
+            self.usd_prices.apply(&metadata.transaction_metadata, &swap_event, &mut details);
+        }
+        if let Some((symbol, mint)) = &token_symbol {
+            if self.symbols.annotate(symbol, mint, &mut details) {
+                log::warn!("{} token {} imitates the symbol {}", platform, mint, symbol);
+                metrics
+                    .increment_counter("symbol_collisions", 1)
+                    .await
+                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+            }
+        }
+        let mut graduations = match curve_trade {
+            Some((mint, real_sol_reserves, real_token_reserves)) => self.curve_progress.record_trade(
+                &mint,
+                real_sol_reserves,
+                real_token_reserves,
+                &metadata.transaction_metadata,
+                &mut details,
+            ),
+            None => Vec::new(),
+        };
+        graduations.extend(graduation);
+        if let Some((virtual_sol_reserves, virtual_token_reserves)) = curve_price {
+            market_cap(
+                virtual_sol_reserves,
+                virtual_token_reserves,
+                &self.usd_prices,
+                &metadata.transaction_metadata,
+                &mut details,
+            );
+        }
+        ui_amounts.apply(&mut details);
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(&metadata.transaction_metadata, &mut details);
+        }
+
         // Create DexEvent for logging
         let event = match event_type {
             "swap" => DexEvent::Swap {
@@ -111,19 +262,62 @@ impl Processor for PumpfunProcessor {
         event.log();
 
         // Create ZeroMQ event data
-        let zmq_data = DexEventData {
-            event_type: event_type.to_string(),
-            platform,
-            signature,
-            timestamp,
-            details,
-        };
+        let zmq_data = timestamp.clone().envelope(event_type, platform.clone(), signature, details);
 
         // Publish to ZeroMQ
-        if let Err(e) = self.publisher.publish("dex_events", &zmq_data).await {
+        if let Err(e) = self.publisher.publish(EVENTS_TOPIC, &zmq_data).await {
             log::error!("Failed to publish to ZeroMQ: {}", e);
         }
 
+        if let Some((trader, fill)) = &whale_trade {
+            self.whale_alerts
+                .check(&platform, &metadata.transaction_metadata, Some(*trader), fill)
+                .await;
+        }
+
+        for graduation in graduations {
+            self.common_process_event(
+                "graduation",
+                platform.clone(),
+                metadata.transaction_metadata.signature.to_string(),
+                timestamp.clone(),
+                graduation,
+            )
+            .await?;
+        }
+
+        if let Some((mint, signal)) = funnel {
+            let transitions =
+                self.launch_funnel.record(&platform, &mint, signal, &metadata.transaction_metadata);
+            for transition in transitions {
+                self.common_process_event(
+                    "launch_funnel",
+                    platform.clone(),
+                    metadata.transaction_metadata.signature.to_string(),
+                    timestamp.clone(),
+                    transition,
+                )
+                .await?;
+            }
+        }
+
         Ok(())
     }
+}
+
+/// Wallet behind each published instruction, read from the event for the
+/// self-CPI events.
+fn trader(instruction: &PumpfunInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<solana_pubkey::Pubkey> {
+    match instruction {
+        PumpfunInstruction::Buy(_) | PumpfunInstruction::Sell(_) => TraderAccount::At(6).find(accounts),
+        PumpfunInstruction::TradeEvent(trade) => Some(trade.user),
+        PumpfunInstruction::CreateEvent(create) => Some(create.user),
+        PumpfunInstruction::CompleteEvent(complete) => Some(complete.user),
+        PumpfunInstruction::CompletePumpAmmMigrationEvent(migration) => Some(migration.user),
+        _ => None,
+    }
+}
+
+impl CommonProcessor for PumpfunProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
 } 
\ No newline at end of file
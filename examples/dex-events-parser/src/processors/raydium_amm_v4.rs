@@ -6,20 +6,67 @@ use {
         metrics::MetricsCollection,
         processor::Processor,
     },
-    carbon_raydium_amm_v4_decoder::instructions::RaydiumAmmV4Instruction,
-    std::{sync::Arc, time::SystemTime},
+    carbon_core::deserialize::ArrangeAccounts,
+    carbon_raydium_amm_v4_decoder::instructions::{
+        deposit::Deposit, initialize2::Initialize2, swap_base_in::SwapBaseIn, swap_base_out::SwapBaseOut,
+        withdraw::Withdraw, RaydiumAmmV4Instruction,
+    },
+    std::sync::Arc,
     serde_json::json,
 };
 
-use crate::{DexEvent, publishers::{DexEventData, UnifiedPublisher, Publisher}};
+use crate::{
+    DexEvent,
+    alerts::whale::WhaleAlerts,
+    analytics::{
+        balances::token_account_mint,
+        decimals::UiAmounts,
+        jito::jito_tip_annotation,
+        new_pair::NewPairEvent,
+        participation::ParticipationTracker,
+        pool_registry::PoolRegistry,
+        pool_reserves::PoolReserves,
+        price_index::PriceIndex,
+        side::TradeSide,
+        slippage::{observed_slippage, QuoteSource, SwapMode},
+        swap_event::SwapEvent,
+        trader::TraderAccount,
+        transfers::SwapTransfers,
+        usd_prices::UsdPrices,
+    },
+    datasources::discovery::PoolDiscovery,
+    publishers::{UnifiedPublisher, Publisher, EVENTS_TOPIC},
+};
+
+use super::{
+    layout::{check_layout, AccountRole},
+    CommonProcessor, EventTime,
+};
 
 pub struct RaydiumAmmV4Processor {
     publisher: UnifiedPublisher,
+    price_index: PriceIndex,
+    whale_alerts: WhaleAlerts,
+    discovery: PoolDiscovery,
+    participation: ParticipationTracker,
+    usd_prices: UsdPrices,
+    pool_registry: PoolRegistry,
+    pool_reserves: PoolReserves,
 }
 
 impl RaydiumAmmV4Processor {
-    pub fn new(publisher: UnifiedPublisher) -> Self {
-        Self { publisher }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        publisher: UnifiedPublisher,
+        price_index: PriceIndex,
+        whale_alerts: WhaleAlerts,
+        discovery: PoolDiscovery,
+        participation: ParticipationTracker,
+        usd_prices: UsdPrices,
+        pool_registry: PoolRegistry,
+        pool_reserves: PoolReserves,
+    ) -> Self {
+        Self { publisher, price_index, whale_alerts, discovery, participation, usd_prices, pool_registry, pool_reserves }
     }
 }
 
@@ -34,46 +81,138 @@ impl Processor for RaydiumAmmV4Processor {
 
     async fn process(
         &mut self,
-        (metadata, instruction, _, _): Self::InputType,
-        _metrics: Arc<MetricsCollection>,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
         let signature = metadata.transaction_metadata.signature.to_string();
         let platform = "Raydium AMM V4".to_string();
-        let timestamp = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction.data, &instruction.accounts));
+        let routed = timestamp.via_aggregator.is_some();
 
-        let (event_type, details) = match instruction.data {
+        let mut fill = None;
+        let mut transfers = None;
+        let mut ui_amounts = UiAmounts::new(&metadata.transaction_metadata);
+        let mut decode_warnings = Vec::new();
+        let mut new_pool = None;
+        let mut new_pair = None;
+        let mut trade = None;
+        let mut venue_quote = None;
+        let (event_type, mut details) = match instruction.data {
             RaydiumAmmV4Instruction::SwapBaseIn(swap) => {
+                let slippage = check_layout::<SwapBaseIn>(
+                    "SwapBaseIn",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    17..=18,
+                    |a| vec![
+                        ("user_source_owner", a.user_source_owner, AccountRole::Signer),
+                        ("user_source_token_account", a.user_source_token_account, AccountRole::Writable),
+                        ("user_destination_token_account", a.user_destination_token_account, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                    .map(|accounts| {
+                        trade = Some((accounts.amm, accounts.user_source_owner));
+                        venue_quote = token_account_mint(&metadata.transaction_metadata, &accounts.pool_pc_token_account);
+                        fill = self.price_index.observe(
+                            &platform,
+                            &metadata.transaction_metadata,
+                            &accounts.user_source_token_account,
+                            &accounts.user_destination_token_account,
+                        );
+                        transfers = SwapTransfers::find(
+                            &metadata.transaction_metadata,
+                            &nested,
+                            &accounts.user_source_token_account,
+                            &accounts.user_destination_token_account,
+                        );
+                        ui_amounts.token_account("amount_in", &accounts.user_source_token_account);
+                        ui_amounts.token_account("minimum_amount_out", &accounts.user_destination_token_account);
+                        observed_slippage(
+                            &metadata.transaction_metadata,
+                            SwapMode::ExactIn,
+                            QuoteSource::Limit,
+                            swap.minimum_amount_out,
+                            &accounts.user_destination_token_account,
+                        )
+                    })
+                    .unwrap_or_default();
                 ("swap", json!({
                     "type": "SwapBaseIn",
                     "amount_in": swap.amount_in,
-                    "minimum_amount_out": swap.minimum_amount_out
+                    "minimum_amount_out": swap.minimum_amount_out,
+                    "slippage": slippage
                 }))
             }
             RaydiumAmmV4Instruction::SwapBaseOut(swap) => {
+                let slippage = check_layout::<SwapBaseOut>(
+                    "SwapBaseOut",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    17..=18,
+                    |a| vec![
+                        ("user_source_owner", a.user_source_owner, AccountRole::Signer),
+                        ("user_source_token_account", a.user_source_token_account, AccountRole::Writable),
+                        ("user_destination_token_account", a.user_destination_token_account, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                )
+                    .map(|accounts| {
+                        trade = Some((accounts.amm, accounts.user_source_owner));
+                        venue_quote = token_account_mint(&metadata.transaction_metadata, &accounts.pool_pc_token_account);
+                        fill = self.price_index.observe(
+                            &platform,
+                            &metadata.transaction_metadata,
+                            &accounts.user_source_token_account,
+                            &accounts.user_destination_token_account,
+                        );
+                        transfers = SwapTransfers::find(
+                            &metadata.transaction_metadata,
+                            &nested,
+                            &accounts.user_source_token_account,
+                            &accounts.user_destination_token_account,
+                        );
+                        ui_amounts.token_account("max_amount_in", &accounts.user_source_token_account);
+                        ui_amounts.token_account("amount_out", &accounts.user_destination_token_account);
+                        observed_slippage(
+                            &metadata.transaction_metadata,
+                            SwapMode::ExactOut,
+                            QuoteSource::Limit,
+                            swap.max_amount_in,
+                            &accounts.user_source_token_account,
+                        )
+                    })
+                    .unwrap_or_default();
                 ("swap", json!({
                     "type": "SwapBaseOut",
                     "max_amount_in": swap.max_amount_in,
-                    "amount_out": swap.amount_out
+                    "amount_out": swap.amount_out,
+                    "slippage": slippage
                 }))
             }
             RaydiumAmmV4Instruction::Deposit(deposit) => {
-                ("liquidity", json!({
+                let mut details = json!({
                     "type": "add",
                     "action": "Deposit",
                     "max_coin_amount": deposit.max_coin_amount,
                     "max_pc_amount": deposit.max_pc_amount,
                     "base_side": deposit.base_side
-                }))
+                });
+                if let Some(accounts) = Deposit::arrange_accounts(&instruction.accounts) {
+                    details["pool"] = json!(accounts.amm.to_string());
+                }
+                ("liquidity", details)
             }
             RaydiumAmmV4Instruction::Withdraw(withdraw) => {
-                ("liquidity", json!({
+                let mut details = json!({
                     "type": "remove",
                     "action": "Withdraw",
                     "amount": withdraw.amount
-                }))
+                });
+                if let Some(accounts) = Withdraw::arrange_accounts(&instruction.accounts) {
+                    details["pool"] = json!(accounts.amm.to_string());
+                }
+                ("liquidity", details)
             }
             RaydiumAmmV4Instruction::Initialize(init) => {
                 ("new_pool", json!({
@@ -82,11 +221,27 @@ impl Processor for RaydiumAmmV4Processor {
                 }))
             }
             RaydiumAmmV4Instruction::Initialize2(init) => {
-                ("new_pool", json!({
+                let mut details = json!({
                     "type": "Initialize2",
                     "nonce": init.nonce,
                     "open_time": init.open_time
-                }))
+                });
+                if let Some(accounts) = Initialize2::arrange_accounts(&instruction.accounts) {
+                    new_pool = Some((accounts.amm, accounts.coin_mint, accounts.pc_mint));
+                    new_pair = Some(
+                        NewPairEvent::new(
+                            accounts.amm,
+                            (accounts.coin_mint, Some(accounts.pool_coin_token_account)),
+                            (accounts.pc_mint, Some(accounts.pool_pc_token_account)),
+                            Some(accounts.user_wallet),
+                        )
+                        .with_reported_amounts(init.init_coin_amount, init.init_pc_amount),
+                    );
+                    details["pool"] = json!(accounts.amm.to_string());
+                    details["base_mint"] = json!(accounts.coin_mint.to_string());
+                    details["quote_mint"] = json!(accounts.pc_mint.to_string());
+                }
+                ("new_pool", details)
             }
             RaydiumAmmV4Instruction::PreInitialize(pre_init) => {
                 ("new_pool", json!({
@@ -97,6 +252,28 @@ impl Processor for RaydiumAmmV4Processor {
             _ => return Ok(()),
         };
 
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(&metadata.transaction_metadata);
+        }
+        if let Some(side) = fill.as_ref().and_then(|fill| TradeSide::from_fill(fill, venue_quote)) {
+            side.apply(&mut details);
+        }
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        let executed = transfers.as_ref().and_then(SwapTransfers::fill).or_else(|| fill.clone());
+        if let (Some(executed), Some((pool, trader))) = (&executed, trade) {
+            let swap_event = SwapEvent::new(executed, Some(pool), Some(trader));
+            swap_event.apply(&metadata.transaction_metadata, &mut details);
+            self.usd_prices.apply(&metadata.transaction_metadata, &swap_event, &mut details);
+        }
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(&metadata.transaction_metadata, &mut details);
+        }
+        ui_amounts.apply(&mut details);
+        self.pool_registry.annotate(&mut details).await;
+        self.pool_reserves.observe(&metadata.transaction_metadata, &mut details).await;
+
         // Create DexEvent for logging
         let event = match event_type {
             "swap" => DexEvent::Swap {
@@ -131,19 +308,48 @@ impl Processor for RaydiumAmmV4Processor {
         event.log();
 
         // Create ZeroMQ event data
-        let zmq_data = DexEventData {
-            event_type: event_type.to_string(),
-            platform,
-            signature,
-            timestamp,
-            details,
-        };
+        let zmq_data = timestamp.clone().envelope(event_type, platform.clone(), signature, details);
 
         // Publish to ZeroMQ
-        if let Err(e) = self.publisher.publish("dex_events", &zmq_data).await {
+        if let Err(e) = self.publisher.publish(EVENTS_TOPIC, &zmq_data).await {
             log::error!("Failed to publish to ZeroMQ: {}", e);
         }
 
+        if let (Some(fill), false) = (&fill, routed) {
+            self.whale_alerts.check(&platform, &metadata.transaction_metadata, None, fill).await;
+        }
+        if let Some((pool, trader)) = trade {
+            self.participation.record(&platform, pool, trader, &metadata.transaction_metadata);
+        }
+
+        self.publish_decode_warnings(&platform, &metadata.transaction_metadata.signature.to_string(), timestamp.clone(), decode_warnings, &metrics)
+            .await?;
+        if let Some(tracking) = new_pool.and_then(|(pool, base_mint, quote_mint)| {
+            self.discovery.discover(&platform, pool, base_mint, quote_mint)
+        }) {
+            self.common_process_event("tracking_started", platform.clone(), metadata.transaction_metadata.signature.to_string(), timestamp, tracking)
+                .await?;
+        }
+
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+/// Wallet behind each published instruction.
+fn trader(instruction: &RaydiumAmmV4Instruction, accounts: &[solana_instruction::AccountMeta]) -> Option<solana_pubkey::Pubkey> {
+    let account = match instruction {
+        RaydiumAmmV4Instruction::SwapBaseIn(_) | RaydiumAmmV4Instruction::SwapBaseOut(_) => TraderAccount::Last,
+        RaydiumAmmV4Instruction::Deposit(_) => TraderAccount::At(12),
+        RaydiumAmmV4Instruction::Withdraw(_) => TraderAccount::At(18),
+        RaydiumAmmV4Instruction::Initialize(_) | RaydiumAmmV4Instruction::Initialize2(_) => TraderAccount::At(17),
+        RaydiumAmmV4Instruction::PreInitialize(_) => TraderAccount::At(13),
+        _ => return None,
+    };
+    account.find(accounts)
+}
+
+impl CommonProcessor for RaydiumAmmV4Processor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+
+    fn pool_registry(&self) -> Option<&PoolRegistry> { Some(&self.pool_registry) }
+}
@@ -6,20 +6,37 @@ use {
         metrics::MetricsCollection,
         processor::Processor,
     },
-    carbon_raydium_clmm_decoder::instructions::RaydiumClmmInstruction,
-    std::{sync::Arc, time::SystemTime},
+    carbon_core::deserialize::ArrangeAccounts,
+    carbon_raydium_clmm_decoder::instructions::{
+        decrease_liquidity::DecreaseLiquidity, decrease_liquidity_v2::DecreaseLiquidityV2,
+        increase_liquidity::IncreaseLiquidity, increase_liquidity_v2::IncreaseLiquidityV2, swap::Swap,
+        swap_v2::SwapV2, RaydiumClmmInstruction,
+    },
+    std::sync::Arc,
     serde_json::json,
 };
 
-use crate::{DexEvent, publishers::{DexEventData, UnifiedPublisher, Publisher}};
+use crate::{
+    DexEvent,
+    analytics::{
+        jito::jito_tip_annotation, pool_registry::PoolRegistry, pool_reserves::PoolReserves, price_index::SwapFill,
+        side::TradeSide, swap_event::SwapEvent, trader::TraderAccount, transfers::SwapTransfers, usd_prices::UsdPrices,
+    },
+    publishers::{UnifiedPublisher, Publisher, EVENTS_TOPIC},
+};
+
+use super::EventTime;
 
 pub struct RaydiumClmmProcessor {
     publisher: UnifiedPublisher,
+    usd_prices: UsdPrices,
+    pool_registry: PoolRegistry,
+    pool_reserves: PoolReserves,
 }
 
 impl RaydiumClmmProcessor {
-    pub fn new(publisher: UnifiedPublisher) -> Self {
-        Self { publisher }
+    pub fn new(publisher: UnifiedPublisher, usd_prices: UsdPrices, pool_registry: PoolRegistry, pool_reserves: PoolReserves) -> Self {
+        Self { publisher, usd_prices, pool_registry, pool_reserves }
     }
 }
 
@@ -34,34 +51,75 @@ impl Processor for RaydiumClmmProcessor {
 
     async fn process(
         &mut self,
-        (metadata, instruction, _, _): Self::InputType,
+        (metadata, instruction, nested, _): Self::InputType,
         _metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
         let signature = metadata.transaction_metadata.signature.to_string();
         let platform = "Raydium CLMM".to_string();
-        let timestamp = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction.data, &instruction.accounts));
 
-        let (event_type, details) = match instruction.data {
+        let mut trade_side = None;
+        let mut swap_event = None;
+        let mut transfers = None;
+        let mut liquidity_pool = None;
+        let (event_type, mut details) = match instruction.data {
             RaydiumClmmInstruction::Swap(swap) => {
+                if let Some(accounts) = Swap::arrange_accounts(&instruction.accounts) {
+                    let fill = SwapFill::from_balance_changes(
+                        &metadata.transaction_metadata,
+                        &accounts.input_token_account,
+                        &accounts.output_token_account,
+                    );
+                    trade_side = fill.as_ref().and_then(|fill| TradeSide::from_fill(fill, None));
+                    transfers = SwapTransfers::find(
+                        &metadata.transaction_metadata,
+                        &nested,
+                        &accounts.input_token_account,
+                        &accounts.output_token_account,
+                    );
+                    swap_event = transfers
+                        .as_ref()
+                        .and_then(SwapTransfers::fill)
+                        .or(fill)
+                        .map(|fill| SwapEvent::new(&fill, Some(accounts.pool_state), Some(accounts.payer)));
+                }
                 ("swap", json!({
                     "type": "Swap",
                     "amount": swap.amount,
                     "other_amount_threshold": swap.other_amount_threshold,
-                    "sqrt_price_limit_x64": swap.sqrt_price_limit_x64
+                    "sqrt_price_limit_x64": swap.sqrt_price_limit_x64,
+                    "is_base_input": swap.is_base_input
                 }))
             }
             RaydiumClmmInstruction::SwapV2(swap) => {
+                if let Some(accounts) = SwapV2::arrange_accounts(&instruction.accounts) {
+                    trade_side = TradeSide::from_mints(accounts.input_vault_mint, accounts.output_vault_mint, None);
+                    transfers = SwapTransfers::find(
+                        &metadata.transaction_metadata,
+                        &nested,
+                        &accounts.input_token_account,
+                        &accounts.output_token_account,
+                    );
+                    swap_event = transfers
+                        .as_ref()
+                        .and_then(SwapTransfers::fill)
+                        .or_else(|| SwapFill::from_balance_changes(
+                            &metadata.transaction_metadata,
+                            &accounts.input_token_account,
+                            &accounts.output_token_account,
+                        ))
+                        .map(|fill| SwapEvent::new(&fill, Some(accounts.pool_state), Some(accounts.payer)));
+                }
                 ("swap", json!({
                     "type": "SwapV2",
                     "amount": swap.amount,
                     "other_amount_threshold": swap.other_amount_threshold,
-                    "sqrt_price_limit_x64": swap.sqrt_price_limit_x64
+                    "sqrt_price_limit_x64": swap.sqrt_price_limit_x64,
+                    "is_base_input": swap.is_base_input
                 }))
             }
             RaydiumClmmInstruction::IncreaseLiquidity(increase) => {
+                liquidity_pool = IncreaseLiquidity::arrange_accounts(&instruction.accounts).map(|accounts| accounts.pool_state);
                 ("liquidity", json!({
                     "type": "add",
                     "action": "IncreaseLiquidity",
@@ -71,6 +129,7 @@ impl Processor for RaydiumClmmProcessor {
                 }))
             }
             RaydiumClmmInstruction::IncreaseLiquidityV2(increase) => {
+                liquidity_pool = IncreaseLiquidityV2::arrange_accounts(&instruction.accounts).map(|accounts| accounts.pool_state);
                 ("liquidity", json!({
                     "type": "add",
                     "action": "IncreaseLiquidityV2",
@@ -80,6 +139,7 @@ impl Processor for RaydiumClmmProcessor {
                 }))
             }
             RaydiumClmmInstruction::DecreaseLiquidity(decrease) => {
+                liquidity_pool = DecreaseLiquidity::arrange_accounts(&instruction.accounts).map(|accounts| accounts.pool_state);
                 ("liquidity", json!({
                     "type": "remove",
                     "action": "DecreaseLiquidity",
@@ -89,6 +149,7 @@ impl Processor for RaydiumClmmProcessor {
                 }))
             }
             RaydiumClmmInstruction::DecreaseLiquidityV2(decrease) => {
+                liquidity_pool = DecreaseLiquidityV2::arrange_accounts(&instruction.accounts).map(|accounts| accounts.pool_state);
                 ("liquidity", json!({
                     "type": "remove",
                     "action": "DecreaseLiquidityV2",
@@ -100,6 +161,25 @@ impl Processor for RaydiumClmmProcessor {
             _ => return Ok(()),
         };
 
+        if let Some(pool) = liquidity_pool {
+            details["pool"] = json!(pool.to_string());
+        }
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(&metadata.transaction_metadata);
+        }
+        if let Some(side) = trade_side {
+            side.apply(&mut details);
+        }
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        if let Some(swap_event) = swap_event {
+            swap_event.apply(&metadata.transaction_metadata, &mut details);
+            self.usd_prices.apply(&metadata.transaction_metadata, &swap_event, &mut details);
+        }
+        self.pool_registry.annotate(&mut details).await;
+        self.pool_reserves.observe(&metadata.transaction_metadata, &mut details).await;
+
         // Create DexEvent for logging
         let event = match event_type {
             "swap" => DexEvent::Swap {
@@ -129,19 +209,27 @@ impl Processor for RaydiumClmmProcessor {
         event.log();
 
         // Create ZeroMQ event data
-        let zmq_data = DexEventData {
-            event_type: event_type.to_string(),
-            platform,
-            signature,
-            timestamp,
-            details,
-        };
+        let zmq_data = timestamp.envelope(event_type, platform, signature, details);
 
         // Publish to ZeroMQ
-        if let Err(e) = self.publisher.publish("dex_events", &zmq_data).await {
+        if let Err(e) = self.publisher.publish(EVENTS_TOPIC, &zmq_data).await {
             log::error!("Failed to publish to ZeroMQ: {}", e);
         }
 
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+/// Wallet behind each published instruction, the payer of swaps and the
+/// position NFT owner of liquidity changes.
+fn trader(instruction: &RaydiumClmmInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<solana_pubkey::Pubkey> {
+    match instruction {
+        RaydiumClmmInstruction::Swap(_)
+        | RaydiumClmmInstruction::SwapV2(_)
+        | RaydiumClmmInstruction::IncreaseLiquidity(_)
+        | RaydiumClmmInstruction::IncreaseLiquidityV2(_)
+        | RaydiumClmmInstruction::DecreaseLiquidity(_)
+        | RaydiumClmmInstruction::DecreaseLiquidityV2(_) => TraderAccount::At(0).find(accounts),
+        _ => None,
+    }
+}
@@ -0,0 +1,419 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionDecoder, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    carbon_raydium_launchpad_decoder::{
+        instructions::{
+            buy_exact_in::BuyExactIn, buy_exact_out::BuyExactOut, initialize::Initialize,
+            migrate_to_amm::MigrateToAmm, migrate_to_cpswap::MigrateToCpswap, sell_exact_in::SellExactIn,
+            sell_exact_out::SellExactOut, trade_event::TradeEvent, RaydiumLaunchpadInstruction,
+        },
+        types::{PoolStatus, TradeDirection},
+        RaydiumLaunchpadDecoder, PROGRAM_ID,
+    },
+    serde_json::json,
+    solana_pubkey::Pubkey,
+    std::sync::Arc,
+};
+
+use crate::{
+    alerts::whale::WhaleAlerts,
+    analytics::{
+        decimals::UiAmounts,
+        jito::jito_tip_annotation,
+        launch_funnel::{LaunchFunnel, LaunchSignal},
+        new_pair::NewPairEvent,
+        price_index::SwapFill,
+        side::TradeSide,
+        swap_event::SwapEvent,
+        symbols::SymbolRegistry,
+        trader::TraderAccount,
+        usd_prices::UsdPrices,
+    },
+    publishers::UnifiedPublisher,
+};
+
+use super::{
+    layout::{check_layout, AccountRole},
+    CommonProcessor, EventTime,
+};
+
+/// Accounts of the trade instructions, with the optional fee vaults of newer
+/// program versions.
+const TRADE_ACCOUNTS: std::ops::RangeInclusive<usize> = 15..=18;
+
+/// Trade instruction of a Raydium Launchpad bonding curve, with the accounts
+/// an event needs.
+struct CurveTrade {
+    pool: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    user: Pubkey,
+}
+
+// Raydium Launchpad (LaunchLab) Processor
+pub struct RaydiumLaunchpadProcessor {
+    publisher: UnifiedPublisher,
+    whale_alerts: WhaleAlerts,
+    launch_funnel: LaunchFunnel,
+    symbols: SymbolRegistry,
+    usd_prices: UsdPrices,
+}
+
+impl RaydiumLaunchpadProcessor {
+    pub fn new(
+        publisher: UnifiedPublisher,
+        whale_alerts: WhaleAlerts,
+        launch_funnel: LaunchFunnel,
+        symbols: SymbolRegistry,
+        usd_prices: UsdPrices,
+    ) -> Self {
+        Self { publisher, whale_alerts, launch_funnel, symbols, usd_prices }
+    }
+}
+
+#[async_trait]
+impl Processor for RaydiumLaunchpadProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<RaydiumLaunchpadInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let transaction = &metadata.transaction_metadata;
+        let signature = transaction.signature.to_string();
+        let platform = "Raydium Launchpad".to_string();
+        let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction.data, &instruction.accounts));
+        let routed = timestamp.via_aggregator.is_some();
+
+        // The mints are only known from the instruction accounts, so the
+        // instructions are published, with the amounts of the TradeEvent
+        // self-CPI they emit, rather than the events on their own.
+        let mut ui_amounts = UiAmounts::new(transaction);
+        let mut decode_warnings = Vec::new();
+        let mut trade = None;
+        let mut token_symbol = None;
+        let mut new_pair = None;
+        let (event_type, mut details, mut funnel) = match &instruction.data {
+            RaydiumLaunchpadInstruction::Initialize(initialize) => {
+                let Some(accounts) = check_layout::<Initialize>(
+                    "Initialize",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    18..=18,
+                    |a| vec![("payer", a.payer, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                let params = &initialize.base_mint_param;
+                token_symbol = Some((params.symbol.clone(), accounts.base_mint));
+                ("new_pool", json!({
+                    "type": "Initialize",
+                    "mint": accounts.base_mint.to_string(),
+                    "quote_mint": accounts.quote_mint.to_string(),
+                    "curve": accounts.pool_state.to_string(),
+                    "creator": accounts.creator.to_string(),
+                    "platform_config": accounts.platform_config.to_string(),
+                    "name": params.name,
+                    "symbol": params.symbol,
+                    "uri": params.uri,
+                    "decimals": params.decimals
+                }), vec![(accounts.base_mint, LaunchSignal::Created)])
+            }
+            RaydiumLaunchpadInstruction::BuyExactIn(buy) => {
+                let Some(accounts) = check_layout::<BuyExactIn>(
+                    "BuyExactIn",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    TRADE_ACCOUNTS,
+                    |a| vec![("payer", a.payer, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                trade = Some(CurveTrade {
+                    pool: accounts.pool_state,
+                    base_mint: accounts.base_token_mint,
+                    quote_mint: accounts.quote_token_mint,
+                    user: accounts.payer,
+                });
+                ui_amounts.mint("amount_in", accounts.quote_token_mint);
+                ui_amounts.mint("minimum_amount_out", accounts.base_token_mint);
+                ("swap", json!({
+                    "type": "BuyExactIn",
+                    "amount_in": buy.amount_in,
+                    "minimum_amount_out": buy.minimum_amount_out,
+                    "share_fee_rate": buy.share_fee_rate
+                }), vec![(accounts.base_token_mint, LaunchSignal::Trade)])
+            }
+            RaydiumLaunchpadInstruction::BuyExactOut(buy) => {
+                let Some(accounts) = check_layout::<BuyExactOut>(
+                    "BuyExactOut",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    TRADE_ACCOUNTS,
+                    |a| vec![("payer", a.payer, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                trade = Some(CurveTrade {
+                    pool: accounts.pool_state,
+                    base_mint: accounts.base_token_mint,
+                    quote_mint: accounts.quote_token_mint,
+                    user: accounts.payer,
+                });
+                ui_amounts.mint("amount_out", accounts.base_token_mint);
+                ui_amounts.mint("maximum_amount_in", accounts.quote_token_mint);
+                ("swap", json!({
+                    "type": "BuyExactOut",
+                    "amount_out": buy.amount_out,
+                    "maximum_amount_in": buy.maximum_amount_in,
+                    "share_fee_rate": buy.share_fee_rate
+                }), vec![(accounts.base_token_mint, LaunchSignal::Trade)])
+            }
+            RaydiumLaunchpadInstruction::SellExactIn(sell) => {
+                let Some(accounts) = check_layout::<SellExactIn>(
+                    "SellExactIn",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    TRADE_ACCOUNTS,
+                    |a| vec![("payer", a.payer, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                trade = Some(CurveTrade {
+                    pool: accounts.pool_state,
+                    base_mint: accounts.base_token_mint,
+                    quote_mint: accounts.quote_token_mint,
+                    user: accounts.payer,
+                });
+                ui_amounts.mint("amount_in", accounts.base_token_mint);
+                ui_amounts.mint("minimum_amount_out", accounts.quote_token_mint);
+                ("swap", json!({
+                    "type": "SellExactIn",
+                    "amount_in": sell.amount_in,
+                    "minimum_amount_out": sell.minimum_amount_out,
+                    "share_fee_rate": sell.share_fee_rate
+                }), vec![(accounts.base_token_mint, LaunchSignal::Trade)])
+            }
+            RaydiumLaunchpadInstruction::SellExactOut(sell) => {
+                let Some(accounts) = check_layout::<SellExactOut>(
+                    "SellExactOut",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    TRADE_ACCOUNTS,
+                    |a| vec![("payer", a.payer, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                trade = Some(CurveTrade {
+                    pool: accounts.pool_state,
+                    base_mint: accounts.base_token_mint,
+                    quote_mint: accounts.quote_token_mint,
+                    user: accounts.payer,
+                });
+                ui_amounts.mint("amount_out", accounts.quote_token_mint);
+                ui_amounts.mint("maximum_amount_in", accounts.base_token_mint);
+                ("swap", json!({
+                    "type": "SellExactOut",
+                    "amount_out": sell.amount_out,
+                    "maximum_amount_in": sell.maximum_amount_in,
+                    "share_fee_rate": sell.share_fee_rate
+                }), vec![(accounts.base_token_mint, LaunchSignal::Trade)])
+            }
+            RaydiumLaunchpadInstruction::MigrateToAmm(_) => {
+                let Some(accounts) = check_layout::<MigrateToAmm>(
+                    "MigrateToAmm",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    32..=32,
+                    |_| Vec::new(),
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                new_pair = Some(NewPairEvent::new(
+                    accounts.amm_pool,
+                    (accounts.base_mint, Some(accounts.amm_base_vault)),
+                    (accounts.quote_mint, Some(accounts.amm_quote_vault)),
+                    None,
+                ));
+                ("new_pool", json!({
+                    "type": "MigrateToAmm",
+                    "mint": accounts.base_mint.to_string(),
+                    "quote_mint": accounts.quote_mint.to_string(),
+                    "curve": accounts.pool_state.to_string(),
+                    "pool": accounts.amm_pool.to_string(),
+                    "target": "Raydium AMM V4"
+                }), vec![
+                    (accounts.base_mint, LaunchSignal::Graduated),
+                    (accounts.base_mint, LaunchSignal::Migrated { pool: Some(accounts.amm_pool) }),
+                ])
+            }
+            RaydiumLaunchpadInstruction::MigrateToCpswap(_) => {
+                let Some(accounts) = check_layout::<MigrateToCpswap>(
+                    "MigrateToCpswap",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    28..=28,
+                    |_| Vec::new(),
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                new_pair = Some(NewPairEvent::new(
+                    accounts.cpswap_pool,
+                    (accounts.base_mint, Some(accounts.cpswap_base_vault)),
+                    (accounts.quote_mint, Some(accounts.cpswap_quote_vault)),
+                    None,
+                ));
+                ("new_pool", json!({
+                    "type": "MigrateToCpswap",
+                    "mint": accounts.base_mint.to_string(),
+                    "quote_mint": accounts.quote_mint.to_string(),
+                    "curve": accounts.pool_state.to_string(),
+                    "pool": accounts.cpswap_pool.to_string(),
+                    "target": "Raydium CPMM"
+                }), vec![
+                    (accounts.base_mint, LaunchSignal::Graduated),
+                    (accounts.base_mint, LaunchSignal::Migrated { pool: Some(accounts.cpswap_pool) }),
+                ])
+            }
+            _ => return Ok(()),
+        };
+
+        let mut fill = None;
+        if let Some(trade) = &trade {
+            details["pool"] = json!(trade.pool.to_string());
+            details["mint"] = json!(trade.base_mint.to_string());
+            details["quote_mint"] = json!(trade.quote_mint.to_string());
+            details["user"] = json!(trade.user.to_string());
+            if let Some(event) = trade_event(&nested, &trade.pool) {
+                let buy = event.trade_direction == TradeDirection::Buy;
+                let (input_mint, output_mint) = if buy {
+                    (trade.quote_mint, trade.base_mint)
+                } else {
+                    (trade.base_mint, trade.quote_mint)
+                };
+                if let Some(side) = TradeSide::from_mints(input_mint, output_mint, Some(trade.quote_mint)) {
+                    side.apply(&mut details);
+                }
+                details["curve"] = json!({
+                    "virtual_base": event.virtual_base,
+                    "virtual_quote": event.virtual_quote,
+                    "real_base": event.real_base_after,
+                    "real_quote": event.real_quote_after,
+                    "total_base_sell": event.total_base_sell,
+                    "protocol_fee": event.protocol_fee,
+                    "platform_fee": event.platform_fee,
+                    "share_fee": event.share_fee
+                });
+                // The trade that fills the curve leaves it waiting for migration
+                if buy && event.pool_status == PoolStatus::Migrate {
+                    funnel.push((trade.base_mint, LaunchSignal::Graduated));
+                }
+                fill = Some(SwapFill {
+                    input_mint,
+                    output_mint,
+                    input_amount: event.amount_in,
+                    output_amount: event.amount_out,
+                });
+            }
+        }
+
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(transaction);
+        }
+        if let (Some(fill), Some(trade)) = (&fill, &trade) {
+            let swap_event = SwapEvent::new(fill, Some(trade.pool), Some(trade.user));
+            swap_event.apply(transaction, &mut details);
+            self.usd_prices.apply(transaction, &swap_event, &mut details);
+        }
+        if let Some((symbol, mint)) = &token_symbol {
+            if self.symbols.annotate(symbol, mint, &mut details) {
+                log::warn!("{} token {} imitates the symbol {}", platform, mint, symbol);
+                metrics
+                    .increment_counter("symbol_collisions", 1)
+                    .await
+                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+            }
+        }
+        ui_amounts.apply(&mut details);
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(transaction, &mut details);
+        }
+
+        self.common_process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details)
+            .await?;
+
+        if let (Some(fill), Some(trade), false) = (&fill, &trade, routed) {
+            self.whale_alerts.check(&platform, transaction, Some(trade.user), fill).await;
+        }
+        for (mint, signal) in funnel {
+            for transition in self.launch_funnel.record(&platform, &mint, signal, transaction) {
+                self.common_process_event("launch_funnel", platform.clone(), signature.clone(), timestamp.clone(), transition)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The TradeEvent a trade instruction emits on `pool` through a self-CPI.
+fn trade_event(nested: &NestedInstructions, pool: &Pubkey) -> Option<TradeEvent> {
+    nested
+        .iter()
+        .filter(|nested_instruction| nested_instruction.instruction.program_id == PROGRAM_ID)
+        .find_map(|nested_instruction| {
+            match RaydiumLaunchpadDecoder.decode_instruction(&nested_instruction.instruction)?.data {
+                RaydiumLaunchpadInstruction::TradeEvent(event) if event.pool_state == *pool => Some(event),
+                _ => None,
+            }
+        })
+}
+
+/// Wallet behind each published instruction. Migrations are signed by the
+/// Raydium migration authority, not a trader.
+fn trader(instruction: &RaydiumLaunchpadInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<Pubkey> {
+    match instruction {
+        RaydiumLaunchpadInstruction::Initialize(_) => TraderAccount::At(1).find(accounts),
+        RaydiumLaunchpadInstruction::BuyExactIn(_)
+        | RaydiumLaunchpadInstruction::BuyExactOut(_)
+        | RaydiumLaunchpadInstruction::SellExactIn(_)
+        | RaydiumLaunchpadInstruction::SellExactOut(_) => TraderAccount::At(0).find(accounts),
+        _ => None,
+    }
+}
+
+impl CommonProcessor for RaydiumLaunchpadProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
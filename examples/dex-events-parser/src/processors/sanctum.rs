@@ -0,0 +1,309 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    serde_json::json,
+    std::sync::Arc,
+};
+
+use crate::{
+    alerts::whale::WhaleAlerts,
+    analytics::{
+        decimals::UiAmounts,
+        jito::jito_tip_annotation,
+        participation::ParticipationTracker,
+        price_index::PriceIndex,
+        side::TradeSide,
+        swap_event::SwapEvent,
+        trader::TraderAccount,
+        transfers::SwapTransfers,
+        usd_prices::UsdPrices,
+    },
+    decoders::sanctum::{
+        InfinityAddLiquidity, InfinityRemoveLiquidity, InfinitySwap, SanctumInfinityInstruction,
+        SanctumRouterInstruction,
+    },
+    publishers::UnifiedPublisher,
+};
+
+use super::{
+    layout::{check_layout, AccountRole},
+    CommonProcessor, EventTime,
+};
+
+/// Sanctum Infinity, a single pool holding many LSTs that swaps any of them
+/// for another at their SOL value.
+pub struct SanctumInfinityProcessor {
+    publisher: UnifiedPublisher,
+    price_index: PriceIndex,
+    whale_alerts: WhaleAlerts,
+    participation: ParticipationTracker,
+    usd_prices: UsdPrices,
+}
+
+impl SanctumInfinityProcessor {
+    pub fn new(
+        publisher: UnifiedPublisher,
+        price_index: PriceIndex,
+        whale_alerts: WhaleAlerts,
+        participation: ParticipationTracker,
+        usd_prices: UsdPrices,
+    ) -> Self {
+        Self { publisher, price_index, whale_alerts, participation, usd_prices }
+    }
+}
+
+#[async_trait]
+impl Processor for SanctumInfinityProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<SanctumInfinityInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let transaction = &metadata.transaction_metadata;
+        let signature = transaction.signature.to_string();
+        let platform = "Sanctum Infinity".to_string();
+        // Every decoded instruction is signed by the user first
+        let trader = match instruction.data {
+            SanctumInfinityInstruction::Unsupported { .. } => None,
+            _ => TraderAccount::At(0).find(&instruction.accounts),
+        };
+        let timestamp = EventTime::now(&metadata).with_trader(trader);
+        let routed = timestamp.via_aggregator.is_some();
+
+        let mut fill = None;
+        let mut transfers = None;
+        let mut trade = None;
+        let mut ui_amounts = UiAmounts::new(transaction);
+        let mut decode_warnings = Vec::new();
+        let (event_type, mut details) = match instruction.data {
+            SanctumInfinityInstruction::Swap(swap) => {
+                let mut details = if swap.exact_out {
+                    json!({
+                        "type": "SwapExactOut",
+                        "amount_out": swap.amount,
+                        "max_amount_in": swap.limit_amount
+                    })
+                } else {
+                    json!({
+                        "type": "SwapExactIn",
+                        "amount_in": swap.amount,
+                        "minimum_amount_out": swap.limit_amount
+                    })
+                };
+                details["src_lst_index"] = json!(swap.src_lst_index);
+                details["dst_lst_index"] = json!(swap.dst_lst_index);
+                if let Some(accounts) = check_layout::<InfinitySwap>(
+                    if swap.exact_out { "SwapExactOut" } else { "SwapExactIn" },
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    12..=usize::MAX,
+                    |a| vec![
+                        ("signer", a.signer, AccountRole::Signer),
+                        ("src_lst_acc", a.src_lst_acc, AccountRole::Writable),
+                        ("dst_lst_acc", a.dst_lst_acc, AccountRole::Writable),
+                    ],
+                    &mut decode_warnings,
+                ) {
+                    trade = Some((accounts.pool_state, accounts.signer));
+                    fill = self.price_index.observe(&platform, transaction, &accounts.src_lst_acc, &accounts.dst_lst_acc);
+                    transfers = SwapTransfers::find(transaction, &nested, &accounts.src_lst_acc, &accounts.dst_lst_acc);
+                    details["pool"] = json!(accounts.pool_state.to_string());
+                    details["input_mint"] = json!(accounts.src_lst_mint.to_string());
+                    details["output_mint"] = json!(accounts.dst_lst_mint.to_string());
+                    ui_amounts.mint("amount_in", accounts.src_lst_mint);
+                    ui_amounts.mint("max_amount_in", accounts.src_lst_mint);
+                    ui_amounts.mint("amount_out", accounts.dst_lst_mint);
+                    ui_amounts.mint("minimum_amount_out", accounts.dst_lst_mint);
+                }
+                ("swap", details)
+            }
+            SanctumInfinityInstruction::AddLiquidity(add) => {
+                let mut details = json!({
+                    "type": "add",
+                    "action": "AddLiquidity",
+                    "lst_index": add.lst_index,
+                    "lst_amount": add.lst_amount,
+                    "min_lp_out": add.min_lp_out
+                });
+                if let Some(accounts) = check_layout::<InfinityAddLiquidity>(
+                    "AddLiquidity",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    11..=usize::MAX,
+                    |a| vec![("signer", a.signer, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) {
+                    details["pool"] = json!(accounts.pool_state.to_string());
+                    details["user"] = json!(accounts.signer.to_string());
+                    details["mint"] = json!(accounts.lst_mint.to_string());
+                    details["lp_mint"] = json!(accounts.lp_token_mint.to_string());
+                    ui_amounts.mint("lst_amount", accounts.lst_mint);
+                    ui_amounts.mint("min_lp_out", accounts.lp_token_mint);
+                }
+                ("liquidity", details)
+            }
+            SanctumInfinityInstruction::RemoveLiquidity(remove) => {
+                let mut details = json!({
+                    "type": "remove",
+                    "action": "RemoveLiquidity",
+                    "lst_index": remove.lst_index,
+                    "lp_token_amount": remove.lp_token_amount,
+                    "min_lst_out": remove.min_lst_out
+                });
+                if let Some(accounts) = check_layout::<InfinityRemoveLiquidity>(
+                    "RemoveLiquidity",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    11..=usize::MAX,
+                    |a| vec![("signer", a.signer, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) {
+                    details["pool"] = json!(accounts.pool_state.to_string());
+                    details["user"] = json!(accounts.signer.to_string());
+                    details["mint"] = json!(accounts.lst_mint.to_string());
+                    details["lp_mint"] = json!(accounts.lp_token_mint.to_string());
+                    ui_amounts.mint("lp_token_amount", accounts.lp_token_mint);
+                    ui_amounts.mint("min_lst_out", accounts.lst_mint);
+                }
+                ("liquidity", details)
+            }
+            SanctumInfinityInstruction::Unsupported { .. } => return Ok(()),
+        };
+
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(transaction);
+        }
+        if let Some(side) = fill.as_ref().and_then(|fill| TradeSide::from_fill(fill, None)) {
+            side.apply(&mut details);
+        }
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        let executed = transfers.as_ref().and_then(SwapTransfers::fill).or_else(|| fill.clone());
+        if let (Some(executed), Some((pool, trader))) = (&executed, trade) {
+            let swap_event = SwapEvent::new(executed, Some(pool), Some(trader));
+            swap_event.apply(transaction, &mut details);
+            self.usd_prices.apply(transaction, &swap_event, &mut details);
+        }
+        ui_amounts.apply(&mut details);
+
+        self.common_process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details)
+            .await?;
+        if let (Some(fill), false) = (&fill, routed) {
+            self.whale_alerts.check(&platform, transaction, None, fill).await;
+        }
+        if let Some((pool, trader)) = trade {
+            self.participation.record(&platform, pool, trader, transaction);
+        }
+        self.publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl CommonProcessor for SanctumInfinityProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
+
+/// Sanctum's stake router, which turns SOL into an LST or one LST into
+/// another by depositing into and withdrawing from stake pools. There is no
+/// pool of its own, so its swaps carry no `pool`.
+pub struct SanctumRouterProcessor {
+    publisher: UnifiedPublisher,
+    price_index: PriceIndex,
+    whale_alerts: WhaleAlerts,
+    usd_prices: UsdPrices,
+}
+
+impl SanctumRouterProcessor {
+    pub fn new(
+        publisher: UnifiedPublisher,
+        price_index: PriceIndex,
+        whale_alerts: WhaleAlerts,
+        usd_prices: UsdPrices,
+    ) -> Self {
+        Self { publisher, price_index, whale_alerts, usd_prices }
+    }
+}
+
+#[async_trait]
+impl Processor for SanctumRouterProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<SanctumRouterInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, nested, _): Self::InputType,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let SanctumRouterInstruction::Swap(swap) = instruction.data else {
+            return Ok(());
+        };
+        let transaction = &metadata.transaction_metadata;
+        let signature = transaction.signature.to_string();
+        let platform = "Sanctum Router".to_string();
+        let timestamp = EventTime::now(&metadata).with_trader(TraderAccount::At(0).find(&instruction.accounts));
+        let routed = timestamp.via_aggregator.is_some();
+
+        let mut fill = None;
+        let mut transfers = None;
+        let mut trader = None;
+        let mut ui_amounts = UiAmounts::new(transaction);
+        let mut details = json!({
+            "type": format!("{:?}", swap.kind),
+            "amount_in": swap.amount
+        });
+        if let Some(accounts) = swap.arrange_accounts(&instruction.accounts) {
+            trader = Some(accounts.user);
+            fill = self.price_index.observe(&platform, transaction, &accounts.source, &accounts.destination);
+            transfers = SwapTransfers::find(transaction, &nested, &accounts.source, &accounts.destination);
+            details["input_mint"] = json!(accounts.source_mint.to_string());
+            details["output_mint"] = json!(accounts.destination_mint.to_string());
+            ui_amounts.mint("amount_in", accounts.source_mint);
+        }
+
+        details["jito_tip"] = jito_tip_annotation(transaction);
+        if let Some(side) = fill.as_ref().and_then(|fill| TradeSide::from_fill(fill, None)) {
+            side.apply(&mut details);
+        }
+        if let Some(transfers) = &transfers {
+            transfers.apply(&mut details);
+        }
+        let executed = transfers.as_ref().and_then(SwapTransfers::fill).or_else(|| fill.clone());
+        if let (Some(executed), Some(trader)) = (&executed, trader) {
+            let swap_event = SwapEvent::new(executed, None, Some(trader));
+            swap_event.apply(transaction, &mut details);
+            self.usd_prices.apply(transaction, &swap_event, &mut details);
+        }
+        ui_amounts.apply(&mut details);
+
+        self.common_process_event("swap", platform.clone(), signature, timestamp, details)
+            .await?;
+        if let (Some(fill), false) = (&fill, routed) {
+            self.whale_alerts.check(&platform, transaction, None, fill).await;
+        }
+
+        Ok(())
+    }
+}
+
+impl CommonProcessor for SanctumRouterProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
@@ -0,0 +1,221 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::{CarbonResult, Error},
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::{mpsc, oneshot},
+};
+
+use super::InstructionInput;
+
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+/// Events that can wait for a processor still busy with a slow one.
+const QUEUE_CAPACITY: usize = 1_024;
+
+/// What happens to an event whose processing exceeds the timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutPolicy {
+    /// The event keeps being processed in the background while the pipeline
+    /// moves on. Later events for the same processor queue behind it without
+    /// being awaited, up to a bound after which the pipeline waits.
+    Continue,
+    /// Processing is dropped at the next await point and the event is lost.
+    Cancel,
+}
+
+impl TimeoutPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeoutPolicy::Continue => "continue",
+            TimeoutPolicy::Cancel => "cancel",
+        }
+    }
+}
+
+/// Timeout applied to every processor invocation, so one stuck lookup
+/// cannot stall the pipeline.
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessorTimeouts {
+    timeout: Duration,
+    policy: TimeoutPolicy,
+}
+
+impl ProcessorTimeouts {
+    pub fn new(timeout: Duration, policy: TimeoutPolicy) -> Self {
+        Self { timeout, policy }
+    }
+
+    /// Reads `PROCESSOR_TIMEOUT_MS` (default `10000`) and
+    /// `PROCESSOR_TIMEOUT_POLICY` (`continue` or `cancel`, default
+    /// `continue`).
+    pub fn from_env() -> Self {
+        let timeout_ms = std::env::var("PROCESSOR_TIMEOUT_MS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_MS);
+        let policy = match std::env::var("PROCESSOR_TIMEOUT_POLICY").as_deref() {
+            Ok("cancel") => TimeoutPolicy::Cancel,
+            Ok("continue") | Err(_) => TimeoutPolicy::Continue,
+            Ok(other) => {
+                log::warn!("Unknown PROCESSOR_TIMEOUT_POLICY {}, using continue", other);
+                TimeoutPolicy::Continue
+            }
+        };
+        Self::new(Duration::from_millis(timeout_ms.max(1)), policy)
+    }
+
+    /// Runs `processor` on its own task behind the timeout.
+    pub fn wrap<T, P>(&self, platform: &'static str, processor: P) -> TimedProcessor<T>
+    where
+        T: Send + 'static,
+        P: Processor<InputType = InstructionInput<T>> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run_processor(platform, *self, processor, receiver));
+
+        TimedProcessor {
+            platform,
+            timeouts: *self,
+            sender,
+            background: None,
+        }
+    }
+}
+
+struct Job<T> {
+    input: InstructionInput<T>,
+    metrics: Arc<MetricsCollection>,
+    picked_up: oneshot::Sender<()>,
+    reply: oneshot::Sender<CarbonResult<()>>,
+}
+
+/// Processor handle returned by [`ProcessorTimeouts::wrap`]. Events are
+/// handed to the wrapped processor in order and awaited up to the timeout,
+/// which starts once the processor picks the event up rather than when it is
+/// queued.
+pub struct TimedProcessor<T> {
+    platform: &'static str,
+    timeouts: ProcessorTimeouts,
+    sender: mpsc::Sender<Job<T>>,
+    /// Result of the event that timed out under [`TimeoutPolicy::Continue`]
+    /// and is still being processed.
+    background: Option<oneshot::Receiver<CarbonResult<()>>>,
+}
+
+#[async_trait]
+impl<T: Send + 'static> Processor for TimedProcessor<T> {
+    type InputType = InstructionInput<T>;
+
+    async fn process(
+        &mut self,
+        input: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let signature = input.0.transaction_metadata.signature;
+        let (picked_up, started) = oneshot::channel();
+        let (reply, mut result) = oneshot::channel();
+        let stopped = || Error::Custom(format!("{} processor stopped", self.platform));
+
+        // While an event that timed out is still being processed, later
+        // events queue behind it without being awaited. This only blocks
+        // once the queue is full.
+        if let Some(background) = &mut self.background {
+            match background.try_recv() {
+                Err(oneshot::error::TryRecvError::Empty) => {
+                    return self
+                        .sender
+                        .send(Job { input, metrics, picked_up, reply })
+                        .await
+                        .map_err(|_| stopped());
+                }
+                Ok(Err(e)) => {
+                    log::error!("{} failed to process an event in the background: {:?}", self.platform, e);
+                }
+                Ok(Ok(())) | Err(oneshot::error::TryRecvError::Closed) => {}
+            }
+            self.background = None;
+        }
+
+        self.sender
+            .send(Job { input, metrics: metrics.clone(), picked_up, reply })
+            .await
+            .map_err(|_| stopped())?;
+        started.await.map_err(|_| stopped())?;
+        let started = Instant::now();
+
+        let Ok(result) = tokio::time::timeout(self.timeouts.timeout, &mut result).await else {
+            if self.timeouts.policy == TimeoutPolicy::Continue {
+                self.background = Some(result);
+            }
+            let elapsed = started.elapsed();
+            log::warn!(
+                "[SLOW_EVENT] [{}] [{}] still processing after {}ms, {}",
+                self.platform,
+                signature,
+                elapsed.as_millis(),
+                match self.timeouts.policy {
+                    TimeoutPolicy::Continue => "continuing in the background",
+                    TimeoutPolicy::Cancel => "cancelled",
+                }
+            );
+            metrics
+                .increment_counter(&format!("processor_timeouts_{}", self.timeouts.policy.as_str()), 1)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+            return Ok(());
+        };
+
+        result.map_err(|_| Error::Custom(format!("{} processor dropped the event", self.platform)))?
+    }
+}
+
+async fn run_processor<T, P>(
+    platform: &'static str,
+    timeouts: ProcessorTimeouts,
+    mut processor: P,
+    mut receiver: mpsc::Receiver<Job<T>>,
+) where
+    P: Processor<InputType = InstructionInput<T>>,
+{
+    while let Some(Job { input, metrics, picked_up, mut reply }) = receiver.recv().await {
+        // Events dropped while queued were cancelled by the caller.
+        if reply.is_closed() && timeouts.policy == TimeoutPolicy::Cancel {
+            continue;
+        }
+        let _ = picked_up.send(());
+
+        let signature = input.0.transaction_metadata.signature;
+        let started = Instant::now();
+        let result = match timeouts.policy {
+            TimeoutPolicy::Continue => processor.process(input, metrics.clone()).await,
+            TimeoutPolicy::Cancel => tokio::select! {
+                result = processor.process(input, metrics.clone()) => result,
+                _ = reply.closed() => continue,
+            },
+        };
+
+        let elapsed = started.elapsed();
+        if elapsed > timeouts.timeout {
+            log::warn!(
+                "[SLOW_EVENT] [{}] [{}] finished in {}ms",
+                platform,
+                signature,
+                elapsed.as_millis()
+            );
+            metrics
+                .record_histogram("processor_slow_event_milliseconds", elapsed.as_millis() as f64)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        }
+
+        if let Err(Err(e)) = reply.send(result) {
+            log::error!("{} failed to process {} in the background: {:?}", platform, signature, e);
+        }
+    }
+}
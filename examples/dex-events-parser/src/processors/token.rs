@@ -0,0 +1,128 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    serde_json::json,
+    std::sync::Arc,
+};
+
+use crate::{
+    analytics::{
+        balances::{token_account_mint, token_account_owner},
+        decimals::UiAmounts,
+        tracked_mints::TrackedMints,
+        transfers::TokenActivity,
+    },
+    publishers::UnifiedPublisher,
+    shard::Shard,
+};
+
+use super::{CommonProcessor, EventTime};
+
+/// Publishes the transfers, mints and burns of tracked mints as
+/// `token_transfer` events, whether made by the transaction itself or by a
+/// program it invoked.
+pub struct TokenTransferProcessor {
+    publisher: UnifiedPublisher,
+    tracked_mints: TrackedMints,
+    shard: Shard,
+}
+
+impl TokenTransferProcessor {
+    pub fn new(publisher: UnifiedPublisher, tracked_mints: TrackedMints, shard: Shard) -> Self {
+        Self { publisher, tracked_mints, shard }
+    }
+}
+
+#[async_trait]
+impl Processor for TokenTransferProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<TokenActivity>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, _, _): Self::InputType,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let transaction = &metadata.transaction_metadata;
+        let platform = if instruction.program_id == carbon_token_2022_decoder::PROGRAM_ID {
+            "Token-2022"
+        } else {
+            "SPL Token"
+        };
+
+        let (mint, authority, mut details) = match instruction.data {
+            TokenActivity::Transfer(transfer) => {
+                // Unchecked transfers only name the token accounts
+                let Some(mint) = transfer.mint.or_else(|| {
+                    token_account_mint(transaction, &transfer.source)
+                        .or_else(|| token_account_mint(transaction, &transfer.destination))
+                }) else {
+                    return Ok(());
+                };
+                let mut details = json!({
+                    "type": "Transfer",
+                    "source": transfer.source.to_string(),
+                    "destination": transfer.destination.to_string(),
+                    "amount": transfer.amount
+                });
+                let fee = transfer.withheld_fee(transaction);
+                if fee > 0 {
+                    details["fee"] = json!(fee);
+                    details["received"] = json!(transfer.amount.saturating_sub(fee));
+                }
+                if let Some(owner) = token_account_owner(transaction, &transfer.source) {
+                    details["source_owner"] = json!(owner.to_string());
+                }
+                if let Some(owner) = token_account_owner(transaction, &transfer.destination) {
+                    details["destination_owner"] = json!(owner.to_string());
+                }
+                (mint, transfer.authority, details)
+            }
+            TokenActivity::MintTo(change) => (change.mint, change.authority, json!({
+                "type": "MintTo",
+                "account": change.account.to_string(),
+                "amount": change.amount
+            })),
+            TokenActivity::Burn(change) => (change.mint, change.authority, json!({
+                "type": "Burn",
+                "account": change.account.to_string(),
+                "amount": change.amount
+            })),
+        };
+        // Tracked mints are subscribed to like programs, and split the same way
+        if !self.tracked_mints.tracks(&mint) || !self.shard.owns_program(&mint) {
+            return Ok(());
+        }
+        details["mint"] = json!(mint.to_string());
+        details["authority"] = json!(authority.to_string());
+
+        let mut ui_amounts = UiAmounts::new(transaction);
+        for field in ["amount", "fee", "received"] {
+            ui_amounts.mint(field, mint);
+        }
+        ui_amounts.apply(&mut details);
+
+        let timestamp = EventTime::now(&metadata).with_trader(Some(authority));
+        self.common_process_event(
+            "token_transfer",
+            platform.to_string(),
+            transaction.signature.to_string(),
+            timestamp,
+            details,
+        )
+        .await
+    }
+}
+
+impl CommonProcessor for TokenTransferProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
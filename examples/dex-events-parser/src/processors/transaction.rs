@@ -0,0 +1,422 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        collection::InstructionDecoderCollection,
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionDecoder},
+        metrics::MetricsCollection,
+        processor::Processor,
+        transaction::TransactionProcessorInputType,
+    },
+    carbon_drift_v2_decoder::{instructions::DriftInstruction, DriftDecoder},
+    carbon_fluxbeam_decoder::{instructions::FluxbeamInstruction, FluxbeamDecoder},
+    carbon_jupiter_swap_decoder::{instructions::JupiterSwapInstruction, JupiterSwapDecoder},
+    carbon_kamino_lending_decoder::{instructions::KaminoLendingInstruction, KaminoLendingDecoder},
+    carbon_lifinity_amm_v2_decoder::{instructions::LifinityAmmV2Instruction, LifinityAmmV2Decoder},
+    carbon_marginfi_v2_decoder::{instructions::MarginfiV2Instruction, MarginfiV2Decoder},
+    carbon_meteora_dlmm_decoder::{instructions::MeteoraDlmmInstruction, MeteoraDlmmDecoder},
+    carbon_meteora_pools_decoder::{instructions::MeteoraPoolsProgramInstruction, MeteoraPoolsDecoder},
+    carbon_moonshot_decoder::{instructions::MoonshotInstruction, MoonshotDecoder},
+    carbon_openbook_v2_decoder::{instructions::OpenbookV2Instruction, OpenbookV2Decoder},
+    carbon_orca_whirlpool_decoder::{instructions::OrcaWhirlpoolInstruction, OrcaWhirlpoolDecoder},
+    carbon_phoenix_v1_decoder::{instructions::PhoenixInstruction, PhoenixDecoder},
+    carbon_pump_swap_decoder::{instructions::PumpSwapInstruction, PumpSwapDecoder},
+    carbon_pumpfun_decoder::{instructions::PumpfunInstruction, PumpfunDecoder},
+    carbon_raydium_amm_v4_decoder::{instructions::RaydiumAmmV4Instruction, RaydiumAmmV4Decoder},
+    carbon_raydium_clmm_decoder::{instructions::RaydiumClmmInstruction, RaydiumClmmDecoder},
+    carbon_raydium_cpmm_decoder::{instructions::RaydiumCpmmInstruction, RaydiumCpmmDecoder},
+    carbon_raydium_launchpad_decoder::{instructions::RaydiumLaunchpadInstruction, RaydiumLaunchpadDecoder},
+    carbon_virtual_curve_decoder::{instructions::VirtualCurveInstruction, VirtualCurveDecoder},
+    serde::Serialize,
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+    std::sync::Arc,
+};
+
+use crate::{
+    decoders::{
+        invariant::{InvariantDecoder, InvariantInstruction},
+        obric::{ObricV2Decoder, ObricV2Instruction},
+        sanctum::{SanctumInfinityDecoder, SanctumInfinityInstruction, SanctumRouterDecoder, SanctumRouterInstruction},
+        zerofi::{ZeroFiDecoder, ZeroFiInstruction},
+    },
+    pipeline::Venue,
+    publishers::UnifiedPublisher,
+    shard::Shard,
+};
+
+use super::{CommonProcessor, EventTime};
+
+type LegDecoder = fn(&solana_instruction::Instruction) -> Option<GroupedInstruction>;
+
+/// Programs whose instructions become legs of a grouped transaction.
+const LEG_DECODERS: [(Pubkey, LegDecoder); 24] = [
+    (carbon_raydium_amm_v4_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(RaydiumAmmV4Decoder, ix)),
+    (carbon_raydium_clmm_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(RaydiumClmmDecoder, ix)),
+    (carbon_raydium_cpmm_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(RaydiumCpmmDecoder, ix)),
+    (carbon_jupiter_swap_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(JupiterSwapDecoder, ix)),
+    (carbon_orca_whirlpool_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(OrcaWhirlpoolDecoder, ix)),
+    (carbon_meteora_dlmm_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(MeteoraDlmmDecoder, ix)),
+    (carbon_meteora_pools_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(MeteoraPoolsDecoder, ix)),
+    (carbon_pumpfun_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(PumpfunDecoder, ix)),
+    (carbon_pump_swap_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(PumpSwapDecoder, ix)),
+    (carbon_openbook_v2_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(OpenbookV2Decoder, ix)),
+    (carbon_phoenix_v1_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(PhoenixDecoder, ix)),
+    (carbon_fluxbeam_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(FluxbeamDecoder, ix)),
+    (carbon_lifinity_amm_v2_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(LifinityAmmV2Decoder, ix)),
+    (carbon_moonshot_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(MoonshotDecoder, ix)),
+    (carbon_raydium_launchpad_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(RaydiumLaunchpadDecoder, ix)),
+    (carbon_drift_v2_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(DriftDecoder, ix)),
+    (carbon_virtual_curve_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(VirtualCurveDecoder, ix)),
+    (crate::decoders::invariant::PROGRAM_ID, |ix| GroupedInstruction::decode(InvariantDecoder, ix)),
+    (crate::decoders::sanctum::INFINITY_PROGRAM_ID, |ix| GroupedInstruction::decode(SanctumInfinityDecoder, ix)),
+    (crate::decoders::sanctum::ROUTER_PROGRAM_ID, |ix| GroupedInstruction::decode(SanctumRouterDecoder, ix)),
+    (crate::decoders::obric::PROGRAM_ID, |ix| GroupedInstruction::decode(ObricV2Decoder, ix)),
+    (crate::decoders::zerofi::PROGRAM_ID, |ix| GroupedInstruction::decode(ZeroFiDecoder, ix)),
+    (carbon_kamino_lending_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(KaminoLendingDecoder, ix)),
+    (carbon_marginfi_v2_decoder::PROGRAM_ID, |ix| GroupedInstruction::decode(MarginfiV2Decoder, ix)),
+];
+
+/// One decoded instruction of a grouped transaction.
+///
+/// The transaction pipe needs `Eq` and `Hash`, which not every decoder enum
+/// derives, so a leg keeps the instruction's name and arguments rather than
+/// the decoded enum.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct GroupedInstruction {
+    pub program: &'static str,
+    pub name: String,
+    /// Decoded arguments as JSON text. Integers above `u64`, such as price
+    /// limits, lose precision once parsed.
+    pub arguments: String,
+    pub flash_loan: Option<FlashLoanStep>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum FlashLoanStep {
+    Borrow,
+    Repay,
+}
+
+impl GroupedInstruction {
+    fn decode<D>(decoder: D, instruction: &solana_instruction::Instruction) -> Option<Self>
+    where
+        D: for<'a> InstructionDecoder<'a>,
+        for<'a> <D as InstructionDecoder<'a>>::InstructionType: LegInstruction,
+    {
+        let data = decoder.decode_instruction(instruction)?.data;
+        Some(Self {
+            program: program_name(&instruction.program_id),
+            name: data.name(),
+            arguments: arguments(&data),
+            flash_loan: data.flash_loan(),
+        })
+    }
+}
+
+impl InstructionDecoderCollection for GroupedInstruction {
+    type InstructionType = String;
+
+    fn parse_instruction(instruction: &solana_instruction::Instruction) -> Option<DecodedInstruction<Self>> {
+        let (_, decode) = LEG_DECODERS
+            .iter()
+            .find(|(program_id, _)| *program_id == instruction.program_id)?;
+        Some(DecodedInstruction {
+            program_id: instruction.program_id,
+            data: decode(instruction)?,
+            accounts: instruction.accounts.clone(),
+        })
+    }
+
+    fn get_type(&self) -> Self::InstructionType {
+        self.name.clone()
+    }
+}
+
+/// Name and flash loan role of a decoded instruction.
+trait LegInstruction: Serialize {
+    fn name(&self) -> String;
+
+    fn flash_loan(&self) -> Option<FlashLoanStep> {
+        None
+    }
+}
+
+/// Decoder crate enums are named after their `InstructionType` variant.
+macro_rules! leg_instruction {
+    ($($instruction:ty),+ $(,)?) => {$(
+        impl LegInstruction for $instruction {
+            fn name(&self) -> String {
+                variant_name(&self.get_instruction_type())
+            }
+        }
+    )+};
+}
+
+leg_instruction!(
+    RaydiumAmmV4Instruction,
+    RaydiumClmmInstruction,
+    RaydiumCpmmInstruction,
+    JupiterSwapInstruction,
+    OrcaWhirlpoolInstruction,
+    MeteoraDlmmInstruction,
+    MeteoraPoolsProgramInstruction,
+    PumpfunInstruction,
+    PumpSwapInstruction,
+    OpenbookV2Instruction,
+    PhoenixInstruction,
+    FluxbeamInstruction,
+    LifinityAmmV2Instruction,
+    MoonshotInstruction,
+    RaydiumLaunchpadInstruction,
+    DriftInstruction,
+    VirtualCurveInstruction,
+);
+
+impl LegInstruction for KaminoLendingInstruction {
+    fn name(&self) -> String {
+        variant_name(&self.get_instruction_type())
+    }
+
+    fn flash_loan(&self) -> Option<FlashLoanStep> {
+        match self {
+            KaminoLendingInstruction::FlashBorrowReserveLiquidity(_) => Some(FlashLoanStep::Borrow),
+            KaminoLendingInstruction::FlashRepayReserveLiquidity(_) => Some(FlashLoanStep::Repay),
+            _ => None,
+        }
+    }
+}
+
+impl LegInstruction for MarginfiV2Instruction {
+    fn name(&self) -> String {
+        variant_name(&self.get_instruction_type())
+    }
+
+    fn flash_loan(&self) -> Option<FlashLoanStep> {
+        match self {
+            MarginfiV2Instruction::LendingAccountStartFlashloan(_) => Some(FlashLoanStep::Borrow),
+            MarginfiV2Instruction::LendingAccountEndFlashloan(_) => Some(FlashLoanStep::Repay),
+            _ => None,
+        }
+    }
+}
+
+impl LegInstruction for InvariantInstruction {
+    fn name(&self) -> String {
+        match self {
+            InvariantInstruction::Swap(_) => "Swap",
+            InvariantInstruction::CreatePosition(_) => "CreatePosition",
+            InvariantInstruction::RemovePosition(_) => "RemovePosition",
+            InvariantInstruction::CreatePool(_) => "CreatePool",
+            InvariantInstruction::Unsupported { .. } => "Unsupported",
+        }
+        .to_string()
+    }
+}
+
+impl LegInstruction for SanctumInfinityInstruction {
+    fn name(&self) -> String {
+        match self {
+            SanctumInfinityInstruction::Swap(_) => "Swap",
+            SanctumInfinityInstruction::AddLiquidity(_) => "AddLiquidity",
+            SanctumInfinityInstruction::RemoveLiquidity(_) => "RemoveLiquidity",
+            SanctumInfinityInstruction::Unsupported { .. } => "Unsupported",
+        }
+        .to_string()
+    }
+}
+
+impl LegInstruction for SanctumRouterInstruction {
+    fn name(&self) -> String {
+        match self {
+            SanctumRouterInstruction::Swap(_) => "Swap",
+            SanctumRouterInstruction::Unsupported { .. } => "Unsupported",
+        }
+        .to_string()
+    }
+}
+
+impl LegInstruction for ObricV2Instruction {
+    fn name(&self) -> String {
+        match self {
+            ObricV2Instruction::Swap(_) => "Swap",
+            ObricV2Instruction::Unsupported { .. } => "Unsupported",
+        }
+        .to_string()
+    }
+}
+
+impl LegInstruction for ZeroFiInstruction {
+    fn name(&self) -> String {
+        match self {
+            ZeroFiInstruction::Swap(_) => "Swap",
+            ZeroFiInstruction::Unsupported { .. } => "Unsupported",
+        }
+        .to_string()
+    }
+}
+
+/// `InstructionType` enums have unit variants only, which serialize as
+/// their name.
+fn variant_name<T: Serialize>(instruction_type: &T) -> String {
+    match serde_json::to_value(instruction_type) {
+        Ok(Value::String(name)) => name,
+        _ => "Unknown".to_string(),
+    }
+}
+
+/// Arguments of a decoded instruction without its variant tag, as JSON
+/// text. Written with `to_string`, as `to_value` rejects `u128` values that
+/// do not fit a `u64`.
+fn arguments<T: Serialize>(instruction: &T) -> String {
+    let value = serde_json::to_string(instruction)
+        .ok()
+        .and_then(|text| serde_json::from_str::<Value>(&text).ok());
+    match value {
+        Some(Value::Object(variant)) if variant.len() == 1 => {
+            variant.into_iter().next().map(|(_, arguments)| arguments).unwrap_or(Value::Null)
+        }
+        _ => Value::Null,
+    }
+    .to_string()
+}
+
+/// Publishes every transaction with more than one decoded instruction as a
+/// single `transaction` event listing its legs in execution order, with
+/// their decoded arguments, so that multi-leg strategies such as a flash
+/// loan, swaps and the repayment are seen together.
+pub struct TransactionProcessor {
+    publisher: UnifiedPublisher,
+    shard: Shard,
+}
+
+impl TransactionProcessor {
+    pub fn new(publisher: UnifiedPublisher, shard: Shard) -> Self {
+        Self { publisher, shard }
+    }
+}
+
+#[async_trait]
+impl Processor for TransactionProcessor {
+    type InputType = TransactionProcessorInputType<GroupedInstruction>;
+
+    async fn process(
+        &mut self,
+        (transaction, instructions, _): Self::InputType,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        // Shards split transactions by the program of their first leg, so
+        // that only one of them publishes each transaction
+        let Some((_, first)) = instructions.first() else {
+            return Ok(());
+        };
+        if instructions.len() < 2 || !self.shard.owns_program(&first.program_id) {
+            return Ok(());
+        }
+
+        let mut venues = Vec::new();
+        let mut flash_loan = FlashLoan::default();
+        let legs = instructions
+            .iter()
+            .enumerate()
+            .map(|(leg, (metadata, instruction))| {
+                let grouped = &instruction.data;
+                if !venues.contains(&grouped.program) {
+                    venues.push(grouped.program);
+                }
+                flash_loan.observe(leg, grouped);
+                let path = metadata
+                    .absolute_path
+                    .iter()
+                    .map(|index| index.to_string())
+                    .collect::<Vec<_>>()
+                    .join(".");
+                json!({
+                    "program": grouped.program,
+                    "instruction": grouped.name,
+                    "arguments": serde_json::from_str::<Value>(&grouped.arguments).unwrap_or(Value::Null),
+                    "instruction_index": metadata.index,
+                    "path": path,
+                    "is_inner": metadata.stack_height > 1
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let details = json!({
+            "type": "Transaction",
+            "legs": legs,
+            "venues": venues,
+            "flash_loan": flash_loan.annotation()
+        });
+        self.common_process_event(
+            "transaction",
+            "Transaction".to_string(),
+            transaction.signature.to_string(),
+            EventTime::of_transaction(&transaction),
+            details,
+        )
+        .await
+    }
+}
+
+/// Platform of a venue, of a route-only market maker, or the lending
+/// program of a flash loan.
+fn program_name(program_id: &Pubkey) -> &'static str {
+    if *program_id == carbon_kamino_lending_decoder::PROGRAM_ID {
+        return "Kamino Lending";
+    }
+    if *program_id == carbon_marginfi_v2_decoder::PROGRAM_ID {
+        return "MarginFi V2";
+    }
+    if *program_id == crate::decoders::obric::PROGRAM_ID {
+        return "Obric V2";
+    }
+    if *program_id == crate::decoders::zerofi::PROGRAM_ID {
+        return "ZeroFi";
+    }
+    Venue::ALL
+        .iter()
+        .find(|venue| venue.program_id() == *program_id)
+        .map(Venue::platform)
+        .unwrap_or("Unknown")
+}
+
+/// Legs borrowing and repaying a flash loan, by leg position.
+#[derive(Debug, Default)]
+struct FlashLoan {
+    program: Option<&'static str>,
+    borrow: Option<usize>,
+    repay: Option<usize>,
+}
+
+impl FlashLoan {
+    fn observe(&mut self, leg: usize, instruction: &GroupedInstruction) {
+        match instruction.flash_loan {
+            Some(FlashLoanStep::Borrow) => {
+                self.program.get_or_insert(instruction.program);
+                self.borrow.get_or_insert(leg);
+            }
+            Some(FlashLoanStep::Repay) => {
+                self.program.get_or_insert(instruction.program);
+                self.repay = Some(leg);
+            }
+            None => {}
+        }
+    }
+
+    /// `null` when the transaction takes no flash loan.
+    fn annotation(&self) -> Value {
+        let Some(program) = self.program else {
+            return Value::Null;
+        };
+        json!({
+            "program": program,
+            "borrow_leg": self.borrow,
+            "repay_leg": self.repay
+        })
+    }
+}
+
+impl CommonProcessor for TransactionProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
@@ -0,0 +1,431 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionDecoder, InstructionMetadata, NestedInstructions},
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    carbon_virtual_curve_decoder::{
+        instructions::{
+            claim_trading_fee::ClaimTradingFee, evt_claim_trading_fee_event::EvtClaimTradingFeeEvent,
+            evt_swap_event::EvtSwapEvent, initialize_virtual_pool_with_spl_token::InitializeVirtualPoolWithSplToken,
+            initialize_virtual_pool_with_token2022::InitializeVirtualPoolWithToken2022,
+            migrate_meteora_damm::MigrateMeteoraDamm, migration_damm_v2::MigrationDammV2, swap::Swap,
+            VirtualCurveInstruction,
+        },
+        types::InitializePoolParameters,
+        VirtualCurveDecoder, PROGRAM_ID,
+    },
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+    std::sync::Arc,
+};
+
+use crate::{
+    alerts::whale::WhaleAlerts,
+    analytics::{
+        decimals::UiAmounts,
+        jito::jito_tip_annotation,
+        launch_funnel::{LaunchFunnel, LaunchSignal},
+        launchpads::LaunchpadConfigs,
+        new_pair::NewPairEvent,
+        price_index::SwapFill,
+        side::TradeSide,
+        swap_event::SwapEvent,
+        symbols::SymbolRegistry,
+        trader::TraderAccount,
+        usd_prices::UsdPrices,
+    },
+    publishers::UnifiedPublisher,
+};
+
+use super::{
+    layout::{check_layout, AccountRole},
+    CommonProcessor, EventTime,
+};
+
+/// `trade_direction` of a swap event selling the base token for the quote.
+const BASE_TO_QUOTE: u8 = 0;
+
+/// Swap on a bonding curve, with the accounts an event needs.
+struct CurveTrade {
+    pool: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    user: Pubkey,
+}
+
+// Meteora Dynamic Bonding Curve (Virtual Curve) Processor
+pub struct VirtualCurveProcessor {
+    publisher: UnifiedPublisher,
+    whale_alerts: WhaleAlerts,
+    launch_funnel: LaunchFunnel,
+    symbols: SymbolRegistry,
+    usd_prices: UsdPrices,
+    launchpads: LaunchpadConfigs,
+}
+
+impl VirtualCurveProcessor {
+    pub fn new(
+        publisher: UnifiedPublisher,
+        whale_alerts: WhaleAlerts,
+        launch_funnel: LaunchFunnel,
+        symbols: SymbolRegistry,
+        usd_prices: UsdPrices,
+        launchpads: LaunchpadConfigs,
+    ) -> Self {
+        Self { publisher, whale_alerts, launch_funnel, symbols, usd_prices, launchpads }
+    }
+}
+
+#[async_trait]
+impl Processor for VirtualCurveProcessor {
+    type InputType = (
+        InstructionMetadata,
+        DecodedInstruction<VirtualCurveInstruction>,
+        NestedInstructions,
+        solana_instruction::Instruction,
+    );
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, nested, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let transaction = &metadata.transaction_metadata;
+        let signature = transaction.signature.to_string();
+        let platform = "Virtual Curve".to_string();
+        let timestamp = EventTime::now(&metadata).with_trader(trader(&instruction.data, &instruction.accounts));
+        let routed = timestamp.via_aggregator.is_some();
+
+        // As with Raydium Launchpad, the mints are only in the instruction
+        // accounts, so instructions are published with the amounts of the
+        // events they emit through a self-CPI.
+        let mut ui_amounts = UiAmounts::new(transaction);
+        let mut decode_warnings = Vec::new();
+        let mut trade = None;
+        let mut token_symbol = None;
+        let mut new_pair = None;
+        let (event_type, mut details, config, mut funnel) = match &instruction.data {
+            VirtualCurveInstruction::InitializeVirtualPoolWithSplToken(initialize) => {
+                let Some(accounts) = check_layout::<InitializeVirtualPoolWithSplToken>(
+                    "InitializeVirtualPoolWithSplToken",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    16..=16,
+                    |a| vec![("creator", a.creator, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                token_symbol = Some((initialize.params.symbol.clone(), accounts.base_mint));
+                let details = launch_details(
+                    "InitializeVirtualPoolWithSplToken",
+                    &initialize.params,
+                    accounts.base_mint,
+                    accounts.quote_mint,
+                    accounts.pool,
+                    accounts.creator,
+                );
+                ("new_pool", details, accounts.config, vec![(accounts.base_mint, LaunchSignal::Created)])
+            }
+            VirtualCurveInstruction::InitializeVirtualPoolWithToken2022(initialize) => {
+                let Some(accounts) = check_layout::<InitializeVirtualPoolWithToken2022>(
+                    "InitializeVirtualPoolWithToken2022",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    14..=14,
+                    |a| vec![("creator", a.creator, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                token_symbol = Some((initialize.params.symbol.clone(), accounts.base_mint));
+                let details = launch_details(
+                    "InitializeVirtualPoolWithToken2022",
+                    &initialize.params,
+                    accounts.base_mint,
+                    accounts.quote_mint,
+                    accounts.pool,
+                    accounts.creator,
+                );
+                ("new_pool", details, accounts.config, vec![(accounts.base_mint, LaunchSignal::Created)])
+            }
+            VirtualCurveInstruction::Swap(swap) => {
+                let Some(accounts) = check_layout::<Swap>(
+                    "Swap",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    15..=usize::MAX,
+                    |a| vec![("payer", a.payer, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                trade = Some(CurveTrade {
+                    pool: accounts.pool,
+                    base_mint: accounts.base_mint,
+                    quote_mint: accounts.quote_mint,
+                    user: accounts.payer,
+                });
+                ("swap", json!({
+                    "type": "Swap",
+                    "amount_in": swap.params.amount_in,
+                    "minimum_amount_out": swap.params.minimum_amount_out
+                }), accounts.config, vec![(accounts.base_mint, LaunchSignal::Trade)])
+            }
+            VirtualCurveInstruction::MigrationDammV2(_) => {
+                let Some(accounts) = check_layout::<MigrationDammV2>(
+                    "MigrationDammV2",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    25..=usize::MAX,
+                    |_| Vec::new(),
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                new_pair = Some(NewPairEvent::new(
+                    accounts.pool,
+                    (accounts.base_mint, Some(accounts.token_a_vault)),
+                    (accounts.quote_mint, Some(accounts.token_b_vault)),
+                    None,
+                ));
+                ("new_pool", json!({
+                    "type": "MigrationDammV2",
+                    "mint": accounts.base_mint.to_string(),
+                    "quote_mint": accounts.quote_mint.to_string(),
+                    "curve": accounts.virtual_pool.to_string(),
+                    "pool": accounts.pool.to_string(),
+                    "target": "Meteora DAMM V2"
+                }), accounts.config, vec![
+                    (accounts.base_mint, LaunchSignal::Graduated),
+                    (accounts.base_mint, LaunchSignal::Migrated { pool: Some(accounts.pool) }),
+                ])
+            }
+            VirtualCurveInstruction::MigrateMeteoraDamm(_) => {
+                let Some(accounts) = check_layout::<MigrateMeteoraDamm>(
+                    "MigrateMeteoraDamm",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    31..=usize::MAX,
+                    |_| Vec::new(),
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                // Meteora Pools vaults are shared with every pool of the mint
+                new_pair = Some(NewPairEvent::new(
+                    accounts.pool,
+                    (accounts.token_a_mint, None),
+                    (accounts.token_b_mint, None),
+                    None,
+                ));
+                ("new_pool", json!({
+                    "type": "MigrateMeteoraDamm",
+                    "mint": accounts.token_a_mint.to_string(),
+                    "quote_mint": accounts.token_b_mint.to_string(),
+                    "curve": accounts.virtual_pool.to_string(),
+                    "pool": accounts.pool.to_string(),
+                    "target": "Meteora Pools"
+                }), accounts.config, vec![
+                    (accounts.token_a_mint, LaunchSignal::Graduated),
+                    (accounts.token_a_mint, LaunchSignal::Migrated { pool: Some(accounts.pool) }),
+                ])
+            }
+            VirtualCurveInstruction::ClaimTradingFee(claim) => {
+                let Some(accounts) = check_layout::<ClaimTradingFee>(
+                    "ClaimTradingFee",
+                    &instruction.accounts,
+                    metadata.stack_height,
+                    14..=14,
+                    |a| vec![("fee_claimer", a.fee_claimer, AccountRole::Signer)],
+                    &mut decode_warnings,
+                ) else {
+                    return self
+                        .publish_decode_warnings(&platform, &signature, timestamp, decode_warnings, &metrics)
+                        .await;
+                };
+                let mut details = json!({
+                    "type": "ClaimTradingFee",
+                    "action": "claim",
+                    "pool": accounts.pool.to_string(),
+                    "fee_claimer": accounts.fee_claimer.to_string(),
+                    "base_mint": accounts.base_mint.to_string(),
+                    "quote_mint": accounts.quote_mint.to_string(),
+                    "max_amount_a": claim.max_amount_a,
+                    "max_amount_b": claim.max_amount_b
+                });
+                if let Some(event) = claim_event(&nested, &accounts.pool) {
+                    details["token_base_amount"] = json!(event.token_base_amount);
+                    details["token_quote_amount"] = json!(event.token_quote_amount);
+                }
+                for field in ["max_amount_a", "token_base_amount"] {
+                    ui_amounts.mint(field, accounts.base_mint);
+                }
+                for field in ["max_amount_b", "token_quote_amount"] {
+                    ui_amounts.mint(field, accounts.quote_mint);
+                }
+                ("creator_fee", details, accounts.config, Vec::new())
+            }
+            _ => return Ok(()),
+        };
+        details["config"] = json!(config.to_string());
+        let launchpad = self.launchpads.name(&config).map(str::to_string);
+        if let Some(launchpad) = &launchpad {
+            details["launchpad"] = json!(launchpad);
+        }
+
+        let mut fill = None;
+        if let Some(trade) = &trade {
+            details["pool"] = json!(trade.pool.to_string());
+            details["mint"] = json!(trade.base_mint.to_string());
+            details["quote_mint"] = json!(trade.quote_mint.to_string());
+            details["user"] = json!(trade.user.to_string());
+            if let Some(event) = swap_event(&nested, &trade.pool) {
+                let (input_mint, output_mint) = if event.trade_direction == BASE_TO_QUOTE {
+                    (trade.base_mint, trade.quote_mint)
+                } else {
+                    (trade.quote_mint, trade.base_mint)
+                };
+                ui_amounts.mint("amount_in", input_mint);
+                ui_amounts.mint("minimum_amount_out", output_mint);
+                if let Some(side) = TradeSide::from_mints(input_mint, output_mint, Some(trade.quote_mint)) {
+                    side.apply(&mut details);
+                }
+                details["curve"] = json!({
+                    "next_sqrt_price": event.swap_result.next_sqrt_price,
+                    "trading_fee": event.swap_result.trading_fee,
+                    "protocol_fee": event.swap_result.protocol_fee,
+                    "referral_fee": event.swap_result.referral_fee
+                });
+                fill = Some(SwapFill {
+                    input_mint,
+                    output_mint,
+                    input_amount: event.swap_result.actual_input_amount,
+                    output_amount: event.swap_result.output_amount,
+                });
+            }
+            // The swap that fills the curve leaves it waiting for migration
+            if curve_completed(&nested, &trade.pool) {
+                details["curve_complete"] = json!(true);
+                funnel.push((trade.base_mint, LaunchSignal::Graduated));
+            }
+        }
+
+        if event_type == "swap" {
+            details["jito_tip"] = jito_tip_annotation(transaction);
+        }
+        if let (Some(fill), Some(trade)) = (&fill, &trade) {
+            let swap_event = SwapEvent::new(fill, Some(trade.pool), Some(trade.user));
+            swap_event.apply(transaction, &mut details);
+            self.usd_prices.apply(transaction, &swap_event, &mut details);
+        }
+        if let Some((symbol, mint)) = &token_symbol {
+            if self.symbols.annotate(symbol, mint, &mut details) {
+                log::warn!("{} token {} imitates the symbol {}", platform, mint, symbol);
+                metrics
+                    .increment_counter("symbol_collisions", 1)
+                    .await
+                    .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+            }
+        }
+        ui_amounts.apply(&mut details);
+        if let Some(new_pair) = &new_pair {
+            new_pair.apply(transaction, &mut details);
+        }
+
+        self.common_process_event(event_type, platform.clone(), signature.clone(), timestamp.clone(), details)
+            .await?;
+
+        if let (Some(fill), Some(trade), false) = (&fill, &trade, routed) {
+            self.whale_alerts.check(&platform, transaction, Some(trade.user), fill).await;
+        }
+        // Launches of a known launchpad go through the funnel under its name
+        let funnel_launchpad = launchpad.as_deref().unwrap_or(&platform);
+        for (mint, signal) in funnel {
+            for transition in self.launch_funnel.record(funnel_launchpad, &mint, signal, transaction) {
+                self.common_process_event("launch_funnel", platform.clone(), signature.clone(), timestamp.clone(), transition)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `new_pool` details of a curve creation, read as a token launch.
+fn launch_details(
+    instruction: &str,
+    params: &InitializePoolParameters,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    pool: Pubkey,
+    creator: Pubkey,
+) -> Value {
+    json!({
+        "type": instruction,
+        "mint": base_mint.to_string(),
+        "quote_mint": quote_mint.to_string(),
+        "curve": pool.to_string(),
+        "creator": creator.to_string(),
+        "name": params.name,
+        "symbol": params.symbol,
+        "uri": params.uri
+    })
+}
+
+/// Events the instruction emitted through a self-CPI.
+fn emitted_events(nested: &NestedInstructions) -> impl Iterator<Item = VirtualCurveInstruction> + '_ {
+    nested
+        .iter()
+        .filter(|nested_instruction| nested_instruction.instruction.program_id == PROGRAM_ID)
+        .filter_map(|nested_instruction| VirtualCurveDecoder.decode_instruction(&nested_instruction.instruction))
+        .map(|decoded| decoded.data)
+}
+
+fn swap_event(nested: &NestedInstructions, pool: &Pubkey) -> Option<EvtSwapEvent> {
+    emitted_events(nested).find_map(|event| match event {
+        VirtualCurveInstruction::EvtSwapEvent(event) if event.pool == *pool => Some(event),
+        _ => None,
+    })
+}
+
+fn curve_completed(nested: &NestedInstructions, pool: &Pubkey) -> bool {
+    emitted_events(nested)
+        .any(|event| matches!(event, VirtualCurveInstruction::EvtCurveCompleteEvent(event) if event.pool == *pool))
+}
+
+fn claim_event(nested: &NestedInstructions, pool: &Pubkey) -> Option<EvtClaimTradingFeeEvent> {
+    emitted_events(nested).find_map(|event| match event {
+        VirtualCurveInstruction::EvtClaimTradingFeeEvent(event) if event.pool == *pool => Some(event),
+        _ => None,
+    })
+}
+
+/// Wallet behind each published instruction. Migrations are run by
+/// keepers, not traders.
+fn trader(instruction: &VirtualCurveInstruction, accounts: &[solana_instruction::AccountMeta]) -> Option<Pubkey> {
+    let account = match instruction {
+        VirtualCurveInstruction::InitializeVirtualPoolWithSplToken(_)
+        | VirtualCurveInstruction::InitializeVirtualPoolWithToken2022(_) => TraderAccount::At(2),
+        VirtualCurveInstruction::Swap(_) | VirtualCurveInstruction::ClaimTradingFee(_) => TraderAccount::At(9),
+        _ => return None,
+    };
+    account.find(accounts)
+}
+
+impl CommonProcessor for VirtualCurveProcessor {
+    fn get_publisher(&self) -> &UnifiedPublisher { &self.publisher }
+}
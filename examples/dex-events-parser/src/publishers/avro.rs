@@ -0,0 +1,334 @@
+use {
+    super::{common::DexEventData, error::PublishErrorKind, serialization::PayloadFormat},
+    apache_avro::{types::Record, Schema},
+    serde_json::json,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+};
+
+const DEFAULT_TOPICS: &str = "dex_events";
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+const CONTENT_TYPE: &str = "application/vnd.schemaregistry.v1+json";
+/// First byte of the Confluent wire format, followed by the schema ID.
+const MAGIC_BYTE: u8 = 0;
+
+/// Avro schema of [`DexEventData`], registered as written so the defaults
+/// count in compatibility checks. Details differ per event type, so they
+/// are kept as a JSON string, like the typed payload.
+const EVENT_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "DexEvent",
+    "namespace": "carbon.dex_events",
+    "fields": [
+        {"name": "event_type", "type": "string"},
+        {"name": "platform", "type": "string"},
+        {"name": "signature", "type": "string"},
+        {"name": "timestamp", "type": "long"},
+        {"name": "ingested_at", "type": ["null", "long"], "default": null},
+        {"name": "block_time", "type": ["null", "long"], "default": null},
+        {"name": "slot", "type": ["null", "long"], "default": null},
+        {"name": "tx_index", "type": ["null", "long"], "default": null},
+        {"name": "instruction_index", "type": ["null", "int"], "default": null},
+        {"name": "is_inner", "type": ["null", "boolean"], "default": null},
+        {"name": "fee_payer", "type": ["null", "string"], "default": null},
+        {"name": "trader", "type": ["null", "string"], "default": null},
+        {"name": "details", "type": "string"},
+        {"name": "payload", "type": ["null", "string"], "default": null}
+    ]
+}"#;
+
+#[derive(Debug)]
+pub enum SchemaRegistryError {
+    Config(String),
+    /// The registry could not be reached.
+    Request { subject: String, source: reqwest::Error },
+    /// The registry answered with an error status.
+    Status { subject: String, status: u16, body: String },
+    /// The event schema cannot evolve from the latest version of the
+    /// subject.
+    Incompatible { subject: String },
+    /// Auto-registration is off and the subject has no such schema.
+    NotRegistered { subject: String },
+    Serialization(serde_json::Error),
+    Encoding(Box<apache_avro::Error>),
+}
+
+impl SchemaRegistryError {
+    pub fn kind(&self) -> PublishErrorKind {
+        match self {
+            SchemaRegistryError::Config(_) | SchemaRegistryError::Request { .. } => PublishErrorKind::Connection,
+            SchemaRegistryError::Status { status, .. } if *status == 429 || *status >= 500 => {
+                PublishErrorKind::Connection
+            }
+            SchemaRegistryError::Status { .. }
+            | SchemaRegistryError::Incompatible { .. }
+            | SchemaRegistryError::NotRegistered { .. } => PublishErrorKind::Rejected,
+            SchemaRegistryError::Serialization(_) | SchemaRegistryError::Encoding(_) => PublishErrorKind::Serialization,
+        }
+    }
+}
+
+impl std::fmt::Display for SchemaRegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SchemaRegistryError::Config(e) => write!(f, "Schema Registry Error: {}", e),
+            SchemaRegistryError::Request { subject, source } => {
+                write!(f, "Schema Registry Error: Request for {} failed: {}", subject, source)
+            }
+            SchemaRegistryError::Status { subject, status, body } => {
+                write!(f, "Schema Registry Error: {} answered with status {}: {}", subject, status, body)
+            }
+            SchemaRegistryError::Incompatible { subject } => {
+                write!(f, "Schema Registry Error: Event schema is incompatible with the latest version of {}", subject)
+            }
+            SchemaRegistryError::NotRegistered { subject } => {
+                write!(f, "Schema Registry Error: Event schema is not registered under {}", subject)
+            }
+            SchemaRegistryError::Serialization(e) => write!(f, "Schema Registry Error: Failed to serialize details: {}", e),
+            SchemaRegistryError::Encoding(e) => write!(f, "Schema Registry Error: Failed to encode Avro: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SchemaRegistryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SchemaRegistryError::Request { source, .. } => Some(source),
+            SchemaRegistryError::Serialization(e) => Some(e),
+            SchemaRegistryError::Encoding(e) => Some(e.as_ref()),
+            SchemaRegistryError::Config(_)
+            | SchemaRegistryError::Status { .. }
+            | SchemaRegistryError::Incompatible { .. }
+            | SchemaRegistryError::NotRegistered { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AvroConfig {
+    pub registry_url: String,
+    /// Username and password.
+    pub credentials: Option<(String, String)>,
+    /// Registers the event schema under subjects that do not have it,
+    /// otherwise it must already be registered.
+    pub auto_register: bool,
+    /// Topics whose subjects are checked when connecting, others are on
+    /// their first event.
+    pub topics: Vec<String>,
+    pub timeout: Duration,
+}
+
+impl AvroConfig {
+    /// Reads `SCHEMA_REGISTRY_URL` (required), `SCHEMA_REGISTRY_USERNAME`
+    /// and `SCHEMA_REGISTRY_PASSWORD`, `SCHEMA_REGISTRY_AUTO_REGISTER`
+    /// (default `true`), `SCHEMA_REGISTRY_TOPICS` (comma separated, default
+    /// `dex_events`) and `SCHEMA_REGISTRY_TIMEOUT_MS` (default `5000`).
+    pub fn from_env() -> Result<Self, String> {
+        let registry_url = std::env::var("SCHEMA_REGISTRY_URL")
+            .map_err(|_| "SCHEMA_REGISTRY_URL is not set".to_string())?
+            .trim_end_matches('/')
+            .to_string();
+        let credentials = match (std::env::var("SCHEMA_REGISTRY_USERNAME"), std::env::var("SCHEMA_REGISTRY_PASSWORD")) {
+            (Ok(username), password) => Some((username, password.unwrap_or_default())),
+            (Err(_), _) => None,
+        };
+        let timeout_ms = match std::env::var("SCHEMA_REGISTRY_TIMEOUT_MS") {
+            Ok(value) => value
+                .parse()
+                .map_err(|e| format!("Invalid SCHEMA_REGISTRY_TIMEOUT_MS {}: {}", value, e))?,
+            Err(_) => DEFAULT_TIMEOUT_MS,
+        };
+
+        Ok(Self {
+            registry_url,
+            credentials,
+            auto_register: std::env::var("SCHEMA_REGISTRY_AUTO_REGISTER")
+                .map_or(true, |value| value != "false" && value != "0"),
+            topics: std::env::var("SCHEMA_REGISTRY_TOPICS")
+                .unwrap_or_else(|_| DEFAULT_TOPICS.to_string())
+                .split(',')
+                .map(str::trim)
+                .filter(|topic| !topic.is_empty())
+                .map(str::to_string)
+                .collect(),
+            timeout: Duration::from_millis(timeout_ms),
+        })
+    }
+}
+
+/// Subject of the values of `topic`, after the registry's default topic
+/// name strategy.
+fn subject(topic: &str) -> String {
+    format!("{}-value", topic)
+}
+
+struct AvroInner {
+    client: reqwest::Client,
+    config: AvroConfig,
+    schema: Schema,
+    /// Schema ID by topic.
+    ids: Mutex<HashMap<String, u32>>,
+}
+
+/// Encodes events as Avro in the Confluent wire format: a zero byte, the
+/// big-endian schema ID and the Avro binary of the event.
+///
+/// Each topic has its own subject, `<topic>-value`. Before the first event
+/// of a topic, the event schema is checked against the latest version of
+/// the subject and then registered, or looked up when `auto_register` is
+/// off, and the ID kept for the following events. The topics of
+/// `AvroConfig::topics` are checked when connecting so an incompatible
+/// schema stops startup.
+#[derive(Clone)]
+pub struct AvroSerializer {
+    inner: Arc<AvroInner>,
+}
+
+impl AvroSerializer {
+    pub async fn connect(config: AvroConfig) -> Result<Self, SchemaRegistryError> {
+        let schema = Schema::parse_str(EVENT_SCHEMA).map_err(|e| SchemaRegistryError::Encoding(Box::new(e)))?;
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| SchemaRegistryError::Config(format!("Failed to build the HTTP client: {}", e)))?;
+
+        let serializer = Self {
+            inner: Arc::new(AvroInner {
+                client,
+                schema,
+                config,
+                ids: Mutex::new(HashMap::new()),
+            }),
+        };
+        for topic in &serializer.inner.config.topics {
+            serializer.schema_id(topic).await?;
+        }
+        Ok(serializer)
+    }
+
+    pub async fn encode(&self, topic: &str, data: &DexEventData, format: &PayloadFormat) -> Result<Vec<u8>, SchemaRegistryError> {
+        let id = self.schema_id(topic).await?;
+        // The details follow the payload format like the JSON publishers
+        let value = format.encode_value(data).map_err(SchemaRegistryError::Serialization)?;
+        let details = value["details"].to_string();
+        let typed = value.get("payload").map(|payload| payload.to_string());
+
+        let mut record = Record::new(&self.inner.schema).expect("the event schema is a record");
+        record.put("event_type", data.event_type.clone());
+        record.put("platform", data.platform.clone());
+        record.put("signature", data.signature.clone());
+        record.put("timestamp", data.timestamp as i64);
+        record.put("ingested_at", data.ingested_at.map(|ingested_at| ingested_at as i64));
+        record.put("block_time", data.block_time);
+        record.put("slot", data.slot.map(|slot| slot as i64));
+        record.put("tx_index", data.tx_index.map(|index| index as i64));
+        record.put("instruction_index", data.instruction_index.map(|index| index as i32));
+        record.put("is_inner", data.is_inner);
+        record.put("fee_payer", data.fee_payer.clone());
+        record.put("trader", data.trader.clone());
+        record.put("details", details);
+        record.put("payload", typed);
+
+        let mut payload = vec![MAGIC_BYTE];
+        payload.extend_from_slice(&id.to_be_bytes());
+        payload.extend(apache_avro::to_avro_datum(&self.inner.schema, record).map_err(|e| SchemaRegistryError::Encoding(Box::new(e)))?);
+        Ok(payload)
+    }
+
+    async fn schema_id(&self, topic: &str) -> Result<u32, SchemaRegistryError> {
+        let cached = self.inner.ids.lock().unwrap_or_else(|e| e.into_inner()).get(topic).copied();
+        if let Some(id) = cached {
+            return Ok(id);
+        }
+        // Concurrent first events may both resolve, registering is idempotent
+        let subject = subject(topic);
+        self.inner.check_compatibility(&subject).await?;
+        let id = if self.inner.config.auto_register {
+            self.inner.register(&subject).await?
+        } else {
+            self.inner.lookup(&subject).await?
+        };
+        log::info!("[SCHEMA REGISTRY] {} uses schema {}", subject, id);
+        self.inner.ids.lock().unwrap_or_else(|e| e.into_inner()).insert(topic.to_string(), id);
+        Ok(id)
+    }
+}
+
+impl AvroInner {
+    /// Fails when the latest version of `subject` cannot evolve into the
+    /// event schema under the subject's compatibility level. A subject
+    /// without versions accepts any schema.
+    async fn check_compatibility(&self, subject: &str) -> Result<(), SchemaRegistryError> {
+        let path = format!("/compatibility/subjects/{}/versions/latest", subject);
+        let Some(response) = self.post(subject, &path).await? else {
+            return Ok(());
+        };
+        if response["is_compatible"].as_bool() == Some(true) {
+            Ok(())
+        } else {
+            Err(SchemaRegistryError::Incompatible {
+                subject: subject.to_string(),
+            })
+        }
+    }
+
+    async fn register(&self, subject: &str) -> Result<u32, SchemaRegistryError> {
+        let path = format!("/subjects/{}/versions", subject);
+        let response = self.post(subject, &path).await?.unwrap_or_default();
+        self.id(subject, &response)
+    }
+
+    async fn lookup(&self, subject: &str) -> Result<u32, SchemaRegistryError> {
+        let path = format!("/subjects/{}", subject);
+        match self.post(subject, &path).await? {
+            Some(response) => self.id(subject, &response),
+            None => Err(SchemaRegistryError::NotRegistered {
+                subject: subject.to_string(),
+            }),
+        }
+    }
+
+    fn id(&self, subject: &str, response: &serde_json::Value) -> Result<u32, SchemaRegistryError> {
+        response["id"]
+            .as_u64()
+            .and_then(|id| u32::try_from(id).ok())
+            .ok_or_else(|| SchemaRegistryError::Status {
+                subject: subject.to_string(),
+                status: 200,
+                body: format!("no schema ID in {}", response),
+            })
+    }
+
+    /// Posts the event schema to `path`, `None` when the subject or its
+    /// schema is not found.
+    async fn post(&self, subject: &str, path: &str) -> Result<Option<serde_json::Value>, SchemaRegistryError> {
+        let mut request = self
+            .client
+            .post(format!("{}{}", self.config.registry_url, path))
+            .header("Content-Type", CONTENT_TYPE)
+            .body(json!({ "schema": EVENT_SCHEMA }).to_string());
+        if let Some((username, password)) = &self.config.credentials {
+            request = request.basic_auth(username, Some(password));
+        }
+
+        let request_error = |source| SchemaRegistryError::Request {
+            subject: subject.to_string(),
+            source,
+        };
+        let response = request.send().await.map_err(request_error)?;
+        let status = response.status().as_u16();
+        let body = response.text().await.map_err(request_error)?;
+        match status {
+            200..=299 => serde_json::from_str(&body).map(Some).map_err(SchemaRegistryError::Serialization),
+            404 => Ok(None),
+            status => Err(SchemaRegistryError::Status {
+                subject: subject.to_string(),
+                status,
+                body,
+            }),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use {
+    super::common::DexEventData,
+    std::{sync::Mutex, time::Duration},
+};
+
+/// When buffered events are handed to the sink in one batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchConfig {
+    pub max_events: usize,
+    pub flush_interval: Duration,
+}
+
+impl BatchConfig {
+    /// Reads `PUBLISH_BATCH_SIZE` and `PUBLISH_BATCH_INTERVAL_MS` (default
+    /// `100`). `None` when the batch size is unset or `0`, which publishes
+    /// each event as it arrives.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let number = |name: &str, default: u64| match std::env::var(name) {
+            Ok(value) => value.parse::<u64>().map_err(|e| format!("Invalid {}: {}", name, e)),
+            Err(_) => Ok(default),
+        };
+        let max_events = number("PUBLISH_BATCH_SIZE", 0)? as usize;
+        if max_events == 0 {
+            return Ok(None);
+        }
+        let flush_interval = number("PUBLISH_BATCH_INTERVAL_MS", 100)?;
+        if flush_interval == 0 {
+            return Err("PUBLISH_BATCH_INTERVAL_MS must be positive".to_string());
+        }
+        Ok(Some(Self {
+            max_events,
+            flush_interval: Duration::from_millis(flush_interval),
+        }))
+    }
+}
+
+/// Events waiting for the next flush, with their topics, in arrival order.
+#[derive(Debug)]
+pub struct EventBatch {
+    max_events: usize,
+    pending: Mutex<Vec<(String, DexEventData)>>,
+}
+
+impl EventBatch {
+    pub fn new(max_events: usize) -> Self {
+        Self {
+            max_events,
+            pending: Mutex::new(Vec::with_capacity(max_events)),
+        }
+    }
+
+    /// Buffers an event. Returns true once the batch is full.
+    pub fn push(&self, topic: &str, data: &DexEventData) -> bool {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.push((topic.to_string(), data.clone()));
+        pending.len() >= self.max_events
+    }
+
+    /// Takes every buffered event.
+    pub fn take(&self) -> Vec<(String, DexEventData)> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::replace(&mut *pending, Vec::with_capacity(self.max_events))
+    }
+}
+
@@ -0,0 +1,172 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use serde_json::json;
+use super::common::DexEventData;
+
+const DEFAULT_TOPIC: &str = "block_summaries";
+const DEFAULT_DELAY_SLOTS: u64 = 2;
+
+#[derive(Default)]
+struct BlockTotals {
+    block_time: Option<i64>,
+    events: u64,
+    swaps: BTreeMap<String, u64>,
+    /// Raw quote amount traded per quote mint.
+    volume: BTreeMap<String, u128>,
+    new_pools: u64,
+}
+
+impl BlockTotals {
+    fn record(&mut self, data: &DexEventData) {
+        self.block_time = self.block_time.or(data.block_time);
+        self.events += 1;
+
+        match data.event_type.as_str() {
+            "swap" => {
+                *self.swaps.entry(data.platform.clone()).or_default() += 1;
+                // The quote leg is what the taker spent on a buy and received on a sell
+                let quote_amount = match data.details["side"].as_str() {
+                    Some("buy") => data.detail_u64("amount_in"),
+                    Some("sell") => data.detail_u64("amount_out"),
+                    _ => None,
+                };
+                if let (Some(quote_mint), Some(amount)) = (data.details["quote_mint"].as_str(), quote_amount) {
+                    *self.volume.entry(quote_mint.to_string()).or_default() += amount as u128;
+                }
+            }
+            "new_pool" => self.new_pools += 1,
+            _ => {}
+        }
+    }
+
+    fn into_event(self, slot: u64) -> DexEventData {
+        // Lists rather than maps keyed by platform or mint, since payload
+        // key transforms would rewrite the names and addresses
+        let swaps_by_platform = self
+            .swaps
+            .iter()
+            .map(|(platform, count)| json!({ "platform": platform, "count": count }))
+            .collect::<Vec<_>>();
+        let volume = self
+            .volume
+            .into_iter()
+            .map(|(mint, amount)| json!({ "mint": mint, "amount": amount.to_string() }))
+            .collect::<Vec<_>>();
+
+        DexEventData {
+            event_type: "block_summary".to_string(),
+            platform: String::new(),
+            signature: String::new(),
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            block_time: self.block_time,
+            slot: Some(slot),
+            details: json!({
+                "slot": slot,
+                "block_time": self.block_time,
+                "events": self.events,
+                "swaps": self.swaps.values().sum::<u64>(),
+                "swaps_by_platform": swaps_by_platform,
+                "volume_by_quote_mint": volume,
+                "new_pools": self.new_pools
+            }),
+            ingested_at: None,
+            tx_index: None,
+            instruction_index: None,
+            is_inner: None,
+            fee_payer: None,
+            trader: None,
+            payload: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Blocks {
+    open: BTreeMap<u64, BlockTotals>,
+    newest_slot: u64,
+    /// Highest slot already summarized, later events for it are too late.
+    summarized_through: u64,
+}
+
+/// Per-block totals of what a sink published, emitted as one
+/// `block_summary` event per block on a dedicated topic.
+///
+/// Blocks are processed concurrently, so a block is only summarized once
+/// an event `BLOCK_SUMMARY_DELAY_SLOTS` slots newer has been seen. Blocks
+/// without any event get no summary.
+#[derive(Clone)]
+pub struct BlockSummaries {
+    topic: String,
+    delay_slots: u64,
+    blocks: Arc<Mutex<Blocks>>,
+}
+
+impl BlockSummaries {
+    /// Reads `BLOCK_SUMMARIES` (`true`/`1` to enable, default off),
+    /// `BLOCK_SUMMARY_TOPIC` (default `block_summaries`) and
+    /// `BLOCK_SUMMARY_DELAY_SLOTS` (default `2`).
+    pub fn from_env() -> Option<Self> {
+        if !matches!(std::env::var("BLOCK_SUMMARIES").as_deref(), Ok("true") | Ok("1")) {
+            return None;
+        }
+        let delay_slots = std::env::var("BLOCK_SUMMARY_DELAY_SLOTS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_DELAY_SLOTS);
+
+        Some(Self::new(
+            std::env::var("BLOCK_SUMMARY_TOPIC").unwrap_or_else(|_| DEFAULT_TOPIC.to_string()),
+            delay_slots,
+        ))
+    }
+
+    pub fn new(topic: impl Into<String>, delay_slots: u64) -> Self {
+        Self {
+            topic: topic.into(),
+            delay_slots,
+            blocks: Arc::default(),
+        }
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Adds a published event to its block. Returns the summaries of the
+    /// blocks that are now complete, oldest first.
+    pub fn record(&self, data: &DexEventData) -> Vec<DexEventData> {
+        let Some(slot) = data.slot else {
+            return Vec::new();
+        };
+        if data.event_type == "block_summary" {
+            return Vec::new();
+        }
+
+        let mut blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+        if slot <= blocks.summarized_through {
+            log::debug!("Event {} for already summarized slot {}", data.signature, slot);
+            return Vec::new();
+        }
+        // Failed transactions still move the blocks along but add no totals
+        if !data.is_failed() {
+            blocks.open.entry(slot).or_default().record(data);
+        }
+        blocks.newest_slot = blocks.newest_slot.max(slot);
+
+        let complete_through = blocks.newest_slot.saturating_sub(self.delay_slots);
+        let still_open = blocks.open.split_off(&(complete_through + 1));
+        let complete = std::mem::replace(&mut blocks.open, still_open);
+        blocks.summarized_through = blocks.summarized_through.max(complete_through);
+
+        complete
+            .into_iter()
+            .map(|(slot, totals)| totals.into_event(slot))
+            .collect()
+    }
+}
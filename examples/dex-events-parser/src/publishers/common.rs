@@ -1,10 +1,3 @@
-use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DexEventData {
-    pub event_type: String,  // "swap", "mint_burn", "liquidity", "new_pool"
-    pub platform: String,
-    pub signature: String,
-    pub timestamp: u64,
-    pub details: serde_json::Value,
-} 
\ No newline at end of file
+// The event envelope is shared with the typed subscribers so both sides
+// always agree on the schema.
+pub use carbon_dex_events_client::{DexEventData, DexEventPayload};
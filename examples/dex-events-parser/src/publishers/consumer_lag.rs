@@ -0,0 +1,152 @@
+use {
+    carbon_core::metrics::Metrics,
+    super::kafka_config::client_config_from_env,
+    rdkafka::{
+        consumer::{BaseConsumer, Consumer},
+        error::KafkaError,
+        topic_partition_list::{Offset, TopicPartitionList},
+    },
+    std::{collections::BTreeMap, sync::Arc, time::Duration},
+};
+
+const DEFAULT_TOPIC: &str = "dex_events";
+const DEFAULT_INTERVAL_SECS: u64 = 30;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Lag of the downstream consumer groups reading our Kafka topics, so the
+/// delivery picture does not stop at the broker ack.
+///
+/// Every interval, the committed offsets of each group are compared with the
+/// high watermark of every partition of the watched topics. The monitor only
+/// reads offsets, it never joins or commits for the groups it watches.
+pub struct ConsumerLagMonitor {
+    /// One client per group, offsets are fetched for the client's `group.id`.
+    consumers: Vec<(String, Arc<BaseConsumer>)>,
+    topics: Vec<String>,
+    interval: Duration,
+    threshold: Option<i64>,
+}
+
+impl ConsumerLagMonitor {
+    /// Reads `KAFKA_LAG_GROUPS` (comma-separated consumer groups, unset
+    /// disables the monitor), `KAFKA_LAG_TOPICS` (default `dex_events`),
+    /// `KAFKA_LAG_INTERVAL_SECS` (default `30`) and `KAFKA_LAG_THRESHOLD`
+    /// (messages behind before a group is reported, default off). Brokers
+    /// and credentials are those of the producer, see
+    /// [`client_config_from_env`].
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Ok(groups) = std::env::var("KAFKA_LAG_GROUPS") else {
+            return Ok(None);
+        };
+        let client_config = client_config_from_env()?;
+        let topics = std::env::var("KAFKA_LAG_TOPICS").unwrap_or_else(|_| DEFAULT_TOPIC.to_string());
+        let interval_secs = std::env::var("KAFKA_LAG_INTERVAL_SECS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_INTERVAL_SECS);
+        let threshold = std::env::var("KAFKA_LAG_THRESHOLD")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<i64>()
+                    .map_err(|e| format!("Invalid KAFKA_LAG_THRESHOLD {}: {}", value, e))
+            })
+            .transpose()?;
+
+        let consumers = split_list(&groups)
+            .into_iter()
+            .map(|group| {
+                let consumer: BaseConsumer = client_config
+                    .clone()
+                    .set("group.id", &group)
+                    .set("enable.auto.commit", "false")
+                    .create()
+                    .map_err(|e| format!("Failed to create Kafka client for group {}: {}", group, e))?;
+                Ok((group, Arc::new(consumer)))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if consumers.is_empty() {
+            return Err("KAFKA_LAG_GROUPS is set but lists no consumer group".to_string());
+        }
+
+        Ok(Some(Self {
+            consumers,
+            topics: split_list(&topics),
+            interval: Duration::from_secs(interval_secs.max(1)),
+            threshold,
+        }))
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.consumers.len()
+    }
+
+    /// Reports the lag of every group on every topic each interval.
+    pub fn spawn(self, metrics: Arc<dyn Metrics>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+
+            loop {
+                interval.tick().await;
+                for (group, consumer) in &self.consumers {
+                    let consumer = consumer.clone();
+                    let topics = self.topics.clone();
+                    let lags = match tokio::task::spawn_blocking(move || group_lag(&consumer, &topics)).await {
+                        Ok(Ok(lags)) => lags,
+                        Ok(Err(e)) => {
+                            log::error!("[CONSUMER_LAG] Failed to fetch offsets of group {}: {}", group, e);
+                            continue;
+                        }
+                        Err(e) => {
+                            log::error!("[CONSUMER_LAG] Offset fetch for group {} panicked: {}", group, e);
+                            continue;
+                        }
+                    };
+
+                    for (topic, lag) in lags {
+                        if self.threshold.is_some_and(|threshold| lag > threshold) {
+                            log::warn!("[CONSUMER_LAG] Group {} is {} messages behind on {}", group, lag, topic);
+                        }
+                        metrics
+                            .update_gauge(&format!("kafka_consumer_lag_{}_{}", group, topic), lag as f64)
+                            .await
+                            .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Messages not yet committed by the consumer's group, summed per topic. A
+/// partition the group never committed on counts everything still retained.
+fn group_lag(consumer: &BaseConsumer, topics: &[String]) -> Result<BTreeMap<String, i64>, KafkaError> {
+    let mut partitions = TopicPartitionList::new();
+    for topic in topics {
+        let metadata = consumer.fetch_metadata(Some(topic), FETCH_TIMEOUT)?;
+        for partition in metadata.topics().iter().flat_map(|topic| topic.partitions()) {
+            partitions.add_partition(topic, partition.id());
+        }
+    }
+    let committed = consumer.committed_offsets(partitions, FETCH_TIMEOUT)?;
+
+    let mut lags = BTreeMap::new();
+    for element in committed.elements() {
+        let (low, high) = consumer.fetch_watermarks(element.topic(), element.partition(), FETCH_TIMEOUT)?;
+        let lag = match element.offset() {
+            Offset::Offset(offset) => (high - offset.max(low)).max(0),
+            _ => (high - low).max(0),
+        };
+        *lags.entry(element.topic().to_string()).or_default() += lag;
+    }
+    Ok(lags)
+}
@@ -0,0 +1,152 @@
+use {
+    super::common::DexEventData,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashSet,
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{Arc, RwLock},
+        time::{Duration, SystemTime},
+    },
+};
+
+const DEFAULT_RELOAD_INTERVAL_SECS: u64 = 5;
+
+/// Mints whose events must not reach consumers, e.g. known scam tokens or
+/// an internal compliance list.
+///
+/// The list is loaded from a file with one mint per line (blank lines and
+/// `#` comments are ignored) and reloaded whenever the file changes. Events
+/// mentioning a listed mint are dropped, or sent to the quarantine topic
+/// when one is configured.
+#[derive(Clone)]
+pub struct MintDenylist {
+    mints: Arc<RwLock<HashSet<String>>>,
+    quarantine_topic: Option<String>,
+    path: PathBuf,
+    reload_interval: Duration,
+}
+
+impl MintDenylist {
+    /// Reads `MINT_DENYLIST_FILE`, `MINT_DENYLIST_RELOAD_SECS` (default `5`)
+    /// and `MINT_DENYLIST_QUARANTINE_TOPIC` (unset drops the events).
+    /// Returns `None` when no file is configured.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Ok(path) = std::env::var("MINT_DENYLIST_FILE") else {
+            return Ok(None);
+        };
+        let path = PathBuf::from(path);
+        let reload_secs = std::env::var("MINT_DENYLIST_RELOAD_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_RELOAD_INTERVAL_SECS);
+        let mints = read_mints(&path)
+            .map_err(|e| format!("Failed to read mint denylist {}: {}", path.display(), e))?;
+        log::info!("Denying {} mints from {}", mints.len(), path.display());
+
+        Ok(Some(Self {
+            mints: Arc::new(RwLock::new(mints)),
+            quarantine_topic: std::env::var("MINT_DENYLIST_QUARANTINE_TOPIC").ok(),
+            path,
+            reload_interval: Duration::from_secs(reload_secs.max(1)),
+        }))
+    }
+
+    pub fn quarantine_topic(&self) -> Option<&str> {
+        self.quarantine_topic.as_deref()
+    }
+
+    /// Listed mint the event mentions, if any.
+    fn denied_mint(&self, data: &DexEventData) -> Option<String> {
+        let mints = self.mints.read().unwrap_or_else(|e| e.into_inner());
+        if mints.is_empty() {
+            return None;
+        }
+        find_denied(&data.details, &mints)
+    }
+
+    /// Topic the event should go to, `None` when it is dropped.
+    pub fn route<'a>(&'a self, topic: &'a str, data: &DexEventData) -> Option<&'a str> {
+        let Some(mint) = self.denied_mint(data) else {
+            return Some(topic);
+        };
+        log::debug!(
+            "[DENYLIST] {} event {} mentions denied mint {}",
+            data.event_type,
+            data.signature,
+            mint
+        );
+        self.quarantine_topic.as_deref()
+    }
+
+    /// Polls the file every `MINT_DENYLIST_RELOAD_SECS` and swaps in the new
+    /// list when it changed. An unreadable file keeps the previous list.
+    pub fn spawn_reloader(&self) -> tokio::task::JoinHandle<()> {
+        let denylist = self.clone();
+
+        tokio::spawn(async move {
+            let mut last_modified = modified(&denylist.path);
+            let mut interval = tokio::time::interval(denylist.reload_interval);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                let modified = modified(&denylist.path);
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                match read_mints(&denylist.path) {
+                    Ok(mints) => {
+                        log::info!("Reloaded mint denylist: {} mints", mints.len());
+                        *denylist.mints.write().unwrap_or_else(|e| e.into_inner()) = mints;
+                    }
+                    Err(e) => log::error!(
+                        "Failed to reload mint denylist {}: {}",
+                        denylist.path.display(),
+                        e
+                    ),
+                }
+            }
+        })
+    }
+}
+
+/// Looks through every `mint` and `*_mint` field, including nested ones
+/// such as the legs of a route.
+fn find_denied(value: &serde_json::Value, mints: &HashSet<String>) -> Option<String> {
+    match value {
+        serde_json::Value::Object(fields) => fields.iter().find_map(|(key, value)| {
+            let is_mint_field = key == "mint" || key.ends_with("_mint");
+            match value {
+                serde_json::Value::String(mint) if is_mint_field && mints.contains(mint) => Some(mint.clone()),
+                _ => find_denied(value, mints),
+            }
+        }),
+        serde_json::Value::Array(values) => values.iter().find_map(|value| find_denied(value, mints)),
+        _ => None,
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn read_mints(path: &Path) -> std::io::Result<HashSet<String>> {
+    let contents = std::fs::read_to_string(path)?;
+
+    Ok(contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| match Pubkey::from_str(line) {
+            Ok(_) => Some(line.to_string()),
+            Err(_) => {
+                log::warn!("Ignoring invalid mint address in denylist: {}", line);
+                None
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,23 @@
+/// What went wrong while publishing, so callers can decide whether to retry
+/// or alert without parsing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishErrorKind {
+    /// The sink could not be reached or set up.
+    Connection,
+    /// The event could not be encoded, retrying will not help.
+    Serialization,
+    /// The sink did not accept the event in time.
+    Timeout,
+    /// The broker received the event and refused it.
+    Rejected,
+    /// A task of the publisher panicked or was cancelled, retrying will
+    /// not help.
+    Internal,
+}
+
+impl PublishErrorKind {
+    /// Connection problems and timeouts are usually transient.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, PublishErrorKind::Connection | PublishErrorKind::Timeout)
+    }
+}
@@ -0,0 +1,123 @@
+use {rdkafka::config::ClientConfig, std::path::Path};
+
+/// Prefix of environment variables passed through as librdkafka
+/// properties: `KAFKA_PROPERTY_BATCH_NUM_MESSAGES` sets
+/// `batch.num.messages`.
+const PROPERTY_PREFIX: &str = "KAFKA_PROPERTY_";
+
+/// Properties whose values are not logged.
+const SECRET_MARKERS: [&str; 3] = ["password", "secret", ".pem"];
+
+/// Connection variables shared by every Kafka client.
+const CONNECTION_VARS: [(&str, &str); 6] = [
+    ("KAFKA_SECURITY_PROTOCOL", "security.protocol"),
+    ("KAFKA_SASL_MECHANISM", "sasl.mechanism"),
+    ("KAFKA_SASL_USERNAME", "sasl.username"),
+    ("KAFKA_SASL_PASSWORD", "sasl.password"),
+    ("KAFKA_SSL_CA_LOCATION", "ssl.ca.location"),
+    ("KAFKA_SSL_ENDPOINT_IDENTIFICATION", "ssl.endpoint.identification.algorithm"),
+];
+
+/// Producer variables.
+const PRODUCER_VARS: [(&str, &str); 3] = [
+    ("KAFKA_ACKS", "acks"),
+    ("KAFKA_LINGER_MS", "linger.ms"),
+    ("KAFKA_COMPRESSION_TYPE", "compression.type"),
+];
+
+/// Configuration of the producer, for managed clusters (MSK, Confluent
+/// Cloud, ...) as well as a local broker. See [`client_config_from_env`]
+/// for the precedence; on top of the connection, reads `KAFKA_ACKS`,
+/// `KAFKA_LINGER_MS` and `KAFKA_COMPRESSION_TYPE` (`none`, `gzip`,
+/// `snappy`, `lz4` or `zstd`).
+pub fn producer_config_from_env() -> Result<ClientConfig, String> {
+    if let Ok(codec) = std::env::var("KAFKA_COMPRESSION_TYPE") {
+        if !matches!(codec.as_str(), "none" | "gzip" | "snappy" | "lz4" | "zstd") {
+            return Err(format!(
+                "Unknown KAFKA_COMPRESSION_TYPE {}, expected none, gzip, snappy, lz4 or zstd",
+                codec
+            ));
+        }
+    }
+    config_from_env(&[("message.timeout.ms", "5000")], &PRODUCER_VARS)
+}
+
+/// Configuration of the clients that only read from the brokers, e.g.
+/// the consumer lag monitor.
+pub fn client_config_from_env() -> Result<ClientConfig, String> {
+    config_from_env(&[], &[])
+}
+
+/// Applies, each overriding the previous: `defaults`, the properties file
+/// at `KAFKA_CONFIG_FILE` (`key=value` lines as downloaded from Confluent
+/// Cloud, `#` comments), `KAFKA_BROKERS` (default `localhost:9092` when
+/// neither sets `bootstrap.servers`), `KAFKA_SECURITY_PROTOCOL`,
+/// `KAFKA_SASL_MECHANISM`, `KAFKA_SASL_USERNAME`, `KAFKA_SASL_PASSWORD`,
+/// `KAFKA_SSL_CA_LOCATION`, `KAFKA_SSL_ENDPOINT_IDENTIFICATION`, the
+/// variables in `vars`, and any `KAFKA_PROPERTY_*`.
+fn config_from_env(defaults: &[(&str, &str)], vars: &[(&str, &str)]) -> Result<ClientConfig, String> {
+    let mut config = ClientConfig::new();
+    for (key, value) in defaults {
+        config.set(*key, *value);
+    }
+    if let Ok(path) = std::env::var("KAFKA_CONFIG_FILE") {
+        for (key, value) in read_properties(Path::new(&path))? {
+            config.set(key, value);
+        }
+    }
+    match std::env::var("KAFKA_BROKERS") {
+        Ok(brokers) => {
+            config.set("bootstrap.servers", brokers);
+        }
+        Err(_) if config.get("bootstrap.servers").is_none() => {
+            config.set("bootstrap.servers", "localhost:9092");
+        }
+        Err(_) => {}
+    }
+    for (var, key) in CONNECTION_VARS.iter().chain(vars) {
+        if let Ok(value) = std::env::var(var) {
+            config.set(*key, value);
+        }
+    }
+    for (var, value) in std::env::vars() {
+        if let Some(name) = var.strip_prefix(PROPERTY_PREFIX) {
+            config.set(name.to_lowercase().replace('_', "."), value);
+        }
+    }
+    Ok(config)
+}
+
+/// Reads a librdkafka properties file.
+fn read_properties(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read KAFKA_CONFIG_FILE {}: {}", path.display(), e))?;
+    contents
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("{}:{}: expected key=value", path.display(), index + 1))?;
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// The properties of `config` for logs, secrets masked.
+pub fn describe(config: &ClientConfig) -> String {
+    let mut properties = config
+        .config_map()
+        .iter()
+        .map(|(key, value)| {
+            if SECRET_MARKERS.iter().any(|marker| key.contains(marker)) {
+                format!("{}=***", key)
+            } else {
+                format!("{}={}", key, value)
+            }
+        })
+        .collect::<Vec<_>>();
+    properties.sort();
+    properties.join(", ")
+}
@@ -1,25 +1,268 @@
 use async_trait::async_trait;
 use rdkafka::config::ClientConfig;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rdkafka::util::Timeout;
 use std::sync::Arc;
-use super::{common::DexEventData, traits::Publisher};
+use std::time::Duration;
+use tokio::sync::{RwLock, RwLockReadGuard};
+use super::{common::DexEventData, error::PublishErrorKind, serialization::PayloadFormat, traits::Publisher, lag::DeliveryLag, watermark::Watermarks};
+use super::{block_summary::BlockSummaries, denylist::MintDenylist, publisher_metrics::PublisherMetrics};
+use crate::analytics::mev::MevDetector;
+#[cfg(feature = "kafka-avro")]
+use super::avro::{AvroSerializer, SchemaRegistryError};
 
 #[derive(Debug)]
-pub struct KafkaPublisherError(pub String);
+pub enum KafkaPublisherError {
+    /// Creating the producer or reaching the brokers failed.
+    Connection(KafkaError),
+    Serialization(serde_json::Error),
+    /// The event was not acknowledged within the delivery timeout, or
+    /// messages were still unacknowledged when the drain timeout of
+    /// `close` ran out.
+    Timeout(KafkaError),
+    /// The broker refused the event, e.g. an unknown topic or a message
+    /// over the size limit.
+    Rejected(KafkaError),
+    /// A blocking librdkafka call, e.g. the partition lookup for
+    /// watermarks, did not complete.
+    Metadata(tokio::task::JoinError),
+    /// Resolving the Avro schema or encoding the event failed.
+    #[cfg(feature = "kafka-avro")]
+    Schema(SchemaRegistryError),
+    /// Initializing, beginning or committing a transaction failed; the
+    /// events of an uncommitted transaction are aborted.
+    Transaction(KafkaError),
+}
+
+impl KafkaPublisherError {
+    /// Classifies a failed delivery by librdkafka's error code.
+    fn delivery(error: KafkaError) -> Self {
+        match error.rdkafka_error_code() {
+            Some(RDKafkaErrorCode::MessageTimedOut | RDKafkaErrorCode::RequestTimedOut) => {
+                KafkaPublisherError::Timeout(error)
+            }
+            Some(
+                RDKafkaErrorCode::BrokerTransportFailure
+                | RDKafkaErrorCode::AllBrokersDown
+                | RDKafkaErrorCode::Resolve
+                | RDKafkaErrorCode::NetworkException,
+            ) => KafkaPublisherError::Connection(error),
+            _ => KafkaPublisherError::Rejected(error),
+        }
+    }
+
+    pub fn kind(&self) -> PublishErrorKind {
+        match self {
+            KafkaPublisherError::Connection(_) | KafkaPublisherError::Transaction(_) => PublishErrorKind::Connection,
+            // The blocking call panicked or the runtime is shutting down
+            KafkaPublisherError::Metadata(_) => PublishErrorKind::Internal,
+            KafkaPublisherError::Serialization(_) => PublishErrorKind::Serialization,
+            KafkaPublisherError::Timeout(_) => PublishErrorKind::Timeout,
+            KafkaPublisherError::Rejected(_) => PublishErrorKind::Rejected,
+            #[cfg(feature = "kafka-avro")]
+            KafkaPublisherError::Schema(e) => e.kind(),
+        }
+    }
+}
 
 impl std::fmt::Display for KafkaPublisherError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Kafka Publisher Error: {}", self.0)
+        match self {
+            KafkaPublisherError::Connection(e) => write!(f, "Kafka Publisher Error: Failed to reach brokers: {}", e),
+            KafkaPublisherError::Serialization(e) => write!(f, "Kafka Publisher Error: Failed to serialize data: {}", e),
+            KafkaPublisherError::Timeout(e) => write!(f, "Kafka Publisher Error: Delivery timed out: {}", e),
+            KafkaPublisherError::Rejected(e) => write!(f, "Kafka Publisher Error: Broker rejected message: {}", e),
+            KafkaPublisherError::Metadata(e) => write!(f, "Kafka Publisher Error: Failed to fetch metadata: {}", e),
+            #[cfg(feature = "kafka-avro")]
+            KafkaPublisherError::Schema(e) => write!(f, "Kafka Publisher Error: {}", e),
+            KafkaPublisherError::Transaction(e) => write!(f, "Kafka Publisher Error: Transaction failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for KafkaPublisherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KafkaPublisherError::Connection(e)
+            | KafkaPublisherError::Timeout(e)
+            | KafkaPublisherError::Rejected(e)
+            | KafkaPublisherError::Transaction(e) => Some(e),
+            KafkaPublisherError::Serialization(e) => Some(e),
+            KafkaPublisherError::Metadata(e) => Some(e),
+            #[cfg(feature = "kafka-avro")]
+            KafkaPublisherError::Schema(e) => Some(e),
+        }
+    }
+}
+
+/// Opt-in exactly-once producing: idempotent writes, and with a
+/// transactional ID, each block's events committed in one transaction.
+#[derive(Debug, Clone, Default)]
+pub struct ExactlyOnceConfig {
+    pub idempotence: bool,
+    pub transactional_id: Option<String>,
+    /// Also bounds how long the last block may wait for the next one,
+    /// which commits it.
+    pub transaction_timeout: Duration,
+}
+
+impl ExactlyOnceConfig {
+    /// Reads `KAFKA_IDEMPOTENCE`, `KAFKA_TRANSACTIONAL_ID` (implies
+    /// idempotence) and `KAFKA_TRANSACTION_TIMEOUT_MS`.
+    pub fn from_env() -> Result<Self, String> {
+        let number = |name: &str, default: u64| match std::env::var(name) {
+            Ok(value) => value.parse::<u64>().map_err(|e| format!("Invalid {}: {}", name, e)),
+            Err(_) => Ok(default),
+        };
+        let transactional_id = std::env::var("KAFKA_TRANSACTIONAL_ID").ok().filter(|id| !id.trim().is_empty());
+        let idempotence = match std::env::var("KAFKA_IDEMPOTENCE").as_deref() {
+            Ok("true") | Ok("1") => true,
+            Ok("false") | Ok("0") | Err(_) => transactional_id.is_some(),
+            Ok(other) => return Err(format!("Invalid KAFKA_IDEMPOTENCE: {}", other)),
+        };
+        if transactional_id.is_some() && !idempotence {
+            return Err("KAFKA_TRANSACTIONAL_ID requires KAFKA_IDEMPOTENCE".to_string());
+        }
+        Ok(Self {
+            idempotence,
+            transactional_id,
+            transaction_timeout: Duration::from_millis(number("KAFKA_TRANSACTION_TIMEOUT_MS", 60_000)?),
+        })
+    }
+
+    /// Sets the producer properties; must be applied before the producer
+    /// is created.
+    pub fn apply(&self, config: &mut ClientConfig) {
+        if self.idempotence {
+            config.set("enable.idempotence", "true").set("acks", "all");
+        }
+        if let Some(transactional_id) = &self.transactional_id {
+            config
+                .set("transactional.id", transactional_id)
+                .set("transaction.timeout.ms", self.transaction_timeout.as_millis().to_string());
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct OpenTransaction {
+    open: bool,
+    /// Slot of the block the transaction holds, `None` while it only
+    /// holds events without a slot.
+    slot: Option<u64>,
+}
+
+impl OpenTransaction {
+    fn accepts(&self, slot: Option<u64>) -> bool {
+        self.open && (slot.is_none() || self.slot == slot)
+    }
+}
+
+/// One transaction per block. Sends hold a read guard until the broker
+/// acknowledges them, so a commit (under the write guard) never races an
+/// in-flight event. Relies on blocks arriving in slot order: the first
+/// event of another slot commits the open block, and only that or `close`
+/// commits, so a block is never split across transactions.
+struct Transactions {
+    producer: Arc<FutureProducer>,
+    state: RwLock<OpenTransaction>,
+    timeout: Duration,
+    #[cfg(test)]
+    committed: std::sync::atomic::AtomicUsize,
+}
+
+impl Transactions {
+    /// Returns once a transaction accepting an event of `slot` is open,
+    /// committing the previous block's transaction first.
+    async fn enter(&self, slot: Option<u64>) -> Result<RwLockReadGuard<'_, OpenTransaction>, KafkaPublisherError> {
+        let state = self.state.read().await;
+        if state.accepts(slot) {
+            return Ok(state);
+        }
+        drop(state);
+
+        let mut state = self.state.write().await;
+        if !state.accepts(slot) {
+            if state.open && state.slot.is_some() {
+                state.open = false;
+                self.commit().await?;
+            }
+            if !state.open {
+                self.blocking(|producer, _| producer.begin_transaction()).await?;
+                state.open = true;
+                state.slot = None;
+            }
+            if slot.is_some() {
+                state.slot = slot;
+            }
+        }
+        Ok(state.downgrade())
+    }
+
+    /// Commits the open transaction, if any.
+    async fn commit_open(&self) -> Result<(), KafkaPublisherError> {
+        let mut state = self.state.write().await;
+        if !state.open {
+            return Ok(());
+        }
+        state.open = false;
+        self.commit().await
+    }
+
+    /// Flushes and commits, aborting when librdkafka says the
+    /// transaction can no longer commit.
+    async fn commit(&self) -> Result<(), KafkaPublisherError> {
+        #[cfg(test)]
+        self.committed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.blocking(|producer, timeout| {
+            producer.commit_transaction(timeout).inspect_err(|e| {
+                if let KafkaError::Transaction(error) = e {
+                    if error.txn_requires_abort() {
+                        if let Err(abort) = producer.abort_transaction(timeout) {
+                            log::error!("[KAFKA] Failed to abort transaction: {}", abort);
+                        }
+                    }
+                }
+            })
+        })
+        .await
+    }
+
+    /// Runs a transactional call, which blocks on the brokers, off the
+    /// runtime.
+    async fn blocking<F>(&self, call: F) -> Result<(), KafkaPublisherError>
+    where
+        F: FnOnce(&FutureProducer, Duration) -> Result<(), KafkaError> + Send + 'static,
+    {
+        let producer = self.producer.clone();
+        let timeout = self.timeout;
+        tokio::task::spawn_blocking(move || call(&producer, timeout))
+            .await
+            .map_err(KafkaPublisherError::Metadata)?
+            .map_err(KafkaPublisherError::Transaction)
     }
 }
 
-impl std::error::Error for KafkaPublisherError {}
+/// How long `close` waits for the messages still buffered by the producer
+/// by default.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Clone)]
 pub struct KafkaPublisher {
     producer: Arc<FutureProducer>,
     timeout: Timeout,
+    drain_timeout: Duration,
+    format: PayloadFormat,
+    watermarks: Option<Watermarks>,
+    denylist: Option<MintDenylist>,
+    block_summaries: Option<BlockSummaries>,
+    mev: Option<MevDetector>,
+    lag: DeliveryLag,
+    metrics: PublisherMetrics,
+    #[cfg(feature = "kafka-avro")]
+    avro: Option<AvroSerializer>,
+    transactions: Option<Arc<Transactions>>,
 }
 
 impl KafkaPublisher {
@@ -39,39 +282,316 @@ impl KafkaPublisher {
     pub fn new_with_config(config: ClientConfig, timeout_ms: u64) -> Result<Self, KafkaPublisherError> {
         let producer: FutureProducer = config
             .create()
-            .map_err(|e| KafkaPublisherError(format!("Failed to create producer: {}", e)))?;
+            .map_err(KafkaPublisherError::Connection)?;
 
         Ok(Self {
             producer: Arc::new(producer),
             timeout: Timeout::After(std::time::Duration::from_millis(timeout_ms)),
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            format: PayloadFormat::default(),
+            watermarks: None,
+            denylist: None,
+            block_summaries: None,
+            mev: None,
+            lag: DeliveryLag::new("kafka"),
+            metrics: PublisherMetrics::new("kafka"),
+            #[cfg(feature = "kafka-avro")]
+            avro: None,
+            transactions: None,
         })
     }
-}
 
-#[async_trait]
-impl Publisher for KafkaPublisher {
-    type Error = KafkaPublisherError;
+    /// Initializes transactions when `config` has a transactional ID. The
+    /// producer must have been created from a `ClientConfig` that
+    /// `config` was applied to.
+    pub async fn with_exactly_once(mut self, config: &ExactlyOnceConfig) -> Result<Self, KafkaPublisherError> {
+        if config.transactional_id.is_none() {
+            return Ok(self);
+        }
+        let transactions = Arc::new(Transactions {
+            producer: self.producer.clone(),
+            state: RwLock::new(OpenTransaction::default()),
+            timeout: config.transaction_timeout,
+            #[cfg(test)]
+            committed: Default::default(),
+        });
+        transactions.blocking(|producer, timeout| producer.init_transactions(timeout)).await?;
+        self.transactions = Some(transactions);
+        Ok(self)
+    }
+
+    /// How long `close` waits for the messages still buffered by the
+    /// producer, e.g. those of publishes cancelled by a shutdown.
+    pub fn with_drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+
+    pub fn with_format(mut self, format: PayloadFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_watermarks(mut self, watermarks: Option<Watermarks>) -> Self {
+        self.watermarks = watermarks;
+        self
+    }
+
+    /// Drops or quarantines events mentioning a denied mint.
+    pub fn with_denylist(mut self, denylist: Option<MintDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    /// Publishes a summary of each block's events once the block is complete.
+    pub fn with_block_summaries(mut self, block_summaries: Option<BlockSummaries>) -> Self {
+        self.block_summaries = block_summaries;
+        self
+    }
+
+    /// Publishes the sandwiches found in each block once the block is
+    /// complete.
+    pub fn with_mev(mut self, mev: Option<MevDetector>) -> Self {
+        self.mev = mev;
+        self
+    }
+
+    /// Encodes events as Avro against the schema registry instead of JSON.
+    #[cfg(feature = "kafka-avro")]
+    pub fn with_avro(mut self, avro: Option<AvroSerializer>) -> Self {
+        self.avro = avro;
+        self
+    }
+
+    pub fn watermarks(&self) -> Option<&Watermarks> {
+        self.watermarks.as_ref()
+    }
+
+    pub fn delivery_lag(&self) -> &DeliveryLag {
+        &self.lag
+    }
+
+    pub fn metrics(&self) -> &PublisherMetrics {
+        &self.metrics
+    }
+
+    /// Encodes `data` with the payload serializer, or as Avro under the
+    /// subject of `topic`.
+    #[cfg_attr(not(feature = "kafka-avro"), allow(unused_variables))]
+    async fn payload(&self, topic: &str, data: &DexEventData) -> Result<Vec<u8>, KafkaPublisherError> {
+        #[cfg(feature = "kafka-avro")]
+        if let Some(avro) = &self.avro {
+            return avro.encode(topic, data, &self.format).await.map_err(KafkaPublisherError::Schema);
+        }
+        self.format.encode_bytes(data).map_err(KafkaPublisherError::Serialization)
+    }
+
+    /// Sends a watermark to every partition of each topic: consumers track
+    /// watermarks per partition, so a partition that never receives one
+    /// would hold back event time for the whole job.
+    pub async fn publish_watermarks(&self) -> Result<(), KafkaPublisherError> {
+        let Some(watermarks) = &self.watermarks else {
+            return Ok(());
+        };
+
+        for (topic, data) in watermarks.events() {
+            let payload = self.payload(&topic, &data).await?;
+
+            let producer = self.producer.clone();
+            let metadata_topic = topic.clone();
+            let partitions = tokio::task::spawn_blocking(move || {
+                producer
+                    .client()
+                    .fetch_metadata(Some(&metadata_topic), std::time::Duration::from_secs(5))
+                    .map(|metadata| {
+                        metadata
+                            .topics()
+                            .iter()
+                            .flat_map(|topic| topic.partitions().iter().map(|partition| partition.id()))
+                            .collect::<Vec<_>>()
+                    })
+            })
+            .await
+            .map_err(KafkaPublisherError::Metadata)?
+            .map_err(KafkaPublisherError::Connection)?;
+
+            let _transaction = match &self.transactions {
+                Some(transactions) => Some(transactions.enter(None).await?),
+                None => None,
+            };
+            for partition in partitions {
+                let record = FutureRecord::to(&topic)
+                    .partition(partition)
+                    .key("watermark")
+                    .payload(&payload);
+                self.producer
+                    .send(record, self.timeout)
+                    .await
+                    .map_err(|(e, _)| KafkaPublisherError::delivery(e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends `data` to `topic`, or where the denylist routes it, within
+    /// the transaction the caller entered. Returns whether it went to
+    /// `topic` unchanged.
+    async fn send(&self, topic: &str, data: &DexEventData) -> Result<bool, KafkaPublisherError> {
+        let Some(routed) = self.denylist.as_ref().map_or(Some(topic), |denylist| denylist.route(topic, data)) else {
+            return Ok(false);
+        };
+        let payload = self.payload(routed, data).await?;
 
-    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
-        let json_data = serde_json::to_string(data)
-            .map_err(|e| KafkaPublisherError(format!("Failed to serialize data: {}", e)))?;
-        
         let key = format!("{}:{}", data.platform, data.signature);
-        
-        let record = FutureRecord::to(topic)
-            .key(&key)
-            .payload(&json_data);
+        // Held until the broker acknowledges the event.
+        let _unflushed = self.watermarks.as_ref().and_then(|watermarks| watermarks.begin(routed, data));
+        self.lag.submit(data.block_time);
 
+        let record = FutureRecord::to(routed)
+            .key(&key)
+            .payload(&payload);
         self.producer
             .send(record, self.timeout)
             .await
-            .map_err(|(e, _)| KafkaPublisherError(format!("Failed to send message: {}", e)))?;
+            .map_err(|(e, _)| KafkaPublisherError::delivery(e))?;
+        self.metrics.sent(payload.len());
+        self.lag.ack(data.block_time);
+        Ok(routed == topic)
+    }
+
+    /// Sends the events of each block concurrently, so the producer
+    /// batches them instead of waiting for every ack in turn. Blocks go
+    /// one after another to keep one transaction per block. On failure,
+    /// returns the positions of the events not delivered: the failed ones
+    /// of the block and every later one.
+    pub async fn send_batch(&self, events: &[(String, DexEventData)]) -> Result<(), (Vec<usize>, KafkaPublisherError)> {
+        let mut start = 0;
+        for block in events.chunk_by(|(_, a), (_, b)| a.slot == b.slot) {
+            let results = futures::future::join_all(block.iter().map(|(topic, data)| self.publish(topic, data))).await;
+            let mut undelivered = Vec::new();
+            let mut error = None;
+            for (position, result) in results.into_iter().enumerate() {
+                if let Err(e) = result {
+                    undelivered.push(start + position);
+                    error.get_or_insert(e);
+                }
+            }
+            start += block.len();
+            if let Some(error) = error {
+                undelivered.extend(start..events.len());
+                return Err((undelivered, error));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Publisher for KafkaPublisher {
+    type Error = KafkaPublisherError;
+
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
+        // Part of the block's transaction until the next block commits it.
+        // The summaries and MEV events this event completes are of older
+        // blocks, they join the open transaction instead of reopening theirs.
+        let _transaction = match &self.transactions {
+            Some(transactions) => Some(transactions.enter(data.slot).await?),
+            None => None,
+        };
+        // Quarantined events stay out of the block summaries
+        if !self.send(topic, data).await? {
+            return Ok(());
+        }
+        if let Some(block_summaries) = &self.block_summaries {
+            for summary in block_summaries.record(data) {
+                self.send(block_summaries.topic(), &summary).await?;
+            }
+        }
+        if let Some(mev) = &self.mev {
+            for event in mev.record(data) {
+                self.send(mev.topic(), &event).await?;
+            }
+        }
 
         Ok(())
     }
 
+    /// See [`KafkaPublisher::send_batch`].
+    async fn publish_batch(&self, events: &[(String, DexEventData)]) -> Result<(), Self::Error> {
+        self.send_batch(events).await.map_err(|(_, e)| e)
+    }
+
+    /// Commits the open transaction, then waits up to the drain timeout
+    /// for every buffered message to be acknowledged.
     async fn close(&self) -> Result<(), Self::Error> {
-        // Kafka producer will be closed when dropped
+        if let Some(transactions) = &self.transactions {
+            transactions.commit_open().await?;
+        }
+        let producer = self.producer.clone();
+        let drain_timeout = self.drain_timeout;
+        tokio::task::spawn_blocking(move || producer.flush(drain_timeout))
+            .await
+            .map_err(KafkaPublisherError::Metadata)?
+            .map_err(KafkaPublisherError::Timeout)?;
+        log::info!("[KAFKA] Flushed the producer");
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use {super::*, rdkafka::mocking::MockCluster, serde_json::json, std::sync::atomic::Ordering};
+
+    fn swap(slot: u64, signature: &str) -> DexEventData {
+        DexEventData {
+            event_type: "swap".to_string(),
+            platform: "raydium_amm_v4".to_string(),
+            signature: signature.to_string(),
+            timestamp: 0,
+            block_time: Some(1_700_000_000),
+            slot: Some(slot),
+            details: json!({}),
+            ingested_at: None,
+            tx_index: None,
+            instruction_index: None,
+            is_inner: None,
+            fee_payer: None,
+            trader: None,
+            payload: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn commits_each_block_once_with_its_summary_in_the_next() {
+        let cluster = MockCluster::new(1).unwrap();
+        let exactly_once = ExactlyOnceConfig {
+            idempotence: true,
+            transactional_id: Some("test".to_string()),
+            transaction_timeout: Duration::from_secs(10),
+        };
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", cluster.bootstrap_servers());
+        exactly_once.apply(&mut config);
+        let publisher = KafkaPublisher::new_with_config(config, 5_000)
+            .unwrap()
+            .with_block_summaries(Some(BlockSummaries::new("block_summaries", 1)))
+            .with_exactly_once(&exactly_once)
+            .await
+            .unwrap();
+        let committed = || publisher.transactions.as_ref().unwrap().committed.load(Ordering::Relaxed);
+
+        // Reentering the transaction for an older block would deadlock
+        let publishes = async {
+            for (slot, signature) in [(10, "a"), (10, "b"), (11, "c"), (11, "d"), (12, "e")] {
+                publisher.publish("swaps", &swap(slot, signature)).await.unwrap();
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(30), publishes).await.unwrap();
+        // The summary of slot 10 went out with the first event of 11
+        // without splitting block 11; block 12 is still open
+        assert_eq!(committed(), 2);
+
+        publisher.close().await.unwrap();
+        assert_eq!(committed(), 3);
+    }
+}
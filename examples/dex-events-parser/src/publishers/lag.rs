@@ -0,0 +1,172 @@
+use {
+    carbon_core::metrics::Metrics,
+    std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+/// How far back acked block times are sampled to measure catch-up speed.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+struct LagState {
+    newest_submitted_block_time: Option<i64>,
+    newest_acked_block_time: Option<i64>,
+    /// Newest acked block time at each report within `RATE_WINDOW`.
+    samples: VecDeque<(Instant, i64)>,
+    behind_since: Option<Instant>,
+}
+
+/// Delivery progress of one sink: the block time of the newest event it
+/// acknowledged, compared with the newest handed to it and the wall clock.
+#[derive(Clone)]
+pub struct DeliveryLag {
+    sink: &'static str,
+    state: Arc<Mutex<LagState>>,
+}
+
+impl DeliveryLag {
+    pub fn new(sink: &'static str) -> Self {
+        Self {
+            sink,
+            state: Arc::new(Mutex::new(LagState::default())),
+        }
+    }
+
+    /// Records an event handed to the sink.
+    pub fn submit(&self, block_time: Option<i64>) {
+        let Some(block_time) = block_time else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.newest_submitted_block_time = state.newest_submitted_block_time.max(Some(block_time));
+    }
+
+    /// Records an event acknowledged by the sink.
+    pub fn ack(&self, block_time: Option<i64>) {
+        let Some(block_time) = block_time else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.newest_acked_block_time = state.newest_acked_block_time.max(Some(block_time));
+    }
+
+    async fn report(&self, threshold: Duration, metrics: &dyn Metrics) {
+        let now = Instant::now();
+        let now_unix = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() as i64;
+
+        let (newest_acked, backlog, lag, catch_up_eta) = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(newest_acked) = state.newest_acked_block_time else {
+                return;
+            };
+            let backlog = state
+                .newest_submitted_block_time
+                .map_or(0, |submitted| (submitted - newest_acked).max(0));
+            let lag = (now_unix - newest_acked).max(0);
+
+            state.samples.push_back((now, newest_acked));
+            while state.samples.len() > 2 && now.duration_since(state.samples[0].0) > RATE_WINDOW {
+                state.samples.pop_front();
+            }
+            // Block time acked per second of wall clock: above 1 the sink is
+            // gaining on the chain.
+            let (oldest_at, oldest_block_time) = state.samples[0];
+            let elapsed = now.duration_since(oldest_at).as_secs_f64();
+            let rate = (elapsed >= 1.0).then(|| (newest_acked - oldest_block_time) as f64 / elapsed);
+
+            let mut catch_up_eta = None;
+            if lag as u64 > threshold.as_secs() {
+                let behind_since = *state.behind_since.get_or_insert(now);
+                catch_up_eta = rate.filter(|rate| *rate > 1.0).map(|rate| lag as f64 / (rate - 1.0));
+                match (rate, catch_up_eta) {
+                    (Some(rate), Some(eta)) => log::warn!(
+                        "[SINK_LAG] {} is {}s behind (for {}s), catching up at {:.1}x, ETA {:.0}s",
+                        self.sink,
+                        lag,
+                        now.duration_since(behind_since).as_secs(),
+                        rate,
+                        eta
+                    ),
+                    _ => log::warn!(
+                        "[SINK_LAG] {} is {}s behind (for {}s), not catching up",
+                        self.sink,
+                        lag,
+                        now.duration_since(behind_since).as_secs()
+                    ),
+                }
+            } else if let Some(behind_since) = state.behind_since.take() {
+                log::info!(
+                    "[SINK_LAG] {} caught up after {}s",
+                    self.sink,
+                    now.duration_since(behind_since).as_secs()
+                );
+            }
+
+            (newest_acked, backlog, lag, catch_up_eta)
+        };
+
+        let gauges = [
+            (format!("sink_newest_acked_block_time_{}", self.sink), newest_acked as f64),
+            (format!("sink_lag_seconds_{}", self.sink), lag as f64),
+            (format!("sink_backlog_seconds_{}", self.sink), backlog as f64),
+            // -1 while behind and not gaining, 0 once caught up
+            (
+                format!("sink_catch_up_eta_seconds_{}", self.sink),
+                match catch_up_eta {
+                    Some(eta) => eta,
+                    None if lag as u64 > threshold.as_secs() => -1.0,
+                    None => 0.0,
+                },
+            ),
+        ];
+        for (name, value) in gauges {
+            metrics
+                .update_gauge(&name, value)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        }
+    }
+}
+
+/// How often sink lag is reported and from which lag a sink is considered
+/// behind.
+#[derive(Debug, Clone, Copy)]
+pub struct DeliveryLagConfig {
+    pub interval: Duration,
+    pub threshold: Duration,
+}
+
+impl DeliveryLagConfig {
+    /// Reads `SINK_LAG_REPORT_SECS` (default `10`) and
+    /// `SINK_LAG_THRESHOLD_SECS` (default `30`).
+    pub fn from_env() -> Self {
+        let secs = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            interval: Duration::from_secs(secs("SINK_LAG_REPORT_SECS", 10).max(1)),
+            threshold: Duration::from_secs(secs("SINK_LAG_THRESHOLD_SECS", 30)),
+        }
+    }
+
+    /// Reports the lag of every sink in `lags` each interval.
+    pub fn spawn(self, lags: Vec<DeliveryLag>, metrics: Arc<dyn Metrics>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                for lag in &lags {
+                    lag.report(self.threshold, metrics.as_ref()).await;
+                }
+            }
+        })
+    }
+}
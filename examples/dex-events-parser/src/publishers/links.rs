@@ -0,0 +1,47 @@
+use {
+    serde_json::{json, Value},
+    solana_pubkey::Pubkey,
+};
+
+/// Program behind each `platform` name used in payloads, with the label shown
+/// to users. Keep in sync when a processor is added.
+pub const PROGRAM_LABELS: &[(&str, Pubkey, &str)] = &[
+    ("Raydium AMM V4", carbon_raydium_amm_v4_decoder::PROGRAM_ID, "Raydium Liquidity Pool V4"),
+    ("Raydium CLMM", carbon_raydium_clmm_decoder::PROGRAM_ID, "Raydium Concentrated Liquidity"),
+    ("Raydium CPMM", carbon_raydium_cpmm_decoder::PROGRAM_ID, "Raydium CPMM"),
+    ("Jupiter Swap", carbon_jupiter_swap_decoder::PROGRAM_ID, "Jupiter Aggregator v6"),
+    ("Orca Whirlpool", carbon_orca_whirlpool_decoder::PROGRAM_ID, "Orca Whirlpools"),
+    ("Meteora DLMM", carbon_meteora_dlmm_decoder::PROGRAM_ID, "Meteora DLMM"),
+    ("Meteora Pools", carbon_meteora_pools_decoder::PROGRAM_ID, "Meteora Dynamic AMM"),
+    ("Pumpfun", carbon_pumpfun_decoder::PROGRAM_ID, "Pump.fun"),
+    ("Pump Swap", carbon_pump_swap_decoder::PROGRAM_ID, "Pump.fun AMM"),
+    ("OpenBook V2", carbon_openbook_v2_decoder::PROGRAM_ID, "OpenBook V2"),
+    ("Phoenix V1", carbon_phoenix_v1_decoder::PROGRAM_ID, "Phoenix"),
+    ("Fluxbeam", carbon_fluxbeam_decoder::PROGRAM_ID, "FluxBeam"),
+    ("Lifinity AMM V2", carbon_lifinity_amm_v2_decoder::PROGRAM_ID, "Lifinity Swap V2"),
+    ("Moonshot", carbon_moonshot_decoder::PROGRAM_ID, "Moonshot"),
+    ("Raydium Launchpad", carbon_raydium_launchpad_decoder::PROGRAM_ID, "Raydium LaunchLab"),
+    ("Drift V2", carbon_drift_v2_decoder::PROGRAM_ID, "Drift Protocol V2"),
+    ("Invariant", crate::decoders::invariant::PROGRAM_ID, "Invariant"),
+    ("Sanctum Infinity", crate::decoders::sanctum::INFINITY_PROGRAM_ID, "Sanctum Infinity"),
+    ("Sanctum Router", crate::decoders::sanctum::ROUTER_PROGRAM_ID, "Sanctum Router"),
+    ("Virtual Curve", carbon_virtual_curve_decoder::PROGRAM_ID, "Meteora Dynamic Bonding Curve"),
+];
+
+/// Explorer links for an event: the transaction on Solscan and SolanaFM, and
+/// the program behind `platform` with its label.
+pub fn links(platform: &str, signature: &str) -> Value {
+    let mut links = json!({});
+    if !signature.is_empty() {
+        links["solscan"] = json!(format!("https://solscan.io/tx/{}", signature));
+        links["solanafm"] = json!(format!("https://solana.fm/tx/{}", signature));
+    }
+    if let Some((_, program_id, label)) = PROGRAM_LABELS.iter().find(|(name, _, _)| *name == platform) {
+        links["program"] = json!({
+            "id": program_id.to_string(),
+            "label": label,
+            "solscan": format!("https://solscan.io/account/{}", program_id)
+        });
+    }
+    links
+}
@@ -1,60 +1,185 @@
+pub mod batching;
+pub mod block_summary;
 pub mod common;
+pub mod consumer_lag;
+pub mod denylist;
+pub mod error;
 pub mod traits;
 pub mod zmq_publisher;
 pub mod kafka_publisher;
+pub mod kafka_config;
+#[cfg(feature = "kafka-avro")]
+pub mod avro;
+pub mod stdout_publisher;
+pub mod ws_server_publisher;
+pub mod webhook_publisher;
+pub mod questdb_publisher;
+#[cfg(feature = "publisher-postgres")]
+pub mod postgres;
+#[cfg(feature = "publisher-object-store")]
+pub mod object_store_publisher;
+#[cfg(feature = "publisher-pubsub")]
+pub mod pubsub_publisher;
+#[cfg(feature = "publisher-mqtt")]
+pub mod mqtt_publisher;
 pub mod unified_publisher;
+pub mod serialization;
+pub mod lag;
+pub mod publisher_metrics;
+pub mod queue;
+pub mod registry;
+pub mod resilience;
+pub mod routing;
+pub mod links;
+pub mod watermark;
 
 // Re-export commonly used types
-pub use common::DexEventData;
-use rdkafka::ClientConfig;
+pub use common::{DexEventData, DexEventPayload};
 pub use traits::Publisher;
-pub use zmq_publisher::{ZmqPublisher, ZmqPublisherError};
-pub use kafka_publisher::{KafkaPublisher, KafkaPublisherError};
-pub use unified_publisher::{UnifiedPublisher, MultiPublisher};
+pub use zmq_publisher::{ZmqPublisher, ZmqPublisherError, ZmqSocketOptions, ZmqSubscriptions, compression_from_env as zmq_compression_from_env};
+pub use kafka_publisher::{ExactlyOnceConfig, KafkaPublisher, KafkaPublisherError};
+pub use kafka_config::{client_config_from_env as kafka_client_config_from_env, describe as describe_kafka_config, producer_config_from_env as kafka_producer_config_from_env};
+#[cfg(feature = "kafka-avro")]
+pub use avro::{AvroConfig, AvroSerializer, SchemaRegistryError};
+pub use stdout_publisher::StdoutPublisher;
+pub use ws_server_publisher::{WsServerPublisher, WsServerPublisherError};
+pub use webhook_publisher::{WebhookConfig, WebhookPublisher, WebhookPublisherError};
+pub use questdb_publisher::{QuestDbConfig, QuestDbPublisher, QuestDbPublisherError, TimestampSource};
+#[cfg(feature = "publisher-postgres")]
+pub use postgres::{PostgresConfig, PostgresPublisher, PostgresPublisherError};
+#[cfg(feature = "publisher-object-store")]
+pub use object_store_publisher::{ArchiveFormat, ObjectStoreConfig, ObjectStorePublisher, ObjectStorePublisherError};
+#[cfg(feature = "publisher-pubsub")]
+pub use pubsub_publisher::{PubSubConfig, PubSubPublisher, PubSubPublisherError};
+#[cfg(feature = "publisher-mqtt")]
+pub use mqtt_publisher::{MqttConfig, MqttPublisher, MqttPublisherError, TopicTemplate};
+pub use unified_publisher::{UnifiedPublisher, UnifiedPublisherError, MultiPublisher, RoutedPublisher, BatchingPublisher, ResilientPublisher, QueuedPublisher, StatsPublisher};
+pub use batching::BatchConfig;
+pub use resilience::ResilienceConfig;
+pub use queue::{QueueConfig, QueuePolicy};
+pub use registry::{BoxError, BoxedPublisher, DynPublisher, PublisherRegistry};
+pub use routing::{TopicRoutes, EVENTS_TOPIC};
+pub use serialization::PayloadFormat;
+pub use watermark::Watermarks;
+pub use lag::DeliveryLagConfig;
+pub use publisher_metrics::PublisherMetrics;
+pub use consumer_lag::ConsumerLagMonitor;
+pub use denylist::MintDenylist;
+pub use block_summary::BlockSummaries;
+use crate::{analytics::{mev::MevDetector, rolling_stats::RollingStats}, ha::Leadership};
+use std::time::Duration;
 
-// Helper function to create publishers from environment variables
-pub fn create_unified_publisher_from_env() -> Result<UnifiedPublisher, Box<dyn std::error::Error + Send + Sync>> {
-    match std::env::var("PUBLISHER_TYPE").as_deref() {
-        Ok("zmq") => {
+/// `PUBLISHER_TYPE`, `zmq` when unset.
+pub fn publisher_type_from_env() -> String {
+    std::env::var("PUBLISHER_TYPE").unwrap_or_else(|_| "zmq".to_string())
+}
+
+// Helper function to create a publisher of `publisher_type` (see
+// `publisher_type_from_env`), configured from environment variables
+pub async fn create_unified_publisher_from_env(publisher_type: &str, leadership: &Leadership, denylist: Option<MintDenylist>) -> Result<UnifiedPublisher, UnifiedPublisherError> {
+    let format = PayloadFormat::from_env();
+    let routes = TopicRoutes::from_env().map_err(UnifiedPublisherError::Config)?;
+    let batch = BatchConfig::from_env().map_err(UnifiedPublisherError::Config)?;
+    let resilience = ResilienceConfig::from_env().map_err(UnifiedPublisherError::Config)?;
+    let queue = QueueConfig::from_env().map_err(UnifiedPublisherError::Config)?;
+    let zmq_compression = zmq_compression_from_env().map_err(UnifiedPublisherError::Config)?;
+    let zmq_options = ZmqSocketOptions::from_env().map_err(UnifiedPublisherError::Config)?;
+    let drain_timeout = drain_timeout_from_env().map_err(UnifiedPublisherError::Config)?;
+
+    // Typed up front: with every publisher feature on, no arm names the error
+    let publisher: Result<UnifiedPublisher, UnifiedPublisherError> = match publisher_type {
+        "zmq" => {
             let endpoint = std::env::var("ZMQ_ENDPOINT").unwrap_or_else(|_| "tcp://*:5555".to_string());
-            let publisher = ZmqPublisher::new(&endpoint)?;
+            let publisher = ZmqPublisher::bind(&endpoint, zmq_options)?.with_format(format).with_compression(zmq_compression).with_drain_timeout(drain_timeout).with_watermarks(Watermarks::from_env()).with_denylist(denylist.clone()).with_block_summaries(BlockSummaries::from_env()).with_mev(MevDetector::from_env());
             Ok(UnifiedPublisher::zmq(publisher))
         }
-        Ok("kafka") => {
-            let brokers = std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".to_string());
+        "kafka" => {
             let timeout = std::env::var("KAFKA_TIMEOUT_MS")
                 .unwrap_or_else(|_| "5000".to_string())
                 .parse::<u64>()
                 .unwrap_or(5000);
-            let publisher_config = ClientConfig::new()
-                .set("bootstrap.servers", brokers)
-                .set("message.timeout.ms", "5000")
-                .clone();
-
-            println!("Kafka publisher config: {:?}", publisher_config);
+            let exactly_once = ExactlyOnceConfig::from_env().map_err(UnifiedPublisherError::Config)?;
+            let mut publisher_config = kafka_producer_config_from_env().map_err(UnifiedPublisherError::Config)?;
+            exactly_once.apply(&mut publisher_config);
+            log::info!("[KAFKA] Producer config: {}", describe_kafka_config(&publisher_config));
 
-            let publisher = KafkaPublisher::new_with_config(publisher_config, timeout)?;
+            let publisher = KafkaPublisher::new_with_config(publisher_config, timeout)?.with_drain_timeout(drain_timeout).with_format(format).with_watermarks(Watermarks::from_env()).with_denylist(denylist.clone()).with_block_summaries(BlockSummaries::from_env()).with_mev(MevDetector::from_env());
+            let publisher = with_kafka_serialization_from_env(publisher).await?.with_exactly_once(&exactly_once).await?;
 
             Ok(UnifiedPublisher::kafka(publisher))
         }
-        Ok("both") => {
+        // Nothing leaves the process, for dry runs
+        "stdout" => {
+            let publisher = StdoutPublisher::new().with_format(format).with_denylist(denylist);
+            Ok(UnifiedPublisher::stdout(publisher))
+        }
+        "ws" => {
+            let addr = std::env::var("WS_SERVER_ADDR").unwrap_or_else(|_| "0.0.0.0:8900".to_string());
+            let queue_size = std::env::var("WS_SERVER_QUEUE_SIZE")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(1000);
+            let publisher = WsServerPublisher::bind(&addr, queue_size)?.with_format(format).with_denylist(denylist);
+            Ok(UnifiedPublisher::ws(publisher))
+        }
+        "webhook" => {
+            let config = WebhookConfig::from_env().map_err(WebhookPublisherError::Config)?;
+            let publisher = WebhookPublisher::new(config)?.with_format(format).with_denylist(denylist);
+            Ok(UnifiedPublisher::webhook(publisher))
+        }
+        "questdb" => {
+            let config = QuestDbConfig::from_env().map_err(QuestDbPublisherError::Config)?;
+            let publisher = QuestDbPublisher::connect(config).await?.with_denylist(denylist);
+            Ok(UnifiedPublisher::questdb(publisher))
+        }
+        #[cfg(feature = "publisher-postgres")]
+        "postgres" => {
+            let config = PostgresConfig::from_env().map_err(PostgresPublisherError::Config)?;
+            let publisher = PostgresPublisher::connect(config).await?.with_denylist(denylist);
+            Ok(UnifiedPublisher::postgres(publisher))
+        }
+        #[cfg(not(feature = "publisher-postgres"))]
+        "postgres" => Err(UnifiedPublisherError::Config("PUBLISHER_TYPE=postgres requires the publisher-postgres feature".to_string())),
+        #[cfg(feature = "publisher-object-store")]
+        "object-store" => {
+            let config = ObjectStoreConfig::from_env().map_err(ObjectStorePublisherError::Config)?;
+            let publisher = ObjectStorePublisher::new(config)?.with_format(format).with_denylist(denylist);
+            Ok(UnifiedPublisher::object_store(publisher))
+        }
+        #[cfg(not(feature = "publisher-object-store"))]
+        "object-store" => Err(UnifiedPublisherError::Config("PUBLISHER_TYPE=object-store requires the publisher-object-store feature".to_string())),
+        #[cfg(feature = "publisher-pubsub")]
+        "pubsub" => {
+            let config = PubSubConfig::from_env().map_err(PubSubPublisherError::Config)?;
+            let publisher = PubSubPublisher::connect(config).await?.with_format(format).with_denylist(denylist);
+            Ok(UnifiedPublisher::pubsub(publisher))
+        }
+        #[cfg(not(feature = "publisher-pubsub"))]
+        "pubsub" => Err(UnifiedPublisherError::Config("PUBLISHER_TYPE=pubsub requires the publisher-pubsub feature".to_string())),
+        #[cfg(feature = "publisher-mqtt")]
+        "mqtt" => {
+            let config = MqttConfig::from_env().map_err(MqttPublisherError::Config)?;
+            let publisher = MqttPublisher::new(config)?.with_format(format).with_denylist(denylist);
+            Ok(UnifiedPublisher::mqtt(publisher))
+        }
+        #[cfg(not(feature = "publisher-mqtt"))]
+        "mqtt" => Err(UnifiedPublisherError::Config("PUBLISHER_TYPE=mqtt requires the publisher-mqtt feature".to_string())),
+        "both" => {
             let zmq_endpoint = std::env::var("ZMQ_ENDPOINT").unwrap_or_else(|_| "tcp://*:5555".to_string());
-            let zmq_publisher = ZmqPublisher::new(&zmq_endpoint)?;
+            let zmq_publisher = ZmqPublisher::bind(&zmq_endpoint, zmq_options)?.with_format(format).with_compression(zmq_compression).with_drain_timeout(drain_timeout).with_watermarks(Watermarks::from_env()).with_denylist(denylist.clone()).with_block_summaries(BlockSummaries::from_env()).with_mev(MevDetector::from_env());
             
-            let brokers = std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".to_string());
             // let kafka_timeout = std::env::var("KAFKA_TIMEOUT_MS")
             //     .unwrap_or_else(|_| "5000".to_string())
             //     .parse::<u64>()
             //     .unwrap_or(5000);
             // let kafka_publisher = KafkaPublisher::new(&kafka_brokers, kafka_timeout)?;
-            let publisher_config = ClientConfig::new()
-                .set("bootstrap.servers", brokers)
-                .set("message.timeout.ms", "5000")
-                .clone();
-
-            println!("Kafka publisher config: {:?}", publisher_config);
+            let exactly_once = ExactlyOnceConfig::from_env().map_err(UnifiedPublisherError::Config)?;
+            let mut publisher_config = kafka_producer_config_from_env().map_err(UnifiedPublisherError::Config)?;
+            exactly_once.apply(&mut publisher_config);
+            log::info!("[KAFKA] Producer config: {}", describe_kafka_config(&publisher_config));
 
-            let publisher = KafkaPublisher::new_with_config(publisher_config, 5000)?;
+            let publisher = KafkaPublisher::new_with_config(publisher_config, 5000)?.with_drain_timeout(drain_timeout).with_format(format).with_watermarks(Watermarks::from_env()).with_denylist(denylist.clone()).with_block_summaries(BlockSummaries::from_env()).with_mev(MevDetector::from_env());
+            let publisher = with_kafka_serialization_from_env(publisher).await?.with_exactly_once(&exactly_once).await?;
 
 
             let multi_publisher = MultiPublisher::new()
@@ -66,8 +191,87 @@ pub fn create_unified_publisher_from_env() -> Result<UnifiedPublisher, Box<dyn s
         _ => {
             // Default to ZMQ
             let endpoint = std::env::var("ZMQ_ENDPOINT").unwrap_or_else(|_| "tcp://*:5555".to_string());
-            let publisher = ZmqPublisher::new(&endpoint)?;
+            let publisher = ZmqPublisher::bind(&endpoint, zmq_options)?.with_format(format).with_compression(zmq_compression).with_drain_timeout(drain_timeout).with_watermarks(Watermarks::from_env()).with_denylist(denylist.clone()).with_block_summaries(BlockSummaries::from_env()).with_mev(MevDetector::from_env());
             Ok(UnifiedPublisher::zmq(publisher))
         }
+    };
+    let publisher = publisher?;
+
+    let publisher = match resilience {
+        Some(resilience) => {
+            log::info!(
+                "Retrying publishes up to {} attempts, opening circuits after {} failures",
+                resilience.max_attempts,
+                resilience.failure_threshold
+            );
+            UnifiedPublisher::resilient(publisher, resilience)
+        }
+        None => publisher,
+    };
+    // Behind the routes, batches and queue, which a standby keeps running
+    let publisher = UnifiedPublisher::standby(publisher, leadership.standby_buffer());
+    let publisher = match routes {
+        Some(routes) => {
+            log::info!("Routing {} events to {}", EVENTS_TOPIC, routes.topics().join(", "));
+            UnifiedPublisher::routed(publisher, routes)
+        }
+        None => publisher,
+    };
+    let publisher = match batch {
+        Some(batch) => {
+            log::info!("Batching up to {} events every {:?}", batch.max_events, batch.flush_interval);
+            UnifiedPublisher::batched(publisher, batch)
+        }
+        None => publisher,
+    };
+    let publisher = match queue {
+        Some(queue) => {
+            log::info!("Queueing up to {} events ({:?} when full)", queue.capacity, queue.policy);
+            UnifiedPublisher::queued(publisher, queue)
+        }
+        None => publisher,
+    };
+    Ok(match RollingStats::from_env() {
+        Some(stats) => {
+            log::info!("Publishing rolling swap statistics to {}", stats.topic());
+            UnifiedPublisher::with_stats(publisher, stats)
+        }
+        None => publisher,
+    })
+} 
+
+/// Reads `PUBLISHER_DRAIN_TIMEOUT_MS` (default `10000`): how long closing
+/// the publisher on shutdown waits for queued and buffered events.
+pub fn drain_timeout_from_env() -> Result<Duration, String> {
+    match std::env::var("PUBLISHER_DRAIN_TIMEOUT_MS") {
+        Ok(value) => value
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|e| format!("Invalid PUBLISHER_DRAIN_TIMEOUT_MS: {}", e)),
+        Err(_) => Ok(Duration::from_millis(10000)),
+    }
+}
+
+/// Applies `KAFKA_SERIALIZATION`: `json` (default) or `avro`, which
+/// connects to the schema registry and checks the subjects first.
+#[cfg(feature = "kafka-avro")]
+async fn with_kafka_serialization_from_env(publisher: KafkaPublisher) -> Result<KafkaPublisher, UnifiedPublisherError> {
+    match std::env::var("KAFKA_SERIALIZATION").as_deref() {
+        Ok("json") | Err(_) => Ok(publisher),
+        Ok("avro") => {
+            let config = AvroConfig::from_env().map_err(SchemaRegistryError::Config).map_err(KafkaPublisherError::Schema)?;
+            let avro = AvroSerializer::connect(config).await.map_err(KafkaPublisherError::Schema)?;
+            Ok(publisher.with_avro(Some(avro)))
+        }
+        Ok(other) => Err(UnifiedPublisherError::Config(format!("Unknown KAFKA_SERIALIZATION {}, expected json or avro", other))),
+    }
+}
+
+#[cfg(not(feature = "kafka-avro"))]
+async fn with_kafka_serialization_from_env(publisher: KafkaPublisher) -> Result<KafkaPublisher, UnifiedPublisherError> {
+    match std::env::var("KAFKA_SERIALIZATION").as_deref() {
+        Ok("json") | Err(_) => Ok(publisher),
+        Ok("avro") => Err(UnifiedPublisherError::Config("KAFKA_SERIALIZATION=avro requires the kafka-avro feature".to_string())),
+        Ok(other) => Err(UnifiedPublisherError::Config(format!("Unknown KAFKA_SERIALIZATION {}, expected json or avro", other))),
     }
-} 
\ No newline at end of file
+}
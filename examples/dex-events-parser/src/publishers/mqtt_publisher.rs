@@ -0,0 +1,391 @@
+use {
+    super::{
+        common::DexEventData,
+        denylist::MintDenylist,
+        error::PublishErrorKind,
+        lag::DeliveryLag,
+        publisher_metrics::PublisherMetrics,
+        serialization::{EventSerializer, PayloadFormat},
+        traits::Publisher,
+    },
+    async_trait::async_trait,
+    rumqttc::{
+        v5::{
+            mqttbytes::{v5::PublishProperties, QoS},
+            AsyncClient, ClientError, Event, EventLoop, MqttOptions,
+        },
+        Transport,
+    },
+    std::{collections::HashMap, time::Duration},
+};
+
+const DEFAULT_PORT: u16 = 1883;
+const DEFAULT_TOPIC_TEMPLATE: &str = "dex/{event_type}/{platform}";
+const DEFAULT_KEEP_ALIVE_SECS: u64 = 30;
+const DEFAULT_QUEUE_SIZE: usize = 1000;
+/// Pause before the event loop reconnects after a connection error.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// Detail fields naming the pool of an event, the first present one fills
+/// `{pool}`.
+const POOL_FIELDS: [&str; 2] = ["pool", "bonding_curve"];
+
+#[derive(Debug)]
+pub enum MqttPublisherError {
+    Config(String),
+    Serialization(serde_json::Error),
+    /// The event loop stopped, so nothing reaches the broker anymore.
+    Client(Box<ClientError>),
+}
+
+impl MqttPublisherError {
+    pub fn kind(&self) -> PublishErrorKind {
+        match self {
+            MqttPublisherError::Config(_) | MqttPublisherError::Client(_) => PublishErrorKind::Connection,
+            MqttPublisherError::Serialization(_) => PublishErrorKind::Serialization,
+        }
+    }
+}
+
+impl std::fmt::Display for MqttPublisherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MqttPublisherError::Config(e) => write!(f, "MQTT Publisher Error: {}", e),
+            MqttPublisherError::Serialization(e) => write!(f, "MQTT Publisher Error: Failed to serialize data: {}", e),
+            MqttPublisherError::Client(e) => write!(f, "MQTT Publisher Error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MqttPublisherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MqttPublisherError::Config(_) => None,
+            MqttPublisherError::Serialization(e) => Some(e),
+            MqttPublisherError::Client(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Topic,
+    EventType,
+    Platform,
+    Pool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+/// MQTT topic of an event, built from a template such as
+/// `dex/{event_type}/{platform}/{pool}`.
+///
+/// `{topic}` is the parser topic and `{pool}` the pool of the event, or
+/// `unknown` when it has none. Characters MQTT reserves (`/`, `+`, `#`) and
+/// whitespace in the values are replaced with `-`, so each value stays one
+/// topic level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicTemplate {
+    parts: Vec<Part>,
+}
+
+impl TopicTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        if template.is_empty() || template.contains(['+', '#']) {
+            return Err(format!("Invalid MQTT topic template {}, it must be non-empty without + or #", template));
+        }
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("Unclosed placeholder in MQTT topic template {}", template))?;
+            let field = match &rest[start + 1..start + end] {
+                "topic" => Field::Topic,
+                "event_type" => Field::EventType,
+                "platform" => Field::Platform,
+                "pool" => Field::Pool,
+                other => {
+                    return Err(format!(
+                        "Unknown placeholder {{{}}} in MQTT topic template, expected topic, event_type, platform or pool",
+                        other
+                    ))
+                }
+            };
+            parts.push(Part::Field(field));
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Self { parts })
+    }
+
+    pub fn render(&self, topic: &str, data: &DexEventData) -> String {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            let value = match part {
+                Part::Literal(literal) => {
+                    rendered.push_str(literal);
+                    continue;
+                }
+                Part::Field(Field::Topic) => topic,
+                Part::Field(Field::EventType) => &data.event_type,
+                Part::Field(Field::Platform) => &data.platform,
+                Part::Field(Field::Pool) => POOL_FIELDS
+                    .iter()
+                    .find_map(|field| data.details.get(field)?.as_str())
+                    .unwrap_or("unknown"),
+            };
+            rendered.extend(value.chars().map(|c| {
+                if matches!(c, '/' | '+' | '#') || c.is_whitespace() {
+                    '-'
+                } else {
+                    c
+                }
+            }));
+        }
+        rendered
+    }
+}
+
+fn parse_qos(value: &str) -> Result<QoS, String> {
+    match value {
+        "0" => Ok(QoS::AtMostOnce),
+        "1" => Ok(QoS::AtLeastOnce),
+        "2" => Ok(QoS::ExactlyOnce),
+        other => Err(format!("Invalid MQTT QoS {}, expected 0, 1 or 2", other)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub client_id: String,
+    /// Username and password.
+    pub credentials: Option<(String, String)>,
+    pub topic_template: TopicTemplate,
+    pub qos: QoS,
+    /// QoS of the listed event types, others use `qos`.
+    pub qos_by_event_type: HashMap<String, QoS>,
+    pub retain: bool,
+    /// Brokers drop events not delivered within this time, so devices that
+    /// reconnect do not get a backlog of stale ones.
+    pub message_expiry: Option<Duration>,
+    pub keep_alive: Duration,
+    /// Events waiting for the event loop.
+    pub queue_size: usize,
+}
+
+impl MqttConfig {
+    /// Reads `MQTT_HOST` (default `localhost`), `MQTT_PORT` (default `1883`),
+    /// `MQTT_TLS` (default `false`), `MQTT_CLIENT_ID` (default
+    /// `dex-events-parser-<pid>`), `MQTT_USERNAME` and `MQTT_PASSWORD`,
+    /// `MQTT_TOPIC_TEMPLATE` (default `dex/{event_type}/{platform}`),
+    /// `MQTT_QOS` (default `1`), `MQTT_QOS_BY_EVENT_TYPE` (e.g.
+    /// `swap=0,new_pool=2`), `MQTT_RETAIN` (default `false`),
+    /// `MQTT_MESSAGE_EXPIRY_SECS`, `MQTT_KEEP_ALIVE_SECS` (default `30`) and
+    /// `MQTT_QUEUE_SIZE` (default `1000`).
+    pub fn from_env() -> Result<Self, String> {
+        let number = |name: &str, default: u64| -> Result<u64, String> {
+            match std::env::var(name) {
+                Ok(value) => value.parse().map_err(|e| format!("Invalid {} {}: {}", name, value, e)),
+                Err(_) => Ok(default),
+            }
+        };
+        let flag = |name: &str| std::env::var(name).is_ok_and(|value| value == "true" || value == "1");
+
+        let credentials = match (std::env::var("MQTT_USERNAME"), std::env::var("MQTT_PASSWORD")) {
+            (Ok(username), password) => Some((username, password.unwrap_or_default())),
+            (Err(_), _) => None,
+        };
+        let qos_by_event_type = std::env::var("MQTT_QOS_BY_EVENT_TYPE")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (event_type, qos) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid MQTT_QOS_BY_EVENT_TYPE entry {}, expected <event_type>=<qos>", entry))?;
+                Ok((event_type.trim().to_string(), parse_qos(qos.trim())?))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+
+        Ok(Self {
+            host: std::env::var("MQTT_HOST").unwrap_or_else(|_| "localhost".to_string()),
+            port: number("MQTT_PORT", DEFAULT_PORT as u64)? as u16,
+            tls: flag("MQTT_TLS"),
+            client_id: std::env::var("MQTT_CLIENT_ID")
+                .unwrap_or_else(|_| format!("dex-events-parser-{}", std::process::id())),
+            credentials,
+            topic_template: TopicTemplate::parse(
+                &std::env::var("MQTT_TOPIC_TEMPLATE").unwrap_or_else(|_| DEFAULT_TOPIC_TEMPLATE.to_string()),
+            )?,
+            qos: parse_qos(&std::env::var("MQTT_QOS").unwrap_or_else(|_| "1".to_string()))?,
+            qos_by_event_type,
+            retain: flag("MQTT_RETAIN"),
+            message_expiry: match std::env::var("MQTT_MESSAGE_EXPIRY_SECS") {
+                Ok(_) => Some(Duration::from_secs(number("MQTT_MESSAGE_EXPIRY_SECS", 0)?)),
+                Err(_) => None,
+            },
+            keep_alive: Duration::from_secs(number("MQTT_KEEP_ALIVE_SECS", DEFAULT_KEEP_ALIVE_SECS)?.max(5)),
+            queue_size: (number("MQTT_QUEUE_SIZE", DEFAULT_QUEUE_SIZE as u64)? as usize).max(1),
+        })
+    }
+}
+
+/// Publishes events to an MQTT 5 broker, for dashboards and alerting on
+/// small devices that cannot run a Kafka or ZeroMQ client.
+///
+/// Each event goes to the topic its [`TopicTemplate`] renders, with the
+/// configured QoS, with the content type of the payload serializer.
+/// `publish` returns once the event is queued for the broker; the
+/// connection is kept in the background and re-established after errors,
+/// QoS 1 and 2 events in flight being resent.
+#[derive(Clone)]
+pub struct MqttPublisher {
+    client: AsyncClient,
+    topic_template: TopicTemplate,
+    qos: QoS,
+    qos_by_event_type: HashMap<String, QoS>,
+    retain: bool,
+    properties: PublishProperties,
+    format: PayloadFormat,
+    denylist: Option<MintDenylist>,
+    metrics: PublisherMetrics,
+    lag: DeliveryLag,
+}
+
+impl MqttPublisher {
+    /// Connects in the background, so this must be called within a Tokio
+    /// runtime.
+    pub fn new(config: MqttConfig) -> Result<Self, MqttPublisherError> {
+        let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+        options.set_keep_alive(config.keep_alive);
+        if let Some((username, password)) = &config.credentials {
+            options.set_credentials(username, password);
+        }
+        if config.tls {
+            options.set_transport(Transport::tls_with_default_config());
+        }
+        let message_expiry_interval = config
+            .message_expiry
+            .map(|expiry| u32::try_from(expiry.as_secs()).unwrap_or(u32::MAX));
+
+        let (client, event_loop) = AsyncClient::new(options, config.queue_size);
+        tokio::spawn(poll(event_loop, format!("{}:{}", config.host, config.port)));
+
+        Ok(Self {
+            client,
+            topic_template: config.topic_template,
+            qos: config.qos,
+            qos_by_event_type: config.qos_by_event_type,
+            retain: config.retain,
+            properties: PublishProperties {
+                // UTF-8 payload
+                payload_format_indicator: Some(1),
+                message_expiry_interval,
+                content_type: Some(EventSerializer::Json.content_type().to_string()),
+                ..Default::default()
+            },
+            format: PayloadFormat::default(),
+            denylist: None,
+            metrics: PublisherMetrics::new("mqtt"),
+            lag: DeliveryLag::new("mqtt"),
+        })
+    }
+
+    pub fn with_format(mut self, format: PayloadFormat) -> Self {
+        self.properties.payload_format_indicator = (format.serializer == EventSerializer::Json).then_some(1);
+        self.properties.content_type = Some(format.serializer.content_type().to_string());
+        self.format = format;
+        self
+    }
+
+    /// Drops or quarantines events mentioning a denied mint.
+    pub fn with_denylist(mut self, denylist: Option<MintDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    pub fn metrics(&self) -> &PublisherMetrics {
+        &self.metrics
+    }
+
+    pub fn delivery_lag(&self) -> &DeliveryLag {
+        &self.lag
+    }
+}
+
+#[async_trait]
+impl Publisher for MqttPublisher {
+    type Error = MqttPublisherError;
+
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
+        let Some(topic) = self.denylist.as_ref().map_or(Some(topic), |denylist| denylist.route(topic, data)) else {
+            return Ok(());
+        };
+        let payload = self.format.encode_bytes(data).map_err(MqttPublisherError::Serialization)?;
+        let qos = self.qos_by_event_type.get(&data.event_type).copied().unwrap_or(self.qos);
+        let size = payload.len();
+
+        self.lag.submit(data.block_time);
+        self.client
+            .publish_with_properties(
+                self.topic_template.render(topic, data),
+                qos,
+                self.retain,
+                payload,
+                self.properties.clone(),
+            )
+            .await
+            .map_err(|e| MqttPublisherError::Client(Box::new(e)))?;
+        self.metrics.sent(size);
+        // The queue fills up while the broker is away, so a queued event
+        // counts as delivered, like on ZeroMQ
+        self.lag.ack(data.block_time);
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), Self::Error> {
+        self.client.disconnect().await.map_err(|e| MqttPublisherError::Client(Box::new(e)))
+    }
+}
+
+/// Drives the connection until the client disconnects, reconnecting after
+/// errors.
+async fn poll(mut event_loop: EventLoop, broker: String) {
+    let mut connected = false;
+    loop {
+        match event_loop.poll().await {
+            Ok(Event::Incoming(rumqttc::v5::Incoming::ConnAck(_))) => {
+                connected = true;
+                log::info!("[MQTT] Connected to {}", broker);
+            }
+            Ok(Event::Outgoing(rumqttc::Outgoing::Disconnect)) => {
+                log::info!("[MQTT] Disconnected from {}", broker);
+                return;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if connected {
+                    log::warn!("[MQTT] Connection to {} lost: {}", broker, e);
+                } else {
+                    log::debug!("[MQTT] Failed to connect to {}: {}", broker, e);
+                }
+                connected = false;
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
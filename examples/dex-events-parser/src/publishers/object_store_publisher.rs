@@ -0,0 +1,731 @@
+use {
+    super::{
+        common::DexEventData, denylist::MintDenylist, error::PublishErrorKind, lag::DeliveryLag,
+        publisher_metrics::PublisherMetrics, serialization::PayloadFormat, traits::Publisher,
+    },
+    async_trait::async_trait,
+    bytes::Bytes,
+    object_store::{
+        aws::AmazonS3Builder,
+        gcp::GoogleCloudStorageBuilder,
+        local::LocalFileSystem,
+        memory::InMemory,
+        multipart::{MultipartStore, PartId},
+        path::Path,
+        MultipartId, ObjectStore, PutPayload,
+    },
+    parquet::{
+        basic::{Compression, ZstdLevel},
+        data_type::{BoolType, ByteArray, ByteArrayType, DataType, Int32Type, Int64Type},
+        errors::ParquetError,
+        file::{
+            properties::WriterProperties,
+            writer::{SerializedFileWriter, SerializedRowGroupWriter},
+        },
+        schema::parser::parse_message_type,
+    },
+    std::{
+        collections::VecDeque,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex, Weak,
+        },
+        time::{Duration, Instant},
+    },
+    tokio::sync::Notify,
+};
+
+const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+const DEFAULT_MAX_AGE_SECS: u64 = 300;
+const DEFAULT_PART_SIZE: usize = 8 * 1024 * 1024;
+/// Smallest part S3 and GCS accept, except for the last one.
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+const DEFAULT_MAX_PENDING: usize = 16;
+/// How often the uploader checks the age of the buffer.
+const UPLOAD_TICK: Duration = Duration::from_secs(1);
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+const PARQUET_SCHEMA: &str = "
+    message dex_event {
+        REQUIRED BYTE_ARRAY event_type (UTF8);
+        REQUIRED BYTE_ARRAY platform (UTF8);
+        REQUIRED BYTE_ARRAY signature (UTF8);
+        REQUIRED INT64 timestamp;
+        OPTIONAL INT64 ingested_at;
+        OPTIONAL INT64 block_time;
+        OPTIONAL INT64 slot;
+        OPTIONAL INT64 tx_index;
+        OPTIONAL INT32 instruction_index;
+        OPTIONAL BOOLEAN is_inner;
+        OPTIONAL BYTE_ARRAY fee_payer (UTF8);
+        OPTIONAL BYTE_ARRAY trader (UTF8);
+        REQUIRED BYTE_ARRAY details (JSON);
+    }
+";
+
+#[derive(Debug)]
+pub enum ObjectStorePublisherError {
+    Config(String),
+    /// Opening the store or uploading an object failed.
+    Store(object_store::Error),
+    Serialization(serde_json::Error),
+    /// Compressing or writing the Parquet file of a batch failed.
+    Encode(String),
+}
+
+impl ObjectStorePublisherError {
+    pub fn kind(&self) -> PublishErrorKind {
+        match self {
+            ObjectStorePublisherError::Config(_) | ObjectStorePublisherError::Store(_) => PublishErrorKind::Connection,
+            ObjectStorePublisherError::Serialization(_) | ObjectStorePublisherError::Encode(_) => {
+                PublishErrorKind::Serialization
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ObjectStorePublisherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ObjectStorePublisherError::Config(e) => write!(f, "Object Store Publisher Error: {}", e),
+            ObjectStorePublisherError::Store(e) => write!(f, "Object Store Publisher Error: {}", e),
+            ObjectStorePublisherError::Serialization(e) => {
+                write!(f, "Object Store Publisher Error: Failed to serialize data: {}", e)
+            }
+            ObjectStorePublisherError::Encode(e) => {
+                write!(f, "Object Store Publisher Error: Failed to encode a batch: {}", e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjectStorePublisherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjectStorePublisherError::Store(e) => Some(e),
+            ObjectStorePublisherError::Serialization(e) => Some(e),
+            ObjectStorePublisherError::Config(_) | ObjectStorePublisherError::Encode(_) => None,
+        }
+    }
+}
+
+impl From<object_store::Error> for ObjectStorePublisherError {
+    fn from(error: object_store::Error) -> Self {
+        ObjectStorePublisherError::Store(error)
+    }
+}
+
+/// Layout of the archived objects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// One payload per line, the whole object compressed with zstd.
+    #[default]
+    Jsonl,
+    /// A Parquet file compressed with zstd, `details` kept as a JSON column.
+    Parquet,
+}
+
+impl ArchiveFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Jsonl => "jsonl.zst",
+            ArchiveFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(ArchiveFormat::Jsonl),
+            "parquet" => Ok(ArchiveFormat::Parquet),
+            other => Err(format!("Unknown archive format {}, expected jsonl or parquet", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectStoreConfig {
+    /// `s3://bucket/prefix`, `gs://bucket/prefix`, `file:///dir` or
+    /// `memory://`.
+    pub url: String,
+    pub format: ArchiveFormat,
+    /// Uncompressed size at which the buffer is uploaded.
+    pub max_bytes: usize,
+    /// Longest an event waits in the buffer.
+    pub max_age: Duration,
+    /// Objects larger than this are uploaded in parts of this size.
+    pub part_size: usize,
+    pub zstd_level: i32,
+    /// Objects kept for a retry while the store is unreachable, older ones
+    /// are dropped beyond.
+    pub max_pending: usize,
+}
+
+impl ObjectStoreConfig {
+    /// Reads `OBJECT_STORE_URL` (required), `OBJECT_STORE_FORMAT` (`jsonl`
+    /// or `parquet`, default `jsonl`), `OBJECT_STORE_MAX_BYTES` (default 64
+    /// MiB), `OBJECT_STORE_MAX_AGE_SECS` (default `300`),
+    /// `OBJECT_STORE_PART_SIZE` (default 8 MiB, at least 5 MiB),
+    /// `OBJECT_STORE_ZSTD_LEVEL` (default `3`) and `OBJECT_STORE_MAX_PENDING`
+    /// (default `16`).
+    ///
+    /// Credentials come from the variables of each store, e.g.
+    /// `AWS_ACCESS_KEY_ID`, `AWS_REGION` and `AWS_ENDPOINT` for S3 and MinIO,
+    /// or `GOOGLE_SERVICE_ACCOUNT` for GCS.
+    pub fn from_env() -> Result<Self, String> {
+        let url = std::env::var("OBJECT_STORE_URL").map_err(|_| "OBJECT_STORE_URL is not set".to_string())?;
+        let format = match std::env::var("OBJECT_STORE_FORMAT") {
+            Ok(format) => format.parse()?,
+            Err(_) => ArchiveFormat::default(),
+        };
+        let number = |name: &str, default: u64| -> Result<u64, String> {
+            match std::env::var(name) {
+                Ok(value) => value.parse().map_err(|e| format!("Invalid {} {}: {}", name, value, e)),
+                Err(_) => Ok(default),
+            }
+        };
+        let zstd_level = match std::env::var("OBJECT_STORE_ZSTD_LEVEL") {
+            Ok(value) => value
+                .parse()
+                .map_err(|e| format!("Invalid OBJECT_STORE_ZSTD_LEVEL {}: {}", value, e))?,
+            Err(_) => DEFAULT_ZSTD_LEVEL,
+        };
+
+        Ok(Self {
+            url,
+            format,
+            max_bytes: (number("OBJECT_STORE_MAX_BYTES", DEFAULT_MAX_BYTES as u64)? as usize).max(1),
+            max_age: Duration::from_secs(number("OBJECT_STORE_MAX_AGE_SECS", DEFAULT_MAX_AGE_SECS)?.max(1)),
+            part_size: (number("OBJECT_STORE_PART_SIZE", DEFAULT_PART_SIZE as u64)? as usize).max(MIN_PART_SIZE),
+            zstd_level,
+            max_pending: (number("OBJECT_STORE_MAX_PENDING", DEFAULT_MAX_PENDING as u64)? as usize).max(1),
+        })
+    }
+}
+
+/// The store an [`ObjectStoreConfig::url`] points at.
+struct Store {
+    objects: Arc<dyn ObjectStore>,
+    /// `None` for stores without resumable multipart uploads, which get
+    /// every object in a single request.
+    multipart: Option<Arc<dyn MultipartStore>>,
+    prefix: Path,
+}
+
+impl Store {
+    fn open(url: &str) -> Result<Self, ObjectStorePublisherError> {
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| ObjectStorePublisherError::Config(format!("Invalid OBJECT_STORE_URL {}", url)))?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+
+        match scheme {
+            "s3" => {
+                let store = Arc::new(AmazonS3Builder::from_env().with_bucket_name(bucket).build()?);
+                Ok(Self::multipart(store, prefix))
+            }
+            "gs" => {
+                let store = Arc::new(GoogleCloudStorageBuilder::from_env().with_bucket_name(bucket).build()?);
+                Ok(Self::multipart(store, prefix))
+            }
+            "file" => {
+                std::fs::create_dir_all(rest).map_err(|e| {
+                    ObjectStorePublisherError::Config(format!("Failed to create {}: {}", rest, e))
+                })?;
+                Ok(Self {
+                    objects: Arc::new(LocalFileSystem::new_with_prefix(rest)?),
+                    multipart: None,
+                    prefix: Path::default(),
+                })
+            }
+            "memory" => Ok(Self::multipart(Arc::new(InMemory::new()), rest)),
+            other => Err(ObjectStorePublisherError::Config(format!(
+                "Unsupported object store scheme {}, expected s3, gs, file or memory",
+                other
+            ))),
+        }
+    }
+
+    fn multipart<S: ObjectStore + MultipartStore>(store: Arc<S>, prefix: &str) -> Self {
+        Self {
+            objects: store.clone(),
+            multipart: Some(store),
+            prefix: Path::from(prefix),
+        }
+    }
+}
+
+/// Events of a Parquet batch, column by column.
+#[derive(Default)]
+struct Columns {
+    event_type: Vec<ByteArray>,
+    platform: Vec<ByteArray>,
+    signature: Vec<ByteArray>,
+    timestamp: Vec<i64>,
+    ingested_at: Vec<Option<i64>>,
+    block_time: Vec<Option<i64>>,
+    slot: Vec<Option<i64>>,
+    tx_index: Vec<Option<i64>>,
+    instruction_index: Vec<Option<i32>>,
+    is_inner: Vec<Option<bool>>,
+    fee_payer: Vec<Option<ByteArray>>,
+    trader: Vec<Option<ByteArray>>,
+    details: Vec<ByteArray>,
+}
+
+enum Batch {
+    Jsonl(Vec<u8>),
+    Parquet(Box<Columns>),
+}
+
+impl Batch {
+    fn new(format: ArchiveFormat) -> Self {
+        match format {
+            ArchiveFormat::Jsonl => Batch::Jsonl(Vec::new()),
+            ArchiveFormat::Parquet => Batch::Parquet(Box::default()),
+        }
+    }
+
+    /// Adds `data` and returns its uncompressed size.
+    fn push(&mut self, data: &DexEventData, format: &PayloadFormat) -> Result<usize, serde_json::Error> {
+        match self {
+            Batch::Jsonl(lines) => {
+                let payload = format.encode(data)?;
+                lines.extend_from_slice(payload.as_bytes());
+                lines.push(b'\n');
+                Ok(payload.len() + 1)
+            }
+            Batch::Parquet(columns) => {
+                let details = serde_json::to_vec(&data.details)?;
+                let size = data.event_type.len() + data.platform.len() + data.signature.len() + details.len() + 24;
+                columns.event_type.push(data.event_type.as_bytes().to_vec().into());
+                columns.platform.push(data.platform.as_bytes().to_vec().into());
+                columns.signature.push(data.signature.as_bytes().to_vec().into());
+                columns.timestamp.push(data.timestamp as i64);
+                columns.ingested_at.push(data.ingested_at.map(|ingested_at| ingested_at as i64));
+                columns.block_time.push(data.block_time);
+                columns.slot.push(data.slot.map(|slot| slot as i64));
+                columns.tx_index.push(data.tx_index.map(|index| index as i64));
+                columns.instruction_index.push(data.instruction_index.map(|index| index as i32));
+                columns.is_inner.push(data.is_inner);
+                columns
+                    .fee_payer
+                    .push(data.fee_payer.as_ref().map(|fee_payer| fee_payer.as_bytes().to_vec().into()));
+                columns.trader.push(data.trader.as_ref().map(|trader| trader.as_bytes().to_vec().into()));
+                columns.details.push(details.into());
+                Ok(size)
+            }
+        }
+    }
+
+    /// The object for the batch. Blocking.
+    fn encode(self, zstd_level: i32) -> Result<Vec<u8>, ObjectStorePublisherError> {
+        match self {
+            Batch::Jsonl(lines) => zstd::encode_all(lines.as_slice(), zstd_level)
+                .map_err(|e| ObjectStorePublisherError::Encode(e.to_string())),
+            Batch::Parquet(columns) => {
+                encode_parquet(*columns, zstd_level).map_err(|e| ObjectStorePublisherError::Encode(e.to_string()))
+            }
+        }
+    }
+}
+
+fn encode_parquet(columns: Columns, zstd_level: i32) -> Result<Vec<u8>, ParquetError> {
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA)?);
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::try_new(zstd_level)?))
+        .build();
+    let mut writer = SerializedFileWriter::new(Vec::new(), schema, Arc::new(properties))?;
+
+    let mut row_group = writer.next_row_group()?;
+    write_column::<ByteArrayType>(&mut row_group, &columns.event_type, None)?;
+    write_column::<ByteArrayType>(&mut row_group, &columns.platform, None)?;
+    write_column::<ByteArrayType>(&mut row_group, &columns.signature, None)?;
+    write_column::<Int64Type>(&mut row_group, &columns.timestamp, None)?;
+    write_optional_column::<Int64Type>(&mut row_group, &columns.ingested_at)?;
+    write_optional_column::<Int64Type>(&mut row_group, &columns.block_time)?;
+    write_optional_column::<Int64Type>(&mut row_group, &columns.slot)?;
+    write_optional_column::<Int64Type>(&mut row_group, &columns.tx_index)?;
+    write_optional_column::<Int32Type>(&mut row_group, &columns.instruction_index)?;
+    write_optional_column::<BoolType>(&mut row_group, &columns.is_inner)?;
+    write_optional_column::<ByteArrayType>(&mut row_group, &columns.fee_payer)?;
+    write_optional_column::<ByteArrayType>(&mut row_group, &columns.trader)?;
+    write_column::<ByteArrayType>(&mut row_group, &columns.details, None)?;
+    row_group.close()?;
+
+    writer.into_inner()
+}
+
+fn write_column<T: DataType>(
+    row_group: &mut SerializedRowGroupWriter<'_, Vec<u8>>,
+    values: &[T::T],
+    definition_levels: Option<&[i16]>,
+) -> Result<(), ParquetError> {
+    let mut column = row_group
+        .next_column()?
+        .ok_or_else(|| ParquetError::General("Parquet schema has fewer columns than written".to_string()))?;
+    column.typed::<T>().write_batch(values, definition_levels, None)?;
+    column.close()
+}
+
+/// Writes the present values, with a definition level of `0` for the
+/// missing ones.
+fn write_optional_column<T: DataType>(
+    row_group: &mut SerializedRowGroupWriter<'_, Vec<u8>>,
+    values: &[Option<T::T>],
+) -> Result<(), ParquetError> {
+    let present = values.iter().flatten().cloned().collect::<Vec<_>>();
+    let levels = values.iter().map(|value| value.is_some() as i16).collect::<Vec<_>>();
+    write_column::<T>(row_group, &present, Some(&levels))
+}
+
+/// Events not uploaded yet.
+struct Buffer {
+    batch: Batch,
+    events: usize,
+    bytes: usize,
+    /// When the first event was added.
+    since: Option<Instant>,
+    /// Newest block time among the events.
+    block_time: Option<i64>,
+}
+
+impl Buffer {
+    fn new(format: ArchiveFormat) -> Self {
+        Self {
+            batch: Batch::new(format),
+            events: 0,
+            bytes: 0,
+            since: None,
+            block_time: None,
+        }
+    }
+}
+
+/// Progress of a multipart upload, kept across attempts so a retry only
+/// uploads the parts still missing.
+struct MultipartProgress {
+    id: MultipartId,
+    parts: Vec<Option<PartId>>,
+}
+
+/// An encoded batch waiting for its upload.
+struct PendingObject {
+    path: Path,
+    bytes: Bytes,
+    events: usize,
+    /// Acknowledged once uploaded.
+    block_time: Option<i64>,
+    upload: Option<MultipartProgress>,
+}
+
+struct ObjectStoreInner {
+    store: Store,
+    config: ObjectStoreConfig,
+    buffer: Mutex<Buffer>,
+    pending: Mutex<VecDeque<PendingObject>>,
+    /// Held while uploading, so objects are uploaded one at a time and in
+    /// order.
+    uploading: tokio::sync::Mutex<()>,
+    /// Wakes the uploader when an object is queued.
+    queued: Arc<Notify>,
+    sequence: AtomicU64,
+    /// Its queue depth counts objects waiting for their upload.
+    metrics: PublisherMetrics,
+    lag: DeliveryLag,
+}
+
+/// Archives events to S3, GCS, MinIO or a local directory for later
+/// analytics.
+///
+/// Events are buffered and written as an object once the buffer reaches
+/// `max_bytes` uncompressed or `max_age` after its first event, either zstd
+/// compressed JSONL (one payload per line) or Parquet. Objects are named
+/// `<prefix>/dt=<YYYY-MM-DD>/hour=<HH>/<unix ms>-<sequence>.<extension>`
+/// after the time they were cut.
+///
+/// Objects larger than `part_size` are uploaded in parts. A failed upload is
+/// retried with backoff, resuming from the first missing part, while later
+/// objects wait behind it; at most `max_pending` objects wait and the oldest
+/// are dropped beyond. Quarantined events are not archived.
+#[derive(Clone)]
+pub struct ObjectStorePublisher {
+    inner: Arc<ObjectStoreInner>,
+    format: PayloadFormat,
+    denylist: Option<MintDenylist>,
+}
+
+impl ObjectStorePublisher {
+    /// Opens the store and uploads in the background, so this must be
+    /// called within a Tokio runtime.
+    pub fn new(config: ObjectStoreConfig) -> Result<Self, ObjectStorePublisherError> {
+        let store = Store::open(&config.url)?;
+        log::info!("[ARCHIVE] Archiving events to {} as {}", config.url, config.format.extension());
+
+        let inner = Arc::new(ObjectStoreInner {
+            store,
+            buffer: Mutex::new(Buffer::new(config.format)),
+            config,
+            pending: Mutex::new(VecDeque::new()),
+            uploading: tokio::sync::Mutex::new(()),
+            queued: Arc::new(Notify::new()),
+            sequence: AtomicU64::new(0),
+            metrics: PublisherMetrics::new("object_store"),
+            lag: DeliveryLag::new("object_store"),
+        });
+        tokio::spawn(upload_periodically(Arc::downgrade(&inner), inner.queued.clone()));
+        Ok(Self {
+            inner,
+            format: PayloadFormat::default(),
+            denylist: None,
+        })
+    }
+
+    /// Applies to the JSONL payloads, Parquet columns keep their names.
+    pub fn with_format(mut self, format: PayloadFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Drops events mentioning a denied mint, quarantined ones included.
+    pub fn with_denylist(mut self, denylist: Option<MintDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    pub fn metrics(&self) -> &PublisherMetrics {
+        &self.inner.metrics
+    }
+
+    pub fn delivery_lag(&self) -> &DeliveryLag {
+        &self.inner.lag
+    }
+}
+
+#[async_trait]
+impl Publisher for ObjectStorePublisher {
+    type Error = ObjectStorePublisherError;
+
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
+        let routed = self.denylist.as_ref().map_or(Some(topic), |denylist| denylist.route(topic, data));
+        if routed != Some(topic) {
+            return Ok(());
+        }
+        self.inner.lag.submit(data.block_time);
+
+        let full = {
+            let mut buffer = self.inner.buffer.lock().unwrap_or_else(|e| e.into_inner());
+            let size = buffer
+                .batch
+                .push(data, &self.format)
+                .map_err(ObjectStorePublisherError::Serialization)?;
+            buffer.events += 1;
+            buffer.bytes += size;
+            buffer.block_time = buffer.block_time.max(data.block_time);
+            buffer.since.get_or_insert_with(Instant::now);
+            if buffer.bytes >= self.inner.config.max_bytes {
+                Some(self.inner.take_buffer(&mut buffer))
+            } else {
+                None
+            }
+        };
+        if let Some(buffer) = full {
+            self.inner.cut(buffer).await?;
+            self.inner.queued.notify_one();
+        }
+        Ok(())
+    }
+
+    /// Uploads the buffer and every waiting object, once.
+    async fn close(&self) -> Result<(), Self::Error> {
+        let buffer = {
+            let mut buffer = self.inner.buffer.lock().unwrap_or_else(|e| e.into_inner());
+            self.inner.take_buffer(&mut buffer)
+        };
+        self.inner.cut(buffer).await?;
+        self.inner.upload_pending().await.inspect_err(|_| {
+            let left = self.inner.pending.lock().unwrap_or_else(|e| e.into_inner()).len();
+            log::error!("[ARCHIVE] {} objects were not archived", left);
+        })
+    }
+}
+
+impl ObjectStoreInner {
+    fn take_buffer(&self, buffer: &mut Buffer) -> Buffer {
+        std::mem::replace(buffer, Buffer::new(self.config.format))
+    }
+
+    /// Encodes `buffer` and queues it for upload.
+    async fn cut(&self, buffer: Buffer) -> Result<(), ObjectStorePublisherError> {
+        if buffer.events == 0 {
+            return Ok(());
+        }
+        let zstd_level = self.config.zstd_level;
+        let batch = buffer.batch;
+        let bytes = tokio::task::spawn_blocking(move || batch.encode(zstd_level))
+            .await
+            .map_err(|e| ObjectStorePublisherError::Encode(e.to_string()))??;
+
+        let now = chrono::Utc::now();
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        let name = format!(
+            "dt={}/hour={}/{}-{}.{}",
+            now.format("%Y-%m-%d"),
+            now.format("%H"),
+            now.timestamp_millis(),
+            sequence,
+            self.config.format.extension()
+        );
+        let object = PendingObject {
+            path: Path::from_iter(self.store.prefix.parts().chain(Path::from(name.as_str()).parts())),
+            bytes: Bytes::from(bytes),
+            events: buffer.events,
+            block_time: buffer.block_time,
+            upload: None,
+        };
+
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.push_back(object);
+        while pending.len() > self.config.max_pending {
+            let Some(dropped) = pending.pop_front() else {
+                break;
+            };
+            log::error!(
+                "[ARCHIVE] {} objects waiting for the store, dropped {} with {} events",
+                self.config.max_pending,
+                dropped.path,
+                dropped.events
+            );
+            if let (Some(upload), Some(multipart)) = (dropped.upload, self.store.multipart.clone()) {
+                tokio::spawn(async move {
+                    let _ = multipart.abort_multipart(&dropped.path, &upload.id).await;
+                });
+            }
+        }
+        self.metrics.queue_depth(pending.len());
+        Ok(())
+    }
+
+    /// Uploads the waiting objects in order, stopping at the first failure
+    /// which stays first in line.
+    async fn upload_pending(&self) -> Result<(), ObjectStorePublisherError> {
+        let _uploading = self.uploading.lock().await;
+        loop {
+            let Some(mut object) = self.pending.lock().unwrap_or_else(|e| e.into_inner()).pop_front() else {
+                return Ok(());
+            };
+            match self.upload(&mut object).await {
+                Ok(()) => {
+                    log::info!(
+                        "[ARCHIVE] Uploaded {} events to {} ({} bytes)",
+                        object.events,
+                        object.path,
+                        object.bytes.len()
+                    );
+                    self.metrics.sent(object.bytes.len());
+                    self.lag.ack(object.block_time);
+                    self.metrics
+                        .queue_depth(self.pending.lock().unwrap_or_else(|e| e.into_inner()).len());
+                }
+                Err(e) => {
+                    self.pending.lock().unwrap_or_else(|e| e.into_inner()).push_front(object);
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+
+    async fn upload(&self, object: &mut PendingObject) -> Result<(), object_store::Error> {
+        let part_size = self.config.part_size;
+        let multipart = match &self.store.multipart {
+            Some(multipart) if object.bytes.len() > part_size => multipart,
+            _ => {
+                self.store.objects.put(&object.path, PutPayload::from(object.bytes.clone())).await?;
+                return Ok(());
+            }
+        };
+
+        let PendingObject { path, bytes, upload, .. } = object;
+        let progress = match upload {
+            Some(progress) => progress,
+            None => upload.insert(MultipartProgress {
+                id: multipart.create_multipart(path).await?,
+                parts: vec![None; bytes.len().div_ceil(part_size)],
+            }),
+        };
+        let result = async {
+            for (index, part) in progress.parts.iter_mut().enumerate() {
+                if part.is_some() {
+                    continue;
+                }
+                let chunk = bytes.slice(index * part_size..bytes.len().min((index + 1) * part_size));
+                *part = Some(multipart.put_part(path, &progress.id, index, chunk.into()).await?);
+            }
+            let parts = progress.parts.iter().flatten().cloned().collect();
+            multipart.complete_multipart(path, &progress.id, parts).await
+        }
+        .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            // The store forgot the upload, e.g. after its expiry, so the
+            // next attempt starts over
+            Err(e @ object_store::Error::NotFound { .. }) => {
+                *upload = None;
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn buffer_expired(&self) -> bool {
+        let buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        buffer.since.is_some_and(|since| since.elapsed() >= self.config.max_age)
+    }
+}
+
+/// Cuts the buffer once it is `max_age` old and uploads the waiting objects,
+/// backing off after a failure, until the publisher is dropped.
+async fn upload_periodically(inner: Weak<ObjectStoreInner>, queued: Arc<Notify>) {
+    let mut retry_delay = RETRY_DELAY;
+    let mut retry_at = None;
+    loop {
+        tokio::select! {
+            _ = queued.notified() => {}
+            _ = tokio::time::sleep(UPLOAD_TICK) => {}
+        }
+
+        let Some(inner) = inner.upgrade() else {
+            return;
+        };
+        if inner.buffer_expired() {
+            let buffer = {
+                let mut buffer = inner.buffer.lock().unwrap_or_else(|e| e.into_inner());
+                inner.take_buffer(&mut buffer)
+            };
+            if let Err(e) = inner.cut(buffer).await {
+                log::error!("[ARCHIVE] {}", e);
+            }
+        }
+        if retry_at.is_some_and(|at| Instant::now() < at) {
+            continue;
+        }
+        match inner.upload_pending().await {
+            Ok(()) => {
+                retry_delay = RETRY_DELAY;
+                retry_at = None;
+            }
+            Err(e) => {
+                log::warn!("[ARCHIVE] Upload failed, retrying in {:?}: {}", retry_delay, e);
+                retry_at = Some(Instant::now() + retry_delay);
+                retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+}
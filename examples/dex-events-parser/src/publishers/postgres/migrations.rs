@@ -0,0 +1,154 @@
+use sqlx_migrator::{error::Error, migration::Migration, operation::Operation};
+
+/// Tables of the events kept as queryable history. Columns pulled out of
+/// `details` are null when the venue does not report them, `details` keeps
+/// the whole event.
+pub struct InitMigration;
+
+impl Migration<sqlx::Postgres> for InitMigration {
+    fn app(&self) -> &str {
+        "dex_events"
+    }
+
+    fn name(&self) -> &str {
+        "init_event_tables"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<sqlx::Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<sqlx::Postgres>>> {
+        vec![Box::new(InitOperation)]
+    }
+}
+
+struct InitOperation;
+
+const UP: [&str; 9] = [
+    "CREATE TABLE IF NOT EXISTS swaps (
+        id BIGSERIAL PRIMARY KEY,
+        signature TEXT NOT NULL,
+        slot BIGINT,
+        block_time TIMESTAMPTZ,
+        platform TEXT NOT NULL,
+        kind TEXT,
+        pool TEXT,
+        side TEXT,
+        base_mint TEXT,
+        quote_mint TEXT,
+        amount_in NUMERIC,
+        amount_out NUMERIC,
+        details JSONB NOT NULL,
+        inserted_at TIMESTAMPTZ NOT NULL DEFAULT now()
+    )",
+    "CREATE INDEX IF NOT EXISTS swaps_signature ON swaps (signature)",
+    "CREATE INDEX IF NOT EXISTS swaps_pool_block_time ON swaps (pool, block_time)",
+    "CREATE INDEX IF NOT EXISTS swaps_base_mint_block_time ON swaps (base_mint, block_time)",
+    "CREATE TABLE IF NOT EXISTS liquidity (
+        id BIGSERIAL PRIMARY KEY,
+        signature TEXT NOT NULL,
+        slot BIGINT,
+        block_time TIMESTAMPTZ,
+        platform TEXT NOT NULL,
+        kind TEXT,
+        direction TEXT,
+        pool TEXT,
+        details JSONB NOT NULL,
+        inserted_at TIMESTAMPTZ NOT NULL DEFAULT now()
+    )",
+    "CREATE INDEX IF NOT EXISTS liquidity_signature ON liquidity (signature)",
+    "CREATE INDEX IF NOT EXISTS liquidity_pool_block_time ON liquidity (pool, block_time)",
+    "CREATE TABLE IF NOT EXISTS new_pools (
+        id BIGSERIAL PRIMARY KEY,
+        signature TEXT NOT NULL,
+        slot BIGINT,
+        block_time TIMESTAMPTZ,
+        platform TEXT NOT NULL,
+        kind TEXT,
+        pool TEXT,
+        base_mint TEXT,
+        quote_mint TEXT,
+        details JSONB NOT NULL,
+        inserted_at TIMESTAMPTZ NOT NULL DEFAULT now()
+    )",
+    "CREATE INDEX IF NOT EXISTS new_pools_base_mint ON new_pools (base_mint)",
+];
+
+#[async_trait::async_trait]
+impl Operation<sqlx::Postgres> for InitOperation {
+    async fn up(&self, connection: &mut sqlx::PgConnection) -> Result<(), Error> {
+        for statement in UP {
+            sqlx::query(statement).execute(&mut *connection).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, connection: &mut sqlx::PgConnection) -> Result<(), Error> {
+        for table in ["swaps", "liquidity", "new_pools"] {
+            sqlx::query(&format!("DROP TABLE IF EXISTS {}", table))
+                .execute(&mut *connection)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the instruction of each event and makes it unique, so events
+/// redelivered after a reconnect or a retry are inserted once. The legs of
+/// an aggregator route share the instruction index, the venue and pool tell
+/// them apart. Rows from before this migration have no instruction index
+/// and are left as they are.
+pub struct EventKeyMigration;
+
+impl Migration<sqlx::Postgres> for EventKeyMigration {
+    fn app(&self) -> &str {
+        "dex_events"
+    }
+
+    fn name(&self) -> &str {
+        "unique_event_key"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<sqlx::Postgres>>> {
+        vec![Box::new(InitMigration)]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<sqlx::Postgres>>> {
+        vec![Box::new(EventKeyOperation)]
+    }
+}
+
+struct EventKeyOperation;
+
+const TABLES: [&str; 3] = ["swaps", "liquidity", "new_pools"];
+
+#[async_trait::async_trait]
+impl Operation<sqlx::Postgres> for EventKeyOperation {
+    async fn up(&self, connection: &mut sqlx::PgConnection) -> Result<(), Error> {
+        for table in TABLES {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN IF NOT EXISTS instruction_index INTEGER", table))
+                .execute(&mut *connection)
+                .await?;
+            sqlx::query(&format!(
+                "CREATE UNIQUE INDEX IF NOT EXISTS {0}_event_key ON {0} (signature, instruction_index, platform, COALESCE(pool, ''))",
+                table
+            ))
+            .execute(&mut *connection)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, connection: &mut sqlx::PgConnection) -> Result<(), Error> {
+        for table in TABLES {
+            sqlx::query(&format!("DROP INDEX IF EXISTS {}_event_key", table))
+                .execute(&mut *connection)
+                .await?;
+            sqlx::query(&format!("ALTER TABLE {} DROP COLUMN IF EXISTS instruction_index", table))
+                .execute(&mut *connection)
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,387 @@
+pub mod migrations;
+
+use {
+    super::{
+        common::{DexEventData, DexEventPayload}, denylist::MintDenylist, error::PublishErrorKind, lag::DeliveryLag,
+        publisher_metrics::PublisherMetrics, traits::Publisher,
+    },
+    async_trait::async_trait,
+    carbon_postgres_client::PgClient,
+    migrations::{EventKeyMigration, InitMigration},
+    sqlx::{query_builder::Separated, Postgres, QueryBuilder},
+    std::{
+        sync::{Arc, Mutex, Weak},
+        time::Duration,
+    },
+};
+
+const DEFAULT_MIN_CONNECTIONS: u32 = 1;
+const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+const DEFAULT_BATCH_SIZE: usize = 500;
+/// Keeps an insert into the widest table under the 65535 bind parameters
+/// of a statement.
+const MAX_BATCH_SIZE: usize = 4000;
+const DEFAULT_FLUSH_INTERVAL_MS: u64 = 1000;
+/// Batches kept for a retry while the database is unreachable, older events
+/// are dropped beyond.
+const MAX_PENDING_BATCHES: usize = 10;
+
+#[derive(Debug)]
+pub enum PostgresPublisherError {
+    Config(String),
+    /// Connecting or applying the migrations failed.
+    Connection(sqlx::Error),
+    /// A batch insert failed.
+    Insert(sqlx::Error),
+}
+
+impl PostgresPublisherError {
+    pub fn kind(&self) -> PublishErrorKind {
+        match self {
+            PostgresPublisherError::Config(_) | PostgresPublisherError::Connection(_) => PublishErrorKind::Connection,
+            PostgresPublisherError::Insert(sqlx::Error::Database(_)) => PublishErrorKind::Rejected,
+            PostgresPublisherError::Insert(sqlx::Error::PoolTimedOut) => PublishErrorKind::Timeout,
+            PostgresPublisherError::Insert(_) => PublishErrorKind::Connection,
+        }
+    }
+}
+
+impl std::fmt::Display for PostgresPublisherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PostgresPublisherError::Config(e) => write!(f, "Postgres Publisher Error: {}", e),
+            PostgresPublisherError::Connection(e) => {
+                write!(f, "Postgres Publisher Error: Failed to connect or migrate: {}", e)
+            }
+            PostgresPublisherError::Insert(e) => write!(f, "Postgres Publisher Error: Failed to insert events: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PostgresPublisherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PostgresPublisherError::Config(_) => None,
+            PostgresPublisherError::Connection(e) | PostgresPublisherError::Insert(e) => Some(e),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PostgresConfig {
+    pub url: String,
+    pub min_connections: u32,
+    pub max_connections: u32,
+    /// Events per insert.
+    pub batch_size: usize,
+    /// Longest an event waits for its batch to fill.
+    pub flush_interval: Duration,
+}
+
+impl PostgresConfig {
+    /// Reads `POSTGRES_URL` (required), `POSTGRES_MIN_CONNECTIONS` (default
+    /// `1`), `POSTGRES_MAX_CONNECTIONS` (default `5`), `POSTGRES_BATCH_SIZE`
+    /// (default `500`, at most `4000`) and `POSTGRES_FLUSH_INTERVAL_MS`
+    /// (default `1000`).
+    pub fn from_env() -> Result<Self, String> {
+        let url = std::env::var("POSTGRES_URL").map_err(|_| "POSTGRES_URL is not set".to_string())?;
+        let number = |name: &str, default: u64| -> Result<u64, String> {
+            match std::env::var(name) {
+                Ok(value) => value.parse().map_err(|e| format!("Invalid {} {}: {}", name, value, e)),
+                Err(_) => Ok(default),
+            }
+        };
+
+        Ok(Self {
+            url,
+            min_connections: number("POSTGRES_MIN_CONNECTIONS", DEFAULT_MIN_CONNECTIONS as u64)? as u32,
+            max_connections: number("POSTGRES_MAX_CONNECTIONS", DEFAULT_MAX_CONNECTIONS as u64)?.max(1) as u32,
+            batch_size: (number("POSTGRES_BATCH_SIZE", DEFAULT_BATCH_SIZE as u64)? as usize).clamp(1, MAX_BATCH_SIZE),
+            flush_interval: Duration::from_millis(number("POSTGRES_FLUSH_INTERVAL_MS", DEFAULT_FLUSH_INTERVAL_MS)?.max(1)),
+        })
+    }
+}
+
+/// Table an event type is stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Table {
+    Swaps,
+    Liquidity,
+    NewPools,
+}
+
+impl Table {
+    const ALL: [Table; 3] = [Table::Swaps, Table::Liquidity, Table::NewPools];
+
+    /// `None` for the event types that are not stored.
+    fn of(event_type: &str) -> Option<Self> {
+        match event_type {
+            "swap" => Some(Table::Swaps),
+            "liquidity" => Some(Table::Liquidity),
+            "new_pool" => Some(Table::NewPools),
+            _ => None,
+        }
+    }
+
+    fn insert(self) -> &'static str {
+        match self {
+            Table::Swaps => "INSERT INTO swaps (signature, slot, block_time, platform, instruction_index, kind, pool, side, base_mint, quote_mint, amount_in, amount_out, details) ",
+            Table::Liquidity => "INSERT INTO liquidity (signature, slot, block_time, platform, instruction_index, kind, direction, pool, details) ",
+            Table::NewPools => "INSERT INTO new_pools (signature, slot, block_time, platform, instruction_index, kind, pool, base_mint, quote_mint, details) ",
+        }
+    }
+
+    /// Binds the columns of `data` in the order of [`Table::insert`].
+    fn push_row(self, mut values: Separated<'_, '_, Postgres, &'static str>, data: &DexEventData) {
+        values
+            .push_bind(data.signature.clone())
+            .push_bind(data.slot.map(|slot| slot as i64))
+            .push("to_timestamp(")
+            .push_bind_unseparated(data.block_time.map(|block_time| block_time as f64))
+            .push_unseparated(")")
+            .push_bind(data.platform.clone())
+            .push_bind(data.instruction_index.map(|index| index as i32));
+        match self {
+            Table::Swaps => {
+                values
+                    .push_bind(detail_str(data, "type"))
+                    .push_bind(detail_str(data, "pool"))
+                    .push_bind(detail_str(data, "side"))
+                    .push_bind(detail_str(data, "base_mint"))
+                    .push_bind(detail_str(data, "quote_mint"))
+                    .push_bind(detail_amount(data, &["amount_in", "in_amount"]))
+                    .push_unseparated("::NUMERIC")
+                    .push_bind(detail_amount(data, &["amount_out", "out_amount"]))
+                    .push_unseparated("::NUMERIC");
+            }
+            Table::Liquidity => {
+                values
+                    .push_bind(detail_str(data, "action"))
+                    .push_bind(detail_str(data, "type"))
+                    .push_bind(detail_str(data, "pool"));
+            }
+            Table::NewPools => {
+                // The payload has the mints of the normalized pair, ordered like swaps
+                let (pool, base_mint, quote_mint) = match &data.payload {
+                    Some(DexEventPayload::NewPool(pool)) => (pool.pool.clone(), pool.base_mint.clone(), pool.quote_mint.clone()),
+                    _ => (detail_str(data, "pool"), detail_str(data, "base_mint"), detail_str(data, "quote_mint")),
+                };
+                values
+                    .push_bind(detail_str(data, "type"))
+                    .push_bind(pool)
+                    .push_bind(base_mint)
+                    .push_bind(quote_mint);
+            }
+        }
+        values.push_bind(data.details.to_string()).push_unseparated("::JSONB");
+    }
+}
+
+fn detail_str(data: &DexEventData, key: &str) -> Option<String> {
+    data.details.get(key)?.as_str().map(str::to_string)
+}
+
+/// The first of `keys` the event reports, as text for a `NUMERIC` column
+/// since amounts may exceed `i64`.
+fn detail_amount(data: &DexEventData, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| data.detail_u64(key)).map(|amount| amount.to_string())
+}
+
+struct Row {
+    table: Table,
+    data: DexEventData,
+}
+
+struct PostgresInner {
+    client: PgClient,
+    config: PostgresConfig,
+    /// Events waiting for their batch, and those of failed batches.
+    pending: Mutex<Vec<Row>>,
+    metrics: PublisherMetrics,
+    lag: DeliveryLag,
+}
+
+/// Inserts swaps, liquidity changes and new pools into the `swaps`,
+/// `liquidity` and `new_pools` tables, for queryable history without a
+/// consumer. Other event types and quarantined events are not stored.
+///
+/// Events are inserted in batches of `batch_size`, one multi-row insert per
+/// table in a transaction, or `flush_interval` after the first event of a
+/// batch. The tables are created by the migrations of the crate when
+/// connecting. A batch that fails on a connection problem is retried with
+/// the next one, a batch the database refuses is dropped. Events already
+/// stored, e.g. redelivered ones, are skipped.
+#[derive(Clone)]
+pub struct PostgresPublisher {
+    inner: Arc<PostgresInner>,
+    denylist: Option<MintDenylist>,
+}
+
+impl PostgresPublisher {
+    /// Connects, applies the migrations and flushes in the background, so
+    /// this must be called within a Tokio runtime.
+    pub async fn connect(config: PostgresConfig) -> Result<Self, PostgresPublisherError> {
+        let client = PgClient::new(&config.url, config.min_connections, config.max_connections)
+            .await
+            .map_err(PostgresPublisherError::Connection)?;
+        client
+            .migrate(vec![Box::new(InitMigration), Box::new(EventKeyMigration)])
+            .await
+            .map_err(PostgresPublisherError::Connection)?;
+        log::info!("[POSTGRES] Inserting events in batches of {}", config.batch_size);
+
+        let inner = Arc::new(PostgresInner {
+            client,
+            config,
+            pending: Mutex::new(Vec::new()),
+            metrics: PublisherMetrics::new("postgres"),
+            lag: DeliveryLag::new("postgres"),
+        });
+        tokio::spawn(flush_periodically(Arc::downgrade(&inner)));
+        Ok(Self { inner, denylist: None })
+    }
+
+    /// Drops events mentioning a denied mint, quarantined ones included.
+    pub fn with_denylist(mut self, denylist: Option<MintDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    pub fn metrics(&self) -> &PublisherMetrics {
+        &self.inner.metrics
+    }
+
+    pub fn delivery_lag(&self) -> &DeliveryLag {
+        &self.inner.lag
+    }
+}
+
+#[async_trait]
+impl Publisher for PostgresPublisher {
+    type Error = PostgresPublisherError;
+
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
+        let routed = self.denylist.as_ref().map_or(Some(topic), |denylist| denylist.route(topic, data));
+        if routed != Some(topic) {
+            return Ok(());
+        }
+        let Some(table) = Table::of(&data.event_type) else {
+            return Ok(());
+        };
+        self.inner.lag.submit(data.block_time);
+
+        let batch = {
+            let mut pending = self.inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.push(Row {
+                table,
+                data: data.clone(),
+            });
+            let batch = if pending.len() >= self.inner.config.batch_size {
+                Some(std::mem::take(&mut *pending))
+            } else {
+                None
+            };
+            self.inner.metrics.queue_depth(pending.len());
+            batch
+        };
+        match batch {
+            Some(rows) => self.inner.write(rows).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Fails with the events that could not be inserted, nothing retries
+    /// them after closing.
+    async fn close(&self) -> Result<(), Self::Error> {
+        let rows = std::mem::take(&mut *self.inner.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        if rows.is_empty() {
+            return Ok(());
+        }
+        self.inner.insert(&rows).await.map_err(PostgresPublisherError::Insert)?;
+        self.inner.inserted(&rows);
+        Ok(())
+    }
+}
+
+impl PostgresInner {
+    async fn flush(&self) -> Result<(), PostgresPublisherError> {
+        let rows = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        if rows.is_empty() {
+            return Ok(());
+        }
+        self.write(rows).await
+    }
+
+    /// Inserts `rows`, or puts them back for the next batch when the
+    /// database could not be reached. Put back events are not an error:
+    /// the publish that handed them over must not be retried on top.
+    async fn write(&self, rows: Vec<Row>) -> Result<(), PostgresPublisherError> {
+        let error = match self.insert(&rows).await {
+            Ok(()) => {
+                log::debug!("[POSTGRES] Inserted {} events", rows.len());
+                self.inserted(&rows);
+                return Ok(());
+            }
+            Err(e) => PostgresPublisherError::Insert(e),
+        };
+        if error.kind() == PublishErrorKind::Rejected {
+            log::error!("[POSTGRES] Dropping a batch of {} events: {}", rows.len(), error);
+            return Err(error);
+        }
+        log::error!("[POSTGRES] Keeping a batch of {} events for the next one: {}", rows.len(), error);
+
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let newer = std::mem::replace(&mut *pending, rows);
+        pending.extend(newer);
+        let limit = self.config.batch_size * MAX_PENDING_BATCHES;
+        if pending.len() > limit {
+            let dropped = pending.len() - limit;
+            pending.drain(..dropped);
+            log::error!("[POSTGRES] {} events waiting for the database, dropped the {} oldest", limit, dropped);
+        }
+        self.metrics.queue_depth(pending.len());
+        Ok(())
+    }
+
+    /// Acknowledges the newest block time of committed `rows`.
+    fn inserted(&self, rows: &[Row]) {
+        self.lag.ack(rows.iter().filter_map(|row| row.data.block_time).max());
+    }
+
+    async fn insert(&self, rows: &[Row]) -> Result<(), sqlx::Error> {
+        let mut transaction = self.client.pool.begin().await?;
+        for table in Table::ALL {
+            let events = rows.iter().filter(|row| row.table == table).map(|row| &row.data).collect::<Vec<_>>();
+            if events.is_empty() {
+                continue;
+            }
+            // Batches put back after a failure can exceed a statement
+            for chunk in events.chunks(MAX_BATCH_SIZE) {
+                let mut query = QueryBuilder::<Postgres>::new(table.insert());
+                query.push_values(chunk, |values, data| table.push_row(values, data));
+                query.push(" ON CONFLICT DO NOTHING");
+                query.build().execute(&mut *transaction).await?;
+            }
+        }
+        transaction.commit().await
+    }
+}
+
+/// Flushes the pending events every `flush_interval` until the publisher is
+/// dropped.
+async fn flush_periodically(inner: Weak<PostgresInner>) {
+    let Some(period) = inner.upgrade().map(|inner| inner.config.flush_interval) else {
+        return;
+    };
+    let mut interval = tokio::time::interval(period);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let Some(inner) = inner.upgrade() else {
+            return;
+        };
+        if let Err(e) = inner.flush().await {
+            log::error!("[POSTGRES] {}", e);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use {
+    carbon_core::metrics::Metrics,
+    std::{
+        future::Future,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// How often publisher metrics are reported.
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Latency samples kept between two reports; publishes beyond it still
+/// count, their latency is not sampled.
+const MAX_LATENCY_SAMPLES: usize = 10_000;
+
+#[derive(Debug, Default)]
+struct PublisherStats {
+    published: u64,
+    failed: u64,
+    bytes_sent: u64,
+    /// Milliseconds per publish call since the last report.
+    latencies_ms: Vec<f64>,
+    queue_depth: Option<usize>,
+}
+
+/// Health of one backend: publish outcomes and latency, bytes handed to
+/// the backend, and its internal queue depth where it has one. Counted
+/// between reports by [`PublisherMetrics::spawn`].
+#[derive(Clone)]
+pub struct PublisherMetrics {
+    backend: &'static str,
+    stats: Arc<Mutex<PublisherStats>>,
+}
+
+impl PublisherMetrics {
+    pub fn new(backend: &'static str) -> Self {
+        Self {
+            backend,
+            stats: Arc::new(Mutex::new(PublisherStats::default())),
+        }
+    }
+
+    pub fn backend(&self) -> &'static str {
+        self.backend
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PublisherStats> {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Awaits a publish of `events` events and records its outcome and
+    /// latency.
+    pub async fn time<T, E>(&self, events: usize, publish: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+        let started = Instant::now();
+        let result = publish.await;
+        let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        let mut stats = self.lock();
+        match result {
+            Ok(_) => stats.published += events as u64,
+            Err(_) => stats.failed += events as u64,
+        }
+        if stats.latencies_ms.len() < MAX_LATENCY_SAMPLES {
+            stats.latencies_ms.push(latency_ms);
+        }
+        result
+    }
+
+    /// Records a payload handed to the backend.
+    pub fn sent(&self, bytes: usize) {
+        self.lock().bytes_sent += bytes as u64;
+    }
+
+    /// Records how many events wait in the backend's own buffer.
+    pub fn queue_depth(&self, depth: usize) {
+        self.lock().queue_depth = Some(depth);
+    }
+
+    async fn report(&self, metrics: &dyn Metrics) {
+        let stats = {
+            let mut stats = self.lock();
+            // The depth stays until the backend updates it
+            let queue_depth = stats.queue_depth;
+            std::mem::replace(
+                &mut *stats,
+                PublisherStats {
+                    queue_depth,
+                    ..Default::default()
+                },
+            )
+        };
+        let counters = [
+            ("publisher_published", stats.published),
+            ("publisher_failed", stats.failed),
+            ("publisher_bytes_sent", stats.bytes_sent),
+        ];
+        for (name, value) in counters {
+            metrics
+                .increment_counter(&format!("{}_{}", name, self.backend), value)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        }
+        let histogram = format!("publisher_publish_latency_ms_{}", self.backend);
+        for latency_ms in stats.latencies_ms {
+            metrics
+                .record_histogram(&histogram, latency_ms)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        }
+        if let Some(depth) = stats.queue_depth {
+            metrics
+                .update_gauge(&format!("publisher_queue_depth_{}", self.backend), depth as f64)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        }
+    }
+
+    /// Reports every backend in `publishers` periodically.
+    pub fn spawn(publishers: Vec<PublisherMetrics>, metrics: Arc<dyn Metrics>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REPORT_INTERVAL);
+            loop {
+                interval.tick().await;
+                for publisher in &publishers {
+                    publisher.report(metrics.as_ref()).await;
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,413 @@
+use {
+    super::{
+        common::DexEventData, denylist::MintDenylist, error::PublishErrorKind, lag::DeliveryLag,
+        publisher_metrics::PublisherMetrics, serialization::PayloadFormat, traits::Publisher,
+    },
+    async_trait::async_trait,
+    base64::{engine::general_purpose::STANDARD, Engine},
+    google_cloud_auth::{project::Config as AuthConfig, token::DefaultTokenSourceProvider},
+    google_cloud_token::{TokenSource, TokenSourceProvider},
+    serde::Serialize,
+    std::{collections::HashMap, sync::Arc, time::Duration},
+    tokio::sync::{mpsc, oneshot},
+};
+
+const DEFAULT_ENDPOINT: &str = "https://pubsub.googleapis.com";
+const SCOPES: [&str; 1] = ["https://www.googleapis.com/auth/pubsub"];
+const DEFAULT_BATCH_SIZE: usize = 100;
+/// Most messages Pub/Sub accepts in one publish request.
+const MAX_BATCH_SIZE: usize = 1000;
+const DEFAULT_BATCH_DELAY_MS: u64 = 10;
+const DEFAULT_TIMEOUT_MS: u64 = 10000;
+const DEFAULT_RETRIES: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_millis(200);
+/// Backoff between retries stops doubling here.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+/// Detail fields naming the pool of an event, the first present one is the
+/// ordering key.
+const POOL_FIELDS: [&str; 2] = ["pool", "bonding_curve"];
+
+#[derive(Debug)]
+pub enum PubSubPublisherError {
+    Config(String),
+    /// No credentials were found or no token could be fetched.
+    Credentials(String),
+    Serialization(serde_json::Error),
+    /// The publish request failed on every attempt.
+    Request { topic: String, message: String, timeout: bool },
+    /// Pub/Sub answered with an error status on every attempt.
+    Status { topic: String, status: u16, message: String },
+    /// The sender stopped, the publisher is shutting down.
+    Closed,
+}
+
+impl PubSubPublisherError {
+    pub fn kind(&self) -> PublishErrorKind {
+        match self {
+            PubSubPublisherError::Config(_)
+            | PubSubPublisherError::Credentials(_)
+            | PubSubPublisherError::Closed => PublishErrorKind::Connection,
+            PubSubPublisherError::Serialization(_) => PublishErrorKind::Serialization,
+            PubSubPublisherError::Request { timeout: true, .. } => PublishErrorKind::Timeout,
+            PubSubPublisherError::Request { .. } => PublishErrorKind::Connection,
+            PubSubPublisherError::Status { status, .. } if is_retryable_status(*status) => PublishErrorKind::Connection,
+            PubSubPublisherError::Status { .. } => PublishErrorKind::Rejected,
+        }
+    }
+
+    /// The error of a failed request, for each of its events.
+    fn for_event(&self) -> Self {
+        match self {
+            PubSubPublisherError::Credentials(message) => PubSubPublisherError::Credentials(message.clone()),
+            PubSubPublisherError::Request { topic, message, timeout } => PubSubPublisherError::Request {
+                topic: topic.clone(),
+                message: message.clone(),
+                timeout: *timeout,
+            },
+            PubSubPublisherError::Status { topic, status, message } => PubSubPublisherError::Status {
+                topic: topic.clone(),
+                status: *status,
+                message: message.clone(),
+            },
+            other => PubSubPublisherError::Config(other.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for PubSubPublisherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PubSubPublisherError::Config(e) => write!(f, "Pub/Sub Publisher Error: {}", e),
+            PubSubPublisherError::Credentials(e) => write!(f, "Pub/Sub Publisher Error: Credentials: {}", e),
+            PubSubPublisherError::Serialization(e) => {
+                write!(f, "Pub/Sub Publisher Error: Failed to serialize data: {}", e)
+            }
+            PubSubPublisherError::Request { topic, message, .. } => {
+                write!(f, "Pub/Sub Publisher Error: Failed to publish to {}: {}", topic, message)
+            }
+            PubSubPublisherError::Status { topic, status, message } => {
+                write!(f, "Pub/Sub Publisher Error: Publishing to {} failed with status {}: {}", topic, status, message)
+            }
+            PubSubPublisherError::Closed => write!(f, "Pub/Sub Publisher Error: Publisher is closed"),
+        }
+    }
+}
+
+impl std::error::Error for PubSubPublisherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PubSubPublisherError::Serialization(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Rate limits and server errors may pass on a retry, other client errors
+/// will not.
+fn is_retryable_status(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+#[derive(Debug, Clone)]
+pub struct PubSubConfig {
+    /// Discovered from the credentials when `None`.
+    pub project_id: Option<String>,
+    /// Prepended to the parser topic to name the Pub/Sub topic.
+    pub topic_prefix: String,
+    pub endpoint: String,
+    /// Talks to an emulator, without credentials.
+    pub emulator: bool,
+    pub batch_size: usize,
+    /// Longest the first event of a batch waits for more.
+    pub batch_delay: Duration,
+    pub timeout: Duration,
+    pub retries: u32,
+}
+
+impl PubSubConfig {
+    /// Reads `PUBSUB_PROJECT_ID` (default from the credentials),
+    /// `PUBSUB_TOPIC_PREFIX` (default empty), `PUBSUB_ENDPOINT` (default
+    /// `https://pubsub.googleapis.com`), `PUBSUB_BATCH_SIZE` (default `100`, at
+    /// most `1000`), `PUBSUB_BATCH_DELAY_MS` (default `10`),
+    /// `PUBSUB_TIMEOUT_MS` (default `10000`) and `PUBSUB_RETRIES` (default
+    /// `3`). `PUBSUB_EMULATOR_HOST` points the publisher at an emulator.
+    pub fn from_env() -> Result<Self, String> {
+        let number = |name: &str, default: u64| -> Result<u64, String> {
+            match std::env::var(name) {
+                Ok(value) => value.parse().map_err(|e| format!("Invalid {} {}: {}", name, value, e)),
+                Err(_) => Ok(default),
+            }
+        };
+        let emulator_host = std::env::var("PUBSUB_EMULATOR_HOST").ok();
+        let endpoint = match &emulator_host {
+            Some(host) => format!("http://{}", host),
+            None => std::env::var("PUBSUB_ENDPOINT").unwrap_or_else(|_| DEFAULT_ENDPOINT.to_string()),
+        };
+
+        Ok(Self {
+            project_id: std::env::var("PUBSUB_PROJECT_ID").ok(),
+            topic_prefix: std::env::var("PUBSUB_TOPIC_PREFIX").unwrap_or_default(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            emulator: emulator_host.is_some(),
+            batch_size: (number("PUBSUB_BATCH_SIZE", DEFAULT_BATCH_SIZE as u64)? as usize).clamp(1, MAX_BATCH_SIZE),
+            batch_delay: Duration::from_millis(number("PUBSUB_BATCH_DELAY_MS", DEFAULT_BATCH_DELAY_MS)?),
+            timeout: Duration::from_millis(number("PUBSUB_TIMEOUT_MS", DEFAULT_TIMEOUT_MS)?),
+            retries: number("PUBSUB_RETRIES", DEFAULT_RETRIES as u64)? as u32,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PubsubMessage {
+    data: String,
+    attributes: HashMap<&'static str, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ordering_key: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PublishRequest<'a> {
+    messages: Vec<&'a PubsubMessage>,
+}
+
+/// An event waiting for its batch, with the caller waiting for the outcome.
+struct Pending {
+    topic: String,
+    message: PubsubMessage,
+    done: oneshot::Sender<Result<(), PubSubPublisherError>>,
+}
+
+struct PubSubSender {
+    client: reqwest::Client,
+    /// `None` for the emulator.
+    token_source: Option<Arc<dyn TokenSource>>,
+    project_id: String,
+    config: PubSubConfig,
+    metrics: PublisherMetrics,
+}
+
+/// Publishes events to Google Cloud Pub/Sub, to the topic named after the
+/// parser topic with `topic_prefix` in front.
+///
+/// Each event carries the pool it happened in as ordering key, so
+/// subscriptions with message ordering get the events of a pool in order;
+/// events without a pool have none. The `topic`, `event_type` and `platform`
+/// attributes allow subscription filters.
+///
+/// Credentials are discovered like the Google client libraries do, from
+/// `GOOGLE_APPLICATION_CREDENTIALS`, the gcloud user credentials or the
+/// metadata server. Events are sent in batches of up to `batch_size`, one
+/// request at a time so the ordering holds, and `publish` returns once
+/// Pub/Sub accepted the batch of the event.
+#[derive(Clone)]
+pub struct PubSubPublisher {
+    queue: mpsc::Sender<Pending>,
+    format: PayloadFormat,
+    denylist: Option<MintDenylist>,
+    metrics: PublisherMetrics,
+    lag: DeliveryLag,
+}
+
+impl PubSubPublisher {
+    /// Discovers the credentials and sends in the background, so this must
+    /// be called within a Tokio runtime.
+    pub async fn connect(config: PubSubConfig) -> Result<Self, PubSubPublisherError> {
+        let (token_source, discovered_project) = if config.emulator {
+            (None, None)
+        } else {
+            let provider = DefaultTokenSourceProvider::new(AuthConfig::default().with_scopes(&SCOPES))
+                .await
+                .map_err(|e| PubSubPublisherError::Credentials(e.to_string()))?;
+            (Some(provider.token_source()), provider.project_id.clone())
+        };
+        let project_id = config.project_id.clone().or(discovered_project).ok_or_else(|| {
+            PubSubPublisherError::Config("No project in the credentials, set PUBSUB_PROJECT_ID".to_string())
+        })?;
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| PubSubPublisherError::Config(e.to_string()))?;
+        log::info!(
+            "[PUBSUB] Publishing to projects/{}/topics/{}<topic> at {}",
+            project_id,
+            config.topic_prefix,
+            config.endpoint
+        );
+
+        let (queue, pending) = mpsc::channel(config.batch_size * 10);
+        let metrics = PublisherMetrics::new("pubsub");
+        let sender = PubSubSender {
+            client,
+            token_source,
+            project_id,
+            config,
+            metrics: metrics.clone(),
+        };
+        tokio::spawn(sender.run(pending));
+        Ok(Self {
+            queue,
+            format: PayloadFormat::default(),
+            denylist: None,
+            metrics,
+            lag: DeliveryLag::new("pubsub"),
+        })
+    }
+
+    pub fn with_format(mut self, format: PayloadFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Drops or quarantines events mentioning a denied mint.
+    pub fn with_denylist(mut self, denylist: Option<MintDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    pub fn metrics(&self) -> &PublisherMetrics {
+        &self.metrics
+    }
+
+    pub fn delivery_lag(&self) -> &DeliveryLag {
+        &self.lag
+    }
+}
+
+#[async_trait]
+impl Publisher for PubSubPublisher {
+    type Error = PubSubPublisherError;
+
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
+        let Some(topic) = self.denylist.as_ref().map_or(Some(topic), |denylist| denylist.route(topic, data)) else {
+            return Ok(());
+        };
+        let payload = self.format.encode_bytes(data).map_err(PubSubPublisherError::Serialization)?;
+
+        let message = PubsubMessage {
+            data: STANDARD.encode(payload),
+            attributes: HashMap::from([
+                ("topic", topic.to_string()),
+                ("event_type", data.event_type.clone()),
+                ("platform", data.platform.clone()),
+            ]),
+            ordering_key: POOL_FIELDS
+                .iter()
+                .find_map(|field| data.details.get(field)?.as_str())
+                .map(str::to_string),
+        };
+        self.lag.submit(data.block_time);
+        let (done, outcome) = oneshot::channel();
+        self.queue
+            .send(Pending {
+                topic: topic.to_string(),
+                message,
+                done,
+            })
+            .await
+            .map_err(|_| PubSubPublisherError::Closed)?;
+        self.metrics.queue_depth(self.queue.max_capacity() - self.queue.capacity());
+        outcome.await.map_err(|_| PubSubPublisherError::Closed)??;
+        self.lag.ack(data.block_time);
+        Ok(())
+    }
+
+    /// Every `publish` waits for its batch, so nothing is left to send.
+    async fn close(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl PubSubSender {
+    /// Sends the queued events in batches until every publisher is dropped.
+    async fn run(self, mut pending: mpsc::Receiver<Pending>) {
+        while let Some(first) = pending.recv().await {
+            let mut batch = vec![first];
+            let deadline = tokio::time::sleep(self.config.batch_delay);
+            tokio::pin!(deadline);
+            while batch.len() < self.config.batch_size {
+                tokio::select! {
+                    next = pending.recv() => match next {
+                        Some(next) => batch.push(next),
+                        None => break,
+                    },
+                    _ = &mut deadline => break,
+                }
+            }
+
+            let mut topics: Vec<(String, Vec<Pending>)> = Vec::new();
+            for event in batch {
+                match topics.iter_mut().find(|(topic, _)| *topic == event.topic) {
+                    Some((_, events)) => events.push(event),
+                    None => topics.push((event.topic.clone(), vec![event])),
+                }
+            }
+            for (topic, events) in topics {
+                let result = self.publish_with_retries(&topic, &events).await;
+                if let Err(e) = &result {
+                    log::error!("[PUBSUB] {} events not published: {}", events.len(), e);
+                }
+                for event in events {
+                    let _ = event.done.send(result.as_ref().map(|_| ()).map_err(|e| e.for_event()));
+                }
+            }
+        }
+    }
+
+    async fn publish_with_retries(&self, topic: &str, events: &[Pending]) -> Result<(), PubSubPublisherError> {
+        let mut backoff = RETRY_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            match self.publish(topic, events).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < self.config.retries && e.kind().is_retryable() => {
+                    log::warn!("[PUBSUB] Retrying in {:?}: {}", backoff, e);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn publish(&self, topic: &str, events: &[Pending]) -> Result<(), PubSubPublisherError> {
+        let pubsub_topic = format!("projects/{}/topics/{}{}", self.project_id, self.config.topic_prefix, topic);
+        let request_error = |e: reqwest::Error| PubSubPublisherError::Request {
+            topic: pubsub_topic.clone(),
+            message: e.to_string(),
+            timeout: e.is_timeout(),
+        };
+
+        let mut request = self
+            .client
+            .post(format!("{}/v1/{}:publish", self.config.endpoint, pubsub_topic))
+            .json(&PublishRequest {
+                messages: events.iter().map(|event| &event.message).collect(),
+            });
+        if let Some(token_source) = &self.token_source {
+            let token = token_source
+                .token()
+                .await
+                .map_err(|e| PubSubPublisherError::Credentials(e.to_string()))?;
+            request = request.header(reqwest::header::AUTHORIZATION, token);
+        }
+
+        let response = request.send().await.map_err(request_error)?;
+        let status = response.status();
+        if status.is_success() {
+            self.metrics.sent(events.iter().map(|event| event.message.data.len()).sum());
+            return Ok(());
+        }
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|body| Some(body.get("error")?.get("message")?.as_str()?.to_string()))
+            .unwrap_or(body);
+        Err(PubSubPublisherError::Status {
+            topic: pubsub_topic,
+            status: status.as_u16(),
+            message,
+        })
+    }
+}
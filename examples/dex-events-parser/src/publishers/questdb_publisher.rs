@@ -0,0 +1,517 @@
+use {
+    super::{
+        common::DexEventData, denylist::MintDenylist, error::PublishErrorKind, lag::DeliveryLag,
+        publisher_metrics::PublisherMetrics, traits::Publisher,
+    },
+    async_trait::async_trait,
+    std::{
+        collections::{HashMap, HashSet},
+        fmt::Write as _,
+        sync::{Arc, Mutex, Weak},
+        time::Duration,
+    },
+    tokio::{io::AsyncWriteExt, net::TcpStream},
+};
+
+const DEFAULT_ADDR: &str = "localhost:9009";
+const DEFAULT_TABLE_PREFIX: &str = "dex_";
+const DEFAULT_BATCH_SIZE: usize = 1000;
+const DEFAULT_FLUSH_INTERVAL_MS: u64 = 1000;
+/// Batches kept for a retry while QuestDB is unreachable, older events are
+/// dropped beyond.
+const MAX_PENDING_BATCHES: usize = 10;
+/// String details written as symbols, QuestDB indexes them and stores each
+/// distinct value once.
+const SYMBOL_FIELDS: [&str; 7] = ["pool", "bonding_curve", "type", "side", "action", "base_mint", "quote_mint"];
+
+#[derive(Debug)]
+pub enum QuestDbPublisherError {
+    Config(String),
+    /// Connecting or writing to the ILP port failed.
+    Connection { addr: String, source: std::io::Error },
+}
+
+impl QuestDbPublisherError {
+    pub fn kind(&self) -> PublishErrorKind {
+        match self {
+            QuestDbPublisherError::Config(_) | QuestDbPublisherError::Connection { .. } => PublishErrorKind::Connection,
+        }
+    }
+}
+
+impl std::fmt::Display for QuestDbPublisherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            QuestDbPublisherError::Config(e) => write!(f, "QuestDB Publisher Error: {}", e),
+            QuestDbPublisherError::Connection { addr, source } => {
+                write!(f, "QuestDB Publisher Error: Failed to write to {}: {}", addr, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for QuestDbPublisherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QuestDbPublisherError::Config(_) => None,
+            QuestDbPublisherError::Connection { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Where the designated timestamp of a row comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampSource {
+    /// Block time of the event, or the time it was decoded when it has none.
+    Block,
+    /// Time the event was decoded, rows arrive almost in order.
+    Event,
+    /// QuestDB assigns the time it receives the row, so rows are never out of
+    /// order.
+    Server,
+}
+
+impl TimestampSource {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "block" => Ok(TimestampSource::Block),
+            "event" => Ok(TimestampSource::Event),
+            "server" => Ok(TimestampSource::Server),
+            other => Err(format!("Invalid QUESTDB_TIMESTAMP {}, expected block, event or server", other)),
+        }
+    }
+
+    /// Nanoseconds since the Unix epoch, `None` when QuestDB assigns it.
+    fn of(self, data: &DexEventData) -> Option<u64> {
+        match self {
+            // The timestamp is the block time when the event has one
+            TimestampSource::Block => Some(data.timestamp.saturating_mul(1_000_000_000)),
+            TimestampSource::Event => Some(match data.ingested_at {
+                Some(ingested_at) => ingested_at.saturating_mul(1_000_000),
+                None => data.timestamp.saturating_mul(1_000_000_000),
+            }),
+            TimestampSource::Server => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QuestDbConfig {
+    /// Host and port of the ILP TCP listener.
+    pub addr: String,
+    /// REST API used to set the out-of-order parameters of the tables, for
+    /// example `http://localhost:9000`.
+    pub http_url: Option<String>,
+    pub table_prefix: String,
+    /// Table name of the listed platforms, without the prefix. Others use
+    /// the platform name.
+    pub table_names: HashMap<String, String>,
+    pub timestamp: TimestampSource,
+    /// How late a row may arrive and still be merged without rewriting
+    /// partitions, QuestDB's `o3MaxLag`.
+    pub o3_max_lag: Option<Duration>,
+    /// Rows QuestDB buffers before committing, `maxUncommittedRows`.
+    pub max_uncommitted_rows: Option<u64>,
+    /// Rows per write.
+    pub batch_size: usize,
+    /// Longest a row waits for its batch to fill.
+    pub flush_interval: Duration,
+}
+
+impl QuestDbConfig {
+    /// Reads `QUESTDB_ADDR` (default `localhost:9009`), `QUESTDB_HTTP_URL`,
+    /// `QUESTDB_TABLE_PREFIX` (default `dex_`), `QUESTDB_TABLE_NAMES` (e.g.
+    /// `raydium_amm_v4=raydium,pumpfun=pump`), `QUESTDB_TIMESTAMP` (`block`,
+    /// `event` or `server`, default `block`), `QUESTDB_O3_MAX_LAG_MS`,
+    /// `QUESTDB_MAX_UNCOMMITTED_ROWS`, `QUESTDB_BATCH_SIZE` (default `1000`)
+    /// and `QUESTDB_FLUSH_INTERVAL_MS` (default `1000`).
+    pub fn from_env() -> Result<Self, String> {
+        let number = |name: &str, default: u64| -> Result<u64, String> {
+            match std::env::var(name) {
+                Ok(value) => value.parse().map_err(|e| format!("Invalid {} {}: {}", name, value, e)),
+                Err(_) => Ok(default),
+            }
+        };
+        let optional = |name: &str| -> Result<Option<u64>, String> {
+            match std::env::var(name) {
+                Ok(_) => number(name, 0).map(Some),
+                Err(_) => Ok(None),
+            }
+        };
+
+        let table_names = std::env::var("QUESTDB_TABLE_NAMES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (platform, table) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid QUESTDB_TABLE_NAMES entry {}, expected <platform>=<table>", entry))?;
+                Ok((platform.trim().to_string(), table.trim().to_string()))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+
+        Ok(Self {
+            addr: std::env::var("QUESTDB_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string()),
+            http_url: std::env::var("QUESTDB_HTTP_URL")
+                .ok()
+                .map(|url| url.trim_end_matches('/').to_string())
+                .filter(|url| !url.is_empty()),
+            table_prefix: std::env::var("QUESTDB_TABLE_PREFIX").unwrap_or_else(|_| DEFAULT_TABLE_PREFIX.to_string()),
+            table_names,
+            timestamp: TimestampSource::parse(&std::env::var("QUESTDB_TIMESTAMP").unwrap_or_else(|_| "block".to_string()))?,
+            o3_max_lag: optional("QUESTDB_O3_MAX_LAG_MS")?.map(Duration::from_millis),
+            max_uncommitted_rows: optional("QUESTDB_MAX_UNCOMMITTED_ROWS")?,
+            batch_size: (number("QUESTDB_BATCH_SIZE", DEFAULT_BATCH_SIZE as u64)? as usize).max(1),
+            flush_interval: Duration::from_millis(number("QUESTDB_FLUSH_INTERVAL_MS", DEFAULT_FLUSH_INTERVAL_MS)?.max(1)),
+        })
+    }
+
+    fn table(&self, platform: &str) -> String {
+        let name = self.table_names.get(platform).map_or(platform, String::as_str);
+        format!("{}{}", self.table_prefix, name)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+            .collect()
+    }
+
+    /// `ALTER TABLE` statement applying the out-of-order parameters, `None`
+    /// when none is set.
+    fn table_params(&self, table: &str) -> Option<String> {
+        let mut params = Vec::new();
+        if let Some(lag) = self.o3_max_lag {
+            params.push(format!("o3MaxLag = {}ms", lag.as_millis()));
+        }
+        if let Some(rows) = self.max_uncommitted_rows {
+            params.push(format!("maxUncommittedRows = {}", rows));
+        }
+        (!params.is_empty()).then(|| format!("ALTER TABLE {} SET PARAM {}", table, params.join(", ")))
+    }
+}
+
+/// A row in line protocol, with the table and timestamp it was built from
+/// and the block time of its event.
+struct Line {
+    table: String,
+    timestamp: Option<u64>,
+    block_time: Option<i64>,
+    text: String,
+}
+
+struct QuestDbInner {
+    config: QuestDbConfig,
+    http: Option<reqwest::Client>,
+    stream: tokio::sync::Mutex<Option<TcpStream>>,
+    /// Rows waiting for their batch, and those of failed writes.
+    pending: Mutex<Vec<Line>>,
+    /// Tables whose out-of-order parameters are set.
+    configured: Mutex<HashSet<String>>,
+    metrics: PublisherMetrics,
+    lag: DeliveryLag,
+}
+
+/// Writes events to QuestDB over the InfluxDB line protocol, for price and
+/// volume charts straight from a time-series database.
+///
+/// Each platform has its own table, `<prefix><platform>` or the name given
+/// in `table_names`, created by QuestDB on the first row. `event_type`,
+/// `pool` and the other string details naming an entity are symbols,
+/// numbers become long or double columns and the signature and slot are
+/// kept alongside. Nested details are not written.
+///
+/// Rows are sent in batches of `batch_size`, or `flush_interval` after the
+/// first one, sorted by timestamp so a batch adds few out-of-order rows.
+/// With an `http_url`, `o3_max_lag` and `max_uncommitted_rows` are applied
+/// to each table once it exists. A batch that could not be written is
+/// retried with the next one over a new connection.
+#[derive(Clone)]
+pub struct QuestDbPublisher {
+    inner: Arc<QuestDbInner>,
+    denylist: Option<MintDenylist>,
+}
+
+impl QuestDbPublisher {
+    /// Connects and flushes in the background, so this must be called within
+    /// a Tokio runtime.
+    pub async fn connect(config: QuestDbConfig) -> Result<Self, QuestDbPublisherError> {
+        let stream = connect(&config.addr).await?;
+        let http = match &config.http_url {
+            Some(_) => Some(
+                reqwest::Client::builder()
+                    .timeout(Duration::from_secs(10))
+                    .build()
+                    .map_err(|e| QuestDbPublisherError::Config(format!("Failed to build the HTTP client: {}", e)))?,
+            ),
+            None => None,
+        };
+        log::info!("[QUESTDB] Writing events to {} in batches of {}", config.addr, config.batch_size);
+
+        let inner = Arc::new(QuestDbInner {
+            config,
+            http,
+            stream: tokio::sync::Mutex::new(Some(stream)),
+            pending: Mutex::new(Vec::new()),
+            configured: Mutex::new(HashSet::new()),
+            metrics: PublisherMetrics::new("questdb"),
+            lag: DeliveryLag::new("questdb"),
+        });
+        tokio::spawn(flush_periodically(Arc::downgrade(&inner)));
+        Ok(Self { inner, denylist: None })
+    }
+
+    /// Drops events mentioning a denied mint, quarantined ones included.
+    pub fn with_denylist(mut self, denylist: Option<MintDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    pub fn metrics(&self) -> &PublisherMetrics {
+        &self.inner.metrics
+    }
+
+    pub fn delivery_lag(&self) -> &DeliveryLag {
+        &self.inner.lag
+    }
+}
+
+#[async_trait]
+impl Publisher for QuestDbPublisher {
+    type Error = QuestDbPublisherError;
+
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
+        let routed = self.denylist.as_ref().map_or(Some(topic), |denylist| denylist.route(topic, data));
+        if routed != Some(topic) {
+            return Ok(());
+        }
+        let table = self.inner.config.table(&data.platform);
+        let timestamp = self.inner.config.timestamp.of(data);
+        let line = Line {
+            text: line(&table, timestamp, data),
+            table,
+            timestamp,
+            block_time: data.block_time,
+        };
+        self.inner.lag.submit(data.block_time);
+
+        let batch = {
+            let mut pending = self.inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.push(line);
+            let batch = if pending.len() >= self.inner.config.batch_size {
+                Some(std::mem::take(&mut *pending))
+            } else {
+                None
+            };
+            self.inner.metrics.queue_depth(pending.len());
+            batch
+        };
+        match batch {
+            Some(lines) => self.inner.write(lines).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn close(&self) -> Result<(), Self::Error> {
+        self.inner.flush().await
+    }
+}
+
+impl QuestDbInner {
+    async fn flush(&self) -> Result<(), QuestDbPublisherError> {
+        let lines = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        if lines.is_empty() {
+            return Ok(());
+        }
+        self.write(lines).await
+    }
+
+    /// Sends `lines`, or puts them back for the next batch when QuestDB
+    /// could not be reached.
+    async fn write(&self, mut lines: Vec<Line>) -> Result<(), QuestDbPublisherError> {
+        lines.sort_by_key(|line| line.timestamp);
+        let body = lines.iter().map(|line| line.text.as_str()).collect::<String>();
+
+        let error = match self.send(body.as_bytes()).await {
+            Ok(()) => {
+                log::debug!("[QUESTDB] Wrote {} rows", lines.len());
+                self.metrics.sent(body.len());
+                // Written to the socket is as far as ILP over TCP confirms
+                self.lag.ack(lines.iter().filter_map(|line| line.block_time).max());
+                self.configure_tables(&lines).await;
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let newer = std::mem::replace(&mut *pending, lines);
+        pending.extend(newer);
+        let limit = self.config.batch_size * MAX_PENDING_BATCHES;
+        if pending.len() > limit {
+            let dropped = pending.len() - limit;
+            pending.drain(..dropped);
+            log::error!("[QUESTDB] {} rows waiting for QuestDB, dropped the {} oldest", limit, dropped);
+        }
+        self.metrics.queue_depth(pending.len());
+        Err(error)
+    }
+
+    /// Writes to the open connection, reconnecting first when the previous
+    /// write failed. ILP over TCP has no acknowledgements, so rows QuestDB
+    /// rejects are only reported in its log.
+    async fn send(&self, body: &[u8]) -> Result<(), QuestDbPublisherError> {
+        let mut stream = self.stream.lock().await;
+        if stream.is_none() {
+            *stream = Some(connect(&self.config.addr).await?);
+        }
+        let connection = stream.as_mut().expect("connected above");
+        let result = match connection.write_all(body).await {
+            Ok(()) => connection.flush().await,
+            Err(e) => Err(e),
+        };
+        result.map_err(|source| {
+            *stream = None;
+            QuestDbPublisherError::Connection {
+                addr: self.config.addr.clone(),
+                source,
+            }
+        })
+    }
+
+    /// Applies the out-of-order parameters to the tables of `lines` not done
+    /// yet. QuestDB creates tables asynchronously, so a failed statement is
+    /// tried again after the next write.
+    async fn configure_tables(&self, lines: &[Line]) {
+        let (Some(http), Some(url)) = (&self.http, &self.config.http_url) else {
+            return;
+        };
+        let tables = {
+            let configured = self.configured.lock().unwrap_or_else(|e| e.into_inner());
+            lines
+                .iter()
+                .map(|line| line.table.as_str())
+                .filter(|table| !configured.contains(*table))
+                .collect::<HashSet<_>>()
+        };
+        for table in tables {
+            let Some(statement) = self.config.table_params(table) else {
+                return;
+            };
+            let response = http.get(format!("{}/exec", url)).query(&[("query", &statement)]).send().await;
+            match response {
+                Ok(response) if response.status().is_success() => {
+                    log::info!("[QUESTDB] {}", statement);
+                    self.configured.lock().unwrap_or_else(|e| e.into_inner()).insert(table.to_string());
+                }
+                Ok(response) => log::debug!("[QUESTDB] {} answered {}, retrying later", statement, response.status()),
+                Err(e) => log::debug!("[QUESTDB] {} failed, retrying later: {}", statement, e),
+            }
+        }
+    }
+}
+
+async fn connect(addr: &str) -> Result<TcpStream, QuestDbPublisherError> {
+    let stream = TcpStream::connect(addr).await.map_err(|source| QuestDbPublisherError::Connection {
+        addr: addr.to_string(),
+        source,
+    })?;
+    // Batches are written at once, nothing gains from waiting
+    let _ = stream.set_nodelay(true);
+    Ok(stream)
+}
+
+/// `data` as a line of the InfluxDB line protocol, ending with a newline.
+fn line(table: &str, timestamp: Option<u64>, data: &DexEventData) -> String {
+    let mut line = String::from(table);
+    let symbol = |line: &mut String, key: &str, value: &str| {
+        let _ = write!(line, ",{}={}", escape_name(key), escape_name(value));
+    };
+    symbol(&mut line, "event_type", &data.event_type);
+    let details = data.details.as_object();
+    for key in SYMBOL_FIELDS {
+        if let Some(value) = details.and_then(|details| details.get(key)?.as_str()) {
+            symbol(&mut line, key, value);
+        }
+    }
+
+    let _ = write!(line, " signature={}", escape_string(&data.signature));
+    if let Some(slot) = data.slot {
+        let _ = write!(line, ",slot={}i", slot);
+    }
+    for (key, value) in details.into_iter().flatten() {
+        if SYMBOL_FIELDS.contains(&key.as_str()) {
+            continue;
+        }
+        let field = match value {
+            serde_json::Value::Bool(value) => String::from(if *value { "t" } else { "f" }),
+            serde_json::Value::Number(number) => match (number.as_i64(), number.as_f64()) {
+                (Some(integer), _) => format!("{}i", integer),
+                (None, Some(float)) if float.is_finite() => float.to_string(),
+                _ => continue,
+            },
+            serde_json::Value::String(string) => escape_string(string),
+            _ => continue,
+        };
+        let _ = write!(line, ",{}={}", escape_name(key), field);
+    }
+
+    if let Some(timestamp) = timestamp {
+        let _ = write!(line, " {}", timestamp);
+    }
+    line.push('\n');
+    line
+}
+
+/// Escapes a symbol or column name or a symbol value.
+fn escape_name(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ' ' | ',' | '=' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Quotes and escapes a string column value.
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Flushes the pending rows every `flush_interval` until the publisher is
+/// dropped.
+async fn flush_periodically(inner: Weak<QuestDbInner>) {
+    let Some(period) = inner.upgrade().map(|inner| inner.config.flush_interval) else {
+        return;
+    };
+    let mut interval = tokio::time::interval(period);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let Some(inner) = inner.upgrade() else {
+            return;
+        };
+        if let Err(e) = inner.flush().await {
+            log::error!("[QUESTDB] {}", e);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use {
+    super::common::DexEventData,
+    carbon_core::metrics::Metrics,
+    std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+    tokio::sync::Notify,
+};
+
+/// How often the queue depth is reported.
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// What `publish` does when the queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueuePolicy {
+    /// Waits for space, slowing block processing down to the sink's pace.
+    Block,
+    /// Evicts the oldest queued event.
+    DropOldest,
+    /// Discards the incoming event.
+    DropNewest,
+    /// Keeps one in every `n` events once the queue is half full, and
+    /// discards the incoming event when it is full.
+    Sample(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueConfig {
+    pub capacity: usize,
+    pub policy: QueuePolicy,
+}
+
+impl QueueConfig {
+    /// Reads `PUBLISH_QUEUE_SIZE`, `PUBLISH_QUEUE_POLICY` (`block`,
+    /// `drop-oldest`, `drop-newest` or `sample`, default `block`) and
+    /// `PUBLISH_QUEUE_SAMPLE_RATE` (default `10`). `None` when the size is
+    /// unset or `0`, which publishes from the processor's task.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let number = |name: &str, default: u64| match std::env::var(name) {
+            Ok(value) => value.parse::<u64>().map_err(|e| format!("Invalid {}: {}", name, e)),
+            Err(_) => Ok(default),
+        };
+        let capacity = number("PUBLISH_QUEUE_SIZE", 0)? as usize;
+        if capacity == 0 {
+            return Ok(None);
+        }
+        let policy = match std::env::var("PUBLISH_QUEUE_POLICY").as_deref() {
+            Ok("block") | Err(_) => QueuePolicy::Block,
+            Ok("drop-oldest") => QueuePolicy::DropOldest,
+            Ok("drop-newest") => QueuePolicy::DropNewest,
+            Ok("sample") => match number("PUBLISH_QUEUE_SAMPLE_RATE", 10)? {
+                0 => return Err("PUBLISH_QUEUE_SAMPLE_RATE must be positive".to_string()),
+                rate => QueuePolicy::Sample(rate),
+            },
+            Ok(other) => {
+                return Err(format!(
+                    "Unknown PUBLISH_QUEUE_POLICY {}, expected block, drop-oldest, drop-newest or sample",
+                    other
+                ))
+            }
+        };
+        Ok(Some(Self { capacity, policy }))
+    }
+}
+
+// Nearly every item is an event, boxing it would only add an allocation
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum QueueItem {
+    Event(String, DexEventData),
+    /// Watermarks are published in order with the events, so they never
+    /// overtake an event still queued.
+    Watermarks,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    items: VecDeque<QueueItem>,
+    /// Events in `items`, watermark markers excluded.
+    events: usize,
+    watermarks_queued: bool,
+    /// The worker took an item and has not finished publishing it.
+    busy: bool,
+    /// Events seen since the queue passed half full, for sampling.
+    sampled: u64,
+    dropped: u64,
+}
+
+/// Bounded queue between the processors and a sink, drained by one
+/// worker.
+pub struct PublishQueue {
+    config: QueueConfig,
+    state: Mutex<QueueState>,
+    /// Signalled when an item is queued.
+    available: Notify,
+    /// Signalled when an item is taken.
+    space: Notify,
+    /// Signalled when the worker finished an item.
+    done: Notify,
+}
+
+impl PublishQueue {
+    pub fn new(config: QueueConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(QueueState::default()),
+            available: Notify::new(),
+            space: Notify::new(),
+            done: Notify::new(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queues an event according to the policy. Only waits with
+    /// [`QueuePolicy::Block`].
+    pub async fn push(&self, topic: &str, data: &DexEventData) {
+        loop {
+            {
+                let mut state = self.lock();
+                let full = state.events >= self.config.capacity;
+                let keep = match self.config.policy {
+                    QueuePolicy::Block if full => None,
+                    QueuePolicy::Block => Some(true),
+                    QueuePolicy::DropOldest => {
+                        if full {
+                            if let Some(index) = state.items.iter().position(|item| matches!(item, QueueItem::Event(..))) {
+                                state.items.remove(index);
+                                state.events -= 1;
+                                state.dropped += 1;
+                            }
+                        }
+                        Some(true)
+                    }
+                    QueuePolicy::DropNewest => Some(!full),
+                    QueuePolicy::Sample(rate) => {
+                        if full {
+                            Some(false)
+                        } else if state.events * 2 >= self.config.capacity {
+                            state.sampled += 1;
+                            Some((state.sampled - 1) % rate == 0)
+                        } else {
+                            state.sampled = 0;
+                            Some(true)
+                        }
+                    }
+                };
+                match keep {
+                    Some(true) => {
+                        state.items.push_back(QueueItem::Event(topic.to_string(), data.clone()));
+                        state.events += 1;
+                        drop(state);
+                        self.available.notify_one();
+                        return;
+                    }
+                    Some(false) => {
+                        state.dropped += 1;
+                        return;
+                    }
+                    None => {}
+                }
+            }
+            self.space.notified().await;
+        }
+    }
+
+    /// Queues a watermark marker, unless one is already waiting.
+    pub fn push_watermarks(&self) {
+        let mut state = self.lock();
+        if state.watermarks_queued {
+            return;
+        }
+        state.watermarks_queued = true;
+        state.items.push_back(QueueItem::Watermarks);
+        drop(state);
+        self.available.notify_one();
+    }
+
+    /// Waits for the next item.
+    pub async fn pop(&self) -> QueueItem {
+        loop {
+            {
+                let mut state = self.lock();
+                if let Some(item) = state.items.pop_front() {
+                    match item {
+                        QueueItem::Event(..) => state.events -= 1,
+                        QueueItem::Watermarks => state.watermarks_queued = false,
+                    }
+                    state.busy = true;
+                    drop(state);
+                    self.space.notify_one();
+                    return item;
+                }
+            }
+            self.available.notified().await;
+        }
+    }
+
+    /// Marks the item last taken by [`PublishQueue::pop`] as published.
+    pub fn finished(&self) {
+        self.lock().busy = false;
+        self.done.notify_waiters();
+    }
+
+    /// Waits until the worker published every queued item.
+    pub async fn drained(&self) {
+        loop {
+            let done = self.done.notified();
+            tokio::pin!(done);
+            // Registered before checking, so a finish in between is not missed
+            done.as_mut().enable();
+            {
+                let state = self.lock();
+                if state.items.is_empty() && !state.busy {
+                    return;
+                }
+            }
+            done.await;
+        }
+    }
+
+    async fn report(&self, metrics: &dyn Metrics) {
+        let (depth, dropped) = {
+            let state = self.lock();
+            (state.events, state.dropped)
+        };
+        let gauges = [
+            ("publish_queue_depth", depth as f64),
+            ("publish_queue_capacity", self.config.capacity as f64),
+            ("publish_queue_dropped", dropped as f64),
+        ];
+        for (name, value) in gauges {
+            metrics
+                .update_gauge(name, value)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        }
+    }
+
+    /// Reports the queue depth and dropped events periodically.
+    pub fn spawn_reports(self: &Arc<Self>, metrics: Arc<dyn Metrics>) -> tokio::task::JoinHandle<()> {
+        let queue = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REPORT_INTERVAL);
+            loop {
+                interval.tick().await;
+                queue.report(metrics.as_ref()).await;
+            }
+        })
+    }
+}
@@ -0,0 +1,170 @@
+use {
+    super::{
+        common::DexEventData,
+        traits::Publisher,
+        unified_publisher::{UnifiedPublisher, UnifiedPublisherError},
+    },
+    async_trait::async_trait,
+    std::sync::Arc,
+};
+
+/// Error of a publisher behind a [`DynPublisher`]. A newtype rather than
+/// a bare `Box<dyn Error>`, which does not implement `Error` itself.
+#[derive(Debug)]
+pub struct BoxError(pub Box<dyn std::error::Error + Send + Sync>);
+
+impl BoxError {
+    pub fn new<E: std::error::Error + Send + Sync + 'static>(error: E) -> Self {
+        Self(Box::new(error))
+    }
+
+    pub fn downcast_ref<E: std::error::Error + 'static>(&self) -> Option<&E> {
+        self.0.downcast_ref()
+    }
+}
+
+impl std::fmt::Display for BoxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for BoxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// Object-safe publisher, so sinks defined outside this crate can be
+/// registered next to the built-in ones.
+pub type DynPublisher = dyn Publisher<Error = BoxError>;
+
+/// Adapts any [`Publisher`] to [`DynPublisher`] by boxing its errors.
+pub struct BoxedPublisher<P>(pub P);
+
+#[async_trait]
+impl<P: Publisher> Publisher for BoxedPublisher<P> {
+    type Error = BoxError;
+
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
+        self.0.publish(topic, data).await.map_err(BoxError::new)
+    }
+
+    async fn publish_batch(&self, events: &[(String, DexEventData)]) -> Result<(), Self::Error> {
+        self.0.publish_batch(events).await.map_err(BoxError::new)
+    }
+
+    async fn close(&self) -> Result<(), Self::Error> {
+        self.0.close().await.map_err(BoxError::new)
+    }
+}
+
+/// Publishes every event to each registered sink: built-in publishers,
+/// which keep their watermarks, standby replay and delivery lag, and
+/// custom [`DynPublisher`]s. A failing sink does not stop the others; the
+/// failures are returned together.
+#[derive(Clone, Default)]
+pub struct PublisherRegistry {
+    builtins: Vec<UnifiedPublisher>,
+    custom: Vec<(String, Arc<DynPublisher>)>,
+}
+
+impl PublisherRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a built-in publisher, e.g. the one configured from the
+    /// environment.
+    pub fn with(mut self, publisher: UnifiedPublisher) -> Self {
+        self.builtins.push(publisher);
+        self
+    }
+
+    /// Adds a custom publisher; `name` identifies it in errors.
+    pub fn register<P: Publisher + 'static>(self, name: &str, publisher: P) -> Self {
+        self.register_dyn(name, Arc::new(BoxedPublisher(publisher)))
+    }
+
+    /// Adds a custom publisher already shared elsewhere.
+    pub fn register_dyn(mut self, name: &str, publisher: Arc<DynPublisher>) -> Self {
+        self.custom.push((name.to_string(), publisher));
+        self
+    }
+
+    pub fn builtins(&self) -> &[UnifiedPublisher] {
+        &self.builtins
+    }
+
+    pub fn len(&self) -> usize {
+        self.builtins.len() + self.custom.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn custom_error(name: &str, source: BoxError) -> UnifiedPublisherError {
+        UnifiedPublisherError::Custom {
+            sink: name.to_string(),
+            source,
+        }
+    }
+
+    pub async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Vec<UnifiedPublisherError>> {
+        let mut errors = Vec::new();
+        for publisher in &self.builtins {
+            if let Err(e) = publisher.publish(topic, data).await {
+                errors.push(e);
+            }
+        }
+        for (name, publisher) in &self.custom {
+            if let Err(e) = publisher.publish(topic, data).await {
+                errors.push(Self::custom_error(name, e));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub async fn publish_batch(&self, events: &[(String, DexEventData)]) -> Result<(), Vec<UnifiedPublisherError>> {
+        let mut errors = Vec::new();
+        for publisher in &self.builtins {
+            if let Err(e) = publisher.publish_batch(events).await {
+                errors.push(e);
+            }
+        }
+        for (name, publisher) in &self.custom {
+            if let Err(e) = publisher.publish_batch(events).await {
+                errors.push(Self::custom_error(name, e));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub async fn close(&self) -> Result<(), Vec<UnifiedPublisherError>> {
+        let mut errors = Vec::new();
+        for publisher in &self.builtins {
+            if let Err(e) = publisher.close().await {
+                errors.push(e);
+            }
+        }
+        for (name, publisher) in &self.custom {
+            if let Err(e) = publisher.close().await {
+                errors.push(Self::custom_error(name, e));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
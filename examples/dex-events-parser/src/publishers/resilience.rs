@@ -0,0 +1,298 @@
+use {
+    super::{common::DexEventData, unified_publisher::UnifiedPublisherError},
+    carbon_core::metrics::Metrics,
+    std::{
+        borrow::Cow,
+        future::Future,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// How often circuit breaker state is reported.
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Retries of a failed publish with exponential backoff, and when to stop
+/// calling a sink that keeps failing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResilienceConfig {
+    /// Attempts per publish, including the first.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Consecutive failed publishes that open the circuit, `0` to never
+    /// open it.
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects publishes before letting one
+    /// through to probe the sink.
+    pub open_duration: Duration,
+}
+
+impl ResilienceConfig {
+    /// Reads `PUBLISH_RETRY_MAX_ATTEMPTS` (default `1`, no retry),
+    /// `PUBLISH_RETRY_BACKOFF_MS` (default `100`),
+    /// `PUBLISH_RETRY_MAX_BACKOFF_MS` (default `5000`),
+    /// `CIRCUIT_BREAKER_FAILURES` (default `0`, disabled) and
+    /// `CIRCUIT_BREAKER_OPEN_SECS` (default `30`). `None` when neither
+    /// retries nor the circuit breaker are enabled.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let number = |name: &str, default: u64| match std::env::var(name) {
+            Ok(value) => value.parse::<u64>().map_err(|e| format!("Invalid {}: {}", name, e)),
+            Err(_) => Ok(default),
+        };
+        let config = Self {
+            max_attempts: number("PUBLISH_RETRY_MAX_ATTEMPTS", 1)?.max(1) as u32,
+            initial_backoff: Duration::from_millis(number("PUBLISH_RETRY_BACKOFF_MS", 100)?),
+            max_backoff: Duration::from_millis(number("PUBLISH_RETRY_MAX_BACKOFF_MS", 5000)?),
+            failure_threshold: number("CIRCUIT_BREAKER_FAILURES", 0)? as u32,
+            open_duration: Duration::from_secs(number("CIRCUIT_BREAKER_OPEN_SECS", 30)?),
+        };
+        if config.max_attempts == 1 && config.failure_threshold == 0 {
+            return Ok(None);
+        }
+        Ok(Some(config))
+    }
+
+    /// Backoff before retry `attempt` (1 for the first retry).
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CircuitState {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    /// One publish is probing the sink; others are rejected until it
+    /// completes, or for another open duration if it was cancelled.
+    HalfOpen { since: Instant },
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    circuit: CircuitState,
+    /// Times the circuit opened since startup.
+    opened: u64,
+}
+
+/// Retry policy and circuit breaker of one sink.
+#[derive(Clone)]
+pub struct Resilience {
+    sink: &'static str,
+    config: ResilienceConfig,
+    state: Arc<Mutex<BreakerState>>,
+}
+
+impl Resilience {
+    pub fn new(sink: &'static str, config: ResilienceConfig) -> Self {
+        Self {
+            sink,
+            config,
+            state: Arc::new(Mutex::new(BreakerState {
+                circuit: CircuitState::Closed { failures: 0 },
+                opened: 0,
+            })),
+        }
+    }
+
+    pub fn sink(&self) -> &'static str {
+        self.sink
+    }
+
+    /// Whether the sink may be called now; moves an open circuit whose
+    /// wait is over to half-open.
+    fn admit(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let probe = match state.circuit {
+            CircuitState::Closed { .. } => return true,
+            CircuitState::Open { until } => now >= until,
+            CircuitState::HalfOpen { since } => now >= since + self.config.open_duration,
+        };
+        if probe {
+            state.circuit = CircuitState::HalfOpen { since: now };
+        }
+        probe
+    }
+
+    fn succeeded(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if matches!(state.circuit, CircuitState::HalfOpen { .. }) {
+            log::info!("[CIRCUIT] {} recovered, closing circuit", self.sink);
+        }
+        state.circuit = CircuitState::Closed { failures: 0 };
+    }
+
+    fn failed(&self) {
+        if self.config.failure_threshold == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let failures = match state.circuit {
+            CircuitState::Closed { failures } => failures + 1,
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => self.config.failure_threshold,
+        };
+        state.circuit = if failures >= self.config.failure_threshold {
+            log::warn!(
+                "[CIRCUIT] {} failing, rejecting publishes for {:?}",
+                self.sink,
+                self.config.open_duration
+            );
+            state.opened += 1;
+            CircuitState::Open {
+                until: Instant::now() + self.config.open_duration,
+            }
+        } else {
+            CircuitState::Closed { failures }
+        };
+    }
+
+    /// Runs `publish`, retrying retryable errors with backoff. Fails fast
+    /// with [`UnifiedPublisherError::CircuitOpen`] while the circuit is
+    /// open. Only retryable errors count as sink failures: a serialization
+    /// error or a rejected event says nothing about the sink's health.
+    pub async fn call<F, Fut>(&self, mut publish: F) -> Result<(), UnifiedPublisherError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), UnifiedPublisherError>>,
+    {
+        if !self.admit() {
+            return Err(UnifiedPublisherError::CircuitOpen(self.sink));
+        }
+        let mut attempt = 1;
+        loop {
+            match publish().await {
+                Ok(()) => {
+                    self.succeeded();
+                    return Ok(());
+                }
+                Err(e) if !e.is_retryable() => {
+                    // The probe reached the sink
+                    self.succeeded();
+                    return Err(e);
+                }
+                Err(e) if attempt >= self.config.max_attempts => {
+                    self.failed();
+                    return Err(e);
+                }
+                Err(e) => {
+                    let backoff = self.config.backoff(attempt);
+                    log::warn!(
+                        "[CIRCUIT] {} publish attempt {} failed, retrying in {:?}: {}",
+                        self.sink,
+                        attempt,
+                        backoff,
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Runs `publish` over `events` like [`Resilience::call`], except that
+    /// a retry only resends the events the failed attempt reported as
+    /// undelivered. A final [`UnifiedPublisherError::Batch`] gives their
+    /// positions in `events`.
+    pub async fn call_batch<'a, F, Fut>(&self, events: &'a [(String, DexEventData)], mut publish: F) -> Result<(), UnifiedPublisherError>
+    where
+        F: FnMut(Cow<'a, [(String, DexEventData)]>) -> Fut,
+        Fut: Future<Output = Result<(), UnifiedPublisherError>>,
+    {
+        // Positions in `events` still to deliver, all of them at first
+        let pending: &Mutex<Option<Vec<usize>>> = &Mutex::new(None);
+        self.call(move || {
+            let positions = pending.lock().unwrap_or_else(|e| e.into_inner()).take();
+            let batch = match &positions {
+                Some(positions) => Cow::Owned(positions.iter().map(|position| events[*position].clone()).collect()),
+                None => Cow::Borrowed(events),
+            };
+            let sent = (0..batch.len()).collect::<Vec<_>>();
+            let publish = publish(batch);
+            async move {
+                publish.await.map_err(|e| {
+                    let (undelivered, source) = match e {
+                        UnifiedPublisherError::Batch { undelivered, source } => (undelivered, *source),
+                        e => (sent, e),
+                    };
+                    let undelivered = match &positions {
+                        Some(positions) => undelivered.iter().map(|position| positions[*position]).collect(),
+                        None => undelivered,
+                    };
+                    *pending.lock().unwrap_or_else(|e| e.into_inner()) = Some(undelivered.clone());
+                    UnifiedPublisherError::Batch { undelivered, source: Box::new(source) }
+                })
+            }
+        })
+        .await
+    }
+
+    async fn report(&self, metrics: &dyn Metrics) {
+        let (circuit, opened) = {
+            let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            (state.circuit, state.opened)
+        };
+        let gauges = [
+            // 0 closed, 1 open, 2 half-open
+            (
+                format!("publisher_circuit_state_{}", self.sink),
+                match circuit {
+                    CircuitState::Closed { .. } => 0.0,
+                    CircuitState::Open { .. } => 1.0,
+                    CircuitState::HalfOpen { .. } => 2.0,
+                },
+            ),
+            (format!("publisher_circuit_opened_{}", self.sink), opened as f64),
+        ];
+        for (name, value) in gauges {
+            metrics
+                .update_gauge(&name, value)
+                .await
+                .unwrap_or_else(|e| log::error!("Error recording metric: {}", e));
+        }
+    }
+
+    /// Reports the circuit state of every sink in `sinks` periodically.
+    pub fn spawn_reports(sinks: Vec<Resilience>, metrics: Arc<dyn Metrics>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REPORT_INTERVAL);
+            loop {
+                interval.tick().await;
+                for sink in &sinks {
+                    sink.report(metrics.as_ref()).await;
+                }
+            }
+        })
+    }
+}
+
+/// Runs `publish` through `resilience` when set.
+pub async fn guarded<F, Fut>(resilience: Option<&Resilience>, mut publish: F) -> Result<(), UnifiedPublisherError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), UnifiedPublisherError>>,
+{
+    match resilience {
+        Some(resilience) => resilience.call(publish).await,
+        None => publish().await,
+    }
+}
+
+/// Runs `publish` over `events` through `resilience` when set.
+pub async fn guarded_batch<'a, F, Fut>(
+    resilience: Option<&Resilience>,
+    events: &'a [(String, DexEventData)],
+    mut publish: F,
+) -> Result<(), UnifiedPublisherError>
+where
+    F: FnMut(Cow<'a, [(String, DexEventData)]>) -> Fut,
+    Fut: Future<Output = Result<(), UnifiedPublisherError>>,
+{
+    match resilience {
+        Some(resilience) => resilience.call_batch(events, publish).await,
+        None => publish(Cow::Borrowed(events)).await,
+    }
+}
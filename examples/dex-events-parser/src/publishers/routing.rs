@@ -0,0 +1,82 @@
+use {super::common::DexEventData, std::collections::HashMap};
+
+/// Topic the processors publish decoded events on, before routing.
+pub const EVENTS_TOPIC: &str = "dex_events";
+
+/// Event type of the rules applying to every event of a platform.
+const ANY_EVENT_TYPE: &str = "*";
+
+/// Topic of each event published on [`EVENTS_TOPIC`], by event type with
+/// per-platform overrides. Events no rule matches stay on `EVENTS_TOPIC`,
+/// and other topics (watermarks, alerts, summaries) are never rerouted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopicRoutes {
+    by_event_type: HashMap<String, String>,
+    /// By platform and event type, or [`ANY_EVENT_TYPE`].
+    by_platform: HashMap<(String, String), String>,
+}
+
+impl TopicRoutes {
+    /// Reads `TOPIC_ROUTES`, comma separated `<event_type>=<topic>` rules
+    /// and `<platform>/<event_type>=<topic>` overrides, where the event
+    /// type may be `*`, e.g.
+    /// `swap=dex.swaps,liquidity=dex.liquidity,Pumpfun/*=dex.pumpfun`.
+    /// `None` when unset.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        match std::env::var("TOPIC_ROUTES") {
+            Ok(rules) if !rules.trim().is_empty() => Self::parse(&rules).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn parse(rules: &str) -> Result<Self, String> {
+        let mut routes = Self::default();
+        for rule in rules.split(',').map(str::trim).filter(|rule| !rule.is_empty()) {
+            let (matcher, topic) = rule
+                .split_once('=')
+                .map(|(matcher, topic)| (matcher.trim(), topic.trim()))
+                .filter(|(matcher, topic)| !matcher.is_empty() && !topic.is_empty())
+                .ok_or_else(|| format!("Invalid TOPIC_ROUTES rule {}, expected [<platform>/]<event_type>=<topic>", rule))?;
+            match matcher.rsplit_once('/') {
+                Some((platform, event_type)) => {
+                    routes
+                        .by_platform
+                        .insert((platform.trim().to_string(), event_type.trim().to_string()), topic.to_string());
+                }
+                None => {
+                    routes.by_event_type.insert(matcher.to_string(), topic.to_string());
+                }
+            }
+        }
+        Ok(routes)
+    }
+
+    /// The most specific rule wins: platform and event type, then every
+    /// event of the platform, then the event type.
+    pub fn topic<'a>(&'a self, topic: &'a str, data: &DexEventData) -> &'a str {
+        if topic != EVENTS_TOPIC {
+            return topic;
+        }
+        let platform_rule = |event_type: &str| {
+            self.by_platform
+                .get(&(data.platform.clone(), event_type.to_string()))
+        };
+        platform_rule(&data.event_type)
+            .or_else(|| platform_rule(ANY_EVENT_TYPE))
+            .or_else(|| self.by_event_type.get(&data.event_type))
+            .map_or(topic, String::as_str)
+    }
+
+    /// Topics the rules can send events to.
+    pub fn topics(&self) -> Vec<&str> {
+        let mut topics = self
+            .by_event_type
+            .values()
+            .chain(self.by_platform.values())
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        topics.sort_unstable();
+        topics.dedup();
+        topics
+    }
+}
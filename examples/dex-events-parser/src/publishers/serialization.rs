@@ -0,0 +1,220 @@
+use serde_json::{Map, Value};
+
+use super::{common::DexEventData, links::links};
+use crate::analytics::mints::{NATIVE_SOL_MINT, WSOL_MINT};
+
+/// Naming convention applied to every payload key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyCase {
+    #[default]
+    Snake,
+    Camel,
+}
+
+impl KeyCase {
+    pub fn apply(&self, key: &str) -> String {
+        match self {
+            KeyCase::Snake => to_snake_case(key),
+            KeyCase::Camel => to_camel_case(key),
+        }
+    }
+}
+
+fn to_camel_case(key: &str) -> String {
+    let mut camel = String::with_capacity(key.len());
+    let mut upper_next = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper_next = !camel.is_empty();
+        } else if upper_next {
+            camel.extend(c.to_uppercase());
+            upper_next = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+fn to_snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_uppercase() {
+            if !snake.is_empty() && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Address SOL legs are reported under. Processors value native SOL as
+/// wrapped SOL, so a swap paid in lamports and one paid from a wSOL account
+/// land on the same mint either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SolMint {
+    #[default]
+    Wrapped,
+    Native,
+}
+
+impl SolMint {
+    /// Rewrites every SOL address in `mint` and `*_mint` fields, and in
+    /// object keys, to the chosen one.
+    fn normalize(&self, value: &mut Value) {
+        let (from, to) = match self {
+            SolMint::Wrapped => (NATIVE_SOL_MINT.to_string(), WSOL_MINT.to_string()),
+            SolMint::Native => (WSOL_MINT.to_string(), NATIVE_SOL_MINT.to_string()),
+        };
+        replace_mint(value, &from, &to);
+    }
+}
+
+fn replace_mint(value: &mut Value, from: &str, to: &str) {
+    match value {
+        Value::Object(object) => {
+            if let Some(entry) = object.remove(from) {
+                object.insert(to.to_string(), entry);
+            }
+            for (key, value) in object.iter_mut() {
+                let is_mint_field = key == "mint" || key.ends_with("_mint");
+                match value {
+                    Value::String(mint) if is_mint_field && mint == from => *mint = to.to_string(),
+                    value => replace_mint(value, from, to),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|value| replace_mint(value, from, to)),
+        _ => {}
+    }
+}
+
+/// Encoding of the payload on sinks that carry bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventSerializer {
+    #[default]
+    Json,
+    /// MessagePack, about half the size of JSON. Self-describing, so the
+    /// free-form `details` decode without a schema, which rules out
+    /// bincode.
+    MessagePack,
+}
+
+impl EventSerializer {
+    pub fn serialize(&self, value: &Value) -> serde_json::Result<Vec<u8>> {
+        match self {
+            EventSerializer::Json => serde_json::to_vec(value),
+            EventSerializer::MessagePack => {
+                rmp_serde::to_vec_named(value).map_err(<serde_json::Error as serde::ser::Error>::custom)
+            }
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            EventSerializer::Json => "application/json",
+            EventSerializer::MessagePack => "application/msgpack",
+        }
+    }
+}
+
+/// Shape of the serialized payload, shared by every publisher so that all
+/// sinks emit the same JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PayloadFormat {
+    pub key_case: KeyCase,
+    /// Emit unsigned integers as strings, since JavaScript numbers lose
+    /// precision above 2^53.
+    pub stringify_u64: bool,
+    /// Add a `links` block with explorer URLs and the program label.
+    pub links: bool,
+    pub sol_mint: SolMint,
+    /// Used by [`PayloadFormat::encode_bytes`], text sinks always get JSON.
+    pub serializer: EventSerializer,
+}
+
+impl PayloadFormat {
+    /// Reads `PAYLOAD_KEY_CASE` (`snake` or `camel`, default `snake`),
+    /// `PAYLOAD_STRINGIFY_U64` and `PAYLOAD_LINKS` (`true`/`1` to enable,
+    /// default off), `PAYLOAD_SOL_MINT` (`wsol` or `native`, default
+    /// `wsol`) and `PAYLOAD_SERIALIZER` (`json` or `msgpack`, default
+    /// `json`).
+    pub fn from_env() -> Self {
+        let key_case = match std::env::var("PAYLOAD_KEY_CASE").as_deref() {
+            Ok("camel") | Ok("camelCase") => KeyCase::Camel,
+            Ok("snake") | Ok("snake_case") | Err(_) => KeyCase::Snake,
+            Ok(other) => {
+                log::warn!("Unknown PAYLOAD_KEY_CASE {}, using snake_case", other);
+                KeyCase::Snake
+            }
+        };
+        let stringify_u64 = matches!(
+            std::env::var("PAYLOAD_STRINGIFY_U64").as_deref(),
+            Ok("true") | Ok("1")
+        );
+        let links = matches!(std::env::var("PAYLOAD_LINKS").as_deref(), Ok("true") | Ok("1"));
+        let sol_mint = match std::env::var("PAYLOAD_SOL_MINT").as_deref() {
+            Ok("native") => SolMint::Native,
+            Ok("wsol") | Err(_) => SolMint::Wrapped,
+            Ok(other) => {
+                log::warn!("Unknown PAYLOAD_SOL_MINT {}, using wsol", other);
+                SolMint::Wrapped
+            }
+        };
+
+        let serializer = match std::env::var("PAYLOAD_SERIALIZER").as_deref() {
+            Ok("msgpack") | Ok("messagepack") => EventSerializer::MessagePack,
+            Ok("json") | Err(_) => EventSerializer::Json,
+            Ok(other) => {
+                log::warn!("Unknown PAYLOAD_SERIALIZER {}, using json", other);
+                EventSerializer::Json
+            }
+        };
+
+        Self { key_case, stringify_u64, links, sol_mint, serializer }
+    }
+
+    pub fn encode_value(&self, data: &DexEventData) -> serde_json::Result<Value> {
+        let mut value = serde_json::to_value(data)?;
+        if self.links {
+            value["links"] = links(&data.platform, &data.signature);
+        }
+        if self.sol_mint != SolMint::default() {
+            self.sol_mint.normalize(&mut value["details"]);
+        }
+        if self.key_case == KeyCase::default() && !self.stringify_u64 {
+            return Ok(value);
+        }
+        Ok(self.transform(value))
+    }
+
+    pub fn encode(&self, data: &DexEventData) -> serde_json::Result<String> {
+        serde_json::to_string(&self.encode_value(data)?)
+    }
+
+    /// Encodes with the configured serializer, for sinks that carry bytes.
+    pub fn encode_bytes(&self, data: &DexEventData) -> serde_json::Result<Vec<u8>> {
+        self.serializer.serialize(&self.encode_value(data)?)
+    }
+
+    fn transform(&self, value: Value) -> Value {
+        match value {
+            Value::Object(object) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (self.key_case.apply(&key), self.transform(value)))
+                    .collect::<Map<_, _>>(),
+            ),
+            Value::Array(values) => {
+                Value::Array(values.into_iter().map(|value| self.transform(value)).collect())
+            }
+            Value::Number(number) if self.stringify_u64 && number.is_u64() => {
+                Value::String(number.to_string())
+            }
+            value => value,
+        }
+    }
+}
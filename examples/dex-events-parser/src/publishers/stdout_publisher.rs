@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use std::io::Write;
+use super::{common::DexEventData, denylist::MintDenylist, publisher_metrics::PublisherMetrics, serialization::PayloadFormat, traits::Publisher};
+
+/// Writes each event to stdout as `<topic> <payload>`, one per line. Used
+/// for dry runs, nothing leaves the process.
+#[derive(Clone)]
+pub struct StdoutPublisher {
+    format: PayloadFormat,
+    denylist: Option<MintDenylist>,
+    metrics: PublisherMetrics,
+}
+
+impl Default for StdoutPublisher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StdoutPublisher {
+    pub fn new() -> Self {
+        Self {
+            format: PayloadFormat::default(),
+            denylist: None,
+            metrics: PublisherMetrics::new("stdout"),
+        }
+    }
+
+    pub fn with_format(mut self, format: PayloadFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Drops or quarantines events mentioning a denied mint.
+    pub fn with_denylist(mut self, denylist: Option<MintDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    pub fn metrics(&self) -> &PublisherMetrics {
+        &self.metrics
+    }
+}
+
+#[async_trait]
+impl Publisher for StdoutPublisher {
+    type Error = serde_json::Error;
+
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
+        let Some(topic) = self.denylist.as_ref().map_or(Some(topic), |denylist| denylist.route(topic, data)) else {
+            return Ok(());
+        };
+        let json_data = self.format.encode(data)?;
+
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{} {}", topic, json_data).map_err(serde_json::Error::io)?;
+        self.metrics.sent(json_data.len());
+
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), Self::Error> {
+        std::io::stdout().flush().map_err(serde_json::Error::io)
+    }
+}
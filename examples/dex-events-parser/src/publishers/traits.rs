@@ -6,6 +6,15 @@ pub trait Publisher: Send + Sync {
     type Error: std::error::Error + Send + Sync + 'static;
     
     async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error>;
+
+    /// Publishes `(topic, event)` pairs in order. Sinks that can have
+    /// several sends in flight override this; the default awaits each.
+    async fn publish_batch(&self, events: &[(String, DexEventData)]) -> Result<(), Self::Error> {
+        for (topic, data) in events {
+            self.publish(topic, data).await?;
+        }
+        Ok(())
+    }
     
     async fn close(&self) -> Result<(), Self::Error>;
 } 
\ No newline at end of file
@@ -1,11 +1,81 @@
 use async_trait::async_trait;
-use super::{common::DexEventData, traits::Publisher, ZmqPublisher, KafkaPublisher, ZmqPublisherError, KafkaPublisherError};
+use std::{sync::Arc, time::Duration};
+use carbon_core::metrics::Metrics;
+use crate::{analytics::rolling_stats::RollingStats, ha::StandbyBuffer};
+use super::{batching::{BatchConfig, EventBatch}, common::DexEventData, error::PublishErrorKind, lag::{DeliveryLag, DeliveryLagConfig}, publisher_metrics::PublisherMetrics, queue::{PublishQueue, QueueConfig, QueueItem}, registry::{BoxError, PublisherRegistry}, resilience::{guarded, guarded_batch, Resilience, ResilienceConfig}, routing::TopicRoutes, traits::Publisher, QuestDbPublisher, QuestDbPublisherError, StdoutPublisher, WebhookPublisher, WebhookPublisherError, WsServerPublisher, WsServerPublisherError, ZmqPublisher, KafkaPublisher, ZmqPublisherError, KafkaPublisherError};
+#[cfg(feature = "publisher-postgres")]
+use super::{PostgresPublisher, PostgresPublisherError};
+#[cfg(feature = "publisher-object-store")]
+use super::{ObjectStorePublisher, ObjectStorePublisherError};
+#[cfg(feature = "publisher-pubsub")]
+use super::{PubSubPublisher, PubSubPublisherError};
+#[cfg(feature = "publisher-mqtt")]
+use super::{MqttPublisher, MqttPublisherError};
 
 #[derive(Debug)]
 pub enum UnifiedPublisherError {
     Zmq(ZmqPublisherError),
     Kafka(KafkaPublisherError),
-    Multi(Vec<String>),
+    Stdout(serde_json::Error),
+    Ws(WsServerPublisherError),
+    Webhook(WebhookPublisherError),
+    QuestDb(QuestDbPublisherError),
+    #[cfg(feature = "publisher-postgres")]
+    Postgres(PostgresPublisherError),
+    #[cfg(feature = "publisher-object-store")]
+    ObjectStore(ObjectStorePublisherError),
+    #[cfg(feature = "publisher-pubsub")]
+    PubSub(PubSubPublisherError),
+    #[cfg(feature = "publisher-mqtt")]
+    Mqtt(MqttPublisherError),
+    /// The publisher configuration cannot be used.
+    Config(String),
+    /// One entry per failed sink.
+    Multi(Vec<UnifiedPublisherError>),
+    /// The sink kept failing and is skipped until its circuit closes.
+    CircuitOpen(&'static str),
+    /// A publisher registered in a [`PublisherRegistry`] failed.
+    Custom { sink: String, source: BoxError },
+    /// Part of a batch was not delivered: the positions of those events in
+    /// the batch, and why.
+    Batch { undelivered: Vec<usize>, source: Box<UnifiedPublisherError> },
+}
+
+impl UnifiedPublisherError {
+    /// Kind of every sink failure.
+    pub fn kinds(&self) -> Vec<PublishErrorKind> {
+        match self {
+            UnifiedPublisherError::Zmq(e) => vec![e.kind()],
+            UnifiedPublisherError::Kafka(e) => vec![e.kind()],
+            UnifiedPublisherError::Stdout(_) => vec![PublishErrorKind::Serialization],
+            UnifiedPublisherError::Ws(e) => vec![e.kind()],
+            UnifiedPublisherError::Webhook(e) => vec![e.kind()],
+            UnifiedPublisherError::QuestDb(e) => vec![e.kind()],
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisherError::Postgres(e) => vec![e.kind()],
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisherError::ObjectStore(e) => vec![e.kind()],
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisherError::PubSub(e) => vec![e.kind()],
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisherError::Mqtt(e) => vec![e.kind()],
+            UnifiedPublisherError::Config(_) => vec![PublishErrorKind::Connection],
+            UnifiedPublisherError::Multi(errors) => errors.iter().flat_map(|e| e.kinds()).collect(),
+            UnifiedPublisherError::CircuitOpen(_) => vec![PublishErrorKind::Connection],
+            // Built-in publishers registered as custom ones keep their
+            // kinds, anything else is assumed transient
+            UnifiedPublisherError::Custom { source, .. } => match source.downcast_ref::<UnifiedPublisherError>() {
+                Some(e) => e.kinds(),
+                None => vec![PublishErrorKind::Connection],
+            },
+            UnifiedPublisherError::Batch { source, .. } => source.kinds(),
+        }
+    }
+
+    /// Whether retrying may succeed for every failed sink.
+    pub fn is_retryable(&self) -> bool {
+        self.kinds().iter().all(|kind| kind.is_retryable())
+    }
 }
 
 impl std::fmt::Display for UnifiedPublisherError {
@@ -13,18 +83,146 @@ impl std::fmt::Display for UnifiedPublisherError {
         match self {
             UnifiedPublisherError::Zmq(e) => write!(f, "ZMQ Error: {}", e),
             UnifiedPublisherError::Kafka(e) => write!(f, "Kafka Error: {}", e),
-            UnifiedPublisherError::Multi(errors) => write!(f, "Multiple errors: {}", errors.join(", ")),
+            UnifiedPublisherError::Stdout(e) => write!(f, "Stdout Error: {}", e),
+            UnifiedPublisherError::Ws(e) => write!(f, "{}", e),
+            UnifiedPublisherError::Webhook(e) => write!(f, "{}", e),
+            UnifiedPublisherError::QuestDb(e) => write!(f, "{}", e),
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisherError::Postgres(e) => write!(f, "{}", e),
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisherError::ObjectStore(e) => write!(f, "{}", e),
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisherError::PubSub(e) => write!(f, "{}", e),
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisherError::Mqtt(e) => write!(f, "{}", e),
+            UnifiedPublisherError::Config(e) => write!(f, "Publisher Config Error: {}", e),
+            UnifiedPublisherError::Multi(errors) => {
+                let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+                write!(f, "Multiple errors: {}", errors.join(", "))
+            }
+            UnifiedPublisherError::CircuitOpen(sink) => write!(f, "Circuit open for {} publisher, skipping event", sink),
+            UnifiedPublisherError::Custom { sink, source } => write!(f, "{} Publisher Error: {}", sink, source),
+            UnifiedPublisherError::Batch { undelivered, source } => {
+                write!(f, "{} events of the batch undelivered: {}", undelivered.len(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UnifiedPublisherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UnifiedPublisherError::Zmq(e) => Some(e),
+            UnifiedPublisherError::Kafka(e) => Some(e),
+            UnifiedPublisherError::Stdout(e) => Some(e),
+            UnifiedPublisherError::Ws(e) => Some(e),
+            UnifiedPublisherError::Webhook(e) => Some(e),
+            UnifiedPublisherError::QuestDb(e) => Some(e),
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisherError::Postgres(e) => Some(e),
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisherError::ObjectStore(e) => Some(e),
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisherError::PubSub(e) => Some(e),
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisherError::Mqtt(e) => Some(e),
+            UnifiedPublisherError::Config(_) => None,
+            UnifiedPublisherError::Multi(errors) => errors.first().map(|e| e as &(dyn std::error::Error + 'static)),
+            UnifiedPublisherError::CircuitOpen(_) => None,
+            UnifiedPublisherError::Custom { source, .. } => Some(source.0.as_ref()),
+            UnifiedPublisherError::Batch { source, .. } => Some(source.as_ref()),
         }
     }
 }
 
-impl std::error::Error for UnifiedPublisherError {}
+impl From<ZmqPublisherError> for UnifiedPublisherError {
+    fn from(error: ZmqPublisherError) -> Self {
+        UnifiedPublisherError::Zmq(error)
+    }
+}
+
+impl From<WsServerPublisherError> for UnifiedPublisherError {
+    fn from(error: WsServerPublisherError) -> Self {
+        UnifiedPublisherError::Ws(error)
+    }
+}
+
+impl From<WebhookPublisherError> for UnifiedPublisherError {
+    fn from(error: WebhookPublisherError) -> Self {
+        UnifiedPublisherError::Webhook(error)
+    }
+}
+
+impl From<QuestDbPublisherError> for UnifiedPublisherError {
+    fn from(error: QuestDbPublisherError) -> Self {
+        UnifiedPublisherError::QuestDb(error)
+    }
+}
+
+#[cfg(feature = "publisher-postgres")]
+impl From<PostgresPublisherError> for UnifiedPublisherError {
+    fn from(error: PostgresPublisherError) -> Self {
+        UnifiedPublisherError::Postgres(error)
+    }
+}
+
+#[cfg(feature = "publisher-object-store")]
+impl From<ObjectStorePublisherError> for UnifiedPublisherError {
+    fn from(error: ObjectStorePublisherError) -> Self {
+        UnifiedPublisherError::ObjectStore(error)
+    }
+}
+
+#[cfg(feature = "publisher-pubsub")]
+impl From<PubSubPublisherError> for UnifiedPublisherError {
+    fn from(error: PubSubPublisherError) -> Self {
+        UnifiedPublisherError::PubSub(error)
+    }
+}
+
+#[cfg(feature = "publisher-mqtt")]
+impl From<MqttPublisherError> for UnifiedPublisherError {
+    fn from(error: MqttPublisherError) -> Self {
+        UnifiedPublisherError::Mqtt(error)
+    }
+}
+
+impl From<KafkaPublisherError> for UnifiedPublisherError {
+    fn from(error: KafkaPublisherError) -> Self {
+        UnifiedPublisherError::Kafka(error)
+    }
+}
+
+impl From<UnifiedPublisherError> for carbon_core::error::Error {
+    fn from(error: UnifiedPublisherError) -> Self {
+        carbon_core::error::Error::Custom(error.to_string())
+    }
+}
 
 #[derive(Clone)]
 pub enum UnifiedPublisher {
     Zmq(ZmqPublisher),
     Kafka(KafkaPublisher),
-    Multi(MultiPublisher),
+    Multi(Box<MultiPublisher>),
+    Stdout(StdoutPublisher),
+    Ws(WsServerPublisher),
+    Webhook(WebhookPublisher),
+    QuestDb(QuestDbPublisher),
+    #[cfg(feature = "publisher-postgres")]
+    Postgres(PostgresPublisher),
+    #[cfg(feature = "publisher-object-store")]
+    ObjectStore(ObjectStorePublisher),
+    #[cfg(feature = "publisher-pubsub")]
+    PubSub(PubSubPublisher),
+    #[cfg(feature = "publisher-mqtt")]
+    Mqtt(MqttPublisher),
+    Routed(RoutedPublisher),
+    Batched(BatchingPublisher),
+    Resilient(ResilientPublisher),
+    Queued(QueuedPublisher),
+    Stats(StatsPublisher),
+    Standby(StandbyPublisher),
+    Registry(PublisherRegistry),
 }
 
 #[async_trait]
@@ -33,9 +231,84 @@ impl Publisher for UnifiedPublisher {
     
     async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
         match self {
-            UnifiedPublisher::Zmq(publisher) => publisher.publish(topic, data).await.map_err(UnifiedPublisherError::Zmq),
-            UnifiedPublisher::Kafka(publisher) => publisher.publish(topic, data).await.map_err(UnifiedPublisherError::Kafka),
+            UnifiedPublisher::Zmq(publisher) => publisher.metrics().time(1, publisher.publish(topic, data)).await.map_err(UnifiedPublisherError::Zmq),
+            UnifiedPublisher::Kafka(publisher) => publisher.metrics().time(1, publisher.publish(topic, data)).await.map_err(UnifiedPublisherError::Kafka),
             UnifiedPublisher::Multi(publisher) => publisher.publish(topic, data).await.map_err(UnifiedPublisherError::Multi),
+            UnifiedPublisher::Stdout(publisher) => publisher.metrics().time(1, publisher.publish(topic, data)).await.map_err(UnifiedPublisherError::Stdout),
+            UnifiedPublisher::Ws(publisher) => publisher.metrics().time(1, publisher.publish(topic, data)).await.map_err(UnifiedPublisherError::Ws),
+            UnifiedPublisher::Webhook(publisher) => publisher.metrics().time(1, publisher.publish(topic, data)).await.map_err(UnifiedPublisherError::Webhook),
+            UnifiedPublisher::QuestDb(publisher) => publisher.metrics().time(1, publisher.publish(topic, data)).await.map_err(UnifiedPublisherError::QuestDb),
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisher::Postgres(publisher) => publisher.metrics().time(1, publisher.publish(topic, data)).await.map_err(UnifiedPublisherError::Postgres),
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisher::ObjectStore(publisher) => publisher.metrics().time(1, publisher.publish(topic, data)).await.map_err(UnifiedPublisherError::ObjectStore),
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisher::PubSub(publisher) => publisher.metrics().time(1, publisher.publish(topic, data)).await.map_err(UnifiedPublisherError::PubSub),
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisher::Mqtt(publisher) => publisher.metrics().time(1, publisher.publish(topic, data)).await.map_err(UnifiedPublisherError::Mqtt),
+            UnifiedPublisher::Routed(publisher) => publisher.inner.publish(publisher.routes.topic(topic, data), data).await,
+            UnifiedPublisher::Batched(publisher) => publisher.publish(topic, data).await,
+            UnifiedPublisher::Resilient(publisher) => {
+                publisher.resilience.call(|| publisher.inner.publish(topic, data)).await
+            }
+            UnifiedPublisher::Queued(publisher) => {
+                publisher.queue.push(topic, data).await;
+                Ok(())
+            }
+            UnifiedPublisher::Stats(publisher) => {
+                publisher.stats.record(data);
+                Box::pin(publisher.inner.publish(topic, data)).await
+            }
+            UnifiedPublisher::Standby(publisher) => publisher.publish(topic, data).await,
+            UnifiedPublisher::Registry(registry) => registry.publish(topic, data).await.map_err(UnifiedPublisherError::Multi),
+        }
+    }
+
+    /// On failure, sinks report which events of the batch they did not
+    /// deliver with [`UnifiedPublisherError::Batch`], so retries resend only
+    /// those.
+    async fn publish_batch(&self, events: &[(String, DexEventData)]) -> Result<(), Self::Error> {
+        match self {
+            UnifiedPublisher::Kafka(publisher) => publisher
+                .metrics()
+                .time(events.len(), publisher.send_batch(events))
+                .await
+                .map_err(|(undelivered, e)| UnifiedPublisherError::Batch { undelivered, source: Box::new(UnifiedPublisherError::Kafka(e)) }),
+            UnifiedPublisher::Multi(publisher) => publisher.publish_batch(events).await.map_err(UnifiedPublisherError::Multi),
+            UnifiedPublisher::Routed(publisher) => {
+                let routed = events
+                    .iter()
+                    .map(|(topic, data)| (publisher.routes.topic(topic, data).to_string(), data.clone()))
+                    .collect::<Vec<_>>();
+                Box::pin(publisher.inner.publish_batch(&routed)).await
+            }
+            UnifiedPublisher::Resilient(publisher) => {
+                publisher
+                    .resilience
+                    .call_batch(events, |batch| async move { publisher.inner.publish_batch(&batch).await })
+                    .await
+            }
+            UnifiedPublisher::Stats(publisher) => {
+                for (_, data) in events {
+                    publisher.stats.record(data);
+                }
+                Box::pin(publisher.inner.publish_batch(events)).await
+            }
+            UnifiedPublisher::Standby(publisher) => publisher.publish_batch(events).await,
+            UnifiedPublisher::Registry(registry) => {
+                Box::pin(registry.publish_batch(events)).await.map_err(UnifiedPublisherError::Multi)
+            }
+            _ => {
+                for (position, (topic, data)) in events.iter().enumerate() {
+                    if let Err(e) = self.publish(topic, data).await {
+                        return Err(UnifiedPublisherError::Batch {
+                            undelivered: (position..events.len()).collect(),
+                            source: Box::new(e),
+                        });
+                    }
+                }
+                Ok(())
+            }
         }
     }
     
@@ -44,22 +317,212 @@ impl Publisher for UnifiedPublisher {
             UnifiedPublisher::Zmq(publisher) => publisher.close().await.map_err(UnifiedPublisherError::Zmq),
             UnifiedPublisher::Kafka(publisher) => publisher.close().await.map_err(UnifiedPublisherError::Kafka),
             UnifiedPublisher::Multi(publisher) => publisher.close().await.map_err(UnifiedPublisherError::Multi),
+            UnifiedPublisher::Stdout(publisher) => publisher.close().await.map_err(UnifiedPublisherError::Stdout),
+            UnifiedPublisher::Ws(publisher) => publisher.close().await.map_err(UnifiedPublisherError::Ws),
+            UnifiedPublisher::Webhook(publisher) => publisher.close().await.map_err(UnifiedPublisherError::Webhook),
+            UnifiedPublisher::QuestDb(publisher) => publisher.close().await.map_err(UnifiedPublisherError::QuestDb),
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisher::Postgres(publisher) => publisher.close().await.map_err(UnifiedPublisherError::Postgres),
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisher::ObjectStore(publisher) => publisher.close().await.map_err(UnifiedPublisherError::ObjectStore),
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisher::PubSub(publisher) => publisher.close().await.map_err(UnifiedPublisherError::PubSub),
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisher::Mqtt(publisher) => publisher.close().await.map_err(UnifiedPublisherError::Mqtt),
+            UnifiedPublisher::Routed(publisher) => publisher.inner.close().await,
+            UnifiedPublisher::Batched(publisher) => {
+                publisher.flush().await?;
+                Box::pin(publisher.inner.close()).await
+            }
+            UnifiedPublisher::Resilient(publisher) => Box::pin(publisher.inner.close()).await,
+            UnifiedPublisher::Queued(publisher) => {
+                publisher.queue.drained().await;
+                Box::pin(publisher.inner.close()).await
+            }
+            UnifiedPublisher::Stats(publisher) => Box::pin(publisher.inner.close()).await,
+            UnifiedPublisher::Standby(publisher) => Box::pin(publisher.inner.close()).await,
+            UnifiedPublisher::Registry(registry) => Box::pin(registry.close()).await.map_err(UnifiedPublisherError::Multi),
+        }
+    }
+}
+
+/// Sends the events of [`super::routing::EVENTS_TOPIC`] to the topic its
+/// routes pick, whatever the sinks behind.
+#[derive(Clone)]
+pub struct RoutedPublisher {
+    inner: Box<UnifiedPublisher>,
+    routes: Arc<TopicRoutes>,
+}
+
+/// Buffers events and hands them to the sinks behind with
+/// `publish_batch`, once `max_events` are buffered or every flush
+/// interval. `publish` returns once an event is buffered: only the call
+/// that fills the batch sees delivery errors, the interval flush logs
+/// them and drops the batch.
+#[derive(Clone)]
+pub struct BatchingPublisher {
+    inner: Box<UnifiedPublisher>,
+    batch: Arc<EventBatch>,
+    /// Held while a batch is taken and sent, so batches reach the sinks
+    /// in order.
+    flushing: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl BatchingPublisher {
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), UnifiedPublisherError> {
+        if self.batch.push(topic, data) {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), UnifiedPublisherError> {
+        let _flushing = self.flushing.lock().await;
+        let events = self.batch.take();
+        if events.is_empty() {
+            return Ok(());
+        }
+        Box::pin(self.inner.publish_batch(&events)).await
+    }
+
+    async fn flush_periodically(publisher: BatchingPublisher, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            // Stops with the last clone handed out to the processors.
+            if Arc::strong_count(&publisher.batch) <= 1 {
+                return;
+            }
+            if let Err(e) = publisher.flush().await {
+                log::error!("Failed to flush batched events: {}", e);
+            }
+        }
+    }
+}
+
+/// Hands events to a worker through a bounded queue, so a slow sink
+/// neither stalls the processors nor grows memory without bound.
+/// `publish` returns once the event is queued (or dropped by the policy);
+/// the worker logs delivery errors. `close` waits for the queue to drain.
+#[derive(Clone)]
+pub struct QueuedPublisher {
+    inner: Box<UnifiedPublisher>,
+    queue: Arc<PublishQueue>,
+}
+
+impl QueuedPublisher {
+    async fn drain(inner: UnifiedPublisher, queue: Arc<PublishQueue>) {
+        loop {
+            let result = match queue.pop().await {
+                QueueItem::Event(topic, data) => inner.publish(&topic, &data).await,
+                QueueItem::Watermarks => inner.publish_watermarks().await,
+            };
+            queue.finished();
+            if let Err(e) = result {
+                if e.is_retryable() {
+                    log::warn!("Failed to publish queued event: {}", e);
+                } else {
+                    log::error!("Failed to publish queued event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Feeds the published swaps to the rolling statistics, whose snapshots
+/// go to the sinks behind.
+#[derive(Clone)]
+pub struct StatsPublisher {
+    inner: Box<UnifiedPublisher>,
+    stats: RollingStats,
+}
+
+/// Holds events back from the sinks behind while this instance is an HA
+/// standby, whatever the sink. Everything in front keeps running so its
+/// state is warm on takeover.
+#[derive(Clone)]
+pub struct StandbyPublisher {
+    inner: Box<UnifiedPublisher>,
+    buffer: StandbyBuffer,
+}
+
+impl StandbyPublisher {
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), UnifiedPublisherError> {
+        if !self.buffer.admit(topic, data) {
+            return Ok(());
+        }
+        Box::pin(self.inner.publish(topic, data)).await?;
+        self.buffer.published(data);
+        Ok(())
+    }
+
+    async fn publish_batch(&self, events: &[(String, DexEventData)]) -> Result<(), UnifiedPublisherError> {
+        let admitted = events
+            .iter()
+            .enumerate()
+            .filter(|(_, (topic, data))| self.buffer.admit(topic, data))
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+        if admitted.is_empty() {
+            return Ok(());
+        }
+        let result = if admitted.len() == events.len() {
+            Box::pin(self.inner.publish_batch(events)).await
+        } else {
+            let batch = admitted.iter().map(|&position| events[position].clone()).collect::<Vec<_>>();
+            Box::pin(self.inner.publish_batch(&batch)).await.map_err(|e| match e {
+                // Back to positions in `events`
+                UnifiedPublisherError::Batch { undelivered, source } => UnifiedPublisherError::Batch {
+                    undelivered: undelivered.into_iter().map(|position| admitted[position]).collect(),
+                    source,
+                },
+                e => e,
+            })
+        };
+        let undelivered = match &result {
+            Ok(()) => Vec::new(),
+            Err(UnifiedPublisherError::Batch { undelivered, .. }) => undelivered.clone(),
+            Err(_) => return result,
+        };
+        for &position in admitted.iter().filter(|position| !undelivered.contains(position)) {
+            self.buffer.published(&events[position].1);
         }
+        result
+    }
+
+    /// Publishes the held back events from `checkpoint` on, straight to
+    /// the sinks behind since this instance is not the leader yet.
+    async fn replay(&self, checkpoint: Option<i64>) -> Result<usize, UnifiedPublisherError> {
+        let events = self.buffer.take_since(checkpoint);
+        for (topic, data) in &events {
+            Box::pin(self.inner.publish(topic, data)).await?;
+            self.buffer.published(data);
+        }
+        Ok(events.len())
     }
 }
 
+/// Retries failed publishes of a single sink and stops calling it while
+/// its circuit is open.
 #[derive(Clone)]
+pub struct ResilientPublisher {
+    inner: Box<UnifiedPublisher>,
+    resilience: Resilience,
+}
+
+#[derive(Clone, Default)]
 pub struct MultiPublisher {
     zmq_publisher: Option<ZmqPublisher>,
     kafka_publisher: Option<KafkaPublisher>,
+    /// Per sink, so a failing sink does not hold back the other.
+    zmq_resilience: Option<Resilience>,
+    kafka_resilience: Option<Resilience>,
 }
 
 impl MultiPublisher {
     pub fn new() -> Self {
-        Self {
-            zmq_publisher: None,
-            kafka_publisher: None,
-        }
+        Self::default()
     }
     
     pub fn with_zmq(mut self, publisher: ZmqPublisher) -> Self {
@@ -71,19 +534,26 @@ impl MultiPublisher {
         self.kafka_publisher = Some(publisher);
         self
     }
+
+    /// Retries and circuit breaks each sink on its own.
+    pub fn with_resilience(mut self, config: Option<ResilienceConfig>) -> Self {
+        self.zmq_resilience = config.map(|config| Resilience::new("zmq", config));
+        self.kafka_resilience = config.map(|config| Resilience::new("kafka", config));
+        self
+    }
     
-    pub async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Vec<String>> {
+    pub async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Vec<UnifiedPublisherError>> {
         let mut errors = Vec::new();
         
         if let Some(zmq) = &self.zmq_publisher {
-            if let Err(e) = zmq.publish(topic, data).await {
-                errors.push(format!("ZMQ: {}", e));
+            if let Err(e) = guarded(self.zmq_resilience.as_ref(), || async move { Ok(zmq.metrics().time(1, zmq.publish(topic, data)).await?) }).await {
+                errors.push(e);
             }
         }
         
         if let Some(kafka) = &self.kafka_publisher {
-            if let Err(e) = kafka.publish(topic, data).await {
-                errors.push(format!("Kafka: {}", e));
+            if let Err(e) = guarded(self.kafka_resilience.as_ref(), || async move { Ok(kafka.metrics().time(1, kafka.publish(topic, data)).await?) }).await {
+                errors.push(e);
             }
         }
         
@@ -94,18 +564,76 @@ impl MultiPublisher {
         }
     }
     
-    pub async fn close(&self) -> Result<(), Vec<String>> {
+    pub async fn publish_batch(&self, events: &[(String, DexEventData)]) -> Result<(), Vec<UnifiedPublisherError>> {
+        let mut errors = Vec::new();
+        
+        if let Some(zmq) = &self.zmq_publisher {
+            if let Err(e) = guarded_batch(self.zmq_resilience.as_ref(), events, |batch| async move {
+                zmq.metrics().time(batch.len(), async {
+                    for (position, (topic, data)) in batch.iter().enumerate() {
+                        zmq.publish(topic, data).await.map_err(|e| UnifiedPublisherError::Batch {
+                            undelivered: (position..batch.len()).collect(),
+                            source: Box::new(UnifiedPublisherError::Zmq(e)),
+                        })?;
+                    }
+                    Ok(())
+                }).await
+            }).await {
+                errors.push(e);
+            }
+        }
+        
+        if let Some(kafka) = &self.kafka_publisher {
+            if let Err(e) = guarded_batch(self.kafka_resilience.as_ref(), events, |batch| async move {
+                kafka.metrics().time(batch.len(), kafka.send_batch(&batch)).await.map_err(|(undelivered, e)| {
+                    UnifiedPublisherError::Batch { undelivered, source: Box::new(UnifiedPublisherError::Kafka(e)) }
+                })
+            }).await {
+                errors.push(e);
+            }
+        }
+        
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+    
+    pub async fn publish_watermarks(&self) -> Result<(), Vec<UnifiedPublisherError>> {
+        let mut errors = Vec::new();
+        
+        if let Some(zmq) = &self.zmq_publisher {
+            if let Err(e) = zmq.publish_watermarks().await {
+                errors.push(UnifiedPublisherError::Zmq(e));
+            }
+        }
+        
+        if let Some(kafka) = &self.kafka_publisher {
+            if let Err(e) = kafka.publish_watermarks().await {
+                errors.push(UnifiedPublisherError::Kafka(e));
+            }
+        }
+        
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+    
+    pub async fn close(&self) -> Result<(), Vec<UnifiedPublisherError>> {
         let mut errors = Vec::new();
         
         if let Some(zmq) = &self.zmq_publisher {
             if let Err(e) = zmq.close().await {
-                errors.push(format!("ZMQ: {}", e));
+                errors.push(UnifiedPublisherError::Zmq(e));
             }
         }
         
         if let Some(kafka) = &self.kafka_publisher {
             if let Err(e) = kafka.close().await {
-                errors.push(format!("Kafka: {}", e));
+                errors.push(UnifiedPublisherError::Kafka(e));
             }
         }
         
@@ -127,6 +655,383 @@ impl UnifiedPublisher {
     }
     
     pub fn multi(publisher: MultiPublisher) -> Self {
-        UnifiedPublisher::Multi(publisher)
+        UnifiedPublisher::Multi(Box::new(publisher))
+    }
+
+    pub fn stdout(publisher: StdoutPublisher) -> Self {
+        UnifiedPublisher::Stdout(publisher)
+    }
+
+    pub fn ws(publisher: WsServerPublisher) -> Self {
+        UnifiedPublisher::Ws(publisher)
+    }
+
+    pub fn webhook(publisher: WebhookPublisher) -> Self {
+        UnifiedPublisher::Webhook(publisher)
+    }
+
+    pub fn questdb(publisher: QuestDbPublisher) -> Self {
+        UnifiedPublisher::QuestDb(publisher)
+    }
+
+    #[cfg(feature = "publisher-postgres")]
+    pub fn postgres(publisher: PostgresPublisher) -> Self {
+        UnifiedPublisher::Postgres(publisher)
+    }
+
+    #[cfg(feature = "publisher-object-store")]
+    pub fn object_store(publisher: ObjectStorePublisher) -> Self {
+        UnifiedPublisher::ObjectStore(publisher)
+    }
+
+    #[cfg(feature = "publisher-pubsub")]
+    pub fn pubsub(publisher: PubSubPublisher) -> Self {
+        UnifiedPublisher::PubSub(publisher)
+    }
+
+    #[cfg(feature = "publisher-mqtt")]
+    pub fn mqtt(publisher: MqttPublisher) -> Self {
+        UnifiedPublisher::Mqtt(publisher)
+    }
+
+    pub fn routed(publisher: UnifiedPublisher, routes: TopicRoutes) -> Self {
+        UnifiedPublisher::Routed(RoutedPublisher {
+            inner: Box::new(publisher),
+            routes: Arc::new(routes),
+        })
+    }
+
+    /// Retries and circuit breaks `publisher`, each sink on its own when
+    /// it publishes to several.
+    pub fn resilient(publisher: UnifiedPublisher, config: ResilienceConfig) -> Self {
+        match publisher {
+            UnifiedPublisher::Multi(multi) => UnifiedPublisher::Multi(Box::new(multi.with_resilience(Some(config)))),
+            publisher => UnifiedPublisher::Resilient(ResilientPublisher {
+                resilience: Resilience::new(publisher.sink(), config),
+                inner: Box::new(publisher),
+            }),
+        }
     }
-} 
\ No newline at end of file
+
+    /// Name of the sink in logs and metrics.
+    fn sink(&self) -> &'static str {
+        match self {
+            UnifiedPublisher::Zmq(_) => "zmq",
+            UnifiedPublisher::Kafka(_) => "kafka",
+            UnifiedPublisher::Multi(_) => "multi",
+            UnifiedPublisher::Stdout(_) => "stdout",
+            UnifiedPublisher::Ws(_) => "ws",
+            UnifiedPublisher::Webhook(_) => "webhook",
+            UnifiedPublisher::QuestDb(_) => "questdb",
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisher::Postgres(_) => "postgres",
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisher::ObjectStore(_) => "object_store",
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisher::PubSub(_) => "pubsub",
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisher::Mqtt(_) => "mqtt",
+            UnifiedPublisher::Routed(publisher) => publisher.inner.sink(),
+            UnifiedPublisher::Batched(publisher) => publisher.inner.sink(),
+            UnifiedPublisher::Resilient(publisher) => publisher.inner.sink(),
+            UnifiedPublisher::Queued(publisher) => publisher.inner.sink(),
+            UnifiedPublisher::Stats(publisher) => publisher.inner.sink(),
+            UnifiedPublisher::Standby(publisher) => publisher.inner.sink(),
+            UnifiedPublisher::Registry(_) => "registry",
+        }
+    }
+
+    fn resilience(&self) -> Vec<Resilience> {
+        match self {
+            UnifiedPublisher::Multi(publisher) => publisher
+                .zmq_resilience
+                .iter()
+                .chain(publisher.kafka_resilience.iter())
+                .cloned()
+                .collect(),
+            UnifiedPublisher::Routed(publisher) => publisher.inner.resilience(),
+            UnifiedPublisher::Batched(publisher) => publisher.inner.resilience(),
+            UnifiedPublisher::Queued(publisher) => publisher.inner.resilience(),
+            UnifiedPublisher::Stats(publisher) => publisher.inner.resilience(),
+            UnifiedPublisher::Standby(publisher) => publisher.inner.resilience(),
+            UnifiedPublisher::Registry(registry) => registry.builtins().iter().flat_map(|p| p.resilience()).collect(),
+            UnifiedPublisher::Resilient(publisher) => vec![publisher.resilience.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Reports each sink's circuit state as gauges. Returns `None` when no
+    /// sink has a circuit breaker.
+    pub fn spawn_circuit_breakers(&self, metrics: Arc<dyn Metrics>) -> Option<tokio::task::JoinHandle<()>> {
+        let sinks = self.resilience();
+        if sinks.is_empty() {
+            return None;
+        }
+        Some(Resilience::spawn_reports(sinks, metrics))
+    }
+
+    fn publisher_metrics(&self) -> Vec<PublisherMetrics> {
+        match self {
+            UnifiedPublisher::Zmq(publisher) => vec![publisher.metrics().clone()],
+            UnifiedPublisher::Kafka(publisher) => vec![publisher.metrics().clone()],
+            UnifiedPublisher::Multi(publisher) => publisher
+                .zmq_publisher
+                .iter()
+                .map(|p| p.metrics().clone())
+                .chain(publisher.kafka_publisher.iter().map(|p| p.metrics().clone()))
+                .collect(),
+            UnifiedPublisher::Stdout(publisher) => vec![publisher.metrics().clone()],
+            UnifiedPublisher::Ws(publisher) => vec![publisher.metrics().clone()],
+            UnifiedPublisher::Webhook(publisher) => vec![publisher.metrics().clone()],
+            UnifiedPublisher::QuestDb(publisher) => vec![publisher.metrics().clone()],
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisher::Postgres(publisher) => vec![publisher.metrics().clone()],
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisher::ObjectStore(publisher) => vec![publisher.metrics().clone()],
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisher::PubSub(publisher) => vec![publisher.metrics().clone()],
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisher::Mqtt(publisher) => vec![publisher.metrics().clone()],
+            UnifiedPublisher::Routed(publisher) => publisher.inner.publisher_metrics(),
+            UnifiedPublisher::Batched(publisher) => publisher.inner.publisher_metrics(),
+            UnifiedPublisher::Resilient(publisher) => publisher.inner.publisher_metrics(),
+            UnifiedPublisher::Queued(publisher) => publisher.inner.publisher_metrics(),
+            UnifiedPublisher::Stats(publisher) => publisher.inner.publisher_metrics(),
+            UnifiedPublisher::Standby(publisher) => publisher.inner.publisher_metrics(),
+            UnifiedPublisher::Registry(registry) => registry.builtins().iter().flat_map(|p| p.publisher_metrics()).collect(),
+        }
+    }
+
+    /// Reports publish outcomes, latency, bytes sent and queue depth of
+    /// each built-in sink, tagged by backend.
+    pub fn spawn_publisher_metrics(&self, metrics: Arc<dyn Metrics>) -> tokio::task::JoinHandle<()> {
+        PublisherMetrics::spawn(self.publisher_metrics(), metrics)
+    }
+
+    /// Publishes to every sink of `registry`, built-in or custom.
+    pub fn registry(registry: PublisherRegistry) -> Self {
+        UnifiedPublisher::Registry(registry)
+    }
+
+    /// Publishes through a bounded queue drained by a background worker.
+    pub fn queued(publisher: UnifiedPublisher, config: QueueConfig) -> Self {
+        let queue = Arc::new(PublishQueue::new(config));
+        tokio::spawn(QueuedPublisher::drain(publisher.clone(), queue.clone()));
+        UnifiedPublisher::Queued(QueuedPublisher {
+            inner: Box::new(publisher),
+            queue,
+        })
+    }
+
+    /// Computes rolling statistics of the swaps published through
+    /// `publisher` and publishes their snapshots every interval.
+    pub fn with_stats(publisher: UnifiedPublisher, stats: RollingStats) -> Self {
+        stats.spawn_publisher(publisher.clone());
+        UnifiedPublisher::Stats(StatsPublisher {
+            inner: Box::new(publisher),
+            stats,
+        })
+    }
+
+    /// Holds back what `publisher` would send while `buffer`'s instance is
+    /// an HA standby, keeping `publisher` as is outside HA mode.
+    pub fn standby(publisher: UnifiedPublisher, buffer: Option<StandbyBuffer>) -> Self {
+        match buffer {
+            Some(buffer) => UnifiedPublisher::Standby(StandbyPublisher {
+                inner: Box::new(publisher),
+                buffer,
+            }),
+            None => publisher,
+        }
+    }
+
+    /// Reports the publish queue depth as gauges. Returns `None` without
+    /// a queue.
+    pub fn spawn_queue_depth(&self, metrics: Arc<dyn Metrics>) -> Option<tokio::task::JoinHandle<()>> {
+        match self {
+            UnifiedPublisher::Queued(publisher) => Some(publisher.queue.spawn_reports(metrics)),
+            _ => None,
+        }
+    }
+
+    /// Buffers events in front of `publisher` and flushes them in the
+    /// background every `config.flush_interval`.
+    pub fn batched(publisher: UnifiedPublisher, config: BatchConfig) -> Self {
+        let batching = BatchingPublisher {
+            inner: Box::new(publisher),
+            batch: Arc::new(EventBatch::new(config.max_events)),
+            flushing: Arc::new(tokio::sync::Mutex::new(())),
+        };
+        tokio::spawn(BatchingPublisher::flush_periodically(batching.clone(), config.flush_interval));
+        UnifiedPublisher::Batched(batching)
+    }
+    
+    pub async fn publish_watermarks(&self) -> Result<(), UnifiedPublisherError> {
+        match self {
+            UnifiedPublisher::Zmq(publisher) => publisher.publish_watermarks().await.map_err(UnifiedPublisherError::Zmq),
+            UnifiedPublisher::Kafka(publisher) => publisher.publish_watermarks().await.map_err(UnifiedPublisherError::Kafka),
+            UnifiedPublisher::Multi(publisher) => publisher.publish_watermarks().await.map_err(UnifiedPublisherError::Multi),
+            UnifiedPublisher::Stdout(_) | UnifiedPublisher::Ws(_) | UnifiedPublisher::Webhook(_) | UnifiedPublisher::QuestDb(_) => Ok(()),
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisher::Postgres(_) => Ok(()),
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisher::ObjectStore(_) => Ok(()),
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisher::PubSub(_) => Ok(()),
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisher::Mqtt(_) => Ok(()),
+            UnifiedPublisher::Routed(publisher) => Box::pin(publisher.inner.publish_watermarks()).await,
+            // Buffered events are not tracked by the sinks' watermarks yet
+            UnifiedPublisher::Batched(publisher) => {
+                publisher.flush().await?;
+                Box::pin(publisher.inner.publish_watermarks()).await
+            }
+            UnifiedPublisher::Resilient(publisher) => Box::pin(publisher.inner.publish_watermarks()).await,
+            // Published by the worker after the events queued before them
+            UnifiedPublisher::Queued(publisher) => {
+                publisher.queue.push_watermarks();
+                Ok(())
+            }
+            UnifiedPublisher::Stats(publisher) => Box::pin(publisher.inner.publish_watermarks()).await,
+            // The new leader publishes its own
+            UnifiedPublisher::Standby(publisher) if !publisher.buffer.is_leader() => Ok(()),
+            UnifiedPublisher::Standby(publisher) => Box::pin(publisher.inner.publish_watermarks()).await,
+            UnifiedPublisher::Registry(registry) => {
+                let mut errors = Vec::new();
+                for publisher in registry.builtins() {
+                    if let Err(e) = Box::pin(publisher.publish_watermarks()).await {
+                        errors.push(e);
+                    }
+                }
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(UnifiedPublisherError::Multi(errors))
+                }
+            }
+        }
+    }
+
+    /// Publishes what each sink held back while standby, from `checkpoint`
+    /// on. Returns the number of events replayed.
+    pub async fn replay_standby(&self, checkpoint: Option<i64>) -> Result<usize, UnifiedPublisherError> {
+        match self {
+            UnifiedPublisher::Zmq(_) | UnifiedPublisher::Kafka(_) | UnifiedPublisher::Multi(_) => Ok(0),
+            UnifiedPublisher::Stdout(_) | UnifiedPublisher::Ws(_) | UnifiedPublisher::Webhook(_) | UnifiedPublisher::QuestDb(_) => Ok(0),
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisher::Postgres(_) => Ok(0),
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisher::ObjectStore(_) => Ok(0),
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisher::PubSub(_) => Ok(0),
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisher::Mqtt(_) => Ok(0),
+            UnifiedPublisher::Routed(publisher) => Box::pin(publisher.inner.replay_standby(checkpoint)).await,
+            UnifiedPublisher::Batched(publisher) => Box::pin(publisher.inner.replay_standby(checkpoint)).await,
+            UnifiedPublisher::Resilient(publisher) => Box::pin(publisher.inner.replay_standby(checkpoint)).await,
+            UnifiedPublisher::Queued(publisher) => Box::pin(publisher.inner.replay_standby(checkpoint)).await,
+            UnifiedPublisher::Stats(publisher) => Box::pin(publisher.inner.replay_standby(checkpoint)).await,
+            UnifiedPublisher::Standby(publisher) => publisher.replay(checkpoint).await,
+            UnifiedPublisher::Registry(registry) => {
+                let mut errors = Vec::new();
+                let mut replayed = 0;
+                for publisher in registry.builtins() {
+                    match Box::pin(publisher.replay_standby(checkpoint)).await {
+                        Ok(count) => replayed += count,
+                        Err(e) => errors.push(e),
+                    }
+                }
+                if errors.is_empty() {
+                    Ok(replayed)
+                } else {
+                    Err(UnifiedPublisherError::Multi(errors))
+                }
+            }
+        }
+    }
+
+    fn watermark_interval(&self) -> Option<Duration> {
+        match self {
+            UnifiedPublisher::Zmq(publisher) => publisher.watermarks().map(|w| w.interval()),
+            UnifiedPublisher::Kafka(publisher) => publisher.watermarks().map(|w| w.interval()),
+            UnifiedPublisher::Multi(publisher) => {
+                let zmq = publisher.zmq_publisher.as_ref().and_then(|p| p.watermarks()).map(|w| w.interval());
+                let kafka = publisher.kafka_publisher.as_ref().and_then(|p| p.watermarks()).map(|w| w.interval());
+                zmq.into_iter().chain(kafka).min()
+            }
+            UnifiedPublisher::Stdout(_) | UnifiedPublisher::Ws(_) | UnifiedPublisher::Webhook(_) | UnifiedPublisher::QuestDb(_) => None,
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisher::Postgres(_) => None,
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisher::ObjectStore(_) => None,
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisher::PubSub(_) => None,
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisher::Mqtt(_) => None,
+            UnifiedPublisher::Routed(publisher) => publisher.inner.watermark_interval(),
+            UnifiedPublisher::Batched(publisher) => publisher.inner.watermark_interval(),
+            UnifiedPublisher::Resilient(publisher) => publisher.inner.watermark_interval(),
+            UnifiedPublisher::Queued(publisher) => publisher.inner.watermark_interval(),
+            UnifiedPublisher::Stats(publisher) => publisher.inner.watermark_interval(),
+            UnifiedPublisher::Standby(publisher) => publisher.inner.watermark_interval(),
+            UnifiedPublisher::Registry(registry) => registry.builtins().iter().filter_map(|p| p.watermark_interval()).min(),
+        }
+    }
+
+    /// Publishes `watermark` events for every topic of each sink every
+    /// `WATERMARK_INTERVAL_MS`. Returns `None` when watermarks are disabled.
+    pub fn spawn_watermarks(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let period = self.watermark_interval()?;
+        let publisher = self.clone();
+
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = publisher.publish_watermarks().await {
+                    log::error!("Failed to publish watermarks: {}", e);
+                }
+            }
+        }))
+    }
+
+    fn delivery_lags(&self) -> Vec<DeliveryLag> {
+        match self {
+            UnifiedPublisher::Zmq(publisher) => vec![publisher.delivery_lag().clone()],
+            UnifiedPublisher::Kafka(publisher) => vec![publisher.delivery_lag().clone()],
+            UnifiedPublisher::Webhook(publisher) => vec![publisher.delivery_lag().clone()],
+            UnifiedPublisher::QuestDb(publisher) => vec![publisher.delivery_lag().clone()],
+            UnifiedPublisher::Ws(publisher) => vec![publisher.delivery_lag().clone()],
+            UnifiedPublisher::Multi(publisher) => publisher
+                .zmq_publisher
+                .iter()
+                .map(|p| p.delivery_lag().clone())
+                .chain(publisher.kafka_publisher.iter().map(|p| p.delivery_lag().clone()))
+                .collect(),
+            UnifiedPublisher::Stdout(_) => Vec::new(),
+            #[cfg(feature = "publisher-postgres")]
+            UnifiedPublisher::Postgres(publisher) => vec![publisher.delivery_lag().clone()],
+            #[cfg(feature = "publisher-object-store")]
+            UnifiedPublisher::ObjectStore(publisher) => vec![publisher.delivery_lag().clone()],
+            #[cfg(feature = "publisher-pubsub")]
+            UnifiedPublisher::PubSub(publisher) => vec![publisher.delivery_lag().clone()],
+            #[cfg(feature = "publisher-mqtt")]
+            UnifiedPublisher::Mqtt(publisher) => vec![publisher.delivery_lag().clone()],
+            UnifiedPublisher::Routed(publisher) => publisher.inner.delivery_lags(),
+            UnifiedPublisher::Batched(publisher) => publisher.inner.delivery_lags(),
+            UnifiedPublisher::Resilient(publisher) => publisher.inner.delivery_lags(),
+            UnifiedPublisher::Queued(publisher) => publisher.inner.delivery_lags(),
+            UnifiedPublisher::Stats(publisher) => publisher.inner.delivery_lags(),
+            UnifiedPublisher::Standby(publisher) => publisher.inner.delivery_lags(),
+            UnifiedPublisher::Registry(registry) => registry.builtins().iter().flat_map(|p| p.delivery_lags()).collect(),
+        }
+    }
+
+    /// Reports per-sink delivery lag as gauges and logs catch-up progress
+    /// while a sink is behind.
+    pub fn spawn_delivery_lag(&self, config: DeliveryLagConfig, metrics: Arc<dyn Metrics>) -> tokio::task::JoinHandle<()> {
+        config.spawn(self.delivery_lags(), metrics)
+    }
+}
@@ -0,0 +1,124 @@
+use {
+    super::common::DexEventData,
+    serde_json::json,
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime},
+    },
+};
+
+/// Event-time progress of one topic on one sink.
+#[derive(Debug, Default)]
+struct TopicProgress {
+    max_block_time: Option<i64>,
+    /// Block times of events handed to the sink and not yet acknowledged,
+    /// with the number of events at each.
+    unflushed: BTreeMap<i64, usize>,
+}
+
+/// Tracks the block times published on each topic of a sink, so the sink
+/// can periodically emit `watermark` events for downstream event-time
+/// windowing (Flink, Arroyo, ...).
+#[derive(Clone)]
+pub struct Watermarks {
+    interval: Duration,
+    topics: Arc<Mutex<HashMap<String, TopicProgress>>>,
+}
+
+impl Watermarks {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            topics: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Reads `WATERMARK_INTERVAL_MS`; watermarks are disabled when unset or
+    /// `0`. Every call returns a separate tracker, one per sink.
+    pub fn from_env() -> Option<Self> {
+        let interval_ms = std::env::var("WATERMARK_INTERVAL_MS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .filter(|interval_ms| *interval_ms > 0)?;
+        Some(Self::new(Duration::from_millis(interval_ms)))
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Records `data` as in flight on `topic` until the returned guard is
+    /// dropped. Events without a block time do not move watermarks.
+    pub fn begin(&self, topic: &str, data: &DexEventData) -> Option<Unflushed> {
+        let block_time = data.block_time?;
+        let mut topics = self.topics.lock().unwrap_or_else(|e| e.into_inner());
+        let progress = topics.entry(topic.to_string()).or_default();
+        progress.max_block_time = progress.max_block_time.max(Some(block_time));
+        *progress.unflushed.entry(block_time).or_default() += 1;
+
+        Some(Unflushed {
+            topics: self.topics.clone(),
+            topic: topic.to_string(),
+            block_time,
+        })
+    }
+
+    /// One `watermark` event per topic that has seen a block time, paired
+    /// with the topic it describes.
+    pub fn events(&self) -> Vec<(String, DexEventData)> {
+        let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+        let topics = self.topics.lock().unwrap_or_else(|e| e.into_inner());
+
+        topics
+            .iter()
+            .map(|(topic, progress)| {
+                let details = json!({
+                    "topic": topic,
+                    "max_block_time": progress.max_block_time,
+                    "min_unflushed_block_time": progress.unflushed.keys().next(),
+                    "unflushed": progress.unflushed.values().sum::<usize>()
+                });
+                let data = DexEventData {
+                    event_type: "watermark".to_string(),
+                    platform: String::new(),
+                    signature: String::new(),
+                    timestamp,
+                    block_time: None,
+                    slot: None,
+                    details,
+                    ingested_at: None,
+                    tx_index: None,
+                    instruction_index: None,
+                    is_inner: None,
+                    fee_payer: None,
+                    trader: None,
+                    payload: None,
+                };
+                (topic.clone(), data)
+            })
+            .collect()
+    }
+}
+
+/// An event handed to a sink and not yet acknowledged by it.
+pub struct Unflushed {
+    topics: Arc<Mutex<HashMap<String, TopicProgress>>>,
+    topic: String,
+    block_time: i64,
+}
+
+impl Drop for Unflushed {
+    fn drop(&mut self) {
+        let mut topics = self.topics.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(progress) = topics.get_mut(&self.topic) else {
+            return;
+        };
+        if let Some(count) = progress.unflushed.get_mut(&self.block_time) {
+            *count -= 1;
+            if *count == 0 {
+                progress.unflushed.remove(&self.block_time);
+            }
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use {
+    super::{
+        common::DexEventData, denylist::MintDenylist, error::PublishErrorKind, lag::DeliveryLag,
+        publisher_metrics::PublisherMetrics, serialization::PayloadFormat, traits::Publisher,
+    },
+    async_trait::async_trait,
+    hmac::{Hmac, Mac},
+    sha2::Sha256,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime},
+    },
+};
+
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 500;
+const DEFAULT_BATCH_INTERVAL_MS: u64 = 1000;
+/// Backoff between retries stops doubling here.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum WebhookPublisherError {
+    /// No URL is configured or the HTTP client could not be built.
+    Config(String),
+    Serialization(serde_json::Error),
+    /// The request failed on every attempt.
+    Request { endpoint: String, source: reqwest::Error },
+    /// The endpoint answered with an error status on every attempt.
+    Status { endpoint: String, status: u16 },
+}
+
+impl WebhookPublisherError {
+    pub fn kind(&self) -> PublishErrorKind {
+        match self {
+            WebhookPublisherError::Config(_) => PublishErrorKind::Connection,
+            WebhookPublisherError::Serialization(_) => PublishErrorKind::Serialization,
+            WebhookPublisherError::Request { source, .. } if source.is_timeout() => PublishErrorKind::Timeout,
+            WebhookPublisherError::Request { .. } => PublishErrorKind::Connection,
+            WebhookPublisherError::Status { status, .. } if is_retryable_status(*status) => PublishErrorKind::Connection,
+            WebhookPublisherError::Status { .. } => PublishErrorKind::Rejected,
+        }
+    }
+}
+
+impl std::fmt::Display for WebhookPublisherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WebhookPublisherError::Config(e) => write!(f, "Webhook Publisher Error: {}", e),
+            WebhookPublisherError::Serialization(e) => {
+                write!(f, "Webhook Publisher Error: Failed to serialize data: {}", e)
+            }
+            WebhookPublisherError::Request { endpoint, source } => {
+                write!(f, "Webhook Publisher Error: Failed to POST to {}: {}", endpoint, source)
+            }
+            WebhookPublisherError::Status { endpoint, status } => {
+                write!(f, "Webhook Publisher Error: {} answered with status {}", endpoint, status)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WebhookPublisherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WebhookPublisherError::Serialization(e) => Some(e),
+            WebhookPublisherError::Request { source, .. } => Some(source),
+            WebhookPublisherError::Config(_) | WebhookPublisherError::Status { .. } => None,
+        }
+    }
+}
+
+/// Rate limits and server errors may pass on a retry, other client errors
+/// will not.
+fn is_retryable_status(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub urls: Vec<String>,
+    /// Signs every request when set, see [`WebhookPublisher`].
+    pub secret: Option<String>,
+    pub timeout: Duration,
+    /// Attempts after the first one.
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each following one.
+    pub retry_backoff: Duration,
+    /// Events per request, `1` posts every event on its own.
+    pub batch_size: usize,
+    /// Longest an event waits for its batch to fill.
+    pub batch_interval: Duration,
+}
+
+impl WebhookConfig {
+    /// Reads `WEBHOOK_URLS` (comma separated, required), `WEBHOOK_SECRET`,
+    /// `WEBHOOK_TIMEOUT_MS` (default `5000`), `WEBHOOK_RETRIES` (default
+    /// `3`), `WEBHOOK_RETRY_BACKOFF_MS` (default `500`), `WEBHOOK_BATCH_SIZE`
+    /// (default `1`) and `WEBHOOK_BATCH_INTERVAL_MS` (default `1000`).
+    pub fn from_env() -> Result<Self, String> {
+        let urls = std::env::var("WEBHOOK_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        if urls.is_empty() {
+            return Err("WEBHOOK_URLS must list at least one URL".to_string());
+        }
+        let number = |name: &str, default: u64| -> Result<u64, String> {
+            match std::env::var(name) {
+                Ok(value) => value.parse().map_err(|e| format!("Invalid {} {}: {}", name, value, e)),
+                Err(_) => Ok(default),
+            }
+        };
+
+        Ok(Self {
+            urls,
+            secret: std::env::var("WEBHOOK_SECRET").ok().filter(|secret| !secret.is_empty()),
+            timeout: Duration::from_millis(number("WEBHOOK_TIMEOUT_MS", DEFAULT_TIMEOUT_MS)?),
+            retries: number("WEBHOOK_RETRIES", DEFAULT_RETRIES as u64)? as u32,
+            retry_backoff: Duration::from_millis(number("WEBHOOK_RETRY_BACKOFF_MS", DEFAULT_RETRY_BACKOFF_MS)?),
+            batch_size: number("WEBHOOK_BATCH_SIZE", 1)?.max(1) as usize,
+            batch_interval: Duration::from_millis(number("WEBHOOK_BATCH_INTERVAL_MS", DEFAULT_BATCH_INTERVAL_MS)?.max(1)),
+        })
+    }
+}
+
+struct Endpoint {
+    url: String,
+    /// Scheme and host only, URLs often carry a token.
+    label: String,
+}
+
+/// Encoded events of a topic waiting to be posted together.
+#[derive(Default)]
+struct Batch {
+    events: Vec<String>,
+    /// Newest block time among them, acknowledged once they are posted.
+    block_time: Option<i64>,
+}
+
+struct WebhookInner {
+    client: reqwest::Client,
+    endpoints: Vec<Endpoint>,
+    config: WebhookConfig,
+    /// Encoded events waiting for their batch, by topic.
+    batches: Mutex<HashMap<String, Batch>>,
+    metrics: PublisherMetrics,
+    lag: DeliveryLag,
+}
+
+/// POSTs events as JSON to every configured URL, for consumers without a
+/// message broker such as serverless functions.
+///
+/// Each request carries the topic in `X-Webhook-Topic` and the Unix time in
+/// `X-Webhook-Timestamp`. With a secret, `X-Webhook-Signature` holds
+/// `sha256=` and the hex HMAC-SHA256 of `<timestamp>.<body>`, so receivers
+/// can check the sender and reject replays. A failed request is retried
+/// with exponential backoff on timeouts, connection errors, `429` and `5xx`.
+///
+/// With a batch size above `1`, the body is a JSON array of the events of a
+/// topic, sent once the batch is full or `batch_interval` after its first
+/// event.
+#[derive(Clone)]
+pub struct WebhookPublisher {
+    inner: Arc<WebhookInner>,
+    format: PayloadFormat,
+    denylist: Option<MintDenylist>,
+}
+
+impl WebhookPublisher {
+    /// Batching flushes in the background, so this must be called within a
+    /// Tokio runtime when `batch_size` is above `1`.
+    pub fn new(config: WebhookConfig) -> Result<Self, WebhookPublisherError> {
+        if config.urls.is_empty() {
+            return Err(WebhookPublisherError::Config("No webhook URL configured".to_string()));
+        }
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| WebhookPublisherError::Config(format!("Failed to build the HTTP client: {}", e)))?;
+        let endpoints = config
+            .urls
+            .iter()
+            .map(|url| Endpoint {
+                url: url.clone(),
+                label: endpoint_label(url),
+            })
+            .collect::<Vec<_>>();
+        log::info!(
+            "[WEBHOOK] Posting events to {}",
+            endpoints.iter().map(|endpoint| endpoint.label.as_str()).collect::<Vec<_>>().join(", ")
+        );
+
+        let inner = Arc::new(WebhookInner {
+            client,
+            endpoints,
+            config,
+            batches: Mutex::new(HashMap::new()),
+            metrics: PublisherMetrics::new("webhook"),
+            lag: DeliveryLag::new("webhook"),
+        });
+        if inner.config.batch_size > 1 {
+            tokio::spawn(flush_periodically(Arc::downgrade(&inner)));
+        }
+        Ok(Self {
+            inner,
+            format: PayloadFormat::default(),
+            denylist: None,
+        })
+    }
+
+    pub fn with_format(mut self, format: PayloadFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Drops or quarantines events mentioning a denied mint.
+    pub fn with_denylist(mut self, denylist: Option<MintDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    pub fn metrics(&self) -> &PublisherMetrics {
+        &self.inner.metrics
+    }
+
+    pub fn delivery_lag(&self) -> &DeliveryLag {
+        &self.inner.lag
+    }
+}
+
+#[async_trait]
+impl Publisher for WebhookPublisher {
+    type Error = WebhookPublisherError;
+
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
+        let Some(topic) = self.denylist.as_ref().map_or(Some(topic), |denylist| denylist.route(topic, data)) else {
+            return Ok(());
+        };
+        let json_data = self.format.encode(data).map_err(WebhookPublisherError::Serialization)?;
+        self.inner.lag.submit(data.block_time);
+
+        if self.inner.config.batch_size == 1 {
+            return self.inner.post(topic, json_data, data.block_time).await;
+        }
+        let full = {
+            let mut batches = self.inner.batches.lock().unwrap_or_else(|e| e.into_inner());
+            let batch = batches.entry(topic.to_string()).or_default();
+            batch.events.push(json_data);
+            batch.block_time = batch.block_time.max(data.block_time);
+            let full = if batch.events.len() >= self.inner.config.batch_size {
+                batches.remove(topic)
+            } else {
+                None
+            };
+            self.inner.metrics.queue_depth(batches.values().map(|batch| batch.events.len()).sum());
+            full
+        };
+        match full {
+            Some(batch) => self.inner.post(topic, batch_body(&batch.events), batch.block_time).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn close(&self) -> Result<(), Self::Error> {
+        self.inner.flush().await
+    }
+}
+
+impl WebhookInner {
+    /// Sends the batches waiting, whatever their size.
+    async fn flush(&self) -> Result<(), WebhookPublisherError> {
+        let batches = std::mem::take(&mut *self.batches.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut result = Ok(());
+        for (topic, batch) in batches {
+            if let Err(e) = self.post(&topic, batch_body(&batch.events), batch.block_time).await {
+                log::error!("[WEBHOOK] Dropping a batch of {} {} events: {}", batch.events.len(), topic, e);
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    /// Posts `body` to every endpoint at once, acknowledging `block_time`
+    /// once each answered with a 2xx. Returns the first failure once every
+    /// endpoint was tried, the others are logged.
+    async fn post(&self, topic: &str, body: String, block_time: Option<i64>) -> Result<(), WebhookPublisherError> {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .to_string();
+        let signature = self.config.secret.as_ref().map(|secret| sign(secret, &timestamp, &body));
+
+        let results = futures::future::join_all(self.endpoints.iter().map(|endpoint| {
+            self.post_with_retries(endpoint, topic, &timestamp, signature.as_deref(), &body)
+        }))
+        .await;
+        let mut errors = results.into_iter().filter_map(Result::err);
+        let first = errors.next();
+        for error in errors {
+            log::error!("[WEBHOOK] {}", error);
+        }
+        if first.is_none() {
+            self.lag.ack(block_time);
+        }
+        first.map_or(Ok(()), Err)
+    }
+
+    async fn post_with_retries(
+        &self,
+        endpoint: &Endpoint,
+        topic: &str,
+        timestamp: &str,
+        signature: Option<&str>,
+        body: &str,
+    ) -> Result<(), WebhookPublisherError> {
+        let mut backoff = self.config.retry_backoff;
+        let mut attempt = 0;
+        loop {
+            let mut request = self
+                .client
+                .post(&endpoint.url)
+                .header("Content-Type", "application/json")
+                .header("X-Webhook-Topic", topic)
+                .header("X-Webhook-Timestamp", timestamp)
+                .body(body.to_string());
+            if let Some(signature) = signature {
+                request = request.header("X-Webhook-Signature", signature);
+            }
+
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    self.metrics.sent(body.len());
+                    return Ok(());
+                }
+                Ok(response) => WebhookPublisherError::Status {
+                    endpoint: endpoint.label.clone(),
+                    status: response.status().as_u16(),
+                },
+                Err(source) => WebhookPublisherError::Request {
+                    endpoint: endpoint.label.clone(),
+                    source,
+                },
+            };
+            if attempt >= self.config.retries || !error.kind().is_retryable() {
+                return Err(error);
+            }
+
+            attempt += 1;
+            log::warn!("[WEBHOOK] {}, retry {} in {:?}", error, attempt, backoff);
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+        }
+    }
+}
+
+/// Flushes the batches every `batch_interval` until the publisher is dropped.
+async fn flush_periodically(inner: std::sync::Weak<WebhookInner>) {
+    let Some(period) = inner.upgrade().map(|inner| inner.config.batch_interval) else {
+        return;
+    };
+    let mut interval = tokio::time::interval(period);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let Some(inner) = inner.upgrade() else {
+            return;
+        };
+        // Failures were logged per batch
+        let _ = inner.flush().await;
+    }
+}
+
+/// The events of a batch as a JSON array, they are already encoded.
+fn batch_body(batch: &[String]) -> String {
+    format!("[{}]", batch.join(","))
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `<timestamp>.<body>` keyed with
+/// `secret`.
+fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn endpoint_label(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let host = rest.split(['/', '?']).next().unwrap_or(rest);
+    if scheme.is_empty() {
+        host.to_string()
+    } else {
+        format!("{}://{}", scheme, host)
+    }
+}
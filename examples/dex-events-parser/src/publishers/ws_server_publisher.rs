@@ -0,0 +1,319 @@
+use {
+    super::{
+        common::DexEventData,
+        denylist::MintDenylist,
+        error::PublishErrorKind,
+        lag::DeliveryLag,
+        publisher_metrics::PublisherMetrics,
+        serialization::{EventSerializer, PayloadFormat},
+        traits::Publisher,
+    },
+    async_trait::async_trait,
+    carbon_dex_events_client::WsSubscription,
+    futures::{SinkExt, StreamExt},
+    std::{
+        collections::HashMap,
+        net::SocketAddr,
+        sync::{Arc, Mutex, MutexGuard},
+        time::Duration,
+    },
+    tokio::{
+        net::{TcpListener, TcpStream},
+        sync::{
+            mpsc::{self, error::TrySendError},
+            oneshot,
+        },
+    },
+    tokio_tungstenite::tungstenite::{
+        protocol::{frame::coding::CloseCode, CloseFrame},
+        Message,
+    },
+};
+
+/// Pause after a failed accept, e.g. when out of file descriptors.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub enum WsServerPublisherError {
+    /// The server could not listen on its address.
+    Bind { addr: String, source: std::io::Error },
+    Serialization(serde_json::Error),
+}
+
+impl WsServerPublisherError {
+    pub fn kind(&self) -> PublishErrorKind {
+        match self {
+            WsServerPublisherError::Bind { .. } => PublishErrorKind::Connection,
+            WsServerPublisherError::Serialization(_) => PublishErrorKind::Serialization,
+        }
+    }
+}
+
+impl std::fmt::Display for WsServerPublisherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WsServerPublisherError::Bind { addr, source } => {
+                write!(f, "WebSocket Server Error: Failed to listen on {}: {}", addr, source)
+            }
+            WsServerPublisherError::Serialization(e) => {
+                write!(f, "WebSocket Server Error: Failed to serialize data: {}", e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WsServerPublisherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WsServerPublisherError::Bind { source, .. } => Some(source),
+            WsServerPublisherError::Serialization(e) => Some(e),
+        }
+    }
+}
+
+/// A connected client.
+struct Client {
+    addr: SocketAddr,
+    /// `None` until the client subscribes, nothing is sent before.
+    subscription: Option<WsSubscription>,
+    queue: mpsc::Sender<Message>,
+    /// Closes the connection with the given frame.
+    close: oneshot::Sender<CloseFrame<'static>>,
+}
+
+type Clients = Arc<Mutex<HashMap<u64, Client>>>;
+
+fn lock(clients: &Clients) -> MutexGuard<'_, HashMap<u64, Client>> {
+    clients.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Serves events over WebSocket to every connected client, one JSON payload
+/// per text frame, or one binary frame with a binary serializer.
+///
+/// A client picks its events by sending a [`WsSubscription`] as a text
+/// frame, and may send another at any time to replace it. Each client has a
+/// queue of `queue_size` events; a client too slow to drain it is
+/// disconnected with close code 1013 rather than holding back the others.
+#[derive(Clone)]
+pub struct WsServerPublisher {
+    addr: SocketAddr,
+    clients: Clients,
+    queue_size: usize,
+    format: PayloadFormat,
+    denylist: Option<MintDenylist>,
+    metrics: PublisherMetrics,
+    lag: DeliveryLag,
+}
+
+impl WsServerPublisher {
+    /// Listens on `addr` and accepts clients in the background. Must be
+    /// called within a Tokio runtime.
+    pub fn bind(addr: &str, queue_size: usize) -> Result<Self, WsServerPublisherError> {
+        let bind_error = |source| WsServerPublisherError::Bind {
+            addr: addr.to_string(),
+            source,
+        };
+        let listener = std::net::TcpListener::bind(addr).map_err(bind_error)?;
+        listener.set_nonblocking(true).map_err(bind_error)?;
+        let listener = TcpListener::from_std(listener).map_err(bind_error)?;
+        let local_addr = listener.local_addr().map_err(bind_error)?;
+
+        let queue_size = queue_size.max(1);
+        let clients = Clients::default();
+        tokio::spawn(accept(listener, clients.clone(), queue_size));
+        log::info!("[WS] Serving events on ws://{}", local_addr);
+
+        Ok(Self {
+            addr: local_addr,
+            clients,
+            queue_size,
+            format: PayloadFormat::default(),
+            denylist: None,
+            metrics: PublisherMetrics::new("ws"),
+            lag: DeliveryLag::new("ws"),
+        })
+    }
+
+    pub fn with_format(mut self, format: PayloadFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Drops or quarantines events mentioning a denied mint.
+    pub fn with_denylist(mut self, denylist: Option<MintDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    /// Address the server listens on, with the actual port when bound to
+    /// port `0`.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn client_count(&self) -> usize {
+        lock(&self.clients).len()
+    }
+
+    pub fn metrics(&self) -> &PublisherMetrics {
+        &self.metrics
+    }
+
+    pub fn delivery_lag(&self) -> &DeliveryLag {
+        &self.lag
+    }
+}
+
+#[async_trait]
+impl Publisher for WsServerPublisher {
+    type Error = WsServerPublisherError;
+
+    async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
+        let Some(topic) = self.denylist.as_ref().map_or(Some(topic), |denylist| denylist.route(topic, data)) else {
+            return Ok(());
+        };
+        self.lag.submit(data.block_time);
+
+        let mut clients = lock(&self.clients);
+        let subscribed = clients
+            .iter()
+            .filter(|(_, client)| {
+                client
+                    .subscription
+                    .as_ref()
+                    .is_some_and(|subscription| subscription.matches(topic, data))
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        if subscribed.is_empty() {
+            self.lag.ack(data.block_time);
+            return Ok(());
+        }
+        // Binary serializers go out as binary frames
+        let frame = match self.format.serializer {
+            EventSerializer::Json => self.format.encode(data).map(Message::Text),
+            EventSerializer::MessagePack => self.format.encode_bytes(data).map(Message::Binary),
+        }
+        .map_err(WsServerPublisherError::Serialization)?;
+        let frame_len = frame.len();
+
+        for id in subscribed {
+            let Some(client) = clients.get(&id) else {
+                continue;
+            };
+            // A closed queue belongs to a client that is disconnecting
+            let sent = client.queue.try_send(frame.clone());
+            if sent.is_ok() {
+                self.metrics.sent(frame_len);
+            }
+            if let Err(TrySendError::Full(_)) = sent {
+                let Some(client) = clients.remove(&id) else {
+                    continue;
+                };
+                log::warn!(
+                    "[WS] Disconnecting slow client {}, {} events are waiting",
+                    client.addr,
+                    self.queue_size
+                );
+                let _ = client.close.send(CloseFrame {
+                    code: CloseCode::Again,
+                    reason: "slow consumer".into(),
+                });
+            }
+        }
+        // The slowest client's backlog
+        let depth = clients
+            .values()
+            .map(|client| self.queue_size.saturating_sub(client.queue.capacity()))
+            .max()
+            .unwrap_or(0);
+        self.metrics.queue_depth(depth);
+        // Clients have no acknowledgement, a queued frame counts as delivered
+        self.lag.ack(data.block_time);
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), Self::Error> {
+        for (_, client) in lock(&self.clients).drain() {
+            let _ = client.close.send(CloseFrame {
+                code: CloseCode::Away,
+                reason: "server shutting down".into(),
+            });
+        }
+        Ok(())
+    }
+}
+
+async fn accept(listener: TcpListener, clients: Clients, queue_size: usize) {
+    let mut next_id = 0;
+    loop {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                next_id += 1;
+                tokio::spawn(serve(stream, addr, next_id, clients.clone(), queue_size));
+            }
+            Err(e) => {
+                log::error!("[WS] Failed to accept a connection: {}", e);
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Runs one connection: applies the subscriptions the client sends and
+/// writes the events queued for it, until either side closes.
+async fn serve(stream: TcpStream, addr: SocketAddr, id: u64, clients: Clients, queue_size: usize) {
+    let websocket = match tokio_tungstenite::accept_async(stream).await {
+        Ok(websocket) => websocket,
+        Err(e) => {
+            log::debug!("[WS] Handshake with {} failed: {}", addr, e);
+            return;
+        }
+    };
+    let (mut sink, mut frames) = websocket.split();
+    let (queue, mut events) = mpsc::channel(queue_size);
+    let (close, mut closed) = oneshot::channel();
+    lock(&clients).insert(
+        id,
+        Client {
+            addr,
+            subscription: None,
+            queue,
+            close,
+        },
+    );
+    log::info!("[WS] Client {} connected", addr);
+
+    loop {
+        tokio::select! {
+            biased;
+            frame = &mut closed => {
+                if let Ok(frame) = frame {
+                    let _ = sink.send(Message::Close(Some(frame))).await;
+                }
+                break;
+            }
+            Some(frame) = events.recv() => {
+                if sink.send(frame).await.is_err() {
+                    break;
+                }
+            }
+            frame = frames.next() => match frame {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<WsSubscription>(&text) {
+                    Ok(subscription) => {
+                        log::info!("[WS] Client {} subscribed to {:?}", addr, subscription);
+                        if let Some(client) = lock(&clients).get_mut(&id) {
+                            client.subscription = Some(subscription);
+                        }
+                    }
+                    Err(e) => log::warn!("[WS] Ignoring invalid subscription from {}: {}", addr, e),
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    lock(&clients).remove(&id);
+    log::info!("[WS] Client {} disconnected", addr);
+}
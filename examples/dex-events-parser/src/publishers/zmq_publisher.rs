@@ -1,37 +1,302 @@
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use tokio::sync::{mpsc, oneshot, Mutex};
 use async_trait::async_trait;
-use super::{common::DexEventData, traits::Publisher};
+use super::{common::DexEventData, error::PublishErrorKind, serialization::PayloadFormat, traits::Publisher, lag::DeliveryLag, watermark::Watermarks};
+use super::{block_summary::BlockSummaries, denylist::MintDenylist, publisher_metrics::PublisherMetrics};
+use crate::analytics::mev::MevDetector;
+use carbon_dex_events_client::Compression;
 
 #[derive(Debug)]
-pub struct ZmqPublisherError(pub String);
+pub enum ZmqPublisherError {
+    /// Creating or binding the socket failed.
+    Connection { endpoint: String, source: zmq::Error },
+    Serialization(serde_json::Error),
+    Compression(std::io::Error),
+    /// The socket refused the message.
+    Send(zmq::Error),
+    /// The publisher was closed.
+    Closed,
+    /// Closing the socket or terminating the context, which waits for
+    /// queued messages, failed.
+    Close(zmq::Error),
+}
+
+impl ZmqPublisherError {
+    pub fn kind(&self) -> PublishErrorKind {
+        match self {
+            ZmqPublisherError::Connection { .. } => PublishErrorKind::Connection,
+            ZmqPublisherError::Serialization(_) | ZmqPublisherError::Compression(_) => PublishErrorKind::Serialization,
+            // PUB sockets only refuse a message when the send would block
+            ZmqPublisherError::Send(zmq::Error::EAGAIN) => PublishErrorKind::Timeout,
+            ZmqPublisherError::Send(_) | ZmqPublisherError::Closed | ZmqPublisherError::Close(_) => {
+                PublishErrorKind::Connection
+            }
+        }
+    }
+}
 
 impl std::fmt::Display for ZmqPublisherError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "ZMQ Publisher Error: {}", self.0)
+        match self {
+            ZmqPublisherError::Connection { endpoint, source } => {
+                write!(f, "ZMQ Publisher Error: Failed to bind to {}: {}", endpoint, source)
+            }
+            ZmqPublisherError::Serialization(e) => write!(f, "ZMQ Publisher Error: Failed to serialize data: {}", e),
+            ZmqPublisherError::Compression(e) => write!(f, "ZMQ Publisher Error: Failed to compress data: {}", e),
+            ZmqPublisherError::Send(e) => write!(f, "ZMQ Publisher Error: Failed to send message: {}", e),
+            ZmqPublisherError::Closed => write!(f, "ZMQ Publisher Error: Publisher is closed"),
+            ZmqPublisherError::Close(e) => write!(f, "ZMQ Publisher Error: Failed to drain queued messages: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ZmqPublisherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ZmqPublisherError::Connection { source, .. } => Some(source),
+            ZmqPublisherError::Serialization(e) => Some(e),
+            ZmqPublisherError::Compression(e) => Some(e),
+            ZmqPublisherError::Send(e) | ZmqPublisherError::Close(e) => Some(e),
+            ZmqPublisherError::Closed => None,
+        }
+    }
+}
+
+/// How long `close` waits for queued messages to reach the subscribers by
+/// default.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+/// Messages waiting for the sender task; `publish` waits beyond.
+const COMMAND_QUEUE_SIZE: usize = 1024;
+/// How often an XPUB socket is checked for subscription changes.
+const SUBSCRIPTION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Options of the publishing socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZmqSocketOptions {
+    /// Messages queued per subscriber; ZeroMQ drops new ones for a
+    /// subscriber that far behind.
+    pub send_hwm: i32,
+    /// How long messages still queued are sent after `close`, the drain
+    /// timeout when unset.
+    pub linger: Option<Duration>,
+    /// Binds an XPUB socket, which reports subscribers joining and leaving.
+    pub xpub: bool,
+}
+
+impl Default for ZmqSocketOptions {
+    fn default() -> Self {
+        Self {
+            // ZeroMQ's default
+            send_hwm: 1000,
+            linger: None,
+            xpub: false,
+        }
+    }
+}
+
+impl ZmqSocketOptions {
+    /// Reads `ZMQ_SNDHWM` (default `1000`), `ZMQ_LINGER_MS` and `ZMQ_XPUB`.
+    pub fn from_env() -> Result<Self, String> {
+        let number = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.parse::<u64>().map_err(|e| format!("Invalid {}: {}", name, e)))
+                .transpose()
+        };
+        let defaults = Self::default();
+        Ok(Self {
+            send_hwm: match number("ZMQ_SNDHWM")? {
+                Some(hwm) => i32::try_from(hwm).map_err(|e| format!("Invalid ZMQ_SNDHWM: {}", e))?,
+                None => defaults.send_hwm,
+            },
+            linger: number("ZMQ_LINGER_MS")?.map(Duration::from_millis),
+            xpub: std::env::var("ZMQ_XPUB").is_ok_and(|value| value == "true" || value == "1"),
+        })
     }
 }
 
-impl std::error::Error for ZmqPublisherError {}
+/// Topics followed by at least one subscriber, as reported by an XPUB
+/// socket. ZeroMQ reports the first subscriber of a topic and the last one
+/// leaving it, not each subscriber.
+#[derive(Debug, Clone, Default)]
+pub struct ZmqSubscriptions {
+    topics: Arc<std::sync::Mutex<HashSet<String>>>,
+}
+
+impl ZmqSubscriptions {
+    /// Followed topic prefixes, the empty one standing for every topic.
+    pub fn topics(&self) -> Vec<String> {
+        self.topics.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
 
+    /// Applies the subscription messages waiting on `socket`.
+    fn read(&self, socket: &zmq::Socket) {
+        while let Ok(message) = socket.recv_bytes(zmq::DONTWAIT) {
+            let Some((&subscribe, topic)) = message.split_first() else {
+                continue;
+            };
+            let topic = String::from_utf8_lossy(topic).into_owned();
+            let mut topics = self.topics.lock().unwrap_or_else(|e| e.into_inner());
+            match subscribe {
+                1 => {
+                    log::info!("[ZMQ] Subscriber joined topic '{}'", topic);
+                    topics.insert(topic);
+                }
+                0 => {
+                    log::info!("[ZMQ] Last subscriber left topic '{}'", topic);
+                    topics.remove(&topic);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+enum Command {
+    Send {
+        frames: [Vec<u8>; 2],
+        done: oneshot::Sender<Result<(), zmq::Error>>,
+    },
+    /// Closes the socket after the messages queued before.
+    Close {
+        linger: Duration,
+        done: oneshot::Sender<Result<(), zmq::Error>>,
+    },
+}
+
+/// Publishes events on a ZeroMQ PUB (or XPUB) socket.
+///
+/// The socket is owned by a sender task fed through a channel, so
+/// `publish` never blocks the runtime and clones share one ordered stream.
+/// A PUB socket does not wait for slow subscribers: past the send
+/// high-water mark their messages are dropped by ZeroMQ.
+#[derive(Clone)]
 pub struct ZmqPublisher {
     context: Arc<Mutex<zmq::Context>>,
-    socket: Arc<Mutex<zmq::Socket>>,
+    commands: mpsc::Sender<Command>,
+    options: ZmqSocketOptions,
+    drain_timeout: Duration,
+    subscriptions: Option<ZmqSubscriptions>,
+    format: PayloadFormat,
+    watermarks: Option<Watermarks>,
+    denylist: Option<MintDenylist>,
+    block_summaries: Option<BlockSummaries>,
+    mev: Option<MevDetector>,
+    lag: DeliveryLag,
+    compression: Option<Compression>,
+    metrics: PublisherMetrics,
 }
 
 impl ZmqPublisher {
+    /// Binds a PUB socket with the default options.
     pub fn new(endpoint: &str) -> Result<Self, ZmqPublisherError> {
+        Self::bind(endpoint, ZmqSocketOptions::default())
+    }
+
+    /// Binds the socket and starts its sender task, so this must be called
+    /// within a Tokio runtime.
+    pub fn bind(endpoint: &str, options: ZmqSocketOptions) -> Result<Self, ZmqPublisherError> {
         let context = zmq::Context::new();
-        let socket = context.socket(zmq::PUB)
-            .map_err(|e| ZmqPublisherError(format!("Failed to create socket: {}", e)))?;
-        socket.bind(endpoint)
-            .map_err(|e| ZmqPublisherError(format!("Failed to bind to {}: {}", endpoint, e)))?;
-        
+        let connection_error = |source| ZmqPublisherError::Connection {
+            endpoint: endpoint.to_string(),
+            source,
+        };
+        let socket = context
+            .socket(if options.xpub { zmq::XPUB } else { zmq::PUB })
+            .map_err(connection_error)?;
+        socket.set_sndhwm(options.send_hwm).map_err(connection_error)?;
+        socket.bind(endpoint).map_err(connection_error)?;
+
+        let subscriptions = options.xpub.then(ZmqSubscriptions::default);
+        let (commands, queued) = mpsc::channel(COMMAND_QUEUE_SIZE);
+        tokio::spawn(run_socket(socket, queued, subscriptions.clone()));
+
         Ok(Self {
             context: Arc::new(Mutex::new(context)),
-            socket: Arc::new(Mutex::new(socket)),
+            commands,
+            options,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            subscriptions,
+            format: PayloadFormat::default(),
+            watermarks: None,
+            denylist: None,
+            block_summaries: None,
+            mev: None,
+            lag: DeliveryLag::new("zmq"),
+            compression: None,
+            metrics: PublisherMetrics::new("zmq"),
         })
     }
+
+    pub fn with_format(mut self, format: PayloadFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Compresses payloads, naming the codec in the topic frame
+    /// (`<topic>@<codec>`) so subscribers know how to decompress.
+    pub fn with_compression(mut self, compression: Option<Compression>) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// How long `close` waits for messages still queued in the socket,
+    /// unless the socket options set a linger period.
+    pub fn with_drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+
+    pub fn with_watermarks(mut self, watermarks: Option<Watermarks>) -> Self {
+        self.watermarks = watermarks;
+        self
+    }
+
+    /// Drops or quarantines events mentioning a denied mint.
+    pub fn with_denylist(mut self, denylist: Option<MintDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    /// Publishes a summary of each block's events once the block is complete.
+    pub fn with_block_summaries(mut self, block_summaries: Option<BlockSummaries>) -> Self {
+        self.block_summaries = block_summaries;
+        self
+    }
+
+    /// Publishes the sandwiches found in each block once the block is
+    /// complete.
+    pub fn with_mev(mut self, mev: Option<MevDetector>) -> Self {
+        self.mev = mev;
+        self
+    }
+
+    pub fn watermarks(&self) -> Option<&Watermarks> {
+        self.watermarks.as_ref()
+    }
+
+    pub fn delivery_lag(&self) -> &DeliveryLag {
+        &self.lag
+    }
+
+    pub fn metrics(&self) -> &PublisherMetrics {
+        &self.metrics
+    }
+
+    /// Topics subscribers follow, with an XPUB socket.
+    pub fn subscriptions(&self) -> Option<&ZmqSubscriptions> {
+        self.subscriptions.as_ref()
+    }
+
+    pub async fn publish_watermarks(&self) -> Result<(), ZmqPublisherError> {
+        let Some(watermarks) = &self.watermarks else {
+            return Ok(());
+        };
+        for (topic, data) in watermarks.events() {
+            self.publish(&topic, &data).await?;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -39,27 +304,118 @@ impl Publisher for ZmqPublisher {
     type Error = ZmqPublisherError;
 
     async fn publish(&self, topic: &str, data: &DexEventData) -> Result<(), Self::Error> {
-        let socket = self.socket.lock().await;
-        let json_data = serde_json::to_string(data)
-            .map_err(|e| ZmqPublisherError(format!("Failed to serialize data: {}", e)))?;
-        
-        socket.send_multipart([topic.as_bytes(), json_data.as_bytes()], 0)
-            .map_err(|e| ZmqPublisherError(format!("Failed to send message: {}", e)))?;
+        let Some(routed) = self.denylist.as_ref().map_or(Some(topic), |denylist| denylist.route(topic, data)) else {
+            return Ok(());
+        };
+        // Quarantined events stay out of the block summaries
+        let summarize = routed == topic;
+        let topic = routed;
+        let _unflushed = self.watermarks.as_ref().and_then(|watermarks| watermarks.begin(topic, data));
+        let payload = self.format.encode_bytes(data).map_err(ZmqPublisherError::Serialization)?;
+        let (frame_topic, payload) = match &self.compression {
+            Some(compression) => (
+                compression.topic(topic),
+                compression.compress(&payload).map_err(ZmqPublisherError::Compression)?,
+            ),
+            None => (topic.to_string(), payload),
+        };
         
+        self.lag.submit(data.block_time);
+        let size = payload.len();
+        let (done, sent) = oneshot::channel();
+        self.commands
+            .send(Command::Send {
+                frames: [frame_topic.into_bytes(), payload],
+                done,
+            })
+            .await
+            .map_err(|_| ZmqPublisherError::Closed)?;
+        self.metrics.queue_depth(self.commands.max_capacity() - self.commands.capacity());
+        sent.await.map_err(|_| ZmqPublisherError::Closed)?.map_err(ZmqPublisherError::Send)?;
+        self.metrics.sent(size);
+        // PUB sockets have no acknowledgement, a queued message counts as delivered
+        self.lag.ack(data.block_time);
+        if let Some(block_summaries) = self.block_summaries.as_ref().filter(|_| summarize) {
+            for summary in block_summaries.record(data) {
+                self.publish(block_summaries.topic(), &summary).await?;
+            }
+        }
+        if let Some(mev) = self.mev.as_ref().filter(|_| summarize) {
+            for event in mev.record(data) {
+                self.publish(mev.topic(), &event).await?;
+            }
+        }
+
         Ok(())
     }
 
+    /// Closes the socket and waits up to the linger period, the drain
+    /// timeout by default, for the messages still queued to reach the
+    /// subscribers. Later publishes
+    /// fail with [`ZmqPublisherError::Closed`].
     async fn close(&self) -> Result<(), Self::Error> {
-        // ZMQ socket will be closed when dropped
-        Ok(())
+        let (done, closed) = oneshot::channel();
+        let close = Command::Close {
+            linger: self.options.linger.unwrap_or(self.drain_timeout),
+            done,
+        };
+        if self.commands.send(close).await.is_err() {
+            // Closed already
+            return Ok(());
+        }
+        closed.await.map_err(|_| ZmqPublisherError::Closed)?.map_err(ZmqPublisherError::Close)?;
+
+        // Terminating the context blocks until the queue is sent or the
+        // linger period is over
+        let context = self.context.clone();
+        tokio::task::spawn_blocking(move || context.blocking_lock().destroy())
+            .await
+            .map_err(|_| ZmqPublisherError::Closed)?
+            .map_err(ZmqPublisherError::Close)
     }
 }
 
-impl Clone for ZmqPublisher {
-    fn clone(&self) -> Self {
-        Self {
-            context: Arc::clone(&self.context),
-            socket: Arc::clone(&self.socket),
+/// Owns the socket: sends what `publish` queues and, with XPUB, reads the
+/// subscription changes. Runs on the runtime since a PUB socket never
+/// blocks on send.
+async fn run_socket(socket: zmq::Socket, mut commands: mpsc::Receiver<Command>, subscriptions: Option<ZmqSubscriptions>) {
+    let mut poll = tokio::time::interval(SUBSCRIPTION_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(Command::Send { frames, done }) => {
+                    let _ = done.send(socket.send_multipart(frames, zmq::DONTWAIT));
+                }
+                Some(Command::Close { linger, done }) => {
+                    let result = socket.set_linger(i32::try_from(linger.as_millis()).unwrap_or(i32::MAX));
+                    drop(socket);
+                    let _ = done.send(result);
+                    return;
+                }
+                None => return,
+            },
+            _ = poll.tick(), if subscriptions.is_some() => {
+                if let Some(subscriptions) = &subscriptions {
+                    subscriptions.read(&socket);
+                }
+            }
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Reads `ZMQ_COMPRESSION` (`zstd`, `lz4`, `gzip` or `none`, default
+/// `none`) and `ZMQ_COMPRESSION_LEVEL` for zstd.
+pub fn compression_from_env() -> Result<Option<Compression>, String> {
+    let compression = match std::env::var("ZMQ_COMPRESSION").as_deref() {
+        Ok("none") | Err(_) => return Ok(None),
+        Ok(name) => Compression::from_name(name)
+            .ok_or_else(|| format!("Unknown ZMQ_COMPRESSION {}, expected zstd, lz4, gzip or none", name))?,
+    };
+    match (compression, std::env::var("ZMQ_COMPRESSION_LEVEL")) {
+        (Compression::Zstd(_), Ok(level)) => level
+            .parse()
+            .map(|level| Some(Compression::Zstd(level)))
+            .map_err(|e| format!("Invalid ZMQ_COMPRESSION_LEVEL: {}", e)),
+        (compression, _) => Ok(Some(compression)),
+    }
+}
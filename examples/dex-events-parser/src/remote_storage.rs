@@ -0,0 +1,200 @@
+//! S3-compatible and GCS object storage for captures and checkpoints, so a
+//! stateless container can restart anywhere and resume.
+//!
+//! Captures are written and read from blocking tasks, so the objects here
+//! expose blocking IO that drives the async store through a runtime handle.
+
+use {
+    crate::datasources::capture_file::CaptureSource,
+    object_store::{
+        aws::AmazonS3Builder, gcp::GoogleCloudStorageBuilder, path::Path, ObjectStore, WriteMultipart,
+    },
+    std::{io, sync::Arc},
+    tokio::runtime::Handle,
+};
+
+/// Uploaded parts are this large, above the 5 MiB S3 minimum.
+const PART_BYTES: usize = 8 * 1024 * 1024;
+/// Parts uploading at once before writes wait.
+const MAX_PARTS_IN_FLIGHT: usize = 4;
+
+/// A bucket and a prefix in it, from `s3://bucket/prefix` or
+/// `gs://bucket/prefix`.
+///
+/// S3 credentials come from `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`,
+/// the region from `S3_REGION` or `AWS_REGION` and a non-AWS endpoint such
+/// as MinIO from `S3_ENDPOINT` or `AWS_ENDPOINT`. Conditional writes need
+/// `AWS_CONDITIONAL_PUT=etag`. GCS reads `GOOGLE_SERVICE_ACCOUNT`.
+#[derive(Clone)]
+pub struct Location {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+}
+
+impl Location {
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let (scheme, rest) = url.split_once("://").ok_or_else(|| format!("Invalid URL {}", url))?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        let store: Arc<dyn ObjectStore> = match scheme {
+            "s3" => {
+                let mut builder = AmazonS3Builder::from_env().with_bucket_name(bucket);
+                if let Ok(region) = std::env::var("S3_REGION") {
+                    builder = builder.with_region(region);
+                }
+                if let Ok(endpoint) = std::env::var("S3_ENDPOINT") {
+                    builder = builder
+                        .with_allow_http(endpoint.starts_with("http://"))
+                        .with_endpoint(endpoint);
+                }
+                Arc::new(builder.build().map_err(|e| format!("Invalid S3 bucket {}: {}", url, e))?)
+            }
+            "gs" => Arc::new(
+                GoogleCloudStorageBuilder::from_env()
+                    .with_bucket_name(bucket)
+                    .build()
+                    .map_err(|e| format!("Invalid GCS bucket {}: {}", url, e))?,
+            ),
+            other => return Err(format!("Unsupported object store scheme {}, expected s3 or gs", other)),
+        };
+        Ok(Self::new(store, prefix))
+    }
+
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: &str) -> Self {
+        Self {
+            store,
+            prefix: Path::from(prefix),
+        }
+    }
+
+    pub fn store(&self) -> &Arc<dyn ObjectStore> {
+        &self.store
+    }
+
+    /// `key` under the prefix.
+    pub fn path(&self, key: &str) -> Path {
+        self.prefix.parts().chain(Path::from(key).parts()).collect()
+    }
+
+    /// Splits `s3://bucket/prefix/key` into the location of its bucket and
+    /// the key, for reading a single object.
+    pub fn parse_object(url: &str) -> Result<(Self, Path), String> {
+        let (scheme, rest) = url.split_once("://").ok_or_else(|| format!("Invalid URL {}", url))?;
+        let (bucket, key) = rest
+            .split_once('/')
+            .filter(|(_, key)| !key.is_empty())
+            .ok_or_else(|| format!("Missing object key in {}", url))?;
+        let location = Self::parse(&format!("{}://{}", scheme, bucket))?;
+        let path = location.path(key);
+        Ok((location, path))
+    }
+}
+
+/// A new object written with a multipart upload. It only appears in the
+/// bucket once [`ObjectWriter::complete`] returns; an upload dropped before
+/// that is abandoned.
+pub struct ObjectWriter {
+    runtime: Handle,
+    upload: WriteMultipart,
+}
+
+impl ObjectWriter {
+    /// Starts the upload of `path`, to be called from a blocking task.
+    pub fn create(location: &Location, path: &Path, runtime: Handle) -> io::Result<Self> {
+        let upload = runtime.block_on(location.store.put_multipart(path))?;
+        Ok(Self {
+            runtime,
+            upload: WriteMultipart::new_with_chunk_size(upload, PART_BYTES),
+        })
+    }
+
+    /// Uploads the last part and completes the upload.
+    pub fn complete(self) -> io::Result<()> {
+        self.runtime.block_on(self.upload.finish())?;
+        Ok(())
+    }
+}
+
+impl io::Write for ObjectWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Full parts are uploaded by tasks spawned on the runtime
+        let _guard = self.runtime.enter();
+        self.upload.write(buf);
+        Ok(buf.len())
+    }
+
+    /// Waits for parts in flight, never uploads a part below the minimum.
+    fn flush(&mut self) -> io::Result<()> {
+        self.runtime
+            .block_on(self.upload.wait_for_capacity(MAX_PARTS_IN_FLIGHT))
+            .map_err(io::Error::from)
+    }
+}
+
+/// A capture object read with range requests.
+pub struct ObjectReader {
+    runtime: Handle,
+    store: Arc<dyn ObjectStore>,
+    path: Path,
+}
+
+impl ObjectReader {
+    pub fn new(location: &Location, path: Path, runtime: Handle) -> Self {
+        Self {
+            runtime,
+            store: location.store.clone(),
+            path,
+        }
+    }
+}
+
+impl CaptureSource for ObjectReader {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.runtime.block_on(self.store.head(&self.path))?.size as u64)
+    }
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let start = offset as usize;
+        let bytes = self.runtime.block_on(self.store.get_range(&self.path, start..start + len))?;
+        if bytes.len() < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(bytes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, object_store::memory::InMemory, std::io::Write};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reads_back_ranges_of_a_completed_upload() {
+        let location = Location::new(Arc::new(InMemory::new()), "captures");
+        let path = location.path("test.dexcap");
+        let bytes = (0..3 * PART_BYTES / 2).map(|i| i as u8).collect::<Vec<_>>();
+
+        let (written, uploaded) = (location.clone(), bytes.clone());
+        let mut reader = tokio::task::spawn_blocking(move || {
+            let mut writer = ObjectWriter::create(&written, &path, Handle::current()).unwrap();
+            writer.write_all(&uploaded).unwrap();
+            writer.flush().unwrap();
+            writer.complete().unwrap();
+            ObjectReader::new(&written, path, Handle::current())
+        })
+        .await
+        .unwrap();
+
+        let len = bytes.len() as u64;
+        let read = tokio::task::spawn_blocking(move || {
+            (
+                reader.size().unwrap(),
+                reader.read_at(PART_BYTES as u64 - 2, 4).unwrap(),
+                reader.read_at(len - 2, 4).is_err(),
+            )
+        })
+        .await
+        .unwrap();
+        assert_eq!(read.0, len);
+        assert_eq!(read.1, bytes[PART_BYTES - 2..PART_BYTES + 2]);
+        assert!(read.2, "reading past the end fails");
+    }
+}
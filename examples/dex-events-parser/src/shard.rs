@@ -0,0 +1,171 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{error::CarbonResult, metrics::MetricsCollection, processor::Processor},
+    solana_pubkey::Pubkey,
+    std::sync::Arc,
+};
+
+use crate::processors::InstructionInput;
+
+/// What instances split the load by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardKey {
+    /// Each instance follows a subset of the DEX programs.
+    Program,
+    /// Each instance follows a subset of the watched pools.
+    Pool,
+}
+
+impl ShardKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShardKey::Program => "program",
+            ShardKey::Pool => "pool",
+        }
+    }
+}
+
+/// This instance's slice of the load when several instances run side by
+/// side. Every address belongs to exactly one of the `count` shards, so the
+/// instances' outputs never overlap.
+#[derive(Debug, Clone)]
+pub struct Shard {
+    index: u64,
+    count: u64,
+    key: ShardKey,
+    /// Every program followed across the shards, sorted.
+    programs: Arc<Vec<Pubkey>>,
+}
+
+impl Default for Shard {
+    fn default() -> Self {
+        Self {
+            index: 0,
+            count: 1,
+            key: ShardKey::Program,
+            programs: Arc::default(),
+        }
+    }
+}
+
+impl Shard {
+    /// Reads `SHARD_COUNT` (default `1`, unsharded), `SHARD_INDEX` (`0` to
+    /// `SHARD_COUNT - 1`) and `SHARD_BY` (`program` or `pool`, default
+    /// `program`).
+    pub fn from_env() -> Result<Self, String> {
+        let count = match std::env::var("SHARD_COUNT") {
+            Ok(value) => value
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid SHARD_COUNT {}", value))?,
+            Err(_) => 1,
+        };
+        if count <= 1 {
+            return Ok(Self::default());
+        }
+
+        let index = std::env::var("SHARD_INDEX")
+            .map_err(|_| "SHARD_INDEX must be set when SHARD_COUNT is above 1".to_string())?;
+        let index = index
+            .parse::<u64>()
+            .ok()
+            .filter(|index| *index < count)
+            .ok_or_else(|| format!("SHARD_INDEX {} must be below SHARD_COUNT {}", index, count))?;
+        let key = match std::env::var("SHARD_BY").as_deref() {
+            Err(_) | Ok("program") => ShardKey::Program,
+            Ok("pool") => ShardKey::Pool,
+            Ok(other) => return Err(format!("Unknown SHARD_BY {}", other)),
+        };
+
+        Ok(Self {
+            index,
+            count,
+            key,
+            programs: Arc::default(),
+        })
+    }
+
+    pub fn is_sharded(&self) -> bool {
+        self.count > 1
+    }
+
+    pub fn key(&self) -> ShardKey {
+        self.key
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} of {} by {}", self.index, self.count, self.key.as_str())
+    }
+
+    /// Addresses are uniformly distributed, so their leading bytes make a
+    /// stable hash that every instance agrees on.
+    fn owns(&self, address: &Pubkey) -> bool {
+        let bytes = address.to_bytes();
+        let hash = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        hash % self.count == self.index
+    }
+
+    /// Programs are too few for the hash to spread them evenly, so the
+    /// followed ones are dealt out round-robin over their sorted list.
+    /// Programs outside the list fall back to the hash.
+    pub fn owns_program(&self, program_id: &Pubkey) -> bool {
+        if self.key != ShardKey::Program {
+            return true;
+        }
+        match self.programs.binary_search(program_id) {
+            Ok(position) => position as u64 % self.count == self.index,
+            Err(_) => self.owns(program_id),
+        }
+    }
+
+    pub fn owns_pool(&self, pool: &Pubkey) -> bool {
+        self.key != ShardKey::Pool || self.owns(pool)
+    }
+
+    /// Sets every program followed across the shards, which must be the
+    /// same on every instance, and returns the ones this instance follows.
+    pub fn assign_programs(&mut self, mut program_ids: Vec<Pubkey>) -> Vec<Pubkey> {
+        program_ids.sort();
+        program_ids.dedup();
+        self.programs = Arc::new(program_ids);
+        self.programs
+            .iter()
+            .filter(|program_id| self.owns_program(program_id))
+            .copied()
+            .collect()
+    }
+
+    pub fn wrap<P>(&self, processor: P) -> Sharded<P> {
+        Sharded {
+            shard: self.clone(),
+            processor,
+        }
+    }
+}
+
+/// Processor handle returned by [`Shard::wrap`].
+pub struct Sharded<P> {
+    shard: Shard,
+    processor: P,
+}
+
+#[async_trait]
+impl<T, P> Processor for Sharded<P>
+where
+    T: Send + 'static,
+    P: Processor<InputType = InstructionInput<T>> + Send,
+{
+    type InputType = InstructionInput<T>;
+
+    async fn process(
+        &mut self,
+        input: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        // Transactions mention several programs, the other shards own the rest
+        if !self.shard.owns_program(&input.3.program_id) {
+            return Ok(());
+        }
+
+        self.processor.process(input, metrics).await
+    }
+}